@@ -1,3 +1,6 @@
 pub mod config;
 pub mod cors;
+pub mod dat_imports;
+pub mod gc;
+pub mod images;
 pub mod template;
@@ -1,8 +1,13 @@
 use crate::fairings::template::TemplateResolver;
 use crate::guards::emailer::SmtpConfig;
+use crate::utils::oidc::OidcProviderConfig;
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
+use ed25519_dalek::SigningKey;
 use jsonwebtoken::{DecodingKey, EncodingKey};
+use retronomicon_db::models;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use wildmatch::WildMatch;
 
 #[derive(Debug, Clone, serde::Deserialize)]
@@ -11,11 +16,56 @@ pub struct RetronomiconConfig {
     pub root_team: Vec<String>,
     pub root_team_id: i32,
 
+    /// Whether members of the root team must enable two-factor
+    /// authentication to use their root privileges.
+    #[serde(default)]
+    pub root_team_require_two_factor: bool,
+
     bypass_email_validation: Vec<String>,
 
+    /// Users (emails) allowed to create teams. Anyone by default.
+    #[serde(default = "default_team_creators")]
+    team_creators: Vec<String>,
+
     template_dir: String,
 
+    /// The maximum size of an artifact, in bytes, for teams that don't have
+    /// their own limit.
+    #[serde(default = "default_upload_size_limit")]
+    pub upload_size_limit: i64,
+
+    /// How often to delete blobs that are not used by any artifact, and
+    /// uploads abandoned for a week, in seconds. 0 disables the garbage
+    /// collection.
+    #[serde(default = "default_blob_gc_interval")]
+    pub blob_gc_interval: u64,
+
+    /// The sizes of the thumbnails generated for game images, in pixels on
+    /// their longest side.
+    #[serde(default = "default_image_thumbnail_sizes")]
+    pub image_thumbnail_sizes: Vec<u32>,
+
     pub smtp: SmtpConfig,
+
+    /// Generic OpenID Connect providers users can log in with, by name.
+    #[serde(default)]
+    pub oidc: BTreeMap<String, OidcProviderConfig>,
+}
+
+fn default_team_creators() -> Vec<String> {
+    vec!["*".to_string()]
+}
+
+fn default_upload_size_limit() -> i64 {
+    256 * 1024 * 1024
+}
+
+fn default_blob_gc_interval() -> u64 {
+    60 * 60
+}
+
+fn default_image_thumbnail_sizes() -> Vec<u32> {
+    vec![128, 256, 512]
 }
 
 impl RetronomiconConfig {
@@ -30,6 +80,17 @@ impl RetronomiconConfig {
             .any(|e| WildMatch::new(e).matches(email))
     }
 
+    pub(crate) fn can_create_team(&self, email: &str) -> bool {
+        self.team_creators
+            .iter()
+            .any(|e| WildMatch::new(e).matches(email))
+    }
+
+    /// The maximum size of an artifact uploaded by a team.
+    pub fn upload_size_limit_for(&self, team: &models::Team) -> i64 {
+        team.upload_size_limit.unwrap_or(self.upload_size_limit)
+    }
+
     pub(crate) fn should_add_to_root(&self, email: &str) -> bool {
         self.root_team
             .iter()
@@ -59,3 +120,25 @@ impl DbPepper {
         Self(secret)
     }
 }
+
+/// The key the server signs release manifests with.
+pub struct ManifestSigningKey(pub SigningKey);
+
+impl ManifestSigningKey {
+    /// A secret of 32 bytes is used as the key itself. Any other secret (e.g.
+    /// the Rocket secret key) is hashed to derive the key.
+    pub fn from_base64(secret: &str) -> Self {
+        let secret = STANDARD
+            .decode(secret)
+            .expect("Invalid base64 manifest signing key");
+        let seed: [u8; 32] = match secret.try_into() {
+            Ok(seed) => seed,
+            Err(secret) => Sha256::new()
+                .chain_update(b"retronomicon manifest signing key")
+                .chain_update(secret)
+                .finalize()
+                .into(),
+        };
+        Self(SigningKey::from_bytes(&seed))
+    }
+}
@@ -0,0 +1,39 @@
+use crate::utils::dat_imports::recover;
+use retronomicon_db::RetronomiconDbPool;
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::{tokio, Orbit, Rocket};
+use std::time::Duration;
+
+/// How often to look for interrupted imports.
+const RECOVERY_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Recovers the DAT imports that were interrupted when a server stopped, on
+/// startup and periodically.
+pub struct DatImportRecovery;
+
+#[rocket::async_trait]
+impl Fairing for DatImportRecovery {
+    fn info(&self) -> Info {
+        Info {
+            name: "DAT import recovery",
+            kind: Kind::Liftoff,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let Some(pool) = rocket.state::<RetronomiconDbPool>() else {
+            rocket::error!("No database pool, DAT imports are not recovered.");
+            return;
+        };
+        let pool = pool.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(RECOVERY_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = recover(&pool).await {
+                    rocket::error!("Recovering DAT imports failed: {}", e);
+                }
+            }
+        });
+    }
+}
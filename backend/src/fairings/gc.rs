@@ -0,0 +1,59 @@
+use crate::fairings::config::RetronomiconConfig;
+use crate::guards::storage::Storage;
+use crate::utils::blobs::collect_garbage;
+use crate::utils::uploads::{expire_uploads, UploadHashers};
+use retronomicon_db::RetronomiconDbPool;
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::{tokio, Orbit, Rocket};
+use std::time::Duration;
+
+/// Periodically deletes blobs that are not used by any artifact, and uploads
+/// that were abandoned.
+pub struct BlobCollector;
+
+#[rocket::async_trait]
+impl Fairing for BlobCollector {
+    fn info(&self) -> Info {
+        Info {
+            name: "Blob garbage collection",
+            kind: Kind::Liftoff,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let Some(config) = rocket.state::<RetronomiconConfig>() else {
+            rocket::error!("No configuration, blob garbage collection disabled.");
+            return;
+        };
+        if config.blob_gc_interval == 0 {
+            return;
+        }
+
+        let interval = Duration::from_secs(config.blob_gc_interval);
+        let figment = rocket.figment().clone();
+        let hashers = rocket.state::<UploadHashers>().cloned().unwrap_or_default();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+
+                let result = async {
+                    let storage = Storage::from_figment(&figment)?;
+                    let mut db = RetronomiconDbPool::connect(&figment).await?;
+                    match expire_uploads(&mut db, &storage, &hashers).await {
+                        Ok(0) => {}
+                        Ok(count) => rocket::info!("Deleted {} expired uploads.", count),
+                        Err(e) => rocket::error!("Expiring uploads failed: {}", e),
+                    }
+                    collect_garbage(&mut db, &storage).await
+                }
+                .await;
+                match result {
+                    Ok(0) => {}
+                    Ok(count) => rocket::info!("Deleted {} unused blobs.", count),
+                    Err(e) => rocket::error!("Blob garbage collection failed: {}", e),
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,46 @@
+use crate::fairings::config::RetronomiconConfig;
+use crate::guards::storage::Storage;
+use crate::utils::images::backfill;
+use retronomicon_db::RetronomiconDbPool;
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::{tokio, Orbit, Rocket};
+
+/// Computes the perceptual hashes and derivatives of the game images that
+/// were uploaded before they were computed on upload, once on startup.
+pub struct ImageBackfill;
+
+#[rocket::async_trait]
+impl Fairing for ImageBackfill {
+    fn info(&self) -> Info {
+        Info {
+            name: "Game image backfill",
+            kind: Kind::Liftoff,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let (Some(config), Some(pool)) = (
+            rocket.state::<RetronomiconConfig>(),
+            rocket.state::<RetronomiconDbPool>(),
+        ) else {
+            rocket::error!("No configuration or database pool, game images are not backfilled.");
+            return;
+        };
+
+        let sizes = config.image_thumbnail_sizes.clone();
+        let pool = pool.clone();
+        let figment = rocket.figment().clone();
+        tokio::spawn(async move {
+            let result = async {
+                let storage = Storage::from_figment(&figment)?;
+                backfill(&pool, &storage, &sizes).await
+            }
+            .await;
+            match result {
+                Ok(0) => {}
+                Ok(count) => rocket::info!("Backfilled {} game images.", count),
+                Err(e) => rocket::error!("Backfilling game images failed: {}", e),
+            }
+        });
+    }
+}
@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 
+#[derive(Clone)]
 pub struct TemplateResolver {
     root: PathBuf,
 }
@@ -16,4 +17,34 @@ impl TemplateResolver {
         let path = self.root.join("email-verification.hbs");
         std::fs::read_to_string(path).expect("Failed to read email verification template")
     }
+
+    pub fn password_reset(&self) -> String {
+        let path = self.root.join("password-reset.hbs");
+        std::fs::read_to_string(path).expect("Failed to read password reset template")
+    }
+
+    pub fn email_change(&self) -> String {
+        let path = self.root.join("email-change.hbs");
+        std::fs::read_to_string(path).expect("Failed to read email change template")
+    }
+
+    pub fn email_change_notice(&self) -> String {
+        let path = self.root.join("email-change-notice.hbs");
+        std::fs::read_to_string(path).expect("Failed to read email change notice template")
+    }
+
+    pub fn team_invitation(&self) -> String {
+        let path = self.root.join("team-invitation.hbs");
+        std::fs::read_to_string(path).expect("Failed to read team invitation template")
+    }
+
+    pub fn release_yanked(&self) -> String {
+        let path = self.root.join("release-yanked.hbs");
+        std::fs::read_to_string(path).expect("Failed to read release yanked template")
+    }
+
+    pub fn core_release(&self) -> String {
+        let path = self.root.join("core-release.hbs");
+        std::fs::read_to_string(path).expect("Failed to read core release template")
+    }
 }
@@ -1,3 +1,6 @@
+pub mod checksums;
+pub mod dat_importer;
 pub mod emailer;
+pub mod languages;
 pub mod storage;
 pub mod users;
@@ -0,0 +1,27 @@
+use crate::utils::uploads::ExpectedChecksums;
+use rocket::http::Status;
+use rocket::request::{self, FromRequest, Request};
+use rocket_okapi::OpenApiFromRequest;
+
+/// The size and checksums a client expects for the body of its request,
+/// sent using the `X-Expected-Size`, `Digest` or `Content-MD5` headers. All
+/// headers are optional.
+#[derive(Debug, Clone, Default, OpenApiFromRequest)]
+pub struct DigestHeaders(pub ExpectedChecksums);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for DigestHeaders {
+    type Error = String;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let headers = request.headers();
+        match ExpectedChecksums::from_headers(
+            headers.get_one("X-Expected-Size"),
+            headers.get_one("Digest"),
+            headers.get_one("Content-MD5"),
+        ) {
+            Ok(checksums) => request::Outcome::Success(Self(checksums)),
+            Err(e) => request::Outcome::Error((Status::BadRequest, e)),
+        }
+    }
+}
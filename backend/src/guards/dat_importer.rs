@@ -0,0 +1,30 @@
+use crate::utils::dat_imports;
+use retronomicon_db::RetronomiconDbPool;
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome};
+
+/// Runs DAT imports in the background, using the database pool of the
+/// server.
+pub struct DatImporter {
+    pool: RetronomiconDbPool,
+}
+
+impl DatImporter {
+    pub fn spawn(&self, import_id: i32) {
+        dat_imports::spawn(self.pool.clone(), import_id);
+    }
+}
+
+#[rocket::async_trait]
+impl<'a> FromRequest<'a> for DatImporter {
+    type Error = String;
+
+    async fn from_request(
+        request: &'a rocket::Request<'_>,
+    ) -> rocket::request::Outcome<Self, Self::Error> {
+        match request.rocket().state::<RetronomiconDbPool>() {
+            Some(pool) => Outcome::Success(DatImporter { pool: pool.clone() }),
+            None => Outcome::Error((Status::InternalServerError, "No database pool".to_string())),
+        }
+    }
+}
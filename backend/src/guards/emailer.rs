@@ -1,7 +1,9 @@
 use crate::fairings::config::RetronomiconConfig;
+use crate::fairings::template::TemplateResolver;
 use lettre::message::header::ContentType;
 use lettre::transport::smtp::authentication::Credentials;
 use lettre::{Message, SmtpTransport, Transport};
+use retronomicon_dto::notifications::NotificationKind;
 use rocket::error;
 use rocket::http::Status;
 use rocket::request::{FromRequest, Outcome};
@@ -26,9 +28,10 @@ pub struct SmtpConfig {
     pub from: String,
 }
 
+#[derive(Clone)]
 pub struct EmailGuard {
     config: SmtpConfig,
-    template: String,
+    templates: TemplateResolver,
 }
 
 #[rocket::async_trait]
@@ -42,35 +45,136 @@ impl<'r> FromRequest<'r> for EmailGuard {
         };
         let smtp_config = config.smtp.clone();
 
-        let template = config.templates().email_verification();
-
         Outcome::Success(Self {
             config: smtp_config,
-            template,
+            templates: config.templates(),
         })
     }
 }
 
 impl EmailGuard {
     pub fn send_email_verification(&self, email: &str, url: &str) -> Result<(), (Status, String)> {
-        let server_url = match self.config.server.as_ref() {
-            Some(api_key) => api_key,
-            None => {
-                rocket::warn!("No SMTP server set, not sending email");
-                rocket::warn!("Url to validate email: {}", url);
-                return Ok(());
+        if self.config.server.is_none() {
+            rocket::warn!("No SMTP server set, not sending email");
+            rocket::warn!("Url to validate email: {}", url);
+            return Ok(());
+        }
+
+        self.send(
+            email,
+            "Retronomicon Email Verification",
+            &self.templates.email_verification(),
+            json!({
+                "email": email,
+                "url": url,
+            }),
+        )
+    }
+
+    pub fn send_password_reset(
+        &self,
+        email: &str,
+        url: &str,
+        hours: i64,
+    ) -> Result<(), (Status, String)> {
+        if self.config.server.is_none() {
+            rocket::warn!("No SMTP server set, not sending email");
+            rocket::warn!("Url to reset password: {}", url);
+            return Ok(());
+        }
+
+        self.send(
+            email,
+            "Retronomicon Password Reset",
+            &self.templates.password_reset(),
+            json!({
+                "email": email,
+                "url": url,
+                "hours": hours,
+            }),
+        )
+    }
+
+    pub fn send_email_change(
+        &self,
+        email: &str,
+        url: &str,
+        hours: i64,
+    ) -> Result<(), (Status, String)> {
+        if self.config.server.is_none() {
+            rocket::warn!("No SMTP server set, not sending email");
+            rocket::warn!("Url to confirm email change: {}", url);
+            return Ok(());
+        }
+
+        self.send(
+            email,
+            "Retronomicon Email Change",
+            &self.templates.email_change(),
+            json!({
+                "email": email,
+                "url": url,
+                "hours": hours,
+            }),
+        )
+    }
+
+    pub fn send_email_change_notice(
+        &self,
+        email: &str,
+        new_email: &str,
+    ) -> Result<(), (Status, String)> {
+        self.send(
+            email,
+            "Retronomicon Email Change",
+            &self.templates.email_change_notice(),
+            json!({
+                "email": email,
+                "new_email": new_email,
+            }),
+        )
+    }
+
+    /// Send a notification by email. `data` is the data of the notification,
+    /// with the URL it links to.
+    pub fn send_notification(
+        &self,
+        email: &str,
+        kind: NotificationKind,
+        data: &serde_json::Value,
+    ) -> Result<(), (Status, String)> {
+        let (subject, template) = match kind {
+            NotificationKind::TeamInvitation => (
+                "Retronomicon Team Invitation",
+                self.templates.team_invitation(),
+            ),
+            NotificationKind::ReleaseYanked => (
+                "Retronomicon Release Yanked",
+                self.templates.release_yanked(),
+            ),
+            NotificationKind::CoreRelease => {
+                ("Retronomicon New Release", self.templates.core_release())
             }
         };
 
+        self.send(email, subject, &template, data.clone())
+    }
+
+    fn send(
+        &self,
+        email: &str,
+        subject: &str,
+        template: &str,
+        data: serde_json::Value,
+    ) -> Result<(), (Status, String)> {
+        let server_url = match self.config.server.as_ref() {
+            Some(server_url) => server_url,
+            None => return Ok(()),
+        };
+
         let hbar = handlebars::Handlebars::new();
         let text = hbar
-            .render_template(
-                &self.template,
-                &json!({
-                    "email": email,
-                    "url": url,
-                }),
-            )
+            .render_template(template, &data)
             .map_err(|e| (Status::InternalServerError, e.to_string()))?;
 
         let from = self.config.from.parse().map_err(|e| {
@@ -89,7 +193,7 @@ impl EmailGuard {
         let email = Message::builder()
             .from(from)
             .to(to)
-            .subject("Retronomicon Email Verification")
+            .subject(subject)
             .header(ContentType::TEXT_PLAIN)
             .body(text)
             .unwrap();
@@ -0,0 +1,56 @@
+use rocket::request::{self, FromRequest, Request};
+use rocket_okapi::OpenApiFromRequest;
+use std::convert::Infallible;
+
+/// The languages a client prefers, most preferred first, from the
+/// `Accept-Language` header. Missing or invalid headers mean no preference.
+#[derive(Debug, Clone, Default, OpenApiFromRequest)]
+pub struct AcceptLanguage(pub Vec<String>);
+
+impl AcceptLanguage {
+    /// Parse the value of an `Accept-Language` header, e.g.
+    /// `fr-CH, fr;q=0.9, en;q=0.8, *;q=0.5`. Ranges with a quality of 0
+    /// are not acceptable and are dropped.
+    pub fn parse(header: &str) -> Self {
+        let mut ranges = header
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';').map(str::trim);
+                let language = parts.next().filter(|l| !l.is_empty())?;
+                let quality = parts
+                    .find_map(|p| p.strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.parse::<f32>().ok())?;
+                (quality > 0.0).then(|| (language.to_string(), quality))
+            })
+            .collect::<Vec<_>>();
+
+        // Stable, so ranges of the same quality keep their order.
+        ranges.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        Self(ranges.into_iter().map(|(l, _)| l).collect())
+    }
+
+    /// The preferences with a language explicitly asked for (e.g. with a
+    /// `lang` parameter) first.
+    pub fn with_preferred(self, language: Option<&str>) -> Vec<String> {
+        language
+            .map(str::to_string)
+            .into_iter()
+            .chain(self.0)
+            .collect()
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AcceptLanguage {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        request::Outcome::Success(
+            request
+                .headers()
+                .get_one("Accept-Language")
+                .map(Self::parse)
+                .unwrap_or_default(),
+        )
+    }
+}
@@ -1,15 +1,16 @@
 use reqwest::Url;
 use retronomicon_db::models;
+use rocket::figment::Figment;
 use rocket::http::Status;
 use rocket::outcome::Outcome;
 use rocket::request::FromRequest;
-use s3::creds::error::CredentialsError;
-use s3::creds::Credentials;
-use s3::error::S3Error;
-use s3::Bucket;
-use s3::Region;
 use serde::Deserialize;
-use std::str::Utf8Error;
+
+pub mod local;
+pub mod s3;
+
+pub use self::local::{LocalStorage, LocalStorageConfig};
+pub use self::s3::{S3Storage, StorageConfig};
 
 pub struct Paths;
 
@@ -22,125 +23,169 @@ impl Paths {
         format!("{}/{}/{}", core.slug, release.version, file_name)
     }
 
+    /// Blobs are stored by their content, so identical artifacts are only
+    /// stored once.
+    pub fn path_for_blob(sha256: &[u8]) -> String {
+        let sha256 = hex::encode(sha256);
+        format!("blobs/{}/{}", &sha256[..2], sha256)
+    }
+
     pub fn path_for_game_image(game: &models::Game, filename: &str) -> String {
         format!("games/{}/images/{}", game.id, filename)
     }
-}
-
-#[derive(Debug, Clone, Deserialize)]
-pub struct StorageConfig {
-    region: String,
 
-    access_key: String,
-    secret_key: String,
+    pub fn path_for_game_image_derivative(
+        game: &models::Game,
+        filename: &str,
+        width: u32,
+        height: u32,
+        extension: &str,
+    ) -> String {
+        format!(
+            "games/{}/images/derivatives/{}/{}x{}.{}",
+            game.id, filename, width, height, extension
+        )
+    }
+}
 
-    cores_bucket: String,
-    cores_bucket_url: Option<String>,
-    games_bucket: String,
-    games_bucket_url: Option<String>,
+/// The logical buckets that files are stored in. Each backend maps these
+/// to its own naming (S3 buckets, local directories, etc).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageBucket {
+    Cores,
+    Games,
 }
 
-impl StorageConfig {
-    pub fn credentials(&self) -> Result<Credentials, CredentialsError> {
-        Credentials::new(
-            Some(self.access_key.as_str()),
-            Some(self.secret_key.as_str()),
-            None,
-            None,
-            None,
-        )
+impl StorageBucket {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StorageBucket::Cores => "cores",
+            StorageBucket::Games => "games",
+        }
     }
 
-    pub fn region(&self) -> Result<Region, Utf8Error> {
-        Ok(Region::Custom {
-            region: "eu-central-1".to_owned(),
-            endpoint: self.region.clone(),
-        })
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "cores" => Some(StorageBucket::Cores),
+            "games" => Some(StorageBucket::Games),
+            _ => None,
+        }
     }
 }
 
-pub struct Storage {
-    config: StorageConfig,
+/// Which storage backend to use. Defaults to S3 when the `storage` section
+/// is missing.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackendKind {
+    #[default]
+    S3,
+    Local,
 }
 
-#[rocket::async_trait]
-impl<'a> FromRequest<'a> for Storage {
-    type Error = String;
-
-    async fn from_request(
-        request: &'a rocket::Request<'_>,
-    ) -> rocket::request::Outcome<Self, Self::Error> {
-        let config = match request
-            .rocket()
-            .figment()
-            .extract_inner::<StorageConfig>("s3")
-        {
-            Ok(storage) => storage,
-            Err(e) => return Outcome::Error((Status::InternalServerError, e.to_string())),
-        };
-
-        Outcome::Success(Self {
-            config: config.clone(),
-        })
-    }
+#[derive(Debug, Default, Clone, Deserialize)]
+struct StorageSelection {
+    #[serde(default)]
+    backend: StorageBackendKind,
 }
 
-impl Storage {
-    async fn bucket(&self, bucket_name: &str, public: bool) -> Result<Bucket, S3Error> {
-        let credentials = self.config.credentials()?;
-        let region: Region = self.config.region()?;
-        let mut bucket =
-            Bucket::new(bucket_name, region.clone(), credentials.clone())?.with_path_style();
-
-        if public {
-            bucket.add_header("x-amz-acl", "public-read");
-        }
-
-        Ok(bucket)
-    }
+/// A part of a multipart upload, as returned by the backend.
+#[derive(Debug, Clone)]
+pub struct StoragePart {
+    pub part_number: u32,
+    pub etag: String,
+}
 
+/// A place where files uploaded to the server can be stored and later
+/// downloaded from a public URL.
+#[rocket::async_trait]
+pub trait StorageBackend: Send + Sync {
+    /// Store `data` under `filename` in the bucket, and return the public URL
+    /// to download it from.
     async fn upload(
         &self,
-        bucket_name: &str,
-        bucket_url_base: Option<&str>,
-        public: bool,
+        bucket: StorageBucket,
         filename: &str,
         data: &[u8],
         content_type: &str,
-    ) -> Result<Url, String> {
-        let bucket = self
-            .bucket(bucket_name, public)
-            .await
-            .map_err(|e| e.to_string())?;
+    ) -> Result<Url, String>;
 
-        let response = match bucket
-            .put_object_with_content_type(&filename, data, content_type)
-            .await
-        {
-            Ok(response) => response,
-            Err(e) => {
-                rocket::error!("Failed to upload file to S3: {}", e);
-                return Err(e.to_string());
-            }
-        };
+    /// Start uploading a file in multiple parts. Returns an ID identifying
+    /// the upload in the backend.
+    async fn create_multipart(
+        &self,
+        bucket: StorageBucket,
+        filename: &str,
+        content_type: &str,
+    ) -> Result<String, String>;
 
-        if response.status_code() != 200 {
-            rocket::error!("Failed to upload file to S3: {}", response.status_code());
-            return Err(format!(
-                "Failed to upload file to S3: {}",
-                response.status_code()
-            ));
-        }
+    /// Store a single part of a multipart upload. Parts are numbered from 1.
+    /// Returns the ETag of the part, needed to complete the upload.
+    async fn upload_part(
+        &self,
+        bucket: StorageBucket,
+        filename: &str,
+        upload_id: &str,
+        part_number: u32,
+        data: Vec<u8>,
+        content_type: &str,
+    ) -> Result<String, String>;
 
-        let url = match bucket_url_base {
-            Some(url_base) => Url::parse(url_base),
-            None => Url::parse(&bucket.url()),
-        }
-        .map_err(|e| e.to_string())?
-        .join(filename)
-        .map_err(|e| e.to_string())?;
+    /// Assemble all parts into the final file, and return its public URL.
+    async fn complete_multipart(
+        &self,
+        bucket: StorageBucket,
+        filename: &str,
+        upload_id: &str,
+        parts: Vec<StoragePart>,
+    ) -> Result<Url, String>;
+
+    /// Cancel a multipart upload and discard all its parts.
+    async fn abort_multipart(
+        &self,
+        bucket: StorageBucket,
+        filename: &str,
+        upload_id: &str,
+    ) -> Result<(), String>;
+
+    /// Copy a file within the bucket, and return the public URL of the copy.
+    async fn copy(&self, bucket: StorageBucket, from: &str, to: &str) -> Result<Url, String>;
+
+    /// Read a file in chunks, so large files are not held in memory.
+    async fn read(
+        &self,
+        bucket: StorageBucket,
+        filename: &str,
+        on_chunk: &mut (dyn for<'c> FnMut(&'c [u8]) + Send),
+    ) -> Result<(), String>;
+
+    /// Delete a file. Deleting a file that does not exist is not an error.
+    async fn delete(&self, bucket: StorageBucket, filename: &str) -> Result<(), String>;
+}
+
+pub struct Storage {
+    backend: Box<dyn StorageBackend>,
+}
 
-        Ok(url)
+impl Storage {
+    pub fn from_figment(figment: &Figment) -> Result<Self, String> {
+        let selection = match figment.find_value("storage") {
+            Ok(_) => figment
+                .extract_inner::<StorageSelection>("storage")
+                .map_err(|e| e.to_string())?,
+            Err(_) => StorageSelection::default(),
+        };
+
+        let backend: Box<dyn StorageBackend> = match selection.backend {
+            StorageBackendKind::S3 => Box::new(S3Storage::new(
+                figment
+                    .extract_inner::<StorageConfig>("s3")
+                    .map_err(|e| e.to_string())?,
+            )),
+            StorageBackendKind::Local => Box::new(LocalStorage::from_figment(figment)?),
+        };
+
+        Ok(Self { backend })
     }
 
     pub async fn upload_core(
@@ -149,16 +194,10 @@ impl Storage {
         data: &[u8],
         content_type: &str,
     ) -> Result<String, String> {
-        self.upload(
-            self.config.cores_bucket.as_str(),
-            self.config.cores_bucket_url.as_deref(),
-            true,
-            filename,
-            data,
-            content_type,
-        )
-        .await
-        .map(|url| url.to_string())
+        self.backend
+            .upload(StorageBucket::Cores, filename, data, content_type)
+            .await
+            .map(|url| url.to_string())
     }
 
     pub async fn upload_game_asset(
@@ -167,15 +206,97 @@ impl Storage {
         data: &[u8],
         content_type: &str,
     ) -> Result<String, String> {
-        self.upload(
-            self.config.games_bucket.as_str(),
-            self.config.games_bucket_url.as_deref(),
-            true,
-            filename,
-            data,
-            content_type,
-        )
-        .await
-        .map(|url| url.to_string())
+        self.backend
+            .upload(StorageBucket::Games, filename, data, content_type)
+            .await
+            .map(|url| url.to_string())
+    }
+
+    pub async fn create_multipart(
+        &self,
+        bucket: StorageBucket,
+        filename: &str,
+        content_type: &str,
+    ) -> Result<String, String> {
+        self.backend
+            .create_multipart(bucket, filename, content_type)
+            .await
+    }
+
+    pub async fn upload_part(
+        &self,
+        bucket: StorageBucket,
+        filename: &str,
+        upload_id: &str,
+        part_number: u32,
+        data: Vec<u8>,
+        content_type: &str,
+    ) -> Result<String, String> {
+        self.backend
+            .upload_part(bucket, filename, upload_id, part_number, data, content_type)
+            .await
+    }
+
+    pub async fn complete_multipart(
+        &self,
+        bucket: StorageBucket,
+        filename: &str,
+        upload_id: &str,
+        parts: Vec<StoragePart>,
+    ) -> Result<String, String> {
+        self.backend
+            .complete_multipart(bucket, filename, upload_id, parts)
+            .await
+            .map(|url| url.to_string())
+    }
+
+    pub async fn abort_multipart(
+        &self,
+        bucket: StorageBucket,
+        filename: &str,
+        upload_id: &str,
+    ) -> Result<(), String> {
+        self.backend
+            .abort_multipart(bucket, filename, upload_id)
+            .await
+    }
+
+    pub async fn copy(
+        &self,
+        bucket: StorageBucket,
+        from: &str,
+        to: &str,
+    ) -> Result<String, String> {
+        self.backend
+            .copy(bucket, from, to)
+            .await
+            .map(|url| url.to_string())
+    }
+
+    pub async fn read(
+        &self,
+        bucket: StorageBucket,
+        filename: &str,
+        on_chunk: &mut (dyn for<'c> FnMut(&'c [u8]) + Send),
+    ) -> Result<(), String> {
+        self.backend.read(bucket, filename, on_chunk).await
+    }
+
+    pub async fn delete(&self, bucket: StorageBucket, filename: &str) -> Result<(), String> {
+        self.backend.delete(bucket, filename).await
+    }
+}
+
+#[rocket::async_trait]
+impl<'a> FromRequest<'a> for Storage {
+    type Error = String;
+
+    async fn from_request(
+        request: &'a rocket::Request<'_>,
+    ) -> rocket::request::Outcome<Self, Self::Error> {
+        match Storage::from_figment(request.rocket().figment()) {
+            Ok(storage) => Outcome::Success(storage),
+            Err(e) => Outcome::Error((Status::InternalServerError, e)),
+        }
     }
 }
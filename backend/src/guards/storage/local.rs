@@ -0,0 +1,328 @@
+use super::{StorageBackend, StorageBucket, StoragePart};
+use rand::Rng;
+use reqwest::Url;
+use rocket::figment::Figment;
+use rocket::http::Status;
+use rocket::outcome::Outcome;
+use rocket::request::FromRequest;
+use rocket::tokio::{fs, io};
+use serde::Deserialize;
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LocalStorageConfig {
+    /// The directory to store files in. Each bucket is a subdirectory.
+    pub root: PathBuf,
+
+    /// The public URL that files are served from. If unset, files are served
+    /// by the backend itself under `{base_url}/api/files/`.
+    pub url: Option<String>,
+}
+
+impl LocalStorageConfig {
+    pub fn from_figment(figment: &Figment) -> Result<Self, String> {
+        figment
+            .extract_inner::<Self>("storage.local")
+            .map_err(|e| e.to_string())
+    }
+
+    /// Returns the path of a file on disk, making sure it cannot escape its
+    /// bucket directory.
+    pub fn path_for(&self, bucket: StorageBucket, filename: &Path) -> Option<PathBuf> {
+        if filename.as_os_str().is_empty()
+            || !filename
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)))
+        {
+            return None;
+        }
+
+        Some(self.root.join(bucket.name()).join(filename))
+    }
+
+    /// Returns the file holding the content type of a file, outside of the
+    /// bucket directories so it cannot be served itself.
+    fn content_type_path_for(&self, bucket: StorageBucket, filename: &Path) -> Option<PathBuf> {
+        self.path_for(bucket, filename)?;
+        Some(
+            self.root
+                .join(".content-types")
+                .join(bucket.name())
+                .join(filename),
+        )
+    }
+
+    /// Returns the content type a file was uploaded with, if it was recorded.
+    pub async fn content_type_for(&self, bucket: StorageBucket, filename: &Path) -> Option<String> {
+        let path = self.content_type_path_for(bucket, filename)?;
+        fs::read_to_string(path).await.ok()
+    }
+
+    /// Returns the directory holding the parts of a multipart upload.
+    fn multipart_dir(&self, upload_id: &str) -> Option<PathBuf> {
+        if upload_id.is_empty() || !upload_id.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        Some(self.root.join(".multipart").join(upload_id))
+    }
+}
+
+#[rocket::async_trait]
+impl<'a> FromRequest<'a> for LocalStorageConfig {
+    type Error = String;
+
+    async fn from_request(
+        request: &'a rocket::Request<'_>,
+    ) -> rocket::request::Outcome<Self, Self::Error> {
+        // Without a local storage configured, there is nothing to serve.
+        match Self::from_figment(request.rocket().figment()) {
+            Ok(config) => Outcome::Success(config),
+            Err(_) => Outcome::Forward(Status::NotFound),
+        }
+    }
+}
+
+pub struct LocalStorage {
+    config: LocalStorageConfig,
+    url_base: Url,
+}
+
+impl LocalStorage {
+    pub fn from_figment(figment: &Figment) -> Result<Self, String> {
+        let config = LocalStorageConfig::from_figment(figment)?;
+        let url_base = match config.url {
+            Some(ref url) => Url::parse(url),
+            None => {
+                let base_url = figment
+                    .extract_inner::<String>("base_url")
+                    .map_err(|e| e.to_string())?;
+                Url::parse(&base_url).and_then(|u| u.join("api/files/"))
+            }
+        }
+        .map_err(|e| e.to_string())?;
+
+        Ok(Self { config, url_base })
+    }
+
+    fn url_for(&self, bucket: StorageBucket, filename: &str) -> Result<Url, String> {
+        self.url_base
+            .join(&format!("{}/", bucket.name()))
+            .and_then(|u| u.join(filename))
+            .map_err(|e| e.to_string())
+    }
+
+    fn multipart_dir(&self, upload_id: &str) -> Result<PathBuf, String> {
+        self.config
+            .multipart_dir(upload_id)
+            .ok_or_else(|| format!("Invalid upload ID: {upload_id}"))
+    }
+
+    /// Write a file, creating its directory if needed.
+    async fn write(path: &Path, data: &[u8]) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await.map_err(|e| {
+                rocket::error!("Failed to create directory {:?}: {}", parent, e);
+                e.to_string()
+            })?;
+        }
+        fs::write(path, data).await.map_err(|e| {
+            rocket::error!("Failed to write file {:?}: {}", path, e);
+            e.to_string()
+        })
+    }
+
+    /// Record the content type of a file, to serve it with.
+    async fn set_content_type(
+        &self,
+        bucket: StorageBucket,
+        filename: &str,
+        content_type: &str,
+    ) -> Result<(), String> {
+        let path = self
+            .config
+            .content_type_path_for(bucket, Path::new(filename))
+            .ok_or_else(|| format!("Invalid file name: {filename}"))?;
+        Self::write(&path, content_type.as_bytes()).await
+    }
+}
+
+/// Remove a file, ignoring files that do not exist.
+async fn remove_file(path: &Path) -> Result<(), String> {
+    match fs::remove_file(path).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[rocket::async_trait]
+impl StorageBackend for LocalStorage {
+    async fn upload(
+        &self,
+        bucket: StorageBucket,
+        filename: &str,
+        data: &[u8],
+        content_type: &str,
+    ) -> Result<Url, String> {
+        let path = self
+            .config
+            .path_for(bucket, Path::new(filename))
+            .ok_or_else(|| format!("Invalid file name: {filename}"))?;
+
+        Self::write(&path, data).await?;
+        self.set_content_type(bucket, filename, content_type)
+            .await?;
+        self.url_for(bucket, filename)
+    }
+
+    async fn create_multipart(
+        &self,
+        _bucket: StorageBucket,
+        _filename: &str,
+        content_type: &str,
+    ) -> Result<String, String> {
+        let upload_id = hex::encode(rand::thread_rng().gen::<[u8; 16]>());
+        // The content type is kept with the parts until the upload completes.
+        Self::write(
+            &self.multipart_dir(&upload_id)?.join("content-type"),
+            content_type.as_bytes(),
+        )
+        .await?;
+        Ok(upload_id)
+    }
+
+    async fn upload_part(
+        &self,
+        _bucket: StorageBucket,
+        _filename: &str,
+        upload_id: &str,
+        part_number: u32,
+        data: Vec<u8>,
+        _content_type: &str,
+    ) -> Result<String, String> {
+        let path = self.multipart_dir(upload_id)?.join(part_number.to_string());
+        let etag = hex::encode(md5::compute(&data).0);
+        fs::write(&path, data).await.map_err(|e| {
+            rocket::error!("Failed to write file {:?}: {}", path, e);
+            e.to_string()
+        })?;
+        Ok(etag)
+    }
+
+    async fn complete_multipart(
+        &self,
+        bucket: StorageBucket,
+        filename: &str,
+        upload_id: &str,
+        parts: Vec<StoragePart>,
+    ) -> Result<Url, String> {
+        let dir = self.multipart_dir(upload_id)?;
+        let path = self
+            .config
+            .path_for(bucket, Path::new(filename))
+            .ok_or_else(|| format!("Invalid file name: {filename}"))?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+
+        let mut output = fs::File::create(&path).await.map_err(|e| e.to_string())?;
+        for part in parts {
+            let mut input = fs::File::open(dir.join(part.part_number.to_string()))
+                .await
+                .map_err(|e| e.to_string())?;
+            io::copy(&mut input, &mut output)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        output.sync_all().await.map_err(|e| e.to_string())?;
+
+        if let Ok(content_type) = fs::read_to_string(dir.join("content-type")).await {
+            self.set_content_type(bucket, filename, &content_type)
+                .await?;
+        }
+        fs::remove_dir_all(&dir).await.map_err(|e| e.to_string())?;
+        self.url_for(bucket, filename)
+    }
+
+    async fn abort_multipart(
+        &self,
+        _bucket: StorageBucket,
+        _filename: &str,
+        upload_id: &str,
+    ) -> Result<(), String> {
+        let dir = self.multipart_dir(upload_id)?;
+        match fs::remove_dir_all(&dir).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    async fn copy(&self, bucket: StorageBucket, from: &str, to: &str) -> Result<Url, String> {
+        let from_path = self
+            .config
+            .path_for(bucket, Path::new(from))
+            .ok_or_else(|| format!("Invalid file name: {from}"))?;
+        let to_path = self
+            .config
+            .path_for(bucket, Path::new(to))
+            .ok_or_else(|| format!("Invalid file name: {to}"))?;
+
+        if let Some(parent) = to_path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        fs::copy(&from_path, &to_path).await.map_err(|e| {
+            rocket::error!("Failed to copy file {:?}: {}", from_path, e);
+            e.to_string()
+        })?;
+
+        if let Some(content_type) = self.config.content_type_for(bucket, Path::new(from)).await {
+            self.set_content_type(bucket, to, &content_type).await?;
+        }
+        self.url_for(bucket, to)
+    }
+
+    async fn read(
+        &self,
+        bucket: StorageBucket,
+        filename: &str,
+        on_chunk: &mut (dyn for<'c> FnMut(&'c [u8]) + Send),
+    ) -> Result<(), String> {
+        use rocket::tokio::io::AsyncReadExt;
+
+        let path = self
+            .config
+            .path_for(bucket, Path::new(filename))
+            .ok_or_else(|| format!("Invalid file name: {filename}"))?;
+        let mut file = fs::File::open(&path).await.map_err(|e| e.to_string())?;
+        let mut buffer = vec![0; 1024 * 1024];
+        loop {
+            let len = file.read(&mut buffer).await.map_err(|e| e.to_string())?;
+            if len == 0 {
+                return Ok(());
+            }
+            on_chunk(&buffer[..len]);
+        }
+    }
+
+    async fn delete(&self, bucket: StorageBucket, filename: &str) -> Result<(), String> {
+        let path = self
+            .config
+            .path_for(bucket, Path::new(filename))
+            .ok_or_else(|| format!("Invalid file name: {filename}"))?;
+        remove_file(&path).await?;
+        if let Some(path) = self
+            .config
+            .content_type_path_for(bucket, Path::new(filename))
+        {
+            remove_file(&path).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,306 @@
+use super::{StorageBackend, StorageBucket, StoragePart};
+use ::s3::creds::error::CredentialsError;
+use ::s3::creds::Credentials;
+use ::s3::error::S3Error;
+use ::s3::serde_types::Part;
+use ::s3::Bucket;
+use ::s3::Region;
+use reqwest::Url;
+use serde::Deserialize;
+use std::str::Utf8Error;
+
+fn default_region_name() -> String {
+    "eu-central-1".to_owned()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StorageConfig {
+    /// The endpoint of the S3 service.
+    region: String,
+
+    /// The name of the region, as sent in the signature.
+    #[serde(default = "default_region_name")]
+    region_name: String,
+
+    access_key: String,
+    secret_key: String,
+
+    cores_bucket: String,
+    cores_bucket_url: Option<String>,
+    games_bucket: String,
+    games_bucket_url: Option<String>,
+}
+
+impl StorageConfig {
+    pub fn credentials(&self) -> Result<Credentials, CredentialsError> {
+        Credentials::new(
+            Some(self.access_key.as_str()),
+            Some(self.secret_key.as_str()),
+            None,
+            None,
+            None,
+        )
+    }
+
+    pub fn region(&self) -> Result<Region, Utf8Error> {
+        Ok(Region::Custom {
+            region: self.region_name.clone(),
+            endpoint: self.region.clone(),
+        })
+    }
+
+    fn bucket_of(&self, bucket: StorageBucket) -> (&str, Option<&str>) {
+        match bucket {
+            StorageBucket::Cores => (self.cores_bucket.as_str(), self.cores_bucket_url.as_deref()),
+            StorageBucket::Games => (self.games_bucket.as_str(), self.games_bucket_url.as_deref()),
+        }
+    }
+}
+
+pub struct S3Storage {
+    config: StorageConfig,
+}
+
+impl S3Storage {
+    pub fn new(config: StorageConfig) -> Self {
+        Self { config }
+    }
+
+    async fn bucket(&self, bucket_name: &str, public: bool) -> Result<Bucket, S3Error> {
+        let credentials = self.config.credentials()?;
+        let region: Region = self.config.region()?;
+        let mut bucket =
+            Bucket::new(bucket_name, region.clone(), credentials.clone())?.with_path_style();
+
+        if public {
+            bucket.add_header("x-amz-acl", "public-read");
+        }
+
+        Ok(bucket)
+    }
+
+    fn url_for(
+        &self,
+        bucket: &Bucket,
+        url_base: Option<&str>,
+        filename: &str,
+    ) -> Result<Url, String> {
+        match url_base {
+            Some(url_base) => Url::parse(url_base),
+            None => Url::parse(&bucket.url()),
+        }
+        .map_err(|e| e.to_string())?
+        .join(filename)
+        .map_err(|e| e.to_string())
+    }
+}
+
+#[rocket::async_trait]
+impl StorageBackend for S3Storage {
+    async fn upload(
+        &self,
+        bucket: StorageBucket,
+        filename: &str,
+        data: &[u8],
+        content_type: &str,
+    ) -> Result<Url, String> {
+        let (bucket_name, bucket_url_base) = self.config.bucket_of(bucket);
+        let bucket = self
+            .bucket(bucket_name, true)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let response = match bucket
+            .put_object_with_content_type(filename, data, content_type)
+            .await
+        {
+            Ok(response) => response,
+            Err(e) => {
+                rocket::error!("Failed to upload file to S3: {}", e);
+                return Err(e.to_string());
+            }
+        };
+
+        if response.status_code() != 200 {
+            rocket::error!("Failed to upload file to S3: {}", response.status_code());
+            return Err(format!(
+                "Failed to upload file to S3: {}",
+                response.status_code()
+            ));
+        }
+
+        self.url_for(&bucket, bucket_url_base, filename)
+    }
+
+    async fn create_multipart(
+        &self,
+        bucket: StorageBucket,
+        filename: &str,
+        content_type: &str,
+    ) -> Result<String, String> {
+        let (bucket_name, _) = self.config.bucket_of(bucket);
+        let bucket = self
+            .bucket(bucket_name, true)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        bucket
+            .initiate_multipart_upload(filename, content_type)
+            .await
+            .map(|response| response.upload_id)
+            .map_err(|e| {
+                rocket::error!("Failed to start multipart upload to S3: {}", e);
+                e.to_string()
+            })
+    }
+
+    async fn upload_part(
+        &self,
+        bucket: StorageBucket,
+        filename: &str,
+        upload_id: &str,
+        part_number: u32,
+        data: Vec<u8>,
+        content_type: &str,
+    ) -> Result<String, String> {
+        let (bucket_name, _) = self.config.bucket_of(bucket);
+        let bucket = self
+            .bucket(bucket_name, true)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        bucket
+            .put_multipart_chunk(data, filename, part_number, upload_id, content_type)
+            .await
+            .map(|part| part.etag)
+            .map_err(|e| {
+                rocket::error!("Failed to upload part to S3: {}", e);
+                e.to_string()
+            })
+    }
+
+    async fn complete_multipart(
+        &self,
+        bucket: StorageBucket,
+        filename: &str,
+        upload_id: &str,
+        parts: Vec<StoragePart>,
+    ) -> Result<Url, String> {
+        let (bucket_name, bucket_url_base) = self.config.bucket_of(bucket);
+        let bucket = self
+            .bucket(bucket_name, true)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let parts = parts
+            .into_iter()
+            .map(|StoragePart { part_number, etag }| Part { part_number, etag })
+            .collect();
+        let response = bucket
+            .complete_multipart_upload(filename, upload_id, parts)
+            .await
+            .map_err(|e| {
+                rocket::error!("Failed to complete multipart upload to S3: {}", e);
+                e.to_string()
+            })?;
+
+        if response.status_code() != 200 {
+            rocket::error!(
+                "Failed to complete multipart upload to S3: {}",
+                response.status_code()
+            );
+            return Err(format!(
+                "Failed to complete multipart upload to S3: {}",
+                response.status_code()
+            ));
+        }
+
+        self.url_for(&bucket, bucket_url_base, filename)
+    }
+
+    async fn abort_multipart(
+        &self,
+        bucket: StorageBucket,
+        filename: &str,
+        upload_id: &str,
+    ) -> Result<(), String> {
+        let (bucket_name, _) = self.config.bucket_of(bucket);
+        let bucket = self
+            .bucket(bucket_name, true)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        bucket
+            .abort_upload(filename, upload_id)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn copy(&self, bucket: StorageBucket, from: &str, to: &str) -> Result<Url, String> {
+        let (bucket_name, bucket_url_base) = self.config.bucket_of(bucket);
+        let bucket = self
+            .bucket(bucket_name, true)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        // The copy keeps the content type of the original object.
+        let status = bucket.copy_object_internal(from, to).await.map_err(|e| {
+            rocket::error!("Failed to copy file in S3: {}", e);
+            e.to_string()
+        })?;
+        if status != 200 {
+            rocket::error!("Failed to copy file in S3: {}", status);
+            return Err(format!("Failed to copy file in S3: {status}"));
+        }
+
+        self.url_for(&bucket, bucket_url_base, to)
+    }
+
+    async fn read(
+        &self,
+        bucket: StorageBucket,
+        filename: &str,
+        on_chunk: &mut (dyn for<'c> FnMut(&'c [u8]) + Send),
+    ) -> Result<(), String> {
+        use rocket::futures::StreamExt;
+
+        let (bucket_name, _) = self.config.bucket_of(bucket);
+        let bucket = self
+            .bucket(bucket_name, false)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut response = bucket.get_object_stream(filename).await.map_err(|e| {
+            rocket::error!("Failed to read file from S3: {}", e);
+            e.to_string()
+        })?;
+        if response.status_code != 200 {
+            return Err(format!(
+                "Failed to read file from S3: {}",
+                response.status_code
+            ));
+        }
+        while let Some(chunk) = response.bytes().next().await {
+            on_chunk(&chunk.map_err(|e| e.to_string())?);
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, bucket: StorageBucket, filename: &str) -> Result<(), String> {
+        let (bucket_name, _) = self.config.bucket_of(bucket);
+        let bucket = self
+            .bucket(bucket_name, false)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        // S3 returns 204 whether the object existed or not.
+        let response = bucket.delete_object(filename).await.map_err(|e| {
+            rocket::error!("Failed to delete file from S3: {}", e);
+            e.to_string()
+        })?;
+        match response.status_code() {
+            200 | 204 => Ok(()),
+            code => Err(format!("Failed to delete file from S3: {code}")),
+        }
+    }
+}
@@ -1,6 +1,9 @@
 use crate::fairings::config::{JwtKeys, RetronomiconConfig};
+use crate::utils::tokens;
 use jsonwebtoken::{DecodingKey, EncodingKey};
-use retronomicon_db::models::{User, UserTeam};
+use retronomicon_db::models::{
+    User, UserSession, UserTeam, UserToken, UserTwoFactor, USER_TOKEN_PREFIX,
+};
 use retronomicon_db::Db;
 use retronomicon_dto as dto;
 use rocket::http::{Cookie, CookieJar, Status};
@@ -9,6 +12,7 @@ use rocket::{request, warn, Request, State};
 use rocket_okapi::OpenApiFromRequest;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::convert::Infallible;
 use std::ops::{Deref, DerefMut};
 
 /// A user that is part of the root team.
@@ -41,6 +45,16 @@ impl<'r> request::FromRequest<'r> for RootUserGuard {
             .await
             .map_err(|e| e.to_string());
         match result {
+            Ok(true) if config.root_team_require_two_factor => {
+                match UserTwoFactor::is_enabled(&mut db, user.id).await {
+                    Ok(true) => Outcome::Success(RootUserGuard { id: user.id }),
+                    Ok(false) => Outcome::Error((
+                        Status::Forbidden,
+                        "Root team members must enable two-factor authentication".into(),
+                    )),
+                    Err(e) => Outcome::Error((Status::InternalServerError, e.to_string())),
+                }
+            }
             Ok(true) => Outcome::Success(RootUserGuard { id: user.id }),
             Ok(false) => Outcome::Forward(Status::Unauthorized),
             Err(e) => Outcome::Error((Status::InternalServerError, e)),
@@ -118,6 +132,21 @@ pub struct UserGuard {
     pub username: Option<String>,
 
     pub exp: i64,
+
+    /// When this session or JWT was issued, in milliseconds since the epoch.
+    /// Sessions issued before the user's sessions were invalidated (e.g. on
+    /// password reset) are rejected.
+    #[serde(default)]
+    pub issued_at: i64,
+
+    /// The key of the server-side session of an auth cookie. JWTs and
+    /// personal access tokens don't have one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    session: Option<String>,
+
+    /// The ID of the session, once it was validated.
+    #[serde(skip)]
+    pub session_id: Option<i32>,
 }
 
 #[rocket::async_trait]
@@ -142,13 +171,37 @@ impl<'r> request::FromRequest<'r> for UserGuard {
             let json: Result<UserGuard, _> =
                 serde_json::from_str(cookie.value()).map_err(|e| e.to_string());
 
-            return json
-                .or_error(Status::Unauthorized)
-                .and_then(validate_exp)
-                .and_then(|user: UserGuard| {
+            let user = match json.or_error(Status::Unauthorized).and_then(validate_exp) {
+                Outcome::Success(user) => validate_cookie_session(request, user).await,
+                outcome => outcome,
+            };
+            return match user {
+                Outcome::Success(user) => validate_session(request, user).await.and_then(|user| {
                     user.update_cookie(cookies);
                     Outcome::Success(user)
-                });
+                }),
+                outcome => outcome,
+            };
+        }
+
+        let authorization = request.headers().get_one("Authorization");
+
+        // Check personal access tokens, caching the result as this guard is
+        // used by other guards.
+        if let Some(token) = authorization
+            .map(|a| a.trim_start_matches("Bearer ").trim())
+            .filter(|t| t.starts_with(USER_TOKEN_PREFIX))
+        {
+            let AccessTokenOutcome(outcome) = request
+                .local_cache_async(async {
+                    AccessTokenOutcome(UserGuard::from_access_token(request, token).await)
+                })
+                .await;
+
+            return match outcome {
+                Ok(user) => Outcome::Success(user.clone()),
+                Err(e) => Outcome::Error(e.clone()),
+            };
         }
 
         let jwt_secret = match request.guard::<&State<JwtKeys>>().await {
@@ -158,13 +211,94 @@ impl<'r> request::FromRequest<'r> for UserGuard {
         };
 
         // Check JWT from the headers.
-        request
-            .headers()
-            .get_one("Authorization")
+        match authorization
             .ok_or("Unauthorized".to_string())
             .and_then(|key| UserGuard::decode_jwt(key, jwt_secret).map_err(|e| e.to_string()))
             .or_error(Status::Unauthorized)
             .and_then(validate_exp)
+        {
+            Outcome::Success(user) => validate_session(request, user).await,
+            outcome => outcome,
+        }
+    }
+}
+
+struct AccessTokenOutcome(Result<UserGuard, (Status, String)>);
+
+struct SessionsInvalidatedAt(Result<Option<i64>, String>);
+
+struct CookieSession(Result<Option<UserSession>, String>);
+
+/// How often the last time a session was seen is updated, in minutes.
+const SESSION_TOUCH_MINUTES: i64 = 1;
+
+async fn session_from_key(db: &mut Db, key: &str) -> Result<Option<UserSession>, String> {
+    let session = UserSession::get_by_key(db, key)
+        .await
+        .map_err(|e| e.to_string())?
+        .filter(|session| !session.is_expired());
+    if let Some(session) = &session {
+        let touch_after = session.last_seen_at + chrono::Duration::minutes(SESSION_TOUCH_MINUTES);
+        if touch_after < chrono::Utc::now().naive_utc() {
+            session.touch(db).await.map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(session)
+}
+
+/// Reject auth cookies whose session was revoked or expired. The lookup is
+/// cached as this guard is used by other guards.
+async fn validate_cookie_session(
+    request: &Request<'_>,
+    mut user: UserGuard,
+) -> request::Outcome<UserGuard, String> {
+    let Some(key) = user.session.as_deref() else {
+        return Outcome::Forward(Status::Unauthorized);
+    };
+
+    let CookieSession(session) = request
+        .local_cache_async(async {
+            let result = match request.guard::<Db>().await {
+                Outcome::Success(mut db) => session_from_key(&mut db, key).await,
+                _ => Err("Database unavailable".to_string()),
+            };
+            CookieSession(result)
+        })
+        .await;
+
+    match session {
+        Ok(Some(session)) if session.user_id == user.id => {
+            user.session_id = Some(session.id);
+            Outcome::Success(user)
+        }
+        Ok(_) => Outcome::Forward(Status::Unauthorized),
+        Err(e) => Outcome::Error((Status::InternalServerError, e.clone())),
+    }
+}
+
+/// Reject sessions and JWTs issued before the sessions of their user were
+/// invalidated. The lookup is cached as this guard is used by other guards.
+async fn validate_session(
+    request: &Request<'_>,
+    user: UserGuard,
+) -> request::Outcome<UserGuard, String> {
+    let SessionsInvalidatedAt(invalidated_at) = request
+        .local_cache_async(async {
+            let result = match request.guard::<Db>().await {
+                Outcome::Success(mut db) => User::sessions_invalidated_at(&mut db, user.id)
+                    .await
+                    .map(|at| at.map(|at| at.and_utc().timestamp_millis()))
+                    .map_err(|e| e.to_string()),
+                _ => Err("Database unavailable".to_string()),
+            };
+            SessionsInvalidatedAt(result)
+        })
+        .await;
+
+    match invalidated_at {
+        Ok(Some(at)) if user.issued_at < *at => Outcome::Forward(Status::Unauthorized),
+        Ok(_) => Outcome::Success(user),
+        Err(e) => Outcome::Error((Status::InternalServerError, e.clone())),
     }
 }
 
@@ -210,7 +344,14 @@ impl UserGuard {
     }
 
     pub fn new_unchecked(id: i32, username: Option<String>, exp: i64) -> Self {
-        Self { id, username, exp }
+        Self {
+            id,
+            username,
+            exp,
+            issued_at: chrono::Utc::now().timestamp_millis(),
+            session: None,
+            session_id: None,
+        }
     }
 
     pub fn set_expiry(&mut self, expiry: i64) {
@@ -279,6 +420,74 @@ impl UserGuard {
         Ok((true, user.clone(), Self::from_model(user)))
     }
 
+    /// Start a session for a user that proved who they are, with a password
+    /// or an OAuth provider. If the user enabled two-factor authentication,
+    /// the login is left pending until a code is verified and this returns
+    /// true. Otherwise the session is created.
+    pub async fn start_session(
+        db: &mut Db,
+        cookies: &CookieJar<'_>,
+        client: &ClientInfo,
+        user: User,
+    ) -> Result<bool, (Status, String)> {
+        let two_factor = UserTwoFactor::is_enabled(db, user.id)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+        if two_factor {
+            PendingLogin::new(user.id).update_cookie(cookies);
+        } else {
+            Self::create_session(db, cookies, client, user).await?;
+        }
+        Ok(two_factor)
+    }
+
+    /// Create a server-side session for a user and set the auth cookie.
+    pub async fn create_session(
+        db: &mut Db,
+        cookies: &CookieJar<'_>,
+        client: &ClientInfo,
+        user: User,
+    ) -> Result<Self, (Status, String)> {
+        let mut guard = Self::from_model(user);
+        let (key, key_hash) = UserSession::generate();
+        let expires_at = chrono::DateTime::from_timestamp(guard.exp, 0)
+            .ok_or((Status::InternalServerError, "Invalid expiry".to_string()))?
+            .naive_utc();
+
+        let session = UserSession::create(
+            db,
+            guard.id,
+            &key_hash,
+            client.device().as_deref(),
+            client.user_agent.as_deref(),
+            client.ip_address.as_deref(),
+            expires_at,
+        )
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+        guard.session = Some(key);
+        guard.session_id = Some(session.id);
+        guard.update_cookie(cookies);
+        Ok(guard)
+    }
+
+    /// Revoke the session of the auth cookie, if any, and remove the cookie.
+    pub async fn end_session(
+        &self,
+        db: &mut Db,
+        cookies: &CookieJar<'_>,
+    ) -> Result<(), (Status, String)> {
+        if let Some(id) = self.session_id {
+            UserSession::delete(db, self.id, id)
+                .await
+                .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+        }
+        self.remove_cookie(cookies);
+        Ok(())
+    }
+
     pub async fn login_from_password(
         db: &mut Db,
         email: &str,
@@ -302,6 +511,49 @@ impl UserGuard {
             .map_err(|e| (Status::InternalServerError, e.to_string()))
     }
 
+    /// Authenticate a request with a personal access token. Unknown and
+    /// expired tokens are rejected, as are requests outside the scopes of
+    /// the token.
+    async fn from_access_token(
+        request: &Request<'_>,
+        token: &str,
+    ) -> Result<Self, (Status, String)> {
+        let mut db = match request.guard::<Db>().await {
+            Outcome::Success(db) => db,
+            _ => {
+                return Err((
+                    Status::InternalServerError,
+                    "Database unavailable".to_string(),
+                ))
+            }
+        };
+
+        let (token, user) = UserToken::get_by_token(&mut db, token)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+            .ok_or((Status::Unauthorized, "Invalid token".to_string()))?;
+        if token.is_expired() {
+            return Err((Status::Unauthorized, "Token expired".to_string()));
+        }
+        if !tokens::scopes_allow(&mut db, request, &token.scopes()).await? {
+            return Err((
+                Status::Forbidden,
+                "Token does not allow this request".to_string(),
+            ));
+        }
+
+        token
+            .touch(&mut db)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+        Ok(Self::new_unchecked(
+            user.id,
+            user.username,
+            token.expires_at.and_utc().timestamp(),
+        ))
+    }
+
     pub fn update_cookie(&self, cookies: &CookieJar<'_>) {
         // Set a private cookie with the user's name, and redirect to the home page.
         cookies.add_private(self);
@@ -320,6 +572,7 @@ impl UserGuard {
     pub fn create_jwt(mut self, key: &EncodingKey) -> Result<String, jsonwebtoken::errors::Error> {
         let expiration = default_expiration_();
         self.set_expiry(expiration);
+        self.session = None;
 
         jsonwebtoken::encode(
             &jsonwebtoken::Header::new(jsonwebtoken::Algorithm::HS512),
@@ -333,6 +586,115 @@ impl UserGuard {
     }
 }
 
+/// The browser a request comes from, recorded with the sessions it creates.
+#[derive(Debug, Clone, Default, OpenApiFromRequest)]
+pub struct ClientInfo {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+#[rocket::async_trait]
+impl<'r> request::FromRequest<'r> for ClientInfo {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        Outcome::Success(Self {
+            user_agent: request
+                .headers()
+                .get_one("User-Agent")
+                .map(|ua| ua.chars().take(Self::MAX_USER_AGENT_LENGTH).collect()),
+            ip_address: request.client_ip().map(|ip| ip.to_string()),
+        })
+    }
+}
+
+impl ClientInfo {
+    const MAX_USER_AGENT_LENGTH: usize = 512;
+
+    /// A short description of the browser and operating system, e.g.
+    /// "Firefox on Linux". The order matters as most user agents mention
+    /// other browsers and systems for compatibility.
+    pub fn device(&self) -> Option<String> {
+        const BROWSERS: &[(&str, &str)] = &[
+            ("Edg/", "Edge"),
+            ("OPR/", "Opera"),
+            ("Firefox/", "Firefox"),
+            ("Chrome/", "Chrome"),
+            ("Safari/", "Safari"),
+            ("curl/", "curl"),
+        ];
+        const SYSTEMS: &[(&str, &str)] = &[
+            ("Windows", "Windows"),
+            ("Android", "Android"),
+            ("iPhone", "iOS"),
+            ("iPad", "iPadOS"),
+            ("Mac OS X", "macOS"),
+            ("CrOS", "ChromeOS"),
+            ("Linux", "Linux"),
+        ];
+
+        let user_agent = self.user_agent.as_deref()?;
+        let find = |names: &[(&str, &'static str)]| {
+            names
+                .iter()
+                .find(|(token, _)| user_agent.contains(token))
+                .map(|(_, name)| *name)
+        };
+
+        match (find(BROWSERS), find(SYSTEMS)) {
+            (Some(browser), Some(system)) => Some(format!("{browser} on {system}")),
+            (Some(name), None) | (None, Some(name)) => Some(name.to_string()),
+            (None, None) => None,
+        }
+    }
+}
+
+/// A login waiting for a two-factor authentication code. It is kept in a
+/// private cookie, separate from the auth cookie so it cannot be used as a
+/// session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingLogin {
+    pub id: i32,
+    pub exp: i64,
+}
+
+impl PendingLogin {
+    const COOKIE: &'static str = "auth_pending";
+
+    /// The number of minutes a user has to enter their code.
+    const EXPIRY_MINUTES: i64 = 5;
+
+    pub fn new(id: i32) -> Self {
+        Self {
+            id,
+            exp: chrono::Utc::now()
+                .checked_add_signed(chrono::Duration::minutes(Self::EXPIRY_MINUTES))
+                .expect("Invalid timestamp")
+                .timestamp(),
+        }
+    }
+
+    /// The pending login of a request, if it has not expired.
+    pub fn from_cookies(cookies: &CookieJar<'_>) -> Option<Self> {
+        cookies
+            .get_private(Self::COOKIE)
+            .and_then(|cookie| serde_json::from_str::<Self>(cookie.value()).ok())
+            .filter(|pending| pending.exp >= chrono::Utc::now().timestamp())
+    }
+
+    pub fn update_cookie(&self, cookies: &CookieJar<'_>) {
+        cookies.add_private(
+            Cookie::build((Self::COOKIE, serde_json::to_string(self).unwrap()))
+                .same_site(rocket::http::SameSite::Lax)
+                .build(),
+        );
+    }
+
+    pub fn remove_cookie(cookies: &CookieJar<'_>) {
+        cookies.remove_private(Self::COOKIE);
+    }
+}
+
 fn default_expiration_() -> i64 {
     chrono::Utc::now()
         .checked_add_signed(chrono::Duration::days(7))
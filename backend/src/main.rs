@@ -1,5 +1,6 @@
-use crate::fairings::config::{DbPepper, JwtKeys, RetronomiconConfig};
+use crate::fairings::config::{DbPepper, JwtKeys, ManifestSigningKey, RetronomiconConfig};
 use crate::routes::v1;
+use crate::utils::uploads::UploadHashers;
 use clap::Parser;
 use retronomicon_db::{run_migrations, RetronomiconDbPool};
 use rocket::fairing::AdHoc;
@@ -60,6 +61,10 @@ async fn rocket() -> _ {
         .extract_inner::<String>("db_pepper")
         .or_else(|_| env::var("DATABASE_PEPPER"))
         .unwrap_or_else(|_| secret_key.clone());
+    let manifest_signing_key = figment
+        .extract_inner::<String>("manifest_signing_key")
+        .or_else(|_| env::var("MANIFEST_SIGNING_KEY"))
+        .unwrap_or_else(|_| secret_key.clone());
 
     let static_root: Option<String> = figment
         .extract_inner("static_root")
@@ -118,7 +123,12 @@ async fn rocket() -> _ {
         .attach(OAuth2::<routes::auth::GoogleUserInfo>::fairing("google"))
         .attach(OAuth2::<routes::auth::PatreonUserInfo>::fairing("patreon"))
         .attach(fairings::cors::Cors)
+        .attach(fairings::gc::BlobCollector)
+        .attach(fairings::dat_imports::DatImportRecovery)
+        .attach(fairings::images::ImageBackfill)
         .manage(JwtKeys::from_base64(&jwt_secret_b64))
         .manage(DbPepper::from_base64(&db_pepper))
+        .manage(ManifestSigningKey::from_base64(&manifest_signing_key))
+        .manage(UploadHashers::default())
         .attach(AdHoc::config::<RetronomiconConfig>())
 }
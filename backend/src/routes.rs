@@ -3,12 +3,15 @@ pub mod v1;
 use rocket::{routes, Route};
 
 pub mod auth;
+pub mod files;
 
 pub fn routes() -> Vec<Route> {
     routes![
         auth::github_callback,
         auth::google_callback,
         auth::patreon_callback,
-        auth::login_token_callback
+        auth::login_token_callback,
+        auth::oidc_callback,
+        files::files_download,
     ]
 }
@@ -1,12 +1,14 @@
 use crate::fairings::config::RetronomiconConfig;
-use crate::guards::users::UserGuard;
+use crate::guards::users::{ClientInfo, UserGuard};
+use crate::utils::oidc::{OidcIdentity, OidcLoginState};
 use retronomicon_db::{models, Db};
+use retronomicon_dto as dto;
 use rocket::http::hyper::header::{ACCEPT, AUTHORIZATION, USER_AGENT};
 use rocket::http::{CookieJar, Status};
 use rocket::response::Redirect;
-use rocket::{error, get, State};
+use rocket::{error, get, warn, State};
 use rocket_oauth2::TokenResponse;
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::collections::BTreeMap;
 
 async fn maybe_add_to_root(
@@ -26,20 +28,72 @@ async fn maybe_add_to_root(
     Ok(())
 }
 
+/// Update the root team membership of a user whose email changed from
+/// `old_email`. Users are removed if only their previous email matched, unless
+/// they are the last owner of the root team.
+pub(crate) async fn update_root_membership(
+    db: &mut Db,
+    config: &RetronomiconConfig,
+    model: &models::User,
+    old_email: &str,
+) -> Result<(), (Status, String)> {
+    let was_root = config.should_add_to_root(old_email);
+    if config.should_add_to_root(&model.email) {
+        if !was_root {
+            maybe_add_to_root(db, config, model).await?;
+        }
+        return Ok(());
+    }
+    if !was_root {
+        return Ok(());
+    }
+
+    let sole_owned = model
+        .sole_owned_teams(db)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    if sole_owned.iter().any(|t| t.id == config.root_team_id) {
+        warn!("User {} is the last owner of the root team", model.id);
+        return Ok(());
+    }
+
+    model
+        .leave_team(db, config.root_team_id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))
+}
+
 async fn login_(
     mut db: Db,
     cookies: &CookieJar<'_>,
+    client: &ClientInfo,
     config: &State<RetronomiconConfig>,
     username: Option<String>,
     email: &str,
     auth_provider: &str,
 ) -> Result<Redirect, (Status, String)> {
-    let (_created, model, user_guard) =
+    let (_created, model, _) =
         UserGuard::login_from_auth(&mut db, username, email, auth_provider.to_string(), None)
             .await?;
 
     maybe_add_to_root(&mut db, config, &model).await?;
-    user_guard.update_cookie(cookies);
+    start_session(&mut db, cookies, client, config, model).await
+}
+
+async fn start_session(
+    db: &mut Db,
+    cookies: &CookieJar<'_>,
+    client: &ClientInfo,
+    config: &RetronomiconConfig,
+    model: models::User,
+) -> Result<Redirect, (Status, String)> {
+    if UserGuard::start_session(db, cookies, client, model).await? {
+        // Let the frontend ask for a two-factor authentication code.
+        let url = url::Url::parse(&config.base_url)
+            .and_then(|base| base.join("login/two-factor"))
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+        return Ok(Redirect::to(url.to_string()));
+    }
 
     let base_url = config.base_url.clone();
     Ok(Redirect::to(base_url))
@@ -49,6 +103,7 @@ async fn login_(
 pub async fn login_token_callback(
     mut db: Db,
     cookies: &CookieJar<'_>,
+    client: ClientInfo,
     config: &State<RetronomiconConfig>,
     email: String,
     token: String,
@@ -67,8 +122,7 @@ pub async fn login_token_callback(
 
     maybe_add_to_root(&mut db, config, &user).await?;
 
-    let user_guard = UserGuard::from_model(user);
-    user_guard.update_cookie(cookies);
+    UserGuard::create_session(&mut db, cookies, &client, user).await?;
 
     let base_url = config.base_url.clone();
     Ok(Redirect::to(base_url))
@@ -86,6 +140,7 @@ pub async fn github_callback(
     db: Db,
     token: TokenResponse<GitHubUserInfo>,
     cookies: &CookieJar<'_>,
+    client: ClientInfo,
     config: &State<RetronomiconConfig>,
 ) -> Result<Redirect, (Status, String)> {
     let json: Value = reqwest::Client::builder()
@@ -108,6 +163,7 @@ pub async fn github_callback(
     login_(
         db,
         cookies,
+        &client,
         config,
         Some(user_info.login),
         &user_info.email,
@@ -131,6 +187,7 @@ pub async fn google_callback(
     db: Db,
     token: TokenResponse<GoogleUserInfo>,
     cookies: &CookieJar<'_>,
+    client: ClientInfo,
     frontend_config: &State<RetronomiconConfig>,
 ) -> Result<Redirect, (Status, String)> {
     let json: Value = reqwest::Client::builder()
@@ -152,7 +209,7 @@ pub async fn google_callback(
         .and_then(|e| e.as_str());
 
     if let Some(email) = email {
-        login_(db, cookies, frontend_config, None, email, "google").await
+        login_(db, cookies, &client, frontend_config, None, email, "google").await
     } else {
         Err((
             Status::InternalServerError,
@@ -178,6 +235,7 @@ pub async fn patreon_callback(
     db: Db,
     token: TokenResponse<PatreonUserInfo>,
     cookies: &CookieJar<'_>,
+    client: ClientInfo,
     frontend_config: &State<RetronomiconConfig>,
 ) -> Result<Redirect, (Status, String)> {
     let json: Value = reqwest::Client::builder()
@@ -223,5 +281,160 @@ pub async fn patreon_callback(
         }
     };
 
-    login_(db, cookies, frontend_config, None, email, "patreon").await
+    login_(
+        db,
+        cookies,
+        &client,
+        frontend_config,
+        None,
+        email,
+        "patreon",
+    )
+    .await
+}
+
+/// Find or create the user of an OpenID Connect identity. Users with an
+/// existing account must log in and link the identity themselves, so an
+/// account cannot be taken over by a provider that does not own its email.
+async fn oidc_user(
+    db: &mut Db,
+    config: &RetronomiconConfig,
+    provider: &str,
+    identity: &OidcIdentity,
+) -> Result<models::User, (Status, String)> {
+    if let Some((user_identity, user)) =
+        models::UserIdentity::get_by_subject(db, provider, &identity.subject)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        user_identity
+            .touch(db, identity.email.as_deref())
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+        return Ok(user);
+    }
+
+    let email = identity.email.as_deref().ok_or((
+        Status::BadRequest,
+        "The provider did not return an email".to_string(),
+    ))?;
+    if models::User::get_by_email(db, email)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .is_some()
+    {
+        return Err((
+            Status::Conflict,
+            "An account already exists with this email, log in and link the provider instead"
+                .to_string(),
+        ));
+    }
+
+    // Drop the username if it isn't valid or is already taken; the user
+    // will pick one.
+    let mut username = identity
+        .username
+        .as_deref()
+        .and_then(|u| dto::user::Username::new(u).ok());
+    if let Some(name) = username.clone() {
+        if models::User::exists(db, dto::user::UserIdOrUsername::Username(name))
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        {
+            username = None;
+        }
+    }
+    let username = username.map(|u| u.into_inner().to_string());
+
+    let user = models::User::create(
+        db,
+        username.as_deref(),
+        None,
+        identity.avatar_url.as_deref(),
+        email,
+        Some(&format!("oidc:{provider}")),
+        None,
+        json!({}),
+        json!({}),
+    )
+    .await
+    .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    models::UserIdentity::create(db, user.id, provider, &identity.subject, Some(email))
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    // Only trust emails verified by the provider for the root team.
+    if identity.email_verified {
+        maybe_add_to_root(db, config, &user).await?;
+    }
+    Ok(user)
+}
+
+#[allow(clippy::too_many_arguments)]
+#[get("/auth/oidc/<provider>?<code>&<state>&<error>")]
+pub async fn oidc_callback(
+    mut db: Db,
+    cookies: &CookieJar<'_>,
+    client: ClientInfo,
+    config: &State<RetronomiconConfig>,
+    user: Option<UserGuard>,
+    provider: &str,
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+) -> Result<Redirect, (Status, String)> {
+    let db = &mut db;
+    let provider_config = config
+        .oidc
+        .get(provider)
+        .ok_or((Status::NotFound, "Unknown provider".to_string()))?;
+    if let Some(error) = error {
+        return Err((Status::Unauthorized, error));
+    }
+    let login = state
+        .and_then(|state| OidcLoginState::take(cookies, provider, &state))
+        .ok_or((Status::BadRequest, "Invalid state".to_string()))?;
+    let code = code.ok_or((Status::BadRequest, "No code".to_string()))?;
+
+    let metadata = provider_config.metadata().await?;
+    let redirect_uri = provider_config.redirect_uri(provider, &config.base_url)?;
+    let identity = provider_config
+        .authenticate(&metadata, &redirect_uri, &login, &code)
+        .await?;
+
+    if let Some(user_id) = login.link_user_id {
+        if user.map(|u| u.id) != Some(user_id) {
+            return Err((Status::Unauthorized, "Not logged in".to_string()));
+        }
+
+        match models::UserIdentity::get_by_subject(db, provider, &identity.subject)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        {
+            Some((_, owner)) if owner.id != user_id => {
+                return Err((
+                    Status::Conflict,
+                    "This account is linked to another user".to_string(),
+                ));
+            }
+            Some(_) => {}
+            None => {
+                models::UserIdentity::create(
+                    db,
+                    user_id,
+                    provider,
+                    &identity.subject,
+                    identity.email.as_deref(),
+                )
+                .await
+                .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+            }
+        }
+
+        let base_url = config.base_url.clone();
+        return Ok(Redirect::to(base_url));
+    }
+
+    let model = oidc_user(db, config, provider, &identity).await?;
+    start_session(db, cookies, &client, config, model).await
 }
@@ -0,0 +1,66 @@
+use crate::guards::storage::{LocalStorageConfig, StorageBucket};
+use rocket::fs::NamedFile;
+use rocket::http::{ContentType, Header, Status};
+use rocket::{get, Responder};
+use std::path::PathBuf;
+
+/// Content types browsers cannot run scripts from. Files are served from the
+/// origin of the API, which holds the session cookie, so any other type
+/// (e.g. HTML or SVG) is served as binary to avoid stored XSS.
+const SAFE_CONTENT_TYPES: &[ContentType] = &[
+    ContentType::Binary,
+    ContentType::Plain,
+    ContentType::JSON,
+    ContentType::ZIP,
+    ContentType::GZIP,
+    ContentType::PNG,
+    ContentType::JPEG,
+    ContentType::GIF,
+    ContentType::WEBP,
+    ContentType::AVIF,
+    ContentType::BMP,
+];
+
+/// A file of the local storage, served so browsers never sniff its content.
+#[derive(Responder)]
+pub struct FileDownload {
+    file: NamedFile,
+    content_type: ContentType,
+    nosniff: Header<'static>,
+}
+
+/// Serve a file uploaded to the local storage backend. When using S3, files
+/// are served by the bucket directly and this route does not find anything.
+#[get("/files/<bucket>/<path..>")]
+pub async fn files_download(
+    config: LocalStorageConfig,
+    bucket: &str,
+    path: PathBuf,
+) -> Result<FileDownload, Status> {
+    let bucket = StorageBucket::from_name(bucket).ok_or(Status::NotFound)?;
+    let file_path = config.path_for(bucket, &path).ok_or(Status::NotFound)?;
+
+    // Files are served with the content type they were uploaded with. Blobs
+    // are stored by their checksum and have no extension to guess it from.
+    let content_type = match config.content_type_for(bucket, &path).await {
+        Some(content_type) => ContentType::parse_flexible(&content_type),
+        None => None,
+    }
+    .or_else(|| {
+        file_path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(ContentType::from_extension)
+    })
+    .filter(|content_type| SAFE_CONTENT_TYPES.contains(content_type))
+    .unwrap_or(ContentType::Binary);
+
+    let file = NamedFile::open(file_path)
+        .await
+        .map_err(|_| Status::NotFound)?;
+    Ok(FileDownload {
+        file,
+        content_type,
+        nosniff: Header::new("X-Content-Type-Options", "nosniff"),
+    })
+}
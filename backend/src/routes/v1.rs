@@ -4,7 +4,10 @@ pub mod auth;
 pub mod cores;
 pub mod games;
 pub mod me;
+pub mod notifications;
+pub mod permissions;
 pub mod platforms;
+pub mod search;
 pub mod systems;
 pub mod tags;
 pub mod teams;
@@ -16,47 +19,127 @@ pub fn routes() -> Vec<rocket::Route> {
         auth::google_login,
         auth::patreon_login,
         auth::login,
+        auth::login_two_factor,
         auth::logout,
+        auth::oidc_link,
+        auth::oidc_login,
+        auth::oidc_providers,
+        auth::password_forgot,
+        auth::password_reset,
+        auth::email_change_confirm,
         auth::signup,
         cores::cores_create,
         cores::cores_details,
+        cores::cores_follow,
         cores::cores_list,
+        cores::cores_unfollow,
+        cores::manifests::cores_releases_manifest,
+        cores::manifests::cores_releases_manifest_sign,
+        cores::manifests::cores_releases_manifest_signatures,
+        cores::manifests::manifests_key,
         cores::releases::cores_releases_artifacts_download,
         cores::releases::cores_releases_artifacts_download_filename,
         cores::releases::cores_releases_artifacts_list,
         cores::releases::cores_releases_artifacts_upload,
         cores::releases::cores_releases_create,
         cores::releases::cores_releases_list,
+        cores::releases::cores_releases_yank,
+        cores::required_files::cores_compatibility,
+        cores::required_files::cores_required_files_create,
+        cores::required_files::cores_required_files_delete,
+        cores::required_files::cores_required_files_list,
+        cores::uploads::cores_releases_uploads_complete,
+        cores::uploads::cores_releases_uploads_create,
+        cores::uploads::cores_releases_uploads_delete,
+        cores::uploads::cores_releases_uploads_details,
+        cores::uploads::cores_releases_uploads_part,
         games::games_add_artifact,
+        games::games_artifacts,
         games::games_create,
         games::games_details,
+        games::games_identify,
         games::games_images,
+        games::games_images_roles_update,
         games::games_images_upload,
         games::games_list,
         games::games_update,
+        games::metadata::companies_list,
+        games::metadata::genres_list,
+        games::metadata::regions_list,
+        games::metadata::series_list,
+        games::relationships::games_related,
+        games::relationships::games_relationships_create,
+        games::relationships::games_relationships_delete,
+        games::translations::games_translations,
+        games::translations::games_translations_delete,
+        games::translations::games_translations_update,
         me::me,
+        me::me_delete,
+        me::me_export,
+        me::me_identities,
+        me::me_identities_unlink,
+        me::me_invitations,
+        me::me_email,
+        me::me_password,
+        me::me_sessions,
+        me::me_sessions_revoke,
+        me::me_sessions_revoke_all,
         me::me_token,
+        me::me_tokens,
+        me::me_tokens_create,
+        me::me_tokens_revoke,
+        me::me_two_factor,
+        me::me_two_factor_confirm,
+        me::me_two_factor_disable,
+        me::me_two_factor_enroll,
+        me::me_two_factor_recovery_codes,
         me::me_update,
+        notifications::me_notification_preferences,
+        notifications::me_notification_preferences_update,
+        notifications::me_notifications,
+        notifications::me_notifications_read,
+        permissions::cores_collaborators,
+        permissions::cores_collaborators_remove,
+        permissions::cores_collaborators_update,
+        permissions::permissions_check,
+        permissions::teams_permissions,
+        permissions::teams_permissions_update,
         platforms::platforms_create,
         platforms::platforms_details,
         platforms::platforms_list,
         platforms::platforms_update,
+        search::search,
         systems::systems_create,
+        systems::imports::systems_imports_apply,
+        systems::imports::systems_imports_create,
+        systems::imports::systems_imports_details,
         systems::systems_details,
+        systems::systems_games_dat,
         systems::systems_list,
         tags::tags,
         tags::tags_create,
         tags::tags_delete,
         teams::invite,
         teams::invite_accept,
+        teams::invite_decline,
+        teams::invite_cancel,
+        teams::teams_invitations,
+        teams::teams_members_update,
+        teams::teams_members_remove,
+        teams::teams_leave,
         teams::teams,
         teams::teams_create,
         teams::teams_delete,
         teams::teams_details,
+        teams::teams_keys,
+        teams::teams_keys_create,
+        teams::teams_keys_revoke,
         teams::teams_update,
+        teams::teams_upload_limit,
         users::check_username,
         users::users,
         users::users_details,
+        users::users_sessions_terminate,
         users::users_update,
     ]
 }
@@ -1,9 +1,13 @@
 use crate::fairings::config::{DbPepper, RetronomiconConfig};
 use crate::guards::emailer::EmailGuard;
-use crate::guards::users::UserGuard;
+use crate::guards::users::{ClientInfo, PendingLogin, UserGuard};
 use crate::routes::auth::{GitHubUserInfo, GoogleUserInfo, PatreonUserInfo};
-use retronomicon_db::models::{User, UserPassword};
-use retronomicon_db::Db;
+use crate::utils::oidc::OidcLoginState;
+use crate::utils::two_factor;
+use retronomicon_db::models::{
+    EmailChange, PasswordResetToken, User, UserPassword, UserToken, UserTwoFactor,
+};
+use retronomicon_db::{is_unique_violation, Db};
 use retronomicon_dto as dto;
 use rocket::http::{CookieJar, Status};
 use rocket::response::Redirect;
@@ -20,6 +24,7 @@ pub async fn signup(
     mut db: Db,
     form: Json<dto::auth::SignupRequest<'_>>,
     cookies: &CookieJar<'_>,
+    client: ClientInfo,
     pepper: &State<DbPepper>,
     config: &State<RetronomiconConfig>,
     emailer: EmailGuard,
@@ -49,6 +54,7 @@ pub async fn signup(
             crate::routes::auth::login_token_callback(
                 db,
                 cookies,
+                client,
                 config,
                 form.email.to_string(),
                 token,
@@ -89,24 +95,165 @@ pub async fn signup(
     }
 }
 
-/// Login with an email and password.
+/// Login with an email and password. If the user enabled two-factor
+/// authentication, the login must be completed with a code using
+/// `/login/two-factor`.
 #[openapi(tag = "Authentication", ignore = "db")]
 #[post("/login", format = "application/json", data = "<form>")]
 pub async fn login(
     mut db: Db,
     cookies: &CookieJar<'_>,
+    client: ClientInfo,
     pepper: &State<DbPepper>,
     form: Json<dto::auth::LoginRequest<'_>>,
-) -> Result<Json<dto::Ok>, (Status, String)> {
+) -> Result<Json<dto::auth::LoginResponse>, (Status, String)> {
     let form = form.into_inner();
 
     let user = User::from_email(&mut db, form.email, form.password, &pepper.inner().0)
         .await
         .map_err(|e| (Status::Unauthorized, e.to_string()))?;
 
-    let guard = UserGuard::from_model(user);
-    guard.update_cookie(cookies);
+    let two_factor_required = UserGuard::start_session(&mut db, cookies, &client, user).await?;
+
+    Ok(Json(dto::auth::LoginResponse {
+        two_factor_required,
+    }))
+}
+
+/// Complete a login with a two-factor authentication code, either from an
+/// authenticator app or a recovery code.
+#[openapi(tag = "Authentication", ignore = "db")]
+#[post("/login/two-factor", format = "application/json", data = "<form>")]
+pub async fn login_two_factor(
+    mut db: Db,
+    cookies: &CookieJar<'_>,
+    client: ClientInfo,
+    form: Json<dto::auth::TwoFactorCodeRequest<'_>>,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let db = &mut db;
+    let pending = PendingLogin::from_cookies(cookies)
+        .ok_or((Status::Unauthorized, "No pending login".to_string()))?;
+
+    let user_two_factor = UserTwoFactor::from_user_id(db, pending.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .filter(|t| t.is_confirmed())
+        .ok_or((Status::Unauthorized, "No pending login".to_string()))?;
+    two_factor::verify(db, &user_two_factor, form.code).await?;
+
+    let user = User::from_id(db, pending.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    PendingLogin::remove_cookie(cookies);
+    UserGuard::create_session(db, cookies, &client, user).await?;
+
+    Ok(Json(dto::Ok))
+}
+
+/// The number of hours a password reset link is valid for.
+const PASSWORD_RESET_EXPIRY_HOURS: i64 = 1;
+
+/// Send a link to reset the password to an email. This always succeeds, to
+/// avoid leaking which emails have an account. The link is valid for an hour
+/// and replaces any previous link.
+#[openapi(tag = "Authentication", ignore = "db", ignore = "emailer")]
+#[post("/password/forgot", format = "application/json", data = "<form>")]
+pub async fn password_forgot(
+    mut db: Db,
+    config: &State<RetronomiconConfig>,
+    emailer: EmailGuard,
+    form: Json<dto::auth::PasswordForgotRequest<'_>>,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let user = match User::get_by_email(&mut db, form.email)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        Some(user) => user,
+        None => return Ok(Json(dto::Ok)),
+    };
+
+    let expires_at =
+        chrono::Utc::now().naive_utc() + chrono::Duration::hours(PASSWORD_RESET_EXPIRY_HOURS);
+    let token = PasswordResetToken::create(&mut db, &user, expires_at)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    let mut url = url::Url::parse(&config.inner().base_url)
+        .and_then(|base| base.join("reset-password"))
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    url.query_pairs_mut()
+        .append_pair("email", &user.email)
+        .append_pair("token", &token);
+
+    emailer.send_password_reset(&user.email, url.as_str(), PASSWORD_RESET_EXPIRY_HOURS)?;
+
+    Ok(Json(dto::Ok))
+}
+
+/// Reset a password using the token sent by email. All existing sessions and
+/// personal access tokens of the user are invalidated.
+#[openapi(tag = "Authentication", ignore = "db")]
+#[post("/password/reset", format = "application/json", data = "<form>")]
+pub async fn password_reset(
+    mut db: Db,
+    pepper: &State<DbPepper>,
+    form: Json<dto::auth::PasswordResetRequest<'_>>,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let form = form.into_inner();
+    if form.password.is_empty() {
+        return Err((Status::BadRequest, "Password cannot be empty".to_string()));
+    }
+
+    let user = PasswordResetToken::consume(&mut db, form.email, form.token)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::NotFound, "Invalid or expired token".to_string()))?;
+
+    UserPassword::set(&mut db, &user, form.password, &pepper.inner().0)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    user.invalidate_sessions(&mut db)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    UserToken::delete_all(&mut db, user.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(dto::Ok))
+}
+
+/// Apply a pending email change and update the root team membership of its
+/// user.
+pub(crate) async fn confirm_email_change(
+    db: &mut Db,
+    config: &RetronomiconConfig,
+    token: &str,
+) -> Result<User, (Status, String)> {
+    let (user, old_email) = EmailChange::consume(db, token)
+        .await
+        .map_err(|e| {
+            if is_unique_violation(&e) {
+                (Status::Conflict, "Email already in use".to_string())
+            } else {
+                (Status::InternalServerError, e.to_string())
+            }
+        })?
+        .ok_or((Status::NotFound, "Invalid or expired token".to_string()))?;
 
+    crate::routes::auth::update_root_membership(db, config, &user, &old_email).await?;
+    Ok(user)
+}
+
+/// Confirm an email change using the token sent to the new email. This does
+/// not need the user to be logged in.
+#[openapi(tag = "Authentication", ignore = "db")]
+#[post("/email/confirm", format = "application/json", data = "<form>")]
+pub async fn email_change_confirm(
+    mut db: Db,
+    config: &State<RetronomiconConfig>,
+    form: Json<dto::auth::EmailChangeConfirmRequest<'_>>,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    confirm_email_change(&mut db, config.inner(), form.token).await?;
     Ok(Json(dto::Ok))
 }
 
@@ -145,15 +292,85 @@ pub async fn google_login(oauth2: OAuth2<GoogleUserInfo>, cookies: &CookieJar<'_
         .unwrap()
 }
 
-/// Logout the current user.
+/// List the OpenID Connect providers users can log in with.
 #[openapi(tag = "Authentication")]
+#[get("/login/oidc")]
+pub async fn oidc_providers(
+    config: &State<RetronomiconConfig>,
+) -> Json<Vec<dto::auth::OidcProvider>> {
+    Json(
+        config
+            .oidc
+            .iter()
+            .map(|(name, provider)| dto::auth::OidcProvider {
+                name: name.clone(),
+                display_name: provider.display_name(name),
+            })
+            .collect(),
+    )
+}
+
+async fn oidc_redirect(
+    config: &RetronomiconConfig,
+    cookies: &CookieJar<'_>,
+    provider: &str,
+    link_user_id: Option<i32>,
+) -> Result<Redirect, (Status, String)> {
+    let provider_config = config
+        .oidc
+        .get(provider)
+        .ok_or((Status::NotFound, "Unknown provider".to_string()))?;
+
+    let metadata = provider_config.metadata().await?;
+    let redirect_uri = provider_config.redirect_uri(provider, &config.base_url)?;
+    let state = OidcLoginState::new(provider, link_user_id);
+    let url = provider_config.authorization_url(&metadata, &redirect_uri, &state)?;
+    state.update_cookie(cookies);
+
+    Ok(Redirect::to(url))
+}
+
+/// Login using a configured OpenID Connect provider. This will redirect the
+/// user to the login page of the provider, which redirects back to
+/// `/api/auth/oidc/<provider>`.
+///
+/// This is not a REST endpoint, but a normal web page.
+#[openapi(tag = "Authentication")]
+#[get("/login/oidc/<provider>")]
+pub async fn oidc_login(
+    config: &State<RetronomiconConfig>,
+    cookies: &CookieJar<'_>,
+    provider: &str,
+) -> Result<Redirect, (Status, String)> {
+    oidc_redirect(config, cookies, provider, None).await
+}
+
+/// Link an account of a configured OpenID Connect provider to the current
+/// user, so they can log in with it. This works like logging in with the
+/// provider.
+///
+/// This is not a REST endpoint, but a normal web page.
+#[openapi(tag = "Authentication")]
+#[get("/login/oidc/<provider>/link")]
+pub async fn oidc_link(
+    config: &State<RetronomiconConfig>,
+    cookies: &CookieJar<'_>,
+    user: UserGuard,
+    provider: &str,
+) -> Result<Redirect, (Status, String)> {
+    oidc_redirect(config, cookies, provider, Some(user.id)).await
+}
+
+/// Logout the current user, revoking their session.
+#[openapi(tag = "Authentication", ignore = "db")]
 #[post("/logout")]
 pub async fn logout(
+    mut db: Db,
     cookies: &CookieJar<'_>,
     config: &State<RetronomiconConfig>,
     user: UserGuard,
-) -> Redirect {
-    user.remove_cookie(cookies);
+) -> Result<Redirect, (Status, String)> {
+    user.end_session(&mut db, cookies).await?;
     let base_url = config.base_url.clone();
-    Redirect::to(base_url)
+    Ok(Redirect::to(base_url))
 }
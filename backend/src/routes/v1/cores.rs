@@ -1,16 +1,20 @@
 use crate::guards;
-use crate::utils::json;
+use crate::utils::{acls, json};
 use retronomicon_db::models;
 use retronomicon_db::types::FetchModel;
 use retronomicon_db::Db;
 use retronomicon_dto as dto;
+use retronomicon_dto::permissions::Permission;
 use rocket::http::Status;
 use rocket::serde::json::Json;
-use rocket::{get, post};
+use rocket::{delete, get, post};
 use rocket_okapi::openapi;
 use serde_json::json;
 
+pub mod manifests;
 pub mod releases;
+pub mod required_files;
+pub mod uploads;
 
 /// List cores.
 #[openapi(tag = "Cores", ignore = "db")]
@@ -49,6 +53,7 @@ pub async fn cores_list(
             system.as_ref(),
             team.as_ref(),
             release,
+            true,
         )
         .await
         .map_err(|e| (Status::InternalServerError, e.to_string()))?
@@ -116,9 +121,7 @@ pub async fn cores_create(
             .map_err(|e| (Status::InternalServerError, e.to_string()))?
             .ok_or((Status::NotFound, "Not found".to_string()))?;
 
-    if !role.can_create_cores() {
-        return Err((Status::Forbidden, "User cannot create cores".to_string()));
-    }
+    acls::require(acls::check_team(&mut db, &team, Some(&role), Permission::CreateCores).await?)?;
 
     let core = models::Core::create(
         &mut db,
@@ -138,3 +141,37 @@ pub async fn cores_create(
         slug: core.slug,
     }))
 }
+
+/// Follow a core, to be notified of its new releases.
+#[openapi(tag = "Cores", ignore = "db")]
+#[post("/cores/<core_id>/follow")]
+pub async fn cores_follow(
+    mut db: Db,
+    user: guards::users::AuthenticatedUserGuard,
+    core_id: dto::types::IdOrSlug<'_>,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let core = models::Core::from_id_or_slug(&mut db, core_id).await?;
+    core.follow(&mut db, user.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    Ok(Json(dto::Ok))
+}
+
+/// Stop following a core.
+#[openapi(tag = "Cores", ignore = "db")]
+#[delete("/cores/<core_id>/follow")]
+pub async fn cores_unfollow(
+    mut db: Db,
+    user: guards::users::AuthenticatedUserGuard,
+    core_id: dto::types::IdOrSlug<'_>,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let core = models::Core::from_id_or_slug(&mut db, core_id).await?;
+    if !core
+        .unfollow(&mut db, user.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        return Err((Status::NotFound, "Not following this core".to_string()));
+    }
+    Ok(Json(dto::Ok))
+}
@@ -0,0 +1,208 @@
+use crate::fairings::config::ManifestSigningKey;
+use crate::guards;
+use crate::utils::acls;
+use ed25519_dalek::{Signature, Signer, Verifier, VerifyingKey};
+use retronomicon_db::models;
+use retronomicon_db::types::FetchModel;
+use retronomicon_db::Db;
+use retronomicon_dto as dto;
+use retronomicon_dto::permissions::Permission;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{get, post, State};
+use rocket_okapi::openapi;
+use sha2::{Digest, Sha256};
+
+/// Build the manifest of a release from its current artifacts.
+async fn manifest(
+    db: &mut Db,
+    core_id: dto::types::IdOrSlug<'_>,
+    release_id: u32,
+) -> Result<
+    (
+        models::Core,
+        models::CoreRelease,
+        dto::cores::manifests::ReleaseManifest,
+    ),
+    (Status, String),
+> {
+    let core = models::Core::from_id_or_slug(db, core_id).await?;
+    let release = models::CoreRelease::from_id(db, release_id as i32)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .filter(|r| r.core_id == core.id)
+        .ok_or((Status::NotFound, "Release not found".to_string()))?;
+    let platform = models::Platform::from_id(db, release.platform_id).await?;
+
+    let artifacts = models::Artifact::list_all(db, &release)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .into_iter()
+        .map(|a| dto::cores::manifests::ReleaseManifestArtifact {
+            filename: a.filename,
+            md5: a.md5.into(),
+            sha1: a.sha1.into(),
+            sha256: a.sha256.into(),
+            size: a.size,
+        })
+        .collect();
+
+    let manifest = dto::cores::manifests::ReleaseManifest {
+        artifacts,
+        core: core.slug.clone(),
+        core_id: core.id,
+        date_released: release.date_released.and_utc().timestamp(),
+        platform: platform.slug,
+        release_id: release.id,
+        version: release.version.clone(),
+    };
+
+    Ok((core, release, manifest))
+}
+
+/// The public key of the server, which signs all release manifests.
+#[openapi(tag = "Cores", ignore = "key")]
+#[get("/manifests/key")]
+pub async fn manifests_key(
+    key: &State<ManifestSigningKey>,
+) -> Json<dto::cores::manifests::ManifestServerKey> {
+    Json(dto::cores::manifests::ManifestServerKey {
+        public_key: key.0.verifying_key().as_bytes().into(),
+    })
+}
+
+/// The manifest of a release, listing all its artifacts with their sizes and
+/// checksums. Signatures are made over its canonical JSON.
+#[openapi(tag = "Cores", ignore = "db")]
+#[get("/cores/<core_id>/releases/<release_id>/manifest")]
+pub async fn cores_releases_manifest(
+    mut db: Db,
+    core_id: dto::types::IdOrSlug<'_>,
+    release_id: u32,
+) -> Result<Json<dto::cores::manifests::ReleaseManifest>, (Status, String)> {
+    let (_, _, manifest) = manifest(&mut db, core_id, release_id).await?;
+    Ok(Json(manifest))
+}
+
+/// The signatures of the current manifest of a release. The first one is
+/// always made by the server, followed by signatures made with the keys of
+/// the team owning the core.
+#[openapi(tag = "Cores", ignore = "db", ignore = "key")]
+#[get("/cores/<core_id>/releases/<release_id>/manifest/signatures")]
+pub async fn cores_releases_manifest_signatures(
+    mut db: Db,
+    key: &State<ManifestSigningKey>,
+    core_id: dto::types::IdOrSlug<'_>,
+    release_id: u32,
+) -> Result<Json<Vec<dto::cores::manifests::ReleaseManifestSignature>>, (Status, String)> {
+    let db = &mut db;
+    let (core, release, manifest) = manifest(db, core_id, release_id).await?;
+    let canonical = manifest.to_canonical_json();
+
+    let mut signatures = vec![dto::cores::manifests::ReleaseManifestSignature {
+        key: None,
+        public_key: key.0.verifying_key().as_bytes().into(),
+        signature: (&key.0.sign(&canonical).to_bytes()).into(),
+    }];
+
+    let team_signatures =
+        models::CoreReleaseSignature::list_valid(db, &release, &Sha256::digest(&canonical))
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    if !team_signatures.is_empty() {
+        let team = models::Team::get(db, core.owner_team_id)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+            .ok_or((Status::NotFound, "Team not found".to_string()))?;
+        let team = dto::teams::TeamRef::from(team);
+
+        signatures.extend(team_signatures.into_iter().map(|(signature, key)| {
+            dto::cores::manifests::ReleaseManifestSignature {
+                key: Some(dto::cores::manifests::ReleaseManifestSigningKeyRef {
+                    id: key.id,
+                    name: key.name,
+                    team: dto::teams::TeamRef {
+                        id: team.id,
+                        name: team.name.clone(),
+                        slug: team.slug.clone(),
+                    },
+                }),
+                public_key: key.public_key.into(),
+                signature: signature.signature.into(),
+            }
+        }));
+    }
+
+    Ok(Json(signatures))
+}
+
+/// Sign the current manifest of a release with a key of the team owning the
+/// core. The signature is verified before being recorded, and replaces any
+/// previous signature made with the same key.
+#[openapi(tag = "Cores", ignore = "db")]
+#[post(
+    "/cores/<core_id>/releases/<release_id>/manifest/signatures",
+    format = "json",
+    data = "<form>"
+)]
+pub async fn cores_releases_manifest_sign(
+    mut db: Db,
+    user: guards::users::AuthenticatedUserGuard,
+    core_id: dto::types::IdOrSlug<'_>,
+    release_id: u32,
+    form: Json<dto::cores::manifests::ReleaseManifestSignRequest>,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let db = &mut db;
+    let (core, release, manifest) = manifest(db, core_id, release_id).await?;
+
+    let (user, team, role) = acls::core_access(db, user.id, &core).await?;
+    acls::require(
+        acls::check_core(
+            db,
+            &user,
+            &team,
+            role.as_ref(),
+            &core,
+            Permission::SignManifests,
+        )
+        .await?,
+    )?;
+
+    let dto::cores::manifests::ReleaseManifestSignRequest {
+        public_key,
+        signature,
+    } = form.into_inner();
+
+    let key = models::TeamSigningKey::get_by_public_key(db, &team, &public_key)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .filter(|k| !k.is_revoked())
+        .ok_or((
+            Status::BadRequest,
+            "Unknown or revoked signing key".to_string(),
+        ))?;
+
+    let verifying_key = VerifyingKey::try_from(key.public_key.as_slice())
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    let signature = Signature::from_slice(&signature)
+        .map_err(|_| (Status::BadRequest, "Invalid signature".to_string()))?;
+    let canonical = manifest.to_canonical_json();
+    verifying_key.verify(&canonical, &signature).map_err(|_| {
+        (
+            Status::UnprocessableEntity,
+            "Signature does not match the current manifest".to_string(),
+        )
+    })?;
+
+    models::CoreReleaseSignature::upsert(
+        db,
+        &release,
+        &key,
+        &Sha256::digest(&canonical),
+        &signature.to_bytes(),
+    )
+    .await
+    .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(dto::Ok))
+}
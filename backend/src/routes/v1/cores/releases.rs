@@ -1,21 +1,25 @@
+use crate::fairings::config::RetronomiconConfig;
 use crate::guards;
+use crate::guards::emailer::EmailGuard;
 use crate::guards::storage::Paths;
-use crate::utils::acls;
+use crate::utils::uploads::{ArtifactHasher, ExpectedChecksums};
+use crate::utils::{acls, notifications};
 use retronomicon_db::models;
 use retronomicon_db::types::FetchModel;
 use retronomicon_db::Db;
 use retronomicon_dto as dto;
+use retronomicon_dto::notifications::NotificationKind;
+use retronomicon_dto::permissions::Permission;
 use rocket::data::ToByteUnit;
 use rocket::http::{ContentType, Header, Status};
 use rocket::response::Responder;
 use rocket::serde::json::Json;
-use rocket::{get, post, Data, Request, Response};
+use rocket::{get, post, put, Data, Request, Response, State};
 use rocket_multipart_form_data::{
     MultipartFormData, MultipartFormDataField, MultipartFormDataOptions, Repetition,
 };
 use rocket_okapi::openapi;
 use serde_json::json;
-use sha1::Digest;
 use std::io::Cursor;
 use std::path::PathBuf;
 
@@ -51,10 +55,14 @@ pub async fn cores_releases_list(
 
 /// Create a release for a core. This does not include any artifacts, which
 /// must be uploaded separately.
-#[openapi(tag = "Core Releases", ignore = "db")]
+/// Followers of the core are notified of the release, unless it is a
+/// prerelease.
+#[openapi(tag = "Core Releases", ignore = "db", ignore = "emailer")]
 #[post("/cores/<core_id>/releases", format = "json", data = "<input>")]
 pub async fn cores_releases_create(
     mut db: Db,
+    config: &State<RetronomiconConfig>,
+    emailer: EmailGuard,
     admin: guards::users::AuthenticatedUserGuard,
     core_id: dto::types::IdOrSlug<'_>,
     input: Json<dto::cores::releases::CoreReleaseCreateRequest<'_>>,
@@ -76,15 +84,18 @@ pub async fn cores_releases_create(
     let core = models::Core::from_id_or_slug(&mut db, core_id).await?;
     let platform = models::Platform::from_id_or_slug(&mut db, platform).await?;
 
-    let (user, team, role) =
-        models::User::get_user_team_and_role(&mut db, admin.into(), core.owner_team_id.into())
-            .await
-            .map_err(|e| (Status::InternalServerError, e.to_string()))?
-            .ok_or((Status::Unauthorized, "Not logged in".to_string()))?;
-
-    if !acls::can_create_core_releases(&user, &team, &role, &core).await {
-        return Err((Status::Forbidden, "Not authorized".to_string()));
-    }
+    let (user, team, role) = acls::core_access(&mut db, admin.id, &core).await?;
+    acls::require(
+        acls::check_core(
+            &mut db,
+            &user,
+            &team,
+            role.as_ref(),
+            &core,
+            Permission::CreateReleases,
+        )
+        .await?,
+    )?;
 
     let timestamp = chrono::NaiveDateTime::from_timestamp_opt(
         date_released.unwrap_or(chrono::Utc::now().timestamp()),
@@ -108,15 +119,94 @@ pub async fn cores_releases_create(
     .await
     .map_err(|e| (Status::InternalServerError, e.to_string()))?;
 
+    if !release.prerelease {
+        notifications::notify_release(
+            &mut db,
+            config.inner(),
+            &emailer,
+            user.id,
+            NotificationKind::CoreRelease,
+            &core,
+            &release,
+            false,
+        )
+        .await;
+    }
+
     Ok(Json(dto::cores::releases::CoreReleaseCreateResponse {
         id: release.id,
     }))
 }
 
+/// Yank a release, or restore a yanked release. Yanked releases are not
+/// listed by default. The uploader of the release and the followers of the
+/// core are notified when a release is yanked.
+#[openapi(tag = "Core Releases", ignore = "db", ignore = "emailer")]
+#[put(
+    "/cores/<core_id>/releases/<release_id>/yank",
+    format = "json",
+    data = "<input>"
+)]
+pub async fn cores_releases_yank(
+    mut db: Db,
+    config: &State<RetronomiconConfig>,
+    emailer: EmailGuard,
+    admin: guards::users::AuthenticatedUserGuard,
+    core_id: dto::types::IdOrSlug<'_>,
+    release_id: i32,
+    input: Json<dto::cores::releases::CoreReleaseYankRequest>,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let db = &mut db;
+    let core = models::Core::from_id_or_slug(db, core_id).await?;
+    let release = models::CoreRelease::from_id(db, release_id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .filter(|r| r.core_id == core.id)
+        .ok_or((Status::NotFound, "Release not found".to_string()))?;
+
+    let (user, team, role) = acls::core_access(db, admin.id, &core).await?;
+    acls::require(
+        acls::check_core(
+            db,
+            &user,
+            &team,
+            role.as_ref(),
+            &core,
+            Permission::YankReleases,
+        )
+        .await?,
+    )?;
+
+    let yanked = input.into_inner().yanked;
+    if release.yanked == yanked {
+        return Ok(Json(dto::Ok));
+    }
+    release
+        .set_yanked(db, yanked)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    if yanked {
+        notifications::notify_release(
+            db,
+            config.inner(),
+            &emailer,
+            user.id,
+            NotificationKind::ReleaseYanked,
+            &core,
+            &release,
+            true,
+        )
+        .await;
+    }
+
+    Ok(Json(dto::Ok))
+}
+
 pub struct ArtifactDownload {
-    filename: String,
-    mime_type: String,
-    data: Vec<u8>,
+    pub(crate) filename: String,
+    pub(crate) mime_type: String,
+    pub(crate) data: Vec<u8>,
 }
 
 impl<'r> Responder<'r, 'static> for ArtifactDownload {
@@ -243,48 +333,57 @@ pub async fn cores_releases_artifacts_list(
     ))
 }
 
+/// The maximum size of a file uploaded in a single request. Larger files
+/// need to be uploaded in parts (see `uploads`).
+const MAX_SINGLE_UPLOAD_SIZE: i64 = 24 * 1024 * 1024;
+
 async fn upload_single_artifact(
     db: &mut Db,
-    core: &models::Core,
     release: &models::CoreRelease,
     storage: &guards::storage::Storage,
     file_name: &str,
     mime_type: &str,
     file_data: &[u8],
+    expected: &ExpectedChecksums,
 ) -> Result<dto::artifact::ArtifactCreateResponse, (Status, String)> {
-    if file_data.len() > 24.mebibytes().as_u64() as usize {
-        return Err((
-            Status::BadRequest,
-            "File is too large (max 24 MiB)".to_string(),
-        ));
-    }
-
-    let md5 = md5::compute(file_data).to_vec();
-    let sha1 = sha1::Sha1::digest(file_data).to_vec();
-    let sha256 = sha2::Sha256::digest(file_data).to_vec();
+    let mut hasher = ArtifactHasher::default();
+    hasher.update(file_data);
+    let (md5, sha1, sha256) = hasher.finalize();
+    expected
+        .verify(file_data.len() as i64, &md5, &sha1, &sha256)
+        .map_err(|e| (Status::UnprocessableEntity, e))?;
+
+    // Identical content is only stored once.
+    let blob = match models::Blob::get_by_sha256(db, &sha256)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        Some(blob) => blob,
+        None => {
+            let path = Paths::path_for_blob(&sha256);
+            let download_url = storage
+                .upload_core(&path, file_data, mime_type)
+                .await
+                .map_err(|e| (Status::InternalServerError, e))?;
+
+            models::Blob::create(
+                db,
+                &md5,
+                &sha1,
+                &sha256,
+                file_data.len() as i64,
+                Some(&path),
+                Some(&download_url),
+            )
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+            .0
+        }
+    };
 
-    // Upload to storage.
-    let download_url = storage
-        .upload_core(
-            &Paths::path_for_core_artifact(&core, &release, file_name),
-            file_data,
-            mime_type,
-        )
+    let artifact = models::Artifact::create_for_blob(db, file_name, mime_type, &blob)
         .await
-        .map_err(|e| (Status::InternalServerError, e))?;
-
-    let artifact = models::Artifact::create_with_checksum(
-        db,
-        file_name,
-        mime_type,
-        Some(&md5),
-        Some(&sha1),
-        Some(&sha256),
-        Some(&download_url),
-        file_data.len() as i32,
-    )
-    .await
-    .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
 
     models::CoreReleaseArtifact::create(db, release, &artifact)
         .await
@@ -292,19 +391,32 @@ async fn upload_single_artifact(
 
     Ok(dto::artifact::ArtifactCreateResponse {
         id: artifact.id,
-        url: Some(download_url),
+        url: artifact.download_url,
     })
 }
 
 /// Upload an artifact to a release. This can be done multiple times.
 /// The upload will be refused if the user does not have permission to
 /// upload artifacts to the release's core.
-#[openapi(tag = "Core Releases", ignore = "db", ignore = "storage")]
+///
+/// When uploading a single file, its expected size and checksums can be sent
+/// as the `size`, `md5`, `sha1` and `sha256` (hex) form fields, or using the
+/// `Digest` and `Content-MD5` headers. The upload is refused with a 422 if
+/// they don't match.
+#[openapi(
+    tag = "Core Releases",
+    ignore = "config",
+    ignore = "db",
+    ignore = "storage"
+)]
 #[post("/cores/<core_id>/releases/<release_id>/artifacts", data = "<file>")]
+#[allow(clippy::too_many_arguments)]
 pub async fn cores_releases_artifacts_upload(
     mut db: Db,
     admin: guards::users::AuthenticatedUserGuard,
+    config: &State<RetronomiconConfig>,
     storage: guards::storage::Storage,
+    digest: guards::checksums::DigestHeaders,
     core_id: dto::types::IdOrSlug<'_>,
     release_id: u32,
     content_type: &ContentType,
@@ -313,37 +425,67 @@ pub async fn cores_releases_artifacts_upload(
     // Check the uploader's role.
     let core = models::Core::from_id_or_slug(&mut db, core_id).await?;
 
-    let (user, team, role) =
-        models::User::get_user_team_and_role(&mut db, admin.into(), core.owner_team_id.into())
-            .await
-            .map_err(|e| (Status::InternalServerError, e.to_string()))?
-            .ok_or((Status::Unauthorized, "Not logged in".to_string()))?;
-
-    if !acls::can_create_core_releases(&user, &team, &role, &core).await {
-        return Err((Status::Forbidden, "Not authorized".to_string()));
-    }
+    let (user, team, role) = acls::core_access(&mut db, admin.id, &core).await?;
+    acls::require(
+        acls::check_core(
+            &mut db,
+            &user,
+            &team,
+            role.as_ref(),
+            &core,
+            Permission::CreateReleases,
+        )
+        .await?,
+    )?;
 
     let release = models::CoreRelease::from_id(&mut db, release_id as i32)
         .await
         .map_err(|e| (Status::InternalServerError, e.to_string()))?
         .ok_or((Status::NotFound, "Release not found".to_string()))?;
+    let size_limit = config
+        .upload_size_limit_for(&team)
+        .min(MAX_SINGLE_UPLOAD_SIZE);
 
     let mut result = Vec::new();
 
+    // Files are read in memory, as they are sent to the storage as a whole.
     let mut options = MultipartFormDataOptions::with_multipart_form_data_fields(vec![
-        MultipartFormDataField::file("file")
-            .size_limit(24.mebibytes().as_u64())
+        MultipartFormDataField::raw("file")
+            .size_limit(MAX_SINGLE_UPLOAD_SIZE as u64)
             .repetition(Repetition::infinite()),
-        MultipartFormDataField::file("artifact")
-            .size_limit(24.mebibytes().as_u64())
+        MultipartFormDataField::raw("artifact")
+            .size_limit(MAX_SINGLE_UPLOAD_SIZE as u64)
             .repetition(Repetition::infinite()),
+        MultipartFormDataField::text("size"),
+        MultipartFormDataField::text("md5"),
+        MultipartFormDataField::text("sha1"),
+        MultipartFormDataField::text("sha256"),
     ]);
     options.max_data_bytes = 40.mebibytes().as_u64();
     let multipart_form_data = MultipartFormData::parse(content_type, file, options)
         .await
         .map_err(|e| (Status::BadRequest, e.to_string()))?;
 
-    for files in multipart_form_data.files.values() {
+    let text = |name: &str| {
+        multipart_form_data
+            .texts
+            .get(name)
+            .and_then(|fields| fields.first())
+            .map(|field| field.text.as_str())
+    };
+    let expected =
+        ExpectedChecksums::from_fields(text("size"), text("md5"), text("sha1"), text("sha256"))
+            .and_then(|fields| fields.merge(digest.0))
+            .map_err(|e| (Status::BadRequest, e))?;
+    let file_count: usize = multipart_form_data.raw.values().map(Vec::len).sum();
+    if !expected.is_empty() && file_count > 1 {
+        return Err((
+            Status::BadRequest,
+            "Checksums can only be verified when uploading a single file".to_string(),
+        ));
+    }
+
+    for files in multipart_form_data.raw.values() {
         for file in files {
             let filename = file
                 .file_name
@@ -353,8 +495,13 @@ pub async fn cores_releases_artifacts_upload(
                 Status::BadRequest,
                 "Content-Type not specified.".to_string(),
             ))?;
-            let file_data = std::fs::read(&file.path)
-                .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+            let file_data = &file.raw;
+            if file_data.len() as i64 > size_limit {
+                return Err((
+                    Status::BadRequest,
+                    format!("File is too large (max {size_limit} bytes)"),
+                ));
+            }
 
             // Make sure the filename is unique.
             // TODO: figure out if we can make this check in the database itself.
@@ -379,12 +526,12 @@ pub async fn cores_releases_artifacts_upload(
 
             let artifact = upload_single_artifact(
                 &mut db,
-                &core,
                 &release,
                 &storage,
                 &filename,
                 mimetype.as_ref(),
-                &file_data,
+                file_data,
+                &expected,
             )
             .await?;
 
@@ -0,0 +1,304 @@
+use crate::guards;
+use crate::utils::acls;
+use crate::utils::files::{compare_checksum, is_valid_path_segment};
+use retronomicon_db::models;
+use retronomicon_db::types::FetchModel;
+use retronomicon_db::Db;
+use retronomicon_dto as dto;
+use retronomicon_dto::cores::required_files::{
+    DeviceFile, RequiredFileCheck, RequiredFileHash, RequiredFileStatus,
+};
+use retronomicon_dto::permissions::Permission;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{delete, get, post};
+use rocket_okapi::openapi;
+use std::collections::BTreeMap;
+
+/// The maximum number of device files checked in a single request.
+const MAX_DEVICE_FILES: usize = 10_000;
+
+/// A path on a device, without leading slashes and with forward slashes.
+fn normalize_path(path: &str) -> String {
+    path.replace('\\', "/").trim_start_matches('/').to_string()
+}
+
+fn validate_required_file(
+    filename: &str,
+    path: &str,
+    hashes: &[RequiredFileHash],
+) -> Result<(), (Status, String)> {
+    if filename.len() > 255 || !is_valid_path_segment(filename) {
+        return Err((Status::BadRequest, format!("Invalid filename {filename:?}")));
+    }
+    let path = normalize_path(path);
+    if path.len() > 1024
+        || (!path.is_empty()
+            && !path
+                .trim_end_matches('/')
+                .split('/')
+                .all(is_valid_path_segment))
+    {
+        return Err((Status::BadRequest, format!("Invalid path {path:?}")));
+    }
+    if hashes
+        .iter()
+        .any(|h| h.crc32.is_none() && h.md5.is_none() && h.sha1.is_none() && h.sha256.is_none())
+    {
+        return Err((
+            Status::BadRequest,
+            "Hashes need at least one checksum".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Whether a file of a device is a version of a required file: same size
+/// when known, and the same checksums where both are known.
+fn is_accepted(file: &DeviceFile, hash: &models::CoreRequiredFileHash) -> bool {
+    let checksums = [
+        compare_checksum(&file.crc32, &hash.crc32),
+        compare_checksum(&file.md5, &hash.md5),
+        compare_checksum(&file.sha1, &hash.sha1),
+        compare_checksum(&file.sha256, &hash.sha256),
+    ];
+    hash.size.map_or(true, |size| size == file.size)
+        && !checksums.contains(&Some(false))
+        && checksums.contains(&Some(true))
+}
+
+/// Look for a required file among the files of a device.
+fn check_file(
+    file: models::CoreRequiredFile,
+    hashes: Vec<models::CoreRequiredFileHash>,
+    device_files: &[DeviceFile],
+) -> RequiredFileCheck {
+    let path = normalize_path(&file.path);
+    let full_path = match path.trim_end_matches('/') {
+        "" => file.filename.clone(),
+        path => format!("{path}/{}", file.filename),
+    };
+    let accepted = |f: &DeviceFile| hashes.is_empty() || hashes.iter().any(|h| is_accepted(f, h));
+
+    let at_path = device_files
+        .iter()
+        .find(|f| normalize_path(&f.path) == full_path);
+    let (status, found_at) = match at_path {
+        Some(f) if accepted(f) => (RequiredFileStatus::Present, None),
+        _ => match device_files
+            .iter()
+            .find(|f| !hashes.is_empty() && accepted(f))
+        {
+            Some(f) => (RequiredFileStatus::Misplaced, Some(f.path.clone())),
+            None if at_path.is_some() => (RequiredFileStatus::Mismatched, None),
+            None => (RequiredFileStatus::Missing, None),
+        },
+    };
+
+    RequiredFileCheck {
+        file: file.into_dto(hashes),
+        status,
+        found_at,
+    }
+}
+
+/// List the files a core needs on the device to run, e.g. BIOS files.
+#[openapi(tag = "Cores", ignore = "db")]
+#[get("/cores/<core_id>/required-files?<filter..>")]
+pub async fn cores_required_files_list(
+    mut db: Db,
+    core_id: dto::types::IdOrSlug<'_>,
+    filter: dto::cores::required_files::CoreRequiredFileListQueryParams,
+) -> Result<Json<Vec<dto::cores::required_files::CoreRequiredFile>>, (Status, String)> {
+    let core = models::Core::from_id_or_slug(&mut db, core_id).await?;
+
+    let files = models::CoreRequiredFile::list_for_cores(&mut db, &[core.id])
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    Ok(Json(
+        files
+            .into_iter()
+            .filter(|(file, _)| filter.release.map_or(true, |r| file.applies_to(r)))
+            .map(|(file, hashes)| file.into_dto(hashes))
+            .collect(),
+    ))
+}
+
+/// Declare a file a core, or one of its releases, needs on the device to
+/// run.
+#[openapi(tag = "Cores", ignore = "db")]
+#[post(
+    "/cores/<core_id>/required-files",
+    format = "application/json",
+    data = "<form>"
+)]
+pub async fn cores_required_files_create(
+    mut db: Db,
+    user: guards::users::AuthenticatedUserGuard,
+    core_id: dto::types::IdOrSlug<'_>,
+    form: Json<dto::cores::required_files::CoreRequiredFileCreateRequest<'_>>,
+) -> Result<Json<dto::cores::required_files::CoreRequiredFileCreateResponse>, (Status, String)> {
+    let dto::cores::required_files::CoreRequiredFileCreateRequest {
+        release,
+        filename,
+        path,
+        optional,
+        description,
+        hashes,
+    } = form.into_inner();
+
+    let core = models::Core::from_id_or_slug(&mut db, core_id).await?;
+    let (user, team, role) = acls::core_access(&mut db, user.id, &core).await?;
+    acls::require(
+        acls::check_core(
+            &mut db,
+            &user,
+            &team,
+            role.as_ref(),
+            &core,
+            Permission::CreateReleases,
+        )
+        .await?,
+    )?;
+
+    validate_required_file(filename, path, &hashes)?;
+    if let Some(release_id) = release {
+        models::CoreRelease::from_id(&mut db, release_id)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+            .filter(|r| r.core_id == core.id)
+            .ok_or((Status::NotFound, "Release not found".to_string()))?;
+    }
+
+    let file = models::CoreRequiredFile::create(
+        &mut db,
+        core.id,
+        release,
+        filename,
+        &normalize_path(path),
+        optional,
+        description,
+        hashes,
+    )
+    .await
+    .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(
+        dto::cores::required_files::CoreRequiredFileCreateResponse { id: file.id },
+    ))
+}
+
+/// Remove a required file of a core.
+#[openapi(tag = "Cores", ignore = "db")]
+#[delete("/cores/<core_id>/required-files/<file_id>")]
+pub async fn cores_required_files_delete(
+    mut db: Db,
+    user: guards::users::AuthenticatedUserGuard,
+    core_id: dto::types::IdOrSlug<'_>,
+    file_id: u32,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let core = models::Core::from_id_or_slug(&mut db, core_id).await?;
+    let (user, team, role) = acls::core_access(&mut db, user.id, &core).await?;
+    acls::require(
+        acls::check_core(
+            &mut db,
+            &user,
+            &team,
+            role.as_ref(),
+            &core,
+            Permission::CreateReleases,
+        )
+        .await?,
+    )?;
+
+    let deleted = models::CoreRequiredFile::delete(&mut db, core.id, file_id as i32)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    if !deleted {
+        return Err((Status::NotFound, "Required file not found".to_string()));
+    }
+    Ok(Json(dto::Ok))
+}
+
+/// Check which cores can run on a device, given the files of the device.
+/// Only the latest release of each core is checked, yanked releases are
+/// skipped.
+#[openapi(tag = "Cores", ignore = "db")]
+#[post("/cores/compatibility", format = "application/json", data = "<form>")]
+pub async fn cores_compatibility(
+    mut db: Db,
+    form: Json<dto::cores::required_files::CoreCompatibilityRequest<'_>>,
+) -> Result<Json<Vec<dto::cores::required_files::CoreCompatibility>>, (Status, String)> {
+    let dto::cores::required_files::CoreCompatibilityRequest {
+        platform,
+        system,
+        files,
+    } = form.into_inner();
+    if files.len() > MAX_DEVICE_FILES {
+        return Err((
+            Status::BadRequest,
+            format!("Too many files, the maximum is {MAX_DEVICE_FILES}"),
+        ));
+    }
+
+    let platform = match platform {
+        Some(platform) => Some(models::Platform::from_id_or_slug(&mut db, platform).await?),
+        None => None,
+    };
+    let system = match system {
+        Some(system) => Some(models::System::from_id_or_slug(&mut db, system).await?),
+        None => None,
+    };
+
+    let cores = models::Core::list_with_teams_and_releases(
+        &mut db,
+        0,
+        i64::MAX,
+        platform.as_ref(),
+        system.as_ref(),
+        None,
+        None,
+        false,
+    )
+    .await
+    .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    let core_ids = cores.iter().map(|(core, ..)| core.id).collect::<Vec<_>>();
+    let mut required_files = BTreeMap::<i32, Vec<_>>::new();
+    for (file, hashes) in models::CoreRequiredFile::list_for_cores(&mut db, &core_ids)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        required_files
+            .entry(file.core_id)
+            .or_default()
+            .push((file, hashes));
+    }
+
+    Ok(Json(
+        cores
+            .into_iter()
+            .filter_map(|(core, _, _, release, platform)| {
+                let release = release?;
+                let checks = required_files
+                    .remove(&core.id)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|(file, _)| file.applies_to(release.id))
+                    .map(|(file, hashes)| check_file(file, hashes, &files))
+                    .collect::<Vec<_>>();
+                Some(dto::cores::required_files::CoreCompatibility {
+                    core: dto::cores::CoreRef {
+                        id: core.id,
+                        slug: core.slug,
+                        name: core.name,
+                    },
+                    release: release.into_ref(platform),
+                    playable: checks
+                        .iter()
+                        .all(|c| c.file.optional || c.status == RequiredFileStatus::Present),
+                    files: checks,
+                })
+            })
+            .collect(),
+    ))
+}
@@ -0,0 +1,508 @@
+use crate::fairings::config::RetronomiconConfig;
+use crate::guards;
+use crate::guards::storage::{Paths, StorageBucket, StoragePart};
+use crate::utils::acls;
+use crate::utils::uploads::{
+    discard_upload, hash_stored_file, UploadBusy, UploadHashers, MAX_PART_SIZE, MIN_PART_SIZE,
+};
+use retronomicon_db::models;
+use retronomicon_db::types::FetchModel;
+use retronomicon_db::Db;
+use retronomicon_dto as dto;
+use retronomicon_dto::permissions::Permission;
+use rocket::data::ToByteUnit;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{delete, get, post, put, Data, State};
+use rocket_okapi::openapi;
+
+/// Fetch the core and release, and check that the user can upload artifacts
+/// to it.
+async fn release_for_upload(
+    db: &mut Db,
+    admin: guards::users::AuthenticatedUserGuard,
+    core_id: dto::types::IdOrSlug<'_>,
+    release_id: u32,
+) -> Result<
+    (
+        models::User,
+        models::Team,
+        models::Core,
+        models::CoreRelease,
+    ),
+    (Status, String),
+> {
+    let core = models::Core::from_id_or_slug(db, core_id).await?;
+
+    let (user, team, role) = acls::core_access(db, admin.id, &core).await?;
+    acls::require(
+        acls::check_core(
+            db,
+            &user,
+            &team,
+            role.as_ref(),
+            &core,
+            Permission::CreateReleases,
+        )
+        .await?,
+    )?;
+
+    let release = models::CoreRelease::from_id(db, release_id as i32)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .filter(|r| r.core_id == core.id)
+        .ok_or((Status::NotFound, "Release not found".to_string()))?;
+
+    Ok((user, team, core, release))
+}
+
+async fn get_upload(
+    db: &mut Db,
+    release: &models::CoreRelease,
+    upload_id: i32,
+) -> Result<models::CoreReleaseUpload, (Status, String)> {
+    models::CoreReleaseUpload::get(db, release, upload_id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::NotFound, "Upload not found".to_string()))
+}
+
+fn upload_to_dto(
+    upload: models::CoreReleaseUpload,
+) -> Result<dto::artifact::ArtifactUpload, (Status, String)> {
+    let next_part = upload
+        .next_part_number()
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(dto::artifact::ArtifactUpload {
+        id: upload.id,
+        filename: upload.filename,
+        mime_type: upload.mime_type,
+        size: upload.size,
+        received: upload.received,
+        next_part,
+        min_part_size: MIN_PART_SIZE,
+        max_part_size: MAX_PART_SIZE,
+        created_at: upload.created_at.timestamp(),
+        updated_at: upload.updated_at.timestamp(),
+    })
+}
+
+fn upload_busy(_: UploadBusy) -> (Status, String) {
+    (
+        Status::Conflict,
+        "Another request is using this upload".to_string(),
+    )
+}
+
+/// Start uploading an artifact in multiple parts. This is needed for files
+/// larger than what a single upload accepts. The size of the file is
+/// checked against the upload limit of the core's team.
+#[openapi(
+    tag = "Core Releases",
+    ignore = "config",
+    ignore = "db",
+    ignore = "storage"
+)]
+#[post("/cores/<core_id>/releases/<release_id>/uploads", data = "<form>")]
+pub async fn cores_releases_uploads_create(
+    mut db: Db,
+    admin: guards::users::AuthenticatedUserGuard,
+    config: &State<RetronomiconConfig>,
+    storage: guards::storage::Storage,
+    core_id: dto::types::IdOrSlug<'_>,
+    release_id: u32,
+    form: Json<dto::artifact::ArtifactUploadCreateRequest<'_>>,
+) -> Result<Json<dto::artifact::ArtifactUpload>, (Status, String)> {
+    let db = &mut db;
+    let (user, team, core, release) = release_for_upload(db, admin, core_id, release_id).await?;
+    let dto::artifact::ArtifactUploadCreateRequest {
+        filename,
+        mime_type,
+        size,
+    } = form.into_inner();
+
+    let size_limit = config.upload_size_limit_for(&team);
+    if size <= 0 {
+        return Err((Status::BadRequest, "File is empty".to_string()));
+    } else if size > size_limit {
+        return Err((
+            Status::BadRequest,
+            format!("File is too large (max {size_limit} bytes)"),
+        ));
+    }
+
+    if !models::CoreReleaseArtifact::is_filename_conform(db, &release, filename)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        return Err((Status::BadRequest, "Filename is invalid".to_string()));
+    }
+    if !models::CoreReleaseArtifact::is_filename_unique_for_release(db, &release, filename)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        || models::CoreReleaseUpload::is_filename_used_for_release(db, &release, filename)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        return Err((
+            Status::Conflict,
+            "Filename already exists for this release".to_string(),
+        ));
+    }
+
+    let storage_path = Paths::path_for_core_artifact(&core, &release, filename);
+    let storage_upload_id = storage
+        .create_multipart(StorageBucket::Cores, &storage_path, mime_type)
+        .await
+        .map_err(|e| (Status::InternalServerError, e))?;
+
+    let upload = models::CoreReleaseUpload::create(
+        db,
+        &release,
+        &user,
+        filename,
+        mime_type,
+        size,
+        &storage_path,
+        &storage_upload_id,
+    )
+    .await
+    .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(upload_to_dto(upload)?))
+}
+
+/// Get the status of an upload, e.g. to know which part to resume from.
+#[openapi(tag = "Core Releases", ignore = "db")]
+#[get("/cores/<core_id>/releases/<release_id>/uploads/<upload_id>")]
+pub async fn cores_releases_uploads_details(
+    mut db: Db,
+    admin: guards::users::AuthenticatedUserGuard,
+    core_id: dto::types::IdOrSlug<'_>,
+    release_id: u32,
+    upload_id: i32,
+) -> Result<Json<dto::artifact::ArtifactUpload>, (Status, String)> {
+    let db = &mut db;
+    let (_, _, _, release) = release_for_upload(db, admin, core_id, release_id).await?;
+    let upload = get_upload(db, &release, upload_id).await?;
+
+    Ok(Json(upload_to_dto(upload)?))
+}
+
+/// Upload a part of the file. Parts must be uploaded in order; the body of
+/// the request is the raw content of the part. If a part fails, it can be
+/// sent again.
+#[openapi(
+    tag = "Core Releases",
+    ignore = "db",
+    ignore = "hashers",
+    ignore = "storage"
+)]
+#[put(
+    "/cores/<core_id>/releases/<release_id>/uploads/<upload_id>/parts/<part>",
+    data = "<data>"
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn cores_releases_uploads_part(
+    mut db: Db,
+    admin: guards::users::AuthenticatedUserGuard,
+    hashers: &State<UploadHashers>,
+    storage: guards::storage::Storage,
+    core_id: dto::types::IdOrSlug<'_>,
+    release_id: u32,
+    upload_id: i32,
+    part: u32,
+    data: Data<'_>,
+) -> Result<Json<dto::artifact::ArtifactUpload>, (Status, String)> {
+    let db = &mut db;
+    let (_, _, _, release) = release_for_upload(db, admin, core_id, release_id).await?;
+    let upload = get_upload(db, &release, upload_id).await?;
+
+    let next_part = upload
+        .next_part_number()
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    if part != next_part {
+        return Err((
+            Status::Conflict,
+            format!("Expected part {next_part}, got part {part}"),
+        ));
+    }
+
+    let data = data
+        .open((MAX_PART_SIZE as u64).bytes())
+        .into_bytes()
+        .await
+        .map_err(|e| (Status::BadRequest, e.to_string()))?;
+    if !data.is_complete() {
+        return Err((
+            Status::PayloadTooLarge,
+            format!("Part is too large (max {MAX_PART_SIZE} bytes)"),
+        ));
+    }
+    let data = data.into_inner();
+
+    let size = data.len() as i64;
+    let received = upload.received + size;
+    if size == 0 {
+        return Err((Status::BadRequest, "Part is empty".to_string()));
+    } else if received > upload.size {
+        return Err((
+            Status::BadRequest,
+            format!(
+                "Part is larger than the rest of the file ({} bytes)",
+                upload.size - upload.received
+            ),
+        ));
+    } else if received < upload.size && size < MIN_PART_SIZE {
+        return Err((
+            Status::BadRequest,
+            format!("Part is too small (min {MIN_PART_SIZE} bytes)"),
+        ));
+    }
+
+    // Without a hasher, the checksums are computed once the file is assembled.
+    let hasher = hashers
+        .checkout(upload.id, upload.received)
+        .map_err(upload_busy)?;
+    let new_hasher = hasher.clone().map(|mut h| {
+        h.update(&data);
+        h
+    });
+
+    let etag = match storage
+        .upload_part(
+            StorageBucket::Cores,
+            &upload.storage_path,
+            &upload.storage_upload_id,
+            part,
+            data,
+            &upload.mime_type,
+        )
+        .await
+    {
+        Ok(etag) => etag,
+        Err(e) => {
+            hashers.checkin(upload.id, hasher);
+            return Err((Status::InternalServerError, e));
+        }
+    };
+
+    let part = models::CoreReleaseUploadPart {
+        part_number: part,
+        etag,
+        size,
+    };
+    match upload.add_part(db, part).await {
+        Ok(Some(upload)) => {
+            hashers.checkin(upload.id, new_hasher);
+            Ok(Json(upload_to_dto(upload)?))
+        }
+        Ok(None) => {
+            hashers.checkin(upload.id, hasher);
+            Err((
+                Status::Conflict,
+                "Upload was modified concurrently".to_string(),
+            ))
+        }
+        Err(e) => {
+            hashers.checkin(upload.id, hasher);
+            Err((Status::InternalServerError, e.to_string()))
+        }
+    }
+}
+
+/// Assemble the parts of an upload in the storage and record the checksums
+/// of the file. If the hasher of the upload was lost, the checksums are
+/// computed by reading the file back.
+async fn assemble_upload(
+    db: &mut Db,
+    hashers: &UploadHashers,
+    storage: &guards::storage::Storage,
+    upload: models::CoreReleaseUpload,
+) -> Result<models::CoreReleaseUpload, (Status, String)> {
+    let hasher = hashers
+        .checkout(upload.id, upload.received)
+        .map_err(upload_busy)?;
+
+    let result = async {
+        let parts = upload
+            .parts()
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+            .into_iter()
+            .map(|p| StoragePart {
+                part_number: p.part_number,
+                etag: p.etag,
+            })
+            .collect();
+
+        let stored = match storage
+            .complete_multipart(
+                StorageBucket::Cores,
+                &upload.storage_path,
+                &upload.storage_upload_id,
+                parts,
+            )
+            .await
+        {
+            Ok(_) => None,
+            // A previous attempt might have assembled the parts already.
+            Err(e) => {
+                match hash_stored_file(storage, StorageBucket::Cores, &upload.storage_path).await {
+                    Ok(stored) if stored.size() == upload.size => Some(stored),
+                    _ => return Err((Status::InternalServerError, e)),
+                }
+            }
+        };
+
+        let hasher = match (stored, hasher.clone()) {
+            (Some(stored), _) => stored,
+            (None, Some(hasher)) if hasher.size() == upload.size => hasher,
+            (None, _) => hash_stored_file(storage, StorageBucket::Cores, &upload.storage_path)
+                .await
+                .map_err(|e| (Status::InternalServerError, e))?,
+        };
+        let (md5, sha1, sha256) = hasher.finalize();
+        upload
+            .set_checksums(db, &md5, &sha1, &sha256)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))
+    }
+    .await;
+
+    hashers.checkin(upload.id, hasher);
+    result
+}
+
+/// Finish an upload once all parts were received, creating the artifact.
+/// Expected checksums can be sent in the `Digest` or `Content-MD5` headers;
+/// if they don't match, the upload is kept as is and can be cancelled.
+#[openapi(
+    tag = "Core Releases",
+    ignore = "db",
+    ignore = "hashers",
+    ignore = "storage"
+)]
+#[post("/cores/<core_id>/releases/<release_id>/uploads/<upload_id>/complete")]
+#[allow(clippy::too_many_arguments)]
+pub async fn cores_releases_uploads_complete(
+    mut db: Db,
+    admin: guards::users::AuthenticatedUserGuard,
+    digest: guards::checksums::DigestHeaders,
+    hashers: &State<UploadHashers>,
+    storage: guards::storage::Storage,
+    core_id: dto::types::IdOrSlug<'_>,
+    release_id: u32,
+    upload_id: i32,
+) -> Result<Json<dto::artifact::ArtifactCreateResponse>, (Status, String)> {
+    let db = &mut db;
+    let (_, _, _, release) = release_for_upload(db, admin, core_id, release_id).await?;
+    let upload = get_upload(db, &release, upload_id).await?;
+
+    if !upload.is_complete() {
+        return Err((
+            Status::BadRequest,
+            format!(
+                "Upload is incomplete ({} of {} bytes received)",
+                upload.received, upload.size
+            ),
+        ));
+    }
+    if !models::CoreReleaseArtifact::is_filename_unique_for_release(db, &release, &upload.filename)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        return Err((
+            Status::Conflict,
+            "Filename already exists for this release".to_string(),
+        ));
+    }
+
+    let upload = match upload.checksums() {
+        Some(_) => upload,
+        None => assemble_upload(db, hashers, &storage, upload).await?,
+    };
+    let (md5, sha1, sha256) = upload.checksums().expect("Upload checksums were recorded");
+    digest
+        .0
+        .verify(upload.size, md5, sha1, sha256)
+        .map_err(|e| (Status::UnprocessableEntity, e))?;
+
+    let existing = models::Blob::get_by_sha256(db, sha256)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    let blob = match existing {
+        Some(blob) => blob,
+        // Blobs are stored by their content, so they can be shared by
+        // artifacts of other releases.
+        None => {
+            let blob_path = Paths::path_for_blob(sha256);
+            let download_url = storage
+                .copy(StorageBucket::Cores, &upload.storage_path, &blob_path)
+                .await
+                .map_err(|e| (Status::InternalServerError, e))?;
+            models::Blob::create(
+                db,
+                md5,
+                sha1,
+                sha256,
+                upload.size,
+                Some(&blob_path),
+                Some(&download_url),
+            )
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+            .0
+        }
+    };
+
+    let (id, storage_path) = (upload.id, upload.storage_path.clone());
+    let artifact = upload
+        .complete(db, &release, &blob)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    hashers.remove(id);
+
+    // Only the blob is kept.
+    if let Err(e) = storage.delete(StorageBucket::Cores, &storage_path).await {
+        rocket::warn!("Failed to delete upload {}: {}", id, e);
+    }
+
+    Ok(Json(dto::artifact::ArtifactCreateResponse {
+        id: artifact.id,
+        url: artifact.download_url,
+    }))
+}
+
+/// Cancel an upload, discarding all parts received so far.
+#[openapi(
+    tag = "Core Releases",
+    ignore = "db",
+    ignore = "hashers",
+    ignore = "storage"
+)]
+#[delete("/cores/<core_id>/releases/<release_id>/uploads/<upload_id>")]
+pub async fn cores_releases_uploads_delete(
+    mut db: Db,
+    admin: guards::users::AuthenticatedUserGuard,
+    hashers: &State<UploadHashers>,
+    storage: guards::storage::Storage,
+    core_id: dto::types::IdOrSlug<'_>,
+    release_id: u32,
+    upload_id: i32,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let db = &mut db;
+    let (_, _, _, release) = release_for_upload(db, admin, core_id, release_id).await?;
+    let upload = get_upload(db, &release, upload_id).await?;
+
+    discard_upload(&storage, &upload)
+        .await
+        .map_err(|e| (Status::InternalServerError, e))?;
+    upload
+        .delete(db)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    hashers.remove(upload.id);
+
+    Ok(Json(dto::Ok))
+}
@@ -1,10 +1,13 @@
 use crate::fairings::config::RetronomiconConfig;
 use crate::guards;
-use crate::utils::acls;
+use crate::utils::files::{compare_checksum, is_valid_path_segment};
+use crate::utils::{acls, images};
 use image::{GenericImageView, ImageFormat};
 use retronomicon_db::models;
+use retronomicon_db::types::FetchModel;
 use retronomicon_db::Db;
 use retronomicon_dto as dto;
+use retronomicon_dto::permissions::Permission;
 use rocket::data::ToByteUnit;
 use rocket::http::{ContentType, Status};
 use rocket::serde::json::Json;
@@ -14,11 +17,144 @@ use rocket_multipart_form_data::{
 };
 use rocket_okapi::openapi;
 use serde_json::json;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+
+pub mod metadata;
+pub mod relationships;
+pub mod translations;
 
 const MAX_IMAGE_WIDTH: u32 = 4096;
 const MAX_IMAGE_HEIGHT: u32 = 4096;
 
+/// The maximum number of files identified in a single request.
+const MAX_IDENTIFY_FILES: usize = 10_000;
+
+fn validate_players(
+    min_players: Option<i32>,
+    max_players: Option<i32>,
+) -> Result<(), (Status, String)> {
+    if min_players.is_some_and(|min| min < 1) {
+        return Err((
+            Status::BadRequest,
+            "Games need at least one player".to_string(),
+        ));
+    }
+    if let (Some(min), Some(max)) = (min_players, max_players) {
+        if max < min {
+            return Err((
+                Status::BadRequest,
+                "The maximum number of players is less than the minimum".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The companies of a game from a request, given either as a list or as a
+/// single company in the deprecated `publisher` and `developer` fields.
+fn companies<'a>(
+    field: &str,
+    list: Option<Vec<&'a str>>,
+    deprecated: Option<&'a str>,
+) -> Result<Option<Vec<&'a str>>, (Status, String)> {
+    match (list, deprecated) {
+        (Some(list), Some(_)) if !list.is_empty() => Err((
+            Status::BadRequest,
+            format!("Cannot specify both {field}s and {field}"),
+        )),
+        (_, Some("")) => Ok(Some(vec![])),
+        (_, Some(name)) => Ok(Some(vec![name])),
+        (list, None) => Ok(list),
+    }
+}
+
+/// Validate the metadata of a game from a request, finding its regions and
+/// genres by their slugs. Lists that are `None` are left as is.
+#[allow(clippy::too_many_arguments)]
+async fn metadata_update<'a>(
+    db: &mut Db,
+    publishers: Option<Vec<&'a str>>,
+    developers: Option<Vec<&'a str>>,
+    releases: Option<Vec<dto::games::GameReleaseRequest<'a>>>,
+    languages: Option<Vec<&'a str>>,
+    genres: Option<Vec<&'a str>>,
+    series: Option<Vec<&'a str>>,
+    tags: Option<Vec<&'a str>>,
+) -> Result<models::GameMetadataUpdate<'a>, (Status, String)> {
+    let releases = match releases {
+        Some(releases) => {
+            let slugs = releases.iter().map(|r| r.region).collect::<Vec<_>>();
+            let regions = models::Region::get_by_slugs(db, &slugs)
+                .await
+                .map_err(|e| (Status::InternalServerError, e.to_string()))?
+                .into_iter()
+                .map(|r| (r.slug, r.id))
+                .collect::<BTreeMap<_, _>>();
+
+            let releases = releases
+                .into_iter()
+                .map(|r| {
+                    let region_id = *regions
+                        .get(r.region)
+                        .ok_or((Status::BadRequest, format!("Unknown region {:?}", r.region)))?;
+                    let date = r
+                        .date
+                        .map(|d| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d"))
+                        .transpose()
+                        .map_err(|e| (Status::BadRequest, format!("Invalid release date: {e}")))?;
+                    Ok((region_id, date))
+                })
+                .collect::<Result<Vec<_>, (Status, String)>>()?;
+            Some(releases)
+        }
+        None => None,
+    };
+
+    if let Some(language) = languages
+        .iter()
+        .flatten()
+        .find(|l| !dto::games::is_valid_language_tag(l))
+    {
+        return Err((Status::BadRequest, format!("Invalid language {language:?}")));
+    }
+
+    let genres = match genres {
+        Some(slugs) => {
+            let genres = models::Genre::get_by_slugs(db, &slugs)
+                .await
+                .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+            if let Some(unknown) = slugs.iter().find(|s| !genres.iter().any(|g| g.slug == **s)) {
+                return Err((Status::BadRequest, format!("Unknown genre {unknown:?}")));
+            }
+            Some(genres.into_iter().map(|g| g.id).collect())
+        }
+        None => None,
+    };
+
+    let tags = match tags {
+        Some(slugs) => {
+            let tags = models::Tag::get_by_slugs(db, &slugs)
+                .await
+                .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+            if let Some(unknown) = slugs.iter().find(|s| !tags.iter().any(|t| t.slug == **s)) {
+                return Err((Status::BadRequest, format!("Unknown tag {unknown:?}")));
+            }
+            Some(tags.into_iter().map(|t| t.id).collect())
+        }
+        None => None,
+    };
+
+    Ok(models::GameMetadataUpdate {
+        publishers,
+        developers,
+        releases,
+        languages,
+        genres,
+        series,
+        tags,
+    })
+}
+
 #[openapi(tag = "Games", ignore = "db")]
 #[post("/games", format = "application/json", data = "<form>")]
 pub async fn games_create(
@@ -31,28 +167,51 @@ pub async fn games_create(
         short_description,
         description,
         year,
-        publisher,
-        developer,
         links,
         system,
         system_unique_id,
+        publishers,
+        developers,
+        publisher,
+        developer,
+        releases,
+        languages,
+        genres,
+        series,
+        tags,
+        min_players,
+        max_players,
     } = form.into_inner();
     let system = models::System::get(&mut db, system)
         .await
         .map_err(|e| (Status::InternalServerError, e.to_string()))?
         .ok_or((Status::NotFound, "Not found".to_string()))?;
 
+    validate_players(min_players, max_players)?;
+    let metadata = metadata_update(
+        &mut db,
+        companies("publisher", Some(publishers), publisher)?,
+        companies("developer", Some(developers), developer)?,
+        Some(releases),
+        Some(languages),
+        Some(genres),
+        Some(series),
+        Some(tags),
+    )
+    .await?;
+
     let game = models::Game::create(
         &mut db,
         name,
         description,
         short_description,
         year,
-        publisher,
-        developer,
         json!(links),
         system.id,
         system_unique_id,
+        min_players,
+        max_players,
+        metadata,
     )
     .await
     .map_err(|e| (Status::InternalServerError, e.to_string()))?;
@@ -64,6 +223,7 @@ pub async fn games_create(
 #[get("/games?<filter..>", format = "application/json", data = "<form>")]
 pub async fn games_list(
     mut db: Db,
+    accept_language: guards::languages::AcceptLanguage,
     filter: dto::games::GameListQueryParams<'_>,
     form: Json<dto::games::GameListBody>,
 ) -> Result<Json<Vec<dto::games::GameListItemResponse>>, (Status, String)> {
@@ -75,6 +235,16 @@ pub async fn games_list(
     let year = filter.year.unwrap_or_default().into();
     let name = filter.name.as_deref();
     let exact_name = filter.exact_name.as_deref();
+    let metadata = models::GameMetadataFilter {
+        region: filter.region.as_deref(),
+        language: filter.language.as_deref(),
+        genre: filter.genre.as_deref(),
+        series: filter.series.as_deref(),
+        publisher: filter.publisher.as_deref(),
+        developer: filter.developer.as_deref(),
+        tag: filter.tag.as_deref(),
+        players: filter.players,
+    };
 
     let mut result = BTreeMap::new();
     let form = form.into_inner();
@@ -105,6 +275,7 @@ pub async fn games_list(
         year,
         name,
         exact_name,
+        metadata,
         md5,
         sha1,
         sha256,
@@ -119,6 +290,7 @@ pub async fn games_list(
                 id: g.id,
                 name: g.name,
                 short_description: g.short_description,
+                language: None,
                 year: g.year,
                 system_id: s.into(),
                 system_unique_id: g.system_unique_id,
@@ -129,30 +301,217 @@ pub async fn games_list(
         }
     }
 
+    let preferences = accept_language.with_preferred(filter.lang.as_deref());
+    if !preferences.is_empty() && !result.is_empty() {
+        let ids = result.keys().copied().collect::<Vec<_>>();
+        let translations = models::GameTranslation::list_for_games(&mut db, &ids)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+        for entry in result.values_mut() {
+            let translation = models::GameTranslation::negotiate(
+                translations.iter().filter(|t| t.game_id == entry.id),
+                &preferences,
+            );
+            if let Some(t) = translation {
+                entry.name = t.name.clone();
+                if let Some(short_description) = &t.short_description {
+                    entry.short_description = short_description.clone();
+                }
+                entry.language = Some(t.language.clone());
+            }
+        }
+    }
+
     Ok(Json(result.into_values().collect::<Vec<_>>()))
 }
 
+/// How confident we are that a file is an artifact, or `None` if they
+/// differ. A file without hashes in common with the artifact can only match
+/// its size and CRC32.
+fn identify_confidence(
+    file: &dto::games::GameIdentifyFile,
+    artifact: &models::Artifact,
+) -> Option<dto::games::GameIdentifyConfidence> {
+    let hashes = [
+        compare_checksum(&file.md5, &artifact.md5),
+        compare_checksum(&file.sha1, &artifact.sha1),
+        compare_checksum(&file.sha256, &artifact.sha256),
+    ];
+    let crc32 = compare_checksum(&file.crc32, &artifact.crc32);
+    if file.size != artifact.size || hashes.contains(&Some(false)) || crc32 == Some(false) {
+        None
+    } else if hashes.contains(&Some(true)) {
+        Some(dto::games::GameIdentifyConfidence::Hash)
+    } else if crc32 == Some(true) {
+        Some(dto::games::GameIdentifyConfidence::SizeAndCrc32)
+    } else {
+        None
+    }
+}
+
+/// Identify files by their size and checksums. Returns the games and
+/// artifacts matching each file, in the same order as the files.
+#[openapi(tag = "Games", ignore = "db")]
+#[post("/games/identify", format = "application/json", data = "<form>")]
+pub async fn games_identify(
+    mut db: Db,
+    form: Json<dto::games::GameIdentifyRequest<'_>>,
+) -> Result<Json<Vec<dto::games::GameIdentifyResult>>, (Status, String)> {
+    let dto::games::GameIdentifyRequest { system, files } = form.into_inner();
+    if files.len() > MAX_IDENTIFY_FILES {
+        return Err((
+            Status::BadRequest,
+            format!("Cannot identify more than {MAX_IDENTIFY_FILES} files at once"),
+        ));
+    }
+
+    let checksums = |f: fn(&dto::games::GameIdentifyFile) -> &Option<dto::encodings::HexString>| {
+        files
+            .iter()
+            .filter_map(|file| f(file).as_ref())
+            .filter(|c| !c.is_empty())
+            .map(|c| c.to_vec())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>()
+    };
+    let crc32 = checksums(|f| &f.crc32);
+    let md5 = checksums(|f| &f.md5);
+    let sha1 = checksums(|f| &f.sha1);
+    let sha256 = checksums(|f| &f.sha256);
+
+    let candidates = models::Game::identify(&mut db, system, crc32, md5, sha1, sha256)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    // Checksums of different kinds have different lengths, so they can share
+    // the same index.
+    let mut by_checksum = BTreeMap::<&[u8], Vec<usize>>::new();
+    for (i, (_, _, a)) in candidates.iter().enumerate() {
+        for checksum in [&a.crc32, &a.md5, &a.sha1, &a.sha256] {
+            if !checksum.is_empty() {
+                by_checksum.entry(checksum).or_default().push(i);
+            }
+        }
+    }
+
+    let results = files
+        .iter()
+        .map(|file| {
+            let indices = [&file.crc32, &file.md5, &file.sha1, &file.sha256]
+                .into_iter()
+                .flatten()
+                .filter_map(|c| by_checksum.get(c.as_slice()))
+                .flatten()
+                .copied()
+                .collect::<BTreeSet<_>>();
+
+            let mut matches = indices
+                .into_iter()
+                .filter_map(|i| {
+                    let (g, s, a) = &candidates[i];
+                    identify_confidence(file, a).map(|confidence| dto::games::GameIdentifyMatch {
+                        game_id: g.id,
+                        name: g.name.clone(),
+                        system: dto::systems::SystemRef {
+                            id: s.id,
+                            slug: s.slug.clone(),
+                        },
+                        artifact_id: a.id,
+                        artifact: a.clone().into(),
+                        confidence,
+                    })
+                })
+                .collect::<Vec<_>>();
+            // Candidates are sorted by name, and the sort is stable.
+            matches.sort_by_key(|m| m.confidence);
+            dto::games::GameIdentifyResult { matches }
+        })
+        .collect();
+
+    Ok(Json(results))
+}
+
 #[openapi(tag = "Games", ignore = "db")]
-#[get("/games/<game_id>")]
+#[get("/games/<game_id>?<params..>")]
 pub async fn games_details(
     mut db: Db,
+    accept_language: guards::languages::AcceptLanguage,
     game_id: u32,
+    params: dto::games::GameDetailsQueryParams,
 ) -> Result<Json<dto::games::GameDetails>, (Status, String)> {
     let (game, system) = models::Game::details(&mut db, game_id as i32)
         .await
         .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    let dat = match game.dat_import_id {
+        Some(id) => models::DatImport::get(&mut db, id)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+            .map(|i| dto::games::GameDatRef {
+                import_id: i.id,
+                name: i.dat_name,
+                version: i.dat_version,
+            }),
+        None => None,
+    };
+    let metadata = models::Game::metadata(&mut db, game.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    let translations = models::GameTranslation::list(&mut db, game.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    let relationships = models::Game::relationships(&mut db, game.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    let related_games = models::Game::related_games(&mut db, game.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    // Fields missing from the translation are not translated.
+    let preferences = accept_language.with_preferred(params.lang.as_deref());
+    let translation = models::GameTranslation::negotiate(&translations, &preferences);
+    let (name, description, short_description, language) = match translation {
+        Some(t) => (
+            t.name.clone(),
+            t.description.clone().unwrap_or(game.description),
+            t.short_description
+                .clone()
+                .unwrap_or(game.short_description),
+            Some(t.language.clone()),
+        ),
+        None => (game.name, game.description, game.short_description, None),
+    };
 
     Ok(Json(dto::games::GameDetails {
         id: game.id,
-        name: game.name,
-        description: game.description,
-        short_description: game.short_description,
+        name,
+        description,
+        short_description,
+        language,
+        translations: translations.into_iter().map(Into::into).collect(),
         year: game.year,
-        publisher: game.publisher,
-        developer: game.developer,
         links: game.links,
         system: system.into(),
         system_unique_id: game.system_unique_id,
+        publishers: metadata.publishers.into_iter().map(Into::into).collect(),
+        developers: metadata.developers.into_iter().map(Into::into).collect(),
+        releases: metadata
+            .releases
+            .into_iter()
+            .map(|(region, date)| dto::games::GameRelease {
+                region: region.into(),
+                date: date.map(|d| d.format("%Y-%m-%d").to_string()),
+            })
+            .collect(),
+        languages: metadata.languages,
+        genres: metadata.genres.into_iter().map(Into::into).collect(),
+        series: metadata.series.into_iter().map(Into::into).collect(),
+        tags: metadata.tags.into_iter().map(Into::into).collect(),
+        min_players: game.min_players,
+        max_players: game.max_players,
+        relationships: relationships::relationships_into_dto(relationships)?,
+        related_games: relationships::relationships_into_dto(related_games)?,
+        dat,
     }))
 }
 
@@ -164,18 +523,41 @@ pub async fn games_update(
     game_id: u32,
     form: Json<dto::games::GameUpdateRequest<'_>>,
 ) -> Result<Json<dto::Ok>, (Status, String)> {
+    let form = form.into_inner();
+    let game = models::Game::get(&mut db, game_id as i32)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::NotFound, "Not found".to_string()))?;
+
+    validate_players(
+        form.min_players.or(game.min_players),
+        form.max_players.or(game.max_players),
+    )?;
+    let metadata = metadata_update(
+        &mut db,
+        companies("publisher", form.publishers, form.publisher)?,
+        companies("developer", form.developers, form.developer)?,
+        form.releases,
+        form.languages,
+        form.genres,
+        form.series,
+        form.tags,
+    )
+    .await?;
+
     models::Game::update(
         &mut db,
-        game_id as i32,
+        game.id,
         form.name,
         form.description,
         form.short_description,
         form.year,
-        form.publisher,
-        form.developer,
-        form.add_links.clone(),
-        form.remove_links.clone(),
+        form.add_links,
+        form.remove_links,
         form.system_unique_id,
+        form.min_players,
+        form.max_players,
+        metadata,
     )
     .await
     .map_err(|e| (Status::InternalServerError, e.to_string()))?;
@@ -183,6 +565,36 @@ pub async fn games_update(
     Ok(Json(dto::Ok))
 }
 
+/// Validate the name and directory of a file of a game. Directories are
+/// separated by `/`, and neither can escape the game.
+fn validate_artifact_layout(filename: &str, path: &str) -> Result<(), (Status, String)> {
+    if !filename.is_empty() && (filename.len() > 255 || !is_valid_path_segment(filename)) {
+        return Err((Status::BadRequest, format!("Invalid filename {filename:?}")));
+    }
+    if !path.is_empty() && (path.len() > 1024 || !path.split('/').all(is_valid_path_segment)) {
+        return Err((Status::BadRequest, format!("Invalid path {path:?}")));
+    }
+    Ok(())
+}
+
+/// List the files of a game, in order.
+#[openapi(tag = "Games", ignore = "db")]
+#[get("/games/<game_id>/artifacts")]
+pub async fn games_artifacts(
+    mut db: Db,
+    game_id: u32,
+) -> Result<Json<Vec<dto::games::GameArtifact>>, (Status, String)> {
+    let game = models::Game::get(&mut db, game_id as i32)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::NotFound, "Not found".to_string()))?;
+
+    models::GameArtifact::list(&mut db, game.id)
+        .await
+        .map(|a| Json(a.into_iter().map(|(ga, a)| ga.into_dto(a)).collect()))
+        .map_err(|e| (Status::InternalServerError, e.to_string()))
+}
+
 #[openapi(tag = "Games", ignore = "db")]
 #[post(
     "/games/<game_id>/artifacts",
@@ -200,11 +612,21 @@ pub async fn games_add_artifact(
         .map_err(|e| (Status::InternalServerError, e.to_string()))?
         .ok_or((Status::NotFound, "Not found".to_string()))?;
 
-    for a in form.into_inner() {
+    let form = form.into_inner();
+    for a in &form {
+        validate_artifact_layout(a.filename.unwrap_or_default(), a.path.unwrap_or_default())?;
+    }
+    let next_order = models::GameArtifact::next_order(&mut db, game.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    for (i, a) in form.into_iter().enumerate() {
+        let filename = a.filename.unwrap_or_default();
         let artifact = models::Artifact::create_with_checksum(
             &mut db,
-            "",
+            filename,
             a.mime_type,
+            a.crc32.as_ref().map(|s| s.as_slice()),
             a.md5.as_ref().map(|s| s.as_slice()),
             a.sha1.as_ref().map(|s| s.as_slice()),
             a.sha256.as_ref().map(|s| s.as_slice()),
@@ -214,14 +636,93 @@ pub async fn games_add_artifact(
         .await
         .map_err(|e: _| (Status::InternalServerError, e.to_string()))?;
 
-        models::GameArtifact::create(&mut db, game.id, artifact.id)
-            .await
-            .map_err(|e: _| (Status::InternalServerError, e.to_string()))?;
+        models::GameArtifact::create(
+            &mut db,
+            game.id,
+            artifact.id,
+            filename,
+            a.path.unwrap_or_default(),
+            a.role.unwrap_or_default(),
+            a.order.unwrap_or(next_order + i as i32),
+            a.status.unwrap_or_default(),
+        )
+        .await
+        .map_err(|e: _| (Status::InternalServerError, e.to_string()))?;
     }
 
     Ok(Json(dto::Ok))
 }
 
+/// The images with their roles and derivatives.
+async fn images_into_dto(
+    db: &mut Db,
+    images: Vec<models::GameImage>,
+) -> Result<Vec<dto::images::Image>, (Status, String)> {
+    let ids = images.iter().map(|i| i.id).collect::<Vec<_>>();
+    let mut roles = models::GameImage::roles(db, &ids)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    let mut derivatives = models::GameImage::derivatives(db, &ids)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(images
+        .into_iter()
+        .map(|i| dto::images::Image {
+            id: i.id,
+            name: i.image_name,
+            url: i.url,
+            mime_type: i.mime_type,
+            width: i.width,
+            height: i.height,
+            // Other tags of the image are not roles.
+            roles: roles
+                .remove(&i.id)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|r| r.parse().ok())
+                .collect(),
+            derivatives: derivatives
+                .remove(&i.id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        })
+        .collect())
+}
+
+/// Images of a game can be managed by the team owning the system of the
+/// game, or by the root team.
+async fn require_manage_game_images(
+    db: &mut Db,
+    admin: guards::users::AuthenticatedUserGuard,
+    config: &RetronomiconConfig,
+    game: &models::Game,
+) -> Result<(), (Status, String)> {
+    let system = models::System::from_id_or_slug(db, game.system_id.into()).await?;
+    let user = admin.into_model(db).await?;
+    let mut denied = None;
+    for team_id in [system.owner_team_id, config.root_team_id] {
+        let team = models::Team::from_id_or_slug(db, team_id.into()).await?;
+        let role = user
+            .role_in(db, team.id)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+        let check =
+            acls::check_team(db, &team, role.as_ref(), Permission::UploadGameImages).await?;
+        if check.allowed {
+            return Ok(());
+        }
+        // Explain a denial with the team of the system.
+        denied.get_or_insert(check);
+    }
+    if let Some(check) = denied {
+        acls::require(check)?;
+    }
+    Ok(())
+}
+
 #[openapi(tag = "Games", ignore = "db")]
 #[get("/games/<game_id>/images?<filter..>")]
 pub async fn games_images(
@@ -234,59 +735,48 @@ pub async fn games_images(
         .paging
         .validate()
         .map_err(|e| (Status::BadRequest, e))?;
-    let images = models::GameImage::list(&mut db, page, limit, game_id)
+    let role = filter.role.map(|r| r.to_string());
+    let images = models::GameImage::list(&mut db, page, limit, game_id, role.as_deref())
         .await
         .map_err(|e| (Status::InternalServerError, e.to_string()))?
         .into_iter()
-        .map(|(i, _)| {
-            Ok(dto::images::Image {
-                name: i.image_name,
-                url: i.url,
-                mime_type: i.mime_type,
-            })
-        })
-        .collect::<Result<Vec<_>, String>>()
-        .map_err(|e| (Status::InternalServerError, e))?;
+        .map(|(i, _)| i)
+        .collect();
 
-    Ok(Json(images))
+    images_into_dto(&mut db, images).await.map(Json)
 }
 
 /// Upload an image to a game. This can be done multiple times (as long
 /// as the filename is unique).
+/// Thumbnails and a WebP version of each image are generated. Images that
+/// look like an image the game already has are refused, unless duplicates
+/// are allowed.
 /// The upload will be refused if the user does not have permission to
 /// upload images to this game.
 #[openapi(tag = "Games", ignore = "config", ignore = "db", ignore = "storage")]
-#[post("/games/<game_id>/images", data = "<file>")]
+#[post("/games/<game_id>/images?<params..>", data = "<file>")]
+#[allow(clippy::too_many_arguments)]
 pub async fn games_images_upload(
     mut db: Db,
     admin: guards::users::AuthenticatedUserGuard,
     config: &State<RetronomiconConfig>,
     storage: guards::storage::Storage,
     game_id: i32,
+    params: dto::games::GameImageUploadQueryParams,
     content_type: &ContentType,
     file: Data<'_>,
 ) -> Result<Json<Vec<dto::images::Image>>, (Status, String)> {
-    // Check the uploader's role.
-    let (user, team, role) =
-        models::User::get_user_team_and_role(&mut db, admin.into(), config.root_team_id.into())
-            .await
-            .map_err(|e| (Status::InternalServerError, e.to_string()))?
-            .ok_or((Status::Unauthorized, "Not logged in".to_string()))?;
-
-    if !acls::can_upload_image(&user, &team, &role) {
-        return Err((Status::Forbidden, "Forbidden".to_string()));
-    }
-
     let game = models::Game::get(&mut db, game_id)
         .await
         .map_err(|e| (Status::InternalServerError, e.to_string()))?
         .ok_or((Status::NotFound, "Game not found".to_string()))?;
+    require_manage_game_images(&mut db, admin, config, &game).await?;
 
     let options = MultipartFormDataOptions::with_multipart_form_data_fields(vec![
-        MultipartFormDataField::file("file")
+        MultipartFormDataField::raw("file")
             .size_limit(2.mebibytes().as_u64())
             .repetition(Repetition::infinite()),
-        MultipartFormDataField::file("image")
+        MultipartFormDataField::raw("image")
             .size_limit(2.mebibytes().as_u64())
             .repetition(Repetition::infinite()),
     ]);
@@ -295,7 +785,7 @@ pub async fn games_images_upload(
         .map_err(|e| (Status::BadRequest, e.to_string()))?;
 
     let mut result = Vec::new();
-    for (_s, files) in multipart_form_data.files.iter() {
+    for files in multipart_form_data.raw.values() {
         for file in files {
             let filename = file
                 .file_name
@@ -317,6 +807,7 @@ pub async fn games_images_upload(
                 "image/png" => ImageFormat::Png,
                 "image/jpeg" => ImageFormat::Jpeg,
                 "image/gif" => ImageFormat::Gif,
+                "image/webp" => ImageFormat::WebP,
                 _ => {
                     return Err((
                         Status::BadRequest,
@@ -326,10 +817,10 @@ pub async fn games_images_upload(
             };
 
             // Validate the image.
-            let bytes = std::fs::read(&file.path)
-                .map_err(|e| (Status::InternalServerError, e.to_string()))?;
-            let image = image::load_from_memory_with_format(&bytes, image_format)
-                .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+            let bytes = &file.raw;
+            let (image, hash) = images::decode(bytes.clone(), image_format)
+                .await
+                .map_err(|e| (Status::InternalServerError, e))?;
 
             let (width, height) = image.dimensions();
             if width > MAX_IMAGE_WIDTH || height > MAX_IMAGE_HEIGHT {
@@ -342,13 +833,31 @@ pub async fn games_images_upload(
                 ));
             }
 
+            if !params.allow_duplicates {
+                let hashes = models::GameImage::hashes(&mut db, game_id)
+                    .await
+                    .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+                if let Some((name, _)) = hashes.iter().find(|(_, h)| {
+                    images::hash_distance(hash, *h) <= images::DUPLICATE_MAX_DISTANCE
+                }) {
+                    return Err((
+                        Status::Conflict,
+                        format!("Image {filename:?} is a duplicate of {name:?}"),
+                    ));
+                }
+            }
+            let derivatives =
+                images::generate_derivatives(image, config.image_thumbnail_sizes.clone())
+                    .await
+                    .map_err(|e| (Status::InternalServerError, e))?;
+
             let path = guards::storage::Paths::path_for_game_image(&game, &filename);
             let url = storage
-                .upload_game_asset(&path, &bytes, mimetype.essence_str())
+                .upload_game_asset(&path, bytes, mimetype.essence_str())
                 .await
                 .map_err(|e| (Status::InternalServerError, e.to_string()))?;
 
-            let _artifact = models::GameImage::create(
+            let game_image = models::GameImage::create(
                 &mut db,
                 game_id,
                 &filename,
@@ -356,17 +865,66 @@ pub async fn games_images_upload(
                 height as i32,
                 mimetype.essence_str(),
                 &url,
+                hash,
             )
             .await
             .map_err(|e| (Status::InternalServerError, e.to_string()))?;
 
-            result.push(dto::images::Image {
-                name: filename,
-                url,
-                mime_type: mimetype.essence_str().to_string(),
-            });
+            images::store_derivatives(&mut db, &storage, &game, &game_image, derivatives)
+                .await
+                .map_err(|e| (Status::InternalServerError, e))?;
+
+            if let Some(role) = params.role {
+                game_image
+                    .set_roles(&mut db, &[role.to_string()])
+                    .await
+                    .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+            }
+
+            result.push(game_image);
         }
     }
 
-    Ok(Json(result))
+    images_into_dto(&mut db, result).await.map(Json)
+}
+
+/// Set the roles of a game image (e.g. box front or title screen).
+#[openapi(tag = "Games", ignore = "config", ignore = "db")]
+#[put(
+    "/games/<game_id>/images/<image_id>/roles",
+    format = "application/json",
+    data = "<form>"
+)]
+pub async fn games_images_roles_update(
+    mut db: Db,
+    admin: guards::users::AuthenticatedUserGuard,
+    config: &State<RetronomiconConfig>,
+    game_id: u32,
+    image_id: u32,
+    form: Json<dto::images::ImageRolesUpdateRequest>,
+) -> Result<Json<dto::images::Image>, (Status, String)> {
+    let game = models::Game::get(&mut db, game_id as i32)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::NotFound, "Game not found".to_string()))?;
+    require_manage_game_images(&mut db, admin, config, &game).await?;
+
+    let image = models::GameImage::get(&mut db, game.id, image_id as i32)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::NotFound, "Image not found".to_string()))?;
+    let roles = form
+        .into_inner()
+        .roles
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    image
+        .set_roles(&mut db, &roles)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    images_into_dto(&mut db, vec![image])
+        .await
+        .map(|mut images| Json(images.remove(0)))
 }
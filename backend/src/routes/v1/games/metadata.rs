@@ -0,0 +1,59 @@
+use retronomicon_db::models;
+use retronomicon_db::Db;
+use retronomicon_dto as dto;
+use rocket::get;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket_okapi::openapi;
+
+/// List the companies publishing or developing games, by name.
+#[openapi(tag = "Games", ignore = "db")]
+#[get("/companies?<paging..>")]
+pub async fn companies_list(
+    mut db: Db,
+    paging: dto::params::PagingParams,
+) -> Result<Json<Vec<dto::games::CompanyRef>>, (Status, String)> {
+    let (page, limit) = paging.validate().map_err(|e| (Status::BadRequest, e))?;
+
+    models::Company::list(&mut db, page, limit)
+        .await
+        .map(|c| Json(c.into_iter().map(Into::into).collect()))
+        .map_err(|e| (Status::InternalServerError, e.to_string()))
+}
+
+/// List all genres of games.
+#[openapi(tag = "Games", ignore = "db")]
+#[get("/genres")]
+pub async fn genres_list(mut db: Db) -> Result<Json<Vec<dto::games::GenreRef>>, (Status, String)> {
+    models::Genre::list(&mut db)
+        .await
+        .map(|g| Json(g.into_iter().map(Into::into).collect()))
+        .map_err(|e| (Status::InternalServerError, e.to_string()))
+}
+
+/// List all regions games are released in.
+#[openapi(tag = "Games", ignore = "db")]
+#[get("/regions")]
+pub async fn regions_list(
+    mut db: Db,
+) -> Result<Json<Vec<dto::games::RegionRef>>, (Status, String)> {
+    models::Region::list(&mut db)
+        .await
+        .map(|r| Json(r.into_iter().map(Into::into).collect()))
+        .map_err(|e| (Status::InternalServerError, e.to_string()))
+}
+
+/// List the series of games, by name.
+#[openapi(tag = "Games", ignore = "db")]
+#[get("/series?<paging..>")]
+pub async fn series_list(
+    mut db: Db,
+    paging: dto::params::PagingParams,
+) -> Result<Json<Vec<dto::games::SeriesRef>>, (Status, String)> {
+    let (page, limit) = paging.validate().map_err(|e| (Status::BadRequest, e))?;
+
+    models::Series::list(&mut db, page, limit)
+        .await
+        .map(|s| Json(s.into_iter().map(Into::into).collect()))
+        .map_err(|e| (Status::InternalServerError, e.to_string()))
+}
@@ -0,0 +1,153 @@
+use crate::guards;
+use retronomicon_db::models;
+use retronomicon_db::Db;
+use retronomicon_dto as dto;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{delete, get, post};
+use rocket_okapi::openapi;
+
+/// The maximum number of relationships followed when traversing them.
+const MAX_DEPTH: u32 = 5;
+
+fn game_ref(game: models::Game, system: models::System) -> dto::games::GameRef {
+    dto::games::GameRef {
+        id: game.id,
+        name: game.name,
+        system: system.into(),
+    }
+}
+
+fn parse_kind(kind: &str) -> Result<dto::games::GameRelationshipKind, (Status, String)> {
+    kind.parse().map_err(|_| {
+        (
+            Status::InternalServerError,
+            format!("Unknown relationship kind {kind:?}"),
+        )
+    })
+}
+
+/// Relationships from the database, with their other game.
+pub(super) fn relationships_into_dto(
+    relationships: Vec<(String, models::Game, models::System)>,
+) -> Result<Vec<dto::games::GameRelationship>, (Status, String)> {
+    relationships
+        .into_iter()
+        .map(|(kind, game, system)| {
+            Ok(dto::games::GameRelationship {
+                kind: parse_kind(&kind)?,
+                game: game_ref(game, system),
+            })
+        })
+        .collect()
+}
+
+async fn game(db: &mut Db, game_id: u32) -> Result<models::Game, (Status, String)> {
+    models::Game::get(db, game_id as i32)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::NotFound, "Game not found".to_string()))
+}
+
+/// Add a relationship from a game to another, e.g. to record that the game
+/// is a hack of the other game. Adding an existing relationship does nothing.
+#[openapi(tag = "Games", ignore = "db")]
+#[post(
+    "/games/<game_id>/relationships",
+    format = "application/json",
+    data = "<form>"
+)]
+pub async fn games_relationships_create(
+    mut db: Db,
+    _root_user: guards::users::RootUserGuard,
+    game_id: u32,
+    form: Json<dto::games::GameRelationshipCreateRequest>,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let game = game(&mut db, game_id).await?;
+    if form.game == game.id {
+        return Err((
+            Status::BadRequest,
+            "A game cannot be related to itself".to_string(),
+        ));
+    }
+    let related = models::Game::get(&mut db, form.game)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::NotFound, "Related game not found".to_string()))?;
+
+    models::Game::add_relationship(&mut db, game.id, form.kind, related.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    Ok(Json(dto::Ok))
+}
+
+/// Remove a relationship from a game to another.
+#[openapi(tag = "Games", ignore = "db")]
+#[delete("/games/<game_id>/relationships/<kind>/<related_game_id>")]
+pub async fn games_relationships_delete(
+    mut db: Db,
+    _root_user: guards::users::RootUserGuard,
+    game_id: u32,
+    kind: &str,
+    related_game_id: u32,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let kind = kind
+        .parse()
+        .map_err(|_| (Status::BadRequest, format!("Invalid kind {kind:?}")))?;
+    let game = game(&mut db, game_id).await?;
+
+    if models::Game::remove_relationship(&mut db, game.id, kind, related_game_id as i32)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        Ok(Json(dto::Ok))
+    } else {
+        Err((Status::NotFound, "Relationship not found".to_string()))
+    }
+}
+
+/// Traverse the relationships of a game, e.g. to find all the revisions
+/// and hacks of a game, or the games of a compilation.
+#[openapi(tag = "Games", ignore = "db")]
+#[get("/games/<game_id>/related?<params..>")]
+pub async fn games_related(
+    mut db: Db,
+    game_id: u32,
+    params: dto::games::GameRelatedQueryParams,
+) -> Result<Json<Vec<dto::games::GameRelatedItem>>, (Status, String)> {
+    let depth = params.depth.unwrap_or(1);
+    if !(1..=MAX_DEPTH).contains(&depth) {
+        return Err((
+            Status::BadRequest,
+            format!("Depth must be between 1 and {MAX_DEPTH}"),
+        ));
+    }
+    let game = game(&mut db, game_id).await?;
+
+    let related = models::Game::traverse_relationships(
+        &mut db,
+        game.id,
+        params.kind,
+        params.direction.unwrap_or_default(),
+        depth as i32,
+    )
+    .await
+    .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    related
+        .into_iter()
+        .map(|(r, game, system)| {
+            Ok(dto::games::GameRelatedItem {
+                kind: parse_kind(&r.kind)?,
+                direction: if r.incoming {
+                    dto::games::GameRelationshipDirection::Incoming
+                } else {
+                    dto::games::GameRelationshipDirection::Outgoing
+                },
+                depth: r.depth,
+                game: game_ref(game, system),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Json)
+}
@@ -0,0 +1,101 @@
+use crate::guards;
+use retronomicon_db::models;
+use retronomicon_db::Db;
+use retronomicon_dto as dto;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{delete, get, put};
+use rocket_okapi::openapi;
+
+/// The canonical form of a language tag from a request.
+fn language_tag(language: &str) -> Result<String, (Status, String)> {
+    if dto::games::is_valid_language_tag(language) {
+        Ok(dto::games::canonical_language_tag(language))
+    } else {
+        Err((
+            Status::BadRequest,
+            format!("Invalid language tag {language:?}"),
+        ))
+    }
+}
+
+async fn game(db: &mut Db, game_id: u32) -> Result<models::Game, (Status, String)> {
+    models::Game::get(db, game_id as i32)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::NotFound, "Not found".to_string()))
+}
+
+/// List the translations of the name and descriptions of a game.
+#[openapi(tag = "Games", ignore = "db")]
+#[get("/games/<game_id>/translations")]
+pub async fn games_translations(
+    mut db: Db,
+    game_id: u32,
+) -> Result<Json<Vec<dto::games::GameTranslation>>, (Status, String)> {
+    let game = game(&mut db, game_id).await?;
+
+    models::GameTranslation::list(&mut db, game.id)
+        .await
+        .map(|t| Json(t.into_iter().map(Into::into).collect()))
+        .map_err(|e| (Status::InternalServerError, e.to_string()))
+}
+
+/// Add or replace the translation of a game in a language.
+#[openapi(tag = "Games", ignore = "db")]
+#[put(
+    "/games/<game_id>/translations/<language>",
+    format = "application/json",
+    data = "<form>"
+)]
+pub async fn games_translations_update(
+    mut db: Db,
+    _root_user: guards::users::RootUserGuard,
+    game_id: u32,
+    language: &str,
+    form: Json<dto::games::GameTranslationRequest<'_>>,
+) -> Result<Json<dto::games::GameTranslation>, (Status, String)> {
+    let language = language_tag(language)?;
+    let name = form.name.trim();
+    if name.is_empty() {
+        return Err((Status::BadRequest, "Name cannot be empty".to_string()));
+    }
+    let game = game(&mut db, game_id).await?;
+
+    models::GameTranslation::upsert(
+        &mut db,
+        game.id,
+        &language,
+        name,
+        form.short_description,
+        form.description,
+    )
+    .await
+    .map(|t| Json(t.into()))
+    .map_err(|e| (Status::InternalServerError, e.to_string()))
+}
+
+/// Delete the translation of a game in a language.
+#[openapi(tag = "Games", ignore = "db")]
+#[delete("/games/<game_id>/translations/<language>")]
+pub async fn games_translations_delete(
+    mut db: Db,
+    _root_user: guards::users::RootUserGuard,
+    game_id: u32,
+    language: &str,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let language = language_tag(language)?;
+    let game = game(&mut db, game_id).await?;
+
+    if models::GameTranslation::delete(&mut db, game.id, &language)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        Ok(Json(dto::Ok))
+    } else {
+        Err((
+            Status::NotFound,
+            "No translation in this language".to_string(),
+        ))
+    }
+}
@@ -1,10 +1,14 @@
-use crate::fairings::config::JwtKeys;
-use crate::guards::users::UserGuard;
+use crate::fairings::config::{DbPepper, JwtKeys, RetronomiconConfig};
+use crate::guards::emailer::EmailGuard;
+use crate::guards::users::{ClientInfo, UserGuard};
+use crate::utils::two_factor;
+use retronomicon_db::models;
+use retronomicon_db::types::FetchModel;
 use retronomicon_db::Db;
 use retronomicon_dto as dto;
 use rocket::http::{CookieJar, Status};
 use rocket::serde::json::Json;
-use rocket::{get, post, put, State};
+use rocket::{delete, get, post, put, State};
 use rocket_okapi::openapi;
 
 #[openapi(tag = "Users", ignore = "db")]
@@ -40,6 +44,268 @@ pub async fn me(db: Db, user: UserGuard) -> Result<Json<dto::user::UserDetails>,
     crate::routes::v1::users::users_details(db, user, id.into()).await
 }
 
+/// Delete the account of the current user. The user must confirm with their
+/// username (or email), their password if they have one, and a two-factor
+/// code if they enabled it. Teams the user is the only owner of must be
+/// given to another member first, or in `transfer_ownership`.
+///
+/// The releases and uploads of the user are kept, but everything identifying
+/// them is removed.
+#[openapi(tag = "Users", ignore = "db")]
+#[delete("/me", format = "application/json", data = "<form>")]
+pub async fn me_delete(
+    mut db: Db,
+    cookies: &CookieJar<'_>,
+    pepper: &State<DbPepper>,
+    user: UserGuard,
+    form: Json<dto::user::AccountDeleteRequest<'_>>,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let db = &mut db;
+    let form = form.into_inner();
+    let model = user.clone().into_model(db).await?;
+
+    if form.confirm != model.username.as_deref().unwrap_or(&model.email) {
+        return Err((Status::BadRequest, "Invalid confirmation".to_string()));
+    }
+
+    let has_password = models::UserPassword::from_user(db, &model)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .is_some_and(|p| !p.password.is_empty());
+    if has_password {
+        let password = form
+            .password
+            .ok_or((Status::BadRequest, "Password is required".to_string()))?;
+        models::UserPassword::verify_password(db, model.clone(), password, &pepper.inner().0)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+            .ok_or((Status::Forbidden, "Invalid password".to_string()))?;
+    }
+
+    if let Some(user_two_factor) = models::UserTwoFactor::from_user_id(db, model.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .filter(|t| t.is_confirmed())
+    {
+        let code = form.code.ok_or((
+            Status::BadRequest,
+            "Two-factor authentication code is required".to_string(),
+        ))?;
+        two_factor::verify(db, &user_two_factor, code).await?;
+    }
+
+    let mut new_owners = Vec::new();
+    let mut orphaned = Vec::new();
+    for team in model
+        .sole_owned_teams(db)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        let Some(transfer) = form
+            .transfer_ownership
+            .iter()
+            .find(|t| t.team_id == team.id)
+        else {
+            orphaned.push(team.slug);
+            continue;
+        };
+
+        let new_owner = models::User::from_id(db, transfer.user_id)
+            .await
+            .map_err(|e| (Status::BadRequest, e.to_string()))?;
+        if new_owner.id == model.id
+            || new_owner
+                .role_in(db, team.id)
+                .await
+                .map_err(|e| (Status::InternalServerError, e.to_string()))?
+                .is_none()
+        {
+            return Err((
+                Status::BadRequest,
+                format!("The new owner of {} must be a member", team.slug),
+            ));
+        }
+        new_owners.push((team.id, new_owner.id));
+    }
+    if !orphaned.is_empty() {
+        return Err((
+            Status::Conflict,
+            format!(
+                "Transfer the ownership of these teams first: {}",
+                orphaned.join(", ")
+            ),
+        ));
+    }
+
+    model
+        .anonymize(db, &new_owners)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    user.remove_cookie(cookies);
+
+    Ok(Json(dto::Ok))
+}
+
+/// Download everything stored about the current user, as JSON.
+#[openapi(tag = "Users", ignore = "db")]
+#[get("/me/export")]
+pub async fn me_export(
+    mut db: Db,
+    user: UserGuard,
+) -> Result<Json<dto::user::UserExport>, (Status, String)> {
+    let model = user.into_model(&mut db).await?;
+    model
+        .export(&mut db)
+        .await
+        .map(Json)
+        .map_err(|e| (Status::InternalServerError, e.to_string()))
+}
+
+/// Change the password of the current user. The current password must be
+/// provided, unless the user has none yet (e.g. they only logged in through
+/// an OAuth provider), in which case this sets it. All other sessions and JWTs
+/// of the user are logged out; personal access tokens are kept.
+#[openapi(tag = "Authentication", ignore = "db")]
+#[put("/me/password", format = "application/json", data = "<form>")]
+pub async fn me_password(
+    mut db: Db,
+    cookies: &CookieJar<'_>,
+    client: ClientInfo,
+    pepper: &State<DbPepper>,
+    user: UserGuard,
+    form: Json<dto::auth::PasswordChangeRequest<'_>>,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let form = form.into_inner();
+    if form.new_password.is_empty() {
+        return Err((Status::BadRequest, "Password cannot be empty".to_string()));
+    }
+
+    let pepper = &pepper.inner().0;
+    let model = user.clone().into_model(&mut db).await?;
+    let has_password = models::UserPassword::from_user(&mut db, &model)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .is_some_and(|p| !p.password.is_empty());
+
+    if has_password {
+        let current_password = form.current_password.ok_or((
+            Status::BadRequest,
+            "Current password is required".to_string(),
+        ))?;
+        models::UserPassword::verify_password(&mut db, model.clone(), current_password, pepper)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+            .ok_or((Status::Forbidden, "Invalid current password".to_string()))?;
+    }
+
+    models::UserPassword::set(&mut db, &model, form.new_password, pepper)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    model
+        .invalidate_sessions(&mut db)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    // Keep the current session logged in.
+    UserGuard::create_session(&mut db, cookies, &client, model).await?;
+
+    Ok(Json(dto::Ok))
+}
+
+/// The number of hours an email change link is valid for.
+const EMAIL_CHANGE_EXPIRY_HOURS: i64 = 24;
+
+/// Change the email of the current user. A link to confirm the change is sent
+/// to the new email and a notice to the current one; the email only changes
+/// once the link is followed. Asking for another change replaces the previous
+/// link.
+#[openapi(tag = "Users", ignore = "db", ignore = "emailer")]
+#[post("/me/email", format = "application/json", data = "<form>")]
+pub async fn me_email(
+    mut db: Db,
+    config: &State<RetronomiconConfig>,
+    pepper: &State<DbPepper>,
+    emailer: EmailGuard,
+    user: UserGuard,
+    form: Json<dto::auth::EmailChangeRequest<'_>>,
+) -> Result<Json<dto::auth::EmailChangeResponse>, (Status, String)> {
+    let db = &mut db;
+    let config = config.inner();
+    let form = form.into_inner();
+    let model = user.into_model(db).await?;
+
+    let new_email = form
+        .email
+        .parse::<lettre::Address>()
+        .map_err(|e| (Status::BadRequest, e.to_string()))?
+        .to_string();
+    if new_email == model.email {
+        return Err((Status::BadRequest, "Email is unchanged".to_string()));
+    }
+
+    // Users who logged in with GitHub, Google or Patreon are found by the
+    // email of that provider.
+    if model
+        .auth_provider
+        .as_deref()
+        .is_some_and(|p| !p.starts_with("oidc:"))
+    {
+        return Err((
+            Status::Conflict,
+            "The email of this account is managed by its login provider".to_string(),
+        ));
+    }
+
+    let has_password = models::UserPassword::from_user(db, &model)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .is_some_and(|p| !p.password.is_empty());
+    if has_password {
+        let password = form
+            .password
+            .ok_or((Status::BadRequest, "Password is required".to_string()))?;
+        models::UserPassword::verify_password(db, model.clone(), password, &pepper.inner().0)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+            .ok_or((Status::Forbidden, "Invalid password".to_string()))?;
+    }
+
+    if models::User::get_by_email(db, &new_email)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .is_some()
+    {
+        return Err((Status::Conflict, "Email already in use".to_string()));
+    }
+
+    let expires_at =
+        chrono::Utc::now().naive_utc() + chrono::Duration::hours(EMAIL_CHANGE_EXPIRY_HOURS);
+    let token = models::EmailChange::create(db, &model, &new_email, expires_at)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    emailer.send_email_change_notice(&model.email, &new_email)?;
+
+    if config.bypass_email_validation(&new_email) {
+        crate::routes::v1::auth::confirm_email_change(db, config, &token).await?;
+        return Ok(Json(dto::auth::EmailChangeResponse {
+            email: new_email,
+            pending: false,
+        }));
+    }
+
+    let mut url = url::Url::parse(&config.base_url)
+        .and_then(|base| base.join("confirm-email"))
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    url.query_pairs_mut().append_pair("token", &token);
+    emailer.send_email_change(&new_email, url.as_str(), EMAIL_CHANGE_EXPIRY_HOURS)?;
+
+    Ok(Json(dto::auth::EmailChangeResponse {
+        email: new_email,
+        pending: true,
+    }))
+}
+
 /// Create a JWT token for the current logged-in user.
 #[openapi(tag = "Authentication")]
 #[post("/me/token")]
@@ -51,3 +317,382 @@ pub async fn me_token(
         .map(|token| Json(dto::AuthTokenResponse { token }))
         .map_err(|e| (Status::Unauthorized, e.to_string()))
 }
+
+/// The number of days a personal access token is valid for by default.
+const DEFAULT_TOKEN_EXPIRY_DAYS: u32 = 30;
+
+/// The maximum number of days a personal access token can be valid for.
+const MAX_TOKEN_EXPIRY_DAYS: u32 = 365;
+
+/// List the personal access tokens of the current user.
+#[openapi(tag = "Authentication", ignore = "db")]
+#[get("/me/tokens")]
+pub async fn me_tokens(
+    mut db: Db,
+    user: UserGuard,
+) -> Result<Json<Vec<dto::tokens::UserToken>>, (Status, String)> {
+    models::UserToken::list(&mut db, user.id)
+        .await
+        .map(|t| Json(t.into_iter().map(Into::into).collect()))
+        .map_err(|e| (Status::InternalServerError, e.to_string()))
+}
+
+/// Create a personal access token with a list of scopes. The token is only
+/// returned once.
+#[openapi(tag = "Authentication", ignore = "db")]
+#[post("/me/tokens", format = "json", data = "<form>")]
+pub async fn me_tokens_create(
+    mut db: Db,
+    user: UserGuard,
+    form: Json<dto::tokens::UserTokenCreateRequest<'_>>,
+) -> Result<Json<dto::tokens::UserTokenCreateResponse>, (Status, String)> {
+    let db = &mut db;
+    let dto::tokens::UserTokenCreateRequest {
+        name,
+        scopes,
+        expires_in_days,
+    } = form.into_inner();
+
+    if name.trim().is_empty() {
+        return Err((Status::BadRequest, "Token name is required".to_string()));
+    }
+    if scopes.is_empty() {
+        return Err((
+            Status::BadRequest,
+            "At least one scope is required".to_string(),
+        ));
+    }
+    let expires_in_days = expires_in_days.unwrap_or(DEFAULT_TOKEN_EXPIRY_DAYS);
+    if expires_in_days == 0 || expires_in_days > MAX_TOKEN_EXPIRY_DAYS {
+        return Err((
+            Status::BadRequest,
+            format!("Tokens must expire within 1 to {MAX_TOKEN_EXPIRY_DAYS} days"),
+        ));
+    }
+
+    let mut resolved = Vec::with_capacity(scopes.len());
+    for scope in scopes {
+        let team = match scope.team {
+            Some(team) => Some(models::Team::from_id_or_slug(db, team).await?.id),
+            None => None,
+        };
+        let core = match scope.core {
+            Some(core) => Some(models::Core::from_id_or_slug(db, core).await?.id),
+            None => None,
+        };
+        resolved.push(dto::tokens::TokenScope {
+            permission: scope.permission,
+            team,
+            core,
+        });
+    }
+
+    let expires_at =
+        chrono::Utc::now().naive_utc() + chrono::Duration::days(i64::from(expires_in_days));
+    let (token, hash) = models::UserToken::generate();
+    let info = models::UserToken::create(db, user.id, name.trim(), &hash, &resolved, expires_at)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(dto::tokens::UserTokenCreateResponse {
+        info: info.into(),
+        token,
+    }))
+}
+
+/// Revoke a personal access token of the current user.
+#[openapi(tag = "Authentication", ignore = "db")]
+#[delete("/me/tokens/<token_id>")]
+pub async fn me_tokens_revoke(
+    mut db: Db,
+    user: UserGuard,
+    token_id: i32,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    if models::UserToken::delete(&mut db, user.id, token_id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        Ok(Json(dto::Ok))
+    } else {
+        Err((Status::NotFound, "Token not found".to_string()))
+    }
+}
+
+/// List the sessions of the current user, most recently used first.
+#[openapi(tag = "Authentication", ignore = "db")]
+#[get("/me/sessions")]
+pub async fn me_sessions(
+    mut db: Db,
+    user: UserGuard,
+) -> Result<Json<Vec<dto::auth::UserSession>>, (Status, String)> {
+    let sessions = models::UserSession::list(&mut db, user.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(
+        sessions
+            .into_iter()
+            .map(|session| {
+                let current = Some(session.id) == user.session_id;
+                dto::auth::UserSession {
+                    current,
+                    ..session.into()
+                }
+            })
+            .collect(),
+    ))
+}
+
+/// Revoke all sessions of the current user, except the one making the
+/// request.
+#[openapi(tag = "Authentication", ignore = "db")]
+#[delete("/me/sessions")]
+pub async fn me_sessions_revoke_all(
+    mut db: Db,
+    user: UserGuard,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    models::UserSession::delete_all(&mut db, user.id, user.session_id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    Ok(Json(dto::Ok))
+}
+
+/// Revoke a session of the current user. Revoking the current session logs
+/// the user out.
+#[openapi(tag = "Authentication", ignore = "db")]
+#[delete("/me/sessions/<session_id>")]
+pub async fn me_sessions_revoke(
+    mut db: Db,
+    cookies: &CookieJar<'_>,
+    user: UserGuard,
+    session_id: i32,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    if !models::UserSession::delete(&mut db, user.id, session_id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        return Err((Status::NotFound, "Session not found".to_string()));
+    }
+
+    if user.session_id == Some(session_id) {
+        user.remove_cookie(cookies);
+    }
+    Ok(Json(dto::Ok))
+}
+
+/// List the pending team invitations of the current user.
+#[openapi(tag = "Teams", ignore = "db")]
+#[get("/me/invitations")]
+pub async fn me_invitations(
+    mut db: Db,
+    user: UserGuard,
+) -> Result<Json<Vec<dto::user::UserInvitation>>, (Status, String)> {
+    let model = user.into_model(&mut db).await?;
+    model
+        .invitations(&mut db)
+        .await
+        .map(Json)
+        .map_err(|e| (Status::InternalServerError, e.to_string()))
+}
+
+/// List the OpenID Connect accounts linked to the current user.
+#[openapi(tag = "Authentication", ignore = "db")]
+#[get("/me/identities")]
+pub async fn me_identities(
+    mut db: Db,
+    user: UserGuard,
+) -> Result<Json<Vec<dto::auth::UserIdentity>>, (Status, String)> {
+    models::UserIdentity::list(&mut db, user.id)
+        .await
+        .map(|identities| Json(identities.into_iter().map(Into::into).collect()))
+        .map_err(|e| (Status::InternalServerError, e.to_string()))
+}
+
+/// Unlink an OpenID Connect account from the current user. The last way a
+/// user can log in cannot be unlinked.
+#[openapi(tag = "Authentication", ignore = "db")]
+#[delete("/me/identities/<identity_id>")]
+pub async fn me_identities_unlink(
+    mut db: Db,
+    user: UserGuard,
+    identity_id: i32,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let db = &mut db;
+    let model = user.into_model(db).await?;
+    let identities = models::UserIdentity::list(db, model.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    if !identities.iter().any(|i| i.id == identity_id) {
+        return Err((Status::NotFound, "Identity not found".to_string()));
+    }
+
+    let has_password = models::UserPassword::from_user(db, &model)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .is_some_and(|p| !p.password.is_empty());
+    let has_oauth = model
+        .auth_provider
+        .as_deref()
+        .is_some_and(|p| !p.starts_with("oidc:"));
+    if !has_password && !has_oauth && identities.len() <= 1 {
+        return Err((
+            Status::Conflict,
+            "Cannot unlink the last way to log in, set a password first".to_string(),
+        ));
+    }
+
+    models::UserIdentity::delete(db, model.id, identity_id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    Ok(Json(dto::Ok))
+}
+
+/// Whether the current user enabled two-factor authentication.
+#[openapi(tag = "Authentication", ignore = "db")]
+#[get("/me/two-factor")]
+pub async fn me_two_factor(
+    mut db: Db,
+    user: UserGuard,
+) -> Result<Json<dto::auth::TwoFactorStatus>, (Status, String)> {
+    let db = &mut db;
+    let enabled = models::UserTwoFactor::is_enabled(db, user.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    let recovery_codes_left = models::UserRecoveryCode::count_unused(db, user.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(dto::auth::TwoFactorStatus {
+        enabled,
+        recovery_codes_left,
+    }))
+}
+
+/// Start enrolling the current user in two-factor authentication. This
+/// returns a new secret to add to an authenticator app; it is only enabled
+/// once a code is confirmed with `/me/two-factor/confirm`.
+#[openapi(tag = "Authentication", ignore = "db")]
+#[post("/me/two-factor")]
+pub async fn me_two_factor_enroll(
+    mut db: Db,
+    user: UserGuard,
+) -> Result<Json<dto::auth::TwoFactorEnrollResponse>, (Status, String)> {
+    let db = &mut db;
+    if models::UserTwoFactor::is_enabled(db, user.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        return Err((
+            Status::Conflict,
+            "Two-factor authentication is already enabled".to_string(),
+        ));
+    }
+
+    let model = user.into_model(db).await?;
+    let secret = two_factor::generate_secret();
+    models::UserTwoFactor::create(db, model.id, &secret)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(dto::auth::TwoFactorEnrollResponse {
+        secret: two_factor::secret_base32(&secret)?,
+        otpauth_uri: two_factor::otpauth_uri(&secret, &model.email)?,
+    }))
+}
+
+/// Confirm the enrolment in two-factor authentication with a code from the
+/// authenticator app. This enables it and returns the recovery codes. All
+/// other sessions and JWTs of the user are logged out.
+#[openapi(tag = "Authentication", ignore = "db")]
+#[post("/me/two-factor/confirm", format = "application/json", data = "<form>")]
+pub async fn me_two_factor_confirm(
+    mut db: Db,
+    cookies: &CookieJar<'_>,
+    client: ClientInfo,
+    user: UserGuard,
+    form: Json<dto::auth::TwoFactorCodeRequest<'_>>,
+) -> Result<Json<dto::auth::TwoFactorRecoveryCodes>, (Status, String)> {
+    let db = &mut db;
+    let user_two_factor = models::UserTwoFactor::from_user_id(db, user.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::NotFound, "No pending enrolment".to_string()))?;
+    if user_two_factor.is_confirmed() {
+        return Err((
+            Status::Conflict,
+            "Two-factor authentication is already enabled".to_string(),
+        ));
+    }
+
+    let step = two_factor::verify_totp(db, &user_two_factor, form.code).await?;
+    let codes = user_two_factor
+        .confirm(db, step)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    let model = user.into_model(db).await?;
+    model
+        .invalidate_sessions(db)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    UserGuard::create_session(db, cookies, &client, model).await?;
+
+    Ok(Json(dto::auth::TwoFactorRecoveryCodes { codes }))
+}
+
+/// Disable two-factor authentication for the current user. This requires a
+/// code from the authenticator app or a recovery code.
+#[openapi(tag = "Authentication", ignore = "db")]
+#[delete("/me/two-factor", format = "application/json", data = "<form>")]
+pub async fn me_two_factor_disable(
+    mut db: Db,
+    user: UserGuard,
+    form: Json<dto::auth::TwoFactorCodeRequest<'_>>,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let db = &mut db;
+    let user_two_factor = enabled_two_factor(db, user.id).await?;
+    two_factor::verify(db, &user_two_factor, form.code).await?;
+
+    user_two_factor
+        .delete(db)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    Ok(Json(dto::Ok))
+}
+
+/// Replace the recovery codes of the current user. This requires a code
+/// from the authenticator app or a recovery code.
+#[openapi(tag = "Authentication", ignore = "db")]
+#[post(
+    "/me/two-factor/recovery-codes",
+    format = "application/json",
+    data = "<form>"
+)]
+pub async fn me_two_factor_recovery_codes(
+    mut db: Db,
+    user: UserGuard,
+    form: Json<dto::auth::TwoFactorCodeRequest<'_>>,
+) -> Result<Json<dto::auth::TwoFactorRecoveryCodes>, (Status, String)> {
+    let db = &mut db;
+    let user_two_factor = enabled_two_factor(db, user.id).await?;
+    two_factor::verify(db, &user_two_factor, form.code).await?;
+
+    let codes = models::UserRecoveryCode::regenerate(db, user.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    Ok(Json(dto::auth::TwoFactorRecoveryCodes { codes }))
+}
+
+async fn enabled_two_factor(
+    db: &mut Db,
+    user_id: i32,
+) -> Result<models::UserTwoFactor, (Status, String)> {
+    models::UserTwoFactor::from_user_id(db, user_id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .filter(|t| t.is_confirmed())
+        .ok_or((
+            Status::NotFound,
+            "Two-factor authentication is not enabled".to_string(),
+        ))
+}
@@ -0,0 +1,81 @@
+use crate::guards::users::UserGuard;
+use retronomicon_db::models;
+use retronomicon_db::Db;
+use retronomicon_dto as dto;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{get, post, put};
+use rocket_okapi::openapi;
+
+/// List the notifications of the current user, most recent first.
+#[openapi(tag = "Notifications", ignore = "db")]
+#[get("/me/notifications?<filter..>")]
+pub async fn me_notifications(
+    mut db: Db,
+    user: UserGuard,
+    filter: dto::notifications::NotificationFilterParams,
+) -> Result<Json<Vec<dto::notifications::Notification>>, (Status, String)> {
+    let (page, limit) = filter
+        .paging()
+        .validate()
+        .map_err(|e| (Status::BadRequest, e))?;
+
+    models::Notification::list(
+        &mut db,
+        user.id,
+        filter.unread.unwrap_or(false),
+        page,
+        limit,
+    )
+    .await
+    .map(|n| Json(n.into_iter().filter_map(|n| n.into_dto()).collect()))
+    .map_err(|e| (Status::InternalServerError, e.to_string()))
+}
+
+/// Mark notifications of the current user as read.
+#[openapi(tag = "Notifications", ignore = "db")]
+#[post("/me/notifications/read", format = "application/json", data = "<form>")]
+pub async fn me_notifications_read(
+    mut db: Db,
+    user: UserGuard,
+    form: Json<dto::notifications::NotificationReadRequest>,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let form = form.into_inner();
+    models::Notification::mark_read(&mut db, user.id, form.ids.as_deref())
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    Ok(Json(dto::Ok))
+}
+
+/// The channels the current user is notified through, for every kind of
+/// notification.
+#[openapi(tag = "Notifications", ignore = "db")]
+#[get("/me/notification-preferences")]
+pub async fn me_notification_preferences(
+    mut db: Db,
+    user: UserGuard,
+) -> Result<Json<Vec<dto::notifications::NotificationPreference>>, (Status, String)> {
+    models::NotificationPreference::list(&mut db, user.id)
+        .await
+        .map(Json)
+        .map_err(|e| (Status::InternalServerError, e.to_string()))
+}
+
+/// Update the channels the current user is notified through. Kinds that are
+/// not listed are left unchanged. Returns all the preferences of the user.
+#[openapi(tag = "Notifications", ignore = "db")]
+#[put(
+    "/me/notification-preferences",
+    format = "application/json",
+    data = "<form>"
+)]
+pub async fn me_notification_preferences_update(
+    mut db: Db,
+    user: UserGuard,
+    form: Json<Vec<dto::notifications::NotificationPreference>>,
+) -> Result<Json<Vec<dto::notifications::NotificationPreference>>, (Status, String)> {
+    models::NotificationPreference::set(&mut db, user.id, &form.into_inner())
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    me_notification_preferences(db, user).await
+}
@@ -0,0 +1,268 @@
+use crate::fairings::config::RetronomiconConfig;
+use crate::guards::users::AuthenticatedUserGuard;
+use crate::utils::acls;
+use retronomicon_db::models;
+use retronomicon_db::types::FetchModel;
+use retronomicon_db::Db;
+use retronomicon_dto as dto;
+use retronomicon_dto::permissions::Permission;
+use retronomicon_dto::reexports::strum::IntoEnumIterator;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{delete, get, put, State};
+use rocket_okapi::openapi;
+
+/// The permissions every role of a team has.
+async fn role_permissions(
+    db: &mut Db,
+    team: &models::Team,
+) -> Result<Vec<dto::permissions::TeamRolePermissions>, (Status, String)> {
+    let changed = models::TeamPermission::list(db, team.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok([
+        models::UserTeamRole::Owner,
+        models::UserTeamRole::Admin,
+        models::UserTeamRole::Member,
+    ]
+    .into_iter()
+    .map(|role| dto::permissions::TeamRolePermissions {
+        role: role.into(),
+        permissions: Permission::iter()
+            .filter(|p| p.is_team_permission())
+            .filter(|p| {
+                role == models::UserTeamRole::Owner
+                    || changed
+                        .iter()
+                        .find(|(r, c, _)| *r == role && c == p)
+                        .map_or_else(
+                            || acls::default_permission(team, &role, *p),
+                            |(_, _, granted)| *granted,
+                        )
+            })
+            .collect(),
+    })
+    .collect())
+}
+
+/// List the permissions of every role of a team. Only members of the team
+/// can see them.
+#[openapi(tag = "Permissions", ignore = "db")]
+#[get("/teams/<team_id>/permissions")]
+pub async fn teams_permissions(
+    mut db: Db,
+    user: AuthenticatedUserGuard,
+    team_id: dto::types::IdOrSlug<'_>,
+) -> Result<Json<Vec<dto::permissions::TeamRolePermissions>>, (Status, String)> {
+    let db = &mut db;
+    let (_user, team, _role) = models::User::get_user_team_and_role(db, user.id.into(), team_id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::NotFound, "Not found".to_string()))?;
+
+    role_permissions(db, &team).await.map(Json)
+}
+
+/// Grant or revoke permissions to the admins and members of a team. Owners
+/// always have every permission. Only owners can change permissions.
+#[openapi(tag = "Permissions", ignore = "db")]
+#[put("/teams/<team_id>/permissions", format = "json", data = "<form>")]
+pub async fn teams_permissions_update(
+    mut db: Db,
+    user: AuthenticatedUserGuard,
+    team_id: dto::types::IdOrSlug<'_>,
+    form: Json<Vec<dto::permissions::TeamPermissionUpdate>>,
+) -> Result<Json<Vec<dto::permissions::TeamRolePermissions>>, (Status, String)> {
+    let db = &mut db;
+    let (_user, team, role) = models::User::get_user_team_and_role(db, user.id.into(), team_id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::NotFound, "Not found".to_string()))?;
+
+    if !acls::can_manage_team_permissions(&role) {
+        return Err((
+            Status::Forbidden,
+            "Only owners can change the permissions of a team".to_string(),
+        ));
+    }
+
+    let updates = form.into_inner();
+    for update in &updates {
+        if update.role == dto::types::UserTeamRole::Owner {
+            return Err((
+                Status::BadRequest,
+                "Owners always have every permission".to_string(),
+            ));
+        }
+        if !update.permission.is_team_permission() {
+            return Err((
+                Status::BadRequest,
+                format!("Teams do not give the {} permission", update.permission),
+            ));
+        }
+    }
+
+    models::TeamPermission::update(db, team.id, &updates)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    role_permissions(db, &team).await.map(Json)
+}
+
+/// List the collaborators of a core. Requires the `manage_members`
+/// permission in the team owning the core.
+#[openapi(tag = "Permissions", ignore = "db")]
+#[get("/cores/<core_id>/collaborators")]
+pub async fn cores_collaborators(
+    mut db: Db,
+    user: AuthenticatedUserGuard,
+    core_id: dto::types::IdOrSlug<'_>,
+) -> Result<Json<Vec<dto::permissions::CoreCollaborator>>, (Status, String)> {
+    let db = &mut db;
+    let core = models::Core::from_id_or_slug(db, core_id).await?;
+    let (_user, team, role) = acls::core_access(db, user.id, &core).await?;
+    acls::require(acls::check_team(db, &team, role.as_ref(), Permission::ManageMembers).await?)?;
+
+    models::CoreCollaborator::list(db, core.id)
+        .await
+        .map(Json)
+        .map_err(|e| (Status::InternalServerError, e.to_string()))
+}
+
+/// Set the permissions of a user on a core, replacing the ones they had.
+/// Only core permissions (releasing, yanking and signing) can be granted.
+/// Requires the `manage_members` permission in the team owning the core.
+#[openapi(tag = "Permissions", ignore = "db")]
+#[put(
+    "/cores/<core_id>/collaborators/<user_id>",
+    format = "json",
+    data = "<form>"
+)]
+pub async fn cores_collaborators_update(
+    mut db: Db,
+    admin: AuthenticatedUserGuard,
+    core_id: dto::types::IdOrSlug<'_>,
+    user_id: dto::user::UserIdOrUsername<'_>,
+    form: Json<dto::permissions::CoreCollaboratorUpdate>,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let db = &mut db;
+    let core = models::Core::from_id_or_slug(db, core_id).await?;
+    let (admin_user, team, role) = acls::core_access(db, admin.id, &core).await?;
+    acls::require(acls::check_team(db, &team, role.as_ref(), Permission::ManageMembers).await?)?;
+
+    let dto::permissions::CoreCollaboratorUpdate { permissions } = form.into_inner();
+    if let Some(p) = permissions.iter().find(|p| !p.is_core_permission()) {
+        return Err((
+            Status::BadRequest,
+            format!("The {p} permission cannot be granted on a core"),
+        ));
+    }
+
+    let user = models::User::from_userid(db, user_id)
+        .await
+        .map_err(|e| (Status::NotFound, e.to_string()))?;
+    models::CoreCollaborator::set(db, core.id, user.id, &permissions, &admin_user)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(dto::Ok))
+}
+
+/// Remove a collaborator of a core. Requires the `manage_members` permission
+/// in the team owning the core.
+#[openapi(tag = "Permissions", ignore = "db")]
+#[delete("/cores/<core_id>/collaborators/<user_id>")]
+pub async fn cores_collaborators_remove(
+    mut db: Db,
+    admin: AuthenticatedUserGuard,
+    core_id: dto::types::IdOrSlug<'_>,
+    user_id: dto::user::UserIdOrUsername<'_>,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let db = &mut db;
+    let core = models::Core::from_id_or_slug(db, core_id).await?;
+    let (admin_user, team, role) = acls::core_access(db, admin.id, &core).await?;
+    acls::require(acls::check_team(db, &team, role.as_ref(), Permission::ManageMembers).await?)?;
+
+    let user = models::User::from_userid(db, user_id)
+        .await
+        .map_err(|e| (Status::NotFound, e.to_string()))?;
+    models::CoreCollaborator::set(db, core.id, user.id, &[], &admin_user)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(dto::Ok))
+}
+
+/// Check whether a user has a permission, and explain why. Team permissions
+/// need a team or a core; core permissions also consider the collaborators
+/// of the core. Checking another user requires the `manage_members`
+/// permission in the team.
+#[openapi(tag = "Permissions", ignore = "db")]
+#[get("/permissions/check?<query..>")]
+pub async fn permissions_check(
+    mut db: Db,
+    config: &State<RetronomiconConfig>,
+    user: AuthenticatedUserGuard,
+    query: dto::permissions::PermissionCheckQuery<'_>,
+) -> Result<Json<dto::permissions::PermissionCheck>, (Status, String)> {
+    let db = &mut db;
+    let dto::permissions::PermissionCheckQuery {
+        permission,
+        team,
+        core,
+        user: target,
+    } = query;
+    let caller = user.into_model(db).await?;
+    let target = match target {
+        Some(target) => models::User::from_userid(db, target)
+            .await
+            .map_err(|e| (Status::NotFound, e.to_string()))?,
+        None => caller.clone(),
+    };
+
+    if !permission.is_team_permission() {
+        if target.id != caller.id {
+            return Err((
+                Status::Forbidden,
+                "Cannot check this permission for another user".to_string(),
+            ));
+        }
+        return Ok(Json(acls::check_create_team(config, &target)));
+    }
+
+    let core = match core {
+        Some(core) => Some(models::Core::from_id_or_slug(db, core).await?),
+        None => None,
+    };
+    let team = match (&core, team) {
+        (Some(core), _) => models::Team::from_id_or_slug(db, core.owner_team_id.into()).await?,
+        (None, Some(team)) => models::Team::from_id_or_slug(db, team).await?,
+        (None, None) => {
+            return Err((
+                Status::BadRequest,
+                "A team or a core is required".to_string(),
+            ))
+        }
+    };
+
+    if target.id != caller.id {
+        let caller_role = caller
+            .role_in(db, team.id)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+        acls::require(
+            acls::check_team(db, &team, caller_role.as_ref(), Permission::ManageMembers).await?,
+        )?;
+    }
+
+    let role = target
+        .role_in(db, team.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    let check = match &core {
+        Some(core) => acls::check_core(db, &target, &team, role.as_ref(), core, permission).await?,
+        None => acls::check_team(db, &team, role.as_ref(), permission).await?,
+    };
+    Ok(Json(check))
+}
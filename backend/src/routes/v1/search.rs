@@ -0,0 +1,39 @@
+use retronomicon_db::models;
+use retronomicon_db::Db;
+use retronomicon_dto as dto;
+use rocket::get;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket_okapi::openapi;
+
+/// Search games, cores, systems and platforms by their names, descriptions,
+/// publishers and developers. Results are sorted by relevance, and facets
+/// count the results of each kind.
+#[openapi(tag = "Search", ignore = "db")]
+#[get("/search?<params..>")]
+pub async fn search(
+    mut db: Db,
+    params: dto::search::SearchQueryParams,
+) -> Result<Json<dto::search::SearchResponse>, (Status, String)> {
+    let (page, limit) = params
+        .paging
+        .validate()
+        .map_err(|e| (Status::BadRequest, e))?;
+    let q = params.q.trim();
+    if q.is_empty() {
+        return Err((Status::BadRequest, "Search query is empty".to_string()));
+    }
+
+    let facets = models::SearchMatch::facets(&mut db, q)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    let results = models::SearchMatch::search(&mut db, q, params.kind, page, limit)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .into_iter()
+        .map(models::SearchMatch::into_dto)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| (Status::InternalServerError, e))?;
+
+    Ok(Json(dto::search::SearchResponse { facets, results }))
+}
@@ -1,15 +1,19 @@
 use crate::guards;
-use crate::utils::json;
+use crate::utils::{acls, dat_exports, json};
 use retronomicon_db::models;
 use retronomicon_db::types::FetchModel;
 use retronomicon_db::Db;
 use retronomicon_dto as dto;
-use rocket::http::Status;
+use retronomicon_dto::permissions::Permission;
+use rocket::futures::Stream;
+use rocket::http::{Header, Status};
+use rocket::response::stream::TextStream;
 use rocket::serde::json::Json;
-use rocket::{get, post};
+use rocket::{get, post, Responder};
 use rocket_okapi::openapi;
 use serde_json::json;
-use std::collections::BTreeMap;
+
+pub mod imports;
 
 #[openapi(tag = "Systems", ignore = "db")]
 #[get("/systems?<paging..>")]
@@ -65,9 +69,7 @@ pub async fn systems_create(
         .map_err(|e| (Status::InternalServerError, e.to_string()))?
         .ok_or((Status::Forbidden, "Not a member of the team".to_string()))?;
 
-    if !role.can_create_systems() {
-        return Err((Status::Forbidden, "Not enough permission".to_string()));
-    }
+    acls::require(acls::check_team(&mut db, &team, Some(&role), Permission::CreateSystems).await?)?;
 
     // Create system.
     let system = models::System::create(
@@ -76,8 +78,8 @@ pub async fn systems_create(
         name,
         description,
         manufacturer,
-        json!(links.unwrap_or_else(|| BTreeMap::new())),
-        json!(metadata.unwrap_or_else(|| BTreeMap::new())),
+        json!(links.unwrap_or_default()),
+        json!(metadata.unwrap_or_default()),
         team.id,
     )
     .await
@@ -113,3 +115,64 @@ pub async fn systems_details(
         owner_team: team.into(),
     }))
 }
+
+/// A DAT, streamed a game at a time.
+#[derive(Responder)]
+#[response(content_type = "application/xml")]
+pub struct DatDownload<S> {
+    stream: TextStream<S>,
+    disposition: Header<'static>,
+}
+
+/// Export the games of a system, with their files, as a logiqx DAT.
+#[openapi(tag = "Systems", ignore = "db", skip)]
+#[get("/systems/<id>/games.dat?<params..>")]
+pub async fn systems_games_dat(
+    mut db: Db,
+    id: dto::types::IdOrSlug<'_>,
+    params: dto::games::DatExportQueryParams,
+) -> Result<DatDownload<impl Stream<Item = String>>, (Status, String)> {
+    let system = models::System::from_id_or_slug(&mut db, id).await?;
+    let mut export = dat_exports::DatExport::new(system.id, params);
+
+    let mut start = String::new();
+    datary::write_start(&mut start, Some(&dat_exports::header(&system)))
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    // Errors once the response started cannot be reported, so the first
+    // games are loaded before.
+    let mut games = export
+        .next_games(&mut db)
+        .await
+        .map_err(|e| (Status::InternalServerError, e))?;
+
+    let system_id = system.id;
+    let stream = TextStream! {
+        yield start;
+        while !games.is_empty() {
+            for game in games {
+                yield game;
+            }
+            games = match export.next_games(&mut db).await {
+                Ok(games) => games,
+                Err(e) => {
+                    rocket::error!("Exporting the DAT of system {system_id} failed: {e}");
+                    return;
+                }
+            };
+        }
+
+        let mut end = String::new();
+        match datary::write_end(&mut end) {
+            Ok(()) => yield end,
+            Err(e) => rocket::error!("Exporting the DAT of system {system_id} failed: {e}"),
+        }
+    };
+
+    Ok(DatDownload {
+        stream,
+        disposition: Header::new(
+            "Content-Disposition",
+            format!("attachment; filename=\"{}.dat\"", system.slug),
+        ),
+    })
+}
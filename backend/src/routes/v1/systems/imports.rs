@@ -0,0 +1,130 @@
+use crate::guards;
+use crate::utils::dat_imports;
+use retronomicon_db::models;
+use retronomicon_db::types::FetchModel;
+use retronomicon_db::Db;
+use retronomicon_dto as dto;
+use rocket::data::ToByteUnit;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{get, post, Data};
+use rocket_okapi::openapi;
+
+/// The maximum size of an uploaded DAT file.
+const MAX_DAT_SIZE: usize = 64 * 1024 * 1024;
+
+async fn get_import(
+    db: &mut Db,
+    system: &models::System,
+    import_id: i32,
+) -> Result<models::DatImport, (Status, String)> {
+    models::DatImport::get(db, import_id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .filter(|i| i.system_id == system.id)
+        .ok_or((Status::NotFound, "Import not found".to_string()))
+}
+
+/// Upload a DAT file to create or update the games of a system. The DAT is
+/// imported in the background; the import tells its preview and progress.
+/// A dry run only computes the preview, and can be applied afterward.
+#[openapi(tag = "Systems", ignore = "db", ignore = "importer")]
+#[post("/systems/<system_id>/imports?<params..>", data = "<dat>")]
+pub async fn systems_imports_create(
+    mut db: Db,
+    root_user: guards::users::RootUserGuard,
+    importer: guards::dat_importer::DatImporter,
+    system_id: dto::types::IdOrSlug<'_>,
+    params: dto::games::DatImportQueryParams,
+    dat: Data<'_>,
+) -> Result<Json<dto::games::DatImportDetails>, (Status, String)> {
+    let system = models::System::from_id_or_slug(&mut db, system_id).await?;
+
+    let data = dat
+        .open(MAX_DAT_SIZE.bytes())
+        .into_bytes()
+        .await
+        .map_err(|e| (Status::BadRequest, e.to_string()))?;
+    if !data.is_complete() {
+        return Err((
+            Status::PayloadTooLarge,
+            format!("DAT is too large (max {MAX_DAT_SIZE} bytes)"),
+        ));
+    }
+    let data = data.into_inner();
+
+    // Refuse invalid DATs right away, instead of failing the import.
+    let dat = dat_imports::parse(&data).map_err(|e| (Status::BadRequest, e))?;
+    let (dat_name, dat_version) = dat.header.map(|h| (h.name, h.version)).unwrap_or_default();
+
+    let import = models::DatImport::create(
+        &mut db,
+        &system,
+        root_user.id,
+        &dat_name,
+        &dat_version,
+        &data,
+        dat.games.len() as i32,
+        params.dry_run.unwrap_or(false),
+    )
+    .await
+    .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    importer.spawn(import.id);
+
+    Ok(Json(
+        import
+            .into_dto(system)
+            .map_err(|e| (Status::InternalServerError, e))?,
+    ))
+}
+
+/// The status of an import, with its preview and progress.
+#[openapi(tag = "Systems", ignore = "db")]
+#[get("/systems/<system_id>/imports/<import_id>")]
+pub async fn systems_imports_details(
+    mut db: Db,
+    _root_user: guards::users::RootUserGuard,
+    system_id: dto::types::IdOrSlug<'_>,
+    import_id: i32,
+) -> Result<Json<dto::games::DatImportDetails>, (Status, String)> {
+    let system = models::System::from_id_or_slug(&mut db, system_id).await?;
+    let import = get_import(&mut db, &system, import_id).await?;
+
+    Ok(Json(
+        import
+            .into_dto(system)
+            .map_err(|e| (Status::InternalServerError, e))?,
+    ))
+}
+
+/// Import the games of a dry run, once its preview is ready. The preview is
+/// computed again, as games might have changed since.
+#[openapi(tag = "Systems", ignore = "db", ignore = "importer")]
+#[post("/systems/<system_id>/imports/<import_id>/apply")]
+pub async fn systems_imports_apply(
+    mut db: Db,
+    _root_user: guards::users::RootUserGuard,
+    importer: guards::dat_importer::DatImporter,
+    system_id: dto::types::IdOrSlug<'_>,
+    import_id: i32,
+) -> Result<Json<dto::games::DatImportDetails>, (Status, String)> {
+    let system = models::System::from_id_or_slug(&mut db, system_id).await?;
+    let import = get_import(&mut db, &system, import_id)
+        .await?
+        .apply(&mut db)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((
+            Status::Conflict,
+            "Only the preview of a dry run can be applied".to_string(),
+        ))?;
+
+    importer.spawn(import.id);
+
+    Ok(Json(
+        import
+            .into_dto(system)
+            .map_err(|e| (Status::InternalServerError, e))?,
+    ))
+}
@@ -1,14 +1,18 @@
-use crate::guards::users::AuthenticatedUserGuard;
-use crate::utils::{acls, json};
+use crate::fairings::config::RetronomiconConfig;
+use crate::guards::emailer::EmailGuard;
+use crate::guards::users::{AuthenticatedUserGuard, RootUserGuard};
+use crate::utils::{acls, json, notifications};
 use retronomicon_db::models;
 use retronomicon_db::models::Team;
 use retronomicon_db::types::FetchModel;
 use retronomicon_db::Db;
 use retronomicon_dto as dto;
+use retronomicon_dto::notifications::NotificationKind;
+use retronomicon_dto::permissions::Permission;
 use retronomicon_dto::types::IdOrSlug;
 use rocket::http::Status;
 use rocket::serde::json::Json;
-use rocket::{delete, get, post, put};
+use rocket::{delete, get, post, put, State};
 use rocket_okapi::openapi;
 use serde_json::{json, Value};
 
@@ -53,6 +57,7 @@ pub async fn teams_details(
         links,
         metadata,
         users,
+        upload_size_limit: team.upload_size_limit,
     }))
 }
 
@@ -61,6 +66,7 @@ pub async fn teams_details(
 #[post("/teams", data = "<form>")]
 pub async fn teams_create(
     mut db: Db,
+    config: &State<RetronomiconConfig>,
     owner: AuthenticatedUserGuard,
     form: Json<dto::teams::TeamCreateRequest<'_>>,
 ) -> Result<Json<dto::teams::TeamCreateResponse>, (Status, String)> {
@@ -79,9 +85,7 @@ pub async fn teams_create(
     let metadata = metadata.map(|m| json!(m));
     let user = owner.into_model(db).await?;
 
-    if !acls::can_create_team(&user) {
-        return Err((Status::Unauthorized, "Insufficient permissions".to_string()));
-    }
+    acls::require(acls::check_create_team(config, &user))?;
 
     let team = models::Team::create(
         db,
@@ -114,14 +118,13 @@ pub async fn teams_update(
     form: Json<dto::teams::TeamUpdateRequest<'_>>,
 ) -> Result<Json<dto::Ok>, (Status, String)> {
     let db = &mut db;
-    let (user, mut team, role) = models::User::get_user_team_and_role(db, owner.id.into(), team_id)
-        .await
-        .map_err(|e| (Status::InternalServerError, e.to_string()))?
-        .ok_or((Status::NotFound, "Not found".to_string()))?;
+    let (_user, mut team, role) =
+        models::User::get_user_team_and_role(db, owner.id.into(), team_id)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+            .ok_or((Status::NotFound, "Not found".to_string()))?;
 
-    if !acls::can_update_team(&user, &team, &role) {
-        return Err((Status::Unauthorized, "Insufficient permissions".to_string()));
-    }
+    acls::require(acls::check_team(db, &team, Some(&role), Permission::UpdateTeam).await?)?;
 
     let dto::teams::TeamUpdateRequest {
         slug,
@@ -160,6 +163,106 @@ pub async fn teams_update(
     Ok(Json(dto::Ok))
 }
 
+/// Change the maximum size of artifacts uploaded by a team. Only members of
+/// the root team can do this.
+#[openapi(tag = "Teams", ignore = "db")]
+#[put("/teams/<team_id>/upload-limit", data = "<form>")]
+pub async fn teams_upload_limit(
+    mut db: Db,
+    _root: RootUserGuard,
+    team_id: dto::types::IdOrSlug<'_>,
+    form: Json<dto::teams::TeamUploadLimitRequest>,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let db = &mut db;
+    let team = Team::from_id_or_slug(db, team_id).await?;
+    let dto::teams::TeamUploadLimitRequest { upload_size_limit } = form.into_inner();
+
+    if upload_size_limit.is_some_and(|limit| limit <= 0) {
+        return Err((
+            Status::BadRequest,
+            "Upload size limit must be positive".to_string(),
+        ));
+    }
+
+    models::Team::set_upload_size_limit(db, team.id, upload_size_limit)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    Ok(Json(dto::Ok))
+}
+
+/// List the keys a team signs release manifests with, including revoked ones.
+#[openapi(tag = "Teams", ignore = "db")]
+#[get("/teams/<team_id>/keys")]
+pub async fn teams_keys(
+    mut db: Db,
+    team_id: dto::types::IdOrSlug<'_>,
+) -> Result<Json<Vec<dto::teams::TeamSigningKey>>, (Status, String)> {
+    let db = &mut db;
+    let team = Team::from_id_or_slug(db, team_id).await?;
+
+    models::TeamSigningKey::list(db, &team)
+        .await
+        .map(|k| Json(k.into_iter().map(Into::into).collect()))
+        .map_err(|e| (Status::InternalServerError, e.to_string()))
+}
+
+/// Register an ed25519 public key to sign release manifests of the team.
+#[openapi(tag = "Teams", ignore = "db")]
+#[post("/teams/<team_id>/keys", format = "json", data = "<form>")]
+pub async fn teams_keys_create(
+    mut db: Db,
+    admin: AuthenticatedUserGuard,
+    team_id: dto::types::IdOrSlug<'_>,
+    form: Json<dto::teams::TeamSigningKeyCreateRequest<'_>>,
+) -> Result<Json<dto::teams::TeamSigningKey>, (Status, String)> {
+    let db = &mut db;
+    let (_user, team, role) = models::User::get_user_team_and_role(db, admin.id.into(), team_id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::NotFound, "Not found".to_string()))?;
+
+    acls::require(acls::check_team(db, &team, Some(&role), Permission::ManageTeamKeys).await?)?;
+
+    let dto::teams::TeamSigningKeyCreateRequest { name, public_key } = form.into_inner();
+    if ed25519_dalek::VerifyingKey::try_from(public_key.as_slice()).is_err() {
+        return Err((Status::BadRequest, "Invalid ed25519 public key".to_string()));
+    }
+
+    models::TeamSigningKey::create(db, &team, name, &public_key)
+        .await
+        .map(|k| Json(k.into()))
+        .map_err(|e| (Status::InternalServerError, e.to_string()))
+}
+
+/// Revoke a signing key of the team. Signatures made with it are not served
+/// anymore.
+#[openapi(tag = "Teams", ignore = "db")]
+#[delete("/teams/<team_id>/keys/<key_id>")]
+pub async fn teams_keys_revoke(
+    mut db: Db,
+    admin: AuthenticatedUserGuard,
+    team_id: dto::types::IdOrSlug<'_>,
+    key_id: i32,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let db = &mut db;
+    let (_user, team, role) = models::User::get_user_team_and_role(db, admin.id.into(), team_id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::NotFound, "Not found".to_string()))?;
+
+    acls::require(acls::check_team(db, &team, Some(&role), Permission::ManageTeamKeys).await?)?;
+
+    let key = models::TeamSigningKey::get(db, &team, key_id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::NotFound, "Key not found".to_string()))?;
+    key.revoke(db)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(dto::Ok))
+}
+
 #[openapi(tag = "Teams", ignore = "db")]
 #[delete("/teams/<team_id>")]
 pub async fn teams_delete(
@@ -168,14 +271,12 @@ pub async fn teams_delete(
     team_id: dto::types::IdOrSlug<'_>,
 ) -> Result<Json<dto::Ok>, (Status, String)> {
     let db = &mut db;
-    let (user, team, role) = models::User::get_user_team_and_role(db, admin.into(), team_id)
+    let (_user, team, role) = models::User::get_user_team_and_role(db, admin.into(), team_id)
         .await
         .map_err(|e| (Status::InternalServerError, e.to_string()))?
         .ok_or((Status::NotFound, "Not found".to_string()))?;
 
-    if !acls::can_delete_team(&user, &team, &role) {
-        return Err((Status::Unauthorized, "Insufficient permissions".to_string()));
-    }
+    acls::require(acls::check_team(db, &team, Some(&role), Permission::DeleteTeam).await?)?;
 
     models::Team::delete(db, team.id)
         .await
@@ -183,10 +284,13 @@ pub async fn teams_delete(
     Ok(Json(dto::Ok))
 }
 
-#[openapi(tag = "Teams", ignore = "db")]
+/// Invite a user to a team. The user is notified of the invitation.
+#[openapi(tag = "Teams", ignore = "db", ignore = "emailer")]
 #[post("/teams/<team_id>/invitation", data = "<form>")]
 pub async fn invite(
     mut db: Db,
+    config: &State<RetronomiconConfig>,
+    emailer: EmailGuard,
     admin: AuthenticatedUserGuard,
     team_id: IdOrSlug<'_>,
     form: Json<dto::teams::TeamInvite<'_>>,
@@ -204,14 +308,36 @@ pub async fn invite(
         .map_err(|e| (Status::NotFound, e.to_string()))?;
     let role = models::UserTeamRole::from(role);
 
-    if !acls::can_invite_to_team(&team, &admin_user, &admin_role, &user, &role) {
-        return Err((Status::Unauthorized, "Insufficient permissions".to_string()));
-    }
+    require_manage_member(db, &team, &admin_role, &role).await?;
 
     user.invite_to(db, admin_user.id, team.id, role)
         .await
         .map_err(|e| (Status::InternalServerError, e.to_string()))?;
 
+    // Members whose role was raised are not invited again.
+    if let Some(role) = user
+        .invitation_to(db, team.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        let role = dto::types::UserTeamRole::from(role);
+        notifications::notify(
+            db,
+            config.inner(),
+            &emailer,
+            &user,
+            NotificationKind::TeamInvitation,
+            json!({
+                "team_id": team.id,
+                "team_slug": team.slug,
+                "team_name": team.name,
+                "role": role,
+                "invited_by": admin_user.username,
+            }),
+        )
+        .await;
+    }
+
     Ok(Json(dto::Ok))
 }
 
@@ -233,3 +359,235 @@ pub async fn invite_accept(
 
     Ok(Json(dto::Ok))
 }
+
+/// Decline an invitation to a team.
+#[openapi(tag = "Teams", ignore = "db")]
+#[delete("/teams/<team_id>/invitation")]
+pub async fn invite_decline(
+    mut db: Db,
+    invited: AuthenticatedUserGuard,
+    team_id: IdOrSlug<'_>,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let db = &mut db;
+    let user = invited.into_model(db).await?;
+    let team = Team::from_id_or_slug(db, team_id).await?;
+
+    user.invitation_to(db, team.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::NotFound, "No invitation".to_string()))?;
+    user.leave_team(db, team.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(dto::Ok))
+}
+
+/// List the invitations to a team that were not accepted yet.
+#[openapi(tag = "Teams", ignore = "db")]
+#[get("/teams/<team_id>/invitations")]
+pub async fn teams_invitations(
+    mut db: Db,
+    admin: AuthenticatedUserGuard,
+    team_id: IdOrSlug<'_>,
+) -> Result<Json<Vec<dto::teams::TeamInvitation>>, (Status, String)> {
+    let db = &mut db;
+    let (_user, team, role) = models::User::get_user_team_and_role(db, admin.id.into(), team_id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::NotFound, "Not found".to_string()))?;
+
+    acls::require(acls::check_team(db, &team, Some(&role), Permission::ManageMembers).await?)?;
+
+    team.invitations_ref(db)
+        .await
+        .map(Json)
+        .map_err(|e| (Status::InternalServerError, e.to_string()))
+}
+
+/// Cancel an invitation to a team.
+#[openapi(tag = "Teams", ignore = "db")]
+#[delete("/teams/<team_id>/invitations/<user_id>")]
+pub async fn invite_cancel(
+    mut db: Db,
+    admin: AuthenticatedUserGuard,
+    team_id: IdOrSlug<'_>,
+    user_id: dto::user::UserIdOrUsername<'_>,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let db = &mut db;
+    let (_admin_user, team, admin_role) =
+        models::User::get_user_team_and_role(db, admin.id.into(), team_id)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+            .ok_or((Status::NotFound, "Not found".to_string()))?;
+
+    let user = models::User::from_userid(db, user_id)
+        .await
+        .map_err(|e| (Status::NotFound, e.to_string()))?;
+    let role = user
+        .invitation_to(db, team.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::NotFound, "No invitation".to_string()))?;
+
+    // Anyone who could have sent the invitation can cancel it.
+    require_manage_member(db, &team, &admin_role, &role).await?;
+
+    user.leave_team(db, team.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(dto::Ok))
+}
+
+/// Change the role of a member of a team. A team always keeps at least one
+/// owner.
+#[openapi(tag = "Teams", ignore = "db")]
+#[put("/teams/<team_id>/members/<user_id>", data = "<form>")]
+pub async fn teams_members_update(
+    mut db: Db,
+    admin: AuthenticatedUserGuard,
+    team_id: IdOrSlug<'_>,
+    user_id: dto::user::UserIdOrUsername<'_>,
+    form: Json<dto::teams::TeamMemberUpdate>,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let db = &mut db;
+    let (_admin_user, team, admin_role) =
+        models::User::get_user_team_and_role(db, admin.id.into(), team_id)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+            .ok_or((Status::NotFound, "Not found".to_string()))?;
+
+    let member = models::User::from_userid(db, user_id)
+        .await
+        .map_err(|e| (Status::NotFound, e.to_string()))?;
+    let member_role = member
+        .role_in(db, team.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::NotFound, "Not a member of the team".to_string()))?;
+    let new_role = models::UserTeamRole::from(form.into_inner().role);
+
+    require_manage_member(db, &team, &admin_role, &member_role).await?;
+    if !acls::can_change_team_role(&team, &admin_role, &member_role, &new_role) {
+        return Err((
+            Status::Forbidden,
+            format!(
+                "Only owners can give the {} role",
+                dto::types::UserTeamRole::from(new_role)
+            ),
+        ));
+    }
+
+    let owner_count = team
+        .owner_count(db)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    if new_role != models::UserTeamRole::Owner && !acls::can_lose_member(&member_role, owner_count)
+    {
+        return Err((
+            Status::Conflict,
+            "A team must have at least one owner".to_string(),
+        ));
+    }
+
+    member
+        .set_role_in(db, team.id, new_role)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(dto::Ok))
+}
+
+/// Remove a member from a team. A team always keeps at least one owner.
+#[openapi(tag = "Teams", ignore = "db")]
+#[delete("/teams/<team_id>/members/<user_id>")]
+pub async fn teams_members_remove(
+    mut db: Db,
+    admin: AuthenticatedUserGuard,
+    team_id: IdOrSlug<'_>,
+    user_id: dto::user::UserIdOrUsername<'_>,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let db = &mut db;
+    let (_admin_user, team, admin_role) =
+        models::User::get_user_team_and_role(db, admin.id.into(), team_id)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+            .ok_or((Status::NotFound, "Not found".to_string()))?;
+
+    let member = models::User::from_userid(db, user_id)
+        .await
+        .map_err(|e| (Status::NotFound, e.to_string()))?;
+    let member_role = member
+        .role_in(db, team.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::NotFound, "Not a member of the team".to_string()))?;
+
+    require_manage_member(db, &team, &admin_role, &member_role).await?;
+
+    leave_team(db, &team, &member, &member_role).await
+}
+
+/// Leave a team. The last owner of a team cannot leave it.
+#[openapi(tag = "Teams", ignore = "db")]
+#[post("/teams/<team_id>/leave")]
+pub async fn teams_leave(
+    mut db: Db,
+    member: AuthenticatedUserGuard,
+    team_id: IdOrSlug<'_>,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let db = &mut db;
+    let (user, team, role) = models::User::get_user_team_and_role(db, member.id.into(), team_id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        .ok_or((Status::NotFound, "Not found".to_string()))?;
+
+    leave_team(db, &team, &user, &role).await
+}
+
+/// Check that a user can manage the members of a team, and a member (or
+/// invitation) with `member_role` in particular.
+async fn require_manage_member(
+    db: &mut Db,
+    team: &Team,
+    admin_role: &models::UserTeamRole,
+    member_role: &models::UserTeamRole,
+) -> Result<(), (Status, String)> {
+    acls::require(acls::check_team(db, team, Some(admin_role), Permission::ManageMembers).await?)?;
+    if !acls::can_manage_member(team, admin_role, member_role) {
+        return Err((
+            Status::Forbidden,
+            format!(
+                "Only owners can manage {}s",
+                dto::types::UserTeamRole::from(*member_role)
+            ),
+        ));
+    }
+    Ok(())
+}
+
+async fn leave_team(
+    db: &mut Db,
+    team: &Team,
+    member: &models::User,
+    role: &models::UserTeamRole,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let owner_count = team
+        .owner_count(db)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    if !acls::can_lose_member(role, owner_count) {
+        return Err((
+            Status::Conflict,
+            "A team must have at least one owner".to_string(),
+        ));
+    }
+
+    member
+        .leave_team(db, team.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(dto::Ok))
+}
@@ -4,7 +4,7 @@ use retronomicon_db::Db;
 use retronomicon_dto as dto;
 use rocket::http::Status;
 use rocket::serde::json::Json;
-use rocket::{get, post, put};
+use rocket::{delete, get, post, put};
 use rocket_okapi::openapi;
 
 /// Check availability of a username. This is easier and less resource intensive than
@@ -97,3 +97,22 @@ pub async fn users_update(
 
     Ok(Json(dto::Ok))
 }
+
+/// Terminate all sessions of a user, and invalidate the JWTs issued to them.
+/// Only root users can do this.
+#[openapi(tag = "Users", ignore = "db")]
+#[delete("/users/<id>/sessions")]
+pub async fn users_sessions_terminate(
+    mut db: Db,
+    _root_user: guards::users::RootUserGuard,
+    id: dto::user::UserIdOrUsername<'_>,
+) -> Result<Json<dto::Ok>, (Status, String)> {
+    let user = User::from_userid(&mut db, id)
+        .await
+        .map_err(|e| (Status::NotFound, e.to_string()))?;
+    user.invalidate_sessions(&mut db)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    Ok(Json(dto::Ok))
+}
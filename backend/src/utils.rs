@@ -1,4 +1,14 @@
 pub mod acls;
+pub mod blobs;
+pub mod dat_exports;
+pub mod dat_imports;
+pub mod files;
+pub mod images;
+pub mod notifications;
+pub mod oidc;
+pub mod tokens;
+pub mod two_factor;
+pub mod uploads;
 
 pub mod json {
     use serde_json::Value;
@@ -1,55 +1,227 @@
+//! Permissions of users on teams and cores.
+//!
+//! Most permissions are given by the role of a user in the team owning the
+//! resource. Each role has default permissions (see [`default_permission`]),
+//! which a team can change for its admins and members. Owners always have
+//! every permission of their team. Users from outside a team can also be
+//! given some permissions on a single core, as collaborators.
+//!
+//! Checks return a [`PermissionCheck`] explaining where the permission comes
+//! from, or why it is missing. See `docs/acls.md`.
+use crate::fairings::config::RetronomiconConfig;
 use retronomicon_db::models;
+use retronomicon_db::types::FetchModel;
+use retronomicon_db::Db;
+use retronomicon_dto::permissions::{Permission, PermissionCheck, PermissionSource};
+use rocket::http::Status;
 
-pub fn can_create_team(_user: &models::User) -> bool {
-    true
-}
-
-pub fn can_update_team(
-    _user: &models::User,
-    _team: &models::Team,
+/// Whether a role has a permission, unless its team changed it.
+pub fn default_permission(
+    team: &models::Team,
     role: &models::UserTeamRole,
+    permission: Permission,
 ) -> bool {
-    role == &models::UserTeamRole::Owner
+    match permission {
+        Permission::CreateTeam => false,
+        Permission::UpdateTeam | Permission::DeleteTeam => role == &models::UserTeamRole::Owner,
+        Permission::ManageMembers
+        | Permission::ManageTeamKeys
+        | Permission::CreateSystems
+        | Permission::CreateCores
+        | Permission::YankReleases => role >= &models::UserTeamRole::Admin,
+        // All members can do releases and sign them.
+        Permission::CreateReleases | Permission::SignManifests => true,
+        // Only the root team curates game images by default.
+        Permission::UploadGameImages => team.is_root(),
+    }
 }
 
-pub fn can_delete_team(
-    _user: &models::User,
-    _team: &models::Team,
-    role: &models::UserTeamRole,
-) -> bool {
-    role == &models::UserTeamRole::Owner
+/// Deny with a 403 and the reason if a check failed.
+pub fn require(check: PermissionCheck) -> Result<PermissionCheck, (Status, String)> {
+    if check.allowed {
+        Ok(check)
+    } else {
+        Err((Status::Forbidden, check.reason))
+    }
 }
 
-pub fn can_invite_to_team(
+/// Whether a user can create teams, according to the server configuration.
+pub fn check_create_team(config: &RetronomiconConfig, user: &models::User) -> PermissionCheck {
+    let allowed = config.can_create_team(&user.email);
+    PermissionCheck {
+        permission: Permission::CreateTeam,
+        allowed,
+        source: PermissionSource::Server,
+        role: None,
+        reason: if allowed {
+            "The server allows this user to create teams".to_string()
+        } else {
+            "The server does not allow this user to create teams".to_string()
+        },
+    }
+}
+
+/// Check a permission given by the role of a user in a team. `role` is
+/// `None` if the user is not a member of the team.
+pub async fn check_team(
+    db: &mut Db,
+    team: &models::Team,
+    role: Option<&models::UserTeamRole>,
+    permission: Permission,
+) -> Result<PermissionCheck, (Status, String)> {
+    let check = |allowed, source, reason| PermissionCheck {
+        permission,
+        allowed,
+        source,
+        role: role.map(|r| (*r).into()),
+        reason,
+    };
+
+    let Some(role) = role else {
+        return Ok(check(
+            false,
+            PermissionSource::NotAMember,
+            format!("Not a member of team {}", team.slug),
+        ));
+    };
+    let role_name = retronomicon_dto::types::UserTeamRole::from(*role);
+    if !permission.is_team_permission() {
+        return Ok(check(
+            false,
+            PermissionSource::Default,
+            format!("Teams do not give the {permission} permission"),
+        ));
+    }
+    if role == &models::UserTeamRole::Owner {
+        return Ok(check(
+            true,
+            PermissionSource::Owner,
+            format!("Owners of team {} have every permission", team.slug),
+        ));
+    }
+
+    let granted = models::TeamPermission::get(db, team.id, *role, permission)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    Ok(match granted {
+        Some(true) => check(
+            true,
+            PermissionSource::Team,
+            format!(
+                "Team {} grants {permission} to the {role_name} role",
+                team.slug
+            ),
+        ),
+        Some(false) => check(
+            false,
+            PermissionSource::Team,
+            format!(
+                "Team {} does not grant {permission} to the {role_name} role",
+                team.slug
+            ),
+        ),
+        None if default_permission(team, role, permission) => check(
+            true,
+            PermissionSource::Default,
+            format!("The {role_name} role has {permission} by default"),
+        ),
+        None => check(
+            false,
+            PermissionSource::Default,
+            format!("The {role_name} role does not have {permission} by default"),
+        ),
+    })
+}
+
+/// Check a permission on a core, given either by the role of the user in the
+/// team owning the core, or to the user as a collaborator of the core.
+pub async fn check_core(
+    db: &mut Db,
+    user: &models::User,
+    team: &models::Team,
+    role: Option<&models::UserTeamRole>,
+    core: &models::Core,
+    permission: Permission,
+) -> Result<PermissionCheck, (Status, String)> {
+    let check = check_team(db, team, role, permission).await?;
+    if check.allowed || !permission.is_core_permission() {
+        return Ok(check);
+    }
+
+    let collaborator = models::CoreCollaborator::has(db, core.id, user.id, permission)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    if collaborator {
+        Ok(PermissionCheck {
+            allowed: true,
+            source: PermissionSource::Collaborator,
+            reason: format!(
+                "Collaborators of core {} were granted {permission}",
+                core.slug
+            ),
+            ..check
+        })
+    } else {
+        Ok(check)
+    }
+}
+
+/// The user, the team owning a core and the role of the user in the team,
+/// if they are a member. Collaborators of a core are usually not.
+pub async fn core_access(
+    db: &mut Db,
+    user_id: i32,
+    core: &models::Core,
+) -> Result<(models::User, models::Team, Option<models::UserTeamRole>), (Status, String)> {
+    let user = models::User::from_id(db, user_id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    let team = models::Team::from_id_or_slug(db, core.owner_team_id.into()).await?;
+    let role = user
+        .role_in(db, team.id)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    Ok((user, team, role))
+}
+
+/// Whether a user managing the members of a team can act on a member (or
+/// invitation) with `member_role`. Owners can manage anyone. Others can only
+/// manage members, and roles below theirs. Only owners can manage the members
+/// of the root team.
+pub fn can_manage_member(
     team: &models::Team,
-    _admin_user: &models::User,
     admin_role: &models::UserTeamRole,
-    _invited_user: &models::User,
-    invited_role: &models::UserTeamRole,
+    member_role: &models::UserTeamRole,
 ) -> bool {
-    if team.is_root() {
-        admin_role == &models::UserTeamRole::Owner
-    } else if admin_role == &models::UserTeamRole::Owner {
+    if admin_role == &models::UserTeamRole::Owner {
         true
+    } else if team.is_root() {
+        false
     } else {
-        admin_role > invited_role
+        member_role == &models::UserTeamRole::Member || admin_role > member_role
     }
 }
 
-pub(crate) async fn can_create_core_releases(
-    _user: &models::User,
-    _team: &models::Team,
-    role: &models::UserTeamRole,
-    _core: &models::Core,
+/// Whether a user managing the members of a team can change the role of a
+/// member. An admin cannot promote someone to their own role, or demote an
+/// owner.
+pub fn can_change_team_role(
+    team: &models::Team,
+    admin_role: &models::UserTeamRole,
+    member_role: &models::UserTeamRole,
+    new_role: &models::UserTeamRole,
 ) -> bool {
-    // All members can do releases.
-    role >= &models::UserTeamRole::Member
+    can_manage_member(team, admin_role, member_role)
+        && can_manage_member(team, admin_role, new_role)
 }
 
-pub(crate) fn can_upload_image(
-    _user: &models::User,
-    team: &models::Team,
-    _role: &models::UserTeamRole,
-) -> bool {
-    team.is_root()
+/// Whether a team would still have an owner once a member with `role` leaves
+/// it or stops being an owner.
+pub fn can_lose_member(role: &models::UserTeamRole, owner_count: i64) -> bool {
+    role != &models::UserTeamRole::Owner || owner_count > 1
+}
+
+/// Only owners can change the permissions of the roles of a team.
+pub fn can_manage_team_permissions(role: &models::UserTeamRole) -> bool {
+    role == &models::UserTeamRole::Owner
 }
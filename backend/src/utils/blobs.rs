@@ -0,0 +1,76 @@
+use crate::guards::storage::{Storage, StorageBucket};
+use retronomicon_db::models;
+use retronomicon_db::DbConnection;
+
+/// Blobs are only collected after this delay (in seconds), so one that was
+/// just created is not deleted before its artifact is.
+const GC_GRACE_PERIOD: i64 = 60 * 60;
+
+/// Number of blobs, and of objects, to delete in a single pass.
+const GC_BATCH_SIZE: i64 = 100;
+
+/// Delete blobs that are not used by any artifact anymore, then their object
+/// in the storage. Returns the number of blobs deleted.
+pub async fn collect_garbage(db: &mut DbConnection, storage: &Storage) -> Result<usize, String> {
+    let created_before =
+        chrono::Utc::now().naive_utc() - chrono::Duration::seconds(GC_GRACE_PERIOD);
+    let blobs = models::Blob::list_unreferenced(db, created_before, GC_BATCH_SIZE)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut count = 0;
+    for blob in blobs {
+        // An artifact might have started using it in the meantime.
+        match blob
+            .delete_if_unreferenced(db, StorageBucket::Cores.name())
+            .await
+        {
+            Ok(true) => count += 1,
+            Ok(false) => {}
+            Err(e) => rocket::warn!("Failed to delete blob {}: {}", blob.id, e),
+        }
+    }
+
+    delete_objects(db, storage).await?;
+    Ok(count)
+}
+
+/// Delete the objects that are not used anymore from the storage. Failed
+/// deletions are kept to be retried later.
+async fn delete_objects(db: &mut DbConnection, storage: &Storage) -> Result<(), String> {
+    let deletions = models::StorageDeletion::list(db, GC_BATCH_SIZE)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    for deletion in deletions {
+        let result = match StorageBucket::from_name(&deletion.bucket) {
+            None => Err(format!("Unknown bucket {}", deletion.bucket)),
+            // Unless the same content was uploaded again since.
+            Some(StorageBucket::Cores) => {
+                match models::Blob::is_path_used(db, &deletion.path).await {
+                    Ok(true) => Ok(()),
+                    Ok(false) => storage.delete(StorageBucket::Cores, &deletion.path).await,
+                    Err(e) => Err(e.to_string()),
+                }
+            }
+            Some(bucket) => storage.delete(bucket, &deletion.path).await,
+        };
+
+        let result = match result {
+            Ok(()) => deletion.done(db).await,
+            Err(e) => {
+                rocket::warn!(
+                    "Failed to delete {}/{} from the storage: {}",
+                    deletion.bucket,
+                    deletion.path,
+                    e
+                );
+                deletion.failed(db, &e).await
+            }
+        };
+        if let Err(e) = result {
+            rocket::warn!("Failed to update storage deletion {}: {}", deletion.id, e);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,168 @@
+use retronomicon_db::{models, DbConnection};
+use retronomicon_dto as dto;
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+fn rom_status(status: &str) -> Option<datary::Status> {
+    use dto::games::GameArtifactStatus;
+
+    match status.parse().unwrap_or_default() {
+        GameArtifactStatus::BadDump => Some(datary::Status::BadDump),
+        GameArtifactStatus::NoDump => Some(datary::Status::NoDump),
+        GameArtifactStatus::Verified => Some(datary::Status::Verified),
+        GameArtifactStatus::Good => None,
+    }
+}
+
+/// The ROM of a file of a game. Its name contains the directory of the file,
+/// separated by backslashes, the way DATs are imported.
+fn rom_of(game_artifact: &models::GameArtifact, artifact: &models::Artifact) -> datary::Rom {
+    let encode = |checksum: &Vec<u8>| Some(hex::encode(checksum)).filter(|c| !c.is_empty());
+    let name = if game_artifact.path.is_empty() {
+        game_artifact.filename.clone()
+    } else {
+        format!(
+            "{}\\{}",
+            game_artifact.path.replace('/', "\\"),
+            game_artifact.filename
+        )
+    };
+
+    datary::Rom {
+        name,
+        size: artifact.size as usize,
+        crc: encode(&artifact.crc32),
+        sha1: encode(&artifact.sha1),
+        md5: encode(&artifact.md5),
+        sha256: encode(&artifact.sha256),
+        merge: None,
+        status: rom_status(&game_artifact.status),
+        date: None,
+    }
+}
+
+/// Number of games loaded at once when exporting a DAT.
+const EXPORT_BATCH_SIZE: i64 = 500;
+
+/// The header of the DAT of a system.
+pub fn header(system: &models::System) -> datary::Header {
+    datary::Header {
+        name: system.name.clone(),
+        description: system.description.clone(),
+        category: None,
+        version: chrono::Utc::now().format("%Y%m%d").to_string(),
+        author: "Retronomicon".to_string(),
+        email: None,
+        homepage: None,
+        url: None,
+        comment: None,
+        clr_mame_pro: None,
+        rom_center: None,
+    }
+}
+
+/// The DAT game of a game, with its files in order.
+///
+/// The ID of a game in the DAT is its unique ID in the system, and its
+/// manufacturer is its first publisher by name, so that importing the DAT
+/// back into the system changes nothing.
+fn game_of(
+    game: models::Game,
+    roms: Vec<datary::Rom>,
+    publisher: Option<models::Company>,
+) -> datary::Game {
+    datary::Game {
+        description: game.description,
+        comment: vec![],
+        id: u32::try_from(game.system_unique_id).ok(),
+        is_bios: datary::IsBios::No,
+        clone_of: None,
+        rom_of: None,
+        sample_of: None,
+        board: None,
+        rebuild_to: None,
+        year: Some(game.year).filter(|y| *y != 0).map(|y| y.to_string()),
+        manufacturer: publisher.map(|c| c.name),
+        releases: vec![],
+        bios_sets: vec![],
+        roms,
+        disks: vec![],
+        samples: vec![],
+        archives: vec![],
+        name: game.name,
+    }
+}
+
+/// An export of the games of a system as a DAT, loaded a batch of games at
+/// a time, by name.
+pub struct DatExport {
+    system_id: i32,
+    year: (Bound<i32>, Bound<i32>),
+    region: Option<String>,
+    tag: Option<String>,
+    /// The name and ID of the last game exported.
+    after: Option<(String, i32)>,
+}
+
+impl DatExport {
+    pub fn new(system_id: i32, params: dto::games::DatExportQueryParams) -> Self {
+        Self {
+            system_id,
+            year: params.year.unwrap_or_default().into(),
+            region: params.region,
+            tag: params.tag,
+            after: None,
+        }
+    }
+
+    /// The next games of the export, each as the XML of a DAT game (see
+    /// [`datary::write_game`]). Empty once all games were exported.
+    pub async fn next_games(&mut self, db: &mut DbConnection) -> Result<Vec<String>, String> {
+        let games = models::Game::list_for_export(
+            db,
+            self.system_id,
+            self.year,
+            self.region.as_deref(),
+            self.tag.as_deref(),
+            self.after.as_ref().map(|(name, id)| (name.as_str(), *id)),
+            EXPORT_BATCH_SIZE,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+        let Some(last) = games.last() else {
+            return Ok(vec![]);
+        };
+        self.after = Some((last.name.clone(), last.id));
+
+        let ids = games.iter().map(|g| g.id).collect::<Vec<_>>();
+        let mut roms = BTreeMap::<i32, Vec<datary::Rom>>::new();
+        for (game_artifact, artifact) in models::GameArtifact::list_for_games(db, &ids)
+            .await
+            .map_err(|e| e.to_string())?
+        {
+            roms.entry(game_artifact.game_id)
+                .or_default()
+                .push(rom_of(&game_artifact, &artifact));
+        }
+        let mut publishers = BTreeMap::new();
+        for (game_id, company) in
+            models::Company::list_for_games(db, &ids, dto::games::CompanyRole::Publisher)
+                .await
+                .map_err(|e| e.to_string())?
+        {
+            publishers.entry(game_id).or_insert(company);
+        }
+
+        games
+            .into_iter()
+            .map(|game| {
+                let roms = roms.remove(&game.id).unwrap_or_default();
+                let publisher = publishers.remove(&game.id);
+                let mut xml = String::new();
+                datary::write_game(&mut xml, &game_of(game, roms, publisher))
+                    .map_err(|e| e.to_string())?;
+                Ok(xml)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,365 @@
+use crate::utils::files::compare_checksum;
+use retronomicon_db::models::{self, DatImportArtifact, DatImportChange, DatImportEntry};
+use retronomicon_db::{DbConnection, RetronomiconDbPool};
+use retronomicon_dto as dto;
+use rocket::tokio;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Number of games imported in a single transaction.
+const IMPORT_BATCH_SIZE: usize = 100;
+
+pub fn parse(dat: &[u8]) -> Result<datary::Datafile, String> {
+    datary::from_reader(dat).map_err(|e| format!("Invalid DAT: {e}"))
+}
+
+/// Running imports that were not updated for this long (in seconds) are
+/// considered interrupted.
+const IMPORT_STALE_AFTER: i64 = 10 * 60;
+
+/// Run a pending import in the background.
+pub fn spawn(pool: RetronomiconDbPool, import_id: i32) {
+    tokio::spawn(async move {
+        let result = async {
+            let mut db = pool.connection().await?;
+            run(&mut db, import_id).await
+        }
+        .await;
+        if let Err(e) = result {
+            rocket::error!("DAT import {} failed: {}", import_id, e);
+        }
+    });
+}
+
+/// Fail the imports that were interrupted, and run the pending ones again,
+/// e.g. after the server restarted.
+pub async fn recover(pool: &RetronomiconDbPool) -> Result<(), String> {
+    let mut db = pool.connection().await?;
+    let updated_before =
+        chrono::Utc::now().naive_utc() - chrono::Duration::seconds(IMPORT_STALE_AFTER);
+    let failed = models::DatImport::fail_stale(
+        &mut db,
+        updated_before,
+        "The import was interrupted, it can be started again",
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    if failed > 0 {
+        rocket::warn!("Failed {} interrupted DAT imports.", failed);
+    }
+
+    // Starting an import is atomic, so imports already running elsewhere
+    // are not run twice.
+    for import in models::DatImport::list_pending(&mut db)
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        spawn(pool.clone(), import.id);
+    }
+    Ok(())
+}
+
+async fn run(db: &mut DbConnection, import_id: i32) -> Result<(), String> {
+    // Another job might have started it already.
+    let Some(import) = models::DatImport::start(db, import_id)
+        .await
+        .map_err(|e| e.to_string())?
+    else {
+        return Ok(());
+    };
+
+    match process(db, &import).await {
+        Ok(status) => import
+            .finish(db, status, None)
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => {
+            import
+                .finish(db, dto::games::DatImportStatus::Failed, Some(&e))
+                .await
+                .map_err(|e| e.to_string())?;
+            Err(e)
+        }
+    }
+}
+
+/// Compute the preview of an import, then import its games unless this is a
+/// dry run. Returns the status the import ends with.
+async fn process(
+    db: &mut DbConnection,
+    import: &models::DatImport,
+) -> Result<dto::games::DatImportStatus, String> {
+    let dat = parse(
+        import
+            .dat
+            .as_deref()
+            .ok_or("The DAT was already imported")?,
+    )?;
+    let games = models::Game::list_for_import(db, import.system_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut publishers = BTreeMap::<i32, BTreeSet<String>>::new();
+    for (game_id, company) in
+        models::Company::list_for_system(db, import.system_id, dto::games::CompanyRole::Publisher)
+            .await
+            .map_err(|e| e.to_string())?
+    {
+        publishers.entry(game_id).or_default().insert(company.slug);
+    }
+
+    let (preview, entries) = plan(&dat, games, &publishers)?;
+    import
+        .set_preview(db, &preview)
+        .await
+        .map_err(|e| e.to_string())?;
+    if import.dry_run {
+        return Ok(dto::games::DatImportStatus::Preview);
+    }
+
+    let mut processed = 0;
+    for batch in entries.chunks(IMPORT_BATCH_SIZE) {
+        processed += batch.len() as i32;
+        import
+            .import_batch(db, batch, processed)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(dto::games::DatImportStatus::Completed)
+}
+
+/// Whether an artifact is a file of a DAT: same size, and the same checksums
+/// where both are known.
+fn is_same_artifact(file: &DatImportArtifact, artifact: &models::Artifact) -> bool {
+    let checksums = [
+        compare_checksum(&file.crc32, &artifact.crc32),
+        compare_checksum(&file.md5, &artifact.md5),
+        compare_checksum(&file.sha1, &artifact.sha1),
+        compare_checksum(&file.sha256, &artifact.sha256),
+    ];
+    file.size == artifact.size
+        && !checksums.contains(&Some(false))
+        && checksums.contains(&Some(true))
+}
+
+/// What a file of a DAT game is used for, guessed from its name and the
+/// other files of the game.
+fn artifact_role(filename: &str, has_cue: bool) -> dto::games::GameArtifactRole {
+    use dto::games::GameArtifactRole;
+
+    let extension = filename
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "cue" | "gdi" => GameArtifactRole::Cue,
+        "bin" | "raw" | "iso" | "wav" if has_cue => GameArtifactRole::Track,
+        _ => GameArtifactRole::Rom,
+    }
+}
+
+fn artifact_status(status: &Option<datary::Status>) -> dto::games::GameArtifactStatus {
+    use dto::games::GameArtifactStatus;
+
+    match status {
+        Some(datary::Status::BadDump) => GameArtifactStatus::BadDump,
+        Some(datary::Status::NoDump) => GameArtifactStatus::NoDump,
+        Some(datary::Status::Verified) => GameArtifactStatus::Verified,
+        Some(datary::Status::Good) | None => GameArtifactStatus::Good,
+    }
+}
+
+/// The artifacts of a game of a DAT. ROMs that were not dumped have no
+/// checksums, and are skipped. ROM names can contain directories, separated
+/// by backslashes.
+fn artifacts_of(game: &datary::Game) -> Result<Vec<DatImportArtifact>, String> {
+    let has_cue = game
+        .roms
+        .iter()
+        .any(|rom| artifact_role(&rom.name, false) == dto::games::GameArtifactRole::Cue);
+
+    game.roms
+        .iter()
+        .enumerate()
+        .filter(|(_, rom)| rom.status != Some(datary::Status::NoDump))
+        .map(|(i, rom)| {
+            let decode = |checksum: &Option<String>| {
+                checksum
+                    .as_deref()
+                    .filter(|c| !c.is_empty())
+                    .map(hex::decode)
+                    .transpose()
+                    .map_err(|e| format!("Invalid checksum for ROM {:?}: {}", rom.name, e))
+            };
+            let name = rom.name.replace('\\', "/");
+            let (path, filename) = name.rsplit_once('/').unwrap_or(("", &name));
+
+            Ok(DatImportArtifact {
+                filename: filename.to_string(),
+                path: path.to_string(),
+                role: artifact_role(filename, has_cue),
+                order: i as i32,
+                status: artifact_status(&rom.status),
+                size: rom.size as i64,
+                crc32: decode(&rom.crc)?,
+                md5: decode(&rom.md5)?,
+                sha1: decode(&rom.sha1)?,
+                sha256: decode(&rom.sha256)?,
+            })
+        })
+        .collect()
+}
+
+/// Compute what importing a DAT does to the games of a system.
+///
+/// Games of the DAT are matched to existing games by their ID in the DAT
+/// (the unique ID of games in their system), then by their name. Games that
+/// do not exist are created, using their ID in the DAT if it is free, or the
+/// next free unique ID. The manufacturer of a game in the DAT is its only
+/// publisher; `publishers` are the slugs of the publishers of existing games.
+fn plan(
+    dat: &datary::Datafile,
+    games: Vec<(models::Game, Option<models::Artifact>)>,
+    publishers: &BTreeMap<i32, BTreeSet<String>>,
+) -> Result<(dto::games::DatImportPreview, Vec<DatImportEntry>), String> {
+    let mut existing = BTreeMap::<i32, (models::Game, Vec<models::Artifact>)>::new();
+    for (game, artifact) in games {
+        let (_, artifacts) = existing.entry(game.id).or_insert_with(|| (game, vec![]));
+        artifacts.extend(artifact);
+    }
+
+    let by_unique_id = existing
+        .values()
+        .map(|(g, _)| (g.system_unique_id, g.id))
+        .collect::<BTreeMap<_, _>>();
+    let mut by_name = BTreeMap::<&str, Vec<i32>>::new();
+    for (g, _) in existing.values() {
+        by_name.entry(g.name.as_str()).or_default().push(g.id);
+    }
+
+    let unique_ids = dat
+        .games
+        .iter()
+        .map(|game| {
+            game.id
+                .map(i32::try_from)
+                .transpose()
+                .map_err(|_| format!("Invalid ID for game {:?}", game.name))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut used_ids = by_unique_id.keys().copied().collect::<BTreeSet<_>>();
+    let mut next_id = used_ids
+        .iter()
+        .copied()
+        .chain(unique_ids.iter().flatten().copied())
+        .max()
+        .unwrap_or(0);
+
+    let mut preview = dto::games::DatImportPreview::default();
+    let mut entries = Vec::with_capacity(dat.games.len());
+    let mut matched = BTreeSet::new();
+    for (game, unique_id) in dat.games.iter().zip(unique_ids) {
+        let artifacts = artifacts_of(game)?;
+        let year = game.year.as_deref().and_then(|y| y.parse::<i32>().ok());
+
+        let game_id = unique_id
+            .and_then(|id| by_unique_id.get(&id))
+            .filter(|id| !matched.contains(*id))
+            .or_else(|| {
+                by_name
+                    .get(game.name.as_str())
+                    .and_then(|ids| ids.iter().find(|id| !matched.contains(*id)))
+            })
+            .copied();
+
+        let Some(game_id) = game_id else {
+            let system_unique_id = match unique_id.filter(|id| !used_ids.contains(id)) {
+                Some(id) => id,
+                None => {
+                    next_id = next_id
+                        .checked_add(1)
+                        .ok_or("No unique ID left in the system")?;
+                    next_id
+                }
+            };
+            used_ids.insert(system_unique_id);
+
+            preview.create.push(dto::games::DatImportGame {
+                game_id: None,
+                name: game.name.clone(),
+                new_artifacts: artifacts.len(),
+            });
+            entries.push(DatImportEntry {
+                change: DatImportChange::Create {
+                    system_unique_id,
+                    name: game.name.clone(),
+                    description: game.description.clone(),
+                    year: year.unwrap_or_default(),
+                    publisher: game.manufacturer.clone().unwrap_or_default(),
+                },
+                artifacts,
+            });
+            continue;
+        };
+        matched.insert(game_id);
+
+        let (existing_game, existing_artifacts) = &existing[&game_id];
+        let artifacts = artifacts
+            .into_iter()
+            .filter(|file| !existing_artifacts.iter().any(|a| is_same_artifact(file, a)))
+            .collect::<Vec<_>>();
+        let system_unique_id =
+            unique_id.filter(|id| *id != existing_game.system_unique_id && !used_ids.contains(id));
+        used_ids.extend(system_unique_id);
+        let name = Some(&game.name).filter(|n| **n != existing_game.name);
+        let description = Some(&game.description).filter(|d| **d != existing_game.description);
+        let year = year.filter(|y| *y != existing_game.year);
+        let publisher = game.manufacturer.as_ref().filter(|p| {
+            let slug = models::slugify(p);
+            match publishers.get(&game_id) {
+                Some(existing) => existing.len() != 1 || !existing.contains(&slug),
+                None => !p.trim().is_empty(),
+            }
+        });
+
+        let changed = system_unique_id.is_some()
+            || name.is_some()
+            || description.is_some()
+            || year.is_some()
+            || publisher.is_some()
+            || !artifacts.is_empty();
+        if changed {
+            preview.update.push(dto::games::DatImportGame {
+                game_id: Some(game_id),
+                name: game.name.clone(),
+                new_artifacts: artifacts.len(),
+            });
+        } else {
+            preview.unchanged += 1;
+        }
+
+        // Unchanged games are still marked as coming from this DAT.
+        entries.push(DatImportEntry {
+            change: DatImportChange::Update {
+                game_id,
+                system_unique_id,
+                name: name.cloned(),
+                description: description.cloned(),
+                year,
+                publisher: publisher.cloned(),
+            },
+            artifacts,
+        });
+    }
+
+    preview.orphaned = existing
+        .values()
+        .filter(|(g, _)| !matched.contains(&g.id))
+        .map(|(g, _)| dto::games::DatImportGame {
+            game_id: Some(g.id),
+            name: g.name.clone(),
+            new_artifacts: 0,
+        })
+        .collect();
+
+    Ok((preview, entries))
+}
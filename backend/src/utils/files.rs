@@ -0,0 +1,14 @@
+/// Compare a checksum of a file with a known checksum. `None` if either is
+/// unknown (known checksums are empty when unknown).
+pub fn compare_checksum<T: AsRef<[u8]>>(checksum: &Option<T>, known: &[u8]) -> Option<bool> {
+    match checksum {
+        Some(checksum) if !known.is_empty() => Some(checksum.as_ref() == known),
+        _ => None,
+    }
+}
+
+/// Whether a file name, or a directory in a path, can be used as is on a
+/// device: not empty, not `.` or `..`, and without separators.
+pub fn is_valid_path_segment(segment: &str) -> bool {
+    !(segment.is_empty() || segment == "." || segment == ".." || segment.contains(['/', '\\']))
+}
@@ -0,0 +1,198 @@
+use crate::guards::storage::{Paths, Storage, StorageBucket};
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::{ColorType, DynamicImage, GenericImageView, ImageFormat};
+use retronomicon_db::models;
+use retronomicon_db::{DbConnection, RetronomiconDbPool};
+use rocket::{tokio, warn};
+
+/// Images whose perceptual hashes differ by at most this many bits are
+/// considered duplicates.
+pub const DUPLICATE_MAX_DISTANCE: u32 = 6;
+
+/// Number of images loaded at once by the backfill.
+const BACKFILL_BATCH_SIZE: i64 = 20;
+
+/// A resized or re-encoded version of an uploaded image.
+pub struct Derivative {
+    pub width: u32,
+    pub height: u32,
+    pub mime_type: &'static str,
+    pub extension: &'static str,
+    pub data: Vec<u8>,
+}
+
+/// The difference hash (dHash) of an image: each bit tells whether a pixel
+/// of a 9x8 grayscale version of the image is brighter than the next one.
+/// Similar images (resized, re-encoded) have hashes differing by few bits.
+pub fn perceptual_hash(image: &DynamicImage) -> i64 {
+    let small = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = small.get_pixel(x, y)[0];
+            let right = small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | u64::from(left > right);
+        }
+    }
+    hash as i64
+}
+
+/// The number of bits two perceptual hashes differ by.
+pub fn hash_distance(a: i64, b: i64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+fn webp(image: &DynamicImage) -> Result<Derivative, String> {
+    let rgba = image.to_rgba8();
+    let mut data = Vec::new();
+    WebPEncoder::new_lossless(&mut data)
+        .encode(rgba.as_raw(), rgba.width(), rgba.height(), ColorType::Rgba8)
+        .map_err(|e| e.to_string())?;
+
+    Ok(Derivative {
+        width: rgba.width(),
+        height: rgba.height(),
+        mime_type: "image/webp",
+        extension: "webp",
+        data,
+    })
+}
+
+/// The derivatives of an image: a WebP version at its original size, and
+/// WebP thumbnails for each size smaller than the image (on its longest
+/// side). Smallest first.
+pub fn derivatives(image: &DynamicImage, sizes: &[u32]) -> Result<Vec<Derivative>, String> {
+    let (width, height) = image.dimensions();
+    let mut sizes = sizes
+        .iter()
+        .copied()
+        .filter(|&s| s > 0 && s < width.max(height))
+        .collect::<Vec<_>>();
+    sizes.sort_unstable();
+    sizes.dedup();
+
+    let mut result = sizes
+        .into_iter()
+        .map(|size| webp(&image.thumbnail(size, size)))
+        .collect::<Result<Vec<_>, _>>()?;
+    result.push(webp(image)?);
+    Ok(result)
+}
+
+/// Decode an image and compute its perceptual hash. Both are CPU bound, so
+/// they run on a blocking thread.
+pub async fn decode(data: Vec<u8>, format: ImageFormat) -> Result<(DynamicImage, i64), String> {
+    tokio::task::spawn_blocking(move || {
+        let image =
+            image::load_from_memory_with_format(&data, format).map_err(|e| e.to_string())?;
+        let hash = perceptual_hash(&image);
+        Ok((image, hash))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Generate the derivatives of an image (see [`derivatives`]) on a blocking
+/// thread.
+pub async fn generate_derivatives(
+    image: DynamicImage,
+    sizes: Vec<u32>,
+) -> Result<Vec<Derivative>, String> {
+    tokio::task::spawn_blocking(move || derivatives(&image, &sizes))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Upload the derivatives of a game image, and add them to the image.
+pub async fn store_derivatives(
+    db: &mut DbConnection,
+    storage: &Storage,
+    game: &models::Game,
+    image: &models::GameImage,
+    derivatives: Vec<Derivative>,
+) -> Result<(), String> {
+    for derivative in derivatives {
+        let path = Paths::path_for_game_image_derivative(
+            game,
+            &image.image_name,
+            derivative.width,
+            derivative.height,
+            derivative.extension,
+        );
+        let url = storage
+            .upload_game_asset(&path, &derivative.data, derivative.mime_type)
+            .await?;
+        image
+            .add_derivative(
+                db,
+                derivative.width as i32,
+                derivative.height as i32,
+                derivative.mime_type,
+                &url,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Compute the perceptual hash and the derivatives of an image uploaded
+/// before they were computed on upload.
+async fn backfill_image(
+    db: &mut DbConnection,
+    storage: &Storage,
+    game: &models::Game,
+    image: &models::GameImage,
+    sizes: &[u32],
+) -> Result<(), String> {
+    let format = ImageFormat::from_mime_type(&image.mime_type)
+        .ok_or_else(|| format!("Unsupported image format: {}", image.mime_type))?;
+    let mut data = Vec::new();
+    storage
+        .read(
+            StorageBucket::Games,
+            &Paths::path_for_game_image(game, &image.image_name),
+            &mut |chunk| data.extend_from_slice(chunk),
+        )
+        .await?;
+
+    let (decoded, hash) = decode(data, format).await?;
+    let derivatives = generate_derivatives(decoded, sizes.to_vec()).await?;
+    store_derivatives(db, storage, game, image, derivatives).await?;
+    // The hash is set last, so images are tried again until they are done.
+    image
+        .set_perceptual_hash(db, hash)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Compute the perceptual hashes and derivatives of the images uploaded
+/// before they were computed on upload. Images that fail are skipped (and
+/// tried again on the next backfill). Returns the number of images done.
+pub async fn backfill(
+    pool: &RetronomiconDbPool,
+    storage: &Storage,
+    sizes: &[u32],
+) -> Result<usize, String> {
+    let mut db = pool.connection().await?;
+    let mut after_id = 0;
+    let mut count = 0;
+    loop {
+        let images = models::GameImage::list_without_hash(&mut db, after_id, BACKFILL_BATCH_SIZE)
+            .await
+            .map_err(|e| e.to_string())?;
+        if images.is_empty() {
+            return Ok(count);
+        }
+
+        for (image, game) in images {
+            after_id = image.id;
+            match backfill_image(&mut db, storage, &game, &image, sizes).await {
+                Ok(()) => count += 1,
+                Err(e) => warn!("Could not backfill game image {}: {e}", image.id),
+            }
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use crate::fairings::config::RetronomiconConfig;
+use crate::guards::emailer::EmailGuard;
+use retronomicon_db::models;
+use retronomicon_db::Db;
+use retronomicon_dto::notifications::NotificationKind;
+use rocket::{tokio, warn};
+use serde_json::{json, Value};
+
+/// Notify a user of an event, through the channels they chose for this kind
+/// of notification. Emails are sent in the background. Notifications are
+/// sent after the event happened, so failing to send them is only logged and
+/// never fails the request.
+pub async fn notify(
+    db: &mut Db,
+    config: &RetronomiconConfig,
+    emailer: &EmailGuard,
+    user: &models::User,
+    kind: NotificationKind,
+    data: Value,
+) {
+    let preference = match models::NotificationPreference::get(db, user.id, kind).await {
+        Ok(preference) => preference,
+        Err(e) => {
+            warn!(
+                "Could not get {kind} notification preference of user {}: {e}",
+                user.id
+            );
+            return;
+        }
+    };
+
+    if preference.email {
+        let mut email_data = data.clone();
+        email_data["url"] = json!(config.base_url);
+        let emailer = emailer.clone();
+        let email = user.email.clone();
+        let user_id = user.id;
+        // Sending an email blocks until the SMTP server answers.
+        tokio::task::spawn_blocking(move || {
+            if let Err((_, e)) = emailer.send_notification(&email, kind, &email_data) {
+                warn!("Could not send {kind} notification to user {user_id}: {e}");
+            }
+        });
+    }
+
+    if preference.in_app {
+        if let Err(e) = models::Notification::create(db, user.id, kind, data).await {
+            warn!(
+                "Could not create {kind} notification for user {}: {e}",
+                user.id
+            );
+        }
+    }
+}
+
+/// Notify the followers of a core of an event about one of its releases, and
+/// its uploader if `notify_uploader` is set. Each user is only notified once,
+/// and `except` (the user who caused the event) is skipped.
+#[allow(clippy::too_many_arguments)]
+pub async fn notify_release(
+    db: &mut Db,
+    config: &RetronomiconConfig,
+    emailer: &EmailGuard,
+    except: i32,
+    kind: NotificationKind,
+    core: &models::Core,
+    release: &models::CoreRelease,
+    notify_uploader: bool,
+) {
+    let mut users = Vec::new();
+    if notify_uploader {
+        match models::User::from_id(db, release.uploader_id).await {
+            Ok(user) => users.push(user),
+            Err(e) => warn!("Could not get the uploader of release {}: {e}", release.id),
+        }
+    }
+    match core.followers(db).await {
+        Ok(followers) => users.extend(followers),
+        Err(e) => warn!("Could not get the followers of core {}: {e}", core.id),
+    }
+
+    let data = json!({
+        "core_id": core.id,
+        "core_slug": core.slug,
+        "core_name": core.name,
+        "release_id": release.id,
+        "version": release.version,
+    });
+
+    let mut notified = vec![except];
+    for user in users {
+        if notified.contains(&user.id) {
+            continue;
+        }
+        notified.push(user.id);
+        notify(db, config, emailer, &user, kind, data.clone()).await;
+    }
+}
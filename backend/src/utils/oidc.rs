@@ -0,0 +1,405 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use rand::Rng;
+use rocket::http::{Cookie, CookieJar, Status};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+fn default_scopes() -> Vec<String> {
+    vec![
+        "openid".to_string(),
+        "email".to_string(),
+        "profile".to_string(),
+    ]
+}
+
+fn default_email_claim() -> String {
+    "email".to_string()
+}
+
+fn default_username_claim() -> String {
+    "preferred_username".to_string()
+}
+
+fn default_avatar_claim() -> String {
+    "picture".to_string()
+}
+
+/// The claims of the ID token (or user info) used for the details of users.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClaimMapping {
+    #[serde(default = "default_email_claim")]
+    pub email: String,
+    #[serde(default = "default_username_claim")]
+    pub username: String,
+    #[serde(default = "default_avatar_claim")]
+    pub avatar: String,
+}
+
+impl Default for ClaimMapping {
+    fn default() -> Self {
+        Self {
+            email: default_email_claim(),
+            username: default_username_claim(),
+            avatar: default_avatar_claim(),
+        }
+    }
+}
+
+/// A generic OpenID Connect provider, configured in the `oidc.<name>`
+/// section of the configuration.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OidcProviderConfig {
+    /// The name shown to users. Defaults to the name of the section.
+    #[serde(default)]
+    pub display_name: Option<String>,
+
+    /// The issuer, which must match the discovery document and ID tokens.
+    pub issuer: String,
+
+    /// The URL of the discovery document. Defaults to
+    /// `{issuer}/.well-known/openid-configuration`.
+    #[serde(default)]
+    pub discovery_url: Option<String>,
+
+    pub client_id: String,
+    pub client_secret: String,
+
+    /// The URL the provider redirects to. Defaults to
+    /// `{base_url}api/auth/oidc/{name}`.
+    #[serde(default)]
+    pub redirect_uri: Option<String>,
+
+    #[serde(default = "default_scopes")]
+    pub scopes: Vec<String>,
+
+    #[serde(default)]
+    pub claims: ClaimMapping,
+
+    /// Endpoints overriding the ones of the discovery document. If the
+    /// authorization, token and JWKS endpoints are all set, the discovery
+    /// document is not fetched.
+    #[serde(default)]
+    pub authorization_endpoint: Option<String>,
+    #[serde(default)]
+    pub token_endpoint: Option<String>,
+    #[serde(default)]
+    pub userinfo_endpoint: Option<String>,
+    #[serde(default)]
+    pub jwks_uri: Option<String>,
+}
+
+/// The parts of a discovery document that are used.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    #[serde(default)]
+    pub userinfo_endpoint: Option<String>,
+    pub jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+    #[serde(default)]
+    access_token: Option<String>,
+}
+
+/// The account of a user with a provider.
+#[derive(Debug, Clone)]
+pub struct OidcIdentity {
+    pub subject: String,
+    pub email: Option<String>,
+    /// Whether the provider verified the email of the user.
+    pub email_verified: bool,
+    pub username: Option<String>,
+    pub avatar_url: Option<String>,
+}
+
+fn http_error(e: impl ToString) -> (Status, String) {
+    (Status::BadGateway, e.to_string())
+}
+
+impl OidcProviderConfig {
+    pub fn display_name(&self, name: &str) -> String {
+        self.display_name
+            .clone()
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    pub fn redirect_uri(&self, name: &str, base_url: &str) -> Result<String, (Status, String)> {
+        match &self.redirect_uri {
+            Some(uri) => Ok(uri.clone()),
+            None => url::Url::parse(base_url)
+                .and_then(|base| base.join(&format!("api/auth/oidc/{name}")))
+                .map(|url| url.to_string())
+                .map_err(|e| (Status::InternalServerError, e.to_string())),
+        }
+    }
+
+    /// The endpoints of the provider, from its discovery document unless
+    /// they are all configured. The document is fetched on every login.
+    pub async fn metadata(&self) -> Result<ProviderMetadata, (Status, String)> {
+        let mut metadata = match (
+            &self.authorization_endpoint,
+            &self.token_endpoint,
+            &self.jwks_uri,
+        ) {
+            (Some(authorization_endpoint), Some(token_endpoint), Some(jwks_uri)) => {
+                ProviderMetadata {
+                    issuer: self.issuer.clone(),
+                    authorization_endpoint: authorization_endpoint.clone(),
+                    token_endpoint: token_endpoint.clone(),
+                    userinfo_endpoint: None,
+                    jwks_uri: jwks_uri.clone(),
+                }
+            }
+            _ => {
+                let url = self.discovery_url.clone().unwrap_or_else(|| {
+                    format!(
+                        "{}/.well-known/openid-configuration",
+                        self.issuer.trim_end_matches('/')
+                    )
+                });
+                let metadata: ProviderMetadata = reqwest::get(url)
+                    .await
+                    .and_then(|r| r.error_for_status())
+                    .map_err(http_error)?
+                    .json()
+                    .await
+                    .map_err(http_error)?;
+                if metadata.issuer != self.issuer {
+                    return Err((
+                        Status::BadGateway,
+                        "Issuer of the discovery document does not match".to_string(),
+                    ));
+                }
+                metadata
+            }
+        };
+
+        if let Some(endpoint) = &self.authorization_endpoint {
+            metadata.authorization_endpoint = endpoint.clone();
+        }
+        if let Some(endpoint) = &self.token_endpoint {
+            metadata.token_endpoint = endpoint.clone();
+        }
+        if let Some(endpoint) = &self.userinfo_endpoint {
+            metadata.userinfo_endpoint = Some(endpoint.clone());
+        }
+        if let Some(endpoint) = &self.jwks_uri {
+            metadata.jwks_uri = endpoint.clone();
+        }
+        Ok(metadata)
+    }
+
+    /// The URL to redirect users to, to log in with the provider.
+    pub fn authorization_url(
+        &self,
+        metadata: &ProviderMetadata,
+        redirect_uri: &str,
+        state: &OidcLoginState,
+    ) -> Result<String, (Status, String)> {
+        let mut url = url::Url::parse(&metadata.authorization_endpoint)
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.client_id)
+            .append_pair("redirect_uri", redirect_uri)
+            .append_pair("scope", &self.scopes.join(" "))
+            .append_pair("state", &state.state)
+            .append_pair("nonce", &state.nonce)
+            .append_pair("code_challenge", &state.code_challenge())
+            .append_pair("code_challenge_method", "S256");
+        Ok(url.to_string())
+    }
+
+    /// Exchange an authorization code for the identity of the user. The ID
+    /// token is verified with the keys of the provider.
+    pub async fn authenticate(
+        &self,
+        metadata: &ProviderMetadata,
+        redirect_uri: &str,
+        state: &OidcLoginState,
+        code: &str,
+    ) -> Result<OidcIdentity, (Status, String)> {
+        let client = reqwest::Client::new();
+        let tokens: TokenResponse = client
+            .post(&metadata.token_endpoint)
+            .basic_auth(&self.client_id, Some(&self.client_secret))
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", redirect_uri),
+                ("code_verifier", &state.code_verifier),
+            ])
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(http_error)?
+            .json()
+            .await
+            .map_err(http_error)?;
+
+        let mut claims = self.verify_id_token(metadata, &tokens.id_token).await?;
+        if claims.get("nonce").and_then(Value::as_str) != Some(state.nonce.as_str()) {
+            return Err((Status::Unauthorized, "Invalid nonce".to_string()));
+        }
+        let subject = claims
+            .get("sub")
+            .and_then(Value::as_str)
+            .ok_or((Status::Unauthorized, "No subject".to_string()))?
+            .to_string();
+
+        // Complete the claims with the user info, if available.
+        if let (Some(endpoint), Some(access_token)) =
+            (&metadata.userinfo_endpoint, &tokens.access_token)
+        {
+            let info: Map<String, Value> = client
+                .get(endpoint)
+                .bearer_auth(access_token)
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(http_error)?
+                .json()
+                .await
+                .map_err(http_error)?;
+            if info.get("sub").and_then(Value::as_str) == Some(subject.as_str()) {
+                for (key, value) in info {
+                    claims.entry(key).or_insert(value);
+                }
+            }
+        }
+
+        Ok(self.map_claims(subject, &claims))
+    }
+
+    async fn verify_id_token(
+        &self,
+        metadata: &ProviderMetadata,
+        id_token: &str,
+    ) -> Result<Map<String, Value>, (Status, String)> {
+        let header = jsonwebtoken::decode_header(id_token)
+            .map_err(|e| (Status::Unauthorized, e.to_string()))?;
+        if matches!(
+            header.alg,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            return Err((
+                Status::Unauthorized,
+                "Unsupported ID token algorithm".to_string(),
+            ));
+        }
+
+        let jwks: JwkSet = reqwest::get(&metadata.jwks_uri)
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(http_error)?
+            .json()
+            .await
+            .map_err(http_error)?;
+        let jwk = match &header.kid {
+            Some(kid) => jwks.find(kid),
+            None if jwks.keys.len() == 1 => jwks.keys.first(),
+            None => None,
+        }
+        .ok_or((Status::Unauthorized, "Unknown ID token key".to_string()))?;
+        let key = DecodingKey::from_jwk(jwk).map_err(|e| (Status::Unauthorized, e.to_string()))?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&metadata.issuer]);
+        validation.set_audience(&[&self.client_id]);
+        jsonwebtoken::decode::<Map<String, Value>>(id_token, &key, &validation)
+            .map(|token| token.claims)
+            .map_err(|e| (Status::Unauthorized, e.to_string()))
+    }
+
+    fn map_claims(&self, subject: String, claims: &Map<String, Value>) -> OidcIdentity {
+        let claim = |name: &str| {
+            claims
+                .get(name)
+                .and_then(Value::as_str)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+
+        OidcIdentity {
+            subject,
+            email: claim(&self.claims.email),
+            email_verified: claims
+                .get("email_verified")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            username: claim(&self.claims.username),
+            avatar_url: claim(&self.claims.avatar),
+        }
+    }
+}
+
+/// A login with a provider in progress. It is kept in a private cookie
+/// between the redirection to the provider and its callback.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcLoginState {
+    pub provider: String,
+    pub state: String,
+    pub nonce: String,
+    pub code_verifier: String,
+    /// The user to link the account to, instead of logging in.
+    pub link_user_id: Option<i32>,
+    pub exp: i64,
+}
+
+impl OidcLoginState {
+    const COOKIE: &'static str = "oidc_state";
+
+    /// The number of minutes a user has to log in with the provider.
+    const EXPIRY_MINUTES: i64 = 10;
+
+    pub fn new(provider: &str, link_user_id: Option<i32>) -> Self {
+        let random = || URL_SAFE_NO_PAD.encode(rand::thread_rng().gen::<[u8; 32]>());
+        Self {
+            provider: provider.to_string(),
+            state: random(),
+            nonce: random(),
+            code_verifier: random(),
+            link_user_id,
+            exp: chrono::Utc::now()
+                .checked_add_signed(chrono::Duration::minutes(Self::EXPIRY_MINUTES))
+                .expect("Invalid timestamp")
+                .timestamp(),
+        }
+    }
+
+    /// The PKCE challenge of the code verifier.
+    fn code_challenge(&self) -> String {
+        URL_SAFE_NO_PAD.encode(Sha256::digest(self.code_verifier.as_bytes()))
+    }
+
+    pub fn update_cookie(&self, cookies: &CookieJar<'_>) {
+        cookies.add_private(
+            Cookie::build((Self::COOKIE, serde_json::to_string(self).unwrap()))
+                .same_site(rocket::http::SameSite::Lax)
+                .build(),
+        );
+    }
+
+    /// Take the login in progress for a provider from the cookies, if it
+    /// matches the state returned by the provider and has not expired.
+    pub fn take(cookies: &CookieJar<'_>, provider: &str, state: &str) -> Option<Self> {
+        let login = cookies
+            .get_private(Self::COOKIE)
+            .and_then(|cookie| serde_json::from_str::<Self>(cookie.value()).ok());
+        cookies.remove_private(Self::COOKIE);
+
+        login.filter(|login| {
+            login.provider == provider
+                && login.state == state
+                && login.exp >= chrono::Utc::now().timestamp()
+        })
+    }
+}
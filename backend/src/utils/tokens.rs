@@ -0,0 +1,71 @@
+use retronomicon_db::models;
+use retronomicon_db::types::FetchModel;
+use retronomicon_db::Db;
+use retronomicon_dto::tokens::{TokenPermission, TokenScope};
+use retronomicon_dto::types::IdOrSlug;
+use rocket::http::{Method, Status};
+use rocket::Request;
+
+/// The permission a personal access token needs for a request. Reading only
+/// needs `read`, changing releases (and their artifacts, uploads and
+/// signatures) needs `releases:write`, and anything else needs `write`.
+fn required_permission(request: &Request<'_>) -> TokenPermission {
+    let route_name = request.route().and_then(|r| r.name.as_deref());
+
+    match request.method() {
+        Method::Get | Method::Head => TokenPermission::Read,
+        _ if route_name.is_some_and(|name| name.starts_with("cores_releases_")) => {
+            TokenPermission::ReleasesWrite
+        }
+        _ => TokenPermission::Write,
+    }
+}
+
+/// The core targeted by a request, for routes under `/cores/<core_id>`.
+async fn target_core(
+    db: &mut Db,
+    request: &Request<'_>,
+) -> Result<Option<models::Core>, (Status, String)> {
+    match (request.routed_segment(0), request.routed_segment(1)) {
+        (Some("cores"), Some(core_id)) => {
+            models::Core::from_id_or_slug(db, IdOrSlug::parse(core_id))
+                .await
+                .map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Whether any of the scopes of a token allows a request. Scopes limited to a
+/// team or a core allow reading anything, but only allow writing to routes
+/// under a core matching the limit.
+pub async fn scopes_allow(
+    db: &mut Db,
+    request: &Request<'_>,
+    scopes: &[TokenScope],
+) -> Result<bool, (Status, String)> {
+    let permission = required_permission(request);
+    let mut core = None;
+
+    for scope in scopes {
+        if !scope.permission.includes(permission) {
+            continue;
+        }
+        if permission == TokenPermission::Read || (scope.team.is_none() && scope.core.is_none()) {
+            return Ok(true);
+        }
+
+        if core.is_none() {
+            core = Some(target_core(db, request).await?);
+        }
+        if let Some(Some(core)) = &core {
+            if scope.core.map_or(true, |id| id == core.id)
+                && scope.team.map_or(true, |id| id == core.owner_team_id)
+            {
+                return Ok(true);
+            }
+        }
+    }
+
+    Ok(false)
+}
@@ -0,0 +1,127 @@
+use rand::Rng;
+use retronomicon_db::models::{UserRecoveryCode, UserTwoFactor};
+use retronomicon_db::Db;
+use rocket::http::Status;
+use totp_rs::{Algorithm, TOTP};
+
+/// The issuer shown in authenticator apps.
+const ISSUER: &str = "Retronomicon";
+
+/// The duration of a TOTP time step, in seconds.
+const STEP: u64 = 30;
+
+/// The number of invalid codes that can be tried before being throttled.
+const MAX_FAILED_ATTEMPTS: i32 = 5;
+
+/// How long a user is throttled after too many invalid codes, in minutes.
+const THROTTLE_MINUTES: i64 = 5;
+
+fn totp(secret: &[u8], account: &str) -> Result<TOTP, (Status, String)> {
+    TOTP::new(
+        Algorithm::SHA1,
+        6,
+        1,
+        STEP,
+        secret.to_vec(),
+        Some(ISSUER.to_string()),
+        account.to_string(),
+    )
+    .map_err(|e| (Status::InternalServerError, e.to_string()))
+}
+
+/// Generate a new random TOTP secret.
+pub fn generate_secret() -> Vec<u8> {
+    rand::thread_rng().gen::<[u8; 20]>().to_vec()
+}
+
+/// The secret encoded in base32, for authenticator apps that can't scan the
+/// otpauth URI.
+pub fn secret_base32(secret: &[u8]) -> Result<String, (Status, String)> {
+    Ok(totp(secret, "")?.get_secret_base32())
+}
+
+/// The otpauth URI of a secret, usually shown as a QR code.
+pub fn otpauth_uri(secret: &[u8], account: &str) -> Result<String, (Status, String)> {
+    Ok(totp(secret, account)?.get_url())
+}
+
+/// Find the time step a code is valid for, allowing one step of clock skew
+/// on each side.
+pub fn verify_code(secret: &[u8], code: &str, time: u64) -> Option<i64> {
+    let totp = totp(secret, "").ok()?;
+    [time.saturating_sub(STEP), time, time + STEP]
+        .into_iter()
+        .find(|t| totp.generate(*t) == code)
+        .map(|t| (t / STEP) as i64)
+}
+
+/// Verify a TOTP code of a user, returning the time step it is valid for.
+/// Replayed codes are rejected. Failed attempts are recorded and throttled.
+pub async fn verify_totp(
+    db: &mut Db,
+    two_factor: &UserTwoFactor,
+    code: &str,
+) -> Result<i64, (Status, String)> {
+    check_throttle(two_factor)?;
+
+    let now = chrono::Utc::now().timestamp() as u64;
+    if let Some(step) = verify_code(&two_factor.secret, code.trim(), now) {
+        if two_factor
+            .use_step(db, step)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?
+        {
+            return Ok(step);
+        }
+    }
+
+    fail(db, two_factor).await
+}
+
+/// Verify a TOTP or recovery code of a user. Recovery codes can only be used
+/// once.
+pub async fn verify(
+    db: &mut Db,
+    two_factor: &UserTwoFactor,
+    code: &str,
+) -> Result<(), (Status, String)> {
+    let code = code.trim();
+    if code.len() == 6 && code.chars().all(|c| c.is_ascii_digit()) {
+        return verify_totp(db, two_factor, code).await.map(|_| ());
+    }
+
+    check_throttle(two_factor)?;
+    if UserRecoveryCode::consume(db, two_factor.user_id, code)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?
+    {
+        two_factor
+            .reset_failures(db)
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+        return Ok(());
+    }
+
+    fail(db, two_factor).await
+}
+
+fn check_throttle(two_factor: &UserTwoFactor) -> Result<(), (Status, String)> {
+    if two_factor.is_throttled(
+        MAX_FAILED_ATTEMPTS,
+        chrono::Duration::minutes(THROTTLE_MINUTES),
+    ) {
+        return Err((
+            Status::TooManyRequests,
+            "Too many invalid codes, try again later".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+async fn fail<T>(db: &mut Db, two_factor: &UserTwoFactor) -> Result<T, (Status, String)> {
+    two_factor
+        .record_failure(db)
+        .await
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+    Err((Status::Unauthorized, "Invalid code".to_string()))
+}
@@ -0,0 +1,310 @@
+use crate::guards::storage::{Storage, StorageBucket};
+use retronomicon_db::models;
+use retronomicon_db::DbConnection;
+use sha1::Digest;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// S3 refuses parts smaller than 5 MiB, except for the last one.
+pub const MIN_PART_SIZE: i64 = 5 * 1024 * 1024;
+
+/// The maximum size of a single part. Parts are held in memory while they're
+/// being sent to the storage.
+pub const MAX_PART_SIZE: i64 = 64 * 1024 * 1024;
+
+/// Uploads that were not updated for this long (in seconds) are expired.
+const UPLOAD_EXPIRY: i64 = 7 * 24 * 60 * 60;
+
+/// Number of uploads to expire in a single pass.
+const UPLOAD_EXPIRY_BATCH_SIZE: i64 = 100;
+
+/// Computes the checksums of an artifact incrementally.
+#[derive(Clone)]
+pub struct ArtifactHasher {
+    size: i64,
+    md5: md5::Context,
+    sha1: sha1::Sha1,
+    sha256: sha2::Sha256,
+}
+
+impl Default for ArtifactHasher {
+    fn default() -> Self {
+        Self {
+            size: 0,
+            md5: md5::Context::new(),
+            sha1: sha1::Sha1::new(),
+            sha256: sha2::Sha256::new(),
+        }
+    }
+}
+
+impl ArtifactHasher {
+    pub fn update(&mut self, data: &[u8]) {
+        self.size += data.len() as i64;
+        self.md5.consume(data);
+        self.sha1.update(data);
+        self.sha256.update(data);
+    }
+
+    /// The number of bytes hashed so far.
+    pub fn size(&self) -> i64 {
+        self.size
+    }
+
+    /// Returns the MD5, SHA1 and SHA256 of all the data.
+    pub fn finalize(self) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        (
+            self.md5.compute().to_vec(),
+            self.sha1.finalize().to_vec(),
+            self.sha256.finalize().to_vec(),
+        )
+    }
+}
+
+/// Another part of an upload is currently being uploaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UploadBusy;
+
+/// The checksums of uploads in progress, computed as their parts are
+/// received, so they do not need to be read back from the storage. A hasher
+/// is checked out while a part is being uploaded, and checked back in once
+/// the part has been stored.
+///
+/// This is only kept in memory. If the server restarts, or parts are sent
+/// to another instance, the hasher is lost and the checksums are computed
+/// from the storage once the upload is assembled.
+#[derive(Default, Clone)]
+pub struct UploadHashers(Arc<Mutex<HashMap<i32, Option<ArtifactHasher>>>>);
+
+impl UploadHashers {
+    /// Take the hasher of an upload, if it hashed the `received` bytes of
+    /// the upload so far. A new hasher is created if nothing was received.
+    pub fn checkout(&self, id: i32, received: i64) -> Result<Option<ArtifactHasher>, UploadBusy> {
+        let mut map = self.0.lock().expect("Upload hashers lock poisoned");
+        match map.insert(id, None) {
+            Some(None) => Err(UploadBusy),
+            Some(Some(hasher)) => Ok(Some(hasher).filter(|h| h.size == received)),
+            None if received == 0 => Ok(Some(ArtifactHasher::default())),
+            None => Ok(None),
+        }
+    }
+
+    /// Return a hasher after using it. Without a hasher, the upload is
+    /// forgotten.
+    pub fn checkin(&self, id: i32, hasher: Option<ArtifactHasher>) {
+        let mut map = self.0.lock().expect("Upload hashers lock poisoned");
+        match hasher {
+            Some(hasher) => map.insert(id, Some(hasher)),
+            None => map.remove(&id),
+        };
+    }
+
+    /// Forget about an upload.
+    pub fn remove(&self, id: i32) {
+        let mut map = self.0.lock().expect("Upload hashers lock poisoned");
+        map.remove(&id);
+    }
+}
+
+/// Compute the size and checksums of a file by reading it from the storage.
+pub async fn hash_stored_file(
+    storage: &Storage,
+    bucket: StorageBucket,
+    path: &str,
+) -> Result<ArtifactHasher, String> {
+    let mut hasher = ArtifactHasher::default();
+    storage
+        .read(bucket, path, &mut |chunk| hasher.update(chunk))
+        .await?;
+    Ok(hasher)
+}
+
+/// Discard what was stored for an upload: its parts, or the assembled file
+/// once its checksums were recorded.
+pub async fn discard_upload(
+    storage: &Storage,
+    upload: &models::CoreReleaseUpload,
+) -> Result<(), String> {
+    if upload.checksums().is_some() {
+        storage
+            .delete(StorageBucket::Cores, &upload.storage_path)
+            .await
+    } else {
+        storage
+            .abort_multipart(
+                StorageBucket::Cores,
+                &upload.storage_path,
+                &upload.storage_upload_id,
+            )
+            .await
+    }
+}
+
+/// Delete uploads that were abandoned, along with what was stored for them.
+/// Returns the number of uploads deleted.
+pub async fn expire_uploads(
+    db: &mut DbConnection,
+    storage: &Storage,
+    hashers: &UploadHashers,
+) -> Result<usize, String> {
+    let updated_before = chrono::Utc::now().naive_utc() - chrono::Duration::seconds(UPLOAD_EXPIRY);
+    let uploads =
+        models::CoreReleaseUpload::list_stale(db, updated_before, UPLOAD_EXPIRY_BATCH_SIZE)
+            .await
+            .map_err(|e| e.to_string())?;
+
+    let mut count = 0;
+    for upload in uploads {
+        // Keep the upload if its parts cannot be discarded, to try again later.
+        if let Err(e) = discard_upload(storage, &upload).await {
+            rocket::warn!("Failed to discard expired upload {}: {}", upload.id, e);
+            continue;
+        }
+        if let Err(e) = upload.delete(db).await {
+            rocket::warn!("Failed to delete expired upload {}: {}", upload.id, e);
+            continue;
+        }
+        hashers.remove(upload.id);
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// The size and checksums a client expects an artifact to have. Those that
+/// are missing are not verified.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExpectedChecksums {
+    pub size: Option<i64>,
+    pub md5: Option<Vec<u8>>,
+    pub sha1: Option<Vec<u8>>,
+    pub sha256: Option<Vec<u8>>,
+}
+
+fn merge_one<T: PartialEq>(name: &str, a: Option<T>, b: Option<T>) -> Result<Option<T>, String> {
+    match (a, b) {
+        (Some(a), Some(b)) if a != b => Err(format!("Conflicting expected {name} values.")),
+        (a, b) => Ok(a.or(b)),
+    }
+}
+
+fn parse_hex(name: &str, value: &str, len: usize) -> Result<Vec<u8>, String> {
+    match hex::decode(value.trim()) {
+        Ok(v) if v.len() == len => Ok(v),
+        _ => Err(format!("Invalid {name}: {value:?}")),
+    }
+}
+
+fn parse_base64(name: &str, value: &str, len: usize) -> Result<Vec<u8>, String> {
+    use base64::Engine;
+
+    match base64::engine::general_purpose::STANDARD.decode(value.trim()) {
+        Ok(v) if v.len() == len => Ok(v),
+        _ => Err(format!("Invalid {name}: {value:?}")),
+    }
+}
+
+impl ExpectedChecksums {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Parse the checksums from form fields. Checksums are hex encoded.
+    pub fn from_fields(
+        size: Option<&str>,
+        md5: Option<&str>,
+        sha1: Option<&str>,
+        sha256: Option<&str>,
+    ) -> Result<Self, String> {
+        Ok(Self {
+            size: size
+                .map(|s| {
+                    s.trim()
+                        .parse::<i64>()
+                        .map_err(|_| format!("Invalid size: {s:?}"))
+                })
+                .transpose()?,
+            md5: md5.map(|v| parse_hex("md5", v, 16)).transpose()?,
+            sha1: sha1.map(|v| parse_hex("sha1", v, 20)).transpose()?,
+            sha256: sha256.map(|v| parse_hex("sha256", v, 32)).transpose()?,
+        })
+    }
+
+    /// Parse the checksums from the `Digest` (RFC 3230) and `Content-MD5`
+    /// headers, and the size from the `X-Expected-Size` header. Checksums
+    /// are base64 encoded, and unknown algorithms are ignored.
+    pub fn from_headers(
+        size: Option<&str>,
+        digest: Option<&str>,
+        content_md5: Option<&str>,
+    ) -> Result<Self, String> {
+        let mut result = Self::from_fields(size, None, None, None)?;
+        result.md5 = content_md5
+            .map(|v| parse_base64("Content-MD5", v, 16))
+            .transpose()?;
+
+        for entry in digest.into_iter().flat_map(|d| d.split(',')) {
+            let (algorithm, value) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid Digest: {entry:?}"))?;
+            let (name, len) = match algorithm.trim().to_ascii_lowercase().as_str() {
+                "md5" => ("md5", 16),
+                "sha" => ("sha1", 20),
+                "sha-256" => ("sha256", 32),
+                _ => continue,
+            };
+            let value = Some(parse_base64(name, value, len)?);
+
+            match name {
+                "md5" => result.md5 = merge_one(name, result.md5, value)?,
+                "sha1" => result.sha1 = merge_one(name, result.sha1, value)?,
+                _ => result.sha256 = merge_one(name, result.sha256, value)?,
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Combine with other expected checksums, e.g. from form fields and
+    /// headers. Both must agree on values they both specify.
+    pub fn merge(self, other: Self) -> Result<Self, String> {
+        Ok(Self {
+            size: merge_one("size", self.size, other.size)?,
+            md5: merge_one("md5", self.md5, other.md5)?,
+            sha1: merge_one("sha1", self.sha1, other.sha1)?,
+            sha256: merge_one("sha256", self.sha256, other.sha256)?,
+        })
+    }
+
+    /// Verify the size and checksums of the data received. The error lists
+    /// all mismatches.
+    pub fn verify(&self, size: i64, md5: &[u8], sha1: &[u8], sha256: &[u8]) -> Result<(), String> {
+        let mut errors = Vec::new();
+        if let Some(expected) = self.size {
+            if expected != size {
+                errors.push(format!("size (expected {expected}, got {size})"));
+            }
+        }
+
+        for (name, expected, actual) in [
+            ("md5", &self.md5, md5),
+            ("sha1", &self.sha1, sha1),
+            ("sha256", &self.sha256, sha256),
+        ] {
+            if let Some(expected) = expected {
+                if expected.as_slice() != actual {
+                    errors.push(format!(
+                        "{name} (expected {}, got {})",
+                        hex::encode(expected),
+                        hex::encode(actual)
+                    ));
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("Artifact does not match: {}", errors.join(", ")))
+        }
+    }
+}
@@ -1,8 +1,9 @@
 use crate::ghenkins::UserParam;
 use crate::user::User as CucumberUser;
 use anyhow::{anyhow, Error};
-use backend::fairings::config::{DbPepper, JwtKeys, RetronomiconConfig};
+use backend::fairings::config::{DbPepper, JwtKeys, ManifestSigningKey, RetronomiconConfig};
 use backend::routes::v1;
+use backend::utils::uploads::UploadHashers;
 use backend::{config, routes};
 use cucumber::{writer, World as _};
 use retronomicon_db as db;
@@ -28,8 +29,50 @@ struct World {
     users: BTreeMap<String, Arc<Mutex<CucumberUser>>>,
     teams: BTreeMap<String, dto::teams::TeamCreateResponse>,
 
+    /// Users authenticated with a personal access token, and the token IDs.
+    pub tokens: BTreeMap<String, Arc<Mutex<CucumberUser>>>,
+    pub token_ids: BTreeMap<String, i32>,
+
+    /// Other sessions of users, logged in with their password.
+    pub sessions: BTreeMap<String, Arc<Mutex<CucumberUser>>>,
+
+    /// The TOTP secret and recovery codes of users, by email.
+    pub two_factor: BTreeMap<String, (Vec<u8>, Vec<String>)>,
+    pub last_two_factor_code: Option<String>,
+
+    /// The email users last changed their email to.
+    pub last_email: Option<String>,
+
+    /// Where the last OpenID Connect login was redirected to.
+    pub oidc_redirect: Option<String>,
+
+    pub cores: BTreeMap<String, i32>,
     pub games: BTreeMap<String, i32>,
+    /// Files to identify by name, with random checksums.
+    pub files: BTreeMap<String, dto::games::GameIdentifyFile>,
     pub systems: BTreeMap<String, i32>,
+    /// The last DAT import, with the ID of its system.
+    pub dat_import: Option<(i32, i32)>,
+    /// The last exported DAT.
+    pub dat_export: Option<datary::Datafile>,
+
+    /// A random word added to the names of items and to searches, so
+    /// searches only find the items of their scenario.
+    pub search_word: String,
+    pub search: Option<dto::search::SearchResponse>,
+
+    /// Releases by name, with the ID of their core.
+    pub releases: BTreeMap<String, (i32, i32)>,
+    /// The platform of the releases of a core, by core name.
+    pub platforms: BTreeMap<String, i32>,
+    pub uploads: BTreeMap<String, i32>,
+    /// The files of a device, to check which cores can run on it.
+    pub device_files: Vec<dto::cores::required_files::DeviceFile>,
+    /// The last compatibility check of the device files.
+    pub compatibility: Option<Vec<dto::cores::required_files::CoreCompatibility>>,
+
+    /// Team signing keys by name, with the ID of their team and their own ID.
+    pub signing_keys: BTreeMap<String, (i32, i32, ed25519_dalek::SigningKey)>,
 
     last_result: Option<Result<String, Error>>,
 }
@@ -57,6 +100,10 @@ impl World {
 
                 Ok(self.users.get(name).expect("Just created user").clone())
             }
+            UserParam::Token(name) => Ok(self.tokens.get(name).expect("Unknown token").clone()),
+            UserParam::Session(name) => {
+                Ok(self.sessions.get(name).expect("Unknown session").clone())
+            }
             UserParam::Anonymous => Ok(Arc::new(Mutex::new(
                 CucumberUser::anonymous(self.client.clone()).await?,
             ))),
@@ -85,8 +132,13 @@ impl World {
 
     async fn new() -> Self {
         // Relative to the root of the crate.
+        // Lists are appended to the ones of `Rocket.toml`, so replace the team
+        // creators here. Users who change their email to another domain cannot
+        // create teams.
         let figment =
-            config::create_figment(&[relative!("tests/Rocket.test.toml").into()], "debug").unwrap();
+            config::create_figment(&[relative!("tests/Rocket.test.toml").into()], "debug")
+                .unwrap()
+                .merge(("team_creators", ["*@user-*", "*@cucumber-admin-*"]));
 
         let secret_key = figment
             .find_value("secret_key")
@@ -113,13 +165,19 @@ impl World {
 
         let rocket = rocket::custom(figment)
             .mount("/", v1::routes())
+            .mount(
+                "/api",
+                rocket::routes![routes::auth::oidc_callback, routes::files::files_download],
+            )
             .attach(db::RetronomiconDbPool::init())
             .attach(OAuth2::<routes::auth::GitHubUserInfo>::fairing("github"))
             .attach(OAuth2::<routes::auth::GoogleUserInfo>::fairing("google"))
             .attach(OAuth2::<routes::auth::PatreonUserInfo>::fairing("patreon"))
             .attach(AdHoc::config::<RetronomiconConfig>())
             .manage(JwtKeys::from_base64(&jwt_secret_b64))
-            .manage(DbPepper::from_base64(&db_pepper));
+            .manage(DbPepper::from_base64(&db_pepper))
+            .manage(ManifestSigningKey::from_base64(&secret_key))
+            .manage(UploadHashers::default());
         let client = Arc::new(
             Client::untracked(rocket)
                 .await
@@ -131,8 +189,27 @@ impl World {
             admins: BTreeMap::new(),
             users: BTreeMap::new(),
             teams: BTreeMap::new(),
+            tokens: BTreeMap::new(),
+            token_ids: BTreeMap::new(),
+            sessions: BTreeMap::new(),
+            two_factor: BTreeMap::new(),
+            last_two_factor_code: None,
+            last_email: None,
+            oidc_redirect: None,
+            cores: BTreeMap::new(),
             games: BTreeMap::new(),
+            files: BTreeMap::new(),
             systems: BTreeMap::new(),
+            dat_import: None,
+            dat_export: None,
+            search_word: user::random_word(),
+            search: None,
+            releases: BTreeMap::new(),
+            platforms: BTreeMap::new(),
+            uploads: BTreeMap::new(),
+            device_files: Vec::new(),
+            compatibility: None,
+            signing_keys: BTreeMap::new(),
             last_result: None,
         }
     }
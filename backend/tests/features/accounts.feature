@@ -0,0 +1,32 @@
+Feature: Account deletion and export
+
+  Scenario: Users can export their data
+    Given team T1 is owned by admin A1
+    Then the export of admin A1 contains their email and team T1
+
+  Scenario: Users can delete their account
+    Given user U1 is not authenticated
+    When user U1 deletes their account
+    Then no error occured
+     And user U1 is logged out
+     And user U1 cannot log in with their initial password
+
+  Scenario: Deleting an account must be confirmed
+    Given user U1 is not authenticated
+    When user U1 deletes their account confirming with nope
+    Then an error occured
+     And user U1 is logged in
+
+  Scenario: The only owner of a team cannot delete their account
+    Given team T1 is owned by admin A1
+    When admin A1 deletes their account
+    Then an error occured
+     And admin A1 is logged in
+
+  Scenario: The only owner of a team can give it to a member before deleting their account
+    Given team T1 is owned by admin A1
+    When admin A1 invites admin A2 to team T1 as member
+     And admin A2 accepts the invitation to team T1
+     And admin A1 deletes their account giving team T1 to admin A2
+    Then team T1 will have admin A2 as owner
+     And admin A1 is logged out
@@ -0,0 +1,50 @@
+Feature: DAT exports
+
+  Scenario: Games of a system are exported with their files
+    Given game G1
+    When admin default adds artifact R1 to game G1 as "G1.cue" with role cue
+    And admin default adds artifact R2 to game G1 as "Disc 1/G1 (Track 1).bin" with role track
+    And anonymous user exports the DAT of system default
+    Then the exported DAT has games "G1"
+    And game G1 of the exported DAT has ROMs "G1.cue:good, Disc 1\G1 (Track 1).bin:good"
+
+  Scenario: Dump statuses are exported
+    Given game G0
+    When admin default imports a DAT with disc games G1 into system default
+    Then the DAT import is completed
+    When anonymous user exports the DAT of system default
+    Then game G1 of the exported DAT has ROMs "G1.cue:good, Disc\G1 (Track 1).bin:verified, Disc\G1 (Track 2).bin:baddump"
+
+  Scenario: Exports are filtered by tag
+    Given game G1
+    And tag T1
+    When admin default creates a game G2 on system default
+    And admin default tags game G1 with T1
+    And anonymous user exports the DAT of system default with tag T1
+    Then game G1 has tag T1
+    And the exported DAT has games "G1"
+
+  Scenario: Exports are filtered by year and region
+    Given game G1
+    When admin default creates a game G2 on system default
+    And admin default updates game G2 with year 1986
+    And admin default updates game G2 with a release in japan on 1986-02-21
+    And anonymous user exports the DAT of system default with year 1980..1990
+    Then the exported DAT has games "G2"
+    When anonymous user exports the DAT of system default with region japan
+    Then the exported DAT has games "G2"
+    When anonymous user exports the DAT of system default with region europe
+    Then the exported DAT has games ""
+
+  Scenario: Unknown tags are refused
+    Given game G1
+    When admin default tags game G1 with T1
+    Then an error occured
+
+  Scenario: Importing an exported DAT changes nothing
+    Given game G1
+    When admin default adds artifact R1 to game G1 as "Disc 1/G1.iso"
+    And admin default creates a game G2 on system default
+    And anonymous user exports the DAT of system default
+    And admin default imports the exported DAT into system default
+    Then the DAT import creates 0 games, updates 0 and orphans 0
@@ -0,0 +1,36 @@
+Feature: DAT imports
+
+  Scenario: Importing a DAT requires root team
+    Given a system S1 created by user U1 owned by team T1
+    When user U1 imports a DAT with games G1 into system S1
+    Then an error occured
+
+  Scenario: Games of a DAT are created with their ROMs
+    Given game G0
+    When admin default imports a DAT with games G1,G2 into system default
+    Then the DAT import is completed
+    And the DAT import creates 2 games, updates 0 and orphans 1
+    And game G1 was imported from the DAT
+    And game G2 was imported from the DAT
+    And anonymous user identifies file G2 as game G2 by hash
+
+  Scenario: Existing games are updated by name
+    Given game G1
+    When admin default imports a DAT with games G1 into system default
+    Then the DAT import is completed
+    And the DAT import creates 0 games, updates 1 and orphans 0
+    And game G1 was imported from the DAT
+
+  Scenario: A dry run only previews the import
+    Given game G1
+    When admin default imports a DAT with games G1,G2 into system default as a dry run
+    Then the DAT import is preview
+    And the DAT import creates 1 game, updates 1 and orphans 0
+    And anonymous user cannot identify file G2
+
+  Scenario: The preview of a dry run can be applied
+    Given game G0
+    When admin default imports a DAT with games G1 into system default as a dry run
+    And admin default applies the DAT import
+    Then the DAT import is completed
+    And game G1 was imported from the DAT
@@ -0,0 +1,33 @@
+Feature: Email change
+
+  Scenario: Users can change their email
+    Given user U1 is not authenticated
+    When user U1 changes their email to one at example.com
+    Then no error occured
+     And user U1 can log in with their new email
+     And user U1 cannot log in with their initial password
+
+  Scenario: Changing the email requires the password
+    Given user U1 is not authenticated
+    When user U1 changes their email to one at example.com with password wrong
+    Then an error occured
+     And user U1 can log in with their initial password
+
+  Scenario: Users cannot take the email of another user
+    Given user U1 is not authenticated
+    When user U1 changes their email to the one of user U2
+    Then an error occured
+     And user U1 can log in with their initial password
+
+  Scenario: Changing the email to one matching the root team adds the user to it
+    Given user U1 is not authenticated
+    When user U1 changes their email to one at cucumber-admin-root
+    Then no error occured
+     And user U1 is in the root team
+
+  Scenario: Changing the email to one not matching the root team removes the user from it
+    Given admin A1
+    Then admin A1 is in the root team
+    When admin A1 changes their email to one at example.com
+    Then no error occured
+     And admin A1 is not in the root team
@@ -10,3 +10,18 @@ Feature: Games
     When admin A1 creates a game G1 on system S1
     Then no error occured
     And game G1 exists on system S1
+
+  Scenario: Files are identified by their hashes
+    Given game G1
+    When admin default adds artifact R1 to game G1
+    Then anonymous user identifies file R1 as game G1 by hash
+
+  Scenario: Files are identified by their size and CRC32 if no hash is known
+    Given game G1
+    When admin default adds artifact R1 to game G1 with only its size and CRC32
+    Then anonymous user identifies file R1 as game G1 by size_and_crc32
+
+  Scenario: Unknown files are not identified
+    Given game G1
+    When admin default adds artifact R1 to game G1
+    Then anonymous user cannot identify file R2
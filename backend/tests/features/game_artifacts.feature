@@ -0,0 +1,24 @@
+Feature: Game artifacts
+
+  Scenario: Artifacts have a name, a path and a role
+    Given game G1
+    When admin default adds artifact R1 to game G1 as "G1.cue" with role cue
+    And admin default adds artifact R2 to game G1 as "Disc 1/G1 (Track 1).bin" with role track
+    Then the artifacts of game G1 are "G1.cue:cue:good, Disc 1/G1 (Track 1).bin:track:good"
+
+  Scenario: Artifacts are ROMs by default
+    Given game G1
+    When admin default adds artifact R1 to game G1 as "G1.rom"
+    Then the artifacts of game G1 are "G1.rom:rom:good"
+
+  Scenario: Artifact paths cannot leave the game
+    Given game G1
+    When admin default adds artifact R1 to game G1 as "../G1.rom"
+    Then an error occured
+
+  Scenario: DAT imports keep the layout of the files
+    Given game G0
+    When admin default imports a DAT with disc games G1 into system default
+    Then the DAT import is completed
+    And game G1 was imported from the DAT
+    And the artifacts of game G1 are "G1.cue:cue:good, Disc/G1 (Track 1).bin:track:verified, Disc/G1 (Track 2).bin:track:baddump"
@@ -11,3 +11,43 @@ Feature: Game Images
     Given game G1
     When user U1 uploads image I1 to game G1
     Then an error occured
+
+  Scenario: Thumbnails and WebP versions of images are generated
+    Given game G1
+    When admin default uploads image I1 to game G1
+    Then image I1 of game G1 has derivatives "128x96, 256x192, 320x240"
+
+  Scenario: Derivatives of older images are generated by the backfill
+    Given game G1
+    When admin default uploads image I1 to game G1
+    And image I1 of game G1 was uploaded before derivatives were generated
+    Then image I1 of game G1 has derivatives ""
+    When the server backfills game images
+    Then image I1 of game G1 has derivatives "128x96, 256x192, 320x240"
+
+  Scenario: Images have roles
+    Given game G1
+    When admin default uploads image I1 to game G1 as box-front
+    Then image I1 of game G1 has roles "box-front"
+    And images of game G1 with role box-front include image I1
+    And images of game G1 with role title-screen do not include image I1
+
+  Scenario: Roles of images can be changed
+    Given game G1
+    When admin default uploads image I1 to game G1 as box-front
+    And admin default sets the roles of image I1 of game G1 to "title-screen, in-game"
+    Then image I1 of game G1 has roles "title-screen, in-game"
+    And images of game G1 with role in-game include image I1
+    And images of game G1 with role box-front do not include image I1
+
+  Scenario: Duplicate images are refused
+    Given game G1
+    When admin default uploads image I1 showing "Zelda" to game G1
+    And admin default uploads image I2 showing "Zelda" to game G1
+    Then an error occured
+
+  Scenario: Duplicate images can be allowed
+    Given game G1
+    When admin default uploads image I1 showing "Zelda" to game G1
+    And admin default uploads duplicate image I2 showing "Zelda" to game G1
+    Then no error occured
@@ -0,0 +1,65 @@
+Feature: Game metadata
+
+  Scenario: Games are created with their publishers and developers
+    Given game G1
+    Then game G1 is published by "cucumber-publisher" and developed by "cucumber-developer"
+
+  Scenario: Games have metadata
+    Given game G1
+    When admin default updates game G1 with publisher "Nintendo" and developer "Nintendo EAD"
+    And admin default updates game G1 with a release in japan on 1986-02-21
+    And admin default updates game G1 with language ja, genre adventure and series "The Legend of Zelda"
+    And admin default updates game G1 for 1 to 2 players
+    Then game G1 is published by "Nintendo" and developed by "Nintendo EAD"
+    And game G1 was released in japan on 1986-02-21
+    And game G1 has language ja, genre adventure and series "The Legend of Zelda"
+    And game G1 is for 1 to 2 players
+
+  Scenario: The deprecated publisher and developer fields replace the companies
+    Given game G1
+    When admin default updates game G1 with the deprecated publisher "Sega" and developer "Sonic Team"
+    Then game G1 is published by "Sega" and developed by "Sonic Team"
+
+  Scenario: The deprecated publisher field cannot be given with the publishers
+    Given game G1
+    When admin default updates game G1 with publisher "Nintendo" and the deprecated publisher "Sega"
+    Then an error occured
+
+  Scenario: Games are filtered by their metadata
+    Given game G1
+    When admin default creates a game G2 on system default
+    And admin default updates game G1 with publisher "Nintendo" and developer "Nintendo EAD"
+    And admin default updates game G1 with a release in japan on 1986-02-21
+    And admin default updates game G1 with language ja, genre adventure and series "The Legend of Zelda"
+    And admin default updates game G1 for 1 to 2 players
+    Then games with region japan include game G1
+    And games with region japan do not include game G2
+    And games with language ja include game G1
+    And games with genre adventure include game G1
+    And games with genre adventure do not include game G2
+    And games with series the-legend-of-zelda include game G1
+    And games with publisher nintendo include game G1
+    And games with publisher nintendo do not include game G2
+    And games with developer nintendo-ead include game G1
+    And games with players 2 include game G1
+    And games with players 3 do not include game G1
+
+  Scenario: Unknown regions are refused
+    Given game G1
+    When admin default updates game G1 with a release in atlantis on 1986-02-21
+    Then an error occured
+
+  Scenario: Invalid release dates are refused
+    Given game G1
+    When admin default updates game G1 with a release in japan on 1986-02-31
+    Then an error occured
+
+  Scenario: Invalid languages are refused
+    Given game G1
+    When admin default updates game G1 with language not_a_language, genre adventure and series "Zelda"
+    Then an error occured
+
+  Scenario: Games cannot have fewer maximum players than minimum
+    Given game G1
+    When admin default updates game G1 for 2 to 1 players
+    Then an error occured
@@ -0,0 +1,67 @@
+Feature: Game relationships
+
+  Scenario: Game details show relationships in both directions
+    Given game G1
+    And game G2
+    When admin default records game G2 hack_of game G1
+    Then game G2 is hack_of game G1
+    And game G1 is not hack_of game G2
+
+  Scenario: Recording a relationship twice does nothing
+    Given game G1
+    And game G2
+    When admin default records game G2 revision_of game G1
+    And admin default records game G2 revision_of game G1
+    Then the games related to game G1 with "" are "G2:revision_of:incoming:1"
+
+  Scenario: Relationships can be removed
+    Given game G1
+    And game G2
+    When admin default records game G2 translation_of game G1
+    And admin default removes game G2 translation_of game G1
+    Then game G2 is not translation_of game G1
+
+  Scenario: Removing a missing relationship fails
+    Given game G1
+    And game G2
+    When admin default removes game G2 translation_of game G1
+    Then an error occured
+
+  Scenario: A game cannot be related to itself
+    Given game G1
+    When admin default records game G1 sequel_of game G1
+    Then an error occured
+
+  Scenario: Only admins can record relationships
+    Given game G1
+    And game G2
+    And user alice
+    When user alice records game G2 hack_of game G1
+    Then an error occured
+
+  Scenario: Relationships are traversed
+    Given game G1
+    And game G2
+    And game G3
+    And game G4
+    When admin default records game G2 revision_of game G1
+    And admin default records game G3 hack_of game G2
+    And admin default records game G4 translation_of game G3
+    Then the games related to game G3 with "" are "G2:hack_of:outgoing:1, G4:translation_of:incoming:1"
+    And the games related to game G1 with "depth=3" are "G2:revision_of:incoming:1, G3:hack_of:incoming:2, G4:translation_of:incoming:3"
+    And the games related to game G4 with "direction=outgoing depth=5" are "G3:translation_of:outgoing:1, G2:hack_of:outgoing:2, G1:revision_of:outgoing:3"
+    And the games related to game G4 with "direction=incoming depth=5" are ""
+    And the games related to game G2 with "kind=hack_of depth=3" are "G3:hack_of:incoming:1"
+
+  Scenario: Compilations contain games
+    Given game G1
+    And game G2
+    And game C
+    When admin default records game C contains game G1
+    And admin default records game C contains game G2
+    Then the games related to game C with "direction=outgoing" are "G1:contains:outgoing:1, G2:contains:outgoing:1"
+
+  Scenario: Traversal depth is limited
+    Given game G1
+    When anonymous user lists the games related to game G1 with "depth=6"
+    Then an error occured
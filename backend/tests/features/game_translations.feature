@@ -0,0 +1,49 @@
+Feature: Game translations
+
+  Scenario: Games have localised names
+    Given game G1 named "The Legend of Zelda"
+    When admin default translates game G1 to ja as "ゼルダの伝説"
+    And admin default translates game G1 to ja-Latn as "Zelda no Densetsu"
+    Then game G1 in ja is named "ゼルダの伝説"
+    And game G1 in ja-Latn is named "Zelda no Densetsu"
+    And game G1 in fr is named "The Legend of Zelda"
+    And game G1 has 2 translations
+
+  Scenario: Preferred languages fall back to more general ones
+    Given game G1 named "The Legend of Zelda"
+    When admin default translates game G1 to ja as "ゼルダの伝説"
+    And admin default translates game G1 to en-GB as "The Legend of Zelda (UK)"
+    Then game G1 in ja-JP is named "ゼルダの伝説"
+    And game G1 in en is named "The Legend of Zelda (UK)"
+    And game G1 in zh-Hant-TW is named "The Legend of Zelda"
+
+  Scenario: The Accept-Language header is honoured
+    Given game G1 named "The Legend of Zelda"
+    When admin default translates game G1 to ja as "ゼルダの伝説"
+    And admin default translates game G1 to ja-Latn as "Zelda no Densetsu"
+    Then game G1 with Accept-Language "fr, ja;q=0.5" is named "ゼルダの伝説"
+    And game G1 with Accept-Language "ja;q=0, fr" is named "The Legend of Zelda"
+    And game G1 in ja-Latn with Accept-Language "ja" is named "Zelda no Densetsu"
+
+  Scenario: Game lists are localised
+    Given game G1 named "The Legend of Zelda"
+    When admin default translates game G1 to ja as "ゼルダの伝説"
+    Then game G1 is listed in ja as "ゼルダの伝説"
+
+  Scenario: Games are found by their localised names
+    Given game G1 named "The Hyrule Fantasy"
+    When admin default translates game G1 to ja-Latn as "Zelda no Densetsu"
+    And anonymous user searches for "densetsu"
+    Then the search finds game G1
+
+  Scenario: Translations can be deleted
+    Given game G1 named "The Legend of Zelda"
+    When admin default translates game G1 to ja as "ゼルダの伝説"
+    And admin default deletes the ja translation of game G1
+    Then game G1 in ja is named "The Legend of Zelda"
+    And game G1 has 0 translations
+
+  Scenario: Translations need a valid language
+    Given game G1 named "The Legend of Zelda"
+    When admin default translates game G1 to not_a_language as "Zelda"
+    Then an error occured
@@ -0,0 +1,38 @@
+Feature: Release Manifests
+
+  Scenario: Release manifests are signed by the server
+    Given core C1 with release R1 owned by admin A1
+    When admin A1 starts uploading a 1024 bytes artifact F1.bin to release R1
+     And admin A1 uploads part 1 of 1024 bytes of artifact F1.bin to release R1
+     And admin A1 completes the upload of artifact F1.bin to release R1
+    Then the manifest of release R1 lists a 1024 bytes artifact F1.bin
+     And the manifest of release R1 is signed by the server
+
+  Scenario: Team members can sign manifests with a team key
+    Given core C1 with release R1 owned by admin A1
+    When admin A1 registers signing key K1 for team C1
+     And admin A1 signs the manifest of release R1 with key K1
+    Then no error occured
+     And the manifest of release R1 is signed with key K1
+
+  Scenario: Signatures made with revoked keys are not served
+    Given core C1 with release R1 owned by admin A1
+    When admin A1 registers signing key K1 for team C1
+     And admin A1 signs the manifest of release R1 with key K1
+     And admin A1 revokes signing key K1
+    Then the manifest of release R1 is not signed with key K1
+
+  Scenario: Signatures only cover the manifest they were made for
+    Given core C1 with release R1 owned by admin A1
+    When admin A1 registers signing key K1 for team C1
+     And admin A1 signs the manifest of release R1 with key K1
+     And admin A1 starts uploading a 1024 bytes artifact F1.bin to release R1
+     And admin A1 uploads part 1 of 1024 bytes of artifact F1.bin to release R1
+     And admin A1 completes the upload of artifact F1.bin to release R1
+    Then the manifest of release R1 is not signed with key K1
+
+  Scenario: Users outside the team cannot sign manifests
+    Given core C1 with release R1 owned by admin A1
+    When admin A1 registers signing key K1 for team C1
+     And user U1 signs the manifest of release R1 with key K1
+    Then an error occured
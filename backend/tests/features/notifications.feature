@@ -0,0 +1,54 @@
+Feature: Notifications
+
+  Scenario: Invited users are notified
+    Given team T1 is owned by user A
+    When user A invites user B to team T1 as member
+    Then user B has 1 pending invitation
+     And user B has 1 unread notification
+     And user B has a team_invitation notification
+
+  Scenario: Accepting an invitation removes it from the pending invitations
+    Given team T1 is owned by user A
+    When user A invites user B to team T1 as member
+     And user B accepts the invitation to team T1
+    Then user B has 0 pending invitations
+
+  Scenario: Users can mark their notifications as read
+    Given team T1 is owned by user A
+    When user A invites user B to team T1 as member
+     And user B reads their notifications
+    Then user B has 0 unread notifications
+     And user B has a team_invitation notification
+
+  Scenario: Users can turn off in-app notifications
+    Given team T1 is owned by user A
+    When user B turns off in-app team_invitation notifications
+     And user A invites user B to team T1 as member
+    Then user B has 1 pending invitation
+     And user B has 0 unread notifications
+
+  Scenario: Followers are notified of new releases
+    Given core C1 with release R1 owned by user A
+    When user B follows core C1
+     And user A releases version 2.0.0 of core C1
+    Then user B has 1 unread notification
+     And user B has a core_release notification
+     And user A has 0 unread notifications
+
+  Scenario: Users who unfollowed a core are not notified
+    Given core C1 with release R1 owned by user A
+    When user B follows core C1
+     And user B unfollows core C1
+     And user A releases version 2.0.0 of core C1
+    Then user B has 0 unread notifications
+
+  Scenario: Followers are notified of yanked releases
+    Given core C1 with release R1 owned by user A
+    When user B follows core C1
+     And user A yanks release R1
+    Then user B has a release_yanked notification
+
+  Scenario: Only admins of the core team can yank releases
+    Given core C1 with release R1 owned by user A
+    When user B yanks release R1
+    Then an error occured
@@ -0,0 +1,24 @@
+Feature: OpenID Connect
+
+  Scenario: Configured providers are listed
+    Then OpenID Connect provider test is available
+
+  Scenario: Logging in redirects to the provider
+    When anonymous user starts logging in with OpenID Connect provider test
+    Then no error occured
+     And the login is redirected to https://oidc.example.com/authorize with a PKCE challenge
+
+  Scenario: Unknown providers cannot be used
+    When anonymous user starts logging in with OpenID Connect provider unknown
+    Then an error occured
+
+  Scenario: The provider must return the state of the login
+    When anonymous user starts logging in with OpenID Connect provider test
+     And anonymous user returns from OpenID Connect provider test with state forged
+    Then an error occured
+
+  Scenario: Users without linked identities cannot unlink any
+    Given user U1
+    Then user U1 has 0 linked identities
+    When user U1 unlinks identity 1
+    Then an error occured
@@ -0,0 +1,22 @@
+Feature: Passwords
+
+  Scenario: Users can change their password
+    Given admin A1
+    When admin A1 changes their password to hunter22
+    Then no error occured
+     And admin A1 can log in with password hunter22
+     And admin A1 cannot log in with their initial password
+
+  Scenario: Changing the password requires the current password
+    Given admin A1
+    When admin A1 changes their password to hunter22 with current password wrong
+    Then an error occured
+     And admin A1 cannot log in with password hunter22
+
+  Scenario: Changing the password logs out other sessions
+    Given admin A1
+    When admin A1 logs in as session S1
+     And admin A1 changes their password to hunter22
+    Then no error occured
+     And session S1 is logged out
+     And admin A1 is logged in
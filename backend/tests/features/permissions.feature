@@ -0,0 +1,295 @@
+Feature: Permissions
+
+  Scenario: Members can release cores of their team by default
+    Given core C1 with release R1 owned by user A
+    When user A invites user B to team C1 as member
+     And user B accepts the invitation to team C1
+     And user B releases version 2.0.0 of core C1
+    Then no error occured
+     And user B has create_releases on core C1 from default
+
+  Scenario: Members cannot yank releases by default
+    Given core C1 with release R1 owned by user A
+    When user A invites user B to team C1 as member
+     And user B accepts the invitation to team C1
+     And user B yanks release R1
+    Then an error occured
+     And user B does not have yank_releases on core C1 because of default
+
+  Scenario: Teams can grant permissions to members
+    Given core C1 with release R1 owned by user A
+    When user A invites user B to team C1 as member
+     And user B accepts the invitation to team C1
+     And user A grants yank_releases to members of team C1
+     And user B yanks release R1
+    Then no error occured
+     And user B has yank_releases on core C1 from team
+
+  Scenario: Teams can revoke permissions from members
+    Given core C1 with release R1 owned by user A
+    When user A invites user B to team C1 as member
+     And user B accepts the invitation to team C1
+     And user A revokes create_releases from members of team C1
+     And user B releases version 2.0.0 of core C1
+    Then an error occured
+     And user B does not have create_releases on core C1 because of team
+
+  Scenario: Admins cannot change the permissions of their team
+    Given core C1 with release R1 owned by user A
+    When user A invites user B to team C1 as admin
+     And user B accepts the invitation to team C1
+     And user B grants yank_releases to members of team C1
+    Then an error occured
+
+  Scenario: Permissions of owners cannot be revoked
+    Given core C1 with release R1 owned by user A
+    When user A revokes create_releases from owners of team C1
+    Then an error occured
+     And user A has create_releases on core C1 from owner
+
+  Scenario: Collaborators can release a core of another team
+    Given core C1 with release R1 owned by user A
+    When user A grants create_releases on core C1 to user B
+     And user B releases version 2.0.0 of core C1
+    Then no error occured
+     And user B has create_releases on core C1 from collaborator
+     And user B does not have yank_releases on core C1 because of not_a_member
+
+  Scenario: Users outside of a team cannot release its cores
+    Given core C1 with release R1 owned by user A
+    When user B releases version 2.0.0 of core C1
+    Then an error occured
+     And user B does not have create_releases on core C1 because of not_a_member
+
+  Scenario: Only core permissions can be granted on a core
+    Given core C1 with release R1 owned by user A
+    When user A grants create_cores on core C1 to user B
+    Then an error occured
+
+  Scenario: Members allowed to manage members can only invite members
+    Given core C1 with release R1 owned by user A
+    When user A invites user B to team C1 as member
+     And user B accepts the invitation to team C1
+     And user A grants manage_members to members of team C1
+     And user B invites user C to team C1 as member
+    Then no error occured
+     And user B will not be able to invite user D to team C1 as admin
+
+  Scenario Outline: Roles have some team permissions by default
+    Given core C1 with release R1 owned by user A
+    When user A invites user B to team C1 as <role>
+     And user B accepts the invitation to team C1
+    Then user B has <permission> on core C1 from default
+
+    Examples:
+      | role   | permission       |
+      | member | create_releases  |
+      | member | sign_manifests   |
+      | admin  | create_releases  |
+      | admin  | sign_manifests   |
+      | admin  | yank_releases    |
+      | admin  | create_cores     |
+      | admin  | create_systems   |
+      | admin  | manage_members   |
+      | admin  | manage_team_keys |
+
+  Scenario Outline: Roles lack other team permissions by default
+    Given core C1 with release R1 owned by user A
+    When user A invites user B to team C1 as <role>
+     And user B accepts the invitation to team C1
+    Then user B does not have <permission> on core C1 because of default
+
+    Examples:
+      | role   | permission         |
+      | member | yank_releases      |
+      | member | create_cores       |
+      | member | create_systems     |
+      | member | manage_members     |
+      | member | manage_team_keys   |
+      | member | update_team        |
+      | member | delete_team        |
+      | member | upload_game_images |
+      | admin  | update_team        |
+      | admin  | delete_team        |
+      | admin  | upload_game_images |
+
+  Scenario Outline: Owners have every team permission
+    Given core C1 with release R1 owned by user A
+    Then user A has <permission> on core C1 from owner
+
+    Examples:
+      | permission         |
+      | create_releases    |
+      | sign_manifests     |
+      | yank_releases      |
+      | create_cores       |
+      | create_systems     |
+      | manage_members     |
+      | manage_team_keys   |
+      | update_team        |
+      | delete_team        |
+      | upload_game_images |
+
+  Scenario Outline: Users outside of a team have none of its permissions
+    Given core C1 with release R1 owned by user A
+    Then user B does not have <permission> on core C1 because of not_a_member
+
+    Examples:
+      | permission         |
+      | create_releases    |
+      | sign_manifests     |
+      | yank_releases      |
+      | create_cores       |
+      | create_systems     |
+      | manage_members     |
+      | manage_team_keys   |
+      | update_team        |
+      | delete_team        |
+      | upload_game_images |
+
+  Scenario: Members cannot create cores by default
+    Given a system S1 created by user A owned by team T1
+    When user A invites user B to team T1 as member
+     And user B accepts the invitation to team T1
+     And user B creates a core C1 on system S1 in team T1
+    Then an error occured
+
+  Scenario: Admins can create cores
+    Given a system S1 created by user A owned by team T1
+    When user A invites user B to team T1 as admin
+     And user B accepts the invitation to team T1
+     And user B creates a core C1 on system S1 in team T1
+    Then no error occured
+
+  Scenario: Teams can allow members to create cores
+    Given a system S1 created by user A owned by team T1
+    When user A invites user B to team T1 as member
+     And user B accepts the invitation to team T1
+     And user A grants create_cores to members of team T1
+     And user B creates a core C1 on system S1 in team T1
+    Then no error occured
+     And user B has create_cores on core C1 from team
+
+  Scenario: Members cannot create systems by default
+    Given team T1 is owned by user A
+    When user A invites user B to team T1 as member
+     And user B accepts the invitation to team T1
+     And user B creates a system S1 in team T1
+    Then an error occured
+
+  Scenario: Admins can create systems
+    Given team T1 is owned by user A
+    When user A invites user B to team T1 as admin
+     And user B accepts the invitation to team T1
+     And user B creates a system S1 in team T1
+    Then no error occured
+
+  Scenario: Users outside of a team cannot create its systems
+    Given team T1 is owned by user A
+    When user B creates a system S1 in team T1
+    Then an error occured
+
+  Scenario: The team of a system can upload images of its games
+    Given a system S1 created by user A owned by team T1
+    When admin default creates a game G1 on system S1
+     And user A uploads image I1 to game G1
+    Then no error occured
+
+  Scenario: Admins of the team of a system cannot upload images by default
+    Given a system S1 created by user A owned by team T1
+    When admin default creates a game G1 on system S1
+     And user A invites user B to team T1 as admin
+     And user B accepts the invitation to team T1
+     And user B uploads image I1 to game G1
+    Then an error occured
+
+  Scenario: Teams can allow members to upload images of the games of their systems
+    Given a system S1 created by user A owned by team T1
+    When admin default creates a game G1 on system S1
+     And user A invites user B to team T1 as member
+     And user B accepts the invitation to team T1
+     And user A grants upload_game_images to members of team T1
+     And user B uploads image I1 to game G1
+    Then no error occured
+
+  Scenario: Other teams cannot upload images of the games of a system
+    Given a system S1 created by user A owned by team T1
+      And team T2 is owned by user B
+    When admin default creates a game G1 on system S1
+     And user B uploads image I1 to game G1
+    Then an error occured
+
+  Scenario: Members cannot manage team keys by default
+    Given core C1 with release R1 owned by user A
+    When user A invites user B to team C1 as member
+     And user B accepts the invitation to team C1
+     And user B registers signing key K1 for team C1
+    Then an error occured
+
+  Scenario: Admins can manage team keys
+    Given core C1 with release R1 owned by user A
+    When user A invites user B to team C1 as admin
+     And user B accepts the invitation to team C1
+     And user B registers signing key K1 for team C1
+     And user B revokes signing key K1
+    Then no error occured
+
+  Scenario: Members can sign manifests by default
+    Given core C1 with release R1 owned by user A
+    When user A registers signing key K1 for team C1
+     And user A invites user B to team C1 as member
+     And user B accepts the invitation to team C1
+     And user B signs the manifest of release R1 with key K1
+    Then no error occured
+     And the manifest of release R1 is signed with key K1
+
+  Scenario: Teams can revoke the permission to sign manifests
+    Given core C1 with release R1 owned by user A
+    When user A registers signing key K1 for team C1
+     And user A invites user B to team C1 as member
+     And user B accepts the invitation to team C1
+     And user A revokes sign_manifests from members of team C1
+     And user B signs the manifest of release R1 with key K1
+    Then an error occured
+     And the manifest of release R1 is not signed with key K1
+
+  Scenario: Owners can update and delete their team
+    Given team T1 is owned by user A
+    When user A updates team T1
+     And user A deletes team T1
+    Then no error occured
+
+  Scenario: Admins cannot update their team by default
+    Given team T1 is owned by user A
+    When user A invites user B to team T1 as admin
+     And user B accepts the invitation to team T1
+     And user B updates team T1
+    Then an error occured
+
+  Scenario: Admins cannot delete their team by default
+    Given team T1 is owned by user A
+    When user A invites user B to team T1 as admin
+     And user B accepts the invitation to team T1
+     And user B deletes team T1
+    Then an error occured
+
+  Scenario: Teams can allow admins to update and delete the team
+    Given team T1 is owned by user A
+    When user A invites user B to team T1 as admin
+     And user B accepts the invitation to team T1
+     And user A grants update_team to admins of team T1
+     And user A grants delete_team to admins of team T1
+     And user B updates team T1
+     And user B deletes team T1
+    Then no error occured
+
+  Scenario: Users allowed by the server can create teams
+    Given user U1
+    When user U1 creates a team T1
+    Then no error occured
+
+  Scenario: Users not allowed by the server cannot create teams
+    Given user U1
+    When user U1 changes their email to one at example.com
+     And user U1 creates a team T1
+    Then an error occured
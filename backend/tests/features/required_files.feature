@@ -0,0 +1,73 @@
+Feature: Required files of cores
+
+  Scenario: Cores list the files they require
+    Given core C1 with release R1 owned by user A
+    When user A requires file F1 at "bios/boot.rom" for core C1
+     And user A requires optional file F2 at "fonts.rom" for core C1
+    Then core C1 requires files "bios/boot.rom, fonts.rom"
+
+  Scenario: Files can be required by a single release
+    Given core C1 with release R1 owned by user A
+    When user A releases version 2.0.0 of core C1
+     And user A requires file F1 at "bios/boot.rom" for core C1
+     And user A requires file F2 at "bios/v2.rom" for release 2.0.0
+    Then release R1 requires files "bios/boot.rom"
+     And release 2.0.0 requires files "bios/boot.rom, bios/v2.rom"
+
+  Scenario: Required files can be removed
+    Given core C1 with release R1 owned by user A
+    When user A requires file F1 at "bios/boot.rom" for core C1
+     And user A requires file F2 at "bios/other.rom" for core C1
+     And user A removes the required file at "bios/boot.rom" of core C1
+    Then core C1 requires files "bios/other.rom"
+
+  Scenario: Only members of the core team can require files
+    Given core C1 with release R1 owned by user A
+    When user B requires file F1 at "bios/boot.rom" for core C1
+    Then an error occured
+
+  Scenario: Hashes need a checksum
+    Given core C1 with release R1 owned by user A
+    When user A requires a file at "boot.rom" for core C1 with a hash without checksum
+    Then an error occured
+
+  Scenario: Cores with all their files are playable
+    Given core C1 with release R1 owned by user A
+    And the device has file F1 at "bios/boot.rom"
+    When user A requires file F1 at "bios/boot.rom" for core C1
+     And anonymous user checks which cores can run on the device
+    Then core C1 is playable on the device
+     And the required files of core C1 are "boot.rom:present" on the device
+
+  Scenario: Missing and misplaced files are reported
+    Given core C1 with release R1 owned by user A
+    And the device has file F1 at "boot.rom"
+    And the device has file F3 at "bios/other.rom"
+    When user A requires file F1 at "bios/boot.rom" for core C1
+     And user A requires file F2 at "bios/other.rom" for core C1
+     And user A requires file F4 at "bios/missing.rom" for core C1
+     And anonymous user checks which cores can run on the device
+    Then core C1 is not playable on the device
+     And the required files of core C1 are "boot.rom:misplaced at boot.rom, other.rom:mismatched, missing.rom:missing" on the device
+
+  Scenario: Optional files do not prevent cores from running
+    Given core C1 with release R1 owned by user A
+    When user A requires optional file F1 at "bios/boot.rom" for core C1
+     And anonymous user checks which cores can run on the device
+    Then core C1 is playable on the device
+     And the required files of core C1 are "boot.rom:missing" on the device
+
+  Scenario: Only the files of the latest release are checked
+    Given core C1 with release R1 owned by user A
+    When user A requires file F1 at "bios/boot.rom" for release R1
+     And user A releases version 2.0.0 of core C1
+     And anonymous user checks which cores can run on the device
+    Then core C1 is playable on the device
+
+  Scenario: Yanked releases are not checked
+    Given core C1 with release R1 owned by user A
+    When user A releases version 2.0.0 of core C1
+     And user A requires file F1 at "bios/boot.rom" for release 2.0.0
+     And user A yanks release 2.0.0
+     And anonymous user checks which cores can run on the device
+    Then core C1 is playable on the device
@@ -0,0 +1,35 @@
+Feature: Search
+
+  Scenario: Games are found by some of the words of their name
+    Given game G1 named "Zelda no Densetsu"
+    And game G2 named "Super Mario Bros"
+    When anonymous user searches for "zelda densetsu"
+    Then the search finds game G1
+    And the search does not find game G2
+    And the search highlights "<mark>Zelda</mark>" in game G1
+
+  Scenario: Highlights escape the text of items
+    Given game G1 named "Zelda <script>alert('1')</script>"
+    When anonymous user searches for "zelda"
+    Then the search highlights "<mark>Zelda</mark> &lt;script&gt;alert" in game G1
+
+  Scenario: Games are found with typos in their name
+    Given game G1 named "Zelda no Densetsu"
+    When anonymous user searches for "zelad densetsu"
+    Then the search finds game G1
+
+  Scenario: Closer names are ranked first
+    Given game G1 named "Zelda no Densetsu"
+    And game G2 named "Zelda"
+    When anonymous user searches for "zelda"
+    Then the search ranks game G2 before game G1
+
+  Scenario: Facets count results of all kinds
+    Given game G1 named "Zelda no Densetsu"
+    And game G2 named "Zelda II"
+    When anonymous user searches systems for "zelda"
+    Then the search finds 0 results out of 2 games
+
+  Scenario: Searching requires a query
+    When anonymous user searches for nothing
+    Then an error occured
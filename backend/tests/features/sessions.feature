@@ -0,0 +1,51 @@
+Feature: Sessions
+
+  Scenario: Users can list their sessions
+    Given admin A1
+    When admin A1 logs in as session S1
+    Then no error occured
+     And admin A1 has 2 sessions
+     And session S1 has 2 sessions
+
+  Scenario: Users can revoke a session
+    Given admin A1
+    When admin A1 logs in as session S1
+     And admin A1 revokes session S1
+    Then no error occured
+     And session S1 is logged out
+     And admin A1 is logged in
+     And admin A1 has 1 session
+
+  Scenario: Users can revoke all their other sessions
+    Given admin A1
+    When admin A1 logs in as session S1
+     And admin A1 logs in as session S2
+     And admin A1 revokes all their other sessions
+    Then no error occured
+     And session S1 is logged out
+     And session S2 is logged out
+     And admin A1 is logged in
+     And admin A1 has 1 session
+
+  Scenario: Logging out revokes the session
+    Given admin A1
+    When admin A1 logs in as session S1
+     And session S1 logs out
+    Then no error occured
+     And session S1 is logged out
+     And admin A1 has 1 session
+
+  Scenario: Admins can terminate all sessions of a user
+    Given admin A1
+      And user U1 is not authenticated
+    When admin A1 terminates the sessions of user U1
+    Then no error occured
+     And user U1 is logged out
+     And admin A1 is logged in
+
+  Scenario: Users cannot terminate the sessions of others
+    Given admin A1
+      And user U1 is not authenticated
+    When user U1 terminates the sessions of admin A1
+    Then an error occured
+     And admin A1 is logged in
@@ -14,3 +14,86 @@ Feature: Team
     Given team T1 is owned by user A
     When user B invites user C to team T1 as member
     Then an error occured
+
+  Scenario: Owners can remove members from a team
+    Given team T1 is owned by user A
+    When user A invites user B to team T1 as member
+     And user B accepts the invitation to team T1
+     And user A removes user B from team T1
+    Then no error occured
+     And team T1 will not have user B
+
+  Scenario: Admins cannot remove owners from a team
+    Given team T1 is owned by user A
+    When user A invites user B to team T1 as admin
+     And user B accepts the invitation to team T1
+     And user B removes user A from team T1
+    Then an error occured
+     And team T1 will have user A as owner
+
+  Scenario: Members can leave a team
+    Given team T1 is owned by user A
+    When user A invites user B to team T1 as member
+     And user B accepts the invitation to team T1
+     And user B leaves team T1
+    Then no error occured
+     And team T1 will not have user B
+
+  Scenario: The last owner cannot leave a team
+    Given team T1 is owned by user A
+    When user A leaves team T1
+    Then an error occured
+     And team T1 will have user A as owner
+
+  Scenario: An owner can leave a team with another owner
+    Given team T1 is owned by user A
+    When user A invites user B to team T1 as owner
+     And user B accepts the invitation to team T1
+     And user A leaves team T1
+    Then no error occured
+     And team T1 will not have user A
+     And team T1 will have user B as owner
+
+  Scenario: Owners can change the role of members
+    Given team T1 is owned by user A
+    When user A invites user B to team T1 as member
+     And user B accepts the invitation to team T1
+     And user A changes the role of user B in team T1 to admin
+    Then no error occured
+     And team T1 will have user B as admin
+
+  Scenario: Admins cannot demote owners
+    Given team T1 is owned by user A
+    When user A invites user B to team T1 as admin
+     And user B accepts the invitation to team T1
+     And user B changes the role of user A in team T1 to member
+    Then an error occured
+     And team T1 will have user A as owner
+
+  Scenario: The last owner cannot demote themselves
+    Given team T1 is owned by user A
+    When user A changes the role of user A in team T1 to admin
+    Then an error occured
+     And team T1 will have user A as owner
+
+  Scenario: Users can decline an invitation
+    Given team T1 is owned by user A
+    When user A invites user B to team T1 as member
+    Then user A sees 1 pending invitation to team T1
+    When user B declines the invitation to team T1
+    Then no error occured
+     And user A sees 0 pending invitations to team T1
+
+  Scenario: Owners can cancel an invitation
+    Given team T1 is owned by user A
+    When user A invites user B to team T1 as member
+     And user A cancels the invitation of user B to team T1
+    Then no error occured
+     And user A sees 0 pending invitations to team T1
+
+  Scenario: Invited users cannot manage a team before accepting
+    Given team T1 is owned by user A
+    When user A invites user B to team T1 as owner
+     And user B removes user A from team T1
+    Then an error occured
+     And team T1 will have user A as owner
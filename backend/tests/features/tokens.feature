@@ -0,0 +1,36 @@
+Feature: Personal Access Tokens
+
+  Scenario: Tokens can upload artifacts to the core they are scoped to
+    Given core C1 with release R1 owned by admin A1
+    When admin A1 creates a token T1 with scope releases:write for core C1
+     And token T1 starts uploading a 1024 bytes artifact F1.bin to release R1
+     And token T1 uploads part 1 of 1024 bytes of artifact F1.bin to release R1
+     And token T1 completes the upload of artifact F1.bin to release R1
+    Then no error occured
+     And release R1 has a 1024 bytes artifact F1.bin
+
+  Scenario: Tokens cannot upload artifacts to other cores
+    Given core C1 with release R1 owned by admin A1
+      And core C2 with release R2 owned by admin A1
+    When admin A1 creates a token T1 with scope releases:write for core C1
+     And token T1 starts uploading a 1024 bytes artifact F1.bin to release R2
+    Then an error occured
+
+  Scenario: Read-only tokens cannot upload artifacts
+    Given core C1 with release R1 owned by admin A1
+    When admin A1 creates a token T1 with scope read
+     And token T1 starts uploading a 1024 bytes artifact F1.bin to release R1
+    Then an error occured
+
+  Scenario: Release tokens cannot create other tokens
+    Given core C1 with release R1 owned by admin A1
+    When admin A1 creates a token T1 with scope releases:write
+     And token T1 creates a token T2 with scope write
+    Then an error occured
+
+  Scenario: Revoked tokens are rejected
+    Given core C1 with release R1 owned by admin A1
+    When admin A1 creates a token T1 with scope releases:write
+     And admin A1 revokes token T1
+     And token T1 starts uploading a 1024 bytes artifact F1.bin to release R1
+    Then an error occured
@@ -0,0 +1,37 @@
+Feature: Two-factor authentication
+
+  Scenario: Users with two-factor authentication need a code to log in
+    Given admin A1
+    When admin A1 enables two-factor authentication
+    Then no error occured
+     And admin A1 cannot log in with their initial password
+     And admin A1 can log in with their initial password and a two-factor code
+
+  Scenario: Invalid and replayed codes are rejected
+    Given admin A1
+    When admin A1 enables two-factor authentication
+    Then admin A1 cannot log in with their initial password and code 12345678
+     And admin A1 can log in with their initial password and a two-factor code
+     And admin A1 cannot log in with their initial password and the same two-factor code
+
+  Scenario: Recovery codes can only be used once
+    Given admin A1
+    When admin A1 enables two-factor authentication
+    Then admin A1 can log in with their initial password and recovery code 1
+     And admin A1 cannot log in with their initial password and recovery code 1
+     And admin A1 can log in with their initial password and recovery code 2
+
+  Scenario: Enabling two-factor authentication logs out other sessions
+    Given admin A1
+    When admin A1 logs in as session S1
+     And admin A1 enables two-factor authentication
+    Then no error occured
+     And session S1 is logged out
+     And admin A1 is logged in
+
+  Scenario: Users can disable two-factor authentication
+    Given admin A1
+    When admin A1 enables two-factor authentication
+     And admin A1 disables two-factor authentication
+    Then no error occured
+     And admin A1 can log in with their initial password
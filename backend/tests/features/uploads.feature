@@ -0,0 +1,89 @@
+Feature: Release Artifact Uploads
+
+  Scenario: Admin can upload an artifact in parts
+    Given core C1 with release R1 owned by admin A1
+    When admin A1 starts uploading a 6291456 bytes artifact F1.bin to release R1
+     And admin A1 uploads part 1 of 5242880 bytes of artifact F1.bin to release R1
+     And admin A1 uploads part 2 of 1048576 bytes of artifact F1.bin to release R1
+     And admin A1 completes the upload of artifact F1.bin to release R1
+    Then no error occured
+     And release R1 has a 6291456 bytes artifact F1.bin
+
+  Scenario: Parts must be uploaded in order
+    Given core C1 with release R1 owned by admin A1
+    When admin A1 starts uploading a 6291456 bytes artifact F1.bin to release R1
+     And admin A1 uploads part 2 of 1048576 bytes of artifact F1.bin to release R1
+    Then an error occured
+
+  Scenario: Uploads cannot be completed before all parts are received
+    Given core C1 with release R1 owned by admin A1
+    When admin A1 starts uploading a 6291456 bytes artifact F1.bin to release R1
+     And admin A1 uploads part 1 of 5242880 bytes of artifact F1.bin to release R1
+     And admin A1 completes the upload of artifact F1.bin to release R1
+    Then an error occured
+
+  Scenario: Uploads are verified against the expected checksums
+    Given core C1 with release R1 owned by admin A1
+    When admin A1 starts uploading a 6291456 bytes artifact F1.bin to release R1
+     And admin A1 uploads part 1 of 5242880 bytes of artifact F1.bin to release R1
+     And admin A1 uploads part 2 of 1048576 bytes of artifact F1.bin to release R1
+     And admin A1 completes the upload of artifact F1.bin to release R1 with a sha256 of 6291455 bytes
+    Then an error occured
+    When admin A1 completes the upload of artifact F1.bin to release R1 with a sha256 of 6291456 bytes
+    Then no error occured
+     And release R1 has a 6291456 bytes artifact F1.bin
+
+  Scenario: Uploads can be completed after the server forgot their checksums
+    Given core C1 with release R1 owned by admin A1
+    When admin A1 starts uploading a 6291456 bytes artifact F1.bin to release R1
+     And admin A1 uploads part 1 of 5242880 bytes of artifact F1.bin to release R1
+     And the server forgets the checksums of artifact F1.bin
+     And admin A1 uploads part 2 of 1048576 bytes of artifact F1.bin to release R1
+     And admin A1 completes the upload of artifact F1.bin to release R1 with a sha256 of 6291455 bytes
+    Then an error occured
+    When the server forgets the checksums of artifact F1.bin
+     And admin A1 completes the upload of artifact F1.bin to release R1 with a sha256 of 6291456 bytes
+    Then no error occured
+     And release R1 has a 6291456 bytes artifact F1.bin
+
+  Scenario: Uploads are verified against the expected size
+    Given core C1 with release R1 owned by admin A1
+    When admin A1 starts uploading a 6291456 bytes artifact F1.bin to release R1
+     And admin A1 uploads part 1 of 5242880 bytes of artifact F1.bin to release R1
+     And admin A1 uploads part 2 of 1048576 bytes of artifact F1.bin to release R1
+     And admin A1 completes the upload of artifact F1.bin to release R1 with an expected size of 6291457 bytes
+    Then an error occured
+    When admin A1 completes the upload of artifact F1.bin to release R1 with an expected size of 6291456 bytes
+    Then no error occured
+     And release R1 has a 6291456 bytes artifact F1.bin
+
+  Scenario: Identical artifacts are only stored once
+    Given core C1 with release R1 owned by admin A1
+      And core C2 with release R2 owned by admin A1
+    When admin A1 starts uploading a 6291456 bytes artifact F1.bin to release R1
+     And admin A1 uploads part 1 of 5242880 bytes of artifact F1.bin to release R1
+     And admin A1 uploads part 2 of 1048576 bytes of artifact F1.bin to release R1
+     And admin A1 completes the upload of artifact F1.bin to release R1
+     And admin A1 starts uploading a 6291456 bytes artifact F2.bin to release R2
+     And admin A1 uploads part 1 of 5242880 bytes of artifact F2.bin to release R2
+     And admin A1 uploads part 2 of 1048576 bytes of artifact F2.bin to release R2
+     And admin A1 completes the upload of artifact F2.bin to release R2
+    Then no error occured
+     And artifact F1.bin of release R1 and artifact F2.bin of release R2 share their content
+
+  Scenario: Artifacts are served with the content type they were uploaded with
+    Given core C1 with release R1 owned by admin A1
+    When admin A1 starts uploading a 6291460 bytes "application/zip" artifact F1.zip to release R1
+     And admin A1 uploads part 1 of 5242880 bytes of artifact F1.zip to release R1
+     And admin A1 uploads part 2 of 1048580 bytes of artifact F1.zip to release R1
+     And admin A1 completes the upload of artifact F1.zip to release R1
+    Then no error occured
+     And artifact F1.zip of release R1 is served as "application/zip"
+
+  Scenario: Artifacts that browsers could run are served as binary
+    Given core C1 with release R1 owned by admin A1
+    When admin A1 starts uploading a 1024 bytes "text/html" artifact F1.html to release R1
+     And admin A1 uploads part 1 of 1024 bytes of artifact F1.html to release R1
+     And admin A1 completes the upload of artifact F1.html to release R1
+    Then no error occured
+     And artifact F1.html of release R1 is served as "application/octet-stream"
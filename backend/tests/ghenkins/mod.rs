@@ -1,7 +1,17 @@
+use crate::user::{random_file, two_factor_code, User};
 use crate::World;
+use backend::fairings::config::RetronomiconConfig;
+use backend::guards::storage::Storage;
+use backend::utils::images::backfill;
+use backend::utils::uploads::UploadHashers;
 use cucumber::{given, then, when};
+use retronomicon_db::RetronomiconDbPool;
 use retronomicon_dto as dto;
+use rocket::futures::lock::Mutex;
+use std::collections::BTreeMap;
+use std::fmt::Write;
 use std::str::FromStr;
+use std::sync::Arc;
 
 #[derive(Debug, cucumber::Parameter)]
 #[param(name = "team_role", regex = "(owner|admin|member)")]
@@ -18,10 +28,17 @@ impl FromStr for TeamRole {
 }
 
 #[derive(Debug, cucumber::Parameter)]
-#[param(name = "user", regex = r#"(admin (\w+)|user (\w+)|anonymous user)"#)]
+#[param(
+    name = "user",
+    regex = r#"(admin (\w+)|user (\w+)|token (\w+)|session (\w+)|anonymous user)"#
+)]
 pub enum UserParam {
     Admin(String),
     User(String),
+    /// A personal access token created by a user.
+    Token(String),
+    /// Another session of a user, logged in with their password.
+    Session(String),
     Anonymous,
 }
 
@@ -35,6 +52,10 @@ impl FromStr for UserParam {
             Ok(Self::Admin(name.to_string()))
         } else if let Some(name) = s.strip_prefix("user ") {
             Ok(Self::User(name.to_string()))
+        } else if let Some(name) = s.strip_prefix("token ") {
+            Ok(Self::Token(name.to_string()))
+        } else if let Some(name) = s.strip_prefix("session ") {
+            Ok(Self::Session(name.to_string()))
         } else {
             Err(format!("Invalid user: {}", s))
         }
@@ -244,10 +265,2742 @@ async fn then_user_can_see(w: &mut World, user: UserParam, image: String, game:
         .find(|i| i.name == format!("{image}.png"))
         .expect("Image not in the list of images.");
 
-    // Download the image.
-    reqwest::get(&i.url)
+    // Download the image. Files in the local storage are served by the
+    // backend itself.
+    let url = url::Url::parse(&i.url).expect("Invalid image URL");
+    if url.path().starts_with("/api/files/") {
+        let response = w.client.get(url.path().to_string()).dispatch().await;
+        assert_eq!(
+            response.status(),
+            rocket::http::Status::Ok,
+            "Could not download the image"
+        );
+    } else {
+        reqwest::get(&i.url)
+            .await
+            .expect("Could not get a response")
+            .error_for_status()
+            .expect("Could not download the image");
+    }
+}
+
+#[when(expr = "{user} uploads image {word} to game {word} as {word}")]
+async fn game_upload_image_with_role(
+    w: &mut World,
+    user: UserParam,
+    image: String,
+    game: String,
+    role: String,
+) {
+    let user = w.auth_user(&user).await.unwrap();
+    let game_id = w.games[&game];
+    let params = dto::games::GameImageUploadQueryParams {
+        role: Some(dto::images::ImageRole::from_str(&role).unwrap()),
+        ..Default::default()
+    };
+
+    let result = user
+        .lock()
+        .await
+        .upload_image_with(game_id, &image, &format!("{game_id} / {image}.png"), params)
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} uploads image {word} showing {string} to game {word}")]
+async fn game_upload_image_showing(
+    w: &mut World,
+    user: UserParam,
+    image: String,
+    text: String,
+    game: String,
+) {
+    let user = w.auth_user(&user).await.unwrap();
+    let game_id = w.games[&game];
+
+    let result = user
+        .lock()
+        .await
+        .upload_image_with(game_id, &image, &text, Default::default())
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} uploads duplicate image {word} showing {string} to game {word}")]
+async fn game_upload_duplicate_image(
+    w: &mut World,
+    user: UserParam,
+    image: String,
+    text: String,
+    game: String,
+) {
+    let user = w.auth_user(&user).await.unwrap();
+    let game_id = w.games[&game];
+    let params = dto::games::GameImageUploadQueryParams {
+        allow_duplicates: true,
+        ..Default::default()
+    };
+
+    let result = user
+        .lock()
+        .await
+        .upload_image_with(game_id, &image, &text, params)
+        .await;
+    w.record_result(result);
+}
+
+async fn game_image(
+    w: &mut World,
+    image: &str,
+    game: &str,
+    role: Option<dto::images::ImageRole>,
+) -> Option<dto::images::Image> {
+    w.assert_result_ok();
+
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let game_id = w.games[game];
+    let images = user
+        .lock()
+        .await
+        .get_game_images_with_role(game_id, role)
+        .await
+        .unwrap();
+    images
+        .into_iter()
+        .find(|i| i.name == format!("{image}.png"))
+}
+
+fn image_roles(roles: &str) -> Vec<dto::images::ImageRole> {
+    roles
+        .split(',')
+        .map(|r| dto::images::ImageRole::from_str(r.trim()).unwrap())
+        .collect()
+}
+
+#[when(expr = "{user} sets the roles of image {word} of game {word} to {string}")]
+async fn game_image_set_roles(
+    w: &mut World,
+    user: UserParam,
+    image: String,
+    game: String,
+    roles: String,
+) {
+    let image_id = game_image(w, &image, &game, None).await.unwrap().id;
+    let user = w.auth_user(&user).await.unwrap();
+    let game_id = w.games[&game];
+
+    let result = user
+        .lock()
+        .await
+        .set_game_image_roles(game_id, image_id, image_roles(&roles))
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "image {word} of game {word} has roles {string}")]
+async fn game_image_has_roles(w: &mut World, image: String, game: String, roles: String) {
+    let mut expected = image_roles(&roles);
+    expected.sort();
+    let mut actual = game_image(w, &image, &game, None).await.unwrap().roles;
+    actual.sort();
+    assert_eq!(actual, expected);
+}
+
+#[then(expr = "images of game {word} with role {word} include image {word}")]
+async fn game_images_with_role_include(w: &mut World, game: String, role: String, image: String) {
+    let role = dto::images::ImageRole::from_str(&role).unwrap();
+    assert!(game_image(w, &image, &game, Some(role)).await.is_some());
+}
+
+#[then(expr = "images of game {word} with role {word} do not include image {word}")]
+async fn game_images_with_role_exclude(w: &mut World, game: String, role: String, image: String) {
+    let role = dto::images::ImageRole::from_str(&role).unwrap();
+    assert!(game_image(w, &image, &game, Some(role)).await.is_none());
+}
+
+#[then(expr = "image {word} of game {word} has derivatives {string}")]
+async fn game_image_has_derivatives(w: &mut World, image: String, game: String, sizes: String) {
+    let image = game_image(w, &image, &game, None).await.unwrap();
+    let actual = image
+        .derivatives
+        .iter()
+        .map(|d| {
+            assert_eq!(d.mime_type, "image/webp");
+            format!("{}x{}", d.width, d.height)
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    assert_eq!(actual, sizes);
+}
+
+/// Images uploaded before perceptual hashes and derivatives were computed
+/// have neither. There is no API to create such images, so they are edited
+/// in the database.
+#[when(expr = "image {word} of game {word} was uploaded before derivatives were generated")]
+async fn game_image_without_derivatives(w: &mut World, image: String, game: String) {
+    let image_id = game_image(w, &image, &game, None).await.unwrap().id;
+    let url = w
+        .client
+        .rocket()
+        .figment()
+        .find_value("databases.retronomicon_db.url")
+        .unwrap()
+        .into_string()
+        .unwrap();
+    let (client, connection) = tokio_postgres::connect(&url, tokio_postgres::NoTls)
+        .await
+        .unwrap();
+    rocket::tokio::spawn(connection);
+    client
+        .execute(
+            "UPDATE game_images SET perceptual_hash = NULL WHERE id = $1",
+            &[&image_id],
+        )
+        .await
+        .unwrap();
+    client
+        .execute(
+            "DELETE FROM game_image_derivatives WHERE game_image_id = $1",
+            &[&image_id],
+        )
+        .await
+        .unwrap();
+}
+
+#[when("the server backfills game images")]
+async fn game_images_backfill(w: &mut World) {
+    let rocket = w.client.rocket();
+    let storage = Storage::from_figment(rocket.figment()).unwrap();
+    backfill(
+        rocket.state::<RetronomiconDbPool>().unwrap(),
+        &storage,
+        &rocket
+            .state::<RetronomiconConfig>()
+            .unwrap()
+            .image_thumbnail_sizes,
+    )
+    .await
+    .unwrap();
+}
+
+/// Create a core with a release, on a new system and platform.
+#[given(expr = "core {word} with release {word} owned by {user}")]
+async fn given_a_release(w: &mut World, core: String, release: String, user: UserParam) {
+    let team = w.team(&user, &core).await.unwrap();
+    let user = w.auth_user(&user).await.unwrap();
+    let mut user = user.lock().await;
+
+    let system = user.create_system(team.id, &core).await.unwrap();
+    let platform = user.create_platform(team.id, &core).await.unwrap();
+    let core_id = user
+        .create_core(team.id, system.id, &core)
+        .await
+        .unwrap()
+        .id;
+    let release_id = user
+        .create_release(core_id, platform.id, "1.0.0")
+        .await
+        .unwrap()
+        .id;
+
+    w.platforms.insert(core.clone(), platform.id);
+    w.cores.insert(core, core_id);
+    w.releases.insert(release, (core_id, release_id));
+}
+
+/// The content of artifacts. Parts of an artifact are filled the same way,
+/// so they add up to the whole artifact.
+fn artifact_data(size: usize) -> Vec<u8> {
+    vec![0x5a; size]
+}
+
+async fn start_upload(
+    w: &mut World,
+    user: UserParam,
+    size: i64,
+    mime_type: &str,
+    name: String,
+    release: String,
+) {
+    w.assert_result_ok();
+
+    let (core_id, release_id) = *w.releases.get(&release).unwrap();
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .create_upload(core_id, release_id, &name, mime_type, size)
+        .await;
+    if let Ok(u) = &result {
+        w.uploads.insert(name, u.id);
+    }
+    w.record_result(result);
+}
+
+#[when(expr = "{user} starts uploading a {int} bytes artifact {word} to release {word}")]
+async fn upload_start(w: &mut World, user: UserParam, size: i64, name: String, release: String) {
+    start_upload(w, user, size, "application/octet-stream", name, release).await;
+}
+
+#[when(expr = "{user} starts uploading a {int} bytes {string} artifact {word} to release {word}")]
+async fn upload_start_with_mime_type(
+    w: &mut World,
+    user: UserParam,
+    size: i64,
+    mime_type: String,
+    name: String,
+    release: String,
+) {
+    start_upload(w, user, size, &mime_type, name, release).await;
+}
+
+#[when(expr = "{user} uploads part {int} of {int} bytes of artifact {word} to release {word}")]
+async fn upload_part(
+    w: &mut World,
+    user: UserParam,
+    part: u32,
+    size: usize,
+    name: String,
+    release: String,
+) {
+    w.assert_result_ok();
+
+    let (core_id, release_id) = *w.releases.get(&release).unwrap();
+    let upload_id = *w.uploads.get(&name).unwrap();
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .upload_part(core_id, release_id, upload_id, part, artifact_data(size))
+        .await;
+    w.record_result(result);
+}
+
+/// Simulate a restart of the server, or parts sent to another instance.
+#[when(expr = "the server forgets the checksums of artifact {word}")]
+async fn upload_forget_checksums(w: &mut World, name: String) {
+    let upload_id = *w.uploads.get(&name).unwrap();
+    w.client
+        .rocket()
+        .state::<UploadHashers>()
+        .unwrap()
+        .remove(upload_id);
+}
+
+async fn upload_complete_with_headers(
+    w: &mut World,
+    user: UserParam,
+    name: String,
+    release: String,
+    headers: Vec<(&'static str, String)>,
+) {
+    w.assert_result_ok();
+
+    let (core_id, release_id) = *w.releases.get(&release).unwrap();
+    let upload_id = *w.uploads.get(&name).unwrap();
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .complete_upload(core_id, release_id, upload_id, headers)
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} completes the upload of artifact {word} to release {word}")]
+async fn upload_complete(w: &mut World, user: UserParam, name: String, release: String) {
+    upload_complete_with_headers(w, user, name, release, vec![]).await;
+}
+
+#[when(
+    expr = "{user} completes the upload of artifact {word} to release {word} with a sha256 of {int} bytes"
+)]
+async fn upload_complete_sha256(
+    w: &mut World,
+    user: UserParam,
+    name: String,
+    release: String,
+    size: usize,
+) {
+    use base64::Engine;
+    use sha2::Digest;
+
+    let sha256 = sha2::Sha256::digest(artifact_data(size));
+    let digest = format!(
+        "SHA-256={}",
+        base64::engine::general_purpose::STANDARD.encode(sha256)
+    );
+    upload_complete_with_headers(w, user, name, release, vec![("Digest", digest)]).await;
+}
+
+#[when(
+    expr = "{user} completes the upload of artifact {word} to release {word} with an expected size of {int} bytes"
+)]
+async fn upload_complete_size(
+    w: &mut World,
+    user: UserParam,
+    name: String,
+    release: String,
+    size: i64,
+) {
+    let headers = vec![("X-Expected-Size", size.to_string())];
+    upload_complete_with_headers(w, user, name, release, headers).await;
+}
+
+#[then(expr = "release {word} has a {int} bytes artifact {word}")]
+async fn release_has_artifact(w: &mut World, release: String, size: u64, name: String) {
+    w.assert_result_ok();
+
+    let (core_id, release_id) = *w.releases.get(&release).unwrap();
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let artifacts = user
+        .lock()
+        .await
+        .get_release_artifacts(core_id, release_id)
+        .await
+        .unwrap();
+
+    let a = artifacts
+        .iter()
+        .find(|a| a.filename == name)
+        .expect("Artifact not in the list of artifacts.");
+    assert_eq!(a.r#ref.size.map(|s| s.get()), Some(size));
+}
+
+#[then(expr = "artifact {word} of release {word} is served as {string}")]
+async fn artifact_served_as(w: &mut World, name: String, release: String, mime_type: String) {
+    w.assert_result_ok();
+
+    let (core_id, release_id) = *w.releases.get(&release).unwrap();
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let artifacts = user
+        .lock()
+        .await
+        .get_release_artifacts(core_id, release_id)
+        .await
+        .unwrap();
+    let a = artifacts
+        .iter()
+        .find(|a| a.filename == name)
+        .expect("Artifact not in the list of artifacts.");
+
+    // Files in the local storage are served by the backend itself.
+    let url = url::Url::parse(&a.download_url).expect("Invalid artifact URL");
+    let response = w.client.get(url.path().to_string()).dispatch().await;
+    assert_eq!(response.status(), rocket::http::Status::Ok);
+    assert_eq!(
+        response.content_type().map(|c| c.to_string()),
+        Some(mime_type)
+    );
+    assert_eq!(
+        response.headers().get_one("X-Content-Type-Options"),
+        Some("nosniff")
+    );
+}
+
+#[then(
+    expr = "artifact {word} of release {word} and artifact {word} of release {word} share their content"
+)]
+async fn artifacts_share_content(
+    w: &mut World,
+    name1: String,
+    release1: String,
+    name2: String,
+    release2: String,
+) {
+    w.assert_result_ok();
+
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let mut urls = Vec::new();
+    for (name, release) in [(name1, release1), (name2, release2)] {
+        let (core_id, release_id) = *w.releases.get(&release).unwrap();
+        let artifacts = user
+            .lock()
+            .await
+            .get_release_artifacts(core_id, release_id)
+            .await
+            .unwrap();
+        let a = artifacts
+            .into_iter()
+            .find(|a| a.filename == name)
+            .expect("Artifact not in the list of artifacts.");
+        urls.push(a.download_url);
+    }
+
+    assert_eq!(urls[0], urls[1]);
+}
+
+#[when(expr = "{user} registers signing key {word} for team {word}")]
+async fn register_signing_key(w: &mut World, user: UserParam, name: String, team: String) {
+    w.assert_result_ok();
+
+    let team_id = w.teams.get(&team).unwrap().id;
+    let signing_key = ed25519_dalek::SigningKey::from_bytes(&rand::random());
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .create_team_key(team_id, &name, signing_key.verifying_key().as_bytes())
+        .await;
+    if let Ok(k) = &result {
+        w.signing_keys.insert(name, (team_id, k.id, signing_key));
+    }
+    w.record_result(result);
+}
+
+#[when(expr = "{user} revokes signing key {word}")]
+async fn revoke_signing_key(w: &mut World, user: UserParam, name: String) {
+    w.assert_result_ok();
+
+    let (team_id, key_id, _) = w.signing_keys.get(&name).unwrap();
+    let (team_id, key_id) = (*team_id, *key_id);
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user.lock().await.revoke_team_key(team_id, key_id).await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} signs the manifest of release {word} with key {word}")]
+async fn sign_manifest(w: &mut World, user: UserParam, release: String, name: String) {
+    use ed25519_dalek::Signer;
+    w.assert_result_ok();
+
+    let (core_id, release_id) = *w.releases.get(&release).unwrap();
+    let signing_key = w.signing_keys.get(&name).unwrap().2.clone();
+    let user = w.auth_user(&user).await.unwrap();
+    let mut user = user.lock().await;
+    let manifest = user
+        .get_release_manifest(core_id, release_id)
+        .await
+        .unwrap();
+    let signature = signing_key.sign(&manifest.to_canonical_json());
+
+    let result = user
+        .sign_release_manifest(
+            core_id,
+            release_id,
+            signing_key.verifying_key().as_bytes(),
+            &signature.to_bytes(),
+        )
+        .await;
+    drop(user);
+    w.record_result(result);
+}
+
+#[then(expr = "the manifest of release {word} lists a {int} bytes artifact {word}")]
+async fn manifest_lists_artifact(w: &mut World, release: String, size: i64, name: String) {
+    use sha2::Digest;
+    w.assert_result_ok();
+
+    let (core_id, release_id) = *w.releases.get(&release).unwrap();
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let manifest = user
+        .lock()
+        .await
+        .get_release_manifest(core_id, release_id)
+        .await
+        .unwrap();
+    let artifact = manifest
+        .artifacts
+        .iter()
+        .find(|a| a.filename == name)
+        .expect("Artifact not in the manifest.");
+
+    let data = artifact_data(size as usize);
+    assert_eq!(artifact.size, size);
+    assert_eq!(*artifact.sha256, sha2::Sha256::digest(data).to_vec());
+}
+
+/// Fetch the signatures of a release manifest, checking that they are all
+/// valid for the current manifest.
+async fn manifest_signatures(
+    w: &mut World,
+    release: &str,
+) -> Vec<dto::cores::manifests::ReleaseManifestSignature> {
+    use ed25519_dalek::Verifier;
+
+    let (core_id, release_id) = *w.releases.get(release).unwrap();
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let mut user = user.lock().await;
+    let manifest = user
+        .get_release_manifest(core_id, release_id)
+        .await
+        .unwrap();
+    let signatures = user
+        .get_release_manifest_signatures(core_id, release_id)
+        .await
+        .unwrap();
+
+    let canonical = manifest.to_canonical_json();
+    for s in &signatures {
+        let key = ed25519_dalek::VerifyingKey::try_from(s.public_key.as_slice()).unwrap();
+        let signature = ed25519_dalek::Signature::from_slice(&s.signature).unwrap();
+        key.verify(&canonical, &signature)
+            .expect("Invalid manifest signature.");
+    }
+    signatures
+}
+
+#[then(expr = "the manifest of release {word} is signed by the server")]
+async fn manifest_signed_by_server(w: &mut World, release: String) {
+    w.assert_result_ok();
+
+    let signatures = manifest_signatures(w, &release).await;
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let server_key = user.lock().await.get_manifest_server_key().await.unwrap();
+
+    assert!(signatures
+        .iter()
+        .any(|s| s.key.is_none() && *s.public_key == *server_key.public_key));
+}
+
+#[then(expr = "the manifest of release {word} is signed with key {word}")]
+async fn manifest_signed_with_key(w: &mut World, release: String, name: String) {
+    let key_id = w.signing_keys.get(&name).unwrap().1;
+    let signatures = manifest_signatures(w, &release).await;
+
+    assert!(signatures
+        .iter()
+        .any(|s| s.key.as_ref().is_some_and(|k| k.id == key_id)));
+}
+
+#[then(expr = "the manifest of release {word} is not signed with key {word}")]
+async fn manifest_not_signed_with_key(w: &mut World, release: String, name: String) {
+    let key_id = w.signing_keys.get(&name).unwrap().1;
+    let signatures = manifest_signatures(w, &release).await;
+
+    assert!(!signatures
+        .iter()
+        .any(|s| s.key.as_ref().is_some_and(|k| k.id == key_id)));
+}
+
+#[when(expr = "{user} creates a token {word} with scope {word}")]
+async fn create_token(w: &mut World, user: UserParam, name: String, permission: String) {
+    create_token_for(w, user, name, permission, None).await;
+}
+
+#[when(expr = "{user} creates a token {word} with scope {word} for core {word}")]
+async fn create_token_for_core(
+    w: &mut World,
+    user: UserParam,
+    name: String,
+    permission: String,
+    core: String,
+) {
+    let core_id = *w.cores.get(&core).unwrap();
+    create_token_for(w, user, name, permission, Some(core_id)).await;
+}
+
+async fn create_token_for(
+    w: &mut World,
+    user: UserParam,
+    name: String,
+    permission: String,
+    core: Option<i32>,
+) {
+    w.assert_result_ok();
+
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .create_token(
+            &name,
+            vec![dto::tokens::TokenScopeRequest {
+                permission: permission.parse().unwrap(),
+                team: None,
+                core: core.map(Into::into),
+            }],
+        )
+        .await;
+    if let Ok(t) = &result {
+        let client = w.client.clone();
+        w.token_ids.insert(name.clone(), t.info.id);
+        w.tokens.insert(
+            name,
+            Arc::new(Mutex::new(User::token(client, t.token.clone()))),
+        );
+    }
+    w.record_result(result);
+}
+
+#[when(expr = "{user} revokes token {word}")]
+async fn revoke_token(w: &mut World, user: UserParam, name: String) {
+    w.assert_result_ok();
+
+    let token_id = *w.token_ids.get(&name).unwrap();
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user.lock().await.revoke_token(token_id).await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} logs in as session {word}")]
+async fn login_session(w: &mut World, user: UserParam, session: String) {
+    w.assert_result_ok();
+
+    let user = w.user(&user).await.unwrap();
+    let email = user.lock().await.email().unwrap().to_string();
+    let result = User::login(w.client.clone(), &session, &email, &email)
+        .await
+        .map(|u| {
+            w.sessions.insert(session, Arc::new(Mutex::new(u)));
+        });
+    w.record_result(result);
+}
+
+#[when(expr = "{user} changes their password to {word}")]
+async fn change_password(w: &mut World, user: UserParam, password: String) {
+    w.assert_result_ok();
+
+    let user = w.user(&user).await.unwrap();
+    let mut user = user.lock().await;
+    let current = user.email().unwrap().to_string();
+    let result = user.change_password(Some(&current), &password).await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} changes their password to {word} with current password {word}")]
+async fn change_password_with_current(
+    w: &mut World,
+    user: UserParam,
+    password: String,
+    current: String,
+) {
+    w.assert_result_ok();
+
+    let user = w.user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .change_password(Some(&current), &password)
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "{user} can log in with password {word}")]
+async fn can_login(w: &mut World, user: UserParam, password: String) {
+    let user = w.user(&user).await.unwrap();
+    let email = user.lock().await.email().unwrap().to_string();
+    User::login(w.client.clone(), "login", &email, &password)
+        .await
+        .unwrap();
+}
+
+#[then(expr = "{user} cannot log in with password {word}")]
+async fn cannot_login(w: &mut World, user: UserParam, password: String) {
+    let user = w.user(&user).await.unwrap();
+    let email = user.lock().await.email().unwrap().to_string();
+    assert!(User::login(w.client.clone(), "login", &email, &password)
+        .await
+        .is_err());
+}
+
+#[then(expr = "{user} cannot log in with their initial password")]
+async fn cannot_login_initial(w: &mut World, user: UserParam) {
+    let user = w.user(&user).await.unwrap();
+    let email = user.lock().await.email().unwrap().to_string();
+    assert!(User::login(w.client.clone(), "login", &email, &email)
+        .await
+        .is_err());
+}
+
+#[then(expr = "{user} is logged out")]
+async fn is_logged_out(w: &mut World, user: UserParam) {
+    let user = w.user(&user).await.unwrap();
+    assert!(user.lock().await.whoami().await.is_err());
+}
+
+#[then(expr = "{user} is logged in")]
+async fn is_logged_in(w: &mut World, user: UserParam) {
+    let user = w.user(&user).await.unwrap();
+    user.lock().await.whoami().await.unwrap();
+}
+
+#[then(expr = "{user} can log in with their initial password")]
+async fn can_login_initial(w: &mut World, user: UserParam) {
+    let user = w.user(&user).await.unwrap();
+    let email = user.lock().await.email().unwrap().to_string();
+    User::login(w.client.clone(), "login", &email, &email)
+        .await
+        .unwrap();
+}
+
+#[when(expr = "{user} enables two-factor authentication")]
+async fn enable_two_factor(w: &mut World, user: UserParam) {
+    w.assert_result_ok();
+
+    let user = w.user(&user).await.unwrap();
+    let mut user = user.lock().await;
+    let email = user.email().unwrap().to_string();
+    let result = user.enable_two_factor().await.map(|secret| {
+        w.two_factor.insert(email, secret);
+    });
+    w.record_result(result);
+}
+
+#[when(expr = "{user} disables two-factor authentication")]
+async fn disable_two_factor(w: &mut World, user: UserParam) {
+    w.assert_result_ok();
+
+    let user = w.user(&user).await.unwrap();
+    let mut user = user.lock().await;
+    let (secret, _) = &w.two_factor[user.email().unwrap()];
+    let result = user.disable_two_factor(&two_factor_code(secret, 1)).await;
+    w.record_result(result);
+}
+
+/// Log in with the initial password of a user and a two-factor code, which
+/// is either `a two-factor code` (the TOTP code of the next time step, as
+/// the current one was used to enable it), `the same two-factor code` (the
+/// last code used, even if a new time step started), `recovery code N` or
+/// `code X`.
+async fn login_with_code(
+    w: &mut World,
+    user: UserParam,
+    code: &str,
+) -> Result<User, anyhow::Error> {
+    let user = w.user(&user).await.unwrap();
+    let email = user.lock().await.email().unwrap().to_string();
+    let (secret, recovery_codes) = &w.two_factor[&email];
+
+    let code = if code == "a two-factor code" {
+        two_factor_code(secret, 1)
+    } else if code == "the same two-factor code" {
+        w.last_two_factor_code.clone().expect("No code used")
+    } else if let Some(n) = code.strip_prefix("recovery code ") {
+        recovery_codes[n.parse::<usize>().unwrap() - 1].clone()
+    } else {
+        code.trim_start_matches("code ").to_string()
+    };
+    w.last_two_factor_code = Some(code.clone());
+    User::login_with_code(w.client.clone(), "login", &email, &email, Some(&code)).await
+}
+
+#[then(expr = "{user} can log in with their initial password and {}")]
+async fn can_login_with_code(w: &mut World, user: UserParam, code: String) {
+    login_with_code(w, user, &code).await.unwrap();
+}
+
+#[then(expr = "{user} cannot log in with their initial password and {}")]
+async fn cannot_login_with_code(w: &mut World, user: UserParam, code: String) {
+    assert!(login_with_code(w, user, &code).await.is_err());
+}
+
+#[then(expr = "OpenID Connect provider {word} is available")]
+async fn oidc_provider_available(w: &mut World, provider: String) {
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let providers = user.lock().await.oidc_providers().await.unwrap();
+    assert!(providers.iter().any(|p| p.name == provider));
+}
+
+#[when(expr = "{user} starts logging in with OpenID Connect provider {word}")]
+async fn start_oidc_login(w: &mut World, user: UserParam, provider: String) {
+    w.assert_result_ok();
+
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.start_oidc_login(&provider).await;
+    w.oidc_redirect = result.as_ref().ok().cloned();
+    w.record_result(result);
+}
+
+#[then(expr = "the login is redirected to {word} with a PKCE challenge")]
+async fn oidc_redirected(w: &mut World, endpoint: String) {
+    let url = url::Url::parse(w.oidc_redirect.as_ref().expect("No redirect")).unwrap();
+    assert!(url.as_str().starts_with(&endpoint));
+
+    let query = url.query_pairs().into_owned().collect::<BTreeMap<_, _>>();
+    assert_eq!(query["client_id"], "retronomicon");
+    assert_eq!(query["code_challenge_method"], "S256");
+    assert!(!query["code_challenge"].is_empty());
+    assert!(!query["state"].is_empty());
+    assert!(!query["nonce"].is_empty());
+}
+
+#[when(expr = "{user} returns from OpenID Connect provider {word} with state {word}")]
+async fn oidc_callback(w: &mut World, user: UserParam, provider: String, state: String) {
+    w.assert_result_ok();
+
+    let user = w.user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .oidc_callback(&provider, "code", &state)
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "{user} has {int} linked identities")]
+async fn linked_identities(w: &mut World, user: UserParam, count: usize) {
+    let user = w.auth_user(&user).await.unwrap();
+    let identities = user.lock().await.identities().await.unwrap();
+    assert_eq!(identities.len(), count);
+}
+
+#[when(expr = "{user} unlinks identity {int}")]
+async fn unlink_identity(w: &mut World, user: UserParam, identity_id: i32) {
+    w.assert_result_ok();
+
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user.lock().await.unlink_identity(identity_id).await;
+    w.record_result(result);
+}
+
+#[then(expr = "{user} has {int} session(s)")]
+async fn has_sessions(w: &mut World, user: UserParam, count: usize) {
+    let user = w.user(&user).await.unwrap();
+    let sessions = user.lock().await.sessions().await.unwrap();
+    assert_eq!(sessions.len(), count);
+    assert_eq!(sessions.iter().filter(|s| s.current).count(), 1);
+}
+
+#[when(expr = "{user} revokes session {word}")]
+async fn revoke_session(w: &mut World, user: UserParam, session: String) {
+    w.assert_result_ok();
+
+    let sessions = w.sessions[&session].lock().await.sessions().await.unwrap();
+    let session_id = sessions.iter().find(|s| s.current).unwrap().id;
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.revoke_session(session_id).await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} revokes all their other sessions")]
+async fn revoke_all_sessions(w: &mut World, user: UserParam) {
+    w.assert_result_ok();
+
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.revoke_all_sessions().await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} logs out")]
+async fn logout(w: &mut World, user: UserParam) {
+    w.assert_result_ok();
+
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.logout().await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} terminates the sessions of {user}")]
+async fn terminate_sessions(w: &mut World, user: UserParam, target: UserParam) {
+    w.assert_result_ok();
+
+    let target = w.user(&target).await.unwrap().lock().await.id();
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.terminate_sessions(target).await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} deletes their account")]
+async fn delete_account(w: &mut World, user: UserParam) {
+    w.assert_result_ok();
+
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.delete_account(None, vec![]).await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} deletes their account confirming with {word}")]
+async fn delete_account_confirming(w: &mut World, user: UserParam, confirm: String) {
+    w.assert_result_ok();
+
+    let user = w.user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .delete_account(Some(&confirm), vec![])
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} deletes their account giving team {word} to {user}")]
+async fn delete_account_transferring(
+    w: &mut World,
+    user: UserParam,
+    team: String,
+    new_owner: UserParam,
+) {
+    w.assert_result_ok();
+
+    let team_id = w.team(&user, &team).await.unwrap().id;
+    let new_owner = w.user(&new_owner).await.unwrap().lock().await.id();
+    let user = w.user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .delete_account(
+            None,
+            vec![dto::user::TeamOwnershipTransfer {
+                team_id,
+                user_id: new_owner,
+            }],
+        )
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "the export of {user} contains their email and team {word}")]
+async fn export_contains(w: &mut World, user: UserParam, team: String) {
+    let team_id = w.team(&user, &team).await.unwrap().id;
+    let user = w.user(&user).await.unwrap();
+    let mut user = user.lock().await;
+    let export = user.export().await.unwrap();
+    assert_eq!(Some(export.profile.email.as_str()), user.email());
+    assert!(export.teams.iter().any(|t| t.team.id == team_id));
+}
+
+#[when(expr = "{user} changes their email to one at {word}")]
+async fn change_email(w: &mut World, user: UserParam, domain: String) {
+    w.assert_result_ok();
+
+    let email = User::create_email(&domain);
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.change_email(&email, None).await;
+    w.last_email = Some(email);
+    w.record_result(result);
+}
+
+#[when(expr = "{user} changes their email to one at {word} with password {word}")]
+async fn change_email_with_password(
+    w: &mut World,
+    user: UserParam,
+    domain: String,
+    password: String,
+) {
+    w.assert_result_ok();
+
+    let email = User::create_email(&domain);
+    let user = w.user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .change_email(&email, Some(&password))
+        .await;
+    w.last_email = Some(email);
+    w.record_result(result);
+}
+
+#[when(expr = "{user} changes their email to the one of {user}")]
+async fn change_email_taken(w: &mut World, user: UserParam, other: UserParam) {
+    w.assert_result_ok();
+
+    let other = w.user(&other).await.unwrap();
+    let email = other.lock().await.email().unwrap().to_string();
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.change_email(&email, None).await;
+    w.record_result(result);
+}
+
+#[then(expr = "{user} can log in with their new email")]
+async fn can_login_new_email(w: &mut World, user: UserParam) {
+    let new_email = w.last_email.clone().expect("No email changed");
+    let user = w.user(&user).await.unwrap();
+    let password = user.lock().await.email().unwrap().to_string();
+    User::login(w.client.clone(), "login", &new_email, &password)
+        .await
+        .unwrap();
+}
+
+#[then(expr = "{user} is in the root team")]
+async fn is_in_root_team(w: &mut World, user: UserParam) {
+    let root_team_id = w
+        .client
+        .rocket()
+        .state::<RetronomiconConfig>()
+        .unwrap()
+        .root_team_id;
+    let user = w.user(&user).await.unwrap();
+    let details = user.lock().await.whoami().await.unwrap();
+    assert!(details.teams.iter().any(|t| t.team.id == root_team_id));
+}
+
+#[then(expr = "{user} is not in the root team")]
+async fn is_not_in_root_team(w: &mut World, user: UserParam) {
+    let root_team_id = w
+        .client
+        .rocket()
+        .state::<RetronomiconConfig>()
+        .unwrap()
+        .root_team_id;
+    let user = w.user(&user).await.unwrap();
+    let details = user.lock().await.whoami().await.unwrap();
+    assert!(!details.teams.iter().any(|t| t.team.id == root_team_id));
+}
+
+#[when(expr = "{user} declines the invitation to team {word}")]
+async fn user_declines_invitation(w: &mut World, user: UserParam, team: String) {
+    w.assert_result_ok();
+
+    let team = w.team(&user, &team).await.unwrap();
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.decline_team_invitation(team.id).await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} cancels the invitation of {user} to team {word}")]
+async fn user_cancels_invitation(w: &mut World, admin: UserParam, user: UserParam, team: String) {
+    w.assert_result_ok();
+
+    let team = w.team(&admin, &team).await.unwrap();
+    let user_id = w.user(&user).await.unwrap().lock().await.id();
+    let admin = w.user(&admin).await.unwrap();
+    let result = admin
+        .lock()
+        .await
+        .cancel_team_invitation(team.id, user_id)
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "{user} sees {int} pending invitation(s) to team {word}")]
+async fn team_has_invitations(w: &mut World, user: UserParam, count: usize, team: String) {
+    w.assert_result_ok();
+
+    let team = w.team(&user, &team).await.unwrap();
+    let user = w.user(&user).await.unwrap();
+    let invitations = user.lock().await.team_invitations(team.id).await.unwrap();
+    assert_eq!(invitations.len(), count);
+}
+
+#[when(expr = "{user} changes the role of {user} in team {word} to {team_role}")]
+async fn user_changes_role(
+    w: &mut World,
+    admin: UserParam,
+    user: UserParam,
+    team: String,
+    role: TeamRole,
+) {
+    w.assert_result_ok();
+
+    let team = w.team(&admin, &team).await.unwrap();
+    let user_id = w.user(&user).await.unwrap().lock().await.id();
+    let admin = w.user(&admin).await.unwrap();
+    let result = admin
+        .lock()
+        .await
+        .set_team_role(team.id, user_id, role.0)
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} removes {user} from team {word}")]
+async fn user_removes_member(w: &mut World, admin: UserParam, user: UserParam, team: String) {
+    w.assert_result_ok();
+
+    let team = w.team(&admin, &team).await.unwrap();
+    let user_id = w.user(&user).await.unwrap().lock().await.id();
+    let admin = w.user(&admin).await.unwrap();
+    let result = admin.lock().await.remove_from_team(team.id, user_id).await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} leaves team {word}")]
+async fn user_leaves_team(w: &mut World, user: UserParam, team: String) {
+    w.assert_result_ok();
+
+    let team = w.team(&user, &team).await.unwrap();
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.leave_team(team.id).await;
+    w.record_result(result);
+}
+
+#[then(expr = "team {word} will not have {user}")]
+async fn team_does_not_have_user(w: &mut World, team: String, user: UserParam) {
+    w.assert_result_ok();
+
+    let team = w.team(&user, &team).await.unwrap();
+    let user = w.user(&user).await.unwrap();
+    let user_id = user.lock().await.id();
+    let team_details = user.lock().await.team_details(team.id).await.unwrap();
+    assert!(!team_details.users.iter().any(|u| u.user.id == user_id));
+}
+
+#[then(expr = "{user} has {int} pending invitation(s)")]
+async fn user_has_invitations(w: &mut World, user: UserParam, count: usize) {
+    w.assert_result_ok();
+
+    let user = w.user(&user).await.unwrap();
+    let invitations = user.lock().await.invitations().await.unwrap();
+    assert_eq!(invitations.len(), count);
+}
+
+#[then(expr = "{user} has {int} unread notification(s)")]
+async fn user_has_unread_notifications(w: &mut World, user: UserParam, count: usize) {
+    w.assert_result_ok();
+
+    let user = w.user(&user).await.unwrap();
+    let notifications = user.lock().await.notifications(true).await.unwrap();
+    assert_eq!(notifications.len(), count);
+}
+
+#[then(expr = "{user} has a {word} notification")]
+async fn user_has_notification(w: &mut World, user: UserParam, kind: String) {
+    w.assert_result_ok();
+
+    let kind = dto::notifications::NotificationKind::from_str(&kind).unwrap();
+    let user = w.user(&user).await.unwrap();
+    let notifications = user.lock().await.notifications(false).await.unwrap();
+    assert!(notifications.iter().any(|n| n.kind == kind));
+}
+
+#[when(expr = "{user} reads their notifications")]
+async fn user_reads_notifications(w: &mut World, user: UserParam) {
+    w.assert_result_ok();
+
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.read_notifications().await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} turns off in-app {word} notifications")]
+async fn user_turns_off_notifications(w: &mut World, user: UserParam, kind: String) {
+    w.assert_result_ok();
+
+    let kind = dto::notifications::NotificationKind::from_str(&kind).unwrap();
+    let user = w.user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .set_notification_preference(dto::notifications::NotificationPreference {
+            kind,
+            in_app: false,
+            email: true,
+        })
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} follows core {word}")]
+async fn user_follows_core(w: &mut World, user: UserParam, core: String) {
+    w.assert_result_ok();
+
+    let core_id = w.cores[&core];
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user.lock().await.follow_core(core_id).await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} unfollows core {word}")]
+async fn user_unfollows_core(w: &mut World, user: UserParam, core: String) {
+    w.assert_result_ok();
+
+    let core_id = w.cores[&core];
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user.lock().await.unfollow_core(core_id).await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} releases version {word} of core {word}")]
+async fn user_releases_core(w: &mut World, user: UserParam, version: String, core: String) {
+    w.assert_result_ok();
+
+    let core_id = w.cores[&core];
+    let platform_id = w.platforms[&core];
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .create_release(core_id, platform_id, &version)
+        .await;
+    if let Ok(r) = &result {
+        w.releases.insert(version, (core_id, r.id));
+    }
+    w.record_result(result);
+}
+
+#[when(expr = "{user} yanks release {word}")]
+async fn user_yanks_release(w: &mut World, user: UserParam, release: String) {
+    w.assert_result_ok();
+
+    let (core_id, release_id) = w.releases[&release];
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .yank_release(core_id, release_id, true)
+        .await;
+    w.record_result(result);
+}
+
+#[derive(Debug, cucumber::Parameter)]
+#[param(name = "permission", regex = r"([a-z_]+)")]
+struct PermissionParam(dto::permissions::Permission);
+
+impl FromStr for PermissionParam {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.parse().map_err(
+            |e: dto::reexports::strum::ParseError| e.to_string(),
+        )?))
+    }
+}
+
+async fn update_team_permission(
+    w: &mut World,
+    owner: UserParam,
+    permission: PermissionParam,
+    role: TeamRole,
+    team: String,
+    granted: bool,
+) {
+    w.assert_result_ok();
+
+    let team = w.team(&owner, &team).await.unwrap();
+    let owner = w.user(&owner).await.unwrap();
+    let result = owner
+        .lock()
+        .await
+        .update_team_permissions(
+            team.id,
+            &[dto::permissions::TeamPermissionUpdate {
+                role: role.0,
+                permission: permission.0,
+                granted: Some(granted),
+            }],
+        )
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} grants {permission} to {team_role}s of team {word}")]
+async fn user_grants_permission(
+    w: &mut World,
+    owner: UserParam,
+    permission: PermissionParam,
+    role: TeamRole,
+    team: String,
+) {
+    update_team_permission(w, owner, permission, role, team, true).await;
+}
+
+#[when(expr = "{user} revokes {permission} from {team_role}s of team {word}")]
+async fn user_revokes_permission(
+    w: &mut World,
+    owner: UserParam,
+    permission: PermissionParam,
+    role: TeamRole,
+    team: String,
+) {
+    update_team_permission(w, owner, permission, role, team, false).await;
+}
+
+#[when(expr = "{user} grants {permission} on core {word} to {user}")]
+async fn user_grants_core_permission(
+    w: &mut World,
+    admin: UserParam,
+    permission: PermissionParam,
+    core: String,
+    user: UserParam,
+) {
+    w.assert_result_ok();
+
+    let core_id = w.cores[&core];
+    let user_id = w.auth_user(&user).await.unwrap().lock().await.id();
+    let admin = w.user(&admin).await.unwrap();
+    let result = admin
+        .lock()
+        .await
+        .set_core_collaborator(core_id, user_id, vec![permission.0])
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "{user} has {permission} on core {word} from {word}")]
+async fn user_has_permission(
+    w: &mut World,
+    user: UserParam,
+    permission: PermissionParam,
+    core: String,
+    source: String,
+) {
+    w.assert_result_ok();
+
+    let core_id = w.cores[&core];
+    let user = w.auth_user(&user).await.unwrap();
+    let check = user
+        .lock()
+        .await
+        .check_permission(permission.0, core_id)
+        .await
+        .unwrap();
+    assert!(check.allowed, "{}", check.reason);
+    assert_eq!(check.source.to_string(), source);
+}
+
+#[then(expr = "{user} does not have {permission} on core {word} because of {word}")]
+async fn user_does_not_have_permission(
+    w: &mut World,
+    user: UserParam,
+    permission: PermissionParam,
+    core: String,
+    source: String,
+) {
+    w.assert_result_ok();
+
+    let core_id = w.cores[&core];
+    let user = w.auth_user(&user).await.unwrap();
+    let check = user
+        .lock()
+        .await
+        .check_permission(permission.0, core_id)
+        .await
+        .unwrap();
+    assert!(!check.allowed, "{}", check.reason);
+    assert_eq!(check.source.to_string(), source);
+}
+
+#[when(expr = "{user} creates a team {word}")]
+async fn team_create(w: &mut World, user: UserParam, team: String) {
+    w.assert_result_ok();
+
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user.lock().await.create_team(&team).await;
+    if let Ok(t) = &result {
+        w.teams.insert(team, t.clone());
+    }
+    w.record_result(result);
+}
+
+#[when(expr = "{user} updates team {word}")]
+async fn team_update(w: &mut World, user: UserParam, team: String) {
+    w.assert_result_ok();
+
+    let team_id = w.teams[&team].id;
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .update_team(team_id, "Updated description")
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} deletes team {word}")]
+async fn team_delete(w: &mut World, user: UserParam, team: String) {
+    w.assert_result_ok();
+
+    let team_id = w.teams[&team].id;
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user.lock().await.delete_team(team_id).await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} creates a system {word} in team {word}")]
+async fn system_create(w: &mut World, user: UserParam, system: String, team: String) {
+    w.assert_result_ok();
+
+    let team_id = w.teams[&team].id;
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user.lock().await.create_system(team_id, &system).await;
+    if let Ok(s) = &result {
+        w.systems.insert(system, s.id);
+    }
+    w.record_result(result);
+}
+
+#[when(expr = "{user} creates a core {word} on system {word} in team {word}")]
+async fn core_create(w: &mut World, user: UserParam, core: String, system: String, team: String) {
+    w.assert_result_ok();
+
+    let team_id = w.teams[&team].id;
+    let system_id = w.systems[&system];
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .create_core(team_id, system_id, &core)
+        .await;
+    if let Ok(c) = &result {
+        w.cores.insert(core, c.id);
+    }
+    w.record_result(result);
+}
+
+async fn add_game_artifact(
+    w: &mut World,
+    user: UserParam,
+    file: String,
+    game: String,
+    only_crc32: bool,
+    name: Option<&str>,
+    role: Option<dto::games::GameArtifactRole>,
+) {
+    w.assert_result_ok();
+
+    let game_id = w.games[&game];
+    let file = w.files.entry(file).or_insert_with(random_file).clone();
+    let (path, filename) = match name.map(|n| n.rsplit_once('/').unwrap_or(("", n))) {
+        Some((path, filename)) => (Some(path), Some(filename)),
+        None => (None, None),
+    };
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .add_game_artifact(
+            game_id,
+            dto::games::GameAddArtifactRequest {
+                mime_type: "application/octet-stream",
+                filename,
+                path,
+                role,
+                order: None,
+                status: None,
+                size: file.size,
+                crc32: file.crc32,
+                md5: file.md5.filter(|_| !only_crc32),
+                sha1: file.sha1.filter(|_| !only_crc32),
+                sha256: file.sha256.filter(|_| !only_crc32),
+            },
+        )
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} adds artifact {word} to game {word}")]
+async fn user_adds_game_artifact(w: &mut World, user: UserParam, file: String, game: String) {
+    add_game_artifact(w, user, file, game, false, None, None).await;
+}
+
+#[when(expr = "{user} adds artifact {word} to game {word} with only its size and CRC32")]
+async fn user_adds_game_artifact_crc32(w: &mut World, user: UserParam, file: String, game: String) {
+    add_game_artifact(w, user, file, game, true, None, None).await;
+}
+
+/// Add an artifact with a name, which can start with directories.
+#[when(expr = "{user} adds artifact {word} to game {word} as {string}")]
+async fn user_adds_game_artifact_named(
+    w: &mut World,
+    user: UserParam,
+    file: String,
+    game: String,
+    name: String,
+) {
+    add_game_artifact(w, user, file, game, false, Some(&name), None).await;
+}
+
+#[when(expr = "{user} adds artifact {word} to game {word} as {string} with role {word}")]
+async fn user_adds_game_artifact_with_role(
+    w: &mut World,
+    user: UserParam,
+    file: String,
+    game: String,
+    name: String,
+    role: String,
+) {
+    let role = role.parse().unwrap();
+    add_game_artifact(w, user, file, game, false, Some(&name), Some(role)).await;
+}
+
+/// Expects a comma separated list of `path:role:status`, in order.
+#[then(expr = "the artifacts of game {word} are {string}")]
+async fn game_artifacts_are(w: &mut World, game: String, expected: String) {
+    w.assert_result_ok();
+
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let game_id = w.games[&game];
+    let artifacts = user.lock().await.get_game_artifacts(game_id).await.unwrap();
+    let actual = artifacts
+        .iter()
+        .map(|a| {
+            let path = if a.path.is_empty() {
+                a.filename.clone()
+            } else {
+                format!("{}/{}", a.path, a.filename)
+            };
+            format!("{path}:{}:{}", a.role, a.status)
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    assert_eq!(actual, expected);
+}
+
+#[then(expr = "{user} identifies file {word} as game {word} by {word}")]
+async fn user_identifies_file(
+    w: &mut World,
+    user: UserParam,
+    file: String,
+    game: String,
+    confidence: String,
+) {
+    w.assert_result_ok();
+
+    let game_id = w.games[&game];
+    let file = w.files.entry(file).or_insert_with(random_file).clone();
+    let user = w.user(&user).await.unwrap();
+    let results = user.lock().await.identify(vec![file]).await.unwrap();
+    assert_eq!(results.len(), 1);
+    let m = results[0]
+        .matches
+        .iter()
+        .find(|m| m.game_id == game_id)
+        .expect("Game was not identified");
+    assert_eq!(m.confidence.to_string(), confidence);
+}
+
+#[then(expr = "{user} cannot identify file {word}")]
+async fn user_cannot_identify_file(w: &mut World, user: UserParam, file: String) {
+    w.assert_result_ok();
+
+    let file = w.files.entry(file).or_insert_with(random_file).clone();
+    let user = w.user(&user).await.unwrap();
+    let results = user.lock().await.identify(vec![file]).await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert!(results[0].matches.is_empty());
+}
+
+/// A DAT with a ROM per game, using the file of the same name.
+/// A DAT with games of a single ROM, or of disc images with a cue sheet
+/// and two tracks in a directory.
+fn dat_with_games(w: &mut World, games: &str, discs: bool) -> String {
+    let hex = |c: &Option<dto::encodings::HexString>| hex::encode(c.as_ref().unwrap().as_slice());
+    let mut dat_games = String::new();
+    for game in games.split(',') {
+        let roms = if discs {
+            vec![
+                (game.to_string(), format!("{game}.cue"), "good"),
+                (
+                    format!("{game}-1"),
+                    format!("Disc\\{game} (Track 1).bin"),
+                    "verified",
+                ),
+                (
+                    format!("{game}-2"),
+                    format!("Disc\\{game} (Track 2).bin"),
+                    "baddump",
+                ),
+            ]
+        } else {
+            vec![(game.to_string(), format!("{game}.rom"), "good")]
+        };
+
+        let mut dat_roms = String::new();
+        for (file, name, status) in roms {
+            let file = w.files.entry(file).or_insert_with(random_file);
+            write!(
+                dat_roms,
+                r#"<rom name="{name}" size="{}" crc="{}" md5="{}" sha1="{}" sha256="{}" status="{status}"/>"#,
+                file.size,
+                hex(&file.crc32),
+                hex(&file.md5),
+                hex(&file.sha1),
+                hex(&file.sha256),
+            )
+            .unwrap();
+        }
+
+        write!(
+            dat_games,
+            r#"<game name="{game}">
+                    <description>{game} from a DAT</description>
+                    <year>1990</year>
+                    {dat_roms}
+                </game>"#,
+        )
+        .unwrap();
+    }
+
+    format!(
+        r#"<datafile>
+            <header>
+                <name>Cucumber</name>
+                <description>Cucumber games</description>
+                <version>20240306</version>
+                <author>cucumber</author>
+            </header>
+            {dat_games}
+        </datafile>"#
+    )
+}
+
+async fn import_dat(
+    w: &mut World,
+    user: UserParam,
+    games: String,
+    system: String,
+    dry_run: bool,
+    discs: bool,
+) {
+    w.assert_result_ok();
+
+    let system_id = w.systems[&system];
+    let dat = dat_with_games(w, &games, discs);
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user.lock().await.import_dat(system_id, dat, dry_run).await;
+    if let Ok(import) = &result {
+        w.dat_import = Some((system_id, import.id));
+    }
+    w.record_result(result);
+}
+
+#[when(expr = "{user} imports a DAT with games {word} into system {word}")]
+async fn user_imports_dat(w: &mut World, user: UserParam, games: String, system: String) {
+    import_dat(w, user, games, system, false, false).await;
+}
+
+#[when(expr = "{user} imports a DAT with games {word} into system {word} as a dry run")]
+async fn user_imports_dat_dry_run(w: &mut World, user: UserParam, games: String, system: String) {
+    import_dat(w, user, games, system, true, false).await;
+}
+
+#[when(expr = "{user} imports a DAT with disc games {word} into system {word}")]
+async fn user_imports_dat_discs(w: &mut World, user: UserParam, games: String, system: String) {
+    import_dat(w, user, games, system, false, true).await;
+}
+
+#[when(expr = "{user} applies the DAT import")]
+async fn user_applies_dat_import(w: &mut World, user: UserParam) {
+    w.assert_result_ok();
+
+    let (system_id, import_id) = w.dat_import.unwrap();
+    wait_for_dat_import(w).await;
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .apply_dat_import(system_id, import_id)
+        .await;
+    w.record_result(result);
+}
+
+/// Wait for the job of the last DAT import to be done.
+async fn wait_for_dat_import(w: &mut World) -> dto::games::DatImportDetails {
+    let (system_id, import_id) = w.dat_import.unwrap();
+    let admin = w
+        .auth_user(&UserParam::Admin("default".to_string()))
+        .await
+        .unwrap();
+
+    for _ in 0..100 {
+        let import = admin
+            .lock()
+            .await
+            .dat_import(system_id, import_id)
+            .await
+            .unwrap();
+        if !matches!(
+            import.status,
+            dto::games::DatImportStatus::Pending | dto::games::DatImportStatus::Running
+        ) {
+            return import;
+        }
+        rocket::tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("DAT import {import_id} did not finish");
+}
+
+#[then(expr = "the DAT import is {word}")]
+async fn dat_import_is(w: &mut World, status: String) {
+    w.assert_result_ok();
+
+    let import = wait_for_dat_import(w).await;
+    assert_eq!(import.status.to_string(), status, "{:?}", import.error);
+    assert_eq!(import.dat_name, "Cucumber");
+    assert_eq!(import.dat_version, "20240306");
+    if import.status == dto::games::DatImportStatus::Completed {
+        assert_eq!(import.processed, import.total);
+    }
+}
+
+#[then(expr = "the DAT import creates {int} game(s), updates {int} and orphans {int}")]
+async fn dat_import_preview(w: &mut World, create: usize, update: usize, orphaned: usize) {
+    w.assert_result_ok();
+
+    let preview = wait_for_dat_import(w).await.preview.unwrap();
+    assert_eq!(preview.create.len(), create);
+    assert_eq!(preview.update.len(), update);
+    assert_eq!(preview.orphaned.len(), orphaned);
+}
+
+#[then(expr = "game {word} was imported from the DAT")]
+async fn game_imported_from_dat(w: &mut World, game: String) {
+    w.assert_result_ok();
+
+    let (system_id, import_id) = w.dat_import.unwrap();
+    let file = w.files[&game].clone();
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let mut user = user.lock().await;
+    let results = user.identify(vec![file]).await.unwrap();
+    let m = results[0]
+        .matches
+        .iter()
+        .find(|m| m.name == game && m.system.id == system_id)
+        .expect("Game was not imported");
+
+    let details = user.get_game_by_id(m.game_id).await.unwrap();
+    assert_eq!(details.description, format!("{game} from a DAT"));
+    assert_eq!(details.year, 1990);
+    assert_eq!(details.dat.unwrap().import_id, import_id);
+    w.games.insert(game, m.game_id);
+}
+
+/// The slug of a tag, unique to the scenario.
+fn tag_slug(w: &World, tag: &str) -> String {
+    format!("{}-{}", tag.to_lowercase(), w.search_word)
+}
+
+#[given(expr = "tag {word}")]
+async fn given_a_tag(w: &mut World, tag: String) {
+    let slug = tag_slug(w, &tag);
+    let admin = w
+        .auth_user(&UserParam::Admin("default".to_string()))
+        .await
+        .unwrap();
+    admin.lock().await.create_tag(&slug).await.unwrap();
+}
+
+#[when(expr = "{user} tags game {word} with {word}")]
+async fn game_update_tags(w: &mut World, user: UserParam, game: String, tag: String) {
+    let slug = tag_slug(w, &tag);
+    let user = w.auth_user(&user).await.unwrap();
+    let game_id = w.games[&game];
+    let result = user
+        .lock()
+        .await
+        .update_game(
+            game_id,
+            &dto::games::GameUpdateRequest {
+                tags: Some(vec![&slug]),
+                ..Default::default()
+            },
+        )
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} updates game {word} with year {int}")]
+async fn game_update_year(w: &mut World, user: UserParam, game: String, year: i32) {
+    let user = w.auth_user(&user).await.unwrap();
+    let game_id = w.games[&game];
+    let result = user
+        .lock()
+        .await
+        .update_game(
+            game_id,
+            &dto::games::GameUpdateRequest {
+                year: Some(year),
+                ..Default::default()
+            },
+        )
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "game {word} has tag {word}")]
+async fn game_has_tag(w: &mut World, game: String, tag: String) {
+    w.assert_result_ok();
+
+    let details = game_details(w, &game).await;
+    assert_eq!(
+        details.tags.into_iter().map(|t| t.slug).collect::<Vec<_>>(),
+        vec![tag_slug(w, &tag)]
+    );
+}
+
+async fn export_dat(w: &mut World, user: UserParam, system: String, filter: Option<(&str, &str)>) {
+    w.assert_result_ok();
+
+    let system_id = w.systems[&system];
+    let user = w.user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .export_dat(system_id, filter.as_slice())
+        .await;
+    match result {
+        Ok(dat) => {
+            w.record_result(Ok::<_, anyhow::Error>(&dat));
+            w.dat_export = Some(dat);
+        }
+        Err(e) => w.record_result(Err::<(), _>(e)),
+    }
+}
+
+#[when(expr = "{user} exports the DAT of system {word}")]
+async fn user_exports_dat(w: &mut World, user: UserParam, system: String) {
+    export_dat(w, user, system, None).await;
+}
+
+#[when(expr = "{user} exports the DAT of system {word} with {word} {word}")]
+async fn user_exports_dat_filtered(
+    w: &mut World,
+    user: UserParam,
+    system: String,
+    filter: String,
+    value: String,
+) {
+    let value = if filter == "tag" {
+        tag_slug(w, &value)
+    } else {
+        value
+    };
+    export_dat(w, user, system, Some((&filter, &value))).await;
+}
+
+#[then(expr = "the exported DAT has games {string}")]
+async fn exported_dat_has_games(w: &mut World, games: String) {
+    w.assert_result_ok();
+
+    let dat = w.dat_export.as_ref().unwrap();
+    let names = dat
+        .games
+        .iter()
+        .map(|g| g.name.as_str())
+        .collect::<Vec<_>>();
+    let expected = games
+        .split(',')
+        .map(str::trim)
+        .filter(|g| !g.is_empty())
+        .collect::<Vec<_>>();
+    assert_eq!(names, expected);
+}
+
+/// The ROMs of a game of the exported DAT, as `name:status`, where the
+/// status is `good` when the DAT has none.
+#[then(expr = "game {word} of the exported DAT has ROMs {string}")]
+async fn exported_dat_game_has_roms(w: &mut World, game: String, roms: String) {
+    w.assert_result_ok();
+
+    let dat = w.dat_export.as_ref().unwrap();
+    let dat_game = dat.games.iter().find(|g| g.name == game).unwrap();
+    let actual = dat_game
+        .roms
+        .iter()
+        .map(|rom| {
+            let status = match rom.status {
+                Some(datary::Status::BadDump) => "baddump",
+                Some(datary::Status::NoDump) => "nodump",
+                Some(datary::Status::Verified) => "verified",
+                Some(datary::Status::Good) | None => "good",
+            };
+            format!("{}:{status}", rom.name)
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    assert_eq!(actual, roms);
+}
+
+#[when(expr = "{user} imports the exported DAT into system {word}")]
+async fn user_imports_exported_dat(w: &mut World, user: UserParam, system: String) {
+    w.assert_result_ok();
+
+    let system_id = w.systems[&system];
+    let mut dat = String::new();
+    datary::to_writer(&mut dat, w.dat_export.as_ref().unwrap()).unwrap();
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user.lock().await.import_dat(system_id, dat, true).await;
+    if let Ok(import) = &result {
+        w.dat_import = Some((system_id, import.id));
+    }
+    w.record_result(result);
+}
+
+/// Create a game on a default system, whose name ends with the search word of
+/// the scenario.
+#[given(expr = "game {word} named {string}")]
+async fn given_a_named_game(w: &mut World, game: String, name: String) {
+    given_a_game(w, format!("{name} {}", w.search_word)).await;
+
+    let game_id = w
+        .games
+        .remove(&format!("{name} {}", w.search_word))
+        .unwrap();
+    w.games.insert(game, game_id);
+}
+
+async fn search(w: &mut World, user: UserParam, q: String, kind: Option<dto::search::SearchKind>) {
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.search(&q, kind).await;
+    match result {
+        Ok(search) => {
+            w.record_result(Ok::<_, anyhow::Error>(&search));
+            w.search = Some(search);
+        }
+        Err(e) => {
+            w.record_result(Err::<(), _>(e));
+            w.search = None;
+        }
+    }
+}
+
+#[when(expr = "{user} searches for {string}")]
+async fn search_all(w: &mut World, user: UserParam, q: String) {
+    let q = format!("{q} {}", w.search_word);
+    search(w, user, q, None).await;
+}
+
+#[when(expr = "{user} searches {word}s for {string}")]
+async fn search_kind(w: &mut World, user: UserParam, kind: String, q: String) {
+    let q = format!("{q} {}", w.search_word);
+    let kind = dto::search::SearchKind::from_str(&kind).unwrap();
+    search(w, user, q, Some(kind)).await;
+}
+
+#[when(expr = "{user} searches for nothing")]
+async fn search_nothing(w: &mut World, user: UserParam) {
+    search(w, user, " ".to_string(), None).await;
+}
+
+fn search_result<'a>(w: &'a World, game: &str) -> Option<&'a dto::search::SearchResult> {
+    let game_id = w.games[game];
+    w.search
+        .as_ref()
+        .expect("No search")
+        .results
+        .iter()
+        .find(|r| r.kind == dto::search::SearchKind::Game && r.id == game_id)
+}
+
+#[then(expr = "the search finds game {word}")]
+async fn search_finds(w: &mut World, game: String) {
+    w.assert_result_ok();
+    assert!(search_result(w, &game).is_some());
+}
+
+#[then(expr = "the search does not find game {word}")]
+async fn search_does_not_find(w: &mut World, game: String) {
+    w.assert_result_ok();
+    assert!(search_result(w, &game).is_none());
+}
+
+#[then(expr = "the search ranks game {word} before game {word}")]
+async fn search_ranks(w: &mut World, first: String, second: String) {
+    w.assert_result_ok();
+    let first = search_result(w, &first).unwrap().rank;
+    let second = search_result(w, &second).unwrap().rank;
+    assert!(first > second, "{first} <= {second}");
+}
+
+#[then(expr = "the search highlights {string} in game {word}")]
+async fn search_highlights(w: &mut World, highlight: String, game: String) {
+    w.assert_result_ok();
+    let result = search_result(w, &game).unwrap();
+    assert!(
+        result.highlight.contains(&highlight),
+        "{:?} does not contain {:?}",
+        result.highlight,
+        highlight
+    );
+}
+
+#[then(expr = "the search finds {int} result(s) out of {int} game(s)")]
+async fn search_facets(w: &mut World, results: usize, games: i64) {
+    w.assert_result_ok();
+    let search = w.search.as_ref().expect("No search");
+    assert_eq!(search.results.len(), results);
+    assert_eq!(search.facets.games, games);
+    assert_eq!(search.facets.cores, 0);
+    assert_eq!(search.facets.systems, 0);
+    assert_eq!(search.facets.platforms, 0);
+}
+
+#[when(expr = "{user} updates game {word} with publisher {string} and developer {string}")]
+async fn game_update_companies(
+    w: &mut World,
+    user: UserParam,
+    game: String,
+    publisher: String,
+    developer: String,
+) {
+    let user = w.auth_user(&user).await.unwrap();
+    let game_id = w.games[&game];
+    let result = user
+        .lock()
+        .await
+        .update_game(
+            game_id,
+            &dto::games::GameUpdateRequest {
+                publishers: Some(vec![&publisher]),
+                developers: Some(vec![&developer]),
+                ..Default::default()
+            },
+        )
+        .await;
+    w.record_result(result);
+}
+
+#[when(
+    expr = "{user} updates game {word} with the deprecated publisher {string} and developer {string}"
+)]
+async fn game_update_deprecated_companies(
+    w: &mut World,
+    user: UserParam,
+    game: String,
+    publisher: String,
+    developer: String,
+) {
+    let user = w.auth_user(&user).await.unwrap();
+    let game_id = w.games[&game];
+    let result = user
+        .lock()
+        .await
+        .update_game(
+            game_id,
+            &dto::games::GameUpdateRequest {
+                publisher: Some(&publisher),
+                developer: Some(&developer),
+                ..Default::default()
+            },
+        )
+        .await;
+    w.record_result(result);
+}
+
+#[when(
+    expr = "{user} updates game {word} with publisher {string} and the deprecated publisher {string}"
+)]
+async fn game_update_both_publishers(
+    w: &mut World,
+    user: UserParam,
+    game: String,
+    publisher: String,
+    deprecated: String,
+) {
+    let user = w.auth_user(&user).await.unwrap();
+    let game_id = w.games[&game];
+    let result = user
+        .lock()
+        .await
+        .update_game(
+            game_id,
+            &dto::games::GameUpdateRequest {
+                publishers: Some(vec![&publisher]),
+                publisher: Some(&deprecated),
+                ..Default::default()
+            },
+        )
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} updates game {word} with a release in {word} on {word}")]
+async fn game_update_release(
+    w: &mut World,
+    user: UserParam,
+    game: String,
+    region: String,
+    date: String,
+) {
+    let user = w.auth_user(&user).await.unwrap();
+    let game_id = w.games[&game];
+    let result = user
+        .lock()
+        .await
+        .update_game(
+            game_id,
+            &dto::games::GameUpdateRequest {
+                releases: Some(vec![dto::games::GameReleaseRequest {
+                    region: &region,
+                    date: Some(&date),
+                }]),
+                ..Default::default()
+            },
+        )
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} updates game {word} with language {word}, genre {word} and series {string}")]
+async fn game_update_classification(
+    w: &mut World,
+    user: UserParam,
+    game: String,
+    language: String,
+    genre: String,
+    series: String,
+) {
+    let user = w.auth_user(&user).await.unwrap();
+    let game_id = w.games[&game];
+    let result = user
+        .lock()
+        .await
+        .update_game(
+            game_id,
+            &dto::games::GameUpdateRequest {
+                languages: Some(vec![&language]),
+                genres: Some(vec![&genre]),
+                series: Some(vec![&series]),
+                ..Default::default()
+            },
+        )
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} updates game {word} for {int} to {int} players")]
+async fn game_update_players(w: &mut World, user: UserParam, game: String, min: i32, max: i32) {
+    let user = w.auth_user(&user).await.unwrap();
+    let game_id = w.games[&game];
+    let result = user
+        .lock()
+        .await
+        .update_game(
+            game_id,
+            &dto::games::GameUpdateRequest {
+                min_players: Some(min),
+                max_players: Some(max),
+                ..Default::default()
+            },
+        )
+        .await;
+    w.record_result(result);
+}
+
+async fn game_details(w: &mut World, game: &str) -> dto::games::GameDetails {
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let game_id = w.games[game];
+    let mut user = user.lock().await;
+    user.get_game_by_id(game_id).await.unwrap()
+}
+
+#[then(expr = "game {word} is published by {string} and developed by {string}")]
+async fn game_has_companies(w: &mut World, game: String, publisher: String, developer: String) {
+    w.assert_result_ok();
+
+    let details = game_details(w, &game).await;
+    let names = |companies: Vec<dto::games::CompanyRef>| {
+        companies.into_iter().map(|c| c.name).collect::<Vec<_>>()
+    };
+    assert_eq!(names(details.publishers), vec![publisher]);
+    assert_eq!(names(details.developers), vec![developer]);
+}
+
+#[then(expr = "game {word} was released in {word} on {word}")]
+async fn game_has_release(w: &mut World, game: String, region: String, date: String) {
+    w.assert_result_ok();
+
+    let details = game_details(w, &game).await;
+    assert_eq!(details.releases.len(), 1);
+    assert_eq!(details.releases[0].region.slug, region);
+    assert_eq!(details.releases[0].date, Some(date));
+}
+
+#[then(expr = "game {word} has language {word}, genre {word} and series {string}")]
+async fn game_has_classification(
+    w: &mut World,
+    game: String,
+    language: String,
+    genre: String,
+    series: String,
+) {
+    w.assert_result_ok();
+
+    let details = game_details(w, &game).await;
+    assert_eq!(details.languages, vec![language]);
+    assert_eq!(
+        details
+            .genres
+            .into_iter()
+            .map(|g| g.slug)
+            .collect::<Vec<_>>(),
+        vec![genre]
+    );
+    assert_eq!(
+        details
+            .series
+            .into_iter()
+            .map(|s| s.name)
+            .collect::<Vec<_>>(),
+        vec![series]
+    );
+}
+
+#[then(expr = "game {word} is for {int} to {int} players")]
+async fn game_has_players(w: &mut World, game: String, min: i32, max: i32) {
+    w.assert_result_ok();
+
+    let details = game_details(w, &game).await;
+    assert_eq!(details.min_players, Some(min));
+    assert_eq!(details.max_players, Some(max));
+}
+
+async fn list_games_by(w: &mut World, filter: &str, value: &str) -> Vec<i32> {
+    w.assert_result_ok();
+
+    let system_id = w.systems["default"];
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let games = user
+        .lock()
+        .await
+        .list_games(system_id, &[(filter, value)])
+        .await
+        .unwrap();
+    games.into_iter().map(|g| g.id).collect()
+}
+
+#[then(expr = "games with {word} {word} include game {word}")]
+async fn games_filter_include(w: &mut World, filter: String, value: String, game: String) {
+    let games = list_games_by(w, &filter, &value).await;
+    assert!(games.contains(&w.games[&game]));
+}
+
+#[then(expr = "games with {word} {word} do not include game {word}")]
+async fn games_filter_exclude(w: &mut World, filter: String, value: String, game: String) {
+    let games = list_games_by(w, &filter, &value).await;
+    assert!(!games.contains(&w.games[&game]));
+}
+
+/// Translate a game. Like game names, translated names end with the search
+/// word of the scenario.
+#[when(expr = "{user} translates game {word} to {word} as {string}")]
+async fn game_translate(
+    w: &mut World,
+    user: UserParam,
+    game: String,
+    language: String,
+    name: String,
+) {
+    let user = w.auth_user(&user).await.unwrap();
+    let game_id = w.games[&game];
+    let name = format!("{name} {}", w.search_word);
+    let result = user
+        .lock()
+        .await
+        .translate_game(
+            game_id,
+            &language,
+            &dto::games::GameTranslationRequest {
+                name: &name,
+                short_description: None,
+                description: None,
+            },
+        )
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} deletes the {word} translation of game {word}")]
+async fn game_translation_delete(w: &mut World, user: UserParam, language: String, game: String) {
+    let user = w.auth_user(&user).await.unwrap();
+    let game_id = w.games[&game];
+    let result = user
+        .lock()
+        .await
+        .delete_game_translation(game_id, &language)
+        .await;
+    w.record_result(result);
+}
+
+async fn game_localised_name(
+    w: &mut World,
+    game: &str,
+    lang: Option<&str>,
+    accept_language: Option<&str>,
+) -> String {
+    w.assert_result_ok();
+
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let game_id = w.games[game];
+    let mut user = user.lock().await;
+    let details = user
+        .get_game_localised(game_id, lang, accept_language)
+        .await
+        .unwrap();
+    details.name
+}
+
+#[then(expr = "game {word} in {word} is named {string}")]
+async fn game_named_in(w: &mut World, game: String, lang: String, name: String) {
+    let actual = game_localised_name(w, &game, Some(&lang), None).await;
+    assert_eq!(actual, format!("{name} {}", w.search_word));
+}
+
+#[then(expr = "game {word} with Accept-Language {string} is named {string}")]
+async fn game_named_accept_language(w: &mut World, game: String, header: String, name: String) {
+    let actual = game_localised_name(w, &game, None, Some(&header)).await;
+    assert_eq!(actual, format!("{name} {}", w.search_word));
+}
+
+#[then(expr = "game {word} in {word} with Accept-Language {string} is named {string}")]
+async fn game_named_in_accept_language(
+    w: &mut World,
+    game: String,
+    lang: String,
+    header: String,
+    name: String,
+) {
+    let actual = game_localised_name(w, &game, Some(&lang), Some(&header)).await;
+    assert_eq!(actual, format!("{name} {}", w.search_word));
+}
+
+#[then(expr = "game {word} has {int} translation(s)")]
+async fn game_has_translations(w: &mut World, game: String, count: usize) {
+    w.assert_result_ok();
+    let details = game_details(w, &game).await;
+    assert_eq!(details.translations.len(), count);
+}
+
+#[then(expr = "game {word} is listed in {word} as {string}")]
+async fn game_listed_in(w: &mut World, game: String, lang: String, name: String) {
+    w.assert_result_ok();
+
+    let system_id = w.systems["default"];
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let games = user
+        .lock()
+        .await
+        .list_games(system_id, &[("lang", &lang)])
+        .await
+        .unwrap();
+    let listed = games.into_iter().find(|g| g.id == w.games[&game]).unwrap();
+    assert_eq!(listed.name, format!("{name} {}", w.search_word));
+    assert_eq!(listed.language, Some(lang));
+}
+
+#[when(expr = "{user} records game {word} {word} game {word}")]
+async fn game_relate(w: &mut World, user: UserParam, game: String, kind: String, other: String) {
+    let user = w.auth_user(&user).await.unwrap();
+    let (game_id, other_id) = (w.games[&game], w.games[&other]);
+    let result = user
+        .lock()
+        .await
+        .relate_games(game_id, kind.parse().unwrap(), other_id)
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} removes game {word} {word} game {word}")]
+async fn game_unrelate(w: &mut World, user: UserParam, game: String, kind: String, other: String) {
+    let user = w.auth_user(&user).await.unwrap();
+    let (game_id, other_id) = (w.games[&game], w.games[&other]);
+    let result = user
+        .lock()
         .await
-        .expect("Could not get a response")
-        .error_for_status()
-        .expect("Could not download the image");
+        .unrelate_games(game_id, kind.parse().unwrap(), other_id)
+        .await;
+    w.record_result(result);
+}
+
+/// Whether the details of both games show the relationship.
+async fn games_related(w: &mut World, game: &str, kind: &str, other: &str) -> bool {
+    w.assert_result_ok();
+    let kind: dto::games::GameRelationshipKind = kind.parse().unwrap();
+    let (game_id, other_id) = (w.games[game], w.games[other]);
+
+    let outgoing = game_details(w, game)
+        .await
+        .relationships
+        .iter()
+        .any(|r| r.kind == kind && r.game.id == other_id);
+    let incoming = game_details(w, other)
+        .await
+        .related_games
+        .iter()
+        .any(|r| r.kind == kind && r.game.id == game_id);
+    assert_eq!(outgoing, incoming);
+    outgoing
+}
+
+#[then(expr = "game {word} is {word} game {word}")]
+async fn game_is_related(w: &mut World, game: String, kind: String, other: String) {
+    assert!(games_related(w, &game, &kind, &other).await);
+}
+
+#[then(expr = "game {word} is not {word} game {word}")]
+async fn game_is_not_related(w: &mut World, game: String, kind: String, other: String) {
+    assert!(!games_related(w, &game, &kind, &other).await);
+}
+
+/// Parameters to traverse relationships, as space separated `key=value`.
+fn related_query(params: &str) -> dto::games::GameRelatedQueryParams {
+    let mut query = dto::games::GameRelatedQueryParams::default();
+    for param in params.split_whitespace() {
+        match param.split_once('=').unwrap() {
+            ("kind", v) => query.kind = Some(v.parse().unwrap()),
+            ("direction", v) => query.direction = Some(v.parse().unwrap()),
+            ("depth", v) => query.depth = Some(v.parse().unwrap()),
+            (k, _) => panic!("Unknown parameter {k:?}"),
+        }
+    }
+    query
+}
+
+#[when(expr = "{user} lists the games related to game {word} with {string}")]
+async fn game_related_list(w: &mut World, user: UserParam, game: String, params: String) {
+    let user = w.user(&user).await.unwrap();
+    let game_id = w.games[&game];
+    let result = user
+        .lock()
+        .await
+        .get_related_games(game_id, related_query(&params))
+        .await;
+    w.record_result(result);
+}
+
+/// Expects a comma separated list of `game:kind:direction:depth`.
+#[then(expr = "the games related to game {word} with {string} are {string}")]
+async fn game_related_with(w: &mut World, game: String, params: String, expected: String) {
+    w.assert_result_ok();
+
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let game_id = w.games[&game];
+    let related = user
+        .lock()
+        .await
+        .get_related_games(game_id, related_query(&params))
+        .await
+        .unwrap();
+
+    let names = w
+        .games
+        .iter()
+        .map(|(name, id)| (*id, name.clone()))
+        .collect::<BTreeMap<_, _>>();
+    let actual = related
+        .iter()
+        .map(|r| {
+            format!(
+                "{}:{}:{}:{}",
+                names[&r.game.id], r.kind, r.direction, r.depth
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    assert_eq!(actual, expected);
+}
+
+/// Require a file at a path for a core or one of its releases, accepting
+/// the version of the file with the given name.
+async fn require_file(
+    w: &mut World,
+    user: UserParam,
+    file: String,
+    path: String,
+    core_id: i32,
+    release_id: Option<i32>,
+    optional: bool,
+) {
+    w.assert_result_ok();
+
+    let file = w.files.entry(file).or_insert_with(random_file).clone();
+    let (path, filename) = path.rsplit_once('/').unwrap_or(("", &path));
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .create_required_file(
+            core_id,
+            &dto::cores::required_files::CoreRequiredFileCreateRequest {
+                release: release_id,
+                filename,
+                path,
+                optional,
+                description: "",
+                hashes: vec![dto::cores::required_files::RequiredFileHash {
+                    size: Some(file.size),
+                    crc32: file.crc32,
+                    md5: file.md5,
+                    sha1: file.sha1,
+                    sha256: file.sha256,
+                }],
+            },
+        )
+        .await;
+    w.record_result(result);
+}
+
+#[when(expr = "{user} requires file {word} at {string} for core {word}")]
+async fn user_requires_file(
+    w: &mut World,
+    user: UserParam,
+    file: String,
+    path: String,
+    core: String,
+) {
+    let core_id = w.cores[&core];
+    require_file(w, user, file, path, core_id, None, false).await;
+}
+
+#[when(expr = "{user} requires optional file {word} at {string} for core {word}")]
+async fn user_requires_optional_file(
+    w: &mut World,
+    user: UserParam,
+    file: String,
+    path: String,
+    core: String,
+) {
+    let core_id = w.cores[&core];
+    require_file(w, user, file, path, core_id, None, true).await;
+}
+
+#[when(expr = "{user} requires file {word} at {string} for release {word}")]
+async fn user_requires_file_for_release(
+    w: &mut World,
+    user: UserParam,
+    file: String,
+    path: String,
+    release: String,
+) {
+    let (core_id, release_id) = w.releases[&release];
+    require_file(w, user, file, path, core_id, Some(release_id), false).await;
+}
+
+#[when(expr = "{user} requires a file at {string} for core {word} with a hash without checksum")]
+async fn user_requires_file_without_checksum(
+    w: &mut World,
+    user: UserParam,
+    path: String,
+    core: String,
+) {
+    w.assert_result_ok();
+
+    let core_id = w.cores[&core];
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .create_required_file(
+            core_id,
+            &dto::cores::required_files::CoreRequiredFileCreateRequest {
+                release: None,
+                filename: &path,
+                path: "",
+                optional: false,
+                description: "",
+                hashes: vec![dto::cores::required_files::RequiredFileHash {
+                    size: Some(1024),
+                    ..Default::default()
+                }],
+            },
+        )
+        .await;
+    w.record_result(result);
+}
+
+/// The required files of a core, as their full paths.
+async fn required_file_paths(w: &mut World, core_id: i32, release_id: Option<i32>) -> String {
+    let user = w.user(&UserParam::Anonymous).await.unwrap();
+    let files = user
+        .lock()
+        .await
+        .required_files(core_id, release_id)
+        .await
+        .unwrap();
+    files
+        .iter()
+        .map(|f| match f.path.as_str() {
+            "" => f.filename.clone(),
+            path => format!("{path}/{}", f.filename),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[when(expr = "{user} removes the required file at {string} of core {word}")]
+async fn user_removes_required_file(w: &mut World, user: UserParam, path: String, core: String) {
+    w.assert_result_ok();
+
+    let core_id = w.cores[&core];
+    let (dir, filename) = path.rsplit_once('/').unwrap_or(("", &path));
+    let anonymous = w.user(&UserParam::Anonymous).await.unwrap();
+    let files = anonymous
+        .lock()
+        .await
+        .required_files(core_id, None)
+        .await
+        .unwrap();
+    let file = files
+        .iter()
+        .find(|f| f.path == dir && f.filename == filename)
+        .unwrap();
+
+    let user = w.auth_user(&user).await.unwrap();
+    let result = user
+        .lock()
+        .await
+        .delete_required_file(core_id, file.id)
+        .await;
+    w.record_result(result);
+}
+
+#[then(expr = "core {word} requires files {string}")]
+async fn core_requires_files(w: &mut World, core: String, expected: String) {
+    w.assert_result_ok();
+
+    let core_id = w.cores[&core];
+    assert_eq!(required_file_paths(w, core_id, None).await, expected);
+}
+
+#[then(expr = "release {word} requires files {string}")]
+async fn release_requires_files(w: &mut World, release: String, expected: String) {
+    w.assert_result_ok();
+
+    let (core_id, release_id) = w.releases[&release];
+    assert_eq!(
+        required_file_paths(w, core_id, Some(release_id)).await,
+        expected
+    );
+}
+
+#[given(expr = "the device has file {word} at {string}")]
+async fn device_has_file(w: &mut World, file: String, path: String) {
+    let file = w.files.entry(file).or_insert_with(random_file).clone();
+    w.device_files.push(dto::cores::required_files::DeviceFile {
+        path,
+        size: file.size,
+        crc32: file.crc32,
+        md5: file.md5,
+        sha1: file.sha1,
+        sha256: file.sha256,
+    });
+}
+
+#[when(expr = "{user} checks which cores can run on the device")]
+async fn user_checks_compatibility(w: &mut World, user: UserParam) {
+    w.assert_result_ok();
+
+    let files = w.device_files.clone();
+    let user = w.user(&user).await.unwrap();
+    let result = user.lock().await.check_compatibility(files).await;
+    match result {
+        Ok(compatibility) => {
+            w.record_result(Ok::<_, anyhow::Error>(&compatibility));
+            w.compatibility = Some(compatibility);
+        }
+        Err(e) => w.record_result(Err::<(), _>(e)),
+    }
+}
+
+fn core_compatibility<'a>(
+    w: &'a World,
+    core: &str,
+) -> &'a dto::cores::required_files::CoreCompatibility {
+    let core_id = w.cores[core];
+    w.compatibility
+        .as_ref()
+        .unwrap()
+        .iter()
+        .find(|c| c.core.id == core_id)
+        .unwrap()
+}
+
+#[then(expr = "core {word} is playable on the device")]
+async fn core_is_playable(w: &mut World, core: String) {
+    w.assert_result_ok();
+    assert!(core_compatibility(w, &core).playable);
+}
+
+#[then(expr = "core {word} is not playable on the device")]
+async fn core_is_not_playable(w: &mut World, core: String) {
+    w.assert_result_ok();
+    assert!(!core_compatibility(w, &core).playable);
+}
+
+/// The status of the required files of a core on the device, as
+/// `filename:status`, with where misplaced files were found.
+#[then(expr = "the required files of core {word} are {string} on the device")]
+async fn core_required_files_status(w: &mut World, core: String, expected: String) {
+    w.assert_result_ok();
+
+    let actual = core_compatibility(w, &core)
+        .files
+        .iter()
+        .map(|check| match &check.found_at {
+            Some(found_at) => format!("{}:{} at {found_at}", check.file.filename, check.status),
+            None => format!("{}:{}", check.file.filename, check.status),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    assert_eq!(actual, expected);
 }
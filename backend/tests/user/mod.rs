@@ -1,12 +1,12 @@
 use anyhow::{anyhow, Error};
-use backend::routes::v1;
+use backend::routes::{self, v1};
 use rand::distributions::Alphanumeric;
 use rand::Rng;
 use retronomicon_dto as dto;
 use retronomicon_dto::types::IdOrSlug;
 use retronomicon_dto::user::UserIdOrUsername;
 use rocket::http::uri::Origin;
-use rocket::http::{Cookie, Method, Status};
+use rocket::http::{Cookie, Header, Method, RawStr, Status, StatusClass};
 use rocket::local::asynchronous::Client;
 use rocket::uri;
 use std::collections::BTreeMap;
@@ -47,6 +47,44 @@ fn create_image(text: String) -> Vec<u8> {
     bytes
 }
 
+/// The TOTP code of a secret, `steps` time steps from now.
+pub fn two_factor_code(secret: &[u8], steps: u64) -> String {
+    totp_rs::TOTP::new_unchecked(
+        totp_rs::Algorithm::SHA1,
+        6,
+        1,
+        30,
+        secret.to_vec(),
+        None,
+        "".into(),
+    )
+    .generate(chrono::Utc::now().timestamp() as u64 + steps * 30)
+}
+
+/// A file with random size and checksums, as if read from a dump.
+pub fn random_file() -> dto::games::GameIdentifyFile {
+    let mut rng = rand::thread_rng();
+    let size = rng.gen_range(1..1 << 24);
+    let mut checksum =
+        |len: usize| Some((0..len).map(|_| rng.gen::<u8>()).collect::<Vec<_>>().into());
+    dto::games::GameIdentifyFile {
+        size,
+        crc32: checksum(4),
+        md5: checksum(16),
+        sha1: checksum(20),
+        sha256: checksum(32),
+    }
+}
+
+/// A random lowercase word, unlikely to be found in other names.
+pub fn random_word() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(10)
+        .map(|c| char::from(c).to_ascii_lowercase())
+        .collect()
+}
+
 static mut COUNTER: AtomicUsize = AtomicUsize::new(0);
 
 fn unique_id() -> usize {
@@ -73,14 +111,20 @@ pub enum User {
         client: Arc<Client>,
         cookie: Cookie<'static>,
         name: String,
+        email: String,
         id: i32,
     },
     Auth {
         client: Arc<Client>,
         cookie: Cookie<'static>,
         name: String,
+        email: String,
         id: i32,
     },
+    Token {
+        client: Arc<Client>,
+        token: String,
+    },
     Anonymous {
         client: Arc<Client>,
     },
@@ -90,7 +134,15 @@ impl User {
     pub fn id(&self) -> i32 {
         match self {
             User::NoAuth { id, .. } | User::Auth { id, .. } => *id,
-            User::Anonymous { .. } => -1,
+            User::Token { .. } | User::Anonymous { .. } => -1,
+        }
+    }
+
+    /// The email of the user, which is also their initial password.
+    pub fn email(&self) -> Option<&str> {
+        match self {
+            User::NoAuth { email, .. } | User::Auth { email, .. } => Some(email),
+            User::Token { .. } | User::Anonymous { .. } => None,
         }
     }
 
@@ -99,14 +151,14 @@ impl User {
         method: Method,
         uri: Origin<'_>,
         cookie: &mut Cookie<'static>,
+        token: Option<&str>,
         body: &impl serde::Serialize,
     ) -> Result<R, Error> {
-        let response = client
-            .req(method, uri)
-            .cookie(cookie.clone())
-            .json(body)
-            .dispatch()
-            .await;
+        let mut request = client.req(method, uri).cookie(cookie.clone()).json(body);
+        if let Some(token) = token {
+            request.add_header(Header::new("Authorization", format!("Bearer {token}")));
+        }
+        let response = request.dispatch().await;
 
         if response.status() != Status::Ok {
             let status = response.status();
@@ -138,10 +190,15 @@ impl User {
     ) -> Result<R, Error> {
         match self {
             User::NoAuth { client, cookie, .. } | User::Auth { client, cookie, .. } => {
-                Self::req_(client, method, uri, cookie, body).await
+                Self::req_(client, method, uri, cookie, None, body).await
+            }
+            User::Token { client, token } => {
+                let mut cookie = Cookie::new("empty", "");
+                Self::req_(client, method, uri, &mut cookie, Some(token), body).await
             }
             User::Anonymous { client } => {
-                Self::req_(client, method, uri, &mut Cookie::new("empty", ""), body).await
+                let mut cookie = Cookie::new("empty", "");
+                Self::req_(client, method, uri, &mut cookie, None, body).await
             }
         }
     }
@@ -162,6 +219,14 @@ impl User {
         self.req(Method::Post, uri, body).await
     }
 
+    async fn delete<R: serde::de::DeserializeOwned>(
+        &mut self,
+        uri: Origin<'_>,
+        body: &impl serde::Serialize,
+    ) -> Result<R, Error> {
+        self.req(Method::Delete, uri, body).await
+    }
+
     async fn put<R: serde::de::DeserializeOwned>(
         &mut self,
         uri: Origin<'_>,
@@ -170,6 +235,34 @@ impl User {
         self.req(Method::Put, uri, body).await
     }
 
+    /// Request a web page that redirects, returning where it redirects to.
+    async fn redirect(&mut self, uri: Origin<'_>) -> Result<String, Error> {
+        let (client, cookie) = match self {
+            User::NoAuth { client, cookie, .. } | User::Auth { client, cookie, .. } => {
+                (client, cookie.clone())
+            }
+            User::Token { client, .. } | User::Anonymous { client } => {
+                (client, Cookie::new("empty", ""))
+            }
+        };
+        let response = client.get(uri).cookie(cookie).dispatch().await;
+
+        if response.status().class() != StatusClass::Redirection {
+            let status = response.status();
+            let body = response.into_string().await;
+            return Err(anyhow!(
+                "Server returned status: {} body: {:?}",
+                status,
+                body
+            ));
+        }
+        response
+            .headers()
+            .get_one("Location")
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("No location"))
+    }
+
     fn gen_string(len: usize) -> String {
         rand::thread_rng()
             .sample_iter(&Alphanumeric)
@@ -178,7 +271,7 @@ impl User {
             .collect::<String>()
     }
 
-    fn create_email(domain: &str) -> String {
+    pub fn create_email(domain: &str) -> String {
         format!("{}@{}", Self::gen_string(10), domain)
     }
 
@@ -200,13 +293,18 @@ impl User {
         Ok(result)
     }
 
+    /// A user authenticated with a personal access token.
+    pub fn token(client: Arc<Client>, token: String) -> Self {
+        Self::Token { client, token }
+    }
+
     pub async fn user(client: Arc<Client>, name: &str) -> Result<Self, Error> {
         Self::create(client, &format!("user-{name}")).await
     }
 
     async fn create(client: Arc<Client>, name: &str) -> Result<Self, Error> {
+        let email = Self::create_email(name);
         let (cookie, id) = {
-            let email = Self::create_email(name);
             let password = email.as_str();
             let user = client
                 .post(uri!(v1::auth::signup()))
@@ -240,18 +338,113 @@ impl User {
             cookie,
             id,
             name: name.to_string(),
+            email,
+        })
+    }
+
+    /// Log in with an email and password, as a new session of an existing
+    /// user.
+    pub async fn login(
+        client: Arc<Client>,
+        name: &str,
+        email: &str,
+        password: &str,
+    ) -> Result<Self, Error> {
+        Self::login_with_code(client, name, email, password, None).await
+    }
+
+    /// Log in with an email and password, completing the login with a
+    /// two-factor authentication code if the user enabled it.
+    pub async fn login_with_code(
+        client: Arc<Client>,
+        name: &str,
+        email: &str,
+        password: &str,
+        code: Option<&str>,
+    ) -> Result<Self, Error> {
+        let cookie = {
+            let response = client
+                .post(uri!(v1::auth::login()))
+                .json(&dto::auth::LoginRequest { email, password })
+                .dispatch()
+                .await;
+
+            if response.status() != Status::Ok {
+                return Err(anyhow!(
+                    "Failed to login: {:?}",
+                    response.into_string().await.expect("No response body")
+                ));
+            }
+            let auth = response.cookies().get("auth").cloned();
+            let pending = response.cookies().get("auth_pending").cloned();
+            let login = response
+                .into_json::<dto::auth::LoginResponse>()
+                .await
+                .expect("Failed to deserialize response");
+
+            if login.two_factor_required {
+                let code = code.ok_or_else(|| anyhow!("Two-factor authentication required"))?;
+                let response = client
+                    .post(uri!(v1::auth::login_two_factor()))
+                    .cookie(pending.expect("No pending cookie"))
+                    .json(&dto::auth::TwoFactorCodeRequest { code })
+                    .dispatch()
+                    .await;
+
+                if response.status() != Status::Ok {
+                    return Err(anyhow!(
+                        "Failed to login with code: {:?}",
+                        response.into_string().await.expect("No response body")
+                    ));
+                }
+                response
+                    .cookies()
+                    .get("auth")
+                    .expect("No auth cookie")
+                    .clone()
+            } else {
+                auth.expect("No auth cookie")
+            }
+        };
+
+        let mut user = Self::NoAuth {
+            client: client.clone(),
+            cookie: cookie.clone(),
+            name: name.to_string(),
+            email: email.to_string(),
+            id: -1,
+        };
+        let details = user.whoami().await?;
+
+        Ok(if details.user.username.is_some() {
+            Self::Auth {
+                client,
+                cookie,
+                name: name.to_string(),
+                email: email.to_string(),
+                id: details.user.id,
+            }
+        } else {
+            Self::NoAuth {
+                client,
+                cookie,
+                name: name.to_string(),
+                email: email.to_string(),
+                id: details.user.id,
+            }
         })
     }
 
     pub async fn authenticate(&mut self) -> Result<(), Error> {
         match self {
             User::Anonymous { .. } => Err(anyhow!("Cannot authenticate anonymous user.")),
-            User::Auth { .. } => Ok(()),
+            User::Auth { .. } | User::Token { .. } => Ok(()),
             User::NoAuth {
                 client,
                 cookie,
                 id,
                 name,
+                email,
             } => {
                 // `self` is already borrowed, so can't borrow twice.
                 Self::req_::<dto::Ok>(
@@ -259,6 +452,7 @@ impl User {
                     Method::Put,
                     uri!(v1::me::me_update()),
                     cookie,
+                    None,
                     &dto::user::UserUpdate {
                         username: Some(&Self::create_username(name)),
                         ..Default::default()
@@ -270,6 +464,7 @@ impl User {
                     client: client.clone(),
                     cookie: cookie.clone(),
                     name: name.clone(),
+                    email: email.clone(),
                     id: *id,
                 };
                 Ok(())
@@ -281,6 +476,174 @@ impl User {
         self.get(uri!(v1::me::me()), &()).await
     }
 
+    /// Enable two-factor authentication. Returns the TOTP secret and the
+    /// recovery codes.
+    pub async fn enable_two_factor(&mut self) -> Result<(Vec<u8>, Vec<String>), Error> {
+        let enrolment: dto::auth::TwoFactorEnrollResponse =
+            self.post(uri!(v1::me::me_two_factor_enroll()), &()).await?;
+        let secret = totp_rs::Secret::Encoded(enrolment.secret)
+            .to_bytes()
+            .map_err(|e| anyhow!("{e:?}"))?;
+
+        let code = two_factor_code(&secret, 0);
+        let recovery: dto::auth::TwoFactorRecoveryCodes = self
+            .post(
+                uri!(v1::me::me_two_factor_confirm()),
+                &dto::auth::TwoFactorCodeRequest { code: &code },
+            )
+            .await?;
+
+        Ok((secret, recovery.codes))
+    }
+
+    pub async fn disable_two_factor(&mut self, code: &str) -> Result<(), Error> {
+        self.delete::<dto::Ok>(
+            uri!(v1::me::me_two_factor_disable()),
+            &dto::auth::TwoFactorCodeRequest { code },
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn change_password(
+        &mut self,
+        current_password: Option<&str>,
+        new_password: &str,
+    ) -> Result<(), Error> {
+        self.put::<dto::Ok>(
+            uri!(v1::me::me_password()),
+            &dto::auth::PasswordChangeRequest {
+                current_password,
+                new_password,
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Start logging in with an OpenID Connect provider, returning the URL of
+    /// the provider to log in with.
+    pub async fn start_oidc_login(&mut self, provider: &str) -> Result<String, Error> {
+        self.redirect(uri!(v1::auth::oidc_login(provider))).await
+    }
+
+    pub async fn oidc_callback(
+        &mut self,
+        provider: &str,
+        code: &str,
+        state: &str,
+    ) -> Result<String, Error> {
+        self.redirect(uri!(
+            "/api",
+            routes::auth::oidc_callback(provider, Some(code), Some(state), None::<&str>)
+        ))
+        .await
+    }
+
+    pub async fn oidc_providers(&mut self) -> Result<Vec<dto::auth::OidcProvider>, Error> {
+        self.get(uri!(v1::auth::oidc_providers()), &()).await
+    }
+
+    /// Delete the account of the user, confirming with their username (or
+    /// email) unless another confirmation is given.
+    pub async fn delete_account(
+        &mut self,
+        confirm: Option<&str>,
+        transfer_ownership: Vec<dto::user::TeamOwnershipTransfer>,
+    ) -> Result<dto::Ok, Error> {
+        let details = self.whoami().await?;
+        let email = self.email().unwrap_or_default().to_string();
+        let confirm = confirm
+            .map(str::to_string)
+            .or(details.user.username)
+            .unwrap_or_else(|| email.clone());
+
+        self.delete(
+            uri!(v1::me::me_delete()),
+            &dto::user::AccountDeleteRequest {
+                confirm: &confirm,
+                password: Some(&email),
+                code: None,
+                transfer_ownership,
+            },
+        )
+        .await
+    }
+
+    /// Change the email of the user, confirming with their initial password
+    /// unless another password is given.
+    pub async fn change_email(
+        &mut self,
+        email: &str,
+        password: Option<&str>,
+    ) -> Result<dto::auth::EmailChangeResponse, Error> {
+        let initial = self.email().unwrap_or_default().to_string();
+        self.post(
+            uri!(v1::me::me_email()),
+            &dto::auth::EmailChangeRequest {
+                email,
+                password: Some(password.unwrap_or(&initial)),
+            },
+        )
+        .await
+    }
+
+    pub async fn export(&mut self) -> Result<dto::user::UserExport, Error> {
+        self.get(uri!(v1::me::me_export()), &()).await
+    }
+
+    pub async fn sessions(&mut self) -> Result<Vec<dto::auth::UserSession>, Error> {
+        self.get(uri!(v1::me::me_sessions()), &()).await
+    }
+
+    pub async fn revoke_session(&mut self, session_id: i32) -> Result<dto::Ok, Error> {
+        self.delete(uri!(v1::me::me_sessions_revoke(session_id)), &())
+            .await
+    }
+
+    pub async fn revoke_all_sessions(&mut self) -> Result<dto::Ok, Error> {
+        self.delete(uri!(v1::me::me_sessions_revoke_all()), &())
+            .await
+    }
+
+    pub async fn terminate_sessions(&mut self, user_id: i32) -> Result<dto::Ok, Error> {
+        self.delete(
+            uri!(v1::users::users_sessions_terminate(UserIdOrUsername::Id(
+                user_id
+            ))),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn logout(&mut self) -> Result<(), Error> {
+        let (client, cookie) = match self {
+            User::NoAuth { client, cookie, .. } | User::Auth { client, cookie, .. } => {
+                (client, cookie.clone())
+            }
+            _ => return Err(anyhow!("Not logged in with a session")),
+        };
+        let response = client
+            .post(uri!(v1::auth::logout()))
+            .cookie(cookie)
+            .dispatch()
+            .await;
+
+        if response.status().class() != StatusClass::Redirection {
+            return Err(anyhow!("Server returned status: {}", response.status()));
+        }
+        Ok(())
+    }
+
+    pub async fn identities(&mut self) -> Result<Vec<dto::auth::UserIdentity>, Error> {
+        self.get(uri!(v1::me::me_identities()), &()).await
+    }
+
+    pub async fn unlink_identity(&mut self, identity_id: i32) -> Result<dto::Ok, Error> {
+        self.delete(uri!(v1::me::me_identities_unlink(identity_id)), &())
+            .await
+    }
+
     pub async fn create_team(
         &mut self,
         name: &str,
@@ -298,6 +661,26 @@ impl User {
         .await
     }
 
+    pub async fn update_team(&mut self, team: i32, description: &str) -> Result<dto::Ok, Error> {
+        self.put(
+            uri!(v1::teams::teams_update(team)),
+            &dto::teams::TeamUpdateRequest {
+                slug: None,
+                name: None,
+                description: Some(description),
+                links: None,
+                metadata: None,
+                add_links: None,
+                remove_links: None,
+            },
+        )
+        .await
+    }
+
+    pub async fn delete_team(&mut self, team: i32) -> Result<dto::Ok, Error> {
+        self.delete(uri!(v1::teams::teams_delete(team)), &()).await
+    }
+
     pub async fn invite_to_team(
         &mut self,
         team: i32,
@@ -321,6 +704,144 @@ impl User {
         Ok(())
     }
 
+    pub async fn decline_team_invitation(&mut self, team: i32) -> Result<dto::Ok, Error> {
+        self.delete(uri!(v1::teams::invite_decline(team)), &())
+            .await
+    }
+
+    pub async fn team_invitations(
+        &mut self,
+        team: i32,
+    ) -> Result<Vec<dto::teams::TeamInvitation>, Error> {
+        self.get(uri!(v1::teams::teams_invitations(team)), &())
+            .await
+    }
+
+    pub async fn cancel_team_invitation(&mut self, team: i32, user: i32) -> Result<dto::Ok, Error> {
+        self.delete(
+            uri!(v1::teams::invite_cancel(team, UserIdOrUsername::Id(user))),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn set_team_role(
+        &mut self,
+        team: i32,
+        user: i32,
+        role: dto::types::UserTeamRole,
+    ) -> Result<dto::Ok, Error> {
+        self.put(
+            uri!(v1::teams::teams_members_update(
+                team,
+                UserIdOrUsername::Id(user)
+            )),
+            &dto::teams::TeamMemberUpdate { role },
+        )
+        .await
+    }
+
+    pub async fn remove_from_team(&mut self, team: i32, user: i32) -> Result<dto::Ok, Error> {
+        self.delete(
+            uri!(v1::teams::teams_members_remove(
+                team,
+                UserIdOrUsername::Id(user)
+            )),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn leave_team(&mut self, team: i32) -> Result<dto::Ok, Error> {
+        self.post(uri!(v1::teams::teams_leave(team)), &()).await
+    }
+
+    pub async fn invitations(&mut self) -> Result<Vec<dto::user::UserInvitation>, Error> {
+        self.get(uri!(v1::me::me_invitations()), &()).await
+    }
+
+    pub async fn notifications(
+        &mut self,
+        unread: bool,
+    ) -> Result<Vec<dto::notifications::Notification>, Error> {
+        self.get(
+            uri!(v1::notifications::me_notifications(
+                dto::notifications::NotificationFilterParams {
+                    unread: Some(unread),
+                    ..Default::default()
+                }
+            )),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn read_notifications(&mut self) -> Result<dto::Ok, Error> {
+        self.post(
+            uri!(v1::notifications::me_notifications_read()),
+            &dto::notifications::NotificationReadRequest { ids: None },
+        )
+        .await
+    }
+
+    pub async fn set_notification_preference(
+        &mut self,
+        preference: dto::notifications::NotificationPreference,
+    ) -> Result<Vec<dto::notifications::NotificationPreference>, Error> {
+        self.put(
+            uri!(v1::notifications::me_notification_preferences_update()),
+            &[preference],
+        )
+        .await
+    }
+
+    pub async fn update_team_permissions(
+        &mut self,
+        team: i32,
+        updates: &[dto::permissions::TeamPermissionUpdate],
+    ) -> Result<Vec<dto::permissions::TeamRolePermissions>, Error> {
+        self.put(
+            uri!(v1::permissions::teams_permissions_update(team)),
+            &updates,
+        )
+        .await
+    }
+
+    pub async fn set_core_collaborator(
+        &mut self,
+        core: i32,
+        user: i32,
+        permissions: Vec<dto::permissions::Permission>,
+    ) -> Result<dto::Ok, Error> {
+        self.put(
+            uri!(v1::permissions::cores_collaborators_update(
+                core,
+                UserIdOrUsername::Id(user)
+            )),
+            &dto::permissions::CoreCollaboratorUpdate { permissions },
+        )
+        .await
+    }
+
+    pub async fn check_permission(
+        &mut self,
+        permission: dto::permissions::Permission,
+        core: i32,
+    ) -> Result<dto::permissions::PermissionCheck, Error> {
+        self.get(
+            uri!(v1::permissions::permissions_check(
+                dto::permissions::PermissionCheckQuery {
+                    permission,
+                    team: None,
+                    core: Some(IdOrSlug::Id(core)),
+                    user: None,
+                }
+            )),
+            &(),
+        )
+        .await
+    }
+
     pub async fn set_info(&mut self, info: dto::user::UserUpdate<'_>) -> Result<(), Error> {
         let username = info.username.map(Self::create_username);
         self.put::<dto::Ok>(
@@ -379,57 +900,746 @@ impl User {
                 description: "",
                 short_description: "",
                 year: 1234,
-                publisher: "cucumber-publisher",
-                developer: "cucumber-developer",
                 links: BTreeMap::new(),
                 system,
                 system_unique_id: unique_id() as i32,
+                publishers: vec!["cucumber-publisher"],
+                developers: vec!["cucumber-developer"],
+                publisher: None,
+                developer: None,
+                releases: vec![],
+                languages: vec![],
+                genres: vec![],
+                series: vec![],
+                tags: vec![],
+                min_players: None,
+                max_players: None,
             },
         )
         .await
     }
 
-    pub async fn get_game_by_id(&mut self, game_id: i32) -> Result<dto::games::GameDetails, Error> {
-        self.get(uri!(v1::games::games_details(game_id as u32)), &())
-            .await
-    }
-
-    pub async fn get_game_images(
+    pub async fn create_platform(
         &mut self,
-        game_id: i32,
-    ) -> Result<Vec<dto::images::Image>, Error> {
-        self.get(
-            uri!(v1::games::games_images(
-                game_id as u32,
-                dto::games::GameImageListQueryParams::default()
-            )),
-            &(),
+        team: i32,
+        name: &str,
+    ) -> Result<dto::platforms::PlatformCreateResponse, Error> {
+        let owner_team = self.team_details(team).await?.team.into();
+        let name = Self::create_username(name);
+        self.post(
+            uri!(v1::platforms::platforms_create()),
+            &dto::platforms::PlatformCreateRequest {
+                slug: &Self::create_slug(&name),
+                name: &name,
+                description: "",
+                links: None,
+                metadata: None,
+                owner_team,
+            },
         )
         .await
     }
 
-    pub async fn get_user_details(
+    pub async fn create_core(
         &mut self,
-        user: Option<UserIdOrUsername<'_>>,
-    ) -> Result<dto::user::UserDetails, Error> {
-        match user {
-            Some(user) => self.get(uri!(v1::users::users_details(user)), &()).await,
-            None => self.whoami().await,
-        }
+        team: i32,
+        system_id: i32,
+        name: &str,
+    ) -> Result<dto::cores::CoreCreateResponse, Error> {
+        let owner_team = self.team_details(team).await?.team.into();
+        let name = Self::create_username(name);
+        self.post(
+            uri!(v1::cores::cores_create()),
+            &dto::cores::CoreCreateRequest {
+                slug: &Self::create_slug(&name),
+                name: &name,
+                description: "",
+                links: BTreeMap::new(),
+                metadata: BTreeMap::new(),
+                system: IdOrSlug::Id(system_id),
+                owner_team,
+            },
+        )
+        .await
     }
 
-    pub async fn upload_image(&mut self, game_id: i32, image_name: &str) -> Result<(), Error> {
-        let bytes = create_image(format!("{game_id} / {image_name}.png"));
-        let cookie = match self {
-            User::NoAuth { cookie, .. } | User::Auth { cookie, .. } => cookie.clone(),
-            User::Anonymous { .. } => Cookie::new("empty", ""),
+    pub async fn create_release(
+        &mut self,
+        core_id: i32,
+        platform_id: i32,
+        version: &str,
+    ) -> Result<dto::cores::releases::CoreReleaseCreateResponse, Error> {
+        self.post(
+            uri!(v1::cores::releases::cores_releases_create(core_id)),
+            &dto::cores::releases::CoreReleaseCreateRequest {
+                version,
+                notes: "",
+                date_released: None,
+                prerelease: false,
+                links: BTreeMap::new(),
+                metadata: BTreeMap::new(),
+                platform: IdOrSlug::Id(platform_id),
+            },
+        )
+        .await
+    }
+
+    pub async fn yank_release(
+        &mut self,
+        core_id: i32,
+        release_id: i32,
+        yanked: bool,
+    ) -> Result<dto::Ok, Error> {
+        self.put(
+            uri!(v1::cores::releases::cores_releases_yank(
+                core_id, release_id
+            )),
+            &dto::cores::releases::CoreReleaseYankRequest { yanked },
+        )
+        .await
+    }
+
+    pub async fn follow_core(&mut self, core_id: i32) -> Result<dto::Ok, Error> {
+        self.post(uri!(v1::cores::cores_follow(core_id)), &()).await
+    }
+
+    pub async fn unfollow_core(&mut self, core_id: i32) -> Result<dto::Ok, Error> {
+        self.delete(uri!(v1::cores::cores_unfollow(core_id)), &())
+            .await
+    }
+
+    pub async fn create_required_file(
+        &mut self,
+        core_id: i32,
+        request: &dto::cores::required_files::CoreRequiredFileCreateRequest<'_>,
+    ) -> Result<dto::cores::required_files::CoreRequiredFileCreateResponse, Error> {
+        self.post(
+            uri!(v1::cores::required_files::cores_required_files_create(
+                core_id
+            )),
+            request,
+        )
+        .await
+    }
+
+    pub async fn required_files(
+        &mut self,
+        core_id: i32,
+        release_id: Option<i32>,
+    ) -> Result<Vec<dto::cores::required_files::CoreRequiredFile>, Error> {
+        self.get(
+            uri!(v1::cores::required_files::cores_required_files_list(
+                core_id,
+                dto::cores::required_files::CoreRequiredFileListQueryParams {
+                    release: release_id,
+                }
+            )),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn delete_required_file(
+        &mut self,
+        core_id: i32,
+        file_id: i32,
+    ) -> Result<dto::Ok, Error> {
+        self.delete(
+            uri!(v1::cores::required_files::cores_required_files_delete(
+                core_id,
+                file_id as u32
+            )),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn check_compatibility(
+        &mut self,
+        files: Vec<dto::cores::required_files::DeviceFile>,
+    ) -> Result<Vec<dto::cores::required_files::CoreCompatibility>, Error> {
+        self.post(
+            uri!(v1::cores::required_files::cores_compatibility()),
+            &dto::cores::required_files::CoreCompatibilityRequest {
+                platform: None,
+                system: None,
+                files,
+            },
+        )
+        .await
+    }
+
+    pub async fn get_release_artifacts(
+        &mut self,
+        core_id: i32,
+        release_id: i32,
+    ) -> Result<Vec<dto::artifact::CoreReleaseArtifactListItem>, Error> {
+        self.get(
+            uri!(v1::cores::releases::cores_releases_artifacts_list(
+                core_id,
+                release_id as u32,
+                dto::params::PagingParams::default()
+            )),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn create_upload(
+        &mut self,
+        core_id: i32,
+        release_id: i32,
+        filename: &str,
+        mime_type: &str,
+        size: i64,
+    ) -> Result<dto::artifact::ArtifactUpload, Error> {
+        self.post(
+            uri!(v1::cores::uploads::cores_releases_uploads_create(
+                core_id,
+                release_id as u32
+            )),
+            &dto::artifact::ArtifactUploadCreateRequest {
+                filename,
+                mime_type,
+                size,
+            },
+        )
+        .await
+    }
+
+    async fn req_raw<R: serde::de::DeserializeOwned>(
+        &mut self,
+        method: Method,
+        uri: Origin<'_>,
+        headers: Vec<Header<'static>>,
+        body: Vec<u8>,
+    ) -> Result<R, Error> {
+        let content = self.req_string(method, uri, headers, body).await?;
+        serde_json::from_str(&content).map_err(|e| anyhow!(e))
+    }
+
+    /// Send a request with a raw body, returning the body of the response.
+    async fn req_string(
+        &mut self,
+        method: Method,
+        uri: Origin<'_>,
+        headers: Vec<Header<'static>>,
+        body: Vec<u8>,
+    ) -> Result<String, Error> {
+        let (client, cookie, token) = match self {
+            User::NoAuth { client, cookie, .. } | User::Auth { client, cookie, .. } => {
+                (client, cookie.clone(), None)
+            }
+            User::Token { client, token } => (client, Cookie::new("empty", ""), Some(token)),
+            User::Anonymous { client } => (client, Cookie::new("empty", ""), None),
         };
-        let mut request = match self {
-            User::NoAuth { client, .. } | User::Auth { client, .. } => client,
-            User::Anonymous { client } => client,
+        let mut request = client.req(method, uri).cookie(cookie).body(body);
+        if let Some(token) = token {
+            request.add_header(Header::new("Authorization", format!("Bearer {token}")));
+        }
+        for header in headers {
+            request.add_header(header);
+        }
+
+        let response = request.dispatch().await;
+        if response.status() != Status::Ok {
+            return Err(anyhow!(
+                "Server returned status: {} body: {:?}",
+                response.status(),
+                response.into_string().await
+            ));
         }
-        .req(Method::Post, uri!(v1::games::games_images_upload(game_id)))
-        .cookie(cookie);
+        response
+            .into_string()
+            .await
+            .ok_or_else(|| anyhow!("Could not deserialize from JSON: empty response."))
+    }
+
+    pub async fn upload_part(
+        &mut self,
+        core_id: i32,
+        release_id: i32,
+        upload_id: i32,
+        part: u32,
+        data: Vec<u8>,
+    ) -> Result<dto::artifact::ArtifactUpload, Error> {
+        self.req_raw(
+            Method::Put,
+            uri!(v1::cores::uploads::cores_releases_uploads_part(
+                core_id,
+                release_id as u32,
+                upload_id,
+                part
+            )),
+            vec![],
+            data,
+        )
+        .await
+    }
+
+    pub async fn complete_upload(
+        &mut self,
+        core_id: i32,
+        release_id: i32,
+        upload_id: i32,
+        headers: Vec<(&'static str, String)>,
+    ) -> Result<dto::artifact::ArtifactCreateResponse, Error> {
+        self.req_raw(
+            Method::Post,
+            uri!(v1::cores::uploads::cores_releases_uploads_complete(
+                core_id,
+                release_id as u32,
+                upload_id
+            )),
+            headers
+                .into_iter()
+                .map(|(name, value)| Header::new(name, value))
+                .collect(),
+            vec![],
+        )
+        .await
+    }
+
+    pub async fn get_release_manifest(
+        &mut self,
+        core_id: i32,
+        release_id: i32,
+    ) -> Result<dto::cores::manifests::ReleaseManifest, Error> {
+        self.get(
+            uri!(v1::cores::manifests::cores_releases_manifest(
+                core_id,
+                release_id as u32
+            )),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn get_release_manifest_signatures(
+        &mut self,
+        core_id: i32,
+        release_id: i32,
+    ) -> Result<Vec<dto::cores::manifests::ReleaseManifestSignature>, Error> {
+        self.get(
+            uri!(v1::cores::manifests::cores_releases_manifest_signatures(
+                core_id,
+                release_id as u32
+            )),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn sign_release_manifest(
+        &mut self,
+        core_id: i32,
+        release_id: i32,
+        public_key: &[u8],
+        signature: &[u8],
+    ) -> Result<dto::Ok, Error> {
+        self.post(
+            uri!(v1::cores::manifests::cores_releases_manifest_sign(
+                core_id,
+                release_id as u32
+            )),
+            &dto::cores::manifests::ReleaseManifestSignRequest {
+                public_key: public_key.into(),
+                signature: signature.into(),
+            },
+        )
+        .await
+    }
+
+    pub async fn get_manifest_server_key(
+        &mut self,
+    ) -> Result<dto::cores::manifests::ManifestServerKey, Error> {
+        self.get(uri!(v1::cores::manifests::manifests_key()), &())
+            .await
+    }
+
+    pub async fn create_team_key(
+        &mut self,
+        team_id: i32,
+        name: &str,
+        public_key: &[u8],
+    ) -> Result<dto::teams::TeamSigningKey, Error> {
+        self.post(
+            uri!(v1::teams::teams_keys_create(team_id)),
+            &dto::teams::TeamSigningKeyCreateRequest {
+                name,
+                public_key: public_key.into(),
+            },
+        )
+        .await
+    }
+
+    pub async fn revoke_team_key(&mut self, team_id: i32, key_id: i32) -> Result<dto::Ok, Error> {
+        self.delete(uri!(v1::teams::teams_keys_revoke(team_id, key_id)), &())
+            .await
+    }
+
+    pub async fn create_token(
+        &mut self,
+        name: &str,
+        scopes: Vec<dto::tokens::TokenScopeRequest<'_>>,
+    ) -> Result<dto::tokens::UserTokenCreateResponse, Error> {
+        self.post(
+            uri!(v1::me::me_tokens_create()),
+            &dto::tokens::UserTokenCreateRequest {
+                name,
+                scopes,
+                expires_in_days: None,
+            },
+        )
+        .await
+    }
+
+    pub async fn revoke_token(&mut self, token_id: i32) -> Result<dto::Ok, Error> {
+        self.delete(uri!(v1::me::me_tokens_revoke(token_id)), &())
+            .await
+    }
+
+    pub async fn get_game_by_id(&mut self, game_id: i32) -> Result<dto::games::GameDetails, Error> {
+        self.get(
+            uri!(v1::games::games_details(
+                game_id as u32,
+                dto::games::GameDetailsQueryParams::default()
+            )),
+            &(),
+        )
+        .await
+    }
+
+    /// The details of a game, in the language asked for with a `lang`
+    /// parameter or an `Accept-Language` header.
+    pub async fn get_game_localised(
+        &mut self,
+        game_id: i32,
+        lang: Option<&str>,
+        accept_language: Option<&str>,
+    ) -> Result<dto::games::GameDetails, Error> {
+        let headers = accept_language
+            .map(|l| Header::new("Accept-Language", l.to_string()))
+            .into_iter()
+            .collect();
+        self.req_raw(
+            Method::Get,
+            uri!(v1::games::games_details(
+                game_id as u32,
+                dto::games::GameDetailsQueryParams {
+                    lang: lang.map(str::to_string),
+                }
+            )),
+            headers,
+            vec![],
+        )
+        .await
+    }
+
+    pub async fn translate_game(
+        &mut self,
+        game_id: i32,
+        language: &str,
+        translation: &dto::games::GameTranslationRequest<'_>,
+    ) -> Result<dto::games::GameTranslation, Error> {
+        self.put(
+            uri!(v1::games::translations::games_translations_update(
+                game_id as u32,
+                language
+            )),
+            translation,
+        )
+        .await
+    }
+
+    pub async fn delete_game_translation(
+        &mut self,
+        game_id: i32,
+        language: &str,
+    ) -> Result<dto::Ok, Error> {
+        self.delete(
+            uri!(v1::games::translations::games_translations_delete(
+                game_id as u32,
+                language
+            )),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn relate_games(
+        &mut self,
+        game_id: i32,
+        kind: dto::games::GameRelationshipKind,
+        related_game_id: i32,
+    ) -> Result<dto::Ok, Error> {
+        self.post(
+            uri!(v1::games::relationships::games_relationships_create(
+                game_id as u32
+            )),
+            &dto::games::GameRelationshipCreateRequest {
+                kind,
+                game: related_game_id,
+            },
+        )
+        .await
+    }
+
+    pub async fn unrelate_games(
+        &mut self,
+        game_id: i32,
+        kind: dto::games::GameRelationshipKind,
+        related_game_id: i32,
+    ) -> Result<dto::Ok, Error> {
+        self.delete(
+            uri!(v1::games::relationships::games_relationships_delete(
+                game_id as u32,
+                kind.to_string(),
+                related_game_id as u32
+            )),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn get_related_games(
+        &mut self,
+        game_id: i32,
+        params: dto::games::GameRelatedQueryParams,
+    ) -> Result<Vec<dto::games::GameRelatedItem>, Error> {
+        self.get(
+            uri!(v1::games::relationships::games_related(
+                game_id as u32,
+                params
+            )),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn update_game(
+        &mut self,
+        game_id: i32,
+        update: &dto::games::GameUpdateRequest<'_>,
+    ) -> Result<dto::Ok, Error> {
+        self.put(uri!(v1::games::games_update(game_id as u32)), update)
+            .await
+    }
+
+    /// List the games of a system, with other filters as query parameters.
+    pub async fn list_games(
+        &mut self,
+        system_id: i32,
+        filters: &[(&str, &str)],
+    ) -> Result<Vec<dto::games::GameListItemResponse>, Error> {
+        let mut query = format!("system={system_id}");
+        for (key, value) in filters {
+            query += &format!("&{key}={}", RawStr::new(value).percent_encode());
+        }
+        let uri =
+            Origin::parse_owned(format!("/games?{query}")).map_err(|e| anyhow!(e.to_string()))?;
+        self.get(uri, &dto::games::GameListBody::default()).await
+    }
+
+    pub async fn get_game_images(
+        &mut self,
+        game_id: i32,
+    ) -> Result<Vec<dto::images::Image>, Error> {
+        self.get_game_images_with_role(game_id, None).await
+    }
+
+    pub async fn get_game_images_with_role(
+        &mut self,
+        game_id: i32,
+        role: Option<dto::images::ImageRole>,
+    ) -> Result<Vec<dto::images::Image>, Error> {
+        self.get(
+            uri!(v1::games::games_images(
+                game_id as u32,
+                dto::games::GameImageListQueryParams {
+                    role,
+                    ..Default::default()
+                }
+            )),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn set_game_image_roles(
+        &mut self,
+        game_id: i32,
+        image_id: i32,
+        roles: Vec<dto::images::ImageRole>,
+    ) -> Result<dto::images::Image, Error> {
+        self.put(
+            uri!(v1::games::games_images_roles_update(
+                game_id as u32,
+                image_id as u32
+            )),
+            &dto::images::ImageRolesUpdateRequest { roles },
+        )
+        .await
+    }
+
+    pub async fn get_user_details(
+        &mut self,
+        user: Option<UserIdOrUsername<'_>>,
+    ) -> Result<dto::user::UserDetails, Error> {
+        match user {
+            Some(user) => self.get(uri!(v1::users::users_details(user)), &()).await,
+            None => self.whoami().await,
+        }
+    }
+
+    pub async fn add_game_artifact(
+        &mut self,
+        game_id: i32,
+        artifact: dto::games::GameAddArtifactRequest<'_>,
+    ) -> Result<dto::Ok, Error> {
+        self.post(
+            uri!(v1::games::games_add_artifact(game_id as u32)),
+            &vec![artifact],
+        )
+        .await
+    }
+
+    pub async fn get_game_artifacts(
+        &mut self,
+        game_id: i32,
+    ) -> Result<Vec<dto::games::GameArtifact>, Error> {
+        self.get(uri!(v1::games::games_artifacts(game_id as u32)), &())
+            .await
+    }
+
+    pub async fn identify(
+        &mut self,
+        files: Vec<dto::games::GameIdentifyFile>,
+    ) -> Result<Vec<dto::games::GameIdentifyResult>, Error> {
+        self.post(
+            uri!(v1::games::games_identify()),
+            &dto::games::GameIdentifyRequest {
+                system: None,
+                files,
+            },
+        )
+        .await
+    }
+
+    pub async fn import_dat(
+        &mut self,
+        system_id: i32,
+        dat: String,
+        dry_run: bool,
+    ) -> Result<dto::games::DatImportDetails, Error> {
+        self.req_raw(
+            Method::Post,
+            uri!(v1::systems::imports::systems_imports_create(
+                system_id,
+                dto::games::DatImportQueryParams {
+                    dry_run: Some(dry_run),
+                }
+            )),
+            vec![],
+            dat.into_bytes(),
+        )
+        .await
+    }
+
+    /// Export the games of a system as a DAT, with filters as query
+    /// parameters.
+    pub async fn export_dat(
+        &mut self,
+        system_id: i32,
+        filters: &[(&str, &str)],
+    ) -> Result<datary::Datafile, Error> {
+        let query = filters
+            .iter()
+            .map(|(key, value)| format!("{key}={}", RawStr::new(value).percent_encode()))
+            .collect::<Vec<_>>()
+            .join("&");
+        let uri = Origin::parse_owned(format!("/systems/{system_id}/games.dat?{query}"))
+            .map_err(|e| anyhow!(e.to_string()))?;
+        let dat = self.req_string(Method::Get, uri, vec![], vec![]).await?;
+        datary::from_reader(dat.as_bytes()).map_err(|e| anyhow!(e))
+    }
+
+    pub async fn create_tag(&mut self, slug: &str) -> Result<dto::Ok, Error> {
+        self.post(
+            uri!(v1::tags::tags_create()),
+            &dto::tags::TagCreate {
+                slug: slug.to_string(),
+                description: "".to_string(),
+                color: 0,
+            },
+        )
+        .await
+    }
+
+    pub async fn dat_import(
+        &mut self,
+        system_id: i32,
+        import_id: i32,
+    ) -> Result<dto::games::DatImportDetails, Error> {
+        self.get(
+            uri!(v1::systems::imports::systems_imports_details(
+                system_id, import_id
+            )),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn apply_dat_import(
+        &mut self,
+        system_id: i32,
+        import_id: i32,
+    ) -> Result<dto::games::DatImportDetails, Error> {
+        self.post(
+            uri!(v1::systems::imports::systems_imports_apply(
+                system_id, import_id
+            )),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn search(
+        &mut self,
+        q: &str,
+        kind: Option<dto::search::SearchKind>,
+    ) -> Result<dto::search::SearchResponse, Error> {
+        self.get(
+            uri!(v1::search::search(dto::search::SearchQueryParams {
+                q: q.to_string(),
+                kind,
+                paging: dto::params::PagingParams::default(),
+            })),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn upload_image(&mut self, game_id: i32, image_name: &str) -> Result<(), Error> {
+        self.upload_image_with(
+            game_id,
+            image_name,
+            &format!("{game_id} / {image_name}.png"),
+            dto::games::GameImageUploadQueryParams::default(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Upload a PNG image showing some text.
+    pub async fn upload_image_with(
+        &mut self,
+        game_id: i32,
+        image_name: &str,
+        text: &str,
+        params: dto::games::GameImageUploadQueryParams,
+    ) -> Result<Vec<dto::images::Image>, Error> {
+        let bytes = create_image(text.to_string());
 
         // Build the form manually. This is very cobbersome but Rocket doesn't provide a better
         // API just yet. See https://github.com/rwf2/Rocket/issues/1591.
@@ -447,21 +1657,15 @@ impl User {
             b"-----testboundary--\r\n".to_vec(),
         ]
         .concat();
-        request.add_header(rocket::http::Header::new(
-            "Content-Type",
-            "multipart/form-data; boundary=---testboundary",
-        ));
-        request.set_body(form);
-
-        let response = request.dispatch().await;
-        if response.status() != Status::Ok {
-            return Err(anyhow!(
-                "Server returned status: {} body: {:?}",
-                response.status(),
-                response.into_string().await
-            ));
-        }
-
-        Ok(())
+        self.req_raw(
+            Method::Post,
+            uri!(v1::games::games_images_upload(game_id, params)),
+            vec![Header::new(
+                "Content-Type",
+                "multipart/form-data; boundary=---testboundary",
+            )],
+            form,
+        )
+        .await
     }
 }
@@ -110,11 +110,13 @@ pub enum Status {
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Datafile {
     #[serde(rename = "@build")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub build: Option<String>,
 
     #[serde(rename = "@debug", default)]
     pub debug: bool,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub header: Option<Header>,
 
     #[serde(rename = "game", default)]
@@ -137,24 +139,32 @@ impl Datafile {
 pub struct Header {
     pub name: String,
     pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub category: Option<String>,
     pub version: String,
     pub author: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub homepage: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub comment: Option<String>,
 
     #[serde(rename = "clrmamepro")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub clr_mame_pro: Option<ClrMamePro>,
 
     #[serde(rename = "romcenter")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub rom_center: Option<RomCenter>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ClrMamePro {
     #[serde(rename = "@header")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub header: Option<String>,
 
     #[serde(rename = "@forcemerging", default)]
@@ -200,29 +210,37 @@ pub struct Game {
     pub comment: Vec<String>,
 
     #[serde(rename = "@id", default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<u32>,
 
     #[serde(rename = "@isbios", default)]
     pub is_bios: IsBios,
 
     #[serde(rename = "@cloneof")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub clone_of: Option<String>,
 
     #[serde(rename = "@romof")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub rom_of: Option<String>,
 
     #[serde(rename = "@sampleof")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub sample_of: Option<String>,
 
     #[serde(rename = "@board")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub board: Option<String>,
 
     #[serde(rename = "@rebuildto")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub rebuild_to: Option<String>,
 
     /// The year of manufacture. Technically a PCDATA but should probably be treated
     /// as an integer.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub year: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub manufacturer: Option<String>,
 
     #[serde(rename = "release", default)]
@@ -253,12 +271,15 @@ pub struct Release {
     region: String,
 
     #[serde(rename = "@language")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     language: Option<String>,
 
     #[serde(rename = "@date")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     date: Option<String>,
 
     #[serde(rename = "@default", default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     default: Option<Default>,
 }
 
@@ -271,6 +292,7 @@ pub struct BiosSet {
     description: String,
 
     #[serde(rename = "@default", default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     default: Option<Default>,
 }
 
@@ -281,16 +303,26 @@ pub struct Rom {
     #[serde(rename = "@size")]
     pub size: usize,
     #[serde(rename = "@crc")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub crc: Option<String>,
     #[serde(rename = "@sha1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub sha1: Option<String>,
     #[serde(rename = "@md5")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub md5: Option<String>,
+    /// Not in the logiqx DTD, but used by No-Intro DATs.
+    #[serde(rename = "@sha256")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
     #[serde(rename = "@merge")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub merge: Option<String>,
     #[serde(rename = "@status")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub status: Option<Status>,
     #[serde(rename = "@date")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub date: Option<String>,
 }
 
@@ -299,12 +331,16 @@ pub struct Disk {
     #[serde(rename = "@name")]
     pub name: String,
     #[serde(rename = "@sha1")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub sha1: Option<String>,
     #[serde(rename = "@md5")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub md5: Option<String>,
     #[serde(rename = "@merge")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub merge: Option<String>,
     #[serde(rename = "@status")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub status: Option<Status>,
 }
 
@@ -3,6 +3,9 @@ pub enum Error {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
+    #[error("Formatting error: {0}")]
+    Fmt(#[from] std::fmt::Error),
+
     #[error("XML error: {0}")]
     Xml(#[from] quick_xml::DeError),
 }
@@ -30,6 +30,32 @@ pub fn to_writer(
     Ok(())
 }
 
+/// Write the start of a DAT, with its header. Games can then be written one
+/// at a time with [`write_game`], and the DAT closed with [`write_end`], so
+/// large DATs do not need to be built in memory.
+pub fn write_start(
+    mut writer: impl std::fmt::Write,
+    header: Option<&dat::Header>,
+) -> Result<(), error::Error> {
+    writer.write_str("<datafile>")?;
+    if let Some(header) = header {
+        quick_xml::se::to_writer_with_root(&mut writer, "header", header)?;
+    }
+    Ok(())
+}
+
+/// Write a game of a DAT started with [`write_start`].
+pub fn write_game(mut writer: impl std::fmt::Write, game: &dat::Game) -> Result<(), error::Error> {
+    quick_xml::se::to_writer_with_root(&mut writer, "game", game)?;
+    Ok(())
+}
+
+/// Close a DAT started with [`write_start`].
+pub fn write_end(mut writer: impl std::fmt::Write) -> Result<(), error::Error> {
+    writer.write_str("</datafile>")?;
+    Ok(())
+}
+
 #[test]
 fn simple() {
     let x = r#"
@@ -51,3 +77,34 @@ fn simple() {
     assert!(!dat.debug);
     assert_eq!(dat.header.unwrap().name, "test");
 }
+
+#[test]
+fn write_games_one_at_a_time() {
+    let x = r#"
+        <datafile>
+        <header>
+            <name>test</name>
+            <version>1.0</version>
+            <author>a</author>
+            <description>d</description>
+        </header>
+
+        <game name="Test Game">
+            <description>Test Game Description</description>
+            <rom name="test.rom" size="123" crc="456" md5="789" sha1="012" />
+        </game>
+        <game name="Other Game">
+            <description>Other Game Description</description>
+        </game>
+        </datafile>"#;
+    let dat: dat::Datafile = from_reader(x.as_bytes()).unwrap();
+
+    let mut written = String::new();
+    write_start(&mut written, dat.header.as_ref()).unwrap();
+    for game in &dat.games {
+        write_game(&mut written, game).unwrap();
+    }
+    write_end(&mut written).unwrap();
+
+    assert_eq!(from_reader(written.as_bytes()).unwrap(), dat);
+}
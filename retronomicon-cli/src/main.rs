@@ -97,6 +97,40 @@ enum Command {
 
     /// Returns the authentication information.
     Whoami,
+
+    /// Verify the signatures of a release manifest, and optionally that local
+    /// files match it.
+    Verify(VerifyOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct VerifyOpts {
+    /// The core of the release.
+    #[clap(long)]
+    core: String,
+
+    /// The release's id.
+    release_id: i32,
+
+    /// The base64 public key the server signs manifests with. If omitted, it
+    /// is fetched from the server, which only protects against tampering
+    /// with the manifest or signatures in transit.
+    #[clap(long)]
+    public_key: Option<String>,
+
+    /// Require a valid signature from a key of the team owning the core.
+    #[clap(long)]
+    require_team_signature: bool,
+
+    /// Local files to check against the manifest, matched by filename.
+    files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Serialize)]
+struct VerifyOutput {
+    manifest: dto::cores::manifests::ReleaseManifest,
+    signed_by: Vec<String>,
+    files: Vec<String>,
 }
 
 #[derive(Debug, Parser)]
@@ -224,6 +258,18 @@ pub enum CoreCommand {
 
     /// Update a core.
     Update(CoreUpdateOpts),
+
+    /// List the files a core needs on the device, e.g. BIOS files.
+    RequiredFiles(CoreRequiredFilesOpts),
+
+    /// Declare a file a core needs on the device.
+    Require(CoreRequireOpts),
+
+    /// Remove a required file of a core.
+    Unrequire(CoreUnrequireOpts),
+
+    /// Check which cores can run with the files of a device.
+    Compatibility(CoreCompatibilityOpts),
 }
 
 #[derive(Debug, Parser)]
@@ -270,6 +316,96 @@ pub struct CoreGetOpts {
 #[derive(Debug, Parser)]
 pub struct CoreUpdateOpts {}
 
+#[derive(Debug, Parser)]
+pub struct CoreRequiredFilesOpts {
+    /// The core's slug or numerical id.
+    id: IdOrSlug<'static>,
+
+    /// Only list the files required by this release.
+    #[clap(long)]
+    release: Option<i32>,
+}
+
+#[derive(Debug, Parser)]
+pub struct CoreRequireOpts {
+    /// The core's slug or numerical id.
+    id: IdOrSlug<'static>,
+
+    /// The name of the file.
+    filename: String,
+
+    /// The directory of the file on the device (e.g. `games/PSX`).
+    #[clap(long, default_value = "")]
+    path: String,
+
+    /// Only require the file for this release.
+    #[clap(long)]
+    release: Option<i32>,
+
+    /// Whether the core can run without the file.
+    #[clap(long)]
+    optional: bool,
+
+    #[clap(long, default_value = "")]
+    description: String,
+
+    /// A version of the file the core accepts, as comma separated checksums
+    /// prefixed by their algorithm, with an optional size (e.g.
+    /// `size=524288,md5=...,sha1=...`). Can be repeated.
+    #[clap(long)]
+    hash: Vec<String>,
+}
+
+#[derive(Debug, Parser)]
+pub struct CoreUnrequireOpts {
+    /// The core's slug or numerical id.
+    id: IdOrSlug<'static>,
+
+    /// The required file's id.
+    file_id: i32,
+}
+
+#[derive(Debug, Parser)]
+pub struct CoreCompatibilityOpts {
+    /// Only check cores with releases for this platform. Can be a slug or a
+    /// numerical id.
+    #[clap(long)]
+    platform: Option<IdOrSlug<'static>>,
+
+    /// Only check cores of this system. Can be a slug or a numerical id.
+    #[clap(long)]
+    system: Option<IdOrSlug<'static>>,
+
+    /// The directory the device is mounted at. Paths of files are sent
+    /// relative to it.
+    #[clap(long)]
+    root: PathBuf,
+
+    /// The files of the device to check.
+    path: Vec<PathBuf>,
+}
+
+/// Parse a version of a required file, e.g. `size=524288,md5=...`.
+fn required_file_hash_from_arg(
+    arg: &str,
+) -> Result<dto::cores::required_files::RequiredFileHash, Error> {
+    let mut hash = dto::cores::required_files::RequiredFileHash::default();
+    for part in arg.split(',') {
+        let (key, value) = part
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Invalid hash {part:?}, expected a key=value pair"))?;
+        match key {
+            "size" => hash.size = Some(value.parse()?),
+            "crc32" => hash.crc32 = Some(value.parse()?),
+            "md5" => hash.md5 = Some(value.parse()?),
+            "sha1" => hash.sha1 = Some(value.parse()?),
+            "sha256" => hash.sha256 = Some(value.parse()?),
+            _ => return Err(anyhow::anyhow!("Unknown checksum {key:?}")),
+        }
+    }
+    Ok(hash)
+}
+
 #[derive(Debug, Parser)]
 pub struct GamesOpts {
     #[command(subcommand)]
@@ -283,8 +419,15 @@ pub enum GamesCommand {
     // Get(GameGetOpts),
     // Update(GameUpdateOpts),
     AddArtifact(GameAddArtifactOpts),
+    Artifacts(GameArtifactsOpts),
+    Identify(GameIdentifyOpts),
     UpdateFromDat(GameUpdateFromDatOpts),
+    ApplyImport(GameApplyImportOpts),
     AddImage(GameAddImageOpts),
+    Translate(GameTranslateOpts),
+    Relate(GameRelateOpts),
+    Unrelate(GameRelateOpts),
+    Related(GameRelatedOpts),
 }
 
 #[derive(Debug, Parser)]
@@ -308,6 +451,42 @@ pub struct GamesListOpts {
     #[clap(long)]
     exact_name: Option<String>,
 
+    /// Filter by the slug of a region the game was released in.
+    #[clap(long)]
+    region: Option<String>,
+
+    /// Filter by a language, as a BCP 47 tag.
+    #[clap(long)]
+    language: Option<String>,
+
+    /// Filter by the slug of a genre.
+    #[clap(long)]
+    genre: Option<String>,
+
+    /// Filter by the slug of a series.
+    #[clap(long)]
+    series: Option<String>,
+
+    /// Filter by the slug of a publisher.
+    #[clap(long)]
+    publisher: Option<String>,
+
+    /// Filter by the slug of a developer.
+    #[clap(long)]
+    developer: Option<String>,
+
+    /// Filter by the slug of a tag.
+    #[clap(long)]
+    tag: Option<String>,
+
+    /// Only list games that can be played by this many players.
+    #[clap(long)]
+    players: Option<i32>,
+
+    /// The preferred language of the names, as a BCP 47 tag.
+    #[clap(long)]
+    lang: Option<String>,
+
     /// Filter by md5, exact substring.
     #[clap(long)]
     md5: Vec<HexString>,
@@ -329,6 +508,15 @@ impl GamesListOpts {
             year: self.year,
             name: self.name.clone(),
             exact_name: self.exact_name.clone(),
+            region: self.region.clone(),
+            language: self.language.clone(),
+            genre: self.genre.clone(),
+            series: self.series.clone(),
+            publisher: self.publisher.clone(),
+            developer: self.developer.clone(),
+            tag: self.tag.clone(),
+            players: self.players,
+            lang: self.lang.clone(),
         }
     }
     pub fn as_body_dto(&self) -> dto::games::GameListBody {
@@ -366,11 +554,41 @@ pub struct GameCreateOpts {
     #[clap(long)]
     year: u32,
 
+    /// The name of a publisher. Can be repeated.
+    #[clap(long)]
+    publisher: Vec<String>,
+
+    /// The name of a developer. Can be repeated.
     #[clap(long)]
-    publisher: String,
+    developer: Vec<String>,
 
+    /// A release of the game, as the slug of its region with an optional
+    /// date separated by an equal sign (e.g. `japan=1986-02-21`). Can be
+    /// repeated.
     #[clap(long)]
-    developer: String,
+    release: Vec<String>,
+
+    /// A language of the game, as a BCP 47 tag. Can be repeated.
+    #[clap(long)]
+    language: Vec<String>,
+
+    /// The slug of a genre of the game. Can be repeated.
+    #[clap(long)]
+    genre: Vec<String>,
+
+    /// The name of a series the game is part of. Can be repeated.
+    #[clap(long)]
+    series: Vec<String>,
+
+    /// The slug of a tag of the game. Can be repeated.
+    #[clap(long)]
+    tag: Vec<String>,
+
+    #[clap(long)]
+    min_players: Option<i32>,
+
+    #[clap(long)]
+    max_players: Option<i32>,
 
     /// Game's links. This is a key-value pair, separated by an equal sign.
     #[clap(long)]
@@ -393,11 +611,30 @@ impl GameCreateOpts {
             description: &self.description,
             short_description: &self.short_description,
             year: self.year as i32,
-            publisher: &self.publisher,
-            developer: &self.developer,
             links: links_dictionary_from_arg(&self.links).unwrap_or_default(),
             system: self.system.clone(),
             system_unique_id: self.system_unique_id as i32,
+            publishers: self.publisher.iter().map(String::as_str).collect(),
+            developers: self.developer.iter().map(String::as_str).collect(),
+            publisher: None,
+            developer: None,
+            releases: self
+                .release
+                .iter()
+                .map(|r| {
+                    let (region, date) = match r.split_once('=') {
+                        Some((region, date)) => (region, Some(date)),
+                        None => (r.as_str(), None),
+                    };
+                    dto::games::GameReleaseRequest { region, date }
+                })
+                .collect(),
+            languages: self.language.iter().map(String::as_str).collect(),
+            genres: self.genre.iter().map(String::as_str).collect(),
+            series: self.series.iter().map(String::as_str).collect(),
+            tags: self.tag.iter().map(String::as_str).collect(),
+            min_players: self.min_players,
+            max_players: self.max_players,
         }
     }
 }
@@ -413,7 +650,11 @@ pub struct GameAddArtifactOpts {
 
     /// Size of the file.
     #[clap(long)]
-    size: i32,
+    size: i64,
+
+    /// CRC32 checksum of the file, in hexadecimal.
+    #[clap(long)]
+    crc32: Option<HexString>,
 
     /// MD5 checksum of the file, in hexadecimal.
     #[clap(long)]
@@ -426,13 +667,39 @@ pub struct GameAddArtifactOpts {
     /// SHA256 checksum of the file, in hexadecimal.
     #[clap(long)]
     sha256: Option<HexString>,
+
+    /// The name of the file.
+    #[clap(long)]
+    filename: Option<String>,
+
+    /// The directory of the file, relative to the game (e.g. `Disc 1`).
+    #[clap(long)]
+    path: Option<String>,
+
+    /// What the file is used for (`rom`, `cue`, `track`, `bios` or `other`).
+    #[clap(long)]
+    role: Option<dto::games::GameArtifactRole>,
+
+    /// The position of the file among the files of the game.
+    #[clap(long)]
+    order: Option<i32>,
+
+    /// The status of the dump (`baddump`, `nodump`, `good` or `verified`).
+    #[clap(long)]
+    status: Option<dto::games::GameArtifactStatus>,
 }
 
 impl GameAddArtifactOpts {
     pub fn as_dto(&self) -> dto::games::GameAddArtifactRequest<'_> {
         dto::games::GameAddArtifactRequest {
             mime_type: &self.content_type,
+            filename: self.filename.as_deref(),
+            path: self.path.as_deref(),
+            role: self.role,
+            order: self.order,
+            status: self.status,
             size: self.size,
+            crc32: self.crc32.clone(),
             md5: self.md5.clone(),
             sha1: self.sha1.clone(),
             sha256: self.sha256.clone(),
@@ -440,6 +707,16 @@ impl GameAddArtifactOpts {
     }
 }
 
+#[derive(Debug, Parser)]
+pub struct GameIdentifyOpts {
+    /// Only match games of this system. Can be a slug or a numerical id.
+    #[clap(long)]
+    system: Option<IdOrSlug<'static>>,
+
+    /// The files to identify.
+    path: Vec<PathBuf>,
+}
+
 #[derive(Debug, Parser)]
 pub struct GameAddImageOpts {
     /// The game's unique id.
@@ -447,6 +724,72 @@ pub struct GameAddImageOpts {
 
     /// The images' path.
     path: Vec<PathBuf>,
+
+    /// What the images show, e.g. `box-front` or `title-screen`.
+    #[clap(long)]
+    role: Option<dto::images::ImageRole>,
+
+    /// Upload images even if they look like images the game already has.
+    #[clap(long)]
+    allow_duplicates: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct GameTranslateOpts {
+    /// The game's unique id.
+    game: i32,
+
+    /// The language of the translation, as a BCP 47 tag (e.g. `ja` or
+    /// `ja-Latn`).
+    language: String,
+
+    /// The name of the game in this language.
+    #[clap(long)]
+    name: String,
+
+    /// The short description in this language.
+    #[clap(long)]
+    short_description: Option<String>,
+
+    /// The description in this language.
+    #[clap(long)]
+    description: Option<String>,
+}
+
+#[derive(Debug, Parser)]
+pub struct GameArtifactsOpts {
+    /// The game's numerical id.
+    game: i32,
+}
+
+#[derive(Debug, Parser)]
+pub struct GameRelateOpts {
+    /// The game's unique id.
+    game: i32,
+
+    /// How the game relates to the other game (e.g. `hack_of`).
+    kind: dto::games::GameRelationshipKind,
+
+    /// The other game's unique id.
+    other: i32,
+}
+
+#[derive(Debug, Parser)]
+pub struct GameRelatedOpts {
+    /// The game's unique id.
+    game: i32,
+
+    /// Only follow relationships of this kind.
+    #[clap(long)]
+    kind: Option<dto::games::GameRelationshipKind>,
+
+    /// Which relationships to follow (`outgoing`, `incoming` or `both`).
+    #[clap(long)]
+    direction: Option<dto::games::GameRelationshipDirection>,
+
+    /// How many relationships to follow from the game.
+    #[clap(long)]
+    depth: Option<u32>,
 }
 
 #[derive(Debug, Parser)]
@@ -456,6 +799,20 @@ pub struct GameUpdateFromDatOpts {
 
     /// The system's slug or numerical id.
     system: IdOrSlug<'static>,
+
+    /// Only show what the import would do. It can be applied later with
+    /// `apply-import`.
+    #[clap(long)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct GameApplyImportOpts {
+    /// The system's slug or numerical id.
+    system: IdOrSlug<'static>,
+
+    /// The id of the dry run to apply.
+    import: i32,
 }
 
 #[derive(Debug, Parser)]
@@ -474,6 +831,70 @@ pub enum TeamCommand {
 
     /// Create a new team.
     Create(TeamCreateOpts),
+
+    /// Invite a user to a team.
+    Invite(TeamInviteOpts),
+
+    /// Accept an invitation to a team.
+    Accept(TeamRefOpts),
+
+    /// Decline an invitation to a team.
+    Decline(TeamRefOpts),
+
+    /// List the pending invitations to a team.
+    Invitations(TeamRefOpts),
+
+    /// Cancel the invitation of a user to a team.
+    CancelInvitation(TeamMemberOpts),
+
+    /// Change the role of a member of a team.
+    SetRole(TeamSetRoleOpts),
+
+    /// Remove a member from a team.
+    Remove(TeamMemberOpts),
+
+    /// Leave a team.
+    Leave(TeamRefOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct TeamRefOpts {
+    /// The team's slug or numerical id.
+    team: IdOrSlug<'static>,
+}
+
+#[derive(Debug, Parser)]
+pub struct TeamMemberOpts {
+    /// The team's slug or numerical id.
+    team: IdOrSlug<'static>,
+
+    /// The user's username or numerical id.
+    user: UserIdOrUsername<'static>,
+}
+
+#[derive(Debug, Parser)]
+pub struct TeamInviteOpts {
+    /// The team's slug or numerical id.
+    team: IdOrSlug<'static>,
+
+    /// The user's username or numerical id.
+    user: UserIdOrUsername<'static>,
+
+    /// The role of the user in the team (owner, admin or member).
+    #[clap(long, default_value = "member")]
+    role: dto::types::UserTeamRole,
+}
+
+#[derive(Debug, Parser)]
+pub struct TeamSetRoleOpts {
+    /// The team's slug or numerical id.
+    team: IdOrSlug<'static>,
+
+    /// The user's username or numerical id.
+    user: UserIdOrUsername<'static>,
+
+    /// The new role of the user in the team (owner, admin or member).
+    role: dto::types::UserTeamRole,
 }
 
 #[derive(Debug, Parser)]
@@ -589,6 +1010,9 @@ pub enum SystemCommand {
 
     /// Get the details of a system.
     Get(SystemGetOpts),
+
+    /// Export the games of a system as a DAT file, to stdout.
+    ExportDat(SystemExportDatOpts),
 }
 
 #[derive(Debug, Parser)]
@@ -597,6 +1021,25 @@ pub struct SystemGetOpts {
     id: String,
 }
 
+#[derive(Debug, Parser)]
+pub struct SystemExportDatOpts {
+    /// The system's slug or numerical id.
+    id: String,
+
+    /// Only export games with this tag.
+    #[clap(long)]
+    tag: Option<String>,
+
+    /// Only export games released in this range of years (e.g. `1990..2000`
+    /// or `>=1995`).
+    #[clap(long)]
+    year: Option<String>,
+
+    /// Only export games released in this region.
+    #[clap(long)]
+    region: Option<String>,
+}
+
 #[derive(Debug, Parser)]
 pub struct SystemsListOpts {
     #[clap(flatten)]
@@ -679,6 +1122,31 @@ fn output_json<J: Serialize>(value: J, opts: &Opts) -> Result<(), Error> {
     Ok(())
 }
 
+/// Wait until the server is done with a DAT import, logging its progress.
+async fn wait_for_import(
+    client: &dto::client::V1Client,
+    system: &IdOrSlug<'_>,
+    mut import: dto::games::DatImportDetails,
+) -> Result<dto::games::DatImportDetails, Error> {
+    use dto::games::DatImportStatus;
+
+    while matches!(
+        import.status,
+        DatImportStatus::Pending | DatImportStatus::Running
+    ) {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        import = client.systems_imports_details(system, import.id).await?;
+        info!(
+            status = %import.status,
+            processed = import.processed,
+            total = import.total,
+            "Importing DAT"
+        );
+    }
+
+    Ok(import)
+}
+
 fn update_request<B: Serialize>(
     mut request: RequestBuilder,
     opts: &Opts,
@@ -760,7 +1228,7 @@ where
     Q: Serialize,
     R: for<'de> Deserialize<'de>,
 {
-    let response = send_(&client, method, path, opts, request).await?;
+    let response = send_(client, method, path, opts, request).await?;
 
     // Can't use `error_for_status()` as it consumes the response and does not
     // provide the body of the response.
@@ -802,6 +1270,151 @@ fn to_query(paging: &dto::params::PagingParams) -> String {
     }
 }
 
+/// Files larger than this are uploaded in parts.
+const SINGLE_UPLOAD_MAX_SIZE: u64 = 16 * 1024 * 1024;
+
+/// The size of each part when uploading in parts.
+const UPLOAD_PART_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Number of times a part is retried before giving up.
+const UPLOAD_PART_RETRIES: usize = 3;
+
+struct FileChecksums {
+    size: i64,
+    crc32: Vec<u8>,
+    md5: Vec<u8>,
+    sha1: Vec<u8>,
+    sha256: Vec<u8>,
+}
+
+/// The size and checksums of a file, reading it in chunks.
+fn file_checksums(path: &PathBuf) -> Result<FileChecksums, Error> {
+    use sha1::Digest;
+    use std::io::Read;
+
+    let mut size = 0;
+    let mut crc32 = crc32fast::Hasher::new();
+    let mut md5 = md5::Context::new();
+    let mut sha1 = sha1::Sha1::new();
+    let mut sha256 = sha2::Sha256::new();
+
+    let mut file = std::fs::File::open(path)?;
+    let mut buffer = vec![0; 1024 * 1024];
+    loop {
+        let len = file.read(&mut buffer)?;
+        if len == 0 {
+            break;
+        }
+        size += len as i64;
+        crc32.update(&buffer[..len]);
+        md5.consume(&buffer[..len]);
+        sha1.update(&buffer[..len]);
+        sha256.update(&buffer[..len]);
+    }
+
+    Ok(FileChecksums {
+        size,
+        crc32: crc32.finalize().to_be_bytes().to_vec(),
+        md5: md5.compute().0.to_vec(),
+        sha1: sha1.finalize().to_vec(),
+        sha256: sha256.finalize().to_vec(),
+    })
+}
+
+/// Compute the size and checksums of a file and return them as the
+/// `X-Expected-Size` and `Digest` headers, so the server can verify the
+/// artifact it received.
+fn digest_headers(path: &PathBuf) -> Result<reqwest::header::HeaderMap, Error> {
+    use base64::Engine;
+
+    let FileChecksums {
+        size,
+        md5,
+        sha1,
+        sha256,
+        ..
+    } = file_checksums(path)?;
+
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let digest = format!(
+        "MD5={}, SHA={}, SHA-256={}",
+        b64.encode(md5),
+        b64.encode(sha1),
+        b64.encode(sha256)
+    );
+
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert("X-Expected-Size", size.into());
+    headers.insert("Digest", digest.parse()?);
+    Ok(headers)
+}
+
+async fn upload_artifact_in_parts(
+    client: &dto::client::V1Client,
+    core: &IdOrSlug<'_>,
+    release_id: i32,
+    path: &PathBuf,
+) -> Result<dto::artifact::ArtifactCreateResponse, Error> {
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+    let filename = path
+        .file_name()
+        .and_then(|f| f.to_str())
+        .ok_or_else(|| anyhow::anyhow!("Invalid file name: {path:?}"))?;
+    let ext = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+    let mime_type = mime_guess2::from_ext(ext).first_or_octet_stream();
+    let size = std::fs::metadata(path)?.len();
+
+    let mut upload = client
+        .cores_releases_uploads_create(
+            core,
+            release_id,
+            &dto::artifact::ArtifactUploadCreateRequest {
+                filename,
+                mime_type: mime_type.as_ref(),
+                size: size as i64,
+            },
+        )
+        .await?;
+
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut retries = 0;
+    while upload.received < upload.size {
+        let part_size = UPLOAD_PART_SIZE.min((upload.size - upload.received) as u64);
+        let mut data = Vec::with_capacity(part_size as usize);
+        file.seek(std::io::SeekFrom::Start(upload.received as u64))
+            .await?;
+        (&mut file).take(part_size).read_to_end(&mut data).await?;
+
+        debug!(
+            part = upload.next_part,
+            received = upload.received,
+            "Uploading part"
+        );
+        match client
+            .cores_releases_uploads_part(core, release_id, upload.id, upload.next_part, data)
+            .await
+        {
+            Ok(u) => {
+                upload = u;
+                retries = 0;
+            }
+            Err(e) if retries < UPLOAD_PART_RETRIES => {
+                retries += 1;
+                info!(?e, retries, "Part failed, resuming");
+                upload = client
+                    .cores_releases_uploads_details(core, release_id, upload.id)
+                    .await?;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(client
+        .cores_releases_uploads_complete(core, release_id, upload.id, digest_headers(path)?)
+        .await?)
+}
+
 async fn release(opts: &Opts, release_opts: &CoreReleaseOpts) -> Result<(), Error> {
     let core = IdOrSlug::parse(&release_opts.core);
 
@@ -846,12 +1459,24 @@ async fn release(opts: &Opts, release_opts: &CoreReleaseOpts) -> Result<(), Erro
             let release_id = response.id;
             for path in &create_opts.files {
                 info!(?path, "Uploading");
-                output_json(
-                    client
-                        .cores_releases_artifacts_upload(&core, release_id, path)
-                        .await?,
-                    opts,
-                )?;
+                if std::fs::metadata(path)?.len() > SINGLE_UPLOAD_MAX_SIZE {
+                    output_json(
+                        upload_artifact_in_parts(&client, &core, release_id, path).await?,
+                        opts,
+                    )?;
+                } else {
+                    output_json(
+                        client
+                            .cores_releases_artifacts_upload(
+                                &core,
+                                release_id,
+                                digest_headers(path)?,
+                                path,
+                            )
+                            .await?,
+                        opts,
+                    )?;
+                }
             }
             info!("Done.");
 
@@ -915,6 +1540,90 @@ async fn core(opts: &Opts, core_opts: &CoreOpts) -> Result<(), Error> {
         CoreCommand::Update(CoreUpdateOpts {}) => {
             todo!()
         }
+        CoreCommand::RequiredFiles(CoreRequiredFilesOpts { id, release }) => output_json(
+            client(opts)
+                .cores_required_files(
+                    id,
+                    &dto::cores::required_files::CoreRequiredFileListQueryParams {
+                        release: *release,
+                    },
+                )
+                .await?,
+            opts,
+        ),
+        CoreCommand::Require(CoreRequireOpts {
+            id,
+            filename,
+            path,
+            release,
+            optional,
+            description,
+            hash,
+        }) => output_json(
+            client(opts)
+                .cores_required_files_create(
+                    id,
+                    &dto::cores::required_files::CoreRequiredFileCreateRequest {
+                        release: *release,
+                        filename,
+                        path,
+                        optional: *optional,
+                        description,
+                        hashes: hash
+                            .iter()
+                            .map(|h| required_file_hash_from_arg(h))
+                            .collect::<Result<_, _>>()?,
+                    },
+                )
+                .await?,
+            opts,
+        ),
+        CoreCommand::Unrequire(CoreUnrequireOpts { id, file_id }) => output_json(
+            client(opts)
+                .cores_required_files_delete(id, *file_id)
+                .await?,
+            opts,
+        ),
+        CoreCommand::Compatibility(CoreCompatibilityOpts {
+            platform,
+            system,
+            root,
+            path,
+        }) => {
+            let files = path
+                .iter()
+                .map(|p| {
+                    let FileChecksums {
+                        size,
+                        crc32,
+                        md5,
+                        sha1,
+                        sha256,
+                    } = file_checksums(p)?;
+                    let relative = p.strip_prefix(root).map_err(|_| {
+                        anyhow::anyhow!("{} is not in {}", p.display(), root.display())
+                    })?;
+                    Ok(dto::cores::required_files::DeviceFile {
+                        path: relative.to_string_lossy().replace('\\', "/"),
+                        size,
+                        crc32: Some(crc32.into()),
+                        md5: Some(md5.into()),
+                        sha1: Some(sha1.into()),
+                        sha256: Some(sha256.into()),
+                    })
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            output_json(
+                client(opts)
+                    .cores_compatibility(&dto::cores::required_files::CoreCompatibilityRequest {
+                        platform: platform.clone(),
+                        system: system.clone(),
+                        files,
+                    })
+                    .await?,
+                opts,
+            )
+        }
     }
 }
 
@@ -1030,6 +1739,30 @@ async fn system(opts: &Opts, system_opts: &SystemOpts) -> Result<(), Error> {
                 get(&format!("/api/v1/systems/{}", id), opts).await?;
             output_json(response, opts)
         }
+
+        SystemCommand::ExportDat(SystemExportDatOpts {
+            id,
+            tag,
+            year,
+            region,
+        }) => {
+            let client = reqwest::Client::new();
+            let request = update_request(
+                client
+                    .get(
+                        opts.server
+                            .join(&format!("/api/v1/systems/{id}/games.dat"))?,
+                    )
+                    .query(&[("tag", tag), ("year", year), ("region", region)]),
+                opts,
+                None::<()>,
+            )
+            .build()?;
+
+            let response = client.execute(request).await?.bytes().await?.to_vec();
+            std::io::stdout().write_all(&response)?;
+            Ok(())
+        }
     }
 }
 
@@ -1066,6 +1799,42 @@ async fn team(opts: &Opts, team_opts: &TeamOpts) -> Result<(), Error> {
             .await?;
             output_json(response, opts)
         }
+        TeamCommand::Invite(TeamInviteOpts { team, user, role }) => output_json(
+            client(opts)
+                .invite(
+                    team,
+                    &dto::teams::TeamInvite {
+                        user: user.clone(),
+                        role: *role,
+                    },
+                )
+                .await?,
+            opts,
+        ),
+        TeamCommand::Accept(TeamRefOpts { team }) => {
+            output_json(client(opts).invite_accept(team).await?, opts)
+        }
+        TeamCommand::Decline(TeamRefOpts { team }) => {
+            output_json(client(opts).invite_decline(team).await?, opts)
+        }
+        TeamCommand::Invitations(TeamRefOpts { team }) => {
+            output_json(client(opts).teams_invitations(team).await?, opts)
+        }
+        TeamCommand::CancelInvitation(TeamMemberOpts { team, user }) => {
+            output_json(client(opts).invite_cancel(team, user).await?, opts)
+        }
+        TeamCommand::SetRole(TeamSetRoleOpts { team, user, role }) => output_json(
+            client(opts)
+                .teams_members_update(team, user, &dto::teams::TeamMemberUpdate { role: *role })
+                .await?,
+            opts,
+        ),
+        TeamCommand::Remove(TeamMemberOpts { team, user }) => {
+            output_json(client(opts).teams_members_remove(team, user).await?, opts)
+        }
+        TeamCommand::Leave(TeamRefOpts { team }) => {
+            output_json(client(opts).teams_leave(team).await?, opts)
+        }
     }
 }
 
@@ -1087,90 +1856,236 @@ async fn game(opts: &Opts, game_opts: &GamesOpts) -> Result<(), Error> {
                 .await?,
             opts,
         ),
-        GamesCommand::UpdateFromDat(update_opts) => {
-            let dat = datary::read_file(&update_opts.dat).unwrap();
-            let mut i = i32::MAX;
+        GamesCommand::Artifacts(GameArtifactsOpts { game }) => {
+            output_json(client(opts).games_artifacts(*game).await?, opts)
+        }
+        GamesCommand::Identify(GameIdentifyOpts { system, path }) => {
+            let files = path
+                .iter()
+                .map(|p| {
+                    let FileChecksums {
+                        size,
+                        crc32,
+                        md5,
+                        sha1,
+                        sha256,
+                    } = file_checksums(p)?;
+                    Ok(dto::games::GameIdentifyFile {
+                        size,
+                        crc32: Some(crc32.into()),
+                        md5: Some(md5.into()),
+                        sha1: Some(sha1.into()),
+                        sha256: Some(sha256.into()),
+                    })
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            output_json(
+                client(opts)
+                    .games_identify(&dto::games::GameIdentifyRequest {
+                        system: system.clone(),
+                        files,
+                    })
+                    .await?,
+                opts,
+            )
+        }
+        GamesCommand::UpdateFromDat(GameUpdateFromDatOpts {
+            dat,
+            system,
+            dry_run,
+        }) => {
             let client = client(opts);
-            for game in dat.games {
-                let game_id = if let Some(g) = client
-                    .games(
-                        &dto::games::GameListQueryParams {
-                            paging: Default::default(),
-                            system: Some(update_opts.system.clone()),
-                            year: None,
-                            name: None,
-                            exact_name: Some(game.name.clone()),
-                        },
-                        &Default::default(),
-                    )
-                    .await?
-                    .first()
-                {
-                    info!(?game, "Updating game");
-                    g.id
-                } else {
-                    info!(?game, "Creating game");
-                    let game = dto::games::GameCreateRequest {
-                        name: &game.name,
-                        description: &game.description,
-                        short_description: "",
-                        year: game
-                            .year
-                            .and_then(|x| x.parse::<i32>().ok())
-                            .unwrap_or_default(),
-                        publisher: "",
-                        developer: "",
-                        links: Default::default(),
-                        system: update_opts.system.clone(),
-                        system_unique_id: game.id.map(|i| i as i32).unwrap_or_else(|| {
-                            i -= 1;
-                            i
-                        }),
-                    };
-                    let json = client.games_create(&game).await?;
-                    output_json(&json, opts)?;
-                    json.id
-                };
+            let import = client
+                .systems_imports_create(
+                    system,
+                    &dto::games::DatImportQueryParams {
+                        dry_run: Some(*dry_run),
+                    },
+                    std::fs::read(dat)?,
+                )
+                .await?;
+            info!(id = import.id, total = import.total, "Importing DAT");
 
-                for r in game.roms {
-                    // One game at a time.
-                    client
-                        .games_add_artifact(
-                            game_id,
-                            &vec![dto::games::GameAddArtifactRequest {
-                                mime_type: mime_guess2::from_ext(
-                                    PathBuf::from(&r.name)
-                                        .extension()
-                                        .unwrap_or_default()
-                                        .to_str()
-                                        .unwrap(),
-                                )
-                                .first_raw()
-                                .unwrap_or("application/octet-stream"),
-                                size: r.size as i32,
-                                md5: r.md5.map(|x| hex::decode(&x).unwrap().into()),
-                                sha1: r.sha1.map(|x| hex::decode(&x).unwrap().into()),
-                                sha256: Some(vec![].into()),
-                            }],
-                        )
-                        .await?;
-                }
-            }
-            Ok(())
+            output_json(wait_for_import(&client, system, import).await?, opts)
         }
-        GamesCommand::AddImage(GameAddImageOpts { game, path }) => {
+        GamesCommand::ApplyImport(GameApplyImportOpts { system, import }) => {
+            let client = client(opts);
+            let import = client.systems_imports_apply(system, *import).await?;
+
+            output_json(wait_for_import(&client, system, import).await?, opts)
+        }
+        GamesCommand::AddImage(GameAddImageOpts {
+            game,
+            path,
+            role,
+            allow_duplicates,
+        }) => {
             let client = client(opts);
 
             // Make sure the game exists.
-            let _ = client.games_details(*game).await?;
+            let _ = client.games_details(*game, &Default::default()).await?;
             for p in path {
                 let _image = image::open(p)?;
-                let result = client.games_add_image(*game, p).await?;
+                let params = dto::games::GameImageUploadQueryParams {
+                    role: *role,
+                    allow_duplicates: *allow_duplicates,
+                };
+                let result = client.games_add_image(*game, &params, p).await?;
                 output_json(result, opts)?;
             }
             Ok(())
         }
+        GamesCommand::Translate(GameTranslateOpts {
+            game,
+            language,
+            name,
+            short_description,
+            description,
+        }) => {
+            let client = client(opts);
+            let result = client
+                .games_translations_update(
+                    *game,
+                    language,
+                    &dto::games::GameTranslationRequest {
+                        name,
+                        short_description: short_description.as_deref(),
+                        description: description.as_deref(),
+                    },
+                )
+                .await?;
+            output_json(result, opts)
+        }
+        GamesCommand::Relate(GameRelateOpts { game, kind, other }) => {
+            let client = client(opts);
+            let result = client
+                .games_relationships_create(
+                    *game,
+                    &dto::games::GameRelationshipCreateRequest {
+                        kind: *kind,
+                        game: *other,
+                    },
+                )
+                .await?;
+            output_json(result, opts)
+        }
+        GamesCommand::Unrelate(GameRelateOpts { game, kind, other }) => {
+            let client = client(opts);
+            let result = client
+                .games_relationships_delete(*game, *kind, *other)
+                .await?;
+            output_json(result, opts)
+        }
+        GamesCommand::Related(GameRelatedOpts {
+            game,
+            kind,
+            direction,
+            depth,
+        }) => {
+            let client = client(opts);
+            let result = client
+                .games_related(
+                    *game,
+                    &dto::games::GameRelatedQueryParams {
+                        kind: *kind,
+                        direction: *direction,
+                        depth: *depth,
+                    },
+                )
+                .await?;
+            output_json(result, opts)
+        }
+    }
+}
+
+async fn verify(opts: &Opts, verify_opts: &VerifyOpts) -> Result<(), Error> {
+    use base64::Engine;
+    use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+    let client = client(opts);
+    let core = IdOrSlug::parse(&verify_opts.core);
+    let release_id = verify_opts.release_id;
+
+    let server_key: Vec<u8> = match &verify_opts.public_key {
+        Some(key) => base64::engine::general_purpose::STANDARD_NO_PAD
+            .decode(key.trim().trim_end_matches('='))?,
+        None => client.manifests_key().await?.public_key.into(),
+    };
+
+    let manifest = client.cores_releases_manifest(&core, release_id).await?;
+    let signatures = client
+        .cores_releases_manifest_signatures(&core, release_id)
+        .await?;
+    let canonical = manifest.to_canonical_json();
+
+    let mut server_signed = false;
+    let mut signed_by = Vec::new();
+    for signature in signatures {
+        let key = VerifyingKey::try_from(signature.public_key.as_slice())?;
+        let sig = Signature::from_slice(&signature.signature)?;
+        if key.verify(&canonical, &sig).is_err() {
+            return Err(anyhow::anyhow!(
+                "Invalid signature for public key {}",
+                HexString::from(signature.public_key.as_slice())
+            ));
+        }
+
+        match signature.key {
+            None if signature.public_key.as_slice() == server_key.as_slice() => {
+                server_signed = true;
+                signed_by.push("server".to_string());
+            }
+            None => {
+                info!("Ignoring signature from an unknown server key.");
+            }
+            Some(key) => signed_by.push(format!("{} ({})", key.name, key.team.slug)),
+        }
+    }
+
+    if !server_signed {
+        return Err(anyhow::anyhow!("Manifest is not signed by the server key."));
+    }
+    if verify_opts.require_team_signature && signed_by.len() < 2 {
+        return Err(anyhow::anyhow!("Manifest is not signed by the team."));
+    }
+
+    let mut files = Vec::new();
+    for path in &verify_opts.files {
+        let filename = path
+            .file_name()
+            .and_then(|f| f.to_str())
+            .ok_or_else(|| anyhow::anyhow!("Invalid filename: {}", path.display()))?;
+        let artifact = manifest
+            .artifacts
+            .iter()
+            .find(|a| a.filename == filename)
+            .ok_or_else(|| anyhow::anyhow!("{filename} is not part of the release."))?;
+
+        let FileChecksums {
+            size,
+            md5,
+            sha1,
+            sha256,
+            ..
+        } = file_checksums(path)?;
+        if size != artifact.size
+            || md5 != *artifact.md5
+            || sha1 != *artifact.sha1
+            || sha256 != *artifact.sha256
+        {
+            return Err(anyhow::anyhow!("{filename} does not match the manifest."));
+        }
+        files.push(filename.to_string());
     }
+
+    output_json(
+        VerifyOutput {
+            manifest,
+            signed_by,
+            files,
+        },
+        opts,
+    )
 }
 
 async fn login(opts: &Opts, login_opts: &LoginOpts) -> Result<(), Error> {
@@ -1179,7 +2094,7 @@ async fn login(opts: &Opts, login_opts: &LoginOpts) -> Result<(), Error> {
 
     let client = reqwest::Client::builder().cookie_store(true).build()?;
 
-    send::<_, dto::Ok>(
+    let login: dto::auth::LoginResponse = send(
         &client,
         reqwest::Method::POST,
         "/api/v1/login",
@@ -1191,6 +2106,18 @@ async fn login(opts: &Opts, login_opts: &LoginOpts) -> Result<(), Error> {
     )
     .await?;
 
+    if login.two_factor_required {
+        let code = None.or_prompt("Two-factor authentication code (or recovery code): ")?;
+        send::<_, dto::Ok>(
+            &client,
+            reqwest::Method::POST,
+            "/api/v1/login/two-factor",
+            opts,
+            dto::auth::TwoFactorCodeRequest { code: code.trim() },
+        )
+        .await?;
+    }
+
     let response: dto::AuthTokenResponse =
         send(&client, reqwest::Method::POST, "/api/v1/me/token", opts, ()).await?;
 
@@ -1246,12 +2173,13 @@ async fn main() {
         Command::Games(games_opts) => game(&opts, games_opts).await,
         Command::Login(login_opts) => login(&opts, login_opts).await,
         Command::Signup(login_opts) => signup(&opts, login_opts).await,
+        Command::Verify(verify_opts) => verify(&opts, verify_opts).await,
     };
 
     match result {
         Ok(()) => {}
         Err(e) => {
-            std::io::stderr().write_fmt(format_args!("{}", e)).unwrap();
+            eprint!("{}", e);
             std::process::exit(1);
         }
     }
@@ -0,0 +1,7 @@
+DROP TABLE core_release_uploads;
+
+ALTER TABLE teams
+    DROP COLUMN upload_size_limit;
+
+ALTER TABLE artifacts
+    ALTER COLUMN size TYPE INTEGER;
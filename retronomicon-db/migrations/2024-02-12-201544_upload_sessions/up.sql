@@ -0,0 +1,32 @@
+-- Artifacts can be larger than 2GB (e.g. disc images).
+ALTER TABLE artifacts
+    ALTER COLUMN size TYPE BIGINT;
+
+-- Maximum size of a single artifact uploaded by members of a team. NULL
+-- means the server default.
+ALTER TABLE teams
+    ADD COLUMN upload_size_limit BIGINT;
+
+-- In-progress uploads of core release artifacts. The file is uploaded
+-- in parts, then finalized into an artifact.
+CREATE TABLE core_release_uploads
+(
+    id                SERIAL PRIMARY KEY,
+    core_release_id   INTEGER      NOT NULL REFERENCES core_releases (id) ON DELETE CASCADE,
+    user_id           INTEGER      NOT NULL REFERENCES users (id),
+    filename          VARCHAR(255) NOT NULL,
+    mime_type         VARCHAR(255) NOT NULL,
+    -- The total size of the file, declared when creating the upload.
+    size              BIGINT       NOT NULL,
+    -- The number of bytes received so far.
+    received          BIGINT       NOT NULL DEFAULT 0,
+    -- The path of the file in the storage, and the ID of the multipart
+    -- upload in the storage backend.
+    storage_path      VARCHAR      NOT NULL,
+    storage_upload_id VARCHAR      NOT NULL,
+    -- Parts uploaded so far, in order.
+    parts             JSONB        NOT NULL DEFAULT '[]',
+    created_at        TIMESTAMP    NOT NULL DEFAULT NOW(),
+    updated_at        TIMESTAMP    NOT NULL DEFAULT NOW(),
+    UNIQUE (core_release_id, filename)
+);
@@ -0,0 +1,22 @@
+-- Duplicates collapsed into a single blob are not split again.
+CREATE TABLE artifact_files
+(
+    id   SERIAL PRIMARY KEY NOT NULL REFERENCES artifacts (id),
+    data bytea              NOT NULL
+);
+
+INSERT INTO artifact_files (id, data)
+SELECT artifacts.id, files.data
+FROM files
+         INNER JOIN artifacts ON artifacts.blob_id = files.id;
+
+DROP TABLE files;
+ALTER TABLE artifact_files RENAME TO files;
+ALTER INDEX artifact_files_pkey RENAME TO files_pkey;
+ALTER SEQUENCE artifact_files_id_seq RENAME TO files_id_seq;
+
+ALTER TABLE artifacts
+    DROP COLUMN blob_id;
+
+DROP TABLE storage_deletions;
+DROP TABLE blobs;
@@ -0,0 +1,111 @@
+-- Content-addressed storage of artifacts. Artifacts with the same content
+-- (e.g. the same binary attached to multiple releases) share a single blob,
+-- which is only stored once.
+CREATE TABLE blobs
+(
+    id           SERIAL PRIMARY KEY,
+    sha256       BYTEA        NOT NULL UNIQUE,
+    md5          BYTEA        NOT NULL,
+    sha1         BYTEA        NOT NULL,
+    size         BIGINT       NOT NULL,
+    -- The path of the object in the cores bucket. NULL if the object is not
+    -- managed by the storage (e.g. it predates blobs, or lives in `files`).
+    storage_path VARCHAR,
+    download_url VARCHAR(255),
+    created_at   TIMESTAMP    NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE artifacts
+    ADD COLUMN blob_id INTEGER REFERENCES blobs (id);
+
+CREATE INDEX artifacts_blob_id_idx ON artifacts (blob_id);
+
+-- Objects to delete from the storage. Deletions are recorded along with the
+-- rows that used the objects, and the objects are deleted afterward. Rows
+-- are only removed once their object was deleted, so failures are retried.
+CREATE TABLE storage_deletions
+(
+    id         SERIAL PRIMARY KEY,
+    bucket     VARCHAR(32)  NOT NULL,
+    path       VARCHAR      NOT NULL,
+    attempts   INTEGER      NOT NULL DEFAULT 0,
+    last_error TEXT,
+    created_at TIMESTAMP    NOT NULL DEFAULT NOW()
+);
+
+-- The objects of existing artifacts, in the cores bucket. Artifacts were
+-- stored at `<core>/<version>/<filename>`, which their URL ends with.
+CREATE TEMPORARY TABLE artifact_paths AS
+SELECT artifacts.id AS artifact_id,
+       cores.slug || '/' || core_releases.version || '/' || artifacts.filename AS path
+FROM artifacts
+         INNER JOIN core_release_artifacts ON core_release_artifacts.artifact_id = artifacts.id
+         INNER JOIN core_releases ON core_releases.id = core_release_artifacts.core_release_id
+         INNER JOIN cores ON cores.id = core_releases.core_id
+WHERE artifacts.download_url LIKE '%/' || cores.slug || '/' || core_releases.version || '/' || artifacts.filename;
+
+-- Collapse existing duplicates. Every distinct content that we have (either
+-- through a download URL or in `files`) becomes a blob, keeping the oldest
+-- artifact's object.
+INSERT INTO blobs (sha256, md5, sha1, size, download_url, created_at)
+SELECT DISTINCT ON (sha256) sha256, md5, sha1, size, download_url, created_at
+FROM artifacts
+WHERE octet_length(sha256) = 32
+  AND (download_url IS NOT NULL OR EXISTS (SELECT 1 FROM files WHERE files.id = artifacts.id))
+ORDER BY sha256, download_url IS NULL, id;
+
+UPDATE artifacts
+SET blob_id = blobs.id
+FROM blobs
+WHERE artifacts.sha256 = blobs.sha256;
+
+-- Blobs are stored in the object of the artifact whose URL they kept, so the
+-- garbage collection deletes it once the blob is unused.
+UPDATE blobs
+SET storage_path = artifact_paths.path
+FROM artifacts
+         INNER JOIN artifact_paths ON artifact_paths.artifact_id = artifacts.id
+WHERE artifacts.blob_id = blobs.id
+  AND artifacts.download_url = blobs.download_url;
+
+-- The objects of the other artifacts are duplicates, which nothing refers to
+-- once the artifacts point to the shared object.
+INSERT
+INTO storage_deletions (bucket, path)
+SELECT DISTINCT 'cores', artifact_paths.path
+FROM artifacts
+         INNER JOIN artifact_paths ON artifact_paths.artifact_id = artifacts.id
+         INNER JOIN blobs ON blobs.id = artifacts.blob_id
+WHERE artifacts.download_url <> blobs.download_url
+  AND NOT EXISTS (SELECT 1 FROM blobs b WHERE b.download_url = artifacts.download_url);
+
+DROP TABLE artifact_paths;
+
+-- Artifacts that were stored point to the shared object.
+UPDATE artifacts
+SET download_url = blobs.download_url
+FROM blobs
+WHERE artifacts.blob_id = blobs.id
+  AND artifacts.download_url IS NOT NULL
+  AND blobs.download_url IS NOT NULL;
+
+-- Raw file contents are stored once per blob.
+CREATE TABLE blob_files
+(
+    id   INTEGER PRIMARY KEY REFERENCES blobs (id) ON DELETE CASCADE,
+    data BYTEA NOT NULL
+);
+
+INSERT INTO blob_files (id, data)
+SELECT DISTINCT ON (artifacts.blob_id) artifacts.blob_id, files.data
+FROM files
+         INNER JOIN artifacts ON artifacts.id = files.id
+WHERE artifacts.blob_id IS NOT NULL
+ORDER BY artifacts.blob_id, files.id;
+
+DROP TABLE files;
+ALTER TABLE blob_files RENAME TO files;
+ALTER INDEX blob_files_pkey RENAME TO files_pkey;
+
+COMMENT
+ON TABLE files IS 'Binary files for storage.';
@@ -0,0 +1,2 @@
+DROP TABLE core_release_signatures;
+DROP TABLE team_signing_keys;
@@ -0,0 +1,23 @@
+-- Public keys teams use to sign the manifests of their releases.
+CREATE TABLE team_signing_keys
+(
+    id         SERIAL PRIMARY KEY,
+    team_id    INTEGER      NOT NULL REFERENCES teams (id) ON DELETE CASCADE,
+    name       VARCHAR(255) NOT NULL,
+    public_key BYTEA        NOT NULL UNIQUE,
+    created_at TIMESTAMP    NOT NULL DEFAULT NOW(),
+    revoked_at TIMESTAMP
+);
+
+CREATE INDEX team_signing_keys_team_id_idx ON team_signing_keys (team_id);
+
+-- Detached signatures of release manifests, made with a team key.
+CREATE TABLE core_release_signatures
+(
+    core_release_id     INTEGER   NOT NULL REFERENCES core_releases (id) ON DELETE CASCADE,
+    team_signing_key_id INTEGER   NOT NULL REFERENCES team_signing_keys (id) ON DELETE CASCADE,
+    manifest_sha256     BYTEA     NOT NULL,
+    signature           BYTEA     NOT NULL,
+    created_at          TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (core_release_id, team_signing_key_id)
+);
@@ -0,0 +1,14 @@
+-- Personal access tokens. Only a hash of the token is stored.
+CREATE TABLE user_tokens
+(
+    id           SERIAL PRIMARY KEY,
+    user_id      INTEGER      NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    name         VARCHAR(255) NOT NULL,
+    token_hash   BYTEA        NOT NULL UNIQUE,
+    scopes       JSONB        NOT NULL DEFAULT '[]',
+    created_at   TIMESTAMP    NOT NULL DEFAULT NOW(),
+    expires_at   TIMESTAMP    NOT NULL,
+    last_used_at TIMESTAMP
+);
+
+CREATE INDEX user_tokens_user_id_idx ON user_tokens (user_id);
@@ -0,0 +1,4 @@
+DROP TABLE password_reset_tokens;
+
+ALTER TABLE users
+    DROP COLUMN sessions_invalidated_at;
@@ -0,0 +1,12 @@
+-- Sessions and JWTs issued before this time are rejected.
+ALTER TABLE users
+    ADD COLUMN sessions_invalidated_at TIMESTAMP;
+
+-- A pending "forgot password" request. Only a hash of the token is stored.
+CREATE TABLE password_reset_tokens
+(
+    user_id    INTEGER PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    token_hash BYTEA     NOT NULL UNIQUE,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1,24 @@
+-- The TOTP secret of a user. Two-factor authentication is only enabled once
+-- the secret is confirmed with a valid code.
+CREATE TABLE user_two_factor
+(
+    user_id         INTEGER PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    secret          BYTEA     NOT NULL,
+    created_at      TIMESTAMP NOT NULL DEFAULT NOW(),
+    confirmed_at    TIMESTAMP,
+    -- The last time step a code was accepted for, to prevent replays.
+    last_used_step  BIGINT,
+    failed_attempts INTEGER   NOT NULL DEFAULT 0,
+    last_failed_at  TIMESTAMP
+);
+
+-- One-time recovery codes, usable instead of a TOTP code. Only a hash of
+-- each code is stored.
+CREATE TABLE user_recovery_codes
+(
+    id        SERIAL PRIMARY KEY,
+    user_id   INTEGER   NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    code_hash BYTEA     NOT NULL,
+    used_at   TIMESTAMP,
+    UNIQUE (user_id, code_hash)
+);
@@ -0,0 +1,17 @@
+-- Server-side sessions of users logged in from a browser. The auth cookie
+-- only contains a random key whose SHA256 is stored here, so sessions can be
+-- listed and revoked.
+CREATE TABLE user_sessions
+(
+    id           SERIAL PRIMARY KEY,
+    user_id      INTEGER     NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    key_hash     BYTEA       NOT NULL UNIQUE,
+    device       VARCHAR(255),
+    user_agent   TEXT,
+    ip_address   VARCHAR(64),
+    created_at   TIMESTAMP   NOT NULL DEFAULT NOW(),
+    last_seen_at TIMESTAMP   NOT NULL DEFAULT NOW(),
+    expires_at   TIMESTAMP   NOT NULL
+);
+
+CREATE INDEX user_sessions_user_id_idx ON user_sessions (user_id);
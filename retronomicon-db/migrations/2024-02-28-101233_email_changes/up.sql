@@ -0,0 +1,10 @@
+-- A pending change of email. The new email is only set on the user once the
+-- link sent to it is followed. Only a hash of the token is stored.
+CREATE TABLE email_changes
+(
+    user_id    INTEGER PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    new_email  VARCHAR(255) NOT NULL,
+    token_hash BYTEA        NOT NULL UNIQUE,
+    created_at TIMESTAMP    NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMP    NOT NULL
+);
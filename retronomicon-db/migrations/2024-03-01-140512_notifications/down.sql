@@ -0,0 +1,3 @@
+DROP TABLE core_followers;
+DROP TABLE notification_preferences;
+DROP TABLE notifications;
@@ -0,0 +1,32 @@
+-- Events users are told about, such as invitations to a team.
+CREATE TABLE notifications
+(
+    id         SERIAL PRIMARY KEY,
+    user_id    INTEGER     NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    kind       VARCHAR(64) NOT NULL,
+    data       JSONB       NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP   NOT NULL DEFAULT NOW(),
+    read_at    TIMESTAMP
+);
+
+CREATE INDEX notifications_user_id_idx ON notifications (user_id, created_at);
+
+-- Which channels users want to be notified through, per kind of
+-- notification. Missing rows mean every channel is enabled.
+CREATE TABLE notification_preferences
+(
+    user_id INTEGER     NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    kind    VARCHAR(64) NOT NULL,
+    in_app  BOOLEAN     NOT NULL DEFAULT TRUE,
+    email   BOOLEAN     NOT NULL DEFAULT TRUE,
+    PRIMARY KEY (user_id, kind)
+);
+
+-- Users following the releases of a core.
+CREATE TABLE core_followers
+(
+    core_id    INTEGER   NOT NULL REFERENCES cores (id) ON DELETE CASCADE,
+    user_id    INTEGER   NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (core_id, user_id)
+);
@@ -0,0 +1,2 @@
+DROP TABLE core_collaborators;
+DROP TABLE team_permissions;
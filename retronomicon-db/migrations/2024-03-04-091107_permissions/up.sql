@@ -0,0 +1,24 @@
+-- Per-team overrides of the permissions granted to a role. Missing rows mean
+-- the role has its default permissions. Owners always have every permission.
+CREATE TABLE team_permissions
+(
+    team_id    INTEGER        NOT NULL REFERENCES teams (id) ON DELETE CASCADE,
+    role       user_team_role NOT NULL,
+    permission VARCHAR(64)    NOT NULL,
+    granted    BOOLEAN        NOT NULL,
+    PRIMARY KEY (team_id, role, permission)
+);
+
+-- Permissions on a core granted to users, usually from outside the team
+-- owning the core.
+CREATE TABLE core_collaborators
+(
+    core_id       INTEGER     NOT NULL REFERENCES cores (id) ON DELETE CASCADE,
+    user_id       INTEGER     NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    permission    VARCHAR(64) NOT NULL,
+    granted_by_id INTEGER     REFERENCES users (id) ON DELETE SET NULL,
+    created_at    TIMESTAMP   NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (core_id, user_id, permission)
+);
+
+CREATE INDEX core_collaborators_user_id_idx ON core_collaborators (user_id);
@@ -0,0 +1,6 @@
+DROP INDEX game_artifacts_artifact_id_idx;
+DROP INDEX artifacts_crc32_size_idx;
+DROP INDEX artifacts_sha256_idx;
+DROP INDEX artifacts_sha1_idx;
+DROP INDEX artifacts_md5_idx;
+ALTER TABLE artifacts DROP COLUMN crc32;
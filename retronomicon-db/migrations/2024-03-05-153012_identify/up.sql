@@ -0,0 +1,13 @@
+-- CRC32 of artifacts, as found in DAT files. Empty if unknown, like other
+-- checksums.
+ALTER TABLE artifacts ADD COLUMN crc32 bytea NOT NULL DEFAULT E''::bytea;
+
+-- Indexes used to identify files by their checksums.
+CREATE INDEX artifacts_md5_idx ON artifacts (md5);
+CREATE INDEX artifacts_sha1_idx ON artifacts (sha1);
+CREATE INDEX artifacts_sha256_idx ON artifacts (sha256);
+CREATE INDEX artifacts_crc32_size_idx ON artifacts (crc32, size);
+
+-- The primary key of game_artifacts starts with game_id, which does not
+-- help finding the games of an artifact.
+CREATE INDEX game_artifacts_artifact_id_idx ON game_artifacts (artifact_id);
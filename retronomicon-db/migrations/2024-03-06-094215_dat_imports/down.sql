@@ -0,0 +1,2 @@
+ALTER TABLE games DROP COLUMN dat_import_id;
+DROP TABLE dat_imports;
@@ -0,0 +1,31 @@
+-- DAT files uploaded to create or update the games of a system. They are
+-- processed by a background job, which first computes a preview of the
+-- changes, then applies them unless this is a dry run.
+CREATE TABLE dat_imports
+(
+    id          SERIAL PRIMARY KEY,
+    system_id   INTEGER     NOT NULL REFERENCES systems (id) ON DELETE CASCADE,
+    user_id     INTEGER     REFERENCES users (id) ON DELETE SET NULL,
+    -- The name and version from the header of the DAT.
+    dat_name    VARCHAR     NOT NULL,
+    dat_version VARCHAR     NOT NULL,
+    -- The DAT file itself, removed once it was imported.
+    dat         BYTEA,
+    dry_run     BOOLEAN     NOT NULL DEFAULT FALSE,
+    status      VARCHAR(32) NOT NULL DEFAULT 'pending',
+    -- The games to create, update or leave orphaned.
+    preview     JSONB,
+    -- The number of games in the DAT, and how many were imported so far.
+    total       INTEGER     NOT NULL DEFAULT 0,
+    processed   INTEGER     NOT NULL DEFAULT 0,
+    error       VARCHAR,
+    created_at  TIMESTAMP   NOT NULL DEFAULT NOW(),
+    updated_at  TIMESTAMP   NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMP
+);
+
+CREATE INDEX dat_imports_system_id_idx ON dat_imports (system_id, created_at);
+
+-- The last DAT import that created or confirmed a game.
+ALTER TABLE games
+    ADD COLUMN dat_import_id INTEGER REFERENCES dat_imports (id) ON DELETE SET NULL;
@@ -0,0 +1,11 @@
+DROP INDEX platforms_name_trgm_idx;
+DROP INDEX systems_name_trgm_idx;
+DROP INDEX cores_name_trgm_idx;
+DROP INDEX games_name_trgm_idx;
+
+ALTER TABLE platforms DROP COLUMN search_document;
+ALTER TABLE systems DROP COLUMN search_document;
+ALTER TABLE cores DROP COLUMN search_document;
+ALTER TABLE games DROP COLUMN search_document;
+
+DROP EXTENSION IF EXISTS pg_trgm;
@@ -0,0 +1,40 @@
+-- Trigrams are used to find names with typos.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- Documents for full-text search. Names weigh more than publishers and
+-- developers, which weigh more than descriptions. These columns are only
+-- used by raw search queries, so they are not in the diesel schema.
+ALTER TABLE games
+    ADD COLUMN search_document tsvector NOT NULL GENERATED ALWAYS AS (
+        setweight(to_tsvector('simple', name), 'A') ||
+        setweight(to_tsvector('simple', publisher || ' ' || developer), 'B') ||
+        setweight(to_tsvector('simple', description), 'C')
+    ) STORED;
+ALTER TABLE cores
+    ADD COLUMN search_document tsvector NOT NULL GENERATED ALWAYS AS (
+        setweight(to_tsvector('simple', name), 'A') ||
+        setweight(to_tsvector('simple', slug), 'B') ||
+        setweight(to_tsvector('simple', description), 'C')
+    ) STORED;
+ALTER TABLE systems
+    ADD COLUMN search_document tsvector NOT NULL GENERATED ALWAYS AS (
+        setweight(to_tsvector('simple', name), 'A') ||
+        setweight(to_tsvector('simple', slug || ' ' || manufacturer), 'B') ||
+        setweight(to_tsvector('simple', description), 'C')
+    ) STORED;
+ALTER TABLE platforms
+    ADD COLUMN search_document tsvector NOT NULL GENERATED ALWAYS AS (
+        setweight(to_tsvector('simple', name), 'A') ||
+        setweight(to_tsvector('simple', slug), 'B') ||
+        setweight(to_tsvector('simple', description), 'C')
+    ) STORED;
+
+CREATE INDEX games_search_document_idx ON games USING GIN (search_document);
+CREATE INDEX cores_search_document_idx ON cores USING GIN (search_document);
+CREATE INDEX systems_search_document_idx ON systems USING GIN (search_document);
+CREATE INDEX platforms_search_document_idx ON platforms USING GIN (search_document);
+
+CREATE INDEX games_name_trgm_idx ON games USING GIN (name gin_trgm_ops);
+CREATE INDEX cores_name_trgm_idx ON cores USING GIN (name gin_trgm_ops);
+CREATE INDEX systems_name_trgm_idx ON systems USING GIN (name gin_trgm_ops);
+CREATE INDEX platforms_name_trgm_idx ON platforms USING GIN (name gin_trgm_ops);
@@ -0,0 +1,41 @@
+ALTER TABLE games
+    DROP COLUMN search_document,
+    ADD COLUMN publisher VARCHAR NOT NULL DEFAULT '',
+    ADD COLUMN developer VARCHAR NOT NULL DEFAULT '';
+
+UPDATE games
+SET publisher = COALESCE((SELECT STRING_AGG(companies.name, ', ' ORDER BY companies.name)
+                          FROM game_companies
+                                   INNER JOIN companies ON companies.id = game_companies.company_id
+                          WHERE game_companies.game_id = games.id
+                            AND game_companies.role = 'publisher'), ''),
+    developer = COALESCE((SELECT STRING_AGG(companies.name, ', ' ORDER BY companies.name)
+                          FROM game_companies
+                                   INNER JOIN companies ON companies.id = game_companies.company_id
+                          WHERE game_companies.game_id = games.id
+                            AND game_companies.role = 'developer'), '');
+
+ALTER TABLE games
+    ALTER COLUMN publisher DROP DEFAULT,
+    ALTER COLUMN developer DROP DEFAULT,
+    DROP COLUMN min_players,
+    DROP COLUMN max_players;
+
+ALTER TABLE games
+    ADD COLUMN search_document tsvector NOT NULL GENERATED ALWAYS AS (
+        setweight(to_tsvector('simple', name), 'A') ||
+        setweight(to_tsvector('simple', publisher || ' ' || developer), 'B') ||
+        setweight(to_tsvector('simple', description), 'C')
+    ) STORED;
+
+CREATE INDEX games_search_document_idx ON games USING GIN (search_document);
+
+DROP TABLE game_series;
+DROP TABLE series;
+DROP TABLE game_genres;
+DROP TABLE genres;
+DROP TABLE game_languages;
+DROP TABLE game_releases;
+DROP TABLE regions;
+DROP TABLE game_companies;
+DROP TABLE companies;
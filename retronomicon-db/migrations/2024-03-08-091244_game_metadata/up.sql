@@ -0,0 +1,165 @@
+-- Companies publishing or developing games.
+CREATE TABLE companies
+(
+    id              SERIAL PRIMARY KEY,
+    slug            VARCHAR  NOT NULL UNIQUE,
+    name            VARCHAR  NOT NULL,
+    -- Used to search games by their publishers and developers.
+    search_document tsvector NOT NULL GENERATED ALWAYS AS (to_tsvector('simple', name)) STORED
+);
+
+CREATE INDEX companies_search_document_idx ON companies USING GIN (search_document);
+
+CREATE TABLE game_companies
+(
+    game_id    INTEGER     NOT NULL REFERENCES games (id) ON DELETE CASCADE,
+    company_id INTEGER     NOT NULL REFERENCES companies (id) ON DELETE CASCADE,
+    -- Either 'publisher' or 'developer'.
+    role       VARCHAR(32) NOT NULL,
+    PRIMARY KEY (game_id, company_id, role)
+);
+
+CREATE INDEX game_companies_company_id_idx ON game_companies (company_id, role);
+
+-- Regions games are released in, as used by DATs.
+CREATE TABLE regions
+(
+    id   SERIAL PRIMARY KEY,
+    slug VARCHAR(255) NOT NULL UNIQUE,
+    name VARCHAR(255) NOT NULL
+);
+
+INSERT INTO regions (slug, name)
+VALUES ('world', 'World'),
+       ('usa', 'USA'),
+       ('europe', 'Europe'),
+       ('japan', 'Japan'),
+       ('asia', 'Asia'),
+       ('australia', 'Australia'),
+       ('brazil', 'Brazil'),
+       ('canada', 'Canada'),
+       ('china', 'China'),
+       ('france', 'France'),
+       ('germany', 'Germany'),
+       ('hong-kong', 'Hong Kong'),
+       ('italy', 'Italy'),
+       ('korea', 'Korea'),
+       ('netherlands', 'Netherlands'),
+       ('spain', 'Spain'),
+       ('sweden', 'Sweden'),
+       ('taiwan', 'Taiwan'),
+       ('uk', 'United Kingdom');
+
+-- The release of a game in a region, with its date if known.
+CREATE TABLE game_releases
+(
+    game_id      INTEGER NOT NULL REFERENCES games (id) ON DELETE CASCADE,
+    region_id    INTEGER NOT NULL REFERENCES regions (id) ON DELETE CASCADE,
+    release_date DATE,
+    PRIMARY KEY (game_id, region_id)
+);
+
+CREATE INDEX game_releases_region_id_idx ON game_releases (region_id);
+
+-- Languages supported by games, as BCP 47 tags.
+CREATE TABLE game_languages
+(
+    game_id  INTEGER     NOT NULL REFERENCES games (id) ON DELETE CASCADE,
+    language VARCHAR(35) NOT NULL,
+    PRIMARY KEY (game_id, language)
+);
+
+CREATE INDEX game_languages_language_idx ON game_languages (language);
+
+CREATE TABLE genres
+(
+    id   SERIAL PRIMARY KEY,
+    slug VARCHAR(255) NOT NULL UNIQUE,
+    name VARCHAR(255) NOT NULL
+);
+
+INSERT INTO genres (slug, name)
+VALUES ('action', 'Action'),
+       ('adventure', 'Adventure'),
+       ('beat-em-up', 'Beat ''em up'),
+       ('fighting', 'Fighting'),
+       ('platformer', 'Platformer'),
+       ('puzzle', 'Puzzle'),
+       ('racing', 'Racing'),
+       ('role-playing', 'Role-playing'),
+       ('shooter', 'Shooter'),
+       ('simulation', 'Simulation'),
+       ('sports', 'Sports'),
+       ('strategy', 'Strategy');
+
+CREATE TABLE game_genres
+(
+    game_id  INTEGER NOT NULL REFERENCES games (id) ON DELETE CASCADE,
+    genre_id INTEGER NOT NULL REFERENCES genres (id) ON DELETE CASCADE,
+    PRIMARY KEY (game_id, genre_id)
+);
+
+CREATE INDEX game_genres_genre_id_idx ON game_genres (genre_id);
+
+-- Series (or franchises) of games.
+CREATE TABLE series
+(
+    id   SERIAL PRIMARY KEY,
+    slug VARCHAR(255) NOT NULL UNIQUE,
+    name VARCHAR(255) NOT NULL
+);
+
+CREATE TABLE game_series
+(
+    game_id   INTEGER NOT NULL REFERENCES games (id) ON DELETE CASCADE,
+    series_id INTEGER NOT NULL REFERENCES series (id) ON DELETE CASCADE,
+    PRIMARY KEY (game_id, series_id)
+);
+
+CREATE INDEX game_series_series_id_idx ON game_series (series_id);
+
+-- The number of players a game supports, if known.
+ALTER TABLE games
+    ADD COLUMN min_players INTEGER CHECK (min_players >= 1),
+    ADD COLUMN max_players INTEGER CHECK (max_players >= min_players);
+
+-- Move publishers and developers to companies. Names with the same slug are
+-- the same company. Names without ASCII letters or digits are their own slug.
+CREATE TEMPORARY TABLE game_company_names AS
+SELECT game_id,
+       role,
+       name,
+       COALESCE(NULLIF(TRIM(BOTH '-' FROM REGEXP_REPLACE(LOWER(name), '[^a-z0-9]+', '-', 'g')), ''),
+                LOWER(name)) AS slug
+FROM (SELECT id AS game_id, 'publisher' AS role, TRIM(publisher) AS name
+      FROM games
+      UNION ALL
+      SELECT id, 'developer', TRIM(developer)
+      FROM games) AS names
+WHERE name <> '';
+
+INSERT INTO companies (slug, name)
+SELECT slug, MIN(name)
+FROM game_company_names
+GROUP BY slug;
+
+INSERT INTO game_companies (game_id, company_id, role)
+SELECT DISTINCT game_company_names.game_id, companies.id, game_company_names.role
+FROM game_company_names
+         INNER JOIN companies ON companies.slug = game_company_names.slug;
+
+DROP TABLE game_company_names;
+
+-- The search document of games depends on their publisher and developer.
+ALTER TABLE games
+    DROP COLUMN search_document,
+    DROP COLUMN publisher,
+    DROP COLUMN developer;
+
+ALTER TABLE games
+    ADD COLUMN search_document tsvector NOT NULL GENERATED ALWAYS AS (
+        setweight(to_tsvector('simple', name), 'A') ||
+        setweight(to_tsvector('simple', description), 'C')
+    ) STORED;
+
+CREATE INDEX games_search_document_idx ON games USING GIN (search_document);
@@ -0,0 +1,20 @@
+-- Localised names and descriptions of games, by BCP 47 language tag (e.g.
+-- `ja` for the Japanese title and `ja-Latn` for its romanisation). Missing
+-- descriptions fall back to the ones of the game.
+CREATE TABLE game_translations
+(
+    game_id           INTEGER      NOT NULL REFERENCES games (id) ON DELETE CASCADE,
+    language          VARCHAR(35)  NOT NULL,
+    name              VARCHAR(255) NOT NULL,
+    short_description VARCHAR(255),
+    description       VARCHAR,
+    -- Used to search games by any of their localised titles.
+    search_document   tsvector     NOT NULL GENERATED ALWAYS AS (
+        setweight(to_tsvector('simple', name), 'A') ||
+        setweight(to_tsvector('simple', coalesce(description, '')), 'C')
+    ) STORED,
+    PRIMARY KEY (game_id, language)
+);
+
+CREATE INDEX game_translations_search_document_idx ON game_translations USING GIN (search_document);
+CREATE INDEX game_translations_name_trgm_idx ON game_translations USING GIN (name gin_trgm_ops);
@@ -0,0 +1,9 @@
+DELETE
+FROM tags
+WHERE slug IN ('box-front', 'box-back', 'cartridge', 'disc', 'manual', 'title-screen', 'in-game', 'logo',
+               'artwork');
+
+DROP TABLE game_image_derivatives;
+
+ALTER TABLE game_images
+    DROP COLUMN perceptual_hash;
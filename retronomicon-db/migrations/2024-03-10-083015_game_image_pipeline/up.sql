@@ -0,0 +1,29 @@
+-- A difference hash (dHash) of each image, to detect duplicates. Images
+-- uploaded before hashes were computed have none.
+ALTER TABLE game_images
+    ADD COLUMN perceptual_hash BIGINT;
+
+-- Resized and re-encoded versions of game images (thumbnails, WebP).
+CREATE TABLE game_image_derivatives
+(
+    id            SERIAL PRIMARY KEY,
+    game_image_id INTEGER      NOT NULL REFERENCES game_images (id) ON DELETE CASCADE,
+    width         INTEGER      NOT NULL,
+    height        INTEGER      NOT NULL,
+    mime_type     VARCHAR(255) NOT NULL,
+    url           VARCHAR      NOT NULL,
+    UNIQUE (game_image_id, width, height, mime_type)
+);
+
+-- The roles of game images are tags.
+INSERT INTO tags (slug, description, color)
+VALUES ('box-front', 'The front of the box of a game.', 15844367),
+       ('box-back', 'The back of the box of a game.', 12436423),
+       ('cartridge', 'The cartridge of a game.', 3447003),
+       ('disc', 'The disc of a game.', 10181046),
+       ('manual', 'A page of the manual of a game.', 9807270),
+       ('title-screen', 'The title screen of a game.', 15105570),
+       ('in-game', 'A screenshot of a game being played.', 3066993),
+       ('logo', 'The logo of a game.', 15277667),
+       ('artwork', 'Promotional artwork of a game.', 1752220)
+ON CONFLICT (slug) DO NOTHING;
@@ -0,0 +1,15 @@
+-- How games relate to each other. A row reads "game <kind> related game",
+-- e.g. a fan translation is the `translation_of` the original game, and a
+-- compilation `contains` each of its games.
+CREATE TABLE game_relationships
+(
+    game_id         INTEGER     NOT NULL REFERENCES games (id) ON DELETE CASCADE,
+    -- One of 'revision_of', 'translation_of', 'hack_of', 'contains' or
+    -- 'sequel_of'.
+    kind            VARCHAR(32) NOT NULL,
+    related_game_id INTEGER     NOT NULL REFERENCES games (id) ON DELETE CASCADE,
+    PRIMARY KEY (game_id, kind, related_game_id),
+    CHECK (game_id <> related_game_id)
+);
+
+CREATE INDEX game_relationships_related_game_id_idx ON game_relationships (related_game_id, kind);
@@ -0,0 +1,6 @@
+ALTER TABLE game_artifacts
+    DROP COLUMN filename,
+    DROP COLUMN path,
+    DROP COLUMN role,
+    DROP COLUMN sort_order,
+    DROP COLUMN status;
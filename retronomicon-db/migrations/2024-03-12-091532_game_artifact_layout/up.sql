@@ -0,0 +1,14 @@
+-- How each file of a game is laid out: its name and directory relative to
+-- the game (e.g. `Disc 1`), what it is used for, its position among the
+-- files of the game, and the status of its dump (as in logiqx DATs).
+ALTER TABLE game_artifacts
+    ADD COLUMN filename   VARCHAR(255)  NOT NULL DEFAULT '',
+    ADD COLUMN path       VARCHAR(1024) NOT NULL DEFAULT '',
+    ADD COLUMN role       VARCHAR(32)   NOT NULL DEFAULT 'rom',
+    ADD COLUMN sort_order INTEGER       NOT NULL DEFAULT 0,
+    ADD COLUMN status     VARCHAR(16)   NOT NULL DEFAULT 'good';
+
+UPDATE game_artifacts
+SET filename = artifacts.filename
+FROM artifacts
+WHERE artifacts.id = game_artifacts.artifact_id;
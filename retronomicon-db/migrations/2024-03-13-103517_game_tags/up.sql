@@ -0,0 +1,9 @@
+-- Tags of games, e.g. to export the games of a system with a tag as a DAT.
+CREATE TABLE game_tags
+(
+    game_id INTEGER NOT NULL REFERENCES games (id) ON DELETE CASCADE,
+    tag_id  INTEGER NOT NULL REFERENCES tags (id) ON DELETE CASCADE,
+    PRIMARY KEY (game_id, tag_id)
+);
+
+CREATE INDEX game_tags_tag_id_idx ON game_tags (tag_id);
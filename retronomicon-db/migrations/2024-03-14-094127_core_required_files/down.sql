@@ -0,0 +1,2 @@
+DROP TABLE core_required_file_hashes;
+DROP TABLE core_required_files;
@@ -0,0 +1,30 @@
+-- Files a core needs on the device to run, e.g. a BIOS. Files without a
+-- release apply to all the releases of the core.
+CREATE TABLE core_required_files
+(
+    id              SERIAL PRIMARY KEY NOT NULL,
+    core_id         INTEGER       NOT NULL REFERENCES cores (id) ON DELETE CASCADE,
+    core_release_id INTEGER REFERENCES core_releases (id) ON DELETE CASCADE,
+    filename        VARCHAR(255)  NOT NULL,
+    -- The directory of the file on the device, e.g. `games/PSX`.
+    path            VARCHAR(1024) NOT NULL DEFAULT '',
+    optional        BOOLEAN       NOT NULL DEFAULT FALSE,
+    description     TEXT          NOT NULL DEFAULT ''
+);
+
+CREATE INDEX core_required_files_core_id_idx ON core_required_files (core_id);
+
+-- The checksums a required file can have, e.g. one per region of a BIOS.
+-- Unknown checksums are empty.
+CREATE TABLE core_required_file_hashes
+(
+    id               SERIAL PRIMARY KEY NOT NULL,
+    required_file_id INTEGER NOT NULL REFERENCES core_required_files (id) ON DELETE CASCADE,
+    size             BIGINT,
+    crc32            BYTEA   NOT NULL DEFAULT E''::bytea,
+    md5              BYTEA   NOT NULL DEFAULT E''::bytea,
+    sha1             BYTEA   NOT NULL DEFAULT E''::bytea,
+    sha256           BYTEA   NOT NULL DEFAULT E''::bytea
+);
+
+CREATE INDEX core_required_file_hashes_required_file_id_idx ON core_required_file_hashes (required_file_id);
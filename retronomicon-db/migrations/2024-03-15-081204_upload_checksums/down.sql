@@ -0,0 +1,6 @@
+DROP INDEX core_release_uploads_updated_at_idx;
+
+ALTER TABLE core_release_uploads
+    DROP COLUMN md5,
+    DROP COLUMN sha1,
+    DROP COLUMN sha256;
@@ -0,0 +1,10 @@
+-- The checksums of an upload, recorded once its parts were assembled in the
+-- storage. They are computed from the parts as they are received, or from
+-- the assembled file if the server lost track of them (e.g. it restarted).
+ALTER TABLE core_release_uploads
+    ADD COLUMN md5    BYTEA,
+    ADD COLUMN sha1   BYTEA,
+    ADD COLUMN sha256 BYTEA;
+
+-- Uploads that are not updated anymore are expired.
+CREATE INDEX core_release_uploads_updated_at_idx ON core_release_uploads (updated_at);
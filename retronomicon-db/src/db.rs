@@ -1,11 +1,13 @@
 use diesel::PgConnection;
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
-use rocket_db_pools::{Connection, Database, Initializer};
+use rocket::figment::Figment;
+use rocket_db_pools::diesel::AsyncPgConnection;
+use rocket_db_pools::{Connection, Database, Initializer, Pool};
 use tracing::info;
 
 pub mod ssl_pool;
 
-#[derive(Database)]
+#[derive(Database, Clone)]
 #[database("retronomicon_db")]
 pub struct RetronomiconDbPool(ssl_pool::Pool);
 
@@ -14,10 +16,36 @@ impl RetronomiconDbPool {
         info!("Initializing database");
         Database::init()
     }
+
+    /// Open a connection outside of a request (e.g. for background jobs).
+    pub async fn connect(figment: &Figment) -> Result<DbConnection, String> {
+        let pool = ssl_pool::Pool::init(&figment.focus("databases.retronomicon_db"))
+            .await
+            .map_err(|e| e.to_string())?;
+        pool.get().await.map_err(|e| e.to_string())
+    }
+
+    /// Get a connection from the pool managed by the server, outside of a
+    /// request (e.g. for background jobs).
+    pub async fn connection(&self) -> Result<DbConnection, String> {
+        self.get().await.map_err(|e| e.to_string())
+    }
 }
 
 pub type Db = Connection<RetronomiconDbPool>;
 
+/// A database connection that is not tied to a request.
+pub type DbConnection = AsyncPgConnection;
+
+/// Whether an error is caused by a unique constraint (e.g. a name that is
+/// already used), which is usually a conflict rather than a server error.
+pub fn is_unique_violation(e: &diesel::result::Error) -> bool {
+    matches!(
+        e,
+        diesel::result::Error::DatabaseError(diesel::result::DatabaseErrorKind::UniqueViolation, _)
+    )
+}
+
 const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/");
 
 pub fn run_migrations(database_url: &str) {
@@ -56,6 +56,7 @@ fn root_certs(additional_certs: &[Certificate]) -> rustls::RootCertStore {
 /// The default diesel-async (and by extension, rocket_db_pool::PgPool) does not support
 /// SSL by default (it uses NoTls when connecting). This is not a problem locally but
 /// when using the databases in production we want to be able to use a proper SSL connection.
+#[derive(Clone)]
 pub struct Pool {
     url: String,
     /// A list of additional certificates to trust when connecting to the database.
@@ -17,18 +17,39 @@ use std::io::Write;
 pub mod artifact;
 pub use artifact::*;
 
+pub mod blobs;
+pub use blobs::*;
+
 pub mod cores;
 pub use cores::*;
 
+pub mod dat_imports;
+pub use dat_imports::*;
+
 pub mod games;
 pub use games::*;
 
 pub mod images;
 pub use images::*;
 
+pub mod notifications;
+pub use notifications::*;
+
+pub mod permissions;
+pub use permissions::*;
+
 pub mod platforms;
 pub use platforms::*;
 
+pub mod search;
+pub use search::*;
+
+pub mod signatures;
+pub use signatures::*;
+
+pub mod storage_deletions;
+pub use storage_deletions::*;
+
 pub mod systems;
 pub use systems::*;
 
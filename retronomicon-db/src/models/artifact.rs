@@ -1,4 +1,4 @@
-use crate::models::{Core, CoreRelease, Platform, System, User};
+use crate::models::{Blob, Core, CoreRelease, Platform, System, User};
 use crate::schema;
 use crate::Db;
 use chrono::NaiveDateTime;
@@ -9,8 +9,9 @@ use diesel::serialize::{IsNull, Output, ToSql};
 use diesel::upsert::on_constraint;
 use diesel::{AsExpression, FromSqlRow};
 use retronomicon_dto as dto;
-use rocket_db_pools::diesel::{AsyncConnection, RunQueryDsl};
+use rocket_db_pools::diesel::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
 use sha2::Digest;
+use std::num::NonZeroU64;
 
 #[derive(Queryable, Debug, Identifiable)]
 #[diesel(primary_key(core_release_id, artifact_id))]
@@ -80,7 +81,7 @@ impl CoreReleaseArtifact {
 
 #[derive(Queryable, Debug, Selectable, Identifiable)]
 #[diesel(table_name = schema::files)]
-#[diesel(belongs_to(models::Artifact))]
+#[diesel(belongs_to(models::Blob))]
 pub struct File {
     pub id: i32,
     pub data: Vec<u8>,
@@ -95,9 +96,11 @@ pub struct Artifact {
     pub created_at: NaiveDateTime,
     pub md5: Vec<u8>,
     pub sha256: Vec<u8>,
-    pub size: i32,
+    pub size: i64,
     pub download_url: Option<String>,
     pub sha1: Vec<u8>,
+    pub blob_id: Option<i32>,
+    pub crc32: Vec<u8>,
 }
 
 impl From<Artifact> for dto::artifact::ArtifactRef {
@@ -108,15 +111,17 @@ impl From<Artifact> for dto::artifact::ArtifactRef {
             size,
             download_url,
             sha1,
+            crc32,
             ..
         }: Artifact,
     ) -> Self {
         Self {
             download_url: download_url.into(),
-            size: if let Ok(size) = (size as u32).try_into() {
-                Some(size)
-            } else {
+            size: u64::try_from(size).ok().and_then(NonZeroU64::new),
+            crc32: if crc32.is_empty() {
                 None
+            } else {
+                Some(crc32.into())
             },
             md5: if md5.is_empty() {
                 None
@@ -148,50 +153,72 @@ impl Artifact {
         let sha1 = sha1::Sha1::digest(data).to_vec();
         let sha256 = sha2::Sha256::digest(data).to_vec();
 
-        let artifact = diesel::insert_into(schema::artifacts::table)
+        let (blob, created) =
+            Blob::create(db, &md5, &sha1, &sha256, data.len() as i64, None, None).await?;
+        if created {
+            diesel::insert_into(schema::files::table)
+                .values((schema::files::id.eq(blob.id), schema::files::data.eq(data)))
+                .execute(db)
+                .await?;
+        }
+
+        Self::create_for_blob(db, filename, mime_type, &blob).await
+    }
+
+    /// Create an artifact whose content is an existing blob.
+    pub async fn create_for_blob(
+        db: &mut Db,
+        filename: &str,
+        mime_type: &str,
+        blob: &Blob,
+    ) -> Result<Self, diesel::result::Error> {
+        diesel::insert_into(schema::artifacts::table)
             .values((
                 schema::artifacts::created_at.eq(chrono::Utc::now().naive_utc()),
                 schema::artifacts::filename.eq(filename),
+                schema::artifacts::download_url.eq(&blob.download_url),
                 schema::artifacts::mime_type.eq(mime_type),
-                schema::artifacts::md5.eq(md5),
-                schema::artifacts::sha256.eq(sha256),
-                schema::artifacts::size.eq(data.len() as i32),
-                schema::artifacts::sha1.eq(sha1),
+                schema::artifacts::md5.eq(&blob.md5),
+                schema::artifacts::sha1.eq(&blob.sha1),
+                schema::artifacts::sha256.eq(&blob.sha256),
+                schema::artifacts::size.eq(blob.size),
+                schema::artifacts::blob_id.eq(blob.id),
             ))
             .returning(schema::artifacts::all_columns)
             .get_result::<Self>(db)
-            .await?;
-
-        diesel::insert_into(schema::files::table)
-            .values((
-                schema::files::id.eq(artifact.id),
-                schema::files::data.eq(data),
-            ))
-            .execute(db)
-            .await?;
-        Ok(artifact)
+            .await
     }
 
+    /// Create an artifact that we only know through its checksums. If we
+    /// have its content, the artifact refers to it.
     pub async fn create_with_checksum(
-        db: &mut Db,
+        db: &mut AsyncPgConnection,
         filename: &str,
         mime_type: &str,
+        crc32: Option<&[u8]>,
         md5: Option<&[u8]>,
         sha1: Option<&[u8]>,
         sha256: Option<&[u8]>,
         download_url: Option<&str>,
-        size: i32,
+        size: i64,
     ) -> Result<Self, diesel::result::Error> {
+        let blob_id = match sha256 {
+            Some(sha256) => Blob::get_by_sha256(db, sha256).await?.map(|b| b.id),
+            None => None,
+        };
+
         diesel::insert_into(schema::artifacts::table)
             .values((
                 schema::artifacts::created_at.eq(chrono::Utc::now().naive_utc()),
                 schema::artifacts::filename.eq(filename),
                 schema::artifacts::download_url.eq(download_url),
                 schema::artifacts::mime_type.eq(mime_type),
+                schema::artifacts::crc32.eq(crc32.unwrap_or(&[])),
                 schema::artifacts::md5.eq(md5.unwrap_or(&[])),
                 schema::artifacts::sha1.eq(sha1.unwrap_or(&[])),
                 schema::artifacts::sha256.eq(sha256.unwrap_or(&[])),
                 schema::artifacts::size.eq(size),
+                schema::artifacts::blob_id.eq(blob_id),
             ))
             .returning(schema::artifacts::all_columns)
             .get_result::<Self>(db)
@@ -214,6 +241,20 @@ impl Artifact {
             .await
     }
 
+    /// All the artifacts of a release, ordered by filename.
+    pub async fn list_all(
+        db: &mut Db,
+        release: &CoreRelease,
+    ) -> Result<Vec<Self>, diesel::result::Error> {
+        schema::artifacts::table
+            .inner_join(schema::core_release_artifacts::table)
+            .filter(schema::core_release_artifacts::core_release_id.eq(release.id))
+            .select(schema::artifacts::all_columns)
+            .order(schema::artifacts::filename.asc())
+            .load::<Self>(db)
+            .await
+    }
+
     pub async fn get_file(
         db: &mut Db,
         core_id: dto::types::IdOrSlug<'_>,
@@ -222,7 +263,10 @@ impl Artifact {
     ) -> Result<(Self, Option<File>), diesel::result::Error> {
         let mut query =
             schema::artifacts::table
-                .inner_join(schema::files::table)
+                .inner_join(
+                    schema::files::table
+                        .on(schema::files::id.nullable().eq(schema::artifacts::blob_id)),
+                )
                 .inner_join(schema::core_release_artifacts::table)
                 .inner_join(schema::core_releases::table.on(
                     schema::core_releases::id.eq(schema::core_release_artifacts::core_release_id),
@@ -248,7 +292,7 @@ impl Artifact {
             .await?;
 
         let file = schema::files::table
-            .filter(schema::files::id.eq(artifact.id))
+            .filter(schema::files::id.nullable().eq(artifact.blob_id))
             .first::<File>(db)
             .await
             .optional()?;
@@ -263,7 +307,10 @@ impl Artifact {
     ) -> Result<(Self, Option<File>), diesel::result::Error> {
         let artifact =
             schema::artifacts::table
-                .inner_join(schema::files::table)
+                .inner_join(
+                    schema::files::table
+                        .on(schema::files::id.nullable().eq(schema::artifacts::blob_id)),
+                )
                 .inner_join(schema::core_release_artifacts::table)
                 .inner_join(schema::core_releases::table.on(
                     schema::core_releases::id.eq(schema::core_release_artifacts::core_release_id),
@@ -279,7 +326,7 @@ impl Artifact {
                 .await?;
 
         let file = schema::files::table
-            .filter(schema::files::id.eq(artifact.id))
+            .filter(schema::files::id.nullable().eq(artifact.blob_id))
             .first::<File>(db)
             .await
             .optional()?;
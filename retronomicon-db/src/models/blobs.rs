@@ -0,0 +1,134 @@
+use crate::models::StorageDeletion;
+use crate::schema;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use rocket_db_pools::diesel::scoped_futures::ScopedFutureExt;
+use rocket_db_pools::diesel::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+
+/// The content of one or more artifacts, stored once and identified by its
+/// SHA256.
+///
+/// Functions here take a connection instead of a `Db`, as they are also used
+/// by background jobs outside of requests.
+#[derive(Clone, Queryable, Debug, Selectable, Identifiable)]
+#[diesel(table_name = schema::blobs)]
+pub struct Blob {
+    pub id: i32,
+    pub sha256: Vec<u8>,
+    pub md5: Vec<u8>,
+    pub sha1: Vec<u8>,
+    pub size: i64,
+    pub storage_path: Option<String>,
+    pub download_url: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl Blob {
+    pub async fn get_by_sha256(
+        db: &mut AsyncPgConnection,
+        sha256: &[u8],
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        schema::blobs::table
+            .filter(schema::blobs::sha256.eq(sha256))
+            .first::<Self>(db)
+            .await
+            .optional()
+    }
+
+    /// Create a blob. If a blob with the same content already exists (e.g.
+    /// it was created concurrently), it is returned instead, along with
+    /// `false`.
+    pub async fn create(
+        db: &mut AsyncPgConnection,
+        md5: &[u8],
+        sha1: &[u8],
+        sha256: &[u8],
+        size: i64,
+        storage_path: Option<&str>,
+        download_url: Option<&str>,
+    ) -> Result<(Self, bool), diesel::result::Error> {
+        let blob = diesel::insert_into(schema::blobs::table)
+            .values((
+                schema::blobs::sha256.eq(sha256),
+                schema::blobs::md5.eq(md5),
+                schema::blobs::sha1.eq(sha1),
+                schema::blobs::size.eq(size),
+                schema::blobs::storage_path.eq(storage_path),
+                schema::blobs::download_url.eq(download_url),
+            ))
+            .on_conflict(schema::blobs::sha256)
+            .do_nothing()
+            .returning(schema::blobs::all_columns)
+            .get_result::<Self>(db)
+            .await
+            .optional()?;
+
+        match blob {
+            Some(blob) => Ok((blob, true)),
+            None => Self::get_by_sha256(db, sha256)
+                .await?
+                .ok_or(diesel::result::Error::NotFound)
+                .map(|blob| (blob, false)),
+        }
+    }
+
+    /// List blobs that no artifact refers to, created before `created_before`.
+    pub async fn list_unreferenced(
+        db: &mut AsyncPgConnection,
+        created_before: NaiveDateTime,
+        limit: i64,
+    ) -> Result<Vec<Self>, diesel::result::Error> {
+        schema::blobs::table
+            .filter(schema::blobs::created_at.lt(created_before))
+            .filter(diesel::dsl::not(diesel::dsl::exists(
+                schema::artifacts::table
+                    .filter(schema::artifacts::blob_id.eq(schema::blobs::id.nullable())),
+            )))
+            .order(schema::blobs::id)
+            .limit(limit)
+            .load::<Self>(db)
+            .await
+    }
+
+    /// Whether a blob is stored at `storage_path`.
+    pub async fn is_path_used(
+        db: &mut AsyncPgConnection,
+        storage_path: &str,
+    ) -> Result<bool, diesel::result::Error> {
+        diesel::select(diesel::dsl::exists(
+            schema::blobs::table.filter(schema::blobs::storage_path.eq(storage_path)),
+        ))
+        .get_result::<bool>(db)
+        .await
+    }
+
+    /// Delete this blob if no artifact refers to it, and record that its
+    /// object needs to be deleted from `bucket`. Returns whether it was
+    /// deleted.
+    pub async fn delete_if_unreferenced(
+        &self,
+        db: &mut AsyncPgConnection,
+        bucket: &str,
+    ) -> Result<bool, diesel::result::Error> {
+        db.transaction(|db| {
+            async move {
+                let deleted = diesel::delete(schema::blobs::table)
+                    .filter(schema::blobs::id.eq(self.id))
+                    .filter(diesel::dsl::not(diesel::dsl::exists(
+                        schema::artifacts::table
+                            .filter(schema::artifacts::blob_id.eq(schema::blobs::id.nullable())),
+                    )))
+                    .execute(db)
+                    .await?
+                    > 0;
+
+                if let (true, Some(path)) = (deleted, &self.storage_path) {
+                    StorageDeletion::create(db, bucket, path).await?;
+                }
+                Ok(deleted)
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+}
@@ -8,9 +8,17 @@ use retronomicon_dto as dto;
 use rocket_db_pools::diesel::{AsyncConnection, RunQueryDsl};
 use serde_json::Value as Json;
 
+mod followers;
+
 mod releases;
 pub use releases::*;
 
+mod required_files;
+pub use required_files::*;
+
+mod uploads;
+pub use uploads::*;
+
 #[derive(Queryable, Debug, Identifiable)]
 #[diesel(table_name = schema::cores)]
 pub struct Core {
@@ -75,6 +83,9 @@ impl Core {
             .await
     }
 
+    /// List cores with their latest release. Yanked releases are skipped
+    /// unless `include_yanked` is set.
+    #[allow(clippy::too_many_arguments)]
     pub async fn list_with_teams_and_releases(
         db: &mut Db,
         page: i64,
@@ -83,6 +94,7 @@ impl Core {
         system: Option<&System>,
         team: Option<&Team>,
         release_date_ge: Option<chrono::NaiveDateTime>,
+        include_yanked: bool,
     ) -> Result<
         Vec<(
             Self,
@@ -99,14 +111,22 @@ impl Core {
                 schema::core_releases::table.on(schema::core_releases::id.eq(
                     // Diesel does not support subqueries on joins, so we have to use raw SQL.
                     // It's okay because it does not actually need inputs.
-                    diesel::dsl::sql(
+                    diesel::dsl::sql(if include_yanked {
+                        r#"(
+                        SELECT id FROM core_releases
+                            WHERE cores.id = core_releases.core_id
+                            ORDER BY date_released DESC, id DESC
+                            LIMIT 1
+                        )"#
+                    } else {
                         r#"(
                         SELECT id FROM core_releases
                             WHERE cores.id = core_releases.core_id
+                              AND NOT core_releases.yanked
                             ORDER BY date_released DESC, id DESC
                             LIMIT 1
-                        )"#,
-                    ),
+                        )"#
+                    }),
                 )),
             )
             .inner_join(
@@ -0,0 +1,41 @@
+use crate::models::{Core, User};
+use crate::schema;
+use crate::Db;
+use diesel::prelude::*;
+use rocket_db_pools::diesel::RunQueryDsl;
+
+impl Core {
+    /// Follow the releases of a core. Following a core twice does nothing.
+    pub async fn follow(&self, db: &mut Db, user_id: i32) -> Result<(), diesel::result::Error> {
+        diesel::insert_into(schema::core_followers::table)
+            .values((
+                schema::core_followers::core_id.eq(self.id),
+                schema::core_followers::user_id.eq(user_id),
+            ))
+            .on_conflict_do_nothing()
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    /// Stop following a core. Returns whether the user was following it.
+    pub async fn unfollow(&self, db: &mut Db, user_id: i32) -> Result<bool, diesel::result::Error> {
+        diesel::delete(schema::core_followers::table)
+            .filter(schema::core_followers::core_id.eq(self.id))
+            .filter(schema::core_followers::user_id.eq(user_id))
+            .execute(db)
+            .await
+            .map(|n| n > 0)
+    }
+
+    /// The users following the releases of this core.
+    pub async fn followers(&self, db: &mut Db) -> Result<Vec<User>, diesel::result::Error> {
+        schema::core_followers::table
+            .inner_join(schema::users::table)
+            .filter(schema::core_followers::core_id.eq(self.id))
+            .filter(schema::users::deleted.eq(false))
+            .select(schema::users::all_columns)
+            .load::<User>(db)
+            .await
+    }
+}
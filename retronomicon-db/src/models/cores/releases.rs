@@ -50,6 +50,16 @@ impl CoreRelease {
             .optional()
     }
 
+    /// Yank or restore the release.
+    pub async fn set_yanked(&self, db: &mut Db, yanked: bool) -> Result<(), diesel::result::Error> {
+        diesel::update(schema::core_releases::table)
+            .filter(schema::core_releases::id.eq(self.id))
+            .set(schema::core_releases::yanked.eq(yanked))
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
     pub async fn create(
         db: &mut Db,
         version: &str,
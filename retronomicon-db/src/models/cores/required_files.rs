@@ -0,0 +1,173 @@
+use crate::schema;
+use crate::Db;
+use diesel::prelude::*;
+use retronomicon_dto as dto;
+use rocket_db_pools::diesel::scoped_futures::ScopedFutureExt;
+use rocket_db_pools::diesel::{AsyncConnection, RunQueryDsl};
+use std::collections::BTreeMap;
+
+#[derive(Queryable, Selectable, Debug, Identifiable)]
+#[diesel(table_name = schema::core_required_files)]
+pub struct CoreRequiredFile {
+    pub id: i32,
+    pub core_id: i32,
+    pub core_release_id: Option<i32>,
+    pub filename: String,
+    pub path: String,
+    pub optional: bool,
+    pub description: String,
+}
+
+/// A version of a required file. Unknown checksums are empty.
+#[derive(Queryable, Selectable, Debug, Identifiable)]
+#[diesel(table_name = schema::core_required_file_hashes)]
+pub struct CoreRequiredFileHash {
+    pub id: i32,
+    pub required_file_id: i32,
+    pub size: Option<i64>,
+    pub crc32: Vec<u8>,
+    pub md5: Vec<u8>,
+    pub sha1: Vec<u8>,
+    pub sha256: Vec<u8>,
+}
+
+impl From<CoreRequiredFileHash> for dto::cores::required_files::RequiredFileHash {
+    fn from(value: CoreRequiredFileHash) -> Self {
+        let hex = |checksum: Vec<u8>| Some(checksum).filter(|c| !c.is_empty()).map(Into::into);
+        Self {
+            size: value.size,
+            crc32: hex(value.crc32),
+            md5: hex(value.md5),
+            sha1: hex(value.sha1),
+            sha256: hex(value.sha256),
+        }
+    }
+}
+
+impl CoreRequiredFile {
+    pub async fn create(
+        db: &mut Db,
+        core_id: i32,
+        core_release_id: Option<i32>,
+        filename: &str,
+        path: &str,
+        optional: bool,
+        description: &str,
+        hashes: Vec<dto::cores::required_files::RequiredFileHash>,
+    ) -> Result<Self, diesel::result::Error> {
+        let filename = filename.to_string();
+        let path = path.to_string();
+        let description = description.to_string();
+        db.transaction(|db| {
+            async move {
+                let file = diesel::insert_into(schema::core_required_files::table)
+                    .values((
+                        schema::core_required_files::core_id.eq(core_id),
+                        schema::core_required_files::core_release_id.eq(core_release_id),
+                        schema::core_required_files::filename.eq(filename),
+                        schema::core_required_files::path.eq(path),
+                        schema::core_required_files::optional.eq(optional),
+                        schema::core_required_files::description.eq(description),
+                    ))
+                    .returning(Self::as_returning())
+                    .get_result::<Self>(db)
+                    .await?;
+
+                let checksum = |c: Option<dto::encodings::HexString>| -> Vec<u8> {
+                    c.map(Into::into).unwrap_or_default()
+                };
+                diesel::insert_into(schema::core_required_file_hashes::table)
+                    .values(
+                        hashes
+                            .into_iter()
+                            .map(|hash| {
+                                (
+                                    schema::core_required_file_hashes::required_file_id.eq(file.id),
+                                    schema::core_required_file_hashes::size.eq(hash.size),
+                                    schema::core_required_file_hashes::crc32
+                                        .eq(checksum(hash.crc32)),
+                                    schema::core_required_file_hashes::md5.eq(checksum(hash.md5)),
+                                    schema::core_required_file_hashes::sha1.eq(checksum(hash.sha1)),
+                                    schema::core_required_file_hashes::sha256
+                                        .eq(checksum(hash.sha256)),
+                                )
+                            })
+                            .collect::<Vec<_>>(),
+                    )
+                    .execute(db)
+                    .await?;
+
+                Ok(file)
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+
+    /// The required files of cores, with their hashes, in the order they
+    /// were added.
+    pub async fn list_for_cores(
+        db: &mut Db,
+        core_ids: &[i32],
+    ) -> Result<Vec<(Self, Vec<CoreRequiredFileHash>)>, diesel::result::Error> {
+        let files = schema::core_required_files::table
+            .filter(schema::core_required_files::core_id.eq_any(core_ids))
+            .order_by(schema::core_required_files::id.asc())
+            .select(Self::as_select())
+            .load::<Self>(db)
+            .await?;
+
+        let mut hashes = BTreeMap::<i32, Vec<CoreRequiredFileHash>>::new();
+        for hash in schema::core_required_file_hashes::table
+            .filter(
+                schema::core_required_file_hashes::required_file_id
+                    .eq_any(files.iter().map(|f| f.id)),
+            )
+            .order_by(schema::core_required_file_hashes::id.asc())
+            .select(CoreRequiredFileHash::as_select())
+            .load::<CoreRequiredFileHash>(db)
+            .await?
+        {
+            hashes.entry(hash.required_file_id).or_default().push(hash);
+        }
+
+        Ok(files
+            .into_iter()
+            .map(|file| {
+                let file_hashes = hashes.remove(&file.id).unwrap_or_default();
+                (file, file_hashes)
+            })
+            .collect())
+    }
+
+    /// Delete a required file of a core. Returns false if it does not exist.
+    pub async fn delete(db: &mut Db, core_id: i32, id: i32) -> Result<bool, diesel::result::Error> {
+        let deleted = diesel::delete(schema::core_required_files::table)
+            .filter(schema::core_required_files::id.eq(id))
+            .filter(schema::core_required_files::core_id.eq(core_id))
+            .execute(db)
+            .await?;
+        Ok(deleted > 0)
+    }
+
+    /// Whether the file is required by a release of its core.
+    pub fn applies_to(&self, core_release_id: i32) -> bool {
+        self.core_release_id
+            .map_or(true, |release_id| release_id == core_release_id)
+    }
+
+    pub fn into_dto(
+        self,
+        hashes: Vec<CoreRequiredFileHash>,
+    ) -> dto::cores::required_files::CoreRequiredFile {
+        dto::cores::required_files::CoreRequiredFile {
+            id: self.id,
+            release_id: self.core_release_id,
+            filename: self.filename,
+            path: self.path,
+            optional: self.optional,
+            description: self.description,
+            hashes: hashes.into_iter().map(Into::into).collect(),
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use crate::models::{Artifact, Blob, CoreRelease, CoreReleaseArtifact, User};
+use crate::schema;
+use crate::Db;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use rocket_db_pools::diesel::scoped_futures::ScopedFutureExt;
+use rocket_db_pools::diesel::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+
+/// A part of an upload that was stored in the storage backend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoreReleaseUploadPart {
+    pub part_number: u32,
+    pub etag: String,
+    pub size: i64,
+}
+
+#[derive(Queryable, Debug, Identifiable, Selectable)]
+#[diesel(table_name = schema::core_release_uploads)]
+#[diesel(belongs_to(CoreRelease))]
+pub struct CoreReleaseUpload {
+    pub id: i32,
+    pub core_release_id: i32,
+    pub user_id: i32,
+    pub filename: String,
+    pub mime_type: String,
+    pub size: i64,
+    pub received: i64,
+    pub storage_path: String,
+    pub storage_upload_id: String,
+    pub parts: Json,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub md5: Option<Vec<u8>>,
+    pub sha1: Option<Vec<u8>>,
+    pub sha256: Option<Vec<u8>>,
+}
+
+impl CoreReleaseUpload {
+    pub fn parts(&self) -> Result<Vec<CoreReleaseUploadPart>, serde_json::Error> {
+        serde_json::from_value(self.parts.clone())
+    }
+
+    pub fn next_part_number(&self) -> Result<u32, serde_json::Error> {
+        Ok(self.parts()?.len() as u32 + 1)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.received == self.size
+    }
+
+    /// The MD5, SHA1 and SHA256 of the file, once its parts were assembled.
+    pub fn checksums(&self) -> Option<(&[u8], &[u8], &[u8])> {
+        match (&self.md5, &self.sha1, &self.sha256) {
+            (Some(md5), Some(sha1), Some(sha256)) => Some((md5, sha1, sha256)),
+            _ => None,
+        }
+    }
+
+    pub async fn create(
+        db: &mut Db,
+        release: &CoreRelease,
+        user: &User,
+        filename: &str,
+        mime_type: &str,
+        size: i64,
+        storage_path: &str,
+        storage_upload_id: &str,
+    ) -> Result<Self, diesel::result::Error> {
+        diesel::insert_into(schema::core_release_uploads::table)
+            .values((
+                schema::core_release_uploads::core_release_id.eq(release.id),
+                schema::core_release_uploads::user_id.eq(user.id),
+                schema::core_release_uploads::filename.eq(filename),
+                schema::core_release_uploads::mime_type.eq(mime_type),
+                schema::core_release_uploads::size.eq(size),
+                schema::core_release_uploads::storage_path.eq(storage_path),
+                schema::core_release_uploads::storage_upload_id.eq(storage_upload_id),
+            ))
+            .returning(schema::core_release_uploads::all_columns)
+            .get_result::<Self>(db)
+            .await
+    }
+
+    pub async fn get(
+        db: &mut Db,
+        release: &CoreRelease,
+        id: i32,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        schema::core_release_uploads::table
+            .filter(schema::core_release_uploads::id.eq(id))
+            .filter(schema::core_release_uploads::core_release_id.eq(release.id))
+            .first::<Self>(db)
+            .await
+            .optional()
+    }
+
+    pub async fn list(
+        db: &mut Db,
+        release: &CoreRelease,
+    ) -> Result<Vec<Self>, diesel::result::Error> {
+        schema::core_release_uploads::table
+            .filter(schema::core_release_uploads::core_release_id.eq(release.id))
+            .order(schema::core_release_uploads::id)
+            .load::<Self>(db)
+            .await
+    }
+
+    pub async fn is_filename_used_for_release(
+        db: &mut Db,
+        release: &CoreRelease,
+        filename: &str,
+    ) -> Result<bool, diesel::result::Error> {
+        schema::core_release_uploads::table
+            .filter(schema::core_release_uploads::core_release_id.eq(release.id))
+            .filter(schema::core_release_uploads::filename.eq(filename))
+            .count()
+            .get_result::<i64>(db)
+            .await
+            .map(|c| c > 0)
+    }
+
+    /// Record a new part. This only succeeds if no other part was added
+    /// since this upload was fetched, so parts cannot be recorded twice.
+    pub async fn add_part(
+        &self,
+        db: &mut Db,
+        part: CoreReleaseUploadPart,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        let mut parts = self
+            .parts()
+            .map_err(|e| diesel::result::Error::DeserializationError(Box::new(e)))?;
+        let received = self.received + part.size;
+        parts.push(part);
+        let parts = serde_json::to_value(parts)
+            .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?;
+
+        diesel::update(schema::core_release_uploads::table)
+            .filter(schema::core_release_uploads::id.eq(self.id))
+            .filter(schema::core_release_uploads::received.eq(self.received))
+            .set((
+                schema::core_release_uploads::parts.eq(parts),
+                schema::core_release_uploads::received.eq(received),
+                schema::core_release_uploads::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .returning(schema::core_release_uploads::all_columns)
+            .get_result::<Self>(db)
+            .await
+            .optional()
+    }
+
+    /// Record the checksums of the file once its parts were assembled in
+    /// the storage.
+    pub async fn set_checksums(
+        &self,
+        db: &mut Db,
+        md5: &[u8],
+        sha1: &[u8],
+        sha256: &[u8],
+    ) -> Result<Self, diesel::result::Error> {
+        diesel::update(schema::core_release_uploads::table)
+            .filter(schema::core_release_uploads::id.eq(self.id))
+            .set((
+                schema::core_release_uploads::md5.eq(md5),
+                schema::core_release_uploads::sha1.eq(sha1),
+                schema::core_release_uploads::sha256.eq(sha256),
+                schema::core_release_uploads::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .returning(schema::core_release_uploads::all_columns)
+            .get_result::<Self>(db)
+            .await
+    }
+
+    /// List uploads that were not updated since `updated_before`.
+    pub async fn list_stale(
+        db: &mut AsyncPgConnection,
+        updated_before: NaiveDateTime,
+        limit: i64,
+    ) -> Result<Vec<Self>, diesel::result::Error> {
+        schema::core_release_uploads::table
+            .filter(schema::core_release_uploads::updated_at.lt(updated_before))
+            .order(schema::core_release_uploads::id)
+            .limit(limit)
+            .load::<Self>(db)
+            .await
+    }
+
+    /// Create the artifact for this upload once all its parts were
+    /// assembled in the storage, and remove the upload.
+    pub async fn complete(
+        self,
+        db: &mut Db,
+        release: &CoreRelease,
+        blob: &Blob,
+    ) -> Result<Artifact, diesel::result::Error> {
+        db.transaction(|db| {
+            async move {
+                let artifact =
+                    Artifact::create_for_blob(db, &self.filename, &self.mime_type, blob).await?;
+                CoreReleaseArtifact::create(db, release, &artifact).await?;
+                self.delete(db).await?;
+
+                Ok(artifact)
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+
+    pub async fn delete(&self, db: &mut AsyncPgConnection) -> Result<(), diesel::result::Error> {
+        diesel::delete(schema::core_release_uploads::table)
+            .filter(schema::core_release_uploads::id.eq(self.id))
+            .execute(db)
+            .await
+            .map(|_| ())
+    }
+}
@@ -0,0 +1,386 @@
+use crate::models::{Artifact, Game, GameArtifact, System};
+use crate::schema;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use retronomicon_dto as dto;
+use rocket_db_pools::diesel::scoped_futures::ScopedFutureExt;
+use rocket_db_pools::diesel::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use serde_json::Value as Json;
+
+/// A DAT file uploaded to create or update the games of a system.
+///
+/// Functions here take a connection instead of a `Db`, as imports are run
+/// by background jobs outside of requests.
+#[derive(Queryable, Debug, Identifiable, Selectable)]
+#[diesel(table_name = schema::dat_imports)]
+#[diesel(belongs_to(System))]
+pub struct DatImport {
+    pub id: i32,
+    pub system_id: i32,
+    pub user_id: Option<i32>,
+    pub dat_name: String,
+    pub dat_version: String,
+    pub dat: Option<Vec<u8>>,
+    pub dry_run: bool,
+    pub status: String,
+    pub preview: Option<Json>,
+    pub total: i32,
+    pub processed: i32,
+    pub error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+}
+
+impl DatImport {
+    pub fn status(&self) -> Result<dto::games::DatImportStatus, String> {
+        self.status.parse().map_err(|_| {
+            format!(
+                "Unknown status {:?} for DAT import {}",
+                self.status, self.id
+            )
+        })
+    }
+
+    pub fn into_dto(self, system: System) -> Result<dto::games::DatImportDetails, String> {
+        Ok(dto::games::DatImportDetails {
+            id: self.id,
+            status: self.status()?,
+            system: system.into(),
+            dat_name: self.dat_name,
+            dat_version: self.dat_version,
+            dry_run: self.dry_run,
+            preview: self
+                .preview
+                .map(serde_json::from_value)
+                .transpose()
+                .map_err(|e| e.to_string())?,
+            total: self.total,
+            processed: self.processed,
+            error: self.error,
+            created_at: self.created_at.and_utc().timestamp(),
+            finished_at: self.finished_at.map(|d| d.and_utc().timestamp()),
+        })
+    }
+
+    pub async fn create(
+        db: &mut AsyncPgConnection,
+        system: &System,
+        user_id: i32,
+        dat_name: &str,
+        dat_version: &str,
+        dat: &[u8],
+        total: i32,
+        dry_run: bool,
+    ) -> Result<Self, diesel::result::Error> {
+        diesel::insert_into(schema::dat_imports::table)
+            .values((
+                schema::dat_imports::system_id.eq(system.id),
+                schema::dat_imports::user_id.eq(user_id),
+                schema::dat_imports::dat_name.eq(dat_name),
+                schema::dat_imports::dat_version.eq(dat_version),
+                schema::dat_imports::dat.eq(dat),
+                schema::dat_imports::total.eq(total),
+                schema::dat_imports::dry_run.eq(dry_run),
+            ))
+            .returning(schema::dat_imports::all_columns)
+            .get_result::<Self>(db)
+            .await
+    }
+
+    pub async fn get(
+        db: &mut AsyncPgConnection,
+        id: i32,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        schema::dat_imports::table
+            .filter(schema::dat_imports::id.eq(id))
+            .first::<Self>(db)
+            .await
+            .optional()
+    }
+
+    /// List the imports waiting to be run.
+    pub async fn list_pending(
+        db: &mut AsyncPgConnection,
+    ) -> Result<Vec<Self>, diesel::result::Error> {
+        schema::dat_imports::table
+            .filter(
+                schema::dat_imports::status.eq(dto::games::DatImportStatus::Pending.to_string()),
+            )
+            .order(schema::dat_imports::id)
+            .load::<Self>(db)
+            .await
+    }
+
+    /// Fail running imports that were not updated since `updated_before`,
+    /// e.g. because the server running them stopped. Returns how many
+    /// imports failed.
+    pub async fn fail_stale(
+        db: &mut AsyncPgConnection,
+        updated_before: NaiveDateTime,
+        error: &str,
+    ) -> Result<usize, diesel::result::Error> {
+        let now = chrono::Utc::now().naive_utc();
+        diesel::update(schema::dat_imports::table)
+            .filter(
+                schema::dat_imports::status.eq(dto::games::DatImportStatus::Running.to_string()),
+            )
+            .filter(schema::dat_imports::updated_at.lt(updated_before))
+            .set((
+                schema::dat_imports::status.eq(dto::games::DatImportStatus::Failed.to_string()),
+                schema::dat_imports::error.eq(error),
+                schema::dat_imports::dat.eq(None::<Vec<u8>>),
+                schema::dat_imports::updated_at.eq(now),
+                schema::dat_imports::finished_at.eq(now),
+            ))
+            .execute(db)
+            .await
+    }
+
+    /// Mark a pending import as running. Returns `None` if it was not
+    /// pending, e.g. if another job already started it.
+    pub async fn start(
+        db: &mut AsyncPgConnection,
+        id: i32,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        diesel::update(schema::dat_imports::table)
+            .filter(schema::dat_imports::id.eq(id))
+            .filter(
+                schema::dat_imports::status.eq(dto::games::DatImportStatus::Pending.to_string()),
+            )
+            .set((
+                schema::dat_imports::status.eq(dto::games::DatImportStatus::Running.to_string()),
+                schema::dat_imports::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .returning(schema::dat_imports::all_columns)
+            .get_result::<Self>(db)
+            .await
+            .optional()
+    }
+
+    /// Queue the import of a dry run whose preview is ready. Returns `None`
+    /// if it was not a preview.
+    pub async fn apply(
+        &self,
+        db: &mut AsyncPgConnection,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        diesel::update(schema::dat_imports::table)
+            .filter(schema::dat_imports::id.eq(self.id))
+            .filter(
+                schema::dat_imports::status.eq(dto::games::DatImportStatus::Preview.to_string()),
+            )
+            .set((
+                schema::dat_imports::status.eq(dto::games::DatImportStatus::Pending.to_string()),
+                schema::dat_imports::dry_run.eq(false),
+                schema::dat_imports::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .returning(schema::dat_imports::all_columns)
+            .get_result::<Self>(db)
+            .await
+            .optional()
+    }
+
+    pub async fn set_preview(
+        &self,
+        db: &mut AsyncPgConnection,
+        preview: &dto::games::DatImportPreview,
+    ) -> Result<(), diesel::result::Error> {
+        let preview = serde_json::to_value(preview)
+            .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?;
+
+        diesel::update(schema::dat_imports::table)
+            .filter(schema::dat_imports::id.eq(self.id))
+            .set((
+                schema::dat_imports::preview.eq(preview),
+                schema::dat_imports::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .execute(db)
+            .await
+            .map(|_| ())
+    }
+
+    pub async fn set_processed(
+        &self,
+        db: &mut AsyncPgConnection,
+        processed: i32,
+    ) -> Result<(), diesel::result::Error> {
+        diesel::update(schema::dat_imports::table)
+            .filter(schema::dat_imports::id.eq(self.id))
+            .set((
+                schema::dat_imports::processed.eq(processed),
+                schema::dat_imports::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .execute(db)
+            .await
+            .map(|_| ())
+    }
+
+    /// Stop running the import. The DAT is only kept for previews, so they
+    /// can be applied later.
+    pub async fn finish(
+        &self,
+        db: &mut AsyncPgConnection,
+        status: dto::games::DatImportStatus,
+        error: Option<&str>,
+    ) -> Result<(), diesel::result::Error> {
+        #[derive(AsChangeset)]
+        #[diesel(table_name = schema::dat_imports)]
+        struct FinishChangeset<'a> {
+            status: String,
+            error: Option<&'a str>,
+            dat: Option<Option<Vec<u8>>>,
+            updated_at: NaiveDateTime,
+            finished_at: Option<NaiveDateTime>,
+        }
+
+        let now = chrono::Utc::now().naive_utc();
+        let preview = status == dto::games::DatImportStatus::Preview;
+        diesel::update(schema::dat_imports::table)
+            .filter(schema::dat_imports::id.eq(self.id))
+            .set(FinishChangeset {
+                status: status.to_string(),
+                error,
+                dat: if preview { None } else { Some(None) },
+                updated_at: now,
+                finished_at: if preview { None } else { Some(now) },
+            })
+            .execute(db)
+            .await
+            .map(|_| ())
+    }
+}
+
+/// An artifact of a game in a DAT, known only through its checksums.
+#[derive(Debug, Clone)]
+pub struct DatImportArtifact {
+    pub filename: String,
+    pub path: String,
+    pub role: dto::games::GameArtifactRole,
+    pub order: i32,
+    pub status: dto::games::GameArtifactStatus,
+    pub size: i64,
+    pub crc32: Option<Vec<u8>>,
+    pub md5: Option<Vec<u8>>,
+    pub sha1: Option<Vec<u8>>,
+    pub sha256: Option<Vec<u8>>,
+}
+
+/// What importing a game of a DAT does.
+#[derive(Debug, Clone)]
+pub enum DatImportChange {
+    Create {
+        system_unique_id: i32,
+        name: String,
+        description: String,
+        year: i32,
+        publisher: String,
+    },
+    /// Update an existing game. Fields that are `None` do not change.
+    Update {
+        game_id: i32,
+        system_unique_id: Option<i32>,
+        name: Option<String>,
+        description: Option<String>,
+        year: Option<i32>,
+        publisher: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct DatImportEntry {
+    pub change: DatImportChange,
+
+    /// Artifacts to add to the game.
+    pub artifacts: Vec<DatImportArtifact>,
+}
+
+impl DatImport {
+    /// Import a batch of games in a single transaction, along with the
+    /// progress of the import.
+    pub async fn import_batch(
+        &self,
+        db: &mut AsyncPgConnection,
+        entries: &[DatImportEntry],
+        processed: i32,
+    ) -> Result<(), diesel::result::Error> {
+        db.transaction(|db| {
+            async move {
+                for DatImportEntry { change, artifacts } in entries {
+                    let game_id = match change {
+                        DatImportChange::Create {
+                            system_unique_id,
+                            name,
+                            description,
+                            year,
+                            publisher,
+                        } => {
+                            Game::create_from_dat(
+                                db,
+                                self.system_id,
+                                *system_unique_id,
+                                name,
+                                description,
+                                *year,
+                                publisher,
+                                self.id,
+                            )
+                            .await?
+                            .id
+                        }
+                        DatImportChange::Update {
+                            game_id,
+                            system_unique_id,
+                            name,
+                            description,
+                            year,
+                            publisher,
+                        } => {
+                            Game::update_from_dat(
+                                db,
+                                *game_id,
+                                name.as_deref(),
+                                description.as_deref(),
+                                *year,
+                                publisher.as_deref(),
+                                *system_unique_id,
+                                self.id,
+                            )
+                            .await?;
+                            *game_id
+                        }
+                    };
+
+                    for a in artifacts {
+                        let artifact = Artifact::create_with_checksum(
+                            db,
+                            &a.filename,
+                            "application/octet-stream",
+                            a.crc32.as_deref(),
+                            a.md5.as_deref(),
+                            a.sha1.as_deref(),
+                            a.sha256.as_deref(),
+                            None,
+                            a.size,
+                        )
+                        .await?;
+                        GameArtifact::create(
+                            db,
+                            game_id,
+                            artifact.id,
+                            &a.filename,
+                            &a.path,
+                            a.role,
+                            a.order,
+                            a.status,
+                        )
+                        .await?;
+                    }
+                }
+
+                self.set_processed(db, processed).await
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+}
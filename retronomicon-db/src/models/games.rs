@@ -4,16 +4,26 @@ use crate::{models, schema};
 use diesel::deserialize::FromSql;
 use diesel::prelude::*;
 use diesel::{AsExpression, FromSqlRow};
+use retronomicon_dto as dto;
 use retronomicon_dto::artifact::ArtifactRef;
 use retronomicon_dto::types::IdOrSlug;
 use rocket::http::Status;
-use rocket_db_pools::diesel::{AsyncConnection, RunQueryDsl};
+use rocket_db_pools::diesel::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
 use scoped_futures::ScopedFutureExt;
 use serde_json::{Value as Json, Value};
 use std::collections::BTreeMap;
 use std::num::NonZeroUsize;
 use std::ops::Bound;
 
+mod metadata;
+pub use metadata::*;
+
+mod relationships;
+pub use relationships::*;
+
+mod translations;
+pub use translations::*;
+
 #[derive(Queryable, Debug, Identifiable)]
 #[diesel(table_name = schema::games)]
 pub struct Game {
@@ -22,11 +32,25 @@ pub struct Game {
     pub description: String,
     pub short_description: String,
     pub year: i32,
-    pub publisher: String,
-    pub developer: String,
     pub links: Json,
     pub system_id: i32,
     pub system_unique_id: i32,
+    pub dat_import_id: Option<i32>,
+    pub min_players: Option<i32>,
+    pub max_players: Option<i32>,
+}
+
+/// Filters on the metadata of games, by slug.
+#[derive(Debug, Default)]
+pub struct GameMetadataFilter<'a> {
+    pub region: Option<&'a str>,
+    pub language: Option<&'a str>,
+    pub genre: Option<&'a str>,
+    pub series: Option<&'a str>,
+    pub publisher: Option<&'a str>,
+    pub developer: Option<&'a str>,
+    pub tag: Option<&'a str>,
+    pub players: Option<i32>,
 }
 
 #[derive(Queryable, Debug, Identifiable)]
@@ -37,23 +61,106 @@ pub struct Game {
 pub struct GameArtifact {
     pub game_id: i32,
     pub artifact_id: i32,
+    pub filename: String,
+    pub path: String,
+    pub role: String,
+    pub sort_order: i32,
+    pub status: String,
 }
 
 impl GameArtifact {
     pub async fn create(
-        db: &mut Db,
+        db: &mut AsyncPgConnection,
         game_id: i32,
         artifact_id: i32,
+        filename: &str,
+        path: &str,
+        role: dto::games::GameArtifactRole,
+        sort_order: i32,
+        status: dto::games::GameArtifactStatus,
     ) -> Result<Self, diesel::result::Error> {
         diesel::insert_into(schema::game_artifacts::table)
             .values((
                 schema::game_artifacts::game_id.eq(game_id),
                 schema::game_artifacts::artifact_id.eq(artifact_id),
+                schema::game_artifacts::filename.eq(filename),
+                schema::game_artifacts::path.eq(path),
+                schema::game_artifacts::role.eq(role.to_string()),
+                schema::game_artifacts::sort_order.eq(sort_order),
+                schema::game_artifacts::status.eq(status.to_string()),
             ))
             .returning(schema::game_artifacts::all_columns)
             .get_result::<Self>(db)
             .await
     }
+
+    /// The files of a game, in order.
+    pub async fn list(
+        db: &mut AsyncPgConnection,
+        game_id: i32,
+    ) -> Result<Vec<(Self, Artifact)>, diesel::result::Error> {
+        schema::game_artifacts::table
+            .inner_join(schema::artifacts::table)
+            .filter(schema::game_artifacts::game_id.eq(game_id))
+            .order_by((
+                schema::game_artifacts::sort_order.asc(),
+                schema::game_artifacts::path.asc(),
+                schema::game_artifacts::filename.asc(),
+            ))
+            .select((
+                schema::game_artifacts::all_columns,
+                schema::artifacts::all_columns,
+            ))
+            .load(db)
+            .await
+    }
+
+    /// The files of games, in order for each game.
+    pub async fn list_for_games(
+        db: &mut AsyncPgConnection,
+        game_ids: &[i32],
+    ) -> Result<Vec<(Self, Artifact)>, diesel::result::Error> {
+        schema::game_artifacts::table
+            .inner_join(schema::artifacts::table)
+            .filter(schema::game_artifacts::game_id.eq_any(game_ids))
+            .order_by((
+                schema::game_artifacts::game_id.asc(),
+                schema::game_artifacts::sort_order.asc(),
+                schema::game_artifacts::path.asc(),
+                schema::game_artifacts::filename.asc(),
+            ))
+            .select((
+                schema::game_artifacts::all_columns,
+                schema::artifacts::all_columns,
+            ))
+            .load(db)
+            .await
+    }
+
+    /// The position after the last file of a game.
+    pub async fn next_order(
+        db: &mut AsyncPgConnection,
+        game_id: i32,
+    ) -> Result<i32, diesel::result::Error> {
+        schema::game_artifacts::table
+            .filter(schema::game_artifacts::game_id.eq(game_id))
+            .select(diesel::dsl::max(schema::game_artifacts::sort_order))
+            .first::<Option<i32>>(db)
+            .await
+            .map(|max| max.map_or(0, |m| m + 1))
+    }
+
+    pub fn into_dto(self, artifact: Artifact) -> dto::games::GameArtifact {
+        dto::games::GameArtifact {
+            id: self.artifact_id,
+            filename: self.filename,
+            path: self.path,
+            role: self.role.parse().unwrap_or_default(),
+            order: self.sort_order,
+            status: self.status.parse().unwrap_or_default(),
+            artifact: artifact.into(),
+        }
+    }
 }
 
 impl Game {
@@ -63,27 +170,36 @@ impl Game {
         description: &str,
         short_description: &str,
         year: i32,
-        publisher: &str,
-        developer: &str,
         links: Json,
         system_id: i32,
         system_unique_id: i32,
+        min_players: Option<i32>,
+        max_players: Option<i32>,
+        metadata: GameMetadataUpdate<'_>,
     ) -> Result<Self, diesel::result::Error> {
-        diesel::insert_into(schema::games::table)
-            .values((
-                schema::games::name.eq(name),
-                schema::games::description.eq(description),
-                schema::games::short_description.eq(short_description),
-                schema::games::year.eq(year),
-                schema::games::publisher.eq(publisher),
-                schema::games::developer.eq(developer),
-                schema::games::links.eq(links),
-                schema::games::system_id.eq(system_id),
-                schema::games::system_unique_id.eq(system_unique_id),
-            ))
-            .returning(schema::games::all_columns)
-            .get_result::<Self>(db)
-            .await
+        db.transaction(|db| {
+            async move {
+                let game = diesel::insert_into(schema::games::table)
+                    .values((
+                        schema::games::name.eq(name),
+                        schema::games::description.eq(description),
+                        schema::games::short_description.eq(short_description),
+                        schema::games::year.eq(year),
+                        schema::games::links.eq(links),
+                        schema::games::system_id.eq(system_id),
+                        schema::games::system_unique_id.eq(system_unique_id),
+                        schema::games::min_players.eq(min_players),
+                        schema::games::max_players.eq(max_players),
+                    ))
+                    .returning(schema::games::all_columns)
+                    .get_result::<Self>(db)
+                    .await?;
+                metadata.apply(db, game.id).await?;
+                Ok(game)
+            }
+            .scope_boxed()
+        })
+        .await
     }
 
     pub async fn list(
@@ -94,6 +210,7 @@ impl Game {
         year: (Bound<i32>, Bound<i32>),
         name: Option<&str>,
         exact_name: Option<&str>,
+        metadata: GameMetadataFilter<'_>,
         md5: Vec<Vec<u8>>,
         sha1: Vec<Vec<u8>>,
         sha256: Vec<Vec<u8>>,
@@ -133,13 +250,93 @@ impl Game {
         };
 
         if let Some(name) = name {
-            query = query.filter(dsl::name.ilike(format!("%{}%", name)));
+            let pattern = format!("%{}%", name);
+            query = query.filter(
+                dsl::name.ilike(pattern.clone()).or(dsl::id.eq_any(
+                    schema::game_translations::table
+                        .filter(schema::game_translations::name.ilike(pattern))
+                        .select(schema::game_translations::game_id),
+                )),
+            );
         }
 
         if let Some(name) = exact_name {
             query = query.filter(dsl::name.eq(name));
         }
 
+        if let Some(region) = metadata.region {
+            query = query.filter(
+                dsl::id.eq_any(
+                    schema::game_releases::table
+                        .inner_join(schema::regions::table)
+                        .filter(schema::regions::slug.eq(region))
+                        .select(schema::game_releases::game_id),
+                ),
+            );
+        }
+        if let Some(language) = metadata.language {
+            query = query.filter(
+                dsl::id.eq_any(
+                    schema::game_languages::table
+                        .filter(schema::game_languages::language.eq(language))
+                        .select(schema::game_languages::game_id),
+                ),
+            );
+        }
+        if let Some(genre) = metadata.genre {
+            query = query.filter(
+                dsl::id.eq_any(
+                    schema::game_genres::table
+                        .inner_join(schema::genres::table)
+                        .filter(schema::genres::slug.eq(genre))
+                        .select(schema::game_genres::game_id),
+                ),
+            );
+        }
+        if let Some(series) = metadata.series {
+            query = query.filter(
+                dsl::id.eq_any(
+                    schema::game_series::table
+                        .inner_join(schema::series::table)
+                        .filter(schema::series::slug.eq(series))
+                        .select(schema::game_series::game_id),
+                ),
+            );
+        }
+        if let Some(tag) = metadata.tag {
+            query = query.filter(
+                dsl::id.eq_any(
+                    schema::game_tags::table
+                        .inner_join(schema::tags::table)
+                        .filter(schema::tags::slug.eq(tag))
+                        .select(schema::game_tags::game_id),
+                ),
+            );
+        }
+        for (role, company) in [
+            (dto::games::CompanyRole::Publisher, metadata.publisher),
+            (dto::games::CompanyRole::Developer, metadata.developer),
+        ] {
+            if let Some(company) = company {
+                query = query.filter(
+                    dsl::id.eq_any(
+                        schema::game_companies::table
+                            .inner_join(schema::companies::table)
+                            .filter(schema::game_companies::role.eq(role.to_string()))
+                            .filter(schema::companies::slug.eq(company))
+                            .select(schema::game_companies::game_id),
+                    ),
+                );
+            }
+        }
+        if let Some(players) = metadata.players {
+            query = query.filter(
+                dsl::min_players
+                    .le(players)
+                    .and(dsl::max_players.ge(players)),
+            );
+        }
+
         if !md5.is_empty() {
             query = query.filter((schema::artifacts::dsl::md5).eq_any(md5));
         }
@@ -163,6 +360,213 @@ impl Game {
             .await
     }
 
+    /// The games of a system to export in a DAT, by name, after the game
+    /// with name and ID `after` (excluded) if any.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn list_for_export(
+        db: &mut AsyncPgConnection,
+        system_id: i32,
+        year: (Bound<i32>, Bound<i32>),
+        region: Option<&str>,
+        tag: Option<&str>,
+        after: Option<(&str, i32)>,
+        limit: i64,
+    ) -> Result<Vec<Self>, diesel::result::Error> {
+        use schema::games::dsl;
+
+        let mut query = schema::games::table
+            .filter(dsl::system_id.eq(system_id))
+            .into_boxed();
+
+        query = match year.0 {
+            Bound::Included(s) => query.filter(dsl::year.ge(s)),
+            Bound::Excluded(s) => query.filter(dsl::year.gt(s)),
+            Bound::Unbounded => query,
+        };
+        query = match year.1 {
+            Bound::Included(e) => query.filter(dsl::year.le(e)),
+            Bound::Excluded(e) => query.filter(dsl::year.lt(e)),
+            Bound::Unbounded => query,
+        };
+
+        if let Some(region) = region {
+            query = query.filter(
+                dsl::id.eq_any(
+                    schema::game_releases::table
+                        .inner_join(schema::regions::table)
+                        .filter(schema::regions::slug.eq(region))
+                        .select(schema::game_releases::game_id),
+                ),
+            );
+        }
+        if let Some(tag) = tag {
+            query = query.filter(
+                dsl::id.eq_any(
+                    schema::game_tags::table
+                        .inner_join(schema::tags::table)
+                        .filter(schema::tags::slug.eq(tag))
+                        .select(schema::game_tags::game_id),
+                ),
+            );
+        }
+
+        if let Some((name, id)) = after {
+            query = query.filter(
+                dsl::name
+                    .gt(name)
+                    .or(dsl::name.eq(name).and(dsl::id.gt(id))),
+            );
+        }
+
+        query
+            .order_by((dsl::name.asc(), dsl::id.asc()))
+            .limit(limit)
+            .load(db)
+            .await
+    }
+
+    /// The game artifacts matching any of the checksums, with their games.
+    /// CRC32 checksums are not unique, so callers should also compare sizes.
+    pub async fn identify(
+        db: &mut Db,
+        system: Option<IdOrSlug<'_>>,
+        crc32: Vec<Vec<u8>>,
+        md5: Vec<Vec<u8>>,
+        sha1: Vec<Vec<u8>>,
+        sha256: Vec<Vec<u8>>,
+    ) -> Result<Vec<(Self, System, Artifact)>, diesel::result::Error> {
+        use schema::artifacts::dsl as artifacts;
+
+        let mut query = schema::games::table
+            .inner_join(schema::systems::table)
+            .inner_join(schema::game_artifacts::table)
+            .inner_join(
+                schema::artifacts::table
+                    .on(schema::artifacts::id.eq(schema::game_artifacts::artifact_id)),
+            )
+            .filter(
+                artifacts::md5
+                    .eq_any(md5)
+                    .or(artifacts::sha1.eq_any(sha1))
+                    .or(artifacts::sha256.eq_any(sha256))
+                    .or(artifacts::crc32.eq_any(crc32)),
+            )
+            .select((
+                schema::games::all_columns,
+                schema::systems::all_columns,
+                schema::artifacts::all_columns,
+            ))
+            .into_boxed();
+
+        if let Some(system) = system {
+            if let Some(system_id) = system.as_id() {
+                query = query.filter(schema::games::dsl::system_id.eq(system_id));
+            } else if let Some(system_slug) = system.as_slug() {
+                query = query.filter(schema::systems::dsl::slug.eq(system_slug.to_string()));
+            }
+        }
+
+        query
+            .order_by(schema::games::dsl::name.asc())
+            .load(db)
+            .await
+    }
+
+    /// All the games of a system with their artifacts, for importing a DAT.
+    pub async fn list_for_import(
+        db: &mut AsyncPgConnection,
+        system_id: i32,
+    ) -> Result<Vec<(Self, Option<Artifact>)>, diesel::result::Error> {
+        schema::games::table
+            .left_join(
+                schema::game_artifacts::table.inner_join(
+                    schema::artifacts::table
+                        .on(schema::artifacts::id.eq(schema::game_artifacts::artifact_id)),
+                ),
+            )
+            .filter(schema::games::dsl::system_id.eq(system_id))
+            .order_by(schema::games::dsl::id.asc())
+            .select((schema::games::all_columns, Option::<Artifact>::as_select()))
+            .load(db)
+            .await
+    }
+
+    /// Create a game from an entry of a DAT.
+    pub async fn create_from_dat(
+        db: &mut AsyncPgConnection,
+        system_id: i32,
+        system_unique_id: i32,
+        name: &str,
+        description: &str,
+        year: i32,
+        publisher: &str,
+        dat_import_id: i32,
+    ) -> Result<Self, diesel::result::Error> {
+        let game = diesel::insert_into(schema::games::table)
+            .values((
+                schema::games::name.eq(name),
+                schema::games::description.eq(description),
+                schema::games::short_description.eq(""),
+                schema::games::year.eq(year),
+                schema::games::links.eq(serde_json::json!({})),
+                schema::games::system_id.eq(system_id),
+                schema::games::system_unique_id.eq(system_unique_id),
+                schema::games::dat_import_id.eq(dat_import_id),
+            ))
+            .returning(schema::games::all_columns)
+            .get_result::<Self>(db)
+            .await?;
+
+        GameMetadataUpdate {
+            publishers: Some(vec![publisher]),
+            ..Default::default()
+        }
+        .apply(db, game.id)
+        .await?;
+        Ok(game)
+    }
+
+    /// Update a game from an entry of a DAT. Fields that are `None` are left
+    /// as is, but the game is always marked as coming from the import.
+    pub async fn update_from_dat(
+        db: &mut AsyncPgConnection,
+        id: i32,
+        name: Option<&str>,
+        description: Option<&str>,
+        year: Option<i32>,
+        publisher: Option<&str>,
+        system_unique_id: Option<i32>,
+        dat_import_id: i32,
+    ) -> Result<(), diesel::result::Error> {
+        #[derive(AsChangeset)]
+        #[diesel(table_name = schema::games)]
+        struct DatChangeset<'a> {
+            name: Option<&'a str>,
+            description: Option<&'a str>,
+            year: Option<i32>,
+            system_unique_id: Option<i32>,
+            dat_import_id: i32,
+        }
+
+        diesel::update(schema::games::table.filter(schema::games::dsl::id.eq(id)))
+            .set(DatChangeset {
+                name,
+                description,
+                year,
+                system_unique_id,
+                dat_import_id,
+            })
+            .execute(db)
+            .await?;
+
+        GameMetadataUpdate {
+            publishers: publisher.map(|p| vec![p]),
+            ..Default::default()
+        }
+        .apply(db, id)
+        .await
+    }
+
     pub async fn find_by_sha256(
         db: &mut Db,
         page: i64,
@@ -259,11 +663,12 @@ impl Game {
         description: Option<&'_ str>,
         short_description: Option<&'_ str>,
         year: Option<i32>,
-        publisher: Option<&'_ str>,
-        developer: Option<&'_ str>,
         add_links: Option<BTreeMap<&'_ str, &'_ str>>,
         remove_links: Option<Vec<&'_ str>>,
         system_unique_id: Option<i32>,
+        min_players: Option<i32>,
+        max_players: Option<i32>,
+        metadata: GameMetadataUpdate<'_>,
     ) -> Result<(), diesel::result::Error> {
         #[derive(AsChangeset)]
         #[diesel(table_name = schema::games)]
@@ -272,10 +677,10 @@ impl Game {
             description: Option<&'a str>,
             short_description: Option<&'a str>,
             year: Option<i32>,
-            publisher: Option<&'a str>,
-            developer: Option<&'a str>,
             links: Option<Json>,
             system_unique_id: Option<i32>,
+            min_players: Option<i32>,
+            max_players: Option<i32>,
         }
 
         db.transaction(|db| {
@@ -285,10 +690,10 @@ impl Game {
                     description,
                     short_description,
                     year,
-                    publisher,
-                    developer,
                     links: None,
                     system_unique_id,
+                    min_players,
+                    max_players,
                 };
 
                 if add_links.is_some() || remove_links.is_some() {
@@ -315,12 +720,23 @@ impl Game {
                     changeset.links = Some(serde_json::to_value(links).unwrap());
                 }
 
-                diesel::update(schema::games::table.filter(schema::games::dsl::id.eq(id)))
-                    .set(changeset)
-                    .execute(db)
-                    .await?;
-
-                Ok(())
+                // Diesel refuses to run an update without any column to set,
+                // which happens when only the metadata of the game changes.
+                let has_changes = changeset.name.is_some()
+                    || changeset.description.is_some()
+                    || changeset.short_description.is_some()
+                    || changeset.year.is_some()
+                    || changeset.links.is_some()
+                    || changeset.system_unique_id.is_some()
+                    || changeset.min_players.is_some()
+                    || changeset.max_players.is_some();
+                if has_changes {
+                    diesel::update(schema::games::table.filter(schema::games::dsl::id.eq(id)))
+                        .set(changeset)
+                        .execute(db)
+                        .await?;
+                }
+                metadata.apply(db, id).await
             }
             .scope_boxed()
         })
@@ -0,0 +1,565 @@
+use crate::models::{Game, Tag};
+use crate::schema;
+use crate::Db;
+use chrono::NaiveDate;
+use diesel::prelude::*;
+use retronomicon_dto as dto;
+use rocket_db_pools::diesel::{AsyncPgConnection, RunQueryDsl};
+use std::collections::BTreeMap;
+
+/// The slug of a company or series from its name. Names without ASCII
+/// letters or digits are their own slug. This must match the migration
+/// that created companies from the publishers and developers of games.
+pub fn slugify(name: &str) -> String {
+    let slug = name
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        name.trim().to_lowercase()
+    } else {
+        slug
+    }
+}
+
+/// Names by slug, in the order they were first given. Empty names are
+/// ignored.
+fn names_by_slug<'a>(names: &[&'a str]) -> Vec<(String, &'a str)> {
+    let mut result: Vec<(String, &str)> = Vec::new();
+    for name in names.iter().map(|n| n.trim()).filter(|n| !n.is_empty()) {
+        let slug = slugify(name);
+        if !result.iter().any(|(s, _)| *s == slug) {
+            result.push((slug, name));
+        }
+    }
+    result
+}
+
+#[derive(Queryable, Selectable, Debug, Clone, Identifiable)]
+#[diesel(table_name = schema::companies)]
+pub struct Company {
+    pub id: i32,
+    pub slug: String,
+    pub name: String,
+}
+
+impl From<Company> for dto::games::CompanyRef {
+    fn from(value: Company) -> Self {
+        Self {
+            id: value.id,
+            slug: value.slug,
+            name: value.name,
+        }
+    }
+}
+
+impl Company {
+    pub async fn list(
+        db: &mut Db,
+        page: i64,
+        limit: i64,
+    ) -> Result<Vec<Self>, diesel::result::Error> {
+        schema::companies::table
+            .select(Self::as_select())
+            .order_by(schema::companies::name.asc())
+            .offset(page * limit)
+            .limit(limit)
+            .load(db)
+            .await
+    }
+
+    /// Get companies by name, creating the unknown ones. Names with the same
+    /// slug are the same company.
+    pub async fn get_or_create(
+        db: &mut AsyncPgConnection,
+        names: &[&str],
+    ) -> Result<Vec<Self>, diesel::result::Error> {
+        let names = names_by_slug(names);
+        if names.is_empty() {
+            return Ok(vec![]);
+        }
+
+        diesel::insert_into(schema::companies::table)
+            .values(
+                names
+                    .iter()
+                    .map(|(slug, name)| {
+                        (
+                            schema::companies::slug.eq(slug),
+                            schema::companies::name.eq(name),
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .on_conflict(schema::companies::slug)
+            .do_nothing()
+            .execute(db)
+            .await?;
+
+        let mut companies = schema::companies::table
+            .filter(schema::companies::slug.eq_any(names.iter().map(|(slug, _)| slug)))
+            .select(Self::as_select())
+            .load::<Self>(db)
+            .await?
+            .into_iter()
+            .map(|c| (c.slug.clone(), c))
+            .collect::<BTreeMap<_, _>>();
+        Ok(names
+            .iter()
+            .filter_map(|(slug, _)| companies.remove(slug))
+            .collect())
+    }
+
+    /// The companies of all games of a system with a role, by game ID.
+    pub async fn list_for_system(
+        db: &mut AsyncPgConnection,
+        system_id: i32,
+        role: dto::games::CompanyRole,
+    ) -> Result<Vec<(i32, Self)>, diesel::result::Error> {
+        schema::game_companies::table
+            .inner_join(schema::companies::table)
+            .inner_join(schema::games::table)
+            .filter(schema::games::system_id.eq(system_id))
+            .filter(schema::game_companies::role.eq(role.to_string()))
+            .order_by(schema::companies::name.asc())
+            .select((schema::game_companies::game_id, Self::as_select()))
+            .load(db)
+            .await
+    }
+
+    /// The companies of games with a role, by game ID.
+    pub async fn list_for_games(
+        db: &mut AsyncPgConnection,
+        game_ids: &[i32],
+        role: dto::games::CompanyRole,
+    ) -> Result<Vec<(i32, Self)>, diesel::result::Error> {
+        schema::game_companies::table
+            .inner_join(schema::companies::table)
+            .filter(schema::game_companies::game_id.eq_any(game_ids))
+            .filter(schema::game_companies::role.eq(role.to_string()))
+            .order_by(schema::companies::name.asc())
+            .select((schema::game_companies::game_id, Self::as_select()))
+            .load(db)
+            .await
+    }
+}
+
+#[derive(Queryable, Selectable, Debug, Clone, Identifiable)]
+#[diesel(table_name = schema::genres)]
+pub struct Genre {
+    pub id: i32,
+    pub slug: String,
+    pub name: String,
+}
+
+impl From<Genre> for dto::games::GenreRef {
+    fn from(value: Genre) -> Self {
+        Self {
+            id: value.id,
+            slug: value.slug,
+            name: value.name,
+        }
+    }
+}
+
+impl Genre {
+    pub async fn list(db: &mut Db) -> Result<Vec<Self>, diesel::result::Error> {
+        schema::genres::table
+            .select(Self::as_select())
+            .order_by(schema::genres::name.asc())
+            .load(db)
+            .await
+    }
+
+    /// The genres with these slugs. Unknown slugs are ignored.
+    pub async fn get_by_slugs(
+        db: &mut Db,
+        slugs: &[&str],
+    ) -> Result<Vec<Self>, diesel::result::Error> {
+        schema::genres::table
+            .filter(schema::genres::slug.eq_any(slugs))
+            .select(Self::as_select())
+            .load(db)
+            .await
+    }
+}
+
+#[derive(Queryable, Selectable, Debug, Clone, Identifiable)]
+#[diesel(table_name = schema::regions)]
+pub struct Region {
+    pub id: i32,
+    pub slug: String,
+    pub name: String,
+}
+
+impl From<Region> for dto::games::RegionRef {
+    fn from(value: Region) -> Self {
+        Self {
+            id: value.id,
+            slug: value.slug,
+            name: value.name,
+        }
+    }
+}
+
+impl Region {
+    pub async fn list(db: &mut Db) -> Result<Vec<Self>, diesel::result::Error> {
+        schema::regions::table
+            .select(Self::as_select())
+            .order_by(schema::regions::name.asc())
+            .load(db)
+            .await
+    }
+
+    /// The regions with these slugs. Unknown slugs are ignored.
+    pub async fn get_by_slugs(
+        db: &mut Db,
+        slugs: &[&str],
+    ) -> Result<Vec<Self>, diesel::result::Error> {
+        schema::regions::table
+            .filter(schema::regions::slug.eq_any(slugs))
+            .select(Self::as_select())
+            .load(db)
+            .await
+    }
+}
+
+#[derive(Queryable, Selectable, Debug, Clone, Identifiable)]
+#[diesel(table_name = schema::series)]
+pub struct Series {
+    pub id: i32,
+    pub slug: String,
+    pub name: String,
+}
+
+impl From<Series> for dto::games::SeriesRef {
+    fn from(value: Series) -> Self {
+        Self {
+            id: value.id,
+            slug: value.slug,
+            name: value.name,
+        }
+    }
+}
+
+impl Series {
+    pub async fn list(
+        db: &mut Db,
+        page: i64,
+        limit: i64,
+    ) -> Result<Vec<Self>, diesel::result::Error> {
+        schema::series::table
+            .select(Self::as_select())
+            .order_by(schema::series::name.asc())
+            .offset(page * limit)
+            .limit(limit)
+            .load(db)
+            .await
+    }
+
+    /// Get series by name, creating the unknown ones.
+    pub async fn get_or_create(
+        db: &mut AsyncPgConnection,
+        names: &[&str],
+    ) -> Result<Vec<Self>, diesel::result::Error> {
+        let names = names_by_slug(names);
+        if names.is_empty() {
+            return Ok(vec![]);
+        }
+
+        diesel::insert_into(schema::series::table)
+            .values(
+                names
+                    .iter()
+                    .map(|(slug, name)| {
+                        (schema::series::slug.eq(slug), schema::series::name.eq(name))
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .on_conflict(schema::series::slug)
+            .do_nothing()
+            .execute(db)
+            .await?;
+
+        let mut series = schema::series::table
+            .filter(schema::series::slug.eq_any(names.iter().map(|(slug, _)| slug)))
+            .select(Self::as_select())
+            .load::<Self>(db)
+            .await?
+            .into_iter()
+            .map(|s| (s.slug.clone(), s))
+            .collect::<BTreeMap<_, _>>();
+        Ok(names
+            .iter()
+            .filter_map(|(slug, _)| series.remove(slug))
+            .collect())
+    }
+}
+
+/// The metadata of a game stored outside of the `games` table.
+#[derive(Debug, Default)]
+pub struct GameMetadata {
+    pub publishers: Vec<Company>,
+    pub developers: Vec<Company>,
+    pub releases: Vec<(Region, Option<NaiveDate>)>,
+    pub languages: Vec<String>,
+    pub genres: Vec<Genre>,
+    pub series: Vec<Series>,
+    pub tags: Vec<Tag>,
+}
+
+/// Changes to the metadata of a game. Lists that are `None` are left as is,
+/// others replace the existing ones.
+#[derive(Debug, Default)]
+pub struct GameMetadataUpdate<'a> {
+    /// Names of the publishers. Unknown companies are created.
+    pub publishers: Option<Vec<&'a str>>,
+    /// Names of the developers. Unknown companies are created.
+    pub developers: Option<Vec<&'a str>>,
+    /// IDs of regions, with their release dates.
+    pub releases: Option<Vec<(i32, Option<NaiveDate>)>>,
+    /// BCP 47 tags of the languages.
+    pub languages: Option<Vec<&'a str>>,
+    /// IDs of the genres.
+    pub genres: Option<Vec<i32>>,
+    /// Names of the series. Unknown series are created.
+    pub series: Option<Vec<&'a str>>,
+    /// IDs of the tags.
+    pub tags: Option<Vec<i32>>,
+}
+
+impl GameMetadataUpdate<'_> {
+    async fn set_companies(
+        db: &mut AsyncPgConnection,
+        game_id: i32,
+        role: dto::games::CompanyRole,
+        names: &[&str],
+    ) -> Result<(), diesel::result::Error> {
+        diesel::delete(
+            schema::game_companies::table
+                .filter(schema::game_companies::game_id.eq(game_id))
+                .filter(schema::game_companies::role.eq(role.to_string())),
+        )
+        .execute(db)
+        .await?;
+
+        let companies = Company::get_or_create(db, names).await?;
+        diesel::insert_into(schema::game_companies::table)
+            .values(
+                companies
+                    .iter()
+                    .map(|c| {
+                        (
+                            schema::game_companies::game_id.eq(game_id),
+                            schema::game_companies::company_id.eq(c.id),
+                            schema::game_companies::role.eq(role.to_string()),
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    /// Apply the changes. This should be done in a transaction.
+    pub async fn apply(
+        &self,
+        db: &mut AsyncPgConnection,
+        game_id: i32,
+    ) -> Result<(), diesel::result::Error> {
+        if let Some(publishers) = &self.publishers {
+            Self::set_companies(db, game_id, dto::games::CompanyRole::Publisher, publishers)
+                .await?;
+        }
+        if let Some(developers) = &self.developers {
+            Self::set_companies(db, game_id, dto::games::CompanyRole::Developer, developers)
+                .await?;
+        }
+
+        if let Some(releases) = &self.releases {
+            diesel::delete(
+                schema::game_releases::table.filter(schema::game_releases::game_id.eq(game_id)),
+            )
+            .execute(db)
+            .await?;
+            diesel::insert_into(schema::game_releases::table)
+                .values(
+                    releases
+                        .iter()
+                        .map(|(region_id, date)| {
+                            (
+                                schema::game_releases::game_id.eq(game_id),
+                                schema::game_releases::region_id.eq(region_id),
+                                schema::game_releases::release_date.eq(date),
+                            )
+                        })
+                        .collect::<Vec<_>>(),
+                )
+                .on_conflict_do_nothing()
+                .execute(db)
+                .await?;
+        }
+
+        if let Some(languages) = &self.languages {
+            diesel::delete(
+                schema::game_languages::table.filter(schema::game_languages::game_id.eq(game_id)),
+            )
+            .execute(db)
+            .await?;
+            diesel::insert_into(schema::game_languages::table)
+                .values(
+                    languages
+                        .iter()
+                        .map(|language| {
+                            (
+                                schema::game_languages::game_id.eq(game_id),
+                                schema::game_languages::language.eq(language),
+                            )
+                        })
+                        .collect::<Vec<_>>(),
+                )
+                .on_conflict_do_nothing()
+                .execute(db)
+                .await?;
+        }
+
+        if let Some(genres) = &self.genres {
+            diesel::delete(
+                schema::game_genres::table.filter(schema::game_genres::game_id.eq(game_id)),
+            )
+            .execute(db)
+            .await?;
+            diesel::insert_into(schema::game_genres::table)
+                .values(
+                    genres
+                        .iter()
+                        .map(|genre_id| {
+                            (
+                                schema::game_genres::game_id.eq(game_id),
+                                schema::game_genres::genre_id.eq(genre_id),
+                            )
+                        })
+                        .collect::<Vec<_>>(),
+                )
+                .on_conflict_do_nothing()
+                .execute(db)
+                .await?;
+        }
+
+        if let Some(series) = &self.series {
+            diesel::delete(
+                schema::game_series::table.filter(schema::game_series::game_id.eq(game_id)),
+            )
+            .execute(db)
+            .await?;
+            let series = Series::get_or_create(db, series).await?;
+            diesel::insert_into(schema::game_series::table)
+                .values(
+                    series
+                        .iter()
+                        .map(|s| {
+                            (
+                                schema::game_series::game_id.eq(game_id),
+                                schema::game_series::series_id.eq(s.id),
+                            )
+                        })
+                        .collect::<Vec<_>>(),
+                )
+                .execute(db)
+                .await?;
+        }
+
+        if let Some(tags) = &self.tags {
+            diesel::delete(schema::game_tags::table.filter(schema::game_tags::game_id.eq(game_id)))
+                .execute(db)
+                .await?;
+            diesel::insert_into(schema::game_tags::table)
+                .values(
+                    tags.iter()
+                        .map(|tag_id| {
+                            (
+                                schema::game_tags::game_id.eq(game_id),
+                                schema::game_tags::tag_id.eq(tag_id),
+                            )
+                        })
+                        .collect::<Vec<_>>(),
+                )
+                .on_conflict_do_nothing()
+                .execute(db)
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Game {
+    pub async fn metadata(
+        db: &mut Db,
+        game_id: i32,
+    ) -> Result<GameMetadata, diesel::result::Error> {
+        let mut metadata = GameMetadata::default();
+
+        let companies = schema::game_companies::table
+            .inner_join(schema::companies::table)
+            .filter(schema::game_companies::game_id.eq(game_id))
+            .order_by(schema::companies::name.asc())
+            .select((schema::game_companies::role, Company::as_select()))
+            .load::<(String, Company)>(db)
+            .await?;
+        for (role, company) in companies {
+            match role.parse() {
+                Ok(dto::games::CompanyRole::Publisher) => metadata.publishers.push(company),
+                Ok(dto::games::CompanyRole::Developer) => metadata.developers.push(company),
+                Err(_) => {}
+            }
+        }
+
+        metadata.releases = schema::game_releases::table
+            .inner_join(schema::regions::table)
+            .filter(schema::game_releases::game_id.eq(game_id))
+            .order_by((
+                schema::game_releases::release_date.asc().nulls_last(),
+                schema::regions::name.asc(),
+            ))
+            .select((Region::as_select(), schema::game_releases::release_date))
+            .load(db)
+            .await?;
+
+        metadata.languages = schema::game_languages::table
+            .filter(schema::game_languages::game_id.eq(game_id))
+            .order_by(schema::game_languages::language.asc())
+            .select(schema::game_languages::language)
+            .load(db)
+            .await?;
+
+        metadata.genres = schema::game_genres::table
+            .inner_join(schema::genres::table)
+            .filter(schema::game_genres::game_id.eq(game_id))
+            .order_by(schema::genres::name.asc())
+            .select(Genre::as_select())
+            .load(db)
+            .await?;
+
+        metadata.series = schema::game_series::table
+            .inner_join(schema::series::table)
+            .filter(schema::game_series::game_id.eq(game_id))
+            .order_by(schema::series::name.asc())
+            .select(Series::as_select())
+            .load(db)
+            .await?;
+
+        metadata.tags = schema::game_tags::table
+            .inner_join(schema::tags::table)
+            .filter(schema::game_tags::game_id.eq(game_id))
+            .order_by(schema::tags::slug.asc())
+            .select(Tag::as_select())
+            .load(db)
+            .await?;
+
+        Ok(metadata)
+    }
+}
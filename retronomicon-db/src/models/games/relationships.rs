@@ -0,0 +1,214 @@
+use crate::models::{Game, System};
+use crate::schema;
+use crate::Db;
+use diesel::prelude::*;
+use retronomicon_dto as dto;
+use rocket_db_pools::diesel::RunQueryDsl;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A game reached by following relationships from another game.
+#[derive(Debug)]
+pub struct GameRelated {
+    pub game_id: i32,
+    pub kind: String,
+    pub incoming: bool,
+    pub depth: i32,
+}
+
+impl Game {
+    /// How this game relates to other games (`kind`, other game).
+    pub async fn relationships(
+        db: &mut Db,
+        id: i32,
+    ) -> Result<Vec<(String, Game, System)>, diesel::result::Error> {
+        schema::game_relationships::table
+            .inner_join(
+                schema::games::table
+                    .on(schema::games::id.eq(schema::game_relationships::related_game_id)),
+            )
+            .inner_join(schema::systems::table.on(schema::systems::id.eq(schema::games::system_id)))
+            .filter(schema::game_relationships::game_id.eq(id))
+            .order_by((
+                schema::game_relationships::kind.asc(),
+                schema::games::name.asc(),
+            ))
+            .select((
+                schema::game_relationships::kind,
+                schema::games::all_columns,
+                schema::systems::all_columns,
+            ))
+            .load(db)
+            .await
+    }
+
+    /// How other games relate to this game (`kind`, other game).
+    pub async fn related_games(
+        db: &mut Db,
+        id: i32,
+    ) -> Result<Vec<(String, Game, System)>, diesel::result::Error> {
+        schema::game_relationships::table
+            .inner_join(
+                schema::games::table.on(schema::games::id.eq(schema::game_relationships::game_id)),
+            )
+            .inner_join(schema::systems::table.on(schema::systems::id.eq(schema::games::system_id)))
+            .filter(schema::game_relationships::related_game_id.eq(id))
+            .order_by((
+                schema::game_relationships::kind.asc(),
+                schema::games::name.asc(),
+            ))
+            .select((
+                schema::game_relationships::kind,
+                schema::games::all_columns,
+                schema::systems::all_columns,
+            ))
+            .load(db)
+            .await
+    }
+
+    /// Add a relationship from this game to another. Returns whether it
+    /// did not exist already.
+    pub async fn add_relationship(
+        db: &mut Db,
+        id: i32,
+        kind: dto::games::GameRelationshipKind,
+        related_game_id: i32,
+    ) -> Result<bool, diesel::result::Error> {
+        let inserted = diesel::insert_into(schema::game_relationships::table)
+            .values((
+                schema::game_relationships::game_id.eq(id),
+                schema::game_relationships::kind.eq(kind.to_string()),
+                schema::game_relationships::related_game_id.eq(related_game_id),
+            ))
+            .on_conflict_do_nothing()
+            .execute(db)
+            .await?;
+        Ok(inserted > 0)
+    }
+
+    /// Remove a relationship from this game to another. Returns whether it
+    /// existed.
+    pub async fn remove_relationship(
+        db: &mut Db,
+        id: i32,
+        kind: dto::games::GameRelationshipKind,
+        related_game_id: i32,
+    ) -> Result<bool, diesel::result::Error> {
+        let deleted = diesel::delete(
+            schema::game_relationships::table
+                .filter(schema::game_relationships::game_id.eq(id))
+                .filter(schema::game_relationships::kind.eq(kind.to_string()))
+                .filter(schema::game_relationships::related_game_id.eq(related_game_id)),
+        )
+        .execute(db)
+        .await?;
+        Ok(deleted > 0)
+    }
+
+    /// The games reached by following up to `depth` relationships from this
+    /// game, optionally only of a kind and in a direction. Each game is only
+    /// returned once, with the shortest way to reach it.
+    pub async fn traverse_relationships(
+        db: &mut Db,
+        id: i32,
+        kind: Option<dto::games::GameRelationshipKind>,
+        direction: dto::games::GameRelationshipDirection,
+        depth: i32,
+    ) -> Result<Vec<(GameRelated, Game, System)>, diesel::result::Error> {
+        use dto::games::GameRelationshipDirection;
+
+        let outgoing = direction != GameRelationshipDirection::Incoming;
+        let incoming = direction != GameRelationshipDirection::Outgoing;
+
+        // Breadth first, one level at a time, so each game is only visited
+        // once and is reached by the shortest way.
+        let mut visited = BTreeSet::from([id]);
+        let mut frontier = vec![id];
+        let mut related = Vec::new();
+        for depth in 1..=depth {
+            if frontier.is_empty() {
+                break;
+            }
+
+            let mut query = schema::game_relationships::table
+                .select((
+                    schema::game_relationships::game_id,
+                    schema::game_relationships::related_game_id,
+                    schema::game_relationships::kind,
+                ))
+                .into_boxed();
+            query = match (outgoing, incoming) {
+                (true, false) => {
+                    query.filter(schema::game_relationships::game_id.eq_any(&frontier))
+                }
+                (false, true) => {
+                    query.filter(schema::game_relationships::related_game_id.eq_any(&frontier))
+                }
+                _ => query.filter(
+                    schema::game_relationships::game_id
+                        .eq_any(&frontier)
+                        .or(schema::game_relationships::related_game_id.eq_any(&frontier)),
+                ),
+            };
+            if let Some(kind) = kind {
+                query = query.filter(schema::game_relationships::kind.eq(kind.to_string()));
+            }
+            let edges = query.load::<(i32, i32, String)>(db).await?;
+
+            // The games reached at this level, with the first kind reaching them.
+            let mut next = BTreeMap::<i32, (String, bool)>::new();
+            for (game_id, related_game_id, kind) in edges {
+                let mut reached = Vec::with_capacity(2);
+                if outgoing && frontier.contains(&game_id) {
+                    reached.push((related_game_id, false));
+                }
+                if incoming && frontier.contains(&related_game_id) {
+                    reached.push((game_id, true));
+                }
+                for (to_id, incoming) in reached {
+                    if visited.contains(&to_id) {
+                        continue;
+                    }
+                    let edge = (kind.clone(), incoming);
+                    next.entry(to_id)
+                        .and_modify(|e| *e = std::cmp::min(e.clone(), edge.clone()))
+                        .or_insert(edge);
+                }
+            }
+
+            frontier = next.keys().copied().collect();
+            visited.extend(&frontier);
+            related.extend(
+                next.into_iter()
+                    .map(|(game_id, (kind, incoming))| GameRelated {
+                        game_id,
+                        kind,
+                        incoming,
+                        depth,
+                    }),
+            );
+        }
+
+        let ids = related.iter().map(|r| r.game_id).collect::<Vec<_>>();
+        let mut games = schema::games::table
+            .inner_join(schema::systems::table)
+            .filter(schema::games::id.eq_any(ids))
+            .select((schema::games::all_columns, schema::systems::all_columns))
+            .load::<(Game, System)>(db)
+            .await?
+            .into_iter()
+            .map(|(g, s)| (g.id, (g, s)))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut result = related
+            .into_iter()
+            .filter_map(|r| {
+                let (game, system) = games.remove(&r.game_id)?;
+                Some((r, game, system))
+            })
+            .collect::<Vec<_>>();
+        result.sort_by(|(a, ga, _), (b, gb, _)| {
+            (a.depth, &a.kind, &ga.name).cmp(&(b.depth, &b.kind, &gb.name))
+        });
+        Ok(result)
+    }
+}
@@ -0,0 +1,113 @@
+use crate::schema;
+use crate::Db;
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use retronomicon_dto as dto;
+use rocket_db_pools::diesel::RunQueryDsl;
+
+/// The name and descriptions of a game in a language.
+#[derive(Queryable, Selectable, Debug, Clone)]
+#[diesel(table_name = schema::game_translations)]
+pub struct GameTranslation {
+    pub game_id: i32,
+    pub language: String,
+    pub name: String,
+    pub short_description: Option<String>,
+    pub description: Option<String>,
+}
+
+impl From<GameTranslation> for dto::games::GameTranslation {
+    fn from(value: GameTranslation) -> Self {
+        Self {
+            language: value.language,
+            name: value.name,
+            short_description: value.short_description,
+            description: value.description,
+        }
+    }
+}
+
+impl GameTranslation {
+    pub async fn list(db: &mut Db, game_id: i32) -> Result<Vec<Self>, diesel::result::Error> {
+        Self::list_for_games(db, &[game_id]).await
+    }
+
+    pub async fn list_for_games(
+        db: &mut Db,
+        game_ids: &[i32],
+    ) -> Result<Vec<Self>, diesel::result::Error> {
+        schema::game_translations::table
+            .filter(schema::game_translations::game_id.eq_any(game_ids))
+            .order_by((
+                schema::game_translations::game_id.asc(),
+                schema::game_translations::language.asc(),
+            ))
+            .select(Self::as_select())
+            .load(db)
+            .await
+    }
+
+    /// The translation in the best language for the preferences, if any.
+    pub fn negotiate<'a>(
+        translations: impl IntoIterator<Item = &'a Self>,
+        preferences: &[impl AsRef<str>],
+    ) -> Option<&'a Self> {
+        let translations = translations.into_iter().collect::<Vec<_>>();
+        let languages = translations
+            .iter()
+            .map(|t| t.language.as_str())
+            .collect::<Vec<_>>();
+        let language = dto::games::negotiate_language(preferences, &languages)?;
+        translations.into_iter().find(|t| t.language == language)
+    }
+
+    /// Add the translation of a game, or replace the existing one in the
+    /// same language.
+    pub async fn upsert(
+        db: &mut Db,
+        game_id: i32,
+        language: &str,
+        name: &str,
+        short_description: Option<&str>,
+        description: Option<&str>,
+    ) -> Result<Self, diesel::result::Error> {
+        use schema::game_translations::dsl;
+
+        diesel::insert_into(schema::game_translations::table)
+            .values((
+                dsl::game_id.eq(game_id),
+                dsl::language.eq(language),
+                dsl::name.eq(name),
+                dsl::short_description.eq(short_description),
+                dsl::description.eq(description),
+            ))
+            .on_conflict((dsl::game_id, dsl::language))
+            .do_update()
+            .set((
+                dsl::name.eq(excluded(dsl::name)),
+                dsl::short_description.eq(excluded(dsl::short_description)),
+                dsl::description.eq(excluded(dsl::description)),
+            ))
+            .returning(Self::as_select())
+            .get_result(db)
+            .await
+    }
+
+    /// Delete the translation of a game. Returns whether it existed.
+    pub async fn delete(
+        db: &mut Db,
+        game_id: i32,
+        language: &str,
+    ) -> Result<bool, diesel::result::Error> {
+        use schema::game_translations::dsl;
+
+        let deleted = diesel::delete(
+            schema::game_translations::table
+                .filter(dsl::game_id.eq(game_id))
+                .filter(dsl::language.eq(language)),
+        )
+        .execute(db)
+        .await?;
+        Ok(deleted > 0)
+    }
+}
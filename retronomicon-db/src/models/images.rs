@@ -7,7 +7,7 @@ use diesel::{AsExpression, FromSqlRow};
 use retronomicon_dto::artifact::ArtifactRef;
 use retronomicon_dto::types::IdOrSlug;
 use rocket::http::Status;
-use rocket_db_pools::diesel::{AsyncConnection, RunQueryDsl};
+use rocket_db_pools::diesel::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
 use scoped_futures::ScopedFutureExt;
 use serde_json::{Value as Json, Value};
 use std::collections::BTreeMap;
@@ -24,6 +24,29 @@ pub struct GameImage {
     pub height: i32,
     pub mime_type: String,
     pub url: String,
+    pub perceptual_hash: Option<i64>,
+}
+
+#[derive(Queryable, Selectable, Debug, Identifiable)]
+#[diesel(table_name = schema::game_image_derivatives)]
+pub struct GameImageDerivative {
+    pub id: i32,
+    pub game_image_id: i32,
+    pub width: i32,
+    pub height: i32,
+    pub mime_type: String,
+    pub url: String,
+}
+
+impl From<GameImageDerivative> for retronomicon_dto::images::ImageDerivative {
+    fn from(value: GameImageDerivative) -> Self {
+        Self {
+            width: value.width,
+            height: value.height,
+            mime_type: value.mime_type,
+            url: value.url,
+        }
+    }
 }
 
 impl GameImage {
@@ -45,6 +68,7 @@ impl GameImage {
         height: i32,
         mime_type: &str,
         url: &str,
+        perceptual_hash: i64,
     ) -> Result<Self, diesel::result::Error> {
         diesel::insert_into(schema::game_images::table)
             .values((
@@ -54,25 +78,221 @@ impl GameImage {
                 schema::game_images::height.eq(height),
                 schema::game_images::mime_type.eq(mime_type),
                 schema::game_images::url.eq(url),
+                schema::game_images::perceptual_hash.eq(perceptual_hash),
             ))
             .returning(schema::game_images::all_columns)
             .get_result::<Self>(db)
             .await
     }
 
+    pub async fn get(
+        db: &mut Db,
+        game_id: i32,
+        id: i32,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        schema::game_images::table
+            .filter(schema::game_images::game_id.eq(game_id))
+            .filter(schema::game_images::id.eq(id))
+            .first::<Self>(db)
+            .await
+            .optional()
+    }
+
+    /// List the images of a game, optionally only those with a role (the
+    /// slug of a tag).
     pub async fn list(
         db: &mut Db,
         page: i64,
         limit: i64,
         game_id: i32,
+        role: Option<&str>,
     ) -> Result<Vec<(Self, Game)>, diesel::result::Error> {
-        schema::game_images::table
+        let mut query = schema::game_images::table
             .inner_join(schema::games::table)
             .filter(schema::game_images::game_id.eq(game_id))
+            .into_boxed();
+
+        if let Some(role) = role {
+            query = query.filter(
+                schema::game_images::id.eq_any(
+                    schema::game_image_tags::table
+                        .inner_join(schema::tags::table)
+                        .filter(schema::tags::slug.eq(role))
+                        .select(schema::game_image_tags::game_image_id),
+                ),
+            );
+        }
+
+        query
             .order(schema::game_images::image_name.asc())
             .offset(page * limit)
             .limit(limit)
             .load::<(Self, Game)>(db)
             .await
     }
+
+    /// The images uploaded before perceptual hashes and derivatives were
+    /// computed, with their game, by id from `after_id` (excluded).
+    pub async fn list_without_hash(
+        db: &mut AsyncPgConnection,
+        after_id: i32,
+        limit: i64,
+    ) -> Result<Vec<(Self, Game)>, diesel::result::Error> {
+        schema::game_images::table
+            .inner_join(schema::games::table)
+            .filter(schema::game_images::perceptual_hash.is_null())
+            .filter(schema::game_images::id.gt(after_id))
+            .order(schema::game_images::id.asc())
+            .limit(limit)
+            .load::<(Self, Game)>(db)
+            .await
+    }
+
+    pub async fn set_perceptual_hash(
+        &self,
+        db: &mut AsyncPgConnection,
+        perceptual_hash: i64,
+    ) -> Result<(), diesel::result::Error> {
+        diesel::update(schema::game_images::table)
+            .filter(schema::game_images::id.eq(self.id))
+            .set(schema::game_images::perceptual_hash.eq(perceptual_hash))
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    /// The perceptual hashes of the images of a game, with their names.
+    pub async fn hashes(
+        db: &mut Db,
+        game_id: i32,
+    ) -> Result<Vec<(String, i64)>, diesel::result::Error> {
+        schema::game_images::table
+            .filter(schema::game_images::game_id.eq(game_id))
+            .filter(schema::game_images::perceptual_hash.is_not_null())
+            .select((
+                schema::game_images::image_name,
+                schema::game_images::perceptual_hash.assume_not_null(),
+            ))
+            .load(db)
+            .await
+    }
+
+    /// The roles (tag slugs) of images, by image id.
+    pub async fn roles(
+        db: &mut Db,
+        image_ids: &[i32],
+    ) -> Result<BTreeMap<i32, Vec<String>>, diesel::result::Error> {
+        let roles = schema::game_image_tags::table
+            .inner_join(schema::tags::table)
+            .filter(schema::game_image_tags::game_image_id.eq_any(image_ids))
+            .order_by(schema::tags::slug.asc())
+            .select((schema::game_image_tags::game_image_id, schema::tags::slug))
+            .load::<(i32, String)>(db)
+            .await?;
+
+        let mut result = BTreeMap::<i32, Vec<String>>::new();
+        for (image_id, slug) in roles {
+            result.entry(image_id).or_default().push(slug);
+        }
+        Ok(result)
+    }
+
+    /// Replace the roles of an image by the tags with these slugs.
+    pub async fn set_roles(
+        &self,
+        db: &mut Db,
+        roles: &[String],
+    ) -> Result<(), diesel::result::Error> {
+        let id = self.id;
+        db.transaction(|db| {
+            async move {
+                diesel::delete(
+                    schema::game_image_tags::table
+                        .filter(schema::game_image_tags::game_image_id.eq(id)),
+                )
+                .execute(db)
+                .await?;
+
+                let tag_ids = schema::tags::table
+                    .filter(schema::tags::slug.eq_any(roles))
+                    .select(schema::tags::id)
+                    .load::<i32>(db)
+                    .await?;
+                diesel::insert_into(schema::game_image_tags::table)
+                    .values(
+                        tag_ids
+                            .into_iter()
+                            .map(|tag_id| {
+                                (
+                                    schema::game_image_tags::game_image_id.eq(id),
+                                    schema::game_image_tags::tag_id.eq(tag_id),
+                                )
+                            })
+                            .collect::<Vec<_>>(),
+                    )
+                    .execute(db)
+                    .await?;
+                Ok(())
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+
+    /// Add a derivative to the image, replacing the URL of the derivative
+    /// of the same size and type if it exists.
+    pub async fn add_derivative(
+        &self,
+        db: &mut AsyncPgConnection,
+        width: i32,
+        height: i32,
+        mime_type: &str,
+        url: &str,
+    ) -> Result<GameImageDerivative, diesel::result::Error> {
+        diesel::insert_into(schema::game_image_derivatives::table)
+            .values((
+                schema::game_image_derivatives::game_image_id.eq(self.id),
+                schema::game_image_derivatives::width.eq(width),
+                schema::game_image_derivatives::height.eq(height),
+                schema::game_image_derivatives::mime_type.eq(mime_type),
+                schema::game_image_derivatives::url.eq(url),
+            ))
+            .on_conflict((
+                schema::game_image_derivatives::game_image_id,
+                schema::game_image_derivatives::width,
+                schema::game_image_derivatives::height,
+                schema::game_image_derivatives::mime_type,
+            ))
+            .do_update()
+            .set(schema::game_image_derivatives::url.eq(url))
+            .returning(GameImageDerivative::as_select())
+            .get_result(db)
+            .await
+    }
+
+    /// The derivatives of images by image id, smallest first.
+    pub async fn derivatives(
+        db: &mut Db,
+        image_ids: &[i32],
+    ) -> Result<BTreeMap<i32, Vec<GameImageDerivative>>, diesel::result::Error> {
+        let derivatives = schema::game_image_derivatives::table
+            .filter(schema::game_image_derivatives::game_image_id.eq_any(image_ids))
+            .order_by((
+                schema::game_image_derivatives::width.asc(),
+                schema::game_image_derivatives::height.asc(),
+                schema::game_image_derivatives::mime_type.asc(),
+            ))
+            .select(GameImageDerivative::as_select())
+            .load::<GameImageDerivative>(db)
+            .await?;
+
+        let mut result = BTreeMap::<i32, Vec<GameImageDerivative>>::new();
+        for derivative in derivatives {
+            result
+                .entry(derivative.game_image_id)
+                .or_default()
+                .push(derivative);
+        }
+        Ok(result)
+    }
 }
@@ -0,0 +1,202 @@
+use crate::models::User;
+use crate::schema;
+use crate::Db;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use retronomicon_dto as dto;
+use retronomicon_dto::reexports::strum::IntoEnumIterator;
+use rocket_db_pools::diesel::RunQueryDsl;
+use serde_json::Value as Json;
+
+/// An event a user is told about.
+#[derive(Queryable, Debug, Identifiable, Selectable)]
+#[diesel(table_name = schema::notifications)]
+#[diesel(belongs_to(User))]
+pub struct Notification {
+    pub id: i32,
+    pub user_id: i32,
+    pub kind: String,
+    pub data: Json,
+    pub created_at: NaiveDateTime,
+    pub read_at: Option<NaiveDateTime>,
+}
+
+impl Notification {
+    /// Convert to the DTO. Notifications of a kind this version does not know
+    /// about are skipped.
+    pub fn into_dto(self) -> Option<dto::notifications::Notification> {
+        Some(dto::notifications::Notification {
+            id: self.id,
+            kind: self.kind.parse().ok()?,
+            data: self.data,
+            created_at: self.created_at.and_utc().timestamp(),
+            read_at: self.read_at.map(|d| d.and_utc().timestamp()),
+        })
+    }
+
+    pub async fn create(
+        db: &mut Db,
+        user_id: i32,
+        kind: dto::notifications::NotificationKind,
+        data: Json,
+    ) -> Result<Self, diesel::result::Error> {
+        diesel::insert_into(schema::notifications::table)
+            .values((
+                schema::notifications::user_id.eq(user_id),
+                schema::notifications::kind.eq(kind.to_string()),
+                schema::notifications::data.eq(data),
+            ))
+            .returning(schema::notifications::all_columns)
+            .get_result::<Self>(db)
+            .await
+    }
+
+    /// List the notifications of a user, most recent first.
+    pub async fn list(
+        db: &mut Db,
+        user_id: i32,
+        unread: bool,
+        page: i64,
+        limit: i64,
+    ) -> Result<Vec<Self>, diesel::result::Error> {
+        let mut query = schema::notifications::table
+            .filter(schema::notifications::user_id.eq(user_id))
+            .into_boxed();
+        if unread {
+            query = query.filter(schema::notifications::read_at.is_null());
+        }
+
+        query
+            .order((
+                schema::notifications::created_at.desc(),
+                schema::notifications::id.desc(),
+            ))
+            .offset(page * limit)
+            .limit(limit)
+            .load::<Self>(db)
+            .await
+    }
+
+    /// Mark notifications of a user as read; all of them if `ids` is `None`.
+    /// Returns the number of notifications that were unread.
+    pub async fn mark_read(
+        db: &mut Db,
+        user_id: i32,
+        ids: Option<&[i32]>,
+    ) -> Result<usize, diesel::result::Error> {
+        let mut query = diesel::update(schema::notifications::table)
+            .filter(schema::notifications::user_id.eq(user_id))
+            .filter(schema::notifications::read_at.is_null())
+            .into_boxed();
+        if let Some(ids) = ids {
+            query = query.filter(schema::notifications::id.eq_any(ids));
+        }
+
+        query
+            .set(schema::notifications::read_at.eq(chrono::Utc::now().naive_utc()))
+            .execute(db)
+            .await
+    }
+}
+
+/// The channels a user wants to be notified through for a kind of
+/// notification. Users without a row for a kind are notified through every
+/// channel.
+#[derive(Queryable, Debug, Identifiable, Selectable)]
+#[diesel(table_name = schema::notification_preferences)]
+#[diesel(primary_key(user_id, kind))]
+#[diesel(belongs_to(User))]
+pub struct NotificationPreference {
+    pub user_id: i32,
+    pub kind: String,
+    pub in_app: bool,
+    pub email: bool,
+}
+
+impl NotificationPreference {
+    /// The preferences of a user for every kind of notification.
+    pub async fn list(
+        db: &mut Db,
+        user_id: i32,
+    ) -> Result<Vec<dto::notifications::NotificationPreference>, diesel::result::Error> {
+        let stored = schema::notification_preferences::table
+            .filter(schema::notification_preferences::user_id.eq(user_id))
+            .load::<Self>(db)
+            .await?;
+
+        Ok(dto::notifications::NotificationKind::iter()
+            .map(|kind| {
+                let kind_str = kind.to_string();
+                let pref = stored.iter().find(|p| p.kind == kind_str);
+                dto::notifications::NotificationPreference {
+                    kind,
+                    in_app: pref.map_or(true, |p| p.in_app),
+                    email: pref.map_or(true, |p| p.email),
+                }
+            })
+            .collect())
+    }
+
+    /// The preference of a user for a kind of notification.
+    pub async fn get(
+        db: &mut Db,
+        user_id: i32,
+        kind: dto::notifications::NotificationKind,
+    ) -> Result<dto::notifications::NotificationPreference, diesel::result::Error> {
+        let pref = schema::notification_preferences::table
+            .filter(schema::notification_preferences::user_id.eq(user_id))
+            .filter(schema::notification_preferences::kind.eq(kind.to_string()))
+            .first::<Self>(db)
+            .await
+            .optional()?;
+
+        Ok(dto::notifications::NotificationPreference {
+            kind,
+            in_app: pref.as_ref().map_or(true, |p| p.in_app),
+            email: pref.as_ref().map_or(true, |p| p.email),
+        })
+    }
+
+    /// Store the preferences of a user. Kinds that are not in `preferences`
+    /// are left as they were.
+    pub async fn set(
+        db: &mut Db,
+        user_id: i32,
+        preferences: &[dto::notifications::NotificationPreference],
+    ) -> Result<(), diesel::result::Error> {
+        if preferences.is_empty() {
+            return Ok(());
+        }
+
+        let values = preferences
+            .iter()
+            .map(|p| {
+                (
+                    schema::notification_preferences::user_id.eq(user_id),
+                    schema::notification_preferences::kind.eq(p.kind.to_string()),
+                    schema::notification_preferences::in_app.eq(p.in_app),
+                    schema::notification_preferences::email.eq(p.email),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        diesel::insert_into(schema::notification_preferences::table)
+            .values(values)
+            .on_conflict((
+                schema::notification_preferences::user_id,
+                schema::notification_preferences::kind,
+            ))
+            .do_update()
+            .set((
+                schema::notification_preferences::in_app
+                    .eq(excluded(schema::notification_preferences::in_app)),
+                schema::notification_preferences::email
+                    .eq(excluded(schema::notification_preferences::email)),
+            ))
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+}
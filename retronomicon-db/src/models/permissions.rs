@@ -0,0 +1,218 @@
+use crate::models::{Core, Team, User, UserTeamRole};
+use crate::schema;
+use crate::Db;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use retronomicon_dto as dto;
+use retronomicon_dto::permissions::Permission;
+use rocket_db_pools::diesel::scoped_futures::ScopedFutureExt;
+use rocket_db_pools::diesel::{AsyncConnection, RunQueryDsl};
+use std::collections::BTreeMap;
+
+/// A permission granted to or revoked from a role of a team, overriding the
+/// default of the role.
+#[derive(Queryable, Debug, Identifiable, Selectable, Associations)]
+#[diesel(belongs_to(Team))]
+#[diesel(table_name = schema::team_permissions)]
+#[diesel(primary_key(team_id, role, permission))]
+pub struct TeamPermission {
+    pub team_id: i32,
+    pub role: UserTeamRole,
+    pub permission: String,
+    pub granted: bool,
+}
+
+impl TeamPermission {
+    /// The permissions a team changed for its roles. Permissions this
+    /// version does not know about are skipped.
+    pub async fn list(
+        db: &mut Db,
+        team_id: i32,
+    ) -> Result<Vec<(UserTeamRole, Permission, bool)>, diesel::result::Error> {
+        Ok(schema::team_permissions::table
+            .filter(schema::team_permissions::team_id.eq(team_id))
+            .load::<Self>(db)
+            .await?
+            .into_iter()
+            .filter_map(|p| Some((p.role, p.permission.parse().ok()?, p.granted)))
+            .collect())
+    }
+
+    /// Whether a team changed a permission of a role, and to what.
+    pub async fn get(
+        db: &mut Db,
+        team_id: i32,
+        role: UserTeamRole,
+        permission: Permission,
+    ) -> Result<Option<bool>, diesel::result::Error> {
+        schema::team_permissions::table
+            .filter(schema::team_permissions::team_id.eq(team_id))
+            .filter(schema::team_permissions::role.eq(role))
+            .filter(schema::team_permissions::permission.eq(permission.to_string()))
+            .select(schema::team_permissions::granted)
+            .first::<bool>(db)
+            .await
+            .optional()
+    }
+
+    /// Grant or revoke permissions to roles of a team. An update without
+    /// `granted` resets the permission of the role to its default.
+    pub async fn update(
+        db: &mut Db,
+        team_id: i32,
+        updates: &[dto::permissions::TeamPermissionUpdate],
+    ) -> Result<(), diesel::result::Error> {
+        db.transaction(|db| {
+            async move {
+                for update in updates {
+                    let role = UserTeamRole::from(update.role);
+                    let permission = update.permission.to_string();
+                    match update.granted {
+                        Some(granted) => {
+                            diesel::insert_into(schema::team_permissions::table)
+                                .values((
+                                    schema::team_permissions::team_id.eq(team_id),
+                                    schema::team_permissions::role.eq(role),
+                                    schema::team_permissions::permission.eq(&permission),
+                                    schema::team_permissions::granted.eq(granted),
+                                ))
+                                .on_conflict((
+                                    schema::team_permissions::team_id,
+                                    schema::team_permissions::role,
+                                    schema::team_permissions::permission,
+                                ))
+                                .do_update()
+                                .set(schema::team_permissions::granted.eq(granted))
+                                .execute(db)
+                                .await?;
+                        }
+                        None => {
+                            diesel::delete(schema::team_permissions::table)
+                                .filter(schema::team_permissions::team_id.eq(team_id))
+                                .filter(schema::team_permissions::role.eq(role))
+                                .filter(schema::team_permissions::permission.eq(&permission))
+                                .execute(db)
+                                .await?;
+                        }
+                    }
+                }
+                Ok(())
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+}
+
+/// A permission on a core granted to a user.
+#[derive(Queryable, Debug, Identifiable, Selectable, Associations)]
+#[diesel(belongs_to(Core))]
+#[diesel(belongs_to(User))]
+#[diesel(table_name = schema::core_collaborators)]
+#[diesel(primary_key(core_id, user_id, permission))]
+pub struct CoreCollaborator {
+    pub core_id: i32,
+    pub user_id: i32,
+    pub permission: String,
+    pub granted_by_id: Option<i32>,
+    pub created_at: NaiveDateTime,
+}
+
+impl CoreCollaborator {
+    /// The collaborators of a core and their permissions, by username.
+    pub async fn list(
+        db: &mut Db,
+        core_id: i32,
+    ) -> Result<Vec<dto::permissions::CoreCollaborator>, diesel::result::Error> {
+        let rows = schema::core_collaborators::table
+            .inner_join(
+                schema::users::table.on(schema::users::id.eq(schema::core_collaborators::user_id)),
+            )
+            .filter(schema::core_collaborators::core_id.eq(core_id))
+            .select((
+                schema::users::id,
+                schema::users::username,
+                schema::core_collaborators::permission,
+            ))
+            .load::<(i32, Option<String>, String)>(db)
+            .await?;
+
+        let mut collaborators = BTreeMap::<i32, dto::permissions::CoreCollaborator>::new();
+        for (id, username, permission) in rows {
+            let Ok(permission) = permission.parse() else {
+                continue;
+            };
+            collaborators
+                .entry(id)
+                .or_insert_with(|| dto::permissions::CoreCollaborator {
+                    user: dto::user::UserRef {
+                        id,
+                        username: username.unwrap_or_default(),
+                    },
+                    permissions: Vec::new(),
+                })
+                .permissions
+                .push(permission);
+        }
+        Ok(collaborators.into_values().collect())
+    }
+
+    /// Whether a user was granted a permission on a core.
+    pub async fn has(
+        db: &mut Db,
+        core_id: i32,
+        user_id: i32,
+        permission: Permission,
+    ) -> Result<bool, diesel::result::Error> {
+        diesel::select(diesel::dsl::exists(
+            schema::core_collaborators::table
+                .filter(schema::core_collaborators::core_id.eq(core_id))
+                .filter(schema::core_collaborators::user_id.eq(user_id))
+                .filter(schema::core_collaborators::permission.eq(permission.to_string())),
+        ))
+        .get_result::<bool>(db)
+        .await
+    }
+
+    /// Replace the permissions of a user on a core. An empty list removes
+    /// the user from the collaborators.
+    pub async fn set(
+        db: &mut Db,
+        core_id: i32,
+        user_id: i32,
+        permissions: &[Permission],
+        granted_by: &User,
+    ) -> Result<(), diesel::result::Error> {
+        let values = permissions
+            .iter()
+            .map(|p| {
+                (
+                    schema::core_collaborators::core_id.eq(core_id),
+                    schema::core_collaborators::user_id.eq(user_id),
+                    schema::core_collaborators::permission.eq(p.to_string()),
+                    schema::core_collaborators::granted_by_id.eq(granted_by.id),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        db.transaction(|db| {
+            async move {
+                diesel::delete(schema::core_collaborators::table)
+                    .filter(schema::core_collaborators::core_id.eq(core_id))
+                    .filter(schema::core_collaborators::user_id.eq(user_id))
+                    .execute(db)
+                    .await?;
+                if !values.is_empty() {
+                    diesel::insert_into(schema::core_collaborators::table)
+                        .values(values)
+                        .on_conflict_do_nothing()
+                        .execute(db)
+                        .await?;
+                }
+                Ok(())
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+}
@@ -0,0 +1,167 @@
+use crate::Db;
+use diesel::sql_types::{BigInt, Float4, Int4, Nullable, Text, Varchar};
+use diesel::QueryableByName;
+use retronomicon_dto as dto;
+use rocket_db_pools::diesel::RunQueryDsl;
+
+/// Games are also found by their localised titles, and by the names of
+/// their publishers and developers.
+const GAME_OTHER_MATCHES: &str = "OR id IN (SELECT game_translations.game_id \
+    FROM game_translations \
+    WHERE game_translations.search_document @@ websearch_to_tsquery('simple', $1) \
+        OR $1 <% game_translations.name) \
+    OR id IN (SELECT game_companies.game_id \
+    FROM game_companies INNER JOIN companies ON companies.id = game_companies.company_id \
+    WHERE companies.search_document @@ websearch_to_tsquery('simple', $1))";
+
+/// The tables that can be searched, with the kind of their items, the
+/// expression of their slug and other conditions to match them.
+const SEARCHABLE: [(dto::search::SearchKind, &str, &str, &str); 4] = [
+    (
+        dto::search::SearchKind::Game,
+        "games",
+        "NULL",
+        GAME_OTHER_MATCHES,
+    ),
+    (dto::search::SearchKind::Core, "cores", "slug", ""),
+    (dto::search::SearchKind::System, "systems", "slug", ""),
+    (dto::search::SearchKind::Platform, "platforms", "slug", ""),
+];
+
+/// A CTE named `matches` with the items matching the query bound to `$1`,
+/// either by full-text search on their search document, or because their
+/// name is close to the query (see `pg_trgm`'s `<%` operator).
+fn matches_cte() -> String {
+    let branches = SEARCHABLE
+        .iter()
+        .map(|(kind, table, slug, other)| {
+            format!(
+                "SELECT '{kind}'::varchar AS kind, id, {slug}::varchar AS slug, \
+                    name::varchar AS name, description::text AS description, \
+                    search_document @@ websearch_to_tsquery('simple', $1) AS full_text, \
+                    (ts_rank(search_document, websearch_to_tsquery('simple', $1)) \
+                        + word_similarity($1, name))::real AS rank \
+                FROM {table} \
+                WHERE search_document @@ websearch_to_tsquery('simple', $1) OR $1 <% name {other}"
+            )
+        })
+        .collect::<Vec<_>>();
+
+    format!("WITH matches AS ({})", branches.join(" UNION ALL "))
+}
+
+/// An SQL expression escaping the HTML special characters of the text
+/// `expr`, so the text can be highlighted with HTML tags.
+fn escape_html(expr: &str) -> String {
+    [
+        ("&", "&amp;"),
+        ("<", "&lt;"),
+        (">", "&gt;"),
+        ("\"", "&quot;"),
+        ("''", "&#39;"),
+    ]
+    .iter()
+    .fold(expr.to_string(), |expr, (from, to)| {
+        format!("replace({expr}, '{from}', '{to}')")
+    })
+}
+
+#[derive(QueryableByName, Debug)]
+pub struct SearchMatch {
+    #[diesel(sql_type = Varchar)]
+    pub kind: String,
+    #[diesel(sql_type = Int4)]
+    pub id: i32,
+    #[diesel(sql_type = Nullable<Varchar>)]
+    pub slug: Option<String>,
+    #[diesel(sql_type = Varchar)]
+    pub name: String,
+    #[diesel(sql_type = Text)]
+    pub highlight: String,
+    #[diesel(sql_type = Float4)]
+    pub rank: f32,
+}
+
+impl SearchMatch {
+    /// Search games, cores, systems and platforms, most relevant first.
+    pub async fn search(
+        db: &mut Db,
+        query: &str,
+        kind: Option<dto::search::SearchKind>,
+        page: i64,
+        limit: i64,
+    ) -> Result<Vec<Self>, diesel::result::Error> {
+        let sql = format!(
+            "{} \
+            SELECT kind, id, slug, name, \
+                CASE WHEN full_text THEN ts_headline('simple', {}, \
+                    websearch_to_tsquery('simple', $1), \
+                    'StartSel=<mark>, StopSel=</mark>, MinWords=5, MaxWords=20') \
+                ELSE '' END AS highlight, \
+                rank \
+            FROM matches \
+            WHERE $2::varchar IS NULL OR kind = $2 \
+            ORDER BY rank DESC, name, kind, id \
+            OFFSET $3 LIMIT $4",
+            matches_cte(),
+            escape_html("name || ' ' || description"),
+        );
+
+        diesel::sql_query(sql)
+            .bind::<Text, _>(query)
+            .bind::<Nullable<Varchar>, _>(kind.map(|k| k.to_string()))
+            .bind::<BigInt, _>(page * limit)
+            .bind::<BigInt, _>(limit)
+            .load::<Self>(db)
+            .await
+    }
+
+    /// Count the items of each kind matching a search.
+    pub async fn facets(
+        db: &mut Db,
+        query: &str,
+    ) -> Result<dto::search::SearchFacets, diesel::result::Error> {
+        #[derive(QueryableByName)]
+        struct Facet {
+            #[diesel(sql_type = Varchar)]
+            kind: String,
+            #[diesel(sql_type = BigInt)]
+            count: i64,
+        }
+
+        let sql = format!(
+            "{} SELECT kind, count(*) AS count FROM matches GROUP BY kind",
+            matches_cte()
+        );
+        let facets = diesel::sql_query(sql)
+            .bind::<Text, _>(query)
+            .load::<Facet>(db)
+            .await?;
+
+        let mut result = dto::search::SearchFacets::default();
+        for Facet { kind, count } in facets {
+            match kind.parse() {
+                Ok(dto::search::SearchKind::Game) => result.games = count,
+                Ok(dto::search::SearchKind::Core) => result.cores = count,
+                Ok(dto::search::SearchKind::System) => result.systems = count,
+                Ok(dto::search::SearchKind::Platform) => result.platforms = count,
+                Err(_) => {}
+            }
+        }
+        Ok(result)
+    }
+
+    pub fn into_dto(self) -> Result<dto::search::SearchResult, String> {
+        Ok(dto::search::SearchResult {
+            kind: self
+                .kind
+                .parse()
+                .map_err(|_| format!("Unknown search result kind {:?}", self.kind))?,
+            id: self.id,
+            slug: self.slug,
+            name: self.name,
+            highlight: self.highlight,
+            rank: self.rank,
+        })
+    }
+}
@@ -0,0 +1,172 @@
+use crate::models::{CoreRelease, Team};
+use crate::schema;
+use crate::Db;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use retronomicon_dto as dto;
+use rocket_db_pools::diesel::RunQueryDsl;
+
+/// A public key a team uses to sign the manifests of its releases.
+#[derive(Queryable, Debug, Identifiable, Selectable)]
+#[diesel(table_name = schema::team_signing_keys)]
+#[diesel(belongs_to(Team))]
+pub struct TeamSigningKey {
+    pub id: i32,
+    pub team_id: i32,
+    pub name: String,
+    pub public_key: Vec<u8>,
+    pub created_at: NaiveDateTime,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+impl From<TeamSigningKey> for dto::teams::TeamSigningKey {
+    fn from(value: TeamSigningKey) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            public_key: value.public_key.into(),
+            created_at: value.created_at.and_utc().timestamp(),
+            revoked_at: value.revoked_at.map(|d| d.and_utc().timestamp()),
+        }
+    }
+}
+
+impl TeamSigningKey {
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+
+    pub async fn get(
+        db: &mut Db,
+        team: &Team,
+        id: i32,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        schema::team_signing_keys::table
+            .filter(schema::team_signing_keys::team_id.eq(team.id))
+            .filter(schema::team_signing_keys::id.eq(id))
+            .first::<Self>(db)
+            .await
+            .optional()
+    }
+
+    /// Find a key of a team by its public key, revoked or not.
+    pub async fn get_by_public_key(
+        db: &mut Db,
+        team: &Team,
+        public_key: &[u8],
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        schema::team_signing_keys::table
+            .filter(schema::team_signing_keys::team_id.eq(team.id))
+            .filter(schema::team_signing_keys::public_key.eq(public_key))
+            .first::<Self>(db)
+            .await
+            .optional()
+    }
+
+    pub async fn list(db: &mut Db, team: &Team) -> Result<Vec<Self>, diesel::result::Error> {
+        schema::team_signing_keys::table
+            .filter(schema::team_signing_keys::team_id.eq(team.id))
+            .order(schema::team_signing_keys::id.asc())
+            .load::<Self>(db)
+            .await
+    }
+
+    pub async fn create(
+        db: &mut Db,
+        team: &Team,
+        name: &str,
+        public_key: &[u8],
+    ) -> Result<Self, diesel::result::Error> {
+        diesel::insert_into(schema::team_signing_keys::table)
+            .values((
+                schema::team_signing_keys::team_id.eq(team.id),
+                schema::team_signing_keys::name.eq(name),
+                schema::team_signing_keys::public_key.eq(public_key),
+            ))
+            .returning(schema::team_signing_keys::all_columns)
+            .get_result::<Self>(db)
+            .await
+    }
+
+    /// Revoke the key. Signatures made with it are not served anymore.
+    pub async fn revoke(&self, db: &mut Db) -> Result<(), diesel::result::Error> {
+        diesel::update(schema::team_signing_keys::table)
+            .filter(schema::team_signing_keys::id.eq(self.id))
+            .filter(schema::team_signing_keys::revoked_at.is_null())
+            .set(schema::team_signing_keys::revoked_at.eq(chrono::Utc::now().naive_utc()))
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+}
+
+/// A detached signature of a release manifest, made with a team key.
+#[derive(Queryable, Debug, Identifiable, Selectable)]
+#[diesel(table_name = schema::core_release_signatures)]
+#[diesel(primary_key(core_release_id, team_signing_key_id))]
+#[diesel(belongs_to(CoreRelease))]
+#[diesel(belongs_to(TeamSigningKey))]
+pub struct CoreReleaseSignature {
+    pub core_release_id: i32,
+    pub team_signing_key_id: i32,
+    pub manifest_sha256: Vec<u8>,
+    pub signature: Vec<u8>,
+    pub created_at: NaiveDateTime,
+}
+
+impl CoreReleaseSignature {
+    /// List the signatures of a release made with keys that are not revoked,
+    /// for the manifest with the given hash. Signatures of a previous version
+    /// of the manifest (e.g. before an artifact was added) are ignored.
+    pub async fn list_valid(
+        db: &mut Db,
+        release: &CoreRelease,
+        manifest_sha256: &[u8],
+    ) -> Result<Vec<(Self, TeamSigningKey)>, diesel::result::Error> {
+        schema::core_release_signatures::table
+            .inner_join(schema::team_signing_keys::table)
+            .filter(schema::core_release_signatures::core_release_id.eq(release.id))
+            .filter(schema::core_release_signatures::manifest_sha256.eq(manifest_sha256))
+            .filter(schema::team_signing_keys::revoked_at.is_null())
+            .order(schema::team_signing_keys::id.asc())
+            .select((
+                schema::core_release_signatures::all_columns,
+                schema::team_signing_keys::all_columns,
+            ))
+            .load::<(Self, TeamSigningKey)>(db)
+            .await
+    }
+
+    /// Record a signature, replacing any previous signature of the release by
+    /// the same key.
+    pub async fn upsert(
+        db: &mut Db,
+        release: &CoreRelease,
+        key: &TeamSigningKey,
+        manifest_sha256: &[u8],
+        signature: &[u8],
+    ) -> Result<Self, diesel::result::Error> {
+        let now = chrono::Utc::now().naive_utc();
+        diesel::insert_into(schema::core_release_signatures::table)
+            .values((
+                schema::core_release_signatures::core_release_id.eq(release.id),
+                schema::core_release_signatures::team_signing_key_id.eq(key.id),
+                schema::core_release_signatures::manifest_sha256.eq(manifest_sha256),
+                schema::core_release_signatures::signature.eq(signature),
+                schema::core_release_signatures::created_at.eq(now),
+            ))
+            .on_conflict((
+                schema::core_release_signatures::core_release_id,
+                schema::core_release_signatures::team_signing_key_id,
+            ))
+            .do_update()
+            .set((
+                schema::core_release_signatures::manifest_sha256.eq(manifest_sha256),
+                schema::core_release_signatures::signature.eq(signature),
+                schema::core_release_signatures::created_at.eq(now),
+            ))
+            .returning(schema::core_release_signatures::all_columns)
+            .get_result::<Self>(db)
+            .await
+    }
+}
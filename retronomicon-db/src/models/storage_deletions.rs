@@ -0,0 +1,75 @@
+use crate::schema;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use rocket_db_pools::diesel::{AsyncPgConnection, RunQueryDsl};
+
+/// An object to delete from the storage, once the rows using it are gone.
+/// It is kept until the object is deleted, so failed deletions are retried.
+#[derive(Clone, Queryable, Debug, Selectable, Identifiable)]
+#[diesel(table_name = schema::storage_deletions)]
+pub struct StorageDeletion {
+    pub id: i32,
+    pub bucket: String,
+    pub path: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl StorageDeletion {
+    pub async fn create(
+        db: &mut AsyncPgConnection,
+        bucket: &str,
+        path: &str,
+    ) -> Result<Self, diesel::result::Error> {
+        diesel::insert_into(schema::storage_deletions::table)
+            .values((
+                schema::storage_deletions::bucket.eq(bucket),
+                schema::storage_deletions::path.eq(path),
+            ))
+            .returning(schema::storage_deletions::all_columns)
+            .get_result::<Self>(db)
+            .await
+    }
+
+    /// List pending deletions, the ones that failed the least first.
+    pub async fn list(
+        db: &mut AsyncPgConnection,
+        limit: i64,
+    ) -> Result<Vec<Self>, diesel::result::Error> {
+        schema::storage_deletions::table
+            .order((
+                schema::storage_deletions::attempts,
+                schema::storage_deletions::id,
+            ))
+            .limit(limit)
+            .load::<Self>(db)
+            .await
+    }
+
+    /// Record a failed attempt to delete the object, to retry later.
+    pub async fn failed(
+        &self,
+        db: &mut AsyncPgConnection,
+        error: &str,
+    ) -> Result<(), diesel::result::Error> {
+        diesel::update(schema::storage_deletions::table)
+            .filter(schema::storage_deletions::id.eq(self.id))
+            .set((
+                schema::storage_deletions::attempts.eq(schema::storage_deletions::attempts + 1),
+                schema::storage_deletions::last_error.eq(error),
+            ))
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    /// Remove the deletion once the object was deleted.
+    pub async fn done(&self, db: &mut AsyncPgConnection) -> Result<(), diesel::result::Error> {
+        diesel::delete(schema::storage_deletions::table)
+            .filter(schema::storage_deletions::id.eq(self.id))
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+}
@@ -89,4 +89,14 @@ impl Tag {
             .load::<Self>(db)
             .await
     }
+    /// The tags with these slugs. Unknown slugs are ignored.
+    pub async fn get_by_slugs(
+        db: &mut crate::Db,
+        slugs: &[&str],
+    ) -> Result<Vec<Self>, diesel::result::Error> {
+        schema::tags::table
+            .filter(schema::tags::slug.eq_any(slugs))
+            .load::<Self>(db)
+            .await
+    }
 }
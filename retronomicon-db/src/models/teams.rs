@@ -12,6 +12,7 @@ use diesel::{AsExpression, FromSqlRow};
 use retronomicon_dto as dto;
 use retronomicon_dto::types::IdOrSlug;
 use rocket::http::Status;
+use rocket_db_pools::diesel::scoped_futures::ScopedFutureExt;
 use rocket_db_pools::diesel::{AsyncConnection, RunQueryDsl};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value as Json};
@@ -27,6 +28,7 @@ pub struct Team {
     pub description: String,
     pub links: Json,
     pub metadata: Json,
+    pub upload_size_limit: Option<i64>,
 }
 
 impl From<Team> for dto::teams::TeamRef {
@@ -141,12 +143,36 @@ impl Team {
         Ok(())
     }
 
-    pub async fn delete(db: &mut Db, id: i32) -> Result<(), diesel::result::Error> {
-        diesel::delete(schema::teams::table)
+    pub async fn set_upload_size_limit(
+        db: &mut Db,
+        id: i32,
+        upload_size_limit: Option<i64>,
+    ) -> Result<(), diesel::result::Error> {
+        diesel::update(schema::teams::table)
             .filter(schema::teams::id.eq(id))
+            .set(schema::teams::upload_size_limit.eq(upload_size_limit))
             .execute(db)
-            .await
-            .map(|_| ())
+            .await?;
+        Ok(())
+    }
+
+    /// Delete a team, along with its memberships and pending invitations.
+    pub async fn delete(db: &mut Db, id: i32) -> Result<(), diesel::result::Error> {
+        db.transaction(|db| {
+            async move {
+                diesel::delete(schema::user_teams::table)
+                    .filter(schema::user_teams::team_id.eq(id))
+                    .execute(db)
+                    .await?;
+                diesel::delete(schema::teams::table)
+                    .filter(schema::teams::id.eq(id))
+                    .execute(db)
+                    .await?;
+                Ok(())
+            }
+            .scope_boxed()
+        })
+        .await
     }
 
     pub async fn list(
@@ -185,4 +211,59 @@ impl Team {
             })
             .collect())
     }
+
+    /// The invitations to this team that were not accepted yet.
+    pub async fn invitations_ref(
+        &self,
+        db: &mut Db,
+    ) -> Result<Vec<dto::teams::TeamInvitation>, diesel::result::Error> {
+        let inviters = diesel::alias!(schema::users as inviters);
+
+        Ok(models::UserTeam::belonging_to(self)
+            .inner_join(schema::users::table.on(schema::users::id.eq(schema::user_teams::user_id)))
+            .inner_join(inviters.on(
+                schema::user_teams::invite_from.eq(inviters.field(schema::users::id).nullable()),
+            ))
+            .select((
+                schema::users::id,
+                schema::users::username,
+                schema::user_teams::role,
+                inviters.field(schema::users::id),
+                inviters.field(schema::users::username),
+            ))
+            .order(schema::users::id.asc())
+            .load::<(
+                i32,
+                Option<String>,
+                models::UserTeamRole,
+                i32,
+                Option<String>,
+            )>(db)
+            .await?
+            .into_iter()
+            .map(
+                |(id, username, role, inviter_id, inviter_username)| dto::teams::TeamInvitation {
+                    user: dto::user::UserRef {
+                        id,
+                        username: username.unwrap_or_default(),
+                    },
+                    role: role.into(),
+                    invited_by: dto::user::UserRef {
+                        id: inviter_id,
+                        username: inviter_username.unwrap_or_default(),
+                    },
+                },
+            )
+            .collect())
+    }
+
+    /// The number of members who are owners of this team.
+    pub async fn owner_count(&self, db: &mut Db) -> Result<i64, diesel::result::Error> {
+        models::UserTeam::belonging_to(self)
+            .filter(schema::user_teams::role.eq(models::UserTeamRole::Owner))
+            .filter(schema::user_teams::invite_from.is_null())
+            .count()
+            .get_result(db)
+            .await
+    }
 }
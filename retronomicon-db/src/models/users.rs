@@ -22,9 +22,29 @@ use std::collections::BTreeMap;
 use std::fmt::{Debug, Formatter};
 use std::io::Write;
 
+mod account;
+
+mod email_change;
+pub use email_change::*;
+
+mod identities;
+pub use identities::*;
+
 mod password;
 pub use password::*;
 
+mod password_reset;
+pub use password_reset::*;
+
+mod sessions;
+pub use sessions::*;
+
+mod tokens;
+pub use tokens::*;
+
+mod two_factor;
+pub use two_factor::*;
+
 #[derive(Clone, Debug, Queryable, Identifiable, Selectable)]
 #[diesel(table_name = schema::users)]
 pub struct User {
@@ -42,6 +62,9 @@ pub struct User {
     pub description: String,
     pub links: Json,
     pub metadata: Json,
+
+    /// Sessions and JWTs issued before this time are not valid anymore.
+    pub sessions_invalidated_at: Option<NaiveDateTime>,
 }
 
 impl From<User> for dto::user::User {
@@ -91,6 +114,19 @@ impl User {
         }
     }
 
+    /// Find a user that is not deleted from their email.
+    pub async fn get_by_email(
+        db: &mut Db,
+        email: &str,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        schema::users::table
+            .filter(schema::users::email.eq(email))
+            .filter(schema::users::deleted.eq(false))
+            .first::<User>(db)
+            .await
+            .optional()
+    }
+
     pub async fn from_email(
         db: &mut Db,
         email: &str,
@@ -150,6 +186,7 @@ impl User {
                 schema::teams::all_columns,
                 schema::user_teams::role,
             ))
+            .filter(schema::user_teams::invite_from.is_null())
             .into_boxed();
 
         if let Some(id) = user_id.as_id() {
@@ -245,15 +282,41 @@ impl User {
             .await
     }
 
+    /// Mark the user as deleted, terminating all their sessions.
     pub async fn delete(&self, db: &mut Db) -> Result<(), diesel::result::Error> {
         diesel::update(schema::users::table)
             .filter(schema::users::id.eq(self.id))
             .set(schema::users::deleted.eq(true))
             .execute(db)
             .await?;
+        self.invalidate_sessions(db).await
+    }
+
+    /// Invalidate all sessions and JWTs issued to this user until now.
+    pub async fn invalidate_sessions(&self, db: &mut Db) -> Result<(), diesel::result::Error> {
+        diesel::update(schema::users::table)
+            .filter(schema::users::id.eq(self.id))
+            .set(schema::users::sessions_invalidated_at.eq(chrono::Utc::now().naive_utc()))
+            .execute(db)
+            .await?;
+        UserSession::delete_all(db, self.id, None).await?;
         Ok(())
     }
 
+    /// The last time the sessions of a user were invalidated, if ever.
+    pub async fn sessions_invalidated_at(
+        db: &mut Db,
+        id: i32,
+    ) -> Result<Option<NaiveDateTime>, diesel::result::Error> {
+        schema::users::table
+            .filter(schema::users::id.eq(id))
+            .select(schema::users::sessions_invalidated_at)
+            .first::<Option<NaiveDateTime>>(db)
+            .await
+            .optional()
+            .map(Option::flatten)
+    }
+
     pub async fn delete_row(&self, db: &mut Db) -> Result<(), diesel::result::Error> {
         diesel::delete(schema::users::table)
             .filter(schema::users::id.eq(self.id))
@@ -410,6 +473,87 @@ impl User {
         Ok(())
     }
 
+    /// Remove a user from a team, or decline their invitation to it.
+    pub async fn leave_team(&self, db: &mut Db, team_id: i32) -> Result<(), diesel::result::Error> {
+        diesel::delete(schema::user_teams::table)
+            .filter(schema::user_teams::user_id.eq(self.id))
+            .filter(schema::user_teams::team_id.eq(team_id))
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Change the role of a member of a team. Invitations are not changed.
+    pub async fn set_role_in(
+        &self,
+        db: &mut Db,
+        team_id: i32,
+        role: models::UserTeamRole,
+    ) -> Result<(), diesel::result::Error> {
+        diesel::update(schema::user_teams::table)
+            .filter(schema::user_teams::user_id.eq(self.id))
+            .filter(schema::user_teams::team_id.eq(team_id))
+            .filter(schema::user_teams::invite_from.is_null())
+            .set(schema::user_teams::role.eq(role))
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Returns the role a user was invited to a team as, if there's a
+    /// pending invitation.
+    pub async fn invitation_to(
+        &self,
+        db: &mut Db,
+        team_id: i32,
+    ) -> Result<Option<models::UserTeamRole>, diesel::result::Error> {
+        schema::user_teams::table
+            .filter(schema::user_teams::user_id.eq(self.id))
+            .filter(schema::user_teams::team_id.eq(team_id))
+            .filter(schema::user_teams::invite_from.is_not_null())
+            .select(schema::user_teams::role)
+            .first::<models::UserTeamRole>(db)
+            .await
+            .optional()
+    }
+
+    /// The invitations of this user to teams that were not accepted yet.
+    pub async fn invitations(
+        &self,
+        db: &mut Db,
+    ) -> Result<Vec<dto::user::UserInvitation>, diesel::result::Error> {
+        Ok(schema::user_teams::table
+            .inner_join(schema::teams::table)
+            .inner_join(
+                schema::users::table
+                    .on(schema::user_teams::invite_from.eq(schema::users::id.nullable())),
+            )
+            .filter(schema::user_teams::user_id.eq(self.id))
+            .select((
+                schema::teams::all_columns,
+                schema::user_teams::role,
+                schema::users::id,
+                schema::users::username,
+            ))
+            .order(schema::teams::id.asc())
+            .load::<(models::Team, models::UserTeamRole, i32, Option<String>)>(db)
+            .await?
+            .into_iter()
+            .map(
+                |(team, role, inviter_id, inviter_username)| dto::user::UserInvitation {
+                    team: team.into(),
+                    role: role.into(),
+                    invited_by: dto::user::UserRef {
+                        id: inviter_id,
+                        username: inviter_username.unwrap_or_default(),
+                    },
+                },
+            )
+            .collect())
+    }
+
     /// Returns the UserTeamRole, if there's one.
     pub async fn role_in(
         &self,
@@ -427,7 +571,7 @@ impl User {
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, FromSqlRow, AsExpression)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, FromSqlRow, AsExpression)]
 #[diesel(sql_type = sql_types::UserTeamRole)]
 pub enum UserTeamRole {
     Owner = 2,
@@ -435,18 +579,6 @@ pub enum UserTeamRole {
     Member = 0,
 }
 
-impl UserTeamRole {
-    pub fn can_create_systems(&self) -> bool {
-        // Admins and owners can create systems.
-        *self >= Self::Admin
-    }
-
-    pub fn can_create_cores(&self) -> bool {
-        // Admins and owners can create cores.
-        *self >= Self::Admin
-    }
-}
-
 impl ToSql<sql_types::UserTeamRole, Pg> for UserTeamRole {
     fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> diesel::serialize::Result {
         match *self {
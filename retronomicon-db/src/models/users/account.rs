@@ -0,0 +1,291 @@
+use crate::models::{
+    Team, User, UserIdentity, UserSession, UserTeamRole, UserToken, UserTwoFactor,
+};
+use crate::{schema, Db};
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use retronomicon_dto as dto;
+use rocket_db_pools::diesel::scoped_futures::ScopedFutureExt;
+use rocket_db_pools::diesel::{AsyncConnection, RunQueryDsl};
+use serde_json::json;
+
+impl User {
+    /// The teams this user is the only owner of. They cannot leave them
+    /// without another owner.
+    pub async fn sole_owned_teams(&self, db: &mut Db) -> Result<Vec<Team>, diesel::result::Error> {
+        let (owners, other_owners) = diesel::alias!(
+            schema::user_teams as owners,
+            schema::user_teams as other_owners
+        );
+
+        schema::teams::table
+            .inner_join(
+                owners.on(owners
+                    .field(schema::user_teams::team_id)
+                    .eq(schema::teams::id)),
+            )
+            .filter(owners.field(schema::user_teams::user_id).eq(self.id))
+            .filter(
+                owners
+                    .field(schema::user_teams::role)
+                    .eq(UserTeamRole::Owner),
+            )
+            .filter(owners.field(schema::user_teams::invite_from).is_null())
+            .filter(diesel::dsl::not(diesel::dsl::exists(
+                other_owners
+                    .filter(
+                        other_owners
+                            .field(schema::user_teams::team_id)
+                            .eq(schema::teams::id),
+                    )
+                    .filter(other_owners.field(schema::user_teams::user_id).ne(self.id))
+                    .filter(
+                        other_owners
+                            .field(schema::user_teams::role)
+                            .eq(UserTeamRole::Owner),
+                    )
+                    .filter(
+                        other_owners
+                            .field(schema::user_teams::invite_from)
+                            .is_null(),
+                    ),
+            )))
+            .select(schema::teams::all_columns)
+            .order(schema::teams::id.asc())
+            .load::<Team>(db)
+            .await
+    }
+
+    /// Delete the account of a user. The row is kept so releases and uploads
+    /// still reference a valid user, but everything identifying the user is
+    /// removed, as are their credentials and team memberships. The teams in
+    /// `new_owners` are given to the member with the matching ID first.
+    pub async fn anonymize(
+        &self,
+        db: &mut Db,
+        new_owners: &[(i32, i32)],
+    ) -> Result<(), diesel::result::Error> {
+        let user_id = self.id;
+        let new_owners = new_owners.to_vec();
+
+        db.transaction(|db| {
+            async move {
+                for (team_id, owner_id) in new_owners {
+                    diesel::update(schema::user_teams::table)
+                        .filter(schema::user_teams::team_id.eq(team_id))
+                        .filter(schema::user_teams::user_id.eq(owner_id))
+                        .filter(schema::user_teams::invite_from.is_null())
+                        .set(schema::user_teams::role.eq(UserTeamRole::Owner))
+                        .execute(db)
+                        .await?;
+                }
+
+                diesel::delete(schema::user_teams::table)
+                    .filter(schema::user_teams::user_id.eq(user_id))
+                    .execute(db)
+                    .await?;
+                diesel::delete(schema::user_passwords::table)
+                    .filter(schema::user_passwords::user_id.eq(user_id))
+                    .execute(db)
+                    .await?;
+                diesel::delete(schema::notifications::table)
+                    .filter(schema::notifications::user_id.eq(user_id))
+                    .execute(db)
+                    .await?;
+                diesel::delete(schema::notification_preferences::table)
+                    .filter(schema::notification_preferences::user_id.eq(user_id))
+                    .execute(db)
+                    .await?;
+                diesel::delete(schema::core_collaborators::table)
+                    .filter(schema::core_collaborators::user_id.eq(user_id))
+                    .execute(db)
+                    .await?;
+                diesel::delete(schema::core_followers::table)
+                    .filter(schema::core_followers::user_id.eq(user_id))
+                    .execute(db)
+                    .await?;
+                diesel::delete(schema::email_changes::table)
+                    .filter(schema::email_changes::user_id.eq(user_id))
+                    .execute(db)
+                    .await?;
+                diesel::delete(schema::password_reset_tokens::table)
+                    .filter(schema::password_reset_tokens::user_id.eq(user_id))
+                    .execute(db)
+                    .await?;
+                diesel::delete(schema::user_tokens::table)
+                    .filter(schema::user_tokens::user_id.eq(user_id))
+                    .execute(db)
+                    .await?;
+                diesel::delete(schema::user_sessions::table)
+                    .filter(schema::user_sessions::user_id.eq(user_id))
+                    .execute(db)
+                    .await?;
+                diesel::delete(schema::user_identities::table)
+                    .filter(schema::user_identities::user_id.eq(user_id))
+                    .execute(db)
+                    .await?;
+                diesel::delete(schema::user_recovery_codes::table)
+                    .filter(schema::user_recovery_codes::user_id.eq(user_id))
+                    .execute(db)
+                    .await?;
+                diesel::delete(schema::user_two_factor::table)
+                    .filter(schema::user_two_factor::user_id.eq(user_id))
+                    .execute(db)
+                    .await?;
+
+                // Emails are unique and cannot be null.
+                diesel::update(schema::users::table)
+                    .filter(schema::users::id.eq(user_id))
+                    .set((
+                        schema::users::username.eq(None::<String>),
+                        schema::users::display_name.eq(None::<String>),
+                        schema::users::avatar_url.eq(None::<String>),
+                        schema::users::email.eq(format!("deleted-{user_id}@deleted.invalid")),
+                        schema::users::auth_provider.eq(None::<String>),
+                        schema::users::deleted.eq(true),
+                        schema::users::description.eq(""),
+                        schema::users::links.eq(json!({})),
+                        schema::users::metadata.eq(json!({})),
+                        schema::users::sessions_invalidated_at.eq(chrono::Utc::now().naive_utc()),
+                    ))
+                    .execute(db)
+                    .await?;
+                Ok(())
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+
+    /// Everything stored about a user, for them to download.
+    pub async fn export(
+        &self,
+        db: &mut Db,
+    ) -> Result<dto::user::UserExport, diesel::result::Error> {
+        let teams = schema::user_teams::table
+            .inner_join(schema::teams::table)
+            .filter(schema::user_teams::user_id.eq(self.id))
+            .select((
+                schema::teams::all_columns,
+                schema::user_teams::role,
+                schema::user_teams::invite_from,
+            ))
+            .order(schema::teams::id.asc())
+            .load::<(Team, UserTeamRole, Option<i32>)>(db)
+            .await?
+            .into_iter()
+            .map(|(team, role, invite_from)| dto::user::UserExportTeam {
+                team: team.into(),
+                role: role.into(),
+                pending: invite_from.is_some(),
+            })
+            .collect();
+
+        let to_release = |(id, slug, version, date_released, yanked): (
+            i32,
+            String,
+            String,
+            NaiveDateTime,
+            bool,
+        )| dto::user::UserExportRelease {
+            id,
+            slug,
+            version,
+            date_released: date_released.and_utc().timestamp(),
+            yanked,
+        };
+        let core_releases = schema::core_releases::table
+            .inner_join(schema::cores::table)
+            .filter(schema::core_releases::uploader_id.eq(self.id))
+            .select((
+                schema::core_releases::id,
+                schema::cores::slug,
+                schema::core_releases::version,
+                schema::core_releases::date_released,
+                schema::core_releases::yanked,
+            ))
+            .order(schema::core_releases::id.asc())
+            .load(db)
+            .await?
+            .into_iter()
+            .map(to_release)
+            .collect();
+        let system_releases = schema::system_releases::table
+            .inner_join(schema::systems::table)
+            .filter(schema::system_releases::uploader_id.eq(self.id))
+            .select((
+                schema::system_releases::id,
+                schema::systems::slug,
+                schema::system_releases::version,
+                schema::system_releases::date_released,
+                schema::system_releases::yanked,
+            ))
+            .order(schema::system_releases::id.asc())
+            .load(db)
+            .await?
+            .into_iter()
+            .map(to_release)
+            .collect();
+
+        let uploads = schema::core_release_uploads::table
+            .filter(schema::core_release_uploads::user_id.eq(self.id))
+            .select((
+                schema::core_release_uploads::id,
+                schema::core_release_uploads::core_release_id,
+                schema::core_release_uploads::filename,
+                schema::core_release_uploads::size,
+                schema::core_release_uploads::received,
+                schema::core_release_uploads::created_at,
+            ))
+            .order(schema::core_release_uploads::id.asc())
+            .load::<(i32, i32, String, i64, i64, NaiveDateTime)>(db)
+            .await?
+            .into_iter()
+            .map(|(id, release_id, filename, size, received, created_at)| {
+                dto::user::UserExportUpload {
+                    id,
+                    release_id,
+                    filename,
+                    size,
+                    received,
+                    created_at: created_at.and_utc().timestamp(),
+                }
+            })
+            .collect();
+
+        Ok(dto::user::UserExport {
+            exported_at: chrono::Utc::now().timestamp(),
+            profile: dto::user::UserExportProfile {
+                id: self.id,
+                username: self.username.clone(),
+                display_name: self.display_name.clone(),
+                avatar_url: self.avatar_url.clone(),
+                email: self.email.clone(),
+                auth_provider: self.auth_provider.clone(),
+                description: self.description.clone(),
+                links: self.links.clone(),
+                metadata: self.metadata.clone(),
+            },
+            teams,
+            core_releases,
+            system_releases,
+            uploads,
+            identities: UserIdentity::list(db, self.id)
+                .await?
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            sessions: UserSession::list(db, self.id)
+                .await?
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            tokens: UserToken::list(db, self.id)
+                .await?
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            two_factor_enabled: UserTwoFactor::is_enabled(db, self.id).await?,
+        })
+    }
+}
@@ -0,0 +1,117 @@
+use crate::models::User;
+use crate::{schema, Db};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use rand::Rng;
+use rocket_db_pools::diesel::scoped_futures::ScopedFutureExt;
+use rocket_db_pools::diesel::{AsyncConnection, RunQueryDsl};
+use sha2::{Digest, Sha256};
+
+/// A pending change of the email of a user. A user has at most one; asking
+/// for a new change replaces the previous one. Only the SHA256 of the token
+/// is stored.
+#[derive(Clone, Debug, Queryable, Identifiable, Selectable)]
+#[diesel(primary_key(user_id))]
+#[diesel(belongs_to(User))]
+#[diesel(table_name = schema::email_changes)]
+pub struct EmailChange {
+    pub user_id: i32,
+    pub new_email: String,
+    pub token_hash: Vec<u8>,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+}
+
+impl EmailChange {
+    fn hash(token: &str) -> Vec<u8> {
+        Sha256::digest(token.as_bytes()).to_vec()
+    }
+
+    /// Create a pending email change for a user, replacing any previous one.
+    /// Returns the token, which is not stored and must be sent to the new
+    /// email.
+    pub async fn create(
+        db: &mut Db,
+        user: &User,
+        new_email: &str,
+        expires_at: NaiveDateTime,
+    ) -> Result<String, diesel::result::Error> {
+        let bytes: [u8; 32] = rand::thread_rng().gen();
+        let token = URL_SAFE_NO_PAD.encode(bytes);
+        let token_hash = Self::hash(&token);
+        let now = chrono::Utc::now().naive_utc();
+
+        diesel::insert_into(schema::email_changes::table)
+            .values((
+                schema::email_changes::user_id.eq(user.id),
+                schema::email_changes::new_email.eq(new_email),
+                schema::email_changes::token_hash.eq(&token_hash),
+                schema::email_changes::created_at.eq(now),
+                schema::email_changes::expires_at.eq(expires_at),
+            ))
+            .on_conflict(schema::email_changes::user_id)
+            .do_update()
+            .set((
+                schema::email_changes::new_email.eq(new_email),
+                schema::email_changes::token_hash.eq(&token_hash),
+                schema::email_changes::created_at.eq(now),
+                schema::email_changes::expires_at.eq(expires_at),
+            ))
+            .execute(db)
+            .await?;
+
+        Ok(token)
+    }
+
+    /// Consume an email change token. If the token exists and is not
+    /// expired, the email of its user is replaced with the new email. Returns
+    /// the updated user and their previous email. The token cannot be used
+    /// again, unless the email could not be changed (e.g. it is used by
+    /// another user in the meantime).
+    pub async fn consume(
+        db: &mut Db,
+        token: &str,
+    ) -> Result<Option<(User, String)>, diesel::result::Error> {
+        let token_hash = Self::hash(token);
+        db.transaction(|db| {
+            async move {
+                let found = schema::email_changes::table
+                    .inner_join(schema::users::table)
+                    .filter(schema::email_changes::token_hash.eq(token_hash))
+                    .filter(schema::users::deleted.eq(false))
+                    .select((
+                        schema::email_changes::all_columns,
+                        schema::users::all_columns,
+                    ))
+                    .for_update()
+                    .first::<(Self, User)>(db)
+                    .await
+                    .optional()?;
+
+                let (change, user) = match found {
+                    Some(found) => found,
+                    None => return Ok(None),
+                };
+
+                diesel::delete(schema::email_changes::table)
+                    .filter(schema::email_changes::user_id.eq(change.user_id))
+                    .execute(db)
+                    .await?;
+
+                if change.expires_at < chrono::Utc::now().naive_utc() {
+                    return Ok(None);
+                }
+
+                let updated = diesel::update(schema::users::table)
+                    .filter(schema::users::id.eq(user.id))
+                    .set(schema::users::email.eq(&change.new_email))
+                    .get_result::<User>(db)
+                    .await?;
+                Ok(Some((updated, user.email)))
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+}
@@ -0,0 +1,111 @@
+use crate::models::User;
+use crate::{schema, Db};
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use retronomicon_dto as dto;
+use rocket_db_pools::diesel::RunQueryDsl;
+
+/// An account of a user with an external OpenID Connect provider, identified
+/// by its subject.
+#[derive(Clone, Debug, Queryable, Identifiable, Selectable)]
+#[diesel(belongs_to(User))]
+#[diesel(table_name = schema::user_identities)]
+pub struct UserIdentity {
+    pub id: i32,
+    pub user_id: i32,
+    pub provider: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub last_login_at: Option<NaiveDateTime>,
+}
+
+impl From<UserIdentity> for dto::auth::UserIdentity {
+    fn from(value: UserIdentity) -> Self {
+        Self {
+            id: value.id,
+            provider: value.provider,
+            email: value.email,
+            created_at: value.created_at.and_utc().timestamp(),
+            last_login_at: value.last_login_at.map(|d| d.and_utc().timestamp()),
+        }
+    }
+}
+
+impl UserIdentity {
+    /// Find an identity and its user. Identities of deleted users are
+    /// ignored.
+    pub async fn get_by_subject(
+        db: &mut Db,
+        provider: &str,
+        subject: &str,
+    ) -> Result<Option<(Self, User)>, diesel::result::Error> {
+        schema::user_identities::table
+            .inner_join(schema::users::table)
+            .filter(schema::user_identities::provider.eq(provider))
+            .filter(schema::user_identities::subject.eq(subject))
+            .filter(schema::users::deleted.eq(false))
+            .select((
+                schema::user_identities::all_columns,
+                schema::users::all_columns,
+            ))
+            .first::<(Self, User)>(db)
+            .await
+            .optional()
+    }
+
+    pub async fn list(db: &mut Db, user_id: i32) -> Result<Vec<Self>, diesel::result::Error> {
+        schema::user_identities::table
+            .filter(schema::user_identities::user_id.eq(user_id))
+            .order(schema::user_identities::id.asc())
+            .load::<Self>(db)
+            .await
+    }
+
+    pub async fn create(
+        db: &mut Db,
+        user_id: i32,
+        provider: &str,
+        subject: &str,
+        email: Option<&str>,
+    ) -> Result<Self, diesel::result::Error> {
+        diesel::insert_into(schema::user_identities::table)
+            .values((
+                schema::user_identities::user_id.eq(user_id),
+                schema::user_identities::provider.eq(provider),
+                schema::user_identities::subject.eq(subject),
+                schema::user_identities::email.eq(email),
+                schema::user_identities::last_login_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .returning(schema::user_identities::all_columns)
+            .get_result::<Self>(db)
+            .await
+    }
+
+    /// Unlink an identity from a user. Returns false if it does not exist.
+    pub async fn delete(db: &mut Db, user_id: i32, id: i32) -> Result<bool, diesel::result::Error> {
+        diesel::delete(schema::user_identities::table)
+            .filter(schema::user_identities::user_id.eq(user_id))
+            .filter(schema::user_identities::id.eq(id))
+            .execute(db)
+            .await
+            .map(|count| count > 0)
+    }
+
+    /// Record a login, updating the email known by the provider.
+    pub async fn touch(
+        &self,
+        db: &mut Db,
+        email: Option<&str>,
+    ) -> Result<(), diesel::result::Error> {
+        diesel::update(schema::user_identities::table)
+            .filter(schema::user_identities::id.eq(self.id))
+            .set((
+                schema::user_identities::email.eq(email),
+                schema::user_identities::last_login_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+}
@@ -68,7 +68,10 @@ pub struct UserPassword {
 }
 
 impl UserPassword {
-    async fn from_user(db: &mut Db, user: &User) -> Result<Option<Self>, diesel::result::Error> {
+    pub async fn from_user(
+        db: &mut Db,
+        user: &User,
+    ) -> Result<Option<Self>, diesel::result::Error> {
         schema::user_passwords::table
             .filter(schema::user_passwords::user_id.eq(user.id))
             .first::<Self>(db)
@@ -129,6 +132,39 @@ impl UserPassword {
             .await?)
     }
 
+    /// Set the password of a user, creating it if the user had none (e.g. a
+    /// user that only signed up through OAuth). The password is marked as
+    /// validated, as the user proved they own the email or account.
+    pub async fn set(
+        db: &mut Db,
+        user: &User,
+        password: &str,
+        pepper: &[u8],
+    ) -> Result<Self, anyhow::Error> {
+        let password_hash: String = DbPassword::create(pepper, password)?.into();
+        let now = chrono::Utc::now().naive_utc();
+
+        Ok(diesel::insert_into(schema::user_passwords::table)
+            .values((
+                schema::user_passwords::user_id.eq(user.id),
+                schema::user_passwords::password.eq(&password_hash),
+                schema::user_passwords::updated_at.eq(now),
+                schema::user_passwords::needs_reset.eq(false),
+                schema::user_passwords::validation_token.eq::<Option<String>>(None),
+            ))
+            .on_conflict(schema::user_passwords::user_id)
+            .do_update()
+            .set((
+                schema::user_passwords::password.eq(&password_hash),
+                schema::user_passwords::updated_at.eq(now),
+                schema::user_passwords::needs_reset.eq(false),
+                schema::user_passwords::validation_token.eq::<Option<String>>(None),
+            ))
+            .returning(schema::user_passwords::all_columns)
+            .get_result(db)
+            .await?)
+    }
+
     pub async fn verify_password(
         db: &mut Db,
         user: User,
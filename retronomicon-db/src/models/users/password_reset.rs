@@ -0,0 +1,96 @@
+use crate::models::User;
+use crate::{schema, Db};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use rand::Rng;
+use rocket_db_pools::diesel::RunQueryDsl;
+use sha2::{Digest, Sha256};
+
+/// A pending password reset for a user. A user has at most one; requesting a
+/// new reset replaces the previous token. Only the SHA256 of the token is
+/// stored.
+#[derive(Clone, Debug, Queryable, Identifiable, Selectable)]
+#[diesel(primary_key(user_id))]
+#[diesel(belongs_to(User))]
+#[diesel(table_name = schema::password_reset_tokens)]
+pub struct PasswordResetToken {
+    pub user_id: i32,
+    pub token_hash: Vec<u8>,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+}
+
+impl PasswordResetToken {
+    fn hash(token: &str) -> Vec<u8> {
+        Sha256::digest(token.as_bytes()).to_vec()
+    }
+
+    /// Create a new reset token for a user, replacing any previous one.
+    /// Returns the token, which is not stored and must be sent to the user.
+    pub async fn create(
+        db: &mut Db,
+        user: &User,
+        expires_at: NaiveDateTime,
+    ) -> Result<String, diesel::result::Error> {
+        let bytes: [u8; 32] = rand::thread_rng().gen();
+        let token = URL_SAFE_NO_PAD.encode(bytes);
+        let token_hash = Self::hash(&token);
+        let now = chrono::Utc::now().naive_utc();
+
+        diesel::insert_into(schema::password_reset_tokens::table)
+            .values((
+                schema::password_reset_tokens::user_id.eq(user.id),
+                schema::password_reset_tokens::token_hash.eq(&token_hash),
+                schema::password_reset_tokens::created_at.eq(now),
+                schema::password_reset_tokens::expires_at.eq(expires_at),
+            ))
+            .on_conflict(schema::password_reset_tokens::user_id)
+            .do_update()
+            .set((
+                schema::password_reset_tokens::token_hash.eq(&token_hash),
+                schema::password_reset_tokens::created_at.eq(now),
+                schema::password_reset_tokens::expires_at.eq(expires_at),
+            ))
+            .execute(db)
+            .await?;
+
+        Ok(token)
+    }
+
+    /// Consume a reset token, returning its user if the token exists, is not
+    /// expired and matches the email. The token cannot be used again.
+    pub async fn consume(
+        db: &mut Db,
+        email: &str,
+        token: &str,
+    ) -> Result<Option<User>, diesel::result::Error> {
+        let found = schema::password_reset_tokens::table
+            .inner_join(schema::users::table)
+            .filter(schema::password_reset_tokens::token_hash.eq(Self::hash(token)))
+            .filter(schema::users::email.eq(email))
+            .filter(schema::users::deleted.eq(false))
+            .select((
+                schema::password_reset_tokens::all_columns,
+                schema::users::all_columns,
+            ))
+            .first::<(Self, User)>(db)
+            .await
+            .optional()?;
+
+        let (reset, user) = match found {
+            Some(found) => found,
+            None => return Ok(None),
+        };
+
+        diesel::delete(schema::password_reset_tokens::table)
+            .filter(schema::password_reset_tokens::user_id.eq(reset.user_id))
+            .execute(db)
+            .await?;
+
+        if reset.expires_at < chrono::Utc::now().naive_utc() {
+            return Ok(None);
+        }
+        Ok(Some(user))
+    }
+}
@@ -0,0 +1,148 @@
+use crate::models::User;
+use crate::{schema, Db};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use rand::Rng;
+use retronomicon_dto as dto;
+use rocket_db_pools::diesel::RunQueryDsl;
+use sha2::{Digest, Sha256};
+
+/// A session of a user logged in from a browser. The auth cookie contains a
+/// random key, of which only the SHA256 is stored.
+#[derive(Clone, Debug, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = schema::user_sessions)]
+#[diesel(belongs_to(User))]
+pub struct UserSession {
+    pub id: i32,
+    pub user_id: i32,
+    pub key_hash: Vec<u8>,
+    pub device: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub last_seen_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+}
+
+impl From<UserSession> for dto::auth::UserSession {
+    fn from(value: UserSession) -> Self {
+        Self {
+            id: value.id,
+            device: value.device,
+            user_agent: value.user_agent,
+            ip_address: value.ip_address,
+            created_at: value.created_at.and_utc().timestamp(),
+            last_seen_at: value.last_seen_at.and_utc().timestamp(),
+            expires_at: value.expires_at.and_utc().timestamp(),
+            current: false,
+        }
+    }
+}
+
+impl UserSession {
+    /// Generate a new random session key, returning it with its hash.
+    pub fn generate() -> (String, Vec<u8>) {
+        let key = URL_SAFE_NO_PAD.encode(rand::thread_rng().gen::<[u8; 32]>());
+        let hash = Self::hash(&key);
+        (key, hash)
+    }
+
+    pub fn hash(key: &str) -> Vec<u8> {
+        Sha256::digest(key.as_bytes()).to_vec()
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at < chrono::Utc::now().naive_utc()
+    }
+
+    /// Find a session from its key. Sessions of deleted users are ignored.
+    pub async fn get_by_key(db: &mut Db, key: &str) -> Result<Option<Self>, diesel::result::Error> {
+        schema::user_sessions::table
+            .inner_join(schema::users::table)
+            .filter(schema::user_sessions::key_hash.eq(Self::hash(key)))
+            .filter(schema::users::deleted.eq(false))
+            .select(schema::user_sessions::all_columns)
+            .first::<Self>(db)
+            .await
+            .optional()
+    }
+
+    /// List the sessions of a user that have not expired.
+    pub async fn list(db: &mut Db, user_id: i32) -> Result<Vec<Self>, diesel::result::Error> {
+        schema::user_sessions::table
+            .filter(schema::user_sessions::user_id.eq(user_id))
+            .filter(schema::user_sessions::expires_at.gt(chrono::Utc::now().naive_utc()))
+            .order(schema::user_sessions::last_seen_at.desc())
+            .load::<Self>(db)
+            .await
+    }
+
+    /// Create a session. Expired sessions of the user are removed at the
+    /// same time.
+    pub async fn create(
+        db: &mut Db,
+        user_id: i32,
+        key_hash: &[u8],
+        device: Option<&str>,
+        user_agent: Option<&str>,
+        ip_address: Option<&str>,
+        expires_at: NaiveDateTime,
+    ) -> Result<Self, diesel::result::Error> {
+        diesel::delete(schema::user_sessions::table)
+            .filter(schema::user_sessions::user_id.eq(user_id))
+            .filter(schema::user_sessions::expires_at.le(chrono::Utc::now().naive_utc()))
+            .execute(db)
+            .await?;
+
+        diesel::insert_into(schema::user_sessions::table)
+            .values((
+                schema::user_sessions::user_id.eq(user_id),
+                schema::user_sessions::key_hash.eq(key_hash),
+                schema::user_sessions::device.eq(device),
+                schema::user_sessions::user_agent.eq(user_agent),
+                schema::user_sessions::ip_address.eq(ip_address),
+                schema::user_sessions::expires_at.eq(expires_at),
+            ))
+            .returning(schema::user_sessions::all_columns)
+            .get_result::<Self>(db)
+            .await
+    }
+
+    /// Record that the session was used.
+    pub async fn touch(&self, db: &mut Db) -> Result<(), diesel::result::Error> {
+        diesel::update(schema::user_sessions::table)
+            .filter(schema::user_sessions::id.eq(self.id))
+            .set(schema::user_sessions::last_seen_at.eq(chrono::Utc::now().naive_utc()))
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    /// Revoke a session of a user. Returns false if the session does not
+    /// exist.
+    pub async fn delete(db: &mut Db, user_id: i32, id: i32) -> Result<bool, diesel::result::Error> {
+        diesel::delete(schema::user_sessions::table)
+            .filter(schema::user_sessions::user_id.eq(user_id))
+            .filter(schema::user_sessions::id.eq(id))
+            .execute(db)
+            .await
+            .map(|count| count > 0)
+    }
+
+    /// Revoke all sessions of a user, except one if specified. Returns the
+    /// number of sessions revoked.
+    pub async fn delete_all(
+        db: &mut Db,
+        user_id: i32,
+        except: Option<i32>,
+    ) -> Result<usize, diesel::result::Error> {
+        let mut query = diesel::delete(schema::user_sessions::table)
+            .filter(schema::user_sessions::user_id.eq(user_id))
+            .into_boxed();
+        if let Some(id) = except {
+            query = query.filter(schema::user_sessions::id.ne(id));
+        }
+        query.execute(db).await
+    }
+}
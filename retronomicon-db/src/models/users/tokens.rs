@@ -0,0 +1,139 @@
+use crate::models::User;
+use crate::{schema, Db};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use rand::Rng;
+use retronomicon_dto as dto;
+use rocket_db_pools::diesel::RunQueryDsl;
+use serde_json::Value as Json;
+use sha2::{Digest, Sha256};
+
+/// The prefix of all personal access tokens, to tell them apart from JWTs.
+pub const USER_TOKEN_PREFIX: &str = "rtk_";
+
+/// A personal access token. Only the SHA256 of the token is stored; tokens
+/// are random so a slow hash isn't needed.
+#[derive(Clone, Debug, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = schema::user_tokens)]
+#[diesel(belongs_to(User))]
+pub struct UserToken {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub token_hash: Vec<u8>,
+    pub scopes: Json,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+}
+
+impl From<UserToken> for dto::tokens::UserToken {
+    fn from(value: UserToken) -> Self {
+        let scopes = value.scopes();
+        Self {
+            id: value.id,
+            name: value.name,
+            scopes,
+            created_at: value.created_at.and_utc().timestamp(),
+            expires_at: value.expires_at.and_utc().timestamp(),
+            last_used_at: value.last_used_at.map(|d| d.and_utc().timestamp()),
+        }
+    }
+}
+
+impl UserToken {
+    /// Generate a new random token, returning it with its hash.
+    pub fn generate() -> (String, Vec<u8>) {
+        let bytes: [u8; 32] = rand::thread_rng().gen();
+        let token = format!("{USER_TOKEN_PREFIX}{}", URL_SAFE_NO_PAD.encode(bytes));
+        let hash = Self::hash(&token);
+        (token, hash)
+    }
+
+    pub fn hash(token: &str) -> Vec<u8> {
+        Sha256::digest(token.as_bytes()).to_vec()
+    }
+
+    pub fn scopes(&self) -> Vec<dto::tokens::TokenScope> {
+        serde_json::from_value(self.scopes.clone()).unwrap_or_default()
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at < chrono::Utc::now().naive_utc()
+    }
+
+    /// Find a token and its user from the token itself. Tokens of deleted
+    /// users are ignored.
+    pub async fn get_by_token(
+        db: &mut Db,
+        token: &str,
+    ) -> Result<Option<(Self, User)>, diesel::result::Error> {
+        schema::user_tokens::table
+            .inner_join(schema::users::table)
+            .filter(schema::user_tokens::token_hash.eq(Self::hash(token)))
+            .filter(schema::users::deleted.eq(false))
+            .select((schema::user_tokens::all_columns, schema::users::all_columns))
+            .first::<(Self, User)>(db)
+            .await
+            .optional()
+    }
+
+    pub async fn list(db: &mut Db, user_id: i32) -> Result<Vec<Self>, diesel::result::Error> {
+        schema::user_tokens::table
+            .filter(schema::user_tokens::user_id.eq(user_id))
+            .order(schema::user_tokens::id.asc())
+            .load::<Self>(db)
+            .await
+    }
+
+    pub async fn create(
+        db: &mut Db,
+        user_id: i32,
+        name: &str,
+        token_hash: &[u8],
+        scopes: &[dto::tokens::TokenScope],
+        expires_at: NaiveDateTime,
+    ) -> Result<Self, diesel::result::Error> {
+        diesel::insert_into(schema::user_tokens::table)
+            .values((
+                schema::user_tokens::user_id.eq(user_id),
+                schema::user_tokens::name.eq(name),
+                schema::user_tokens::token_hash.eq(token_hash),
+                schema::user_tokens::scopes
+                    .eq(serde_json::to_value(scopes).expect("Scopes are serializable")),
+                schema::user_tokens::expires_at.eq(expires_at),
+            ))
+            .returning(schema::user_tokens::all_columns)
+            .get_result::<Self>(db)
+            .await
+    }
+
+    /// Revoke a token of a user. Returns false if the token does not exist.
+    pub async fn delete(db: &mut Db, user_id: i32, id: i32) -> Result<bool, diesel::result::Error> {
+        diesel::delete(schema::user_tokens::table)
+            .filter(schema::user_tokens::user_id.eq(user_id))
+            .filter(schema::user_tokens::id.eq(id))
+            .execute(db)
+            .await
+            .map(|count| count > 0)
+    }
+
+    /// Revoke all tokens of a user.
+    pub async fn delete_all(db: &mut Db, user_id: i32) -> Result<(), diesel::result::Error> {
+        diesel::delete(schema::user_tokens::table)
+            .filter(schema::user_tokens::user_id.eq(user_id))
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn touch(&self, db: &mut Db) -> Result<(), diesel::result::Error> {
+        diesel::update(schema::user_tokens::table)
+            .filter(schema::user_tokens::id.eq(self.id))
+            .set(schema::user_tokens::last_used_at.eq(chrono::Utc::now().naive_utc()))
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,282 @@
+use crate::models::User;
+use crate::{schema, Db};
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use rand::Rng;
+use rocket_db_pools::diesel::scoped_futures::ScopedFutureExt;
+use rocket_db_pools::diesel::{AsyncConnection, RunQueryDsl};
+use sha2::{Digest, Sha256};
+
+/// The number of recovery codes generated for a user.
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+/// The TOTP secret of a user. Two-factor authentication is enabled once the
+/// secret has been confirmed.
+#[derive(Clone, Debug, Queryable, Identifiable, Selectable)]
+#[diesel(primary_key(user_id))]
+#[diesel(belongs_to(User))]
+#[diesel(table_name = schema::user_two_factor)]
+pub struct UserTwoFactor {
+    pub user_id: i32,
+    pub secret: Vec<u8>,
+    pub created_at: NaiveDateTime,
+    pub confirmed_at: Option<NaiveDateTime>,
+    pub last_used_step: Option<i64>,
+    pub failed_attempts: i32,
+    pub last_failed_at: Option<NaiveDateTime>,
+}
+
+impl UserTwoFactor {
+    pub fn is_confirmed(&self) -> bool {
+        self.confirmed_at.is_some()
+    }
+
+    pub async fn from_user_id(
+        db: &mut Db,
+        user_id: i32,
+    ) -> Result<Option<Self>, diesel::result::Error> {
+        schema::user_two_factor::table
+            .filter(schema::user_two_factor::user_id.eq(user_id))
+            .first::<Self>(db)
+            .await
+            .optional()
+    }
+
+    /// Whether a user has two-factor authentication enabled.
+    pub async fn is_enabled(db: &mut Db, user_id: i32) -> Result<bool, diesel::result::Error> {
+        Ok(Self::from_user_id(db, user_id)
+            .await?
+            .is_some_and(|t| t.is_confirmed()))
+    }
+
+    /// Start enrolling a user with a new secret, replacing any secret that
+    /// was not confirmed yet.
+    pub async fn create(
+        db: &mut Db,
+        user_id: i32,
+        secret: &[u8],
+    ) -> Result<Self, diesel::result::Error> {
+        let now = chrono::Utc::now().naive_utc();
+        diesel::insert_into(schema::user_two_factor::table)
+            .values((
+                schema::user_two_factor::user_id.eq(user_id),
+                schema::user_two_factor::secret.eq(secret),
+                schema::user_two_factor::created_at.eq(now),
+            ))
+            .on_conflict(schema::user_two_factor::user_id)
+            .do_update()
+            .set((
+                schema::user_two_factor::secret.eq(secret),
+                schema::user_two_factor::created_at.eq(now),
+                schema::user_two_factor::confirmed_at.eq::<Option<NaiveDateTime>>(None),
+                schema::user_two_factor::last_used_step.eq::<Option<i64>>(None),
+                schema::user_two_factor::failed_attempts.eq(0),
+                schema::user_two_factor::last_failed_at.eq::<Option<NaiveDateTime>>(None),
+            ))
+            .returning(schema::user_two_factor::all_columns)
+            .get_result::<Self>(db)
+            .await
+    }
+
+    /// Confirm the secret, enabling two-factor authentication, and replace
+    /// the recovery codes of the user. Returns the new recovery codes.
+    pub async fn confirm(
+        &self,
+        db: &mut Db,
+        step: i64,
+    ) -> Result<Vec<String>, diesel::result::Error> {
+        let user_id = self.user_id;
+        db.transaction(|db| {
+            async move {
+                diesel::update(schema::user_two_factor::table)
+                    .filter(schema::user_two_factor::user_id.eq(user_id))
+                    .set((
+                        schema::user_two_factor::confirmed_at.eq(chrono::Utc::now().naive_utc()),
+                        schema::user_two_factor::last_used_step.eq(step),
+                    ))
+                    .execute(db)
+                    .await?;
+                UserRecoveryCode::regenerate(db, user_id).await
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+
+    /// Record a code accepted for a time step. Returns false if a code was
+    /// already accepted for this step or a later one, i.e. the code is being
+    /// replayed.
+    pub async fn use_step(&self, db: &mut Db, step: i64) -> Result<bool, diesel::result::Error> {
+        diesel::update(schema::user_two_factor::table)
+            .filter(schema::user_two_factor::user_id.eq(self.user_id))
+            .filter(
+                schema::user_two_factor::last_used_step
+                    .is_null()
+                    .or(schema::user_two_factor::last_used_step.lt(step)),
+            )
+            .set((
+                schema::user_two_factor::last_used_step.eq(step),
+                schema::user_two_factor::failed_attempts.eq(0),
+            ))
+            .execute(db)
+            .await
+            .map(|count| count > 0)
+    }
+
+    /// Whether too many invalid codes were tried recently.
+    pub fn is_throttled(&self, max_attempts: i32, window: chrono::Duration) -> bool {
+        self.failed_attempts >= max_attempts
+            && self
+                .last_failed_at
+                .is_some_and(|at| at + window > chrono::Utc::now().naive_utc())
+    }
+
+    pub async fn record_failure(&self, db: &mut Db) -> Result<(), diesel::result::Error> {
+        diesel::update(schema::user_two_factor::table)
+            .filter(schema::user_two_factor::user_id.eq(self.user_id))
+            .set((
+                schema::user_two_factor::failed_attempts
+                    .eq(schema::user_two_factor::failed_attempts + 1),
+                schema::user_two_factor::last_failed_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn reset_failures(&self, db: &mut Db) -> Result<(), diesel::result::Error> {
+        diesel::update(schema::user_two_factor::table)
+            .filter(schema::user_two_factor::user_id.eq(self.user_id))
+            .set(schema::user_two_factor::failed_attempts.eq(0))
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    /// Disable two-factor authentication, removing the secret and all
+    /// recovery codes.
+    pub async fn delete(&self, db: &mut Db) -> Result<(), diesel::result::Error> {
+        let user_id = self.user_id;
+        db.transaction(|db| {
+            async move {
+                diesel::delete(schema::user_recovery_codes::table)
+                    .filter(schema::user_recovery_codes::user_id.eq(user_id))
+                    .execute(db)
+                    .await?;
+                diesel::delete(schema::user_two_factor::table)
+                    .filter(schema::user_two_factor::user_id.eq(user_id))
+                    .execute(db)
+                    .await?;
+                Ok(())
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+}
+
+/// A one-time recovery code, usable instead of a TOTP code. Codes are random
+/// so only their SHA256 is stored.
+#[derive(Clone, Debug, Queryable, Identifiable, Selectable)]
+#[diesel(belongs_to(User))]
+#[diesel(table_name = schema::user_recovery_codes)]
+pub struct UserRecoveryCode {
+    pub id: i32,
+    pub user_id: i32,
+    pub code_hash: Vec<u8>,
+    pub used_at: Option<NaiveDateTime>,
+}
+
+impl UserRecoveryCode {
+    /// Normalize a code as typed by a user (case and dashes are ignored)
+    /// and hash it.
+    fn hash(code: &str) -> Vec<u8> {
+        let code = code
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .map(|c| c.to_ascii_lowercase())
+            .collect::<String>();
+        Sha256::digest(code.as_bytes()).to_vec()
+    }
+
+    fn generate() -> String {
+        const ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+        let mut rng = rand::thread_rng();
+        let mut code = String::with_capacity(11);
+        for i in 0..10 {
+            if i == 5 {
+                code.push('-');
+            }
+            code.push(ALPHABET[rng.gen_range(0..ALPHABET.len())] as char);
+        }
+        code
+    }
+
+    /// Replace all recovery codes of a user. Returns the new codes.
+    pub async fn regenerate(
+        db: &mut Db,
+        user_id: i32,
+    ) -> Result<Vec<String>, diesel::result::Error> {
+        let codes = (0..RECOVERY_CODE_COUNT)
+            .map(|_| Self::generate())
+            .collect::<Vec<_>>();
+
+        diesel::delete(schema::user_recovery_codes::table)
+            .filter(schema::user_recovery_codes::user_id.eq(user_id))
+            .execute(db)
+            .await?;
+        diesel::insert_into(schema::user_recovery_codes::table)
+            .values(
+                codes
+                    .iter()
+                    .map(|code| {
+                        (
+                            schema::user_recovery_codes::user_id.eq(user_id),
+                            schema::user_recovery_codes::code_hash.eq(Self::hash(code)),
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .execute(db)
+            .await?;
+
+        Ok(codes)
+    }
+
+    /// The number of recovery codes a user has not used yet.
+    pub async fn count_unused(db: &mut Db, user_id: i32) -> Result<i64, diesel::result::Error> {
+        schema::user_recovery_codes::table
+            .filter(schema::user_recovery_codes::user_id.eq(user_id))
+            .filter(schema::user_recovery_codes::used_at.is_null())
+            .count()
+            .get_result(db)
+            .await
+    }
+
+    /// Use a recovery code. Returns false if the code does not exist or was
+    /// already used.
+    pub async fn consume(
+        db: &mut Db,
+        user_id: i32,
+        code: &str,
+    ) -> Result<bool, diesel::result::Error> {
+        diesel::update(schema::user_recovery_codes::table)
+            .filter(schema::user_recovery_codes::user_id.eq(user_id))
+            .filter(schema::user_recovery_codes::code_hash.eq(Self::hash(code)))
+            .filter(schema::user_recovery_codes::used_at.is_null())
+            .set(schema::user_recovery_codes::used_at.eq(chrono::Utc::now().naive_utc()))
+            .execute(db)
+            .await
+            .map(|count| count > 0)
+    }
+}
+
+#[test]
+fn recovery_codes_ignore_case_and_dashes() {
+    let code = UserRecoveryCode::generate();
+    assert_eq!(code.len(), 11);
+    assert_eq!(
+        UserRecoveryCode::hash(&code),
+        UserRecoveryCode::hash(&code.replace('-', "").to_uppercase())
+    );
+}
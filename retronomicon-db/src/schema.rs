@@ -16,10 +16,26 @@ diesel::table! {
         created_at -> Timestamp,
         md5 -> Bytea,
         sha256 -> Bytea,
-        size -> Int4,
+        size -> Int8,
         #[max_length = 255]
         download_url -> Nullable<Varchar>,
         sha1 -> Bytea,
+        blob_id -> Nullable<Int4>,
+        crc32 -> Bytea,
+    }
+}
+
+diesel::table! {
+    blobs (id) {
+        id -> Int4,
+        sha256 -> Bytea,
+        md5 -> Bytea,
+        sha1 -> Bytea,
+        size -> Int8,
+        storage_path -> Nullable<Varchar>,
+        #[max_length = 255]
+        download_url -> Nullable<Varchar>,
+        created_at -> Timestamp,
     }
 }
 
@@ -30,6 +46,65 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    companies (id) {
+        id -> Int4,
+        slug -> Varchar,
+        name -> Varchar,
+    }
+}
+
+diesel::table! {
+    core_collaborators (core_id, user_id, permission) {
+        core_id -> Int4,
+        user_id -> Int4,
+        #[max_length = 64]
+        permission -> Varchar,
+        granted_by_id -> Nullable<Int4>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    core_followers (core_id, user_id) {
+        core_id -> Int4,
+        user_id -> Int4,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    core_release_signatures (core_release_id, team_signing_key_id) {
+        core_release_id -> Int4,
+        team_signing_key_id -> Int4,
+        manifest_sha256 -> Bytea,
+        signature -> Bytea,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    core_release_uploads (id) {
+        id -> Int4,
+        core_release_id -> Int4,
+        user_id -> Int4,
+        #[max_length = 255]
+        filename -> Varchar,
+        #[max_length = 255]
+        mime_type -> Varchar,
+        size -> Int8,
+        received -> Int8,
+        storage_path -> Varchar,
+        storage_upload_id -> Varchar,
+        parts -> Jsonb,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        md5 -> Nullable<Bytea>,
+        sha1 -> Nullable<Bytea>,
+        sha256 -> Nullable<Bytea>,
+    }
+}
+
 diesel::table! {
     core_releases (id) {
         id -> Int4,
@@ -47,6 +122,32 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    core_required_file_hashes (id) {
+        id -> Int4,
+        required_file_id -> Int4,
+        size -> Nullable<Int8>,
+        crc32 -> Bytea,
+        md5 -> Bytea,
+        sha1 -> Bytea,
+        sha256 -> Bytea,
+    }
+}
+
+diesel::table! {
+    core_required_files (id) {
+        id -> Int4,
+        core_id -> Int4,
+        core_release_id -> Nullable<Int4>,
+        #[max_length = 255]
+        filename -> Varchar,
+        #[max_length = 1024]
+        path -> Varchar,
+        optional -> Bool,
+        description -> Text,
+    }
+}
+
 diesel::table! {
     core_tags (tag_id, core_id) {
         core_id -> Int4,
@@ -68,6 +169,38 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    dat_imports (id) {
+        id -> Int4,
+        system_id -> Int4,
+        user_id -> Nullable<Int4>,
+        dat_name -> Varchar,
+        dat_version -> Varchar,
+        dat -> Nullable<Bytea>,
+        dry_run -> Bool,
+        #[max_length = 32]
+        status -> Varchar,
+        preview -> Nullable<Jsonb>,
+        total -> Int4,
+        processed -> Int4,
+        error -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        finished_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    email_changes (user_id) {
+        user_id -> Int4,
+        #[max_length = 255]
+        new_email -> Varchar,
+        token_hash -> Bytea,
+        created_at -> Timestamp,
+        expires_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     files (id) {
         id -> Int4,
@@ -79,6 +212,43 @@ diesel::table! {
     game_artifacts (game_id, artifact_id) {
         game_id -> Int4,
         artifact_id -> Int4,
+        #[max_length = 255]
+        filename -> Varchar,
+        #[max_length = 1024]
+        path -> Varchar,
+        #[max_length = 32]
+        role -> Varchar,
+        sort_order -> Int4,
+        #[max_length = 16]
+        status -> Varchar,
+    }
+}
+
+diesel::table! {
+    game_companies (game_id, company_id, role) {
+        game_id -> Int4,
+        company_id -> Int4,
+        #[max_length = 32]
+        role -> Varchar,
+    }
+}
+
+diesel::table! {
+    game_genres (game_id, genre_id) {
+        game_id -> Int4,
+        genre_id -> Int4,
+    }
+}
+
+diesel::table! {
+    game_image_derivatives (id) {
+        id -> Int4,
+        game_image_id -> Int4,
+        width -> Int4,
+        height -> Int4,
+        #[max_length = 255]
+        mime_type -> Varchar,
+        url -> Varchar,
     }
 }
 
@@ -100,6 +270,59 @@ diesel::table! {
         #[max_length = 255]
         mime_type -> Varchar,
         url -> Varchar,
+        perceptual_hash -> Nullable<Int8>,
+    }
+}
+
+diesel::table! {
+    game_languages (game_id, language) {
+        game_id -> Int4,
+        #[max_length = 35]
+        language -> Varchar,
+    }
+}
+
+diesel::table! {
+    game_relationships (game_id, kind, related_game_id) {
+        game_id -> Int4,
+        #[max_length = 32]
+        kind -> Varchar,
+        related_game_id -> Int4,
+    }
+}
+
+diesel::table! {
+    game_releases (game_id, region_id) {
+        game_id -> Int4,
+        region_id -> Int4,
+        release_date -> Nullable<Date>,
+    }
+}
+
+diesel::table! {
+    game_series (game_id, series_id) {
+        game_id -> Int4,
+        series_id -> Int4,
+    }
+}
+
+diesel::table! {
+    game_tags (game_id, tag_id) {
+        game_id -> Int4,
+        tag_id -> Int4,
+    }
+}
+
+diesel::table! {
+    game_translations (game_id, language) {
+        game_id -> Int4,
+        #[max_length = 35]
+        language -> Varchar,
+        #[max_length = 255]
+        name -> Varchar,
+        #[max_length = 255]
+        short_description -> Nullable<Varchar>,
+        description -> Nullable<Varchar>,
     }
 }
 
@@ -112,11 +335,53 @@ diesel::table! {
         #[max_length = 255]
         short_description -> Varchar,
         year -> Int4,
-        publisher -> Varchar,
-        developer -> Varchar,
         links -> Jsonb,
         system_id -> Int4,
         system_unique_id -> Int4,
+        dat_import_id -> Nullable<Int4>,
+        min_players -> Nullable<Int4>,
+        max_players -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    genres (id) {
+        id -> Int4,
+        #[max_length = 255]
+        slug -> Varchar,
+        #[max_length = 255]
+        name -> Varchar,
+    }
+}
+
+diesel::table! {
+    notification_preferences (user_id, kind) {
+        user_id -> Int4,
+        #[max_length = 64]
+        kind -> Varchar,
+        in_app -> Bool,
+        email -> Bool,
+    }
+}
+
+diesel::table! {
+    notifications (id) {
+        id -> Int4,
+        user_id -> Int4,
+        #[max_length = 64]
+        kind -> Varchar,
+        data -> Jsonb,
+        created_at -> Timestamp,
+        read_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    password_reset_tokens (user_id) {
+        user_id -> Int4,
+        token_hash -> Bytea,
+        created_at -> Timestamp,
+        expires_at -> Timestamp,
     }
 }
 
@@ -141,6 +406,26 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    regions (id) {
+        id -> Int4,
+        #[max_length = 255]
+        slug -> Varchar,
+        #[max_length = 255]
+        name -> Varchar,
+    }
+}
+
+diesel::table! {
+    series (id) {
+        id -> Int4,
+        #[max_length = 255]
+        slug -> Varchar,
+        #[max_length = 255]
+        name -> Varchar,
+    }
+}
+
 diesel::table! {
     system_release_artifacts (artifact_id, system_release_id) {
         system_release_id -> Int4,
@@ -163,6 +448,18 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    storage_deletions (id) {
+        id -> Int4,
+        #[max_length = 32]
+        bucket -> Varchar,
+        path -> Varchar,
+        attempts -> Int4,
+        last_error -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     system_tags (tag_id, system_id) {
         system_id -> Int4,
@@ -194,6 +491,31 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::UserTeamRole;
+
+    team_permissions (team_id, role, permission) {
+        team_id -> Int4,
+        role -> UserTeamRole,
+        #[max_length = 64]
+        permission -> Varchar,
+        granted -> Bool,
+    }
+}
+
+diesel::table! {
+    team_signing_keys (id) {
+        id -> Int4,
+        team_id -> Int4,
+        #[max_length = 255]
+        name -> Varchar,
+        public_key -> Bytea,
+        created_at -> Timestamp,
+        revoked_at -> Nullable<Timestamp>,
+    }
+}
+
 diesel::table! {
     teams (id) {
         id -> Int4,
@@ -203,6 +525,22 @@ diesel::table! {
         description -> Text,
         links -> Jsonb,
         metadata -> Jsonb,
+        upload_size_limit -> Nullable<Int8>,
+    }
+}
+
+diesel::table! {
+    user_identities (id) {
+        id -> Int4,
+        user_id -> Int4,
+        #[max_length = 64]
+        provider -> Varchar,
+        #[max_length = 255]
+        subject -> Varchar,
+        #[max_length = 255]
+        email -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        last_login_at -> Nullable<Timestamp>,
     }
 }
 
@@ -218,6 +556,31 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    user_recovery_codes (id) {
+        id -> Int4,
+        user_id -> Int4,
+        code_hash -> Bytea,
+        used_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    user_sessions (id) {
+        id -> Int4,
+        user_id -> Int4,
+        key_hash -> Bytea,
+        #[max_length = 255]
+        device -> Nullable<Varchar>,
+        user_agent -> Nullable<Text>,
+        #[max_length = 64]
+        ip_address -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        last_seen_at -> Timestamp,
+        expires_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::UserTeamRole;
@@ -230,6 +593,32 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    user_tokens (id) {
+        id -> Int4,
+        user_id -> Int4,
+        #[max_length = 255]
+        name -> Varchar,
+        token_hash -> Bytea,
+        scopes -> Jsonb,
+        created_at -> Timestamp,
+        expires_at -> Timestamp,
+        last_used_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    user_two_factor (user_id) {
+        user_id -> Int4,
+        secret -> Bytea,
+        created_at -> Timestamp,
+        confirmed_at -> Nullable<Timestamp>,
+        last_used_step -> Nullable<Int8>,
+        failed_attempts -> Int4,
+        last_failed_at -> Nullable<Timestamp>,
+    }
+}
+
 diesel::table! {
     users (id) {
         id -> Int4,
@@ -247,25 +636,57 @@ diesel::table! {
         description -> Text,
         links -> Jsonb,
         metadata -> Jsonb,
+        sessions_invalidated_at -> Nullable<Timestamp>,
     }
 }
 
+diesel::joinable!(artifacts -> blobs (blob_id));
+diesel::joinable!(core_collaborators -> cores (core_id));
+diesel::joinable!(core_followers -> cores (core_id));
+diesel::joinable!(core_followers -> users (user_id));
 diesel::joinable!(core_release_artifacts -> artifacts (artifact_id));
 diesel::joinable!(core_release_artifacts -> core_releases (core_release_id));
+diesel::joinable!(core_release_signatures -> core_releases (core_release_id));
+diesel::joinable!(core_release_signatures -> team_signing_keys (team_signing_key_id));
+diesel::joinable!(core_release_uploads -> core_releases (core_release_id));
+diesel::joinable!(core_release_uploads -> users (user_id));
 diesel::joinable!(core_releases -> cores (core_id));
 diesel::joinable!(core_releases -> platforms (platform_id));
 diesel::joinable!(core_releases -> users (uploader_id));
+diesel::joinable!(core_required_file_hashes -> core_required_files (required_file_id));
+diesel::joinable!(core_required_files -> core_releases (core_release_id));
+diesel::joinable!(core_required_files -> cores (core_id));
 diesel::joinable!(core_tags -> cores (core_id));
 diesel::joinable!(core_tags -> tags (tag_id));
 diesel::joinable!(cores -> systems (system_id));
 diesel::joinable!(cores -> teams (owner_team_id));
-diesel::joinable!(files -> artifacts (id));
+diesel::joinable!(dat_imports -> systems (system_id));
+diesel::joinable!(dat_imports -> users (user_id));
+diesel::joinable!(email_changes -> users (user_id));
+diesel::joinable!(files -> blobs (id));
 diesel::joinable!(game_artifacts -> artifacts (artifact_id));
 diesel::joinable!(game_artifacts -> games (game_id));
+diesel::joinable!(game_companies -> companies (company_id));
+diesel::joinable!(game_companies -> games (game_id));
+diesel::joinable!(game_genres -> games (game_id));
+diesel::joinable!(game_genres -> genres (genre_id));
+diesel::joinable!(game_image_derivatives -> game_images (game_image_id));
 diesel::joinable!(game_image_tags -> game_images (game_image_id));
 diesel::joinable!(game_image_tags -> tags (tag_id));
 diesel::joinable!(game_images -> games (game_id));
+diesel::joinable!(game_languages -> games (game_id));
+diesel::joinable!(game_releases -> games (game_id));
+diesel::joinable!(game_releases -> regions (region_id));
+diesel::joinable!(game_series -> games (game_id));
+diesel::joinable!(game_series -> series (series_id));
+diesel::joinable!(game_tags -> games (game_id));
+diesel::joinable!(game_tags -> tags (tag_id));
+diesel::joinable!(game_translations -> games (game_id));
+diesel::joinable!(games -> dat_imports (dat_import_id));
 diesel::joinable!(games -> systems (system_id));
+diesel::joinable!(notification_preferences -> users (user_id));
+diesel::joinable!(notifications -> users (user_id));
+diesel::joinable!(password_reset_tokens -> users (user_id));
 diesel::joinable!(platform_tags -> platforms (platform_id));
 diesel::joinable!(platform_tags -> tags (tag_id));
 diesel::joinable!(platforms -> teams (owner_team_id));
@@ -274,29 +695,69 @@ diesel::joinable!(system_release_artifacts -> system_releases (system_release_id
 diesel::joinable!(system_releases -> systems (system_id));
 diesel::joinable!(system_releases -> users (uploader_id));
 diesel::joinable!(systems -> teams (owner_team_id));
+diesel::joinable!(team_permissions -> teams (team_id));
+diesel::joinable!(team_signing_keys -> teams (team_id));
+diesel::joinable!(user_identities -> users (user_id));
 diesel::joinable!(user_passwords -> users (user_id));
+diesel::joinable!(user_recovery_codes -> users (user_id));
+diesel::joinable!(user_sessions -> users (user_id));
 diesel::joinable!(user_teams -> teams (team_id));
+diesel::joinable!(user_tokens -> users (user_id));
+diesel::joinable!(user_two_factor -> users (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     artifacts,
+    blobs,
+    companies,
+    core_collaborators,
+    core_followers,
     core_release_artifacts,
+    core_release_signatures,
+    core_release_uploads,
     core_releases,
+    core_required_file_hashes,
+    core_required_files,
     core_tags,
     cores,
+    dat_imports,
+    email_changes,
     files,
     game_artifacts,
+    game_companies,
+    game_genres,
+    game_image_derivatives,
     game_image_tags,
     game_images,
+    game_languages,
+    game_relationships,
+    game_releases,
+    game_series,
+    game_tags,
+    game_translations,
     games,
+    genres,
+    notification_preferences,
+    notifications,
+    password_reset_tokens,
     platform_tags,
     platforms,
+    regions,
+    series,
+    storage_deletions,
     system_release_artifacts,
     system_releases,
     system_tags,
     systems,
     tags,
+    team_permissions,
+    team_signing_keys,
     teams,
+    user_identities,
     user_passwords,
+    user_recovery_codes,
+    user_sessions,
     user_teams,
+    user_tokens,
+    user_two_factor,
     users,
 );
@@ -1,6 +1,6 @@
 use crate::encodings::{Base64String, HexString};
 use serde::{Deserialize, Serialize};
-use std::num::NonZeroU32;
+use std::num::NonZeroU64;
 use url::Url;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -11,7 +11,10 @@ pub struct ArtifactRef {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub download_url: Option<String>,
 
-    pub size: Option<NonZeroU32>,
+    pub size: Option<NonZeroU64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crc32: Option<HexString>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub md5: Option<HexString>,
@@ -31,8 +34,8 @@ pub struct ArtifactChecksum {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub download_url: Option<Url>,
 
-    /// Size of the file in bytes. Files cannot be larger than 20MB.
-    pub size: i32,
+    /// Size of the file in bytes.
+    pub size: i64,
 
     /// MD5 checksum of the file, in hexadecimal.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -72,6 +75,50 @@ pub struct ArtifactCreateResponse {
     pub url: Option<String>,
 }
 
+/// Arguments to start uploading a file in multiple parts.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct ArtifactUploadCreateRequest<'a> {
+    /// The name of the file.
+    pub filename: &'a str,
+
+    /// Its content type.
+    pub mime_type: &'a str,
+
+    /// The total size of the file in bytes.
+    pub size: i64,
+}
+
+/// The status of an upload in progress. Parts must be uploaded in order,
+/// starting at `next_part`. Every part except the last one must be at least
+/// `min_part_size` bytes.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct ArtifactUpload {
+    /// The ID of the upload.
+    pub id: i32,
+    pub filename: String,
+    pub mime_type: String,
+
+    /// The total size of the file in bytes.
+    pub size: i64,
+
+    /// The number of bytes received so far.
+    pub received: i64,
+
+    /// The number of the next part to upload.
+    pub next_part: u32,
+
+    /// The minimum size of a part, except the last one.
+    pub min_part_size: i64,
+
+    /// The maximum size of a part.
+    pub max_part_size: i64,
+
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct CoreReleaseArtifactListItem {
@@ -28,3 +28,147 @@ pub struct LoginRequest<'a> {
     pub email: &'a str,
     pub password: &'a str,
 }
+
+/// The result of a login with an email and password.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct LoginResponse {
+    /// The user has two-factor authentication enabled. The login must be
+    /// completed with a code (see `/login/two-factor`) before the session
+    /// is usable.
+    #[serde(default)]
+    pub two_factor_required: bool,
+}
+
+/// Ask for a password reset link to be sent to an email.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct PasswordForgotRequest<'a> {
+    pub email: &'a str,
+}
+
+/// Reset a password using the token sent by email.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct PasswordResetRequest<'a> {
+    pub email: &'a str,
+    pub token: &'a str,
+    pub password: &'a str,
+}
+
+/// Change the password of the current user.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct PasswordChangeRequest<'a> {
+    /// The current password. Required unless the user has no password yet
+    /// (e.g. they only logged in through an OAuth provider).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_password: Option<&'a str>,
+    pub new_password: &'a str,
+}
+
+/// Change the email of the current user.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct EmailChangeRequest<'a> {
+    pub email: &'a str,
+
+    /// The current password, required if the user has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<&'a str>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct EmailChangeResponse {
+    pub email: String,
+
+    /// Whether the change must still be confirmed with the link sent to the
+    /// new email.
+    pub pending: bool,
+}
+
+/// Confirm an email change using the token sent to the new email.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct EmailChangeConfirmRequest<'a> {
+    pub token: &'a str,
+}
+
+/// A two-factor authentication code; either a TOTP code or a recovery code.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TwoFactorCodeRequest<'a> {
+    pub code: &'a str,
+}
+
+/// Whether two-factor authentication is enabled for the current user.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    /// The number of recovery codes that were not used yet.
+    pub recovery_codes_left: i64,
+}
+
+/// A new TOTP secret to add to an authenticator app. Two-factor
+/// authentication is only enabled once a code is confirmed.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TwoFactorEnrollResponse {
+    /// The secret, encoded in base32.
+    pub secret: String,
+    /// The `otpauth://` URI of the secret, usually shown as a QR code.
+    pub otpauth_uri: String,
+}
+
+/// One-time recovery codes, usable instead of a TOTP code. They are only
+/// shown once.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TwoFactorRecoveryCodes {
+    pub codes: Vec<String>,
+}
+
+/// An OpenID Connect provider users can log in with.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct OidcProvider {
+    /// The name of the provider, used in its login URL.
+    pub name: String,
+    /// The name to show to users.
+    pub display_name: String,
+}
+
+/// An account with an OpenID Connect provider linked to a user.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct UserIdentity {
+    pub id: i32,
+    pub provider: String,
+    /// The email of the account, as known by the provider.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    pub created_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_login_at: Option<i64>,
+}
+
+/// A session of a user logged in from a browser.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct UserSession {
+    pub id: i32,
+    /// A short description of the browser and operating system.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ip_address: Option<String>,
+    pub created_at: i64,
+    pub last_seen_at: i64,
+    pub expires_at: i64,
+    /// Whether this is the session making the request.
+    pub current: bool,
+}
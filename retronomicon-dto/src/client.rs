@@ -57,10 +57,104 @@ macro_rules! declare_client_impl {
                 ("users/{id}", id: &crate::user::UserIdOrUsername<'_>),
                 @body body: &crate::user::UserUpdate<'_>,
             ) -> crate::Ok;
+            delete users_sessions_terminate(
+                ("users/{id}/sessions", id: &crate::user::UserIdOrUsername<'_>),
+            ) -> crate::Ok;
             put me_update(
                 ("me"),
                 @body body: &crate::user::UserUpdate<'_>,
             ) -> crate::Ok;
+            delete me_delete(
+                ("me"),
+                @body body: &crate::user::AccountDeleteRequest<'_>,
+            ) -> crate::Ok;
+            get me_export(
+                ("me/export"),
+            ) -> crate::user::UserExport;
+            post me_email(
+                ("me/email"),
+                @body body: &crate::auth::EmailChangeRequest<'_>,
+            ) -> crate::auth::EmailChangeResponse;
+            post email_change_confirm(
+                ("email/confirm"),
+                @body body: &crate::auth::EmailChangeConfirmRequest<'_>,
+            ) -> crate::Ok;
+            put me_password(
+                ("me/password"),
+                @body body: &crate::auth::PasswordChangeRequest<'_>,
+            ) -> crate::Ok;
+            post password_forgot(
+                ("password/forgot"),
+                @body body: &crate::auth::PasswordForgotRequest<'_>,
+            ) -> crate::Ok;
+            post password_reset(
+                ("password/reset"),
+                @body body: &crate::auth::PasswordResetRequest<'_>,
+            ) -> crate::Ok;
+            get me_two_factor(
+                ("me/two-factor"),
+            ) -> crate::auth::TwoFactorStatus;
+            post me_two_factor_enroll(
+                ("me/two-factor"),
+            ) -> crate::auth::TwoFactorEnrollResponse;
+            post me_two_factor_confirm(
+                ("me/two-factor/confirm"),
+                @body body: &crate::auth::TwoFactorCodeRequest<'_>,
+            ) -> crate::auth::TwoFactorRecoveryCodes;
+            delete me_two_factor_disable(
+                ("me/two-factor"),
+                @body body: &crate::auth::TwoFactorCodeRequest<'_>,
+            ) -> crate::Ok;
+            post me_two_factor_recovery_codes(
+                ("me/two-factor/recovery-codes"),
+                @body body: &crate::auth::TwoFactorCodeRequest<'_>,
+            ) -> crate::auth::TwoFactorRecoveryCodes;
+            get oidc_providers(
+                ("login/oidc"),
+            ) -> Vec<crate::auth::OidcProvider>;
+            get me_identities(
+                ("me/identities"),
+            ) -> Vec<crate::auth::UserIdentity>;
+            delete me_identities_unlink(
+                ("me/identities/{id}", id: i32),
+            ) -> crate::Ok;
+            get me_sessions(
+                ("me/sessions"),
+            ) -> Vec<crate::auth::UserSession>;
+            delete me_sessions_revoke_all(
+                ("me/sessions"),
+            ) -> crate::Ok;
+            delete me_sessions_revoke(
+                ("me/sessions/{id}", id: i32),
+            ) -> crate::Ok;
+            get me_tokens(
+                ("me/tokens"),
+            ) -> Vec<crate::tokens::UserToken>;
+            post me_tokens_create(
+                ("me/tokens"),
+                @body body: &crate::tokens::UserTokenCreateRequest<'_>,
+            ) -> crate::tokens::UserTokenCreateResponse;
+            delete me_tokens_revoke(
+                ("me/tokens/{id}", id: i32),
+            ) -> crate::Ok;
+            get me_invitations(
+                ("me/invitations"),
+            ) -> Vec<crate::user::UserInvitation>;
+            get me_notifications(
+                ("me/notifications"),
+                @query filter: &crate::notifications::NotificationFilterParams,
+            ) -> Vec<crate::notifications::Notification>;
+            post me_notifications_read(
+                ("me/notifications/read"),
+                @body body: &crate::notifications::NotificationReadRequest,
+            ) -> crate::Ok;
+            get me_notification_preferences(
+                ("me/notification-preferences"),
+            ) -> Vec<crate::notifications::NotificationPreference>;
+            put me_notification_preferences_update(
+                ("me/notification-preferences"),
+                @body body: &[crate::notifications::NotificationPreference],
+            ) -> Vec<crate::notifications::NotificationPreference>;
 
             get cores(
                 ("cores"),
@@ -73,6 +167,31 @@ macro_rules! declare_client_impl {
                 ("cores"),
                 @body body: &crate::cores::CoreCreateRequest<'_>,
             ) -> crate::cores::CoreCreateResponse;
+            post cores_follow(
+                ("cores/{id}/follow", id: &crate::types::IdOrSlug<'_>),
+            ) -> crate::Ok;
+            delete cores_unfollow(
+                ("cores/{id}/follow", id: &crate::types::IdOrSlug<'_>),
+            ) -> crate::Ok;
+            get cores_required_files(
+                ("cores/{id}/required-files", id: &crate::types::IdOrSlug<'_>),
+                @query params: &crate::cores::required_files::CoreRequiredFileListQueryParams,
+            ) -> Vec<crate::cores::required_files::CoreRequiredFile>;
+            post cores_required_files_create(
+                ("cores/{id}/required-files", id: &crate::types::IdOrSlug<'_>),
+                @body body: &crate::cores::required_files::CoreRequiredFileCreateRequest<'_>,
+            ) -> crate::cores::required_files::CoreRequiredFileCreateResponse;
+            delete cores_required_files_delete(
+                (
+                    "cores/{core_id}/required-files/{file_id}",
+                    core_id: &crate::types::IdOrSlug<'_>,
+                    file_id: i32,
+                ),
+            ) -> crate::Ok;
+            post cores_compatibility(
+                ("cores/compatibility"),
+                @body body: &crate::cores::required_files::CoreCompatibilityRequest<'_>,
+            ) -> Vec<crate::cores::required_files::CoreCompatibility>;
 
             get cores_releases(
                 ("cores/{id}/releases", id: &crate::types::IdOrSlug<'_>),
@@ -90,23 +209,219 @@ macro_rules! declare_client_impl {
                 ("cores/{id}/releases", id: &crate::types::IdOrSlug<'_>),
                 @body body: &crate::cores::releases::CoreReleaseCreateRequest<'_>,
             ) -> crate::cores::releases::CoreReleaseCreateResponse;
+            put cores_releases_yank(
+                (
+                    "cores/{core_id}/releases/{release_id}/yank",
+                    core_id: &crate::types::IdOrSlug<'_>,
+                    release_id: i32,
+                ),
+                @body body: &crate::cores::releases::CoreReleaseYankRequest,
+            ) -> crate::Ok;
             post cores_releases_artifacts_upload(
                 (
                     "cores/{core_id}/releases/{release_id}/artifacts",
                     core_id: &crate::types::IdOrSlug<'_>,
                     release_id: i32,
                 ),
+                @headers headers,
                 @file file,
             ) -> Vec<crate::artifact::ArtifactCreateResponse>;
+            post cores_releases_uploads_create(
+                (
+                    "cores/{core_id}/releases/{release_id}/uploads",
+                    core_id: &crate::types::IdOrSlug<'_>,
+                    release_id: i32,
+                ),
+                @body body: &crate::artifact::ArtifactUploadCreateRequest<'_>,
+            ) -> crate::artifact::ArtifactUpload;
+            get cores_releases_uploads_details(
+                (
+                    "cores/{core_id}/releases/{release_id}/uploads/{upload_id}",
+                    core_id: &crate::types::IdOrSlug<'_>,
+                    release_id: i32,
+                    upload_id: i32,
+                ),
+            ) -> crate::artifact::ArtifactUpload;
+            put cores_releases_uploads_part(
+                (
+                    "cores/{core_id}/releases/{release_id}/uploads/{upload_id}/parts/{part}",
+                    core_id: &crate::types::IdOrSlug<'_>,
+                    release_id: i32,
+                    upload_id: i32,
+                    part: u32,
+                ),
+                @bytes data,
+            ) -> crate::artifact::ArtifactUpload;
+            post cores_releases_uploads_complete(
+                (
+                    "cores/{core_id}/releases/{release_id}/uploads/{upload_id}/complete",
+                    core_id: &crate::types::IdOrSlug<'_>,
+                    release_id: i32,
+                    upload_id: i32,
+                ),
+                @headers headers,
+            ) -> crate::artifact::ArtifactCreateResponse;
+            delete cores_releases_uploads_delete(
+                (
+                    "cores/{core_id}/releases/{release_id}/uploads/{upload_id}",
+                    core_id: &crate::types::IdOrSlug<'_>,
+                    release_id: i32,
+                    upload_id: i32,
+                ),
+            ) -> crate::Ok;
+            get cores_releases_manifest(
+                (
+                    "cores/{core_id}/releases/{release_id}/manifest",
+                    core_id: &crate::types::IdOrSlug<'_>,
+                    release_id: i32,
+                ),
+            ) -> crate::cores::manifests::ReleaseManifest;
+            get cores_releases_manifest_signatures(
+                (
+                    "cores/{core_id}/releases/{release_id}/manifest/signatures",
+                    core_id: &crate::types::IdOrSlug<'_>,
+                    release_id: i32,
+                ),
+            ) -> Vec<crate::cores::manifests::ReleaseManifestSignature>;
+            post cores_releases_manifest_sign(
+                (
+                    "cores/{core_id}/releases/{release_id}/manifest/signatures",
+                    core_id: &crate::types::IdOrSlug<'_>,
+                    release_id: i32,
+                ),
+                @body body: &crate::cores::manifests::ReleaseManifestSignRequest,
+            ) -> crate::Ok;
+            get manifests_key(
+                ("manifests/key"),
+            ) -> crate::cores::manifests::ManifestServerKey;
+
+            post invite(
+                ("teams/{id}/invitation", id: &crate::types::IdOrSlug<'_>),
+                @body body: &crate::teams::TeamInvite<'_>,
+            ) -> crate::Ok;
+            post invite_accept(
+                ("teams/{id}/invitation/accept", id: &crate::types::IdOrSlug<'_>),
+            ) -> crate::Ok;
+            delete invite_decline(
+                ("teams/{id}/invitation", id: &crate::types::IdOrSlug<'_>),
+            ) -> crate::Ok;
+            get teams_invitations(
+                ("teams/{id}/invitations", id: &crate::types::IdOrSlug<'_>),
+            ) -> Vec<crate::teams::TeamInvitation>;
+            delete invite_cancel(
+                (
+                    "teams/{id}/invitations/{user_id}",
+                    id: &crate::types::IdOrSlug<'_>,
+                    user_id: &crate::user::UserIdOrUsername<'_>,
+                ),
+            ) -> crate::Ok;
+            put teams_members_update(
+                (
+                    "teams/{id}/members/{user_id}",
+                    id: &crate::types::IdOrSlug<'_>,
+                    user_id: &crate::user::UserIdOrUsername<'_>,
+                ),
+                @body body: &crate::teams::TeamMemberUpdate,
+            ) -> crate::Ok;
+            delete teams_members_remove(
+                (
+                    "teams/{id}/members/{user_id}",
+                    id: &crate::types::IdOrSlug<'_>,
+                    user_id: &crate::user::UserIdOrUsername<'_>,
+                ),
+            ) -> crate::Ok;
+            post teams_leave(
+                ("teams/{id}/leave", id: &crate::types::IdOrSlug<'_>),
+            ) -> crate::Ok;
+            get teams_permissions(
+                ("teams/{id}/permissions", id: &crate::types::IdOrSlug<'_>),
+            ) -> Vec<crate::permissions::TeamRolePermissions>;
+            put teams_permissions_update(
+                ("teams/{id}/permissions", id: &crate::types::IdOrSlug<'_>),
+                @body body: &[crate::permissions::TeamPermissionUpdate],
+            ) -> Vec<crate::permissions::TeamRolePermissions>;
+            get cores_collaborators(
+                ("cores/{id}/collaborators", id: &crate::types::IdOrSlug<'_>),
+            ) -> Vec<crate::permissions::CoreCollaborator>;
+            put cores_collaborators_update(
+                (
+                    "cores/{id}/collaborators/{user_id}",
+                    id: &crate::types::IdOrSlug<'_>,
+                    user_id: &crate::user::UserIdOrUsername<'_>,
+                ),
+                @body body: &crate::permissions::CoreCollaboratorUpdate,
+            ) -> crate::Ok;
+            delete cores_collaborators_remove(
+                (
+                    "cores/{id}/collaborators/{user_id}",
+                    id: &crate::types::IdOrSlug<'_>,
+                    user_id: &crate::user::UserIdOrUsername<'_>,
+                ),
+            ) -> crate::Ok;
+            get permissions_check(
+                ("permissions/check"),
+                @query query: &crate::permissions::PermissionCheckQuery<'_>,
+            ) -> crate::permissions::PermissionCheck;
+            get teams_keys(
+                ("teams/{id}/keys", id: &crate::types::IdOrSlug<'_>),
+            ) -> Vec<crate::teams::TeamSigningKey>;
+            post teams_keys_create(
+                ("teams/{id}/keys", id: &crate::types::IdOrSlug<'_>),
+                @body body: &crate::teams::TeamSigningKeyCreateRequest<'_>,
+            ) -> crate::teams::TeamSigningKey;
+            delete teams_keys_revoke(
+                ("teams/{id}/keys/{key_id}", id: &crate::types::IdOrSlug<'_>, key_id: i32),
+            ) -> crate::Ok;
 
             get games(
                 ("games"),
                 @query paging: &crate::games::GameListQueryParams<'_>,
                 @body filter: &crate::games::GameListBody,
             ) -> Vec<crate::games::GameListItemResponse>;
+            get companies(
+                ("companies"),
+                @query paging: &crate::params::PagingParams,
+            ) -> Vec<crate::games::CompanyRef>;
+            get genres(
+                ("genres"),
+            ) -> Vec<crate::games::GenreRef>;
+            get regions(
+                ("regions"),
+            ) -> Vec<crate::games::RegionRef>;
+            get series(
+                ("series"),
+                @query paging: &crate::params::PagingParams,
+            ) -> Vec<crate::games::SeriesRef>;
             get games_details(
                 ("games/{id}", id: i32),
+                @query query: &crate::games::GameDetailsQueryParams,
             ) -> crate::games::GameDetails;
+            get games_translations(
+                ("games/{id}/translations", id: i32),
+            ) -> Vec<crate::games::GameTranslation>;
+            put games_translations_update(
+                ("games/{id}/translations/{language}", id: i32, language: &str),
+                @body body: &crate::games::GameTranslationRequest<'_>,
+            ) -> crate::games::GameTranslation;
+            delete games_translations_delete(
+                ("games/{id}/translations/{language}", id: i32, language: &str),
+            ) -> crate::Ok;
+            get games_related(
+                ("games/{id}/related", id: i32),
+                @query query: &crate::games::GameRelatedQueryParams,
+            ) -> Vec<crate::games::GameRelatedItem>;
+            post games_relationships_create(
+                ("games/{id}/relationships", id: i32),
+                @body body: &crate::games::GameRelationshipCreateRequest,
+            ) -> crate::Ok;
+            delete games_relationships_delete(
+                (
+                    "games/{id}/relationships/{kind}/{related}",
+                    id: i32,
+                    kind: crate::games::GameRelationshipKind,
+                    related: i32,
+                ),
+            ) -> crate::Ok;
             post games_create(
                 ("games"),
                 @body body: &crate::games::GameCreateRequest<'_>,
@@ -115,18 +430,53 @@ macro_rules! declare_client_impl {
                 ("games/{id}", id: i32),
                 @body body: &crate::games::GameUpdateRequest<'_>,
             ) -> crate::Ok;
+            post games_identify(
+                ("games/identify"),
+                @body body: &crate::games::GameIdentifyRequest<'_>,
+            ) -> Vec<crate::games::GameIdentifyResult>;
+            get games_artifacts(
+                ("games/{id}/artifacts", id: i32),
+            ) -> Vec<crate::games::GameArtifact>;
             post games_add_artifact(
                 ("games/{id}/artifacts", id: i32),
                 @body body: &Vec<crate::games::GameAddArtifactRequest<'_>>,
             ) -> crate::Ok;
             get games_images(
                 ("games/{id}/images", id: i32),
-                @query paging: &crate::params::PagingParams,
+                @query filter: &crate::games::GameImageListQueryParams,
             ) -> Vec<crate::images::Image>;
             post games_add_image(
                 ("games/{id}/images", id: i32),
+                @query params: &crate::games::GameImageUploadQueryParams,
                 @file file,
             ) -> Vec<crate::images::Image>;
+            put games_images_roles_update(
+                ("games/{id}/images/{image_id}/roles", id: i32, image_id: i32),
+                @body body: &crate::images::ImageRolesUpdateRequest,
+            ) -> crate::images::Image;
+            post systems_imports_create(
+                ("systems/{id}/imports", id: &crate::types::IdOrSlug<'_>),
+                @query query: &crate::games::DatImportQueryParams,
+                @bytes dat,
+            ) -> crate::games::DatImportDetails;
+            get systems_imports_details(
+                (
+                    "systems/{id}/imports/{import_id}",
+                    id: &crate::types::IdOrSlug<'_>,
+                    import_id: i32,
+                ),
+            ) -> crate::games::DatImportDetails;
+            post systems_imports_apply(
+                (
+                    "systems/{id}/imports/{import_id}/apply",
+                    id: &crate::types::IdOrSlug<'_>,
+                    import_id: i32,
+                ),
+            ) -> crate::games::DatImportDetails;
+            get search(
+                ("search"),
+                @query params: &crate::search::SearchQueryParams,
+            ) -> crate::search::SearchResponse;
         }
     };
 }
@@ -145,7 +495,9 @@ macro_rules! declare_client {
                 ),
                 $(@query $query_name: ident: $query_type: ty, )*
                 $(@body $body_name: ident: $body_type: ty, )*
+                $(@headers $headers_name: ident, )*
                 $(@file $file_name: ident $(,)? )*
+                $(@bytes $bytes_name: ident $(,)? )*
             ) -> $rtype: ty;
         )*
     ) => {
@@ -170,7 +522,9 @@ macro_rules! declare_client {
                 ),
                 $(@query $query_name: ident: $query_type: ty, )*
                 $(@body $body_name: ident: $body_type: ty, )*
+                $(@headers $headers_name: ident, )*
                 $(@file $file_name: ident $(,)? )*
+                $(@bytes $bytes_name: ident $(,)? )*
             ) -> $rtype: ty;
         )*
     ) => {
@@ -181,12 +535,16 @@ macro_rules! declare_client {
                 $( $path_name: $path_type, )*
                 $( $query_name: $query_type, )*
                 $( $body_name: $body_type, )*
+                $( $headers_name: reqwest::header::HeaderMap, )*
                 $( $file_name: &std::path::Path, )*
+                $( $bytes_name: Vec<u8>, )*
             ) -> Result<$rtype, super::Error> {
                 let request = self.1
                     . $method (crate::routes::v1:: $fname ( &self.0, $( $path_name, )* ))
                     $(.query( $query_name ))*
                     $(.json( $body_name ))*
+                    $(.headers( $headers_name ))*
+                    $(.body( $bytes_name ))*
                 ;
 
                 $(
@@ -242,7 +600,9 @@ macro_rules! declare_client {
                 ),
                 $(@query $query_name: ident: $query_type: ty, )*
                 $(@body $body_name: ident: $body_type: ty, )*
+                $(@headers $headers_name: ident, )*
                 $(@file $file_name: ident $(,)? )*
+                $(@bytes $bytes_name: ident $(,)? )*
             ) -> $rtype: ty;
         )*
     ) => {
@@ -253,12 +613,16 @@ macro_rules! declare_client {
                 $( $path_name: $path_type, )*
                 $( $query_name: $query_type, )*
                 $( $body_name: $body_type, )*
+                $( $headers_name: reqwest::header::HeaderMap, )*
                 $( $file_name: &std::path::Path, )*
+                $( $bytes_name: Vec<u8>, )*
             ) -> Result<$rtype, super::Error> {
                 let request = self.1
                     . $method (crate::routes::v1:: $fname ( &self.0, $( $path_name, )* ))
                     $(.query( $query_name ))*
                     $(.json( $body_name ))*
+                    $(.headers( $headers_name ))*
+                    $(.body( $bytes_name ))*
                 ;
 
                 $(
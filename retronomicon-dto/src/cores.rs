@@ -7,7 +7,10 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::BTreeMap;
 
+pub mod manifests;
 pub mod releases;
+#[allow(clippy::needless_borrows_for_generic_args)]
+pub mod required_files;
 
 /// Parameters for filtering the list of cores.
 #[derive(Debug, Serialize, Deserialize)]
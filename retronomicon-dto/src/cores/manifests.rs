@@ -0,0 +1,114 @@
+use crate::encodings::{Base64String, HexString};
+use crate::teams::TeamRef;
+use serde::{Deserialize, Serialize};
+
+/// The manifest of a core release, listing all its artifacts.
+///
+/// Signatures are made over the canonical JSON of the manifest, as returned
+/// by [`ReleaseManifest::to_canonical_json`]: compact, with keys sorted. The
+/// fields here are declared in alphabetical order so that serializing them
+/// directly produces sorted keys.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct ReleaseManifest {
+    /// The artifacts of the release, sorted by filename.
+    pub artifacts: Vec<ReleaseManifestArtifact>,
+
+    /// The slug of the core.
+    pub core: String,
+    pub core_id: i32,
+
+    /// Date the release was uploaded to the server, in seconds since UNIX EPOCH.
+    pub date_released: i64,
+
+    /// The slug of the platform this release was made for.
+    pub platform: String,
+
+    pub release_id: i32,
+    pub version: String,
+}
+
+impl ReleaseManifest {
+    /// The bytes that are signed.
+    pub fn to_canonical_json(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("Manifest serialization cannot fail")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct ReleaseManifestArtifact {
+    pub filename: String,
+    pub md5: HexString,
+    pub sha1: HexString,
+    pub sha256: HexString,
+    pub size: i64,
+}
+
+/// A detached ed25519 signature of the canonical JSON of a manifest.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct ReleaseManifestSignature {
+    /// The team key that made this signature. Signatures made by the server
+    /// do not have one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<ReleaseManifestSigningKeyRef>,
+
+    /// The ed25519 public key to verify the signature with.
+    pub public_key: Base64String,
+    pub signature: Base64String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct ReleaseManifestSigningKeyRef {
+    pub id: i32,
+    pub name: String,
+    pub team: TeamRef,
+}
+
+/// Arguments to add a team signature to a release manifest.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct ReleaseManifestSignRequest {
+    /// The public key of a key registered by the team that owns the core.
+    pub public_key: Base64String,
+
+    /// The signature of the canonical JSON of the current manifest.
+    pub signature: Base64String,
+}
+
+/// The key the server signs all release manifests with.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct ManifestServerKey {
+    /// The ed25519 public key.
+    pub public_key: Base64String,
+}
+
+#[test]
+fn manifest_canonical_json() {
+    let manifest = ReleaseManifest {
+        artifacts: vec![ReleaseManifestArtifact {
+            filename: "core.rbf".to_string(),
+            md5: (&[1u8, 2]).into(),
+            sha1: (&[3u8]).into(),
+            sha256: (&[4u8]).into(),
+            size: 2,
+        }],
+        core: "core".to_string(),
+        core_id: 1,
+        date_released: 1700000000,
+        platform: "golem".to_string(),
+        release_id: 2,
+        version: "1.0.0".to_string(),
+    };
+
+    let json = manifest.to_canonical_json();
+    let sorted = serde_json::to_vec(&serde_json::to_value(&manifest).unwrap()).unwrap();
+    assert_eq!(json, sorted);
+    assert_eq!(
+        String::from_utf8(json).unwrap(),
+        r#"{"artifacts":[{"filename":"core.rbf","md5":"0102","sha1":"03","sha256":"04","size":2}],"core":"core","core_id":1,"date_released":1700000000,"platform":"golem","release_id":2,"version":"1.0.0"}"#
+    );
+}
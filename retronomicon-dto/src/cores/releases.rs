@@ -77,3 +77,10 @@ pub struct CoreReleaseCreateRequest<'v> {
 pub struct CoreReleaseCreateResponse {
     pub id: i32,
 }
+
+/// Yank a release, or restore a yanked release.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct CoreReleaseYankRequest {
+    pub yanked: bool,
+}
@@ -0,0 +1,195 @@
+use crate::cores::releases::CoreReleaseRef;
+use crate::cores::CoreRef;
+use crate::encodings::HexString;
+use crate::types::IdOrSlug;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumString};
+
+/// A size and checksums a required file can have. A file on a device is
+/// this file if it has the same size (when known) and the same checksums,
+/// where both are known.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct RequiredFileHash {
+    /// Size of the file in bytes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<i64>,
+
+    /// CRC32 checksum of the file, in hexadecimal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crc32: Option<HexString>,
+
+    /// MD5 checksum of the file, in hexadecimal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub md5: Option<HexString>,
+
+    /// SHA1 checksum of the file, in hexadecimal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha1: Option<HexString>,
+
+    /// SHA256 checksum of the file, in hexadecimal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<HexString>,
+}
+
+/// A file a core needs on the device to run, e.g. a BIOS.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct CoreRequiredFile {
+    pub id: i32,
+
+    /// The release this file is required by. Files without a release are
+    /// required by all the releases of the core.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_id: Option<i32>,
+
+    pub filename: String,
+
+    /// The directory of the file on the device, e.g. `games/PSX`.
+    pub path: String,
+
+    /// Whether the core can run without this file.
+    pub optional: bool,
+
+    pub description: String,
+
+    /// The versions of the file the core accepts. Any file at the right
+    /// path is accepted if empty.
+    pub hashes: Vec<RequiredFileHash>,
+}
+
+/// Parameters for listing the required files of a core.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "rocket", derive(rocket::form::FromForm))]
+#[cfg_attr(feature = "rocket", derive(rocket::UriDisplayQuery))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct CoreRequiredFileListQueryParams {
+    /// Only list the files required by this release, including the files
+    /// required by all releases. By default, list all the files.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct CoreRequiredFileCreateRequest<'v> {
+    /// Only require the file for this release of the core.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release: Option<i32>,
+
+    pub filename: &'v str,
+
+    /// The directory of the file on the device, relative to its root.
+    #[serde(default)]
+    pub path: &'v str,
+
+    #[serde(default)]
+    pub optional: bool,
+
+    #[serde(default)]
+    pub description: &'v str,
+
+    #[serde(default)]
+    pub hashes: Vec<RequiredFileHash>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct CoreRequiredFileCreateResponse {
+    pub id: i32,
+}
+
+/// A file on a device, with its path relative to the root of the device.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct DeviceFile {
+    /// The path of the file, e.g. `games/PSX/boot.rom`.
+    pub path: String,
+
+    /// Size of the file in bytes.
+    pub size: i64,
+
+    /// CRC32 checksum of the file, in hexadecimal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crc32: Option<HexString>,
+
+    /// MD5 checksum of the file, in hexadecimal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub md5: Option<HexString>,
+
+    /// SHA1 checksum of the file, in hexadecimal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha1: Option<HexString>,
+
+    /// SHA256 checksum of the file, in hexadecimal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<HexString>,
+}
+
+/// The files of a device, to check which cores can run on it.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct CoreCompatibilityRequest<'v> {
+    /// Only check the cores with releases for this platform. By default,
+    /// check all cores.
+    #[serde(borrow)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub platform: Option<IdOrSlug<'v>>,
+
+    /// Only check the cores of this system. By default, check all cores.
+    #[serde(borrow)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<IdOrSlug<'v>>,
+
+    /// The files of the device. Only files that could be required need to be
+    /// listed.
+    pub files: Vec<DeviceFile>,
+}
+
+/// Whether a required file is on a device.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize, EnumString, Display)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum RequiredFileStatus {
+    /// An accepted version of the file is at its path.
+    Present,
+
+    /// An accepted version of the file is on the device, but at another path.
+    Misplaced,
+
+    /// A file is at the path, but it is not an accepted version.
+    Mismatched,
+
+    /// The file is not on the device.
+    Missing,
+}
+
+/// A required file of a core, and whether it is on the device.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct RequiredFileCheck {
+    #[serde(flatten)]
+    pub file: CoreRequiredFile,
+
+    pub status: RequiredFileStatus,
+
+    /// Where an accepted version of a misplaced file was found.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub found_at: Option<String>,
+}
+
+/// Whether the latest release of a core (that is not yanked) can run on a
+/// device.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct CoreCompatibility {
+    pub core: CoreRef,
+    pub release: CoreReleaseRef,
+
+    /// Whether all the files the release needs are present.
+    pub playable: bool,
+
+    /// The files required by the release, optional or not.
+    pub files: Vec<RequiredFileCheck>,
+}
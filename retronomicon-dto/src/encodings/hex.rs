@@ -80,6 +80,12 @@ impl Deref for HexString {
     }
 }
 
+impl AsRef<[u8]> for HexString {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
 impl DerefMut for HexString {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.0
@@ -1,11 +1,26 @@
 use crate::artifact::ArtifactRef;
 use crate::encodings::HexString;
+use crate::images::ImageRole;
 use crate::params::{PagingParams, RangeParams};
 use crate::systems::SystemRef;
+use crate::tags::Tag;
 use crate::types::IdOrSlug;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::BTreeMap;
+use strum::{Display, EnumString};
+
+mod artifacts;
+pub use artifacts::*;
+
+mod metadata;
+pub use metadata::*;
+
+mod relationships;
+pub use relationships::*;
+
+mod translations;
+pub use translations::*;
 
 /// Parameters for filtering the list of games.
 #[derive(Debug, Serialize, Deserialize)]
@@ -25,8 +40,9 @@ pub struct GameListQueryParams<'v> {
     #[serde(flatten)]
     pub paging: PagingParams,
 
-    /// Filter by name, exact substring. If both name and exact_name are specified,
-    /// they will both try to match and may give no result.
+    /// Filter by name, exact substring, in any language. If both name and
+    /// exact_name are specified, they will both try to match and may give
+    /// no result.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
 
@@ -34,6 +50,43 @@ pub struct GameListQueryParams<'v> {
     /// they will both try to match and may give no result.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub exact_name: Option<String>,
+
+    /// Filter by the slug of a region the game was released in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+
+    /// Filter by a language the game supports, as a BCP 47 tag.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+
+    /// Filter by the slug of a genre.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub genre: Option<String>,
+
+    /// Filter by the slug of a series.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub series: Option<String>,
+
+    /// Filter by the slug of a publisher.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub publisher: Option<String>,
+
+    /// Filter by the slug of a developer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub developer: Option<String>,
+
+    /// Filter by the slug of a tag.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+
+    /// Only include games that can be played by this many players.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub players: Option<i32>,
+
+    /// The preferred language of the names, as a BCP 47 tag. Takes
+    /// precedence over the `Accept-Language` header.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
 }
 
 /// Parameters for filtering the list of game images.
@@ -45,6 +98,25 @@ pub struct GameImageListQueryParams {
     /// Paging parameters.
     #[serde(flatten)]
     pub paging: PagingParams,
+
+    /// Only list images with this role.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<ImageRole>,
+}
+
+/// Parameters for uploading game images.
+#[derive(Default, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "rocket", derive(rocket::form::FromForm))]
+#[cfg_attr(feature = "rocket", derive(rocket::UriDisplayQuery))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct GameImageUploadQueryParams {
+    /// The role of the uploaded images.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<ImageRole>,
+
+    /// Upload images even if they look like images the game already has.
+    #[serde(default)]
+    pub allow_duplicates: bool,
 }
 
 /// Parameters for filtering the list of games using checksums.
@@ -71,12 +143,17 @@ pub struct GameListItemResponse {
     /// The identifier for the game, this is unique for ALL games.
     pub id: i32,
 
-    /// The name of the game. Does not include any `[]` tags.
+    /// The name of the game, in the preferred language if translated.
+    /// Does not include any `[]` tags.
     pub name: String,
 
     /// A short description of the game.
     pub short_description: String,
 
+    /// The language of the name, if it is a translation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+
     /// The year the game was released.
     pub year: i32,
 
@@ -99,11 +176,53 @@ pub struct GameCreateRequest<'a> {
     pub description: &'a str,
     pub short_description: &'a str,
     pub year: i32,
-    pub publisher: &'a str,
-    pub developer: &'a str,
     pub links: BTreeMap<&'a str, &'a str>,
     pub system: IdOrSlug<'a>,
     pub system_unique_id: i32,
+
+    /// The names of the publishers. Unknown companies are created.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub publishers: Vec<&'a str>,
+
+    /// The names of the developers. Unknown companies are created.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub developers: Vec<&'a str>,
+
+    /// Deprecated, use `publishers`. The name of the publisher, which cannot
+    /// be given with `publishers`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publisher: Option<&'a str>,
+
+    /// Deprecated, use `developers`. The name of the developer, which cannot
+    /// be given with `developers`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub developer: Option<&'a str>,
+
+    /// The releases of the game, by region.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub releases: Vec<GameReleaseRequest<'a>>,
+
+    /// The languages the game supports, as BCP 47 tags.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub languages: Vec<&'a str>,
+
+    /// The slugs of the genres of the game.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub genres: Vec<&'a str>,
+
+    /// The names of the series the game is part of. Unknown series are
+    /// created.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub series: Vec<&'a str>,
+
+    /// The slugs of the tags of the game.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<&'a str>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_players: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_players: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -116,18 +235,52 @@ pub struct GameCreateResponse {
 #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct GameDetails {
     pub id: i32,
+
+    /// The name and descriptions are in the preferred language if
+    /// translated, in which case `language` is the language used.
     pub name: String,
     pub description: String,
     pub short_description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+
+    /// All the translations of the game.
+    pub translations: Vec<GameTranslation>,
+
     pub year: i32,
-    pub publisher: String,
-    pub developer: String,
     pub links: Value,
     pub system_unique_id: i32,
     pub system: SystemRef,
+
+    pub publishers: Vec<CompanyRef>,
+    pub developers: Vec<CompanyRef>,
+    pub releases: Vec<GameRelease>,
+
+    /// The languages the game supports, as BCP 47 tags.
+    pub languages: Vec<String>,
+    pub genres: Vec<GenreRef>,
+    pub series: Vec<SeriesRef>,
+    pub tags: Vec<Tag>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_players: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_players: Option<i32>,
+
+    /// How this game relates to other games, e.g. it is the `hack_of`
+    /// another game.
+    pub relationships: Vec<GameRelationship>,
+
+    /// How other games relate to this game, e.g. they are a `hack_of` this
+    /// game.
+    pub related_games: Vec<GameRelationship>,
+
+    /// The DAT this game was last imported from, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dat: Option<GameDatRef>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 #[cfg_attr(feature = "rocket", derive(rocket::form::FromForm))]
 #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct GameUpdateRequest<'a> {
@@ -135,11 +288,28 @@ pub struct GameUpdateRequest<'a> {
     pub description: Option<&'a str>,
     pub short_description: Option<&'a str>,
     pub year: Option<i32>,
-    pub publisher: Option<&'a str>,
-    pub developer: Option<&'a str>,
     pub add_links: Option<BTreeMap<&'a str, &'a str>>,
     pub remove_links: Option<Vec<&'a str>>,
     pub system_unique_id: Option<i32>,
+
+    /// Lists below replace the existing ones when specified.
+    pub publishers: Option<Vec<&'a str>>,
+    pub developers: Option<Vec<&'a str>>,
+
+    /// Deprecated, use `publishers` and `developers`. Replace the publishers
+    /// or developers by a single company (none if empty). They cannot be
+    /// given with the lists they replace.
+    pub publisher: Option<&'a str>,
+    pub developer: Option<&'a str>,
+
+    pub releases: Option<Vec<GameReleaseRequest<'a>>>,
+    pub languages: Option<Vec<&'a str>>,
+    pub genres: Option<Vec<&'a str>>,
+    pub series: Option<Vec<&'a str>>,
+    pub tags: Option<Vec<&'a str>>,
+
+    pub min_players: Option<i32>,
+    pub max_players: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -149,8 +319,34 @@ pub struct GameAddArtifactRequest<'a> {
     /// Its content type.
     pub mime_type: &'a str,
 
-    /// Size of the file in bytes. Files cannot be larger than 20MB.
-    pub size: i32,
+    /// The name of the file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filename: Option<&'a str>,
+
+    /// The directory of the file, relative to the game (e.g. `Disc 1`).
+    /// The root of the game by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<&'a str>,
+
+    /// What the file is used for. `rom` by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<GameArtifactRole>,
+
+    /// The position of the file among the files of the game. By default,
+    /// after the files the game already has, in the order of the request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<i32>,
+
+    /// The status of the dump of the file. `good` by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<GameArtifactStatus>,
+
+    /// Size of the file in bytes.
+    pub size: i64,
+
+    /// CRC32 checksum of the file, in hexadecimal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crc32: Option<HexString>,
 
     /// MD5 checksum of the file, in hexadecimal.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -164,3 +360,212 @@ pub struct GameAddArtifactRequest<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sha256: Option<HexString>,
 }
+
+/// The size and checksums of a file to identify. Files are identified by
+/// any of their hashes, or by their size and CRC32 if no hash matches.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct GameIdentifyFile {
+    /// Size of the file in bytes.
+    pub size: i64,
+
+    /// CRC32 checksum of the file, in hexadecimal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crc32: Option<HexString>,
+
+    /// MD5 checksum of the file, in hexadecimal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub md5: Option<HexString>,
+
+    /// SHA1 checksum of the file, in hexadecimal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha1: Option<HexString>,
+
+    /// SHA256 checksum of the file, in hexadecimal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<HexString>,
+}
+
+/// Files to identify.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct GameIdentifyRequest<'v> {
+    /// Only match games of this system. By default, include all systems.
+    #[serde(borrow)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<IdOrSlug<'v>>,
+
+    /// The files to identify.
+    pub files: Vec<GameIdentifyFile>,
+}
+
+/// How much a file is known to match an artifact.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize, Display)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum GameIdentifyConfidence {
+    /// One of the MD5, SHA1 or SHA256 checksums matched.
+    Hash,
+
+    /// Only the size and CRC32 checksum matched. Different files can have
+    /// the same CRC32.
+    SizeAndCrc32,
+}
+
+/// A game artifact matching a file.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct GameIdentifyMatch {
+    /// The identifier of the game.
+    pub game_id: i32,
+
+    /// The name of the game.
+    pub name: String,
+
+    /// The system this game is for.
+    pub system: SystemRef,
+
+    /// The identifier of the artifact.
+    pub artifact_id: i32,
+
+    /// The checksums and size of the artifact.
+    pub artifact: ArtifactRef,
+
+    pub confidence: GameIdentifyConfidence,
+}
+
+/// The games matching a file, in the order of the files in the request.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct GameIdentifyResult {
+    /// The matching artifacts, most confident first. Empty if the file is
+    /// unknown.
+    pub matches: Vec<GameIdentifyMatch>,
+}
+
+/// Parameters for importing a DAT file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "rocket", derive(rocket::form::FromForm))]
+#[cfg_attr(feature = "rocket", derive(rocket::UriDisplayQuery))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct DatImportQueryParams {
+    /// Only compute the preview of the import. It can be applied later.
+    /// Defaults to false.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dry_run: Option<bool>,
+}
+
+/// Parameters for exporting the games of a system as a DAT file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "rocket", derive(rocket::form::FromForm))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct DatExportQueryParams {
+    /// Only export games with this tag.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+
+    /// Only export games released in this range of years.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub year: Option<RangeParams<i32>>,
+
+    /// Only export games released in this region.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+}
+
+/// The state of a DAT import.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize, EnumString, Display)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum DatImportStatus {
+    /// Waiting for the import job to start.
+    Pending,
+
+    /// The import job is computing the preview, or importing games.
+    Running,
+
+    /// The preview of a dry run is ready, and the import can be applied.
+    Preview,
+
+    /// The games were imported.
+    Completed,
+
+    /// The import failed, see its error.
+    Failed,
+}
+
+/// A game of a DAT import preview.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct DatImportGame {
+    /// The existing game, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub game_id: Option<i32>,
+
+    /// The name of the game, from the DAT for games to create or update.
+    pub name: String,
+
+    /// The number of artifacts to add to the game.
+    pub new_artifacts: usize,
+}
+
+/// What a DAT import does to the games of its system.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct DatImportPreview {
+    /// Games of the DAT that do not exist yet.
+    pub create: Vec<DatImportGame>,
+
+    /// Existing games whose information or artifacts change.
+    pub update: Vec<DatImportGame>,
+
+    /// The number of games of the DAT that already exist as is.
+    pub unchanged: usize,
+
+    /// Games of the system that are not in the DAT. They are left as is.
+    pub orphaned: Vec<DatImportGame>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct DatImportDetails {
+    pub id: i32,
+    pub system: SystemRef,
+
+    /// The name and version from the header of the DAT.
+    pub dat_name: String,
+    pub dat_version: String,
+
+    pub dry_run: bool,
+    pub status: DatImportStatus,
+
+    /// The changes of the import, once computed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview: Option<DatImportPreview>,
+
+    /// The number of games in the DAT, and how many were imported so far.
+    pub total: i32,
+    pub processed: i32,
+
+    /// Why the import failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// When the import was created, in seconds since UNIX EPOCH.
+    pub created_at: i64,
+
+    /// When the import completed or failed, in seconds since UNIX EPOCH.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<i64>,
+}
+
+/// The DAT a game was last imported from.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct GameDatRef {
+    pub import_id: i32,
+    pub name: String,
+    pub version: String,
+}
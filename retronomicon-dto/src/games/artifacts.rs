@@ -0,0 +1,112 @@
+use crate::artifact::ArtifactRef;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumString};
+
+/// What a file of a game is used for.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Serialize,
+    Deserialize,
+    EnumString,
+    Display,
+)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum GameArtifactRole {
+    /// A ROM or a disc image in a single file.
+    #[default]
+    Rom,
+    /// The file describing the tracks of a disc image (e.g. `.cue`, `.gdi`).
+    Cue,
+    /// A track of a disc image.
+    Track,
+    /// A BIOS or firmware needed by the game.
+    Bios,
+    Other,
+}
+
+#[cfg(feature = "rocket")]
+impl<'v> rocket::form::FromFormField<'v> for GameArtifactRole {
+    fn from_value(field: rocket::form::ValueField<'v>) -> rocket::form::Result<'v, Self> {
+        field
+            .value
+            .parse()
+            .map_err(|_| rocket::form::Error::validation("Invalid artifact role").into())
+    }
+}
+
+#[cfg(feature = "rocket")]
+impl<T: rocket::http::uri::fmt::Part> rocket::http::uri::fmt::UriDisplay<T> for GameArtifactRole {
+    fn fmt(&self, f: &mut rocket::http::uri::fmt::Formatter<'_, T>) -> std::fmt::Result {
+        f.write_value(self.to_string())
+    }
+}
+
+/// The status of the dump of a file, as in logiqx DATs.
+#[derive(
+    Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, EnumString, Display,
+)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum GameArtifactStatus {
+    /// The dump is known to be bad.
+    BadDump,
+    /// The file was never dumped.
+    NoDump,
+    #[default]
+    Good,
+    /// The dump was verified against the original media.
+    Verified,
+}
+
+#[cfg(feature = "rocket")]
+impl<'v> rocket::form::FromFormField<'v> for GameArtifactStatus {
+    fn from_value(field: rocket::form::ValueField<'v>) -> rocket::form::Result<'v, Self> {
+        field
+            .value
+            .parse()
+            .map_err(|_| rocket::form::Error::validation("Invalid dump status").into())
+    }
+}
+
+#[cfg(feature = "rocket")]
+impl<T: rocket::http::uri::fmt::Part> rocket::http::uri::fmt::UriDisplay<T> for GameArtifactStatus {
+    fn fmt(&self, f: &mut rocket::http::uri::fmt::Formatter<'_, T>) -> std::fmt::Result {
+        f.write_value(self.to_string())
+    }
+}
+
+/// A file of a game.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct GameArtifact {
+    /// The ID of the artifact.
+    pub id: i32,
+
+    /// The name of the file.
+    pub filename: String,
+
+    /// The directory of the file, relative to the game (e.g. `Disc 1`).
+    /// Empty if the file is at the root of the game.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub path: String,
+
+    pub role: GameArtifactRole,
+
+    /// The position of the file among the files of the game.
+    pub order: i32,
+
+    pub status: GameArtifactStatus,
+
+    #[serde(flatten)]
+    pub artifact: ArtifactRef,
+}
@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumString};
+
+/// A company publishing or developing games.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct CompanyRef {
+    pub id: i32,
+    pub slug: String,
+    pub name: String,
+}
+
+/// What a company did for a game.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize, EnumString, Display)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum CompanyRole {
+    Publisher,
+    Developer,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct GenreRef {
+    pub id: i32,
+    pub slug: String,
+    pub name: String,
+}
+
+/// A region games are released in, e.g. `usa` or `japan`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct RegionRef {
+    pub id: i32,
+    pub slug: String,
+    pub name: String,
+}
+
+/// A series (or franchise) of games.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct SeriesRef {
+    pub id: i32,
+    pub slug: String,
+    pub name: String,
+}
+
+/// The release of a game in a region.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct GameRelease {
+    pub region: RegionRef,
+
+    /// The date of the release, as `YYYY-MM-DD`, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "rocket", derive(rocket::form::FromForm))]
+pub struct GameReleaseRequest<'a> {
+    /// The slug of the region.
+    pub region: &'a str,
+
+    /// The date of the release, as `YYYY-MM-DD`, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<&'a str>,
+}
+
+/// Whether a language tag is well-formed, following the syntax of BCP 47
+/// (e.g. `en`, `pt-BR` or `zh-Hant-TW`). Whether its subtags are registered
+/// is not checked.
+pub fn is_valid_language_tag(tag: &str) -> bool {
+    let mut subtags = tag.split('-');
+    let Some(language) = subtags.next() else {
+        return false;
+    };
+
+    tag.len() <= 35
+        && (2..=8).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_alphabetic())
+        && subtags
+            .all(|s| (1..=8).contains(&s.len()) && s.chars().all(|c| c.is_ascii_alphanumeric()))
+}
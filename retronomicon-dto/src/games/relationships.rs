@@ -0,0 +1,139 @@
+use crate::systems::SystemRef;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumString};
+
+/// How a game relates to another game. Relationships read "game `kind`
+/// other game", e.g. a fan translation is the `translation_of` the
+/// original game.
+#[derive(
+    Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize, EnumString, Display,
+)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum GameRelationshipKind {
+    /// A revision (e.g. `Rev 1`) of the other game.
+    RevisionOf,
+    /// A translation, official or not, of the other game.
+    TranslationOf,
+    /// A modified version of the other game.
+    HackOf,
+    /// A compilation containing the other game.
+    Contains,
+    SequelOf,
+}
+
+#[cfg(feature = "rocket")]
+impl<'v> rocket::form::FromFormField<'v> for GameRelationshipKind {
+    fn from_value(field: rocket::form::ValueField<'v>) -> rocket::form::Result<'v, Self> {
+        field
+            .value
+            .parse()
+            .map_err(|_| rocket::form::Error::validation("Invalid relationship kind").into())
+    }
+}
+
+#[cfg(feature = "rocket")]
+impl<T: rocket::http::uri::fmt::Part> rocket::http::uri::fmt::UriDisplay<T>
+    for GameRelationshipKind
+{
+    fn fmt(&self, f: &mut rocket::http::uri::fmt::Formatter<'_, T>) -> std::fmt::Result {
+        f.write_value(self.to_string())
+    }
+}
+
+/// Which relationships of a game to follow: those from the game to other
+/// games (`outgoing`), those from other games to it (`incoming`), or both.
+#[derive(
+    Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, EnumString, Display,
+)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum GameRelationshipDirection {
+    Outgoing,
+    Incoming,
+    #[default]
+    Both,
+}
+
+#[cfg(feature = "rocket")]
+impl<'v> rocket::form::FromFormField<'v> for GameRelationshipDirection {
+    fn from_value(field: rocket::form::ValueField<'v>) -> rocket::form::Result<'v, Self> {
+        field
+            .value
+            .parse()
+            .map_err(|_| rocket::form::Error::validation("Invalid direction").into())
+    }
+}
+
+#[cfg(feature = "rocket")]
+impl<T: rocket::http::uri::fmt::Part> rocket::http::uri::fmt::UriDisplay<T>
+    for GameRelationshipDirection
+{
+    fn fmt(&self, f: &mut rocket::http::uri::fmt::Formatter<'_, T>) -> std::fmt::Result {
+        f.write_value(self.to_string())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct GameRef {
+    pub id: i32,
+    pub name: String,
+    pub system: SystemRef,
+}
+
+/// A relationship between a game and another game.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct GameRelationship {
+    pub kind: GameRelationshipKind,
+    pub game: GameRef,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct GameRelationshipCreateRequest {
+    pub kind: GameRelationshipKind,
+
+    /// The id of the other game.
+    pub game: i32,
+}
+
+/// Parameters for traversing the relationships of a game.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "rocket", derive(rocket::form::FromForm))]
+#[cfg_attr(feature = "rocket", derive(rocket::UriDisplayQuery))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct GameRelatedQueryParams {
+    /// Only follow relationships of this kind. By default, follow all.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<GameRelationshipKind>,
+
+    /// Which relationships to follow. Both directions by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub direction: Option<GameRelationshipDirection>,
+
+    /// How many relationships to follow from the game, e.g. 2 to find the
+    /// hacks of the revisions of a game. 1 by default, at most 5.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub depth: Option<u32>,
+}
+
+/// A game reached by traversing the relationships of a game.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct GameRelatedItem {
+    /// The kind of the last relationship followed to reach the game.
+    pub kind: GameRelationshipKind,
+
+    /// `outgoing` if the game reached before this one is `kind` of this
+    /// game, `incoming` if this game is `kind` of the game reached before.
+    pub direction: GameRelationshipDirection,
+
+    /// The number of relationships followed to reach the game.
+    pub depth: i32,
+
+    pub game: GameRef,
+}
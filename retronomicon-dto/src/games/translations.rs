@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+
+/// Parameters for the details of a game.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "rocket", derive(rocket::form::FromForm))]
+#[cfg_attr(feature = "rocket", derive(rocket::UriDisplayQuery))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct GameDetailsQueryParams {
+    /// The preferred language of the name and descriptions, as a BCP 47
+    /// tag. Takes precedence over the `Accept-Language` header.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+}
+
+/// The name and descriptions of a game in a language.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct GameTranslation {
+    /// The language of this translation, as a BCP 47 tag (e.g. `ja`, or
+    /// `ja-Latn` for a romanised Japanese name).
+    pub language: String,
+    pub name: String,
+
+    /// The short description in this language, if different from the
+    /// one of the game.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub short_description: Option<String>,
+
+    /// The description in this language, if different from the one of
+    /// the game.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Add or replace the translation of a game in a language.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct GameTranslationRequest<'a> {
+    pub name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub short_description: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<&'a str>,
+}
+
+/// The best of the available languages for a list of preferred language
+/// ranges, most preferred first. Ranges are shortened until they match a
+/// language (`zh-Hant-TW` falls back to `zh-Hant` then `zh`, see RFC 4647),
+/// then match the languages they are a prefix of (`en` matches `en-GB`).
+/// Returns `None` if no language is acceptable.
+pub fn negotiate_language<'a>(
+    preferences: &[impl AsRef<str>],
+    available: &[&'a str],
+) -> Option<&'a str> {
+    for preference in preferences {
+        let preference = preference.as_ref();
+        if preference == "*" {
+            continue;
+        }
+
+        let mut range = preference;
+        loop {
+            if let Some(language) = available.iter().find(|l| l.eq_ignore_ascii_case(range)) {
+                return Some(language);
+            }
+            match range.rfind('-') {
+                Some(i) => range = &range[..i],
+                None => break,
+            }
+        }
+
+        let prefix = format!("{}-", preference.to_ascii_lowercase());
+        if let Some(language) = available
+            .iter()
+            .find(|l| l.to_ascii_lowercase().starts_with(&prefix))
+        {
+            return Some(language);
+        }
+    }
+    None
+}
+
+/// A well-formed language tag in its conventional case, e.g. `zh-Hant-TW`
+/// for `ZH-hant-tw`, so the same language is always stored the same way.
+pub fn canonical_language_tag(tag: &str) -> String {
+    tag.split('-')
+        .enumerate()
+        .map(|(i, subtag)| match subtag.len() {
+            _ if i == 0 => subtag.to_ascii_lowercase(),
+            2 => subtag.to_ascii_uppercase(),
+            4 => subtag[..1].to_ascii_uppercase() + &subtag[1..].to_ascii_lowercase(),
+            _ => subtag.to_ascii_lowercase(),
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
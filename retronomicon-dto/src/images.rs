@@ -1,10 +1,73 @@
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumString};
+
+#[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct Image {
+    pub id: i32,
     /// The image's name.
     pub name: String,
     /// The image's content type.
     pub mime_type: String,
     /// The image's URL to download.
     pub url: String,
+    pub width: i32,
+    pub height: i32,
+    /// What the image shows.
+    pub roles: Vec<ImageRole>,
+    /// Resized and re-encoded versions of the image, smallest first.
+    pub derivatives: Vec<ImageDerivative>,
+}
+
+/// A resized or re-encoded version of an image, generated when uploaded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct ImageDerivative {
+    pub width: i32,
+    pub height: i32,
+    pub mime_type: String,
+    pub url: String,
+}
+
+/// What a game image shows. Roles are stored as tags with the same slug.
+#[derive(
+    Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize, EnumString, Display,
+)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum ImageRole {
+    BoxFront,
+    BoxBack,
+    Cartridge,
+    Disc,
+    Manual,
+    TitleScreen,
+    InGame,
+    Logo,
+    Artwork,
+}
+
+#[cfg(feature = "rocket")]
+impl<'v> rocket::form::FromFormField<'v> for ImageRole {
+    fn from_value(field: rocket::form::ValueField<'v>) -> rocket::form::Result<'v, Self> {
+        field
+            .value
+            .parse()
+            .map_err(|_| rocket::form::Error::validation("Invalid image role").into())
+    }
+}
+
+#[cfg(feature = "rocket")]
+impl<T: rocket::http::uri::fmt::Part> rocket::http::uri::fmt::UriDisplay<T> for ImageRole {
+    fn fmt(&self, f: &mut rocket::http::uri::fmt::Formatter<'_, T>) -> std::fmt::Result {
+        f.write_value(self.to_string())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct ImageRolesUpdateRequest {
+    /// The roles of the image, replacing the existing ones.
+    pub roles: Vec<ImageRole>,
 }
@@ -1,17 +1,27 @@
 pub mod encodings;
 pub mod error;
+// `rocket::UriDisplayQuery` generates borrows that clippy complains about.
+#[allow(clippy::needless_borrows_for_generic_args)]
 pub mod params;
 pub mod types;
 
 pub mod artifact;
 pub mod auth;
 pub mod cores;
+#[allow(clippy::needless_borrows_for_generic_args)]
 pub mod games;
 pub mod images;
+#[allow(clippy::needless_borrows_for_generic_args)]
+pub mod notifications;
+#[allow(clippy::needless_borrows_for_generic_args)]
+pub mod permissions;
 pub mod platforms;
+#[allow(clippy::needless_borrows_for_generic_args)]
+pub mod search;
 pub mod systems;
 pub mod tags;
 pub mod teams;
+pub mod tokens;
 pub mod user;
 
 pub mod client;
@@ -0,0 +1,95 @@
+use crate::params::PagingParams;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use strum::{Display, EnumIter, EnumString};
+
+/// What a notification is about.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Serialize,
+    Deserialize,
+    EnumString,
+    EnumIter,
+    Display,
+)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// The user was invited to a team.
+    TeamInvitation,
+
+    /// A release the user uploaded, or of a core they follow, was yanked.
+    ReleaseYanked,
+
+    /// A new release of a core the user follows was created.
+    CoreRelease,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct Notification {
+    pub id: i32,
+    pub kind: NotificationKind,
+
+    /// Information about the event, depending on its kind (e.g. the team
+    /// and role of an invitation).
+    pub data: Value,
+
+    /// When the notification was created, in seconds since UNIX EPOCH.
+    pub created_at: i64,
+
+    /// When the notification was marked as read, in seconds since UNIX EPOCH.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_at: Option<i64>,
+}
+
+/// Parameters for filtering the notifications of a user.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "rocket", derive(rocket::form::FromForm))]
+#[cfg_attr(feature = "rocket", derive(rocket::UriDisplayQuery))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct NotificationFilterParams {
+    /// Only include notifications that were not read. Defaults to false.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unread: Option<bool>,
+
+    /// Paging parameters.
+    #[serde(flatten)]
+    pub paging: PagingParams,
+}
+
+impl NotificationFilterParams {
+    pub fn paging(&self) -> PagingParams {
+        self.paging
+    }
+}
+
+/// Mark notifications as read.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct NotificationReadRequest {
+    /// The notifications to mark as read. If missing, all notifications of
+    /// the user are marked as read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ids: Option<Vec<i32>>,
+}
+
+/// The channels a user is notified through for a kind of notification.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct NotificationPreference {
+    pub kind: NotificationKind,
+
+    /// Whether notifications are listed in the website.
+    pub in_app: bool,
+
+    /// Whether notifications are sent by email.
+    pub email: bool,
+}
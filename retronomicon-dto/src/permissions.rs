@@ -0,0 +1,202 @@
+use crate::types::{IdOrSlug, UserTeamRole};
+use crate::user::{UserIdOrUsername, UserRef};
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+/// Something a user can be allowed to do. Most permissions are given to the
+/// members of a team through their role; the owners of a team always have
+/// every permission of the team.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Hash,
+    Serialize,
+    Deserialize,
+    EnumString,
+    EnumIter,
+    Display,
+)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// Create a team. This is not given by a team, but by the server
+    /// configuration.
+    CreateTeam,
+
+    /// Edit the description, links and metadata of the team.
+    UpdateTeam,
+
+    /// Delete the team.
+    DeleteTeam,
+
+    /// Invite users to the team, cancel invitations, remove members and
+    /// change their roles. Only owners can manage owners and admins.
+    ManageMembers,
+
+    /// Register and revoke the keys the team signs release manifests with.
+    ManageTeamKeys,
+
+    /// Create systems owned by the team.
+    CreateSystems,
+
+    /// Create cores owned by the team.
+    CreateCores,
+
+    /// Create releases of a core, upload their artifacts, and declare the
+    /// files they require on devices.
+    CreateReleases,
+
+    /// Yank releases of a core, or restore them.
+    YankReleases,
+
+    /// Sign the manifest of a release with one of the keys of the team.
+    SignManifests,
+
+    /// Upload images of the games of a system.
+    UploadGameImages,
+}
+
+impl Permission {
+    /// Whether this permission can be granted to the collaborators of a
+    /// core.
+    pub fn is_core_permission(&self) -> bool {
+        matches!(
+            self,
+            Self::CreateReleases | Self::YankReleases | Self::SignManifests
+        )
+    }
+
+    /// Whether this permission is given by the role of a user in a team.
+    pub fn is_team_permission(&self) -> bool {
+        !matches!(self, Self::CreateTeam)
+    }
+}
+
+#[cfg(feature = "rocket")]
+impl<'v> rocket::form::FromFormField<'v> for Permission {
+    fn from_value(field: rocket::form::ValueField<'v>) -> rocket::form::Result<'v, Self> {
+        field
+            .value
+            .parse()
+            .map_err(|_| rocket::form::Error::validation("Invalid permission").into())
+    }
+}
+
+#[cfg(feature = "rocket")]
+impl<T: rocket::http::uri::fmt::Part> rocket::http::uri::fmt::UriDisplay<T> for Permission {
+    fn fmt(&self, f: &mut rocket::http::uri::fmt::Formatter<'_, T>) -> std::fmt::Result {
+        f.write_value(self.to_string())
+    }
+}
+
+/// The permissions given to a role in a team.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TeamRolePermissions {
+    pub role: UserTeamRole,
+
+    /// The permissions the role has, including defaults that were not
+    /// changed by the team.
+    pub permissions: Vec<Permission>,
+}
+
+/// Grant or revoke a permission to a role of a team.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TeamPermissionUpdate {
+    pub role: UserTeamRole,
+    pub permission: Permission,
+
+    /// Whether the role has the permission. If missing, the role goes back
+    /// to the default for this permission.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub granted: Option<bool>,
+}
+
+/// A user with permissions on a core.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct CoreCollaborator {
+    pub user: UserRef,
+    pub permissions: Vec<Permission>,
+}
+
+/// Set the permissions of a user on a core.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct CoreCollaboratorUpdate {
+    /// The permissions of the user on the core, replacing the ones they had.
+    pub permissions: Vec<Permission>,
+}
+
+/// Parameters to check a permission.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "rocket", derive(rocket::form::FromForm))]
+#[cfg_attr(feature = "rocket", derive(rocket::UriDisplayQuery))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct PermissionCheckQuery<'v> {
+    pub permission: Permission,
+
+    /// The team to check the permission in. Required for team permissions,
+    /// unless a core is given.
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub team: Option<IdOrSlug<'v>>,
+
+    /// The core to check the permission on. Its owner team is used for team
+    /// permissions, and its collaborators for core permissions.
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub core: Option<IdOrSlug<'v>>,
+
+    /// The user to check the permission of. Defaults to the current user.
+    /// Checking another user requires the `manage_members` permission in
+    /// the team.
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub user: Option<UserIdOrUsername<'v>>,
+}
+
+/// Where a permission comes from, or why it is missing.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Display)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionSource {
+    /// The server configuration.
+    Server,
+
+    /// The user is an owner of the team.
+    Owner,
+
+    /// The default permissions of the role of the user.
+    Default,
+
+    /// The team changed the permissions of the role of the user.
+    Team,
+
+    /// The user is a collaborator on the core.
+    Collaborator,
+
+    /// The user is not a member of the team.
+    NotAMember,
+}
+
+/// The result of checking a permission, and why.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct PermissionCheck {
+    pub permission: Permission,
+    pub allowed: bool,
+    pub source: PermissionSource,
+
+    /// The role of the user in the team, if they are a member.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<UserTeamRole>,
+
+    /// A human readable explanation.
+    pub reason: String,
+}
@@ -0,0 +1,92 @@
+use crate::params::PagingParams;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumString};
+
+/// Parameters for searching games, cores, systems and platforms.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "rocket", derive(rocket::form::FromForm))]
+#[cfg_attr(feature = "rocket", derive(rocket::UriDisplayQuery))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct SearchQueryParams {
+    /// The words to search for. Quoted phrases, `or` and `-word` are
+    /// supported, and words with typos still match names.
+    pub q: String,
+
+    /// Only return results of this kind. Facets still count all kinds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<SearchKind>,
+
+    /// Paging parameters.
+    #[serde(flatten)]
+    pub paging: PagingParams,
+}
+
+/// The kind of item a search result is.
+#[derive(
+    Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize, EnumString, Display,
+)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SearchKind {
+    Game,
+    Core,
+    System,
+    Platform,
+}
+
+#[cfg(feature = "rocket")]
+impl<'v> rocket::form::FromFormField<'v> for SearchKind {
+    fn from_value(field: rocket::form::ValueField<'v>) -> rocket::form::Result<'v, Self> {
+        field
+            .value
+            .parse()
+            .map_err(|_| rocket::form::Error::validation("Invalid kind").into())
+    }
+}
+
+#[cfg(feature = "rocket")]
+impl<T: rocket::http::uri::fmt::Part> rocket::http::uri::fmt::UriDisplay<T> for SearchKind {
+    fn fmt(&self, f: &mut rocket::http::uri::fmt::Formatter<'_, T>) -> std::fmt::Result {
+        f.write_value(self.to_string())
+    }
+}
+
+/// An item matching a search.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct SearchResult {
+    pub kind: SearchKind,
+    pub id: i32,
+
+    /// The slug of the item. Games have no slug.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+
+    pub name: String,
+
+    /// An HTML excerpt of the item where the words of the search are
+    /// surrounded by `<mark>` and `</mark>`. The text of the item is escaped.
+    /// Empty if only the name matched, with typos.
+    pub highlight: String,
+
+    /// How relevant the item is. Results are sorted by decreasing rank.
+    pub rank: f32,
+}
+
+/// The number of items of each kind matching a search.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct SearchFacets {
+    pub games: i64,
+    pub cores: i64,
+    pub systems: i64,
+    pub platforms: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct SearchResponse {
+    pub facets: SearchFacets,
+    pub results: Vec<SearchResult>,
+}
@@ -1,3 +1,4 @@
+use crate::encodings::Base64String;
 use crate::types::{IdOrSlug, UserTeamRole};
 use crate::user::{UserIdOrUsername, UserRef};
 use serde::{Deserialize, Serialize};
@@ -48,6 +49,11 @@ pub struct TeamDetails {
     pub metadata: BTreeMap<String, Value>,
 
     pub users: Vec<TeamUserRef>,
+
+    /// The maximum size of an artifact uploaded by this team, in bytes. If
+    /// not set, the server default applies.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upload_size_limit: Option<i64>,
 }
 
 /// Arguments to create a team.
@@ -101,6 +107,15 @@ pub struct TeamUpdateRequest<'a> {
     pub remove_links: Option<Vec<&'a str>>,
 }
 
+/// Arguments to change the upload size limit of a team.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TeamUploadLimitRequest {
+    /// The maximum size of an artifact, in bytes. `null` resets it to the
+    /// server default.
+    pub upload_size_limit: Option<i64>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct TeamInvite<'a> {
@@ -110,3 +125,47 @@ pub struct TeamInvite<'a> {
     #[serde(default)]
     pub role: UserTeamRole,
 }
+
+/// An invitation to a team that was not accepted yet.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TeamInvitation {
+    pub user: UserRef,
+    pub role: UserTeamRole,
+    pub invited_by: UserRef,
+}
+
+/// Change the role of a member of a team.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TeamMemberUpdate {
+    pub role: UserTeamRole,
+}
+
+/// A public key registered by a team to sign release manifests.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TeamSigningKey {
+    pub id: i32,
+    pub name: String,
+
+    /// The ed25519 public key.
+    pub public_key: Base64String,
+
+    pub created_at: i64,
+
+    /// When the key was revoked, in seconds since UNIX EPOCH. Signatures made
+    /// with a revoked key are not served anymore.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<i64>,
+}
+
+/// Arguments to register a signing key for a team.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TeamSigningKeyCreateRequest<'a> {
+    pub name: &'a str,
+
+    /// The ed25519 public key, 32 bytes.
+    pub public_key: Base64String,
+}
@@ -0,0 +1,127 @@
+use crate::types::IdOrSlug;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumString};
+
+/// What a personal access token can be used for.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize, EnumString, Display)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub enum TokenPermission {
+    /// Read anything the user can see.
+    #[serde(rename = "read")]
+    #[strum(serialize = "read")]
+    Read,
+
+    /// Create releases and upload their artifacts. This includes reading.
+    #[serde(rename = "releases:write")]
+    #[strum(serialize = "releases:write")]
+    ReleasesWrite,
+
+    /// Do anything the user can do.
+    #[serde(rename = "write")]
+    #[strum(serialize = "write")]
+    Write,
+}
+
+impl TokenPermission {
+    /// Whether this permission includes another one.
+    pub fn includes(&self, other: TokenPermission) -> bool {
+        match self {
+            Self::Write => true,
+            Self::ReleasesWrite => other != Self::Write,
+            Self::Read => other == Self::Read,
+        }
+    }
+}
+
+/// A permission granted to a token. A scope limited to a team or a core only
+/// allows writing to the cores of that team, or to that core.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TokenScope {
+    pub permission: TokenPermission,
+
+    /// The ID of the team this scope is limited to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub team: Option<i32>,
+
+    /// The ID of the core this scope is limited to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub core: Option<i32>,
+}
+
+/// A scope to grant to a new token.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TokenScopeRequest<'a> {
+    pub permission: TokenPermission,
+
+    #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+    pub team: Option<IdOrSlug<'a>>,
+
+    #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+    pub core: Option<IdOrSlug<'a>>,
+}
+
+/// A personal access token. The token itself is only returned when created.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct UserToken {
+    pub id: i32,
+    pub name: String,
+    pub scopes: Vec<TokenScope>,
+
+    pub created_at: i64,
+    pub expires_at: i64,
+
+    /// The last time the token was used, in seconds since UNIX EPOCH.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_used_at: Option<i64>,
+}
+
+/// Arguments to create a personal access token.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct UserTokenCreateRequest<'a> {
+    pub name: &'a str,
+
+    #[serde(borrow)]
+    pub scopes: Vec<TokenScopeRequest<'a>>,
+
+    /// Number of days the token is valid for. Defaults to 30 days, and
+    /// cannot be more than a year.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_in_days: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct UserTokenCreateResponse {
+    #[serde(flatten)]
+    pub info: UserToken,
+
+    /// The token, to send in the `Authorization` header. It cannot be
+    /// retrieved again.
+    pub token: String,
+}
+
+#[test]
+fn token_scope_serde() {
+    let scope: TokenScope =
+        serde_json::from_str(r#"{"permission":"releases:write","core":3}"#).unwrap();
+    assert_eq!(
+        scope,
+        TokenScope {
+            permission: TokenPermission::ReleasesWrite,
+            team: None,
+            core: Some(3),
+        }
+    );
+    assert_eq!(
+        serde_json::to_string(&scope).unwrap(),
+        r#"{"permission":"releases:write","core":3}"#
+    );
+    assert_eq!(
+        "releases:write".parse::<TokenPermission>(),
+        Ok(TokenPermission::ReleasesWrite)
+    );
+}
@@ -149,6 +149,14 @@ impl<'v> rocket::request::FromParam<'v> for UserIdOrUsername<'v> {
     }
 }
 
+#[cfg(feature = "rocket")]
+impl<'v> rocket::form::FromFormField<'v> for UserIdOrUsername<'v> {
+    fn from_value(field: rocket::form::ValueField<'v>) -> rocket::form::Result<'v, Self> {
+        rocket::request::FromParam::from_param(field.value)
+            .map_err(|e: &str| rocket::form::Error::validation(e.to_string()).into())
+    }
+}
+
 #[cfg(feature = "rocket")]
 impl<'v, T: rocket::http::uri::fmt::Part> rocket::http::uri::fmt::UriDisplay<T>
     for UserIdOrUsername<'v>
@@ -268,3 +276,107 @@ pub struct User {
 }
 
 pub type Me = UserDetails;
+
+/// An invitation of the current user to a team, not accepted yet.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct UserInvitation {
+    pub team: TeamRef,
+    pub role: UserTeamRole,
+    pub invited_by: UserRef,
+}
+
+/// A team whose ownership is given to another member when deleting an
+/// account.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TeamOwnershipTransfer {
+    pub team_id: i32,
+    pub user_id: i32,
+}
+
+/// Parameters for deleting the account of the current user.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct AccountDeleteRequest<'a> {
+    /// The username of the user, or their email if they have no username,
+    /// to confirm the deletion.
+    pub confirm: &'a str,
+
+    /// The current password, required if the user has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<&'a str>,
+
+    /// A two-factor authentication code, required if the user enabled it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<&'a str>,
+
+    /// New owners for the teams the user is the only owner of.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transfer_ownership: Vec<TeamOwnershipTransfer>,
+}
+
+/// The profile of a user, as stored.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct UserExportProfile {
+    pub id: i32,
+    pub username: Option<String>,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+    pub email: String,
+    pub auth_provider: Option<String>,
+    pub description: String,
+    pub links: Value,
+    pub metadata: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct UserExportTeam {
+    #[serde(flatten)]
+    pub team: TeamRef,
+    pub role: UserTeamRole,
+    /// Whether this is an invitation that was not accepted yet.
+    pub pending: bool,
+}
+
+/// A core or system release uploaded by a user.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct UserExportRelease {
+    pub id: i32,
+    /// The slug of the core or system of the release.
+    pub slug: String,
+    pub version: String,
+    pub date_released: i64,
+    pub yanked: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct UserExportUpload {
+    pub id: i32,
+    pub release_id: i32,
+    pub filename: String,
+    pub size: i64,
+    pub received: i64,
+    pub created_at: i64,
+}
+
+/// Everything stored about a user. Secrets (passwords, tokens, keys) are
+/// never included.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct UserExport {
+    pub exported_at: i64,
+    pub profile: UserExportProfile,
+    pub teams: Vec<UserExportTeam>,
+    pub core_releases: Vec<UserExportRelease>,
+    pub system_releases: Vec<UserExportRelease>,
+    pub uploads: Vec<UserExportUpload>,
+    pub identities: Vec<crate::auth::UserIdentity>,
+    pub sessions: Vec<crate::auth::UserSession>,
+    pub tokens: Vec<crate::tokens::UserToken>,
+    pub two_factor_enabled: bool,
+}